@@ -5,17 +5,40 @@
 //!     audiosync sync file1.mp4 file2.wav -o ./output --format wav
 //!     audiosync drift -r reference.wav -t target.wav
 //!     audiosync info *.mp4 *.wav
-
-use clap::{Parser, Subcommand};
+//!     audiosync check project.audiosync.json
+//!     audiosync show project.audiosync.json
+//!     audiosync export-clip project.audiosync.json --clip B001.wav -o ./punch
+//!     audiosync query project.audiosync.json --confidence-below 50
+//!     audiosync serve --bind 0.0.0.0:7878
+//!     audiosync tui file1.mp4 file2.wav -o ./output
+//!     audiosync doctor
+
+mod serve;
+mod tui;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::Instant;
 
-use audiosync_core::audio_io::{export_track, is_supported_file, load_clip};
-use audiosync_core::engine::{analyze, compute_delay, measure_drift, sync};
+use audiosync_core::audio_io::{
+    is_supported_file, load_clip, precache_files, preflight_check, AnalysisCache,
+};
+use audiosync_core::diagnostics::capability_report;
+use audiosync_core::engine::{match_delay, measure_drift};
 use audiosync_core::grouping::group_files_by_device;
+use audiosync_core::metadata::probe_stream_metadata;
+use audiosync_core::nulltest::{null_test, NullOffset};
 use audiosync_core::models::*;
-use audiosync_core::project_io::save_project;
-use audiosync_core::timeline_export::{export_edl, export_fcpxml};
+use audiosync_core::project_io::load_project;
+use audiosync_core::query::{parse_date, query_clips, ClipQuery};
+use audiosync_core::remote::analyze_remote;
+use audiosync_core::session::{ExportOutcome, ExportRequest, SessionController};
+#[cfg(debug_assertions)]
+use audiosync_core::simulate::{run_simulated_job, SimulatedJob};
+
+/// Import peak below which a file is reported as silent (about -80 dBFS).
+const SILENT_PEAK: f32 = 1e-4;
 
 #[derive(Parser)]
 #[command(
@@ -31,6 +54,411 @@ struct Cli {
     command: Commands,
 }
 
+/// Where placements come from when not analyzed here: a serve-mode
+/// instance or a saved sync map.
+#[derive(Args)]
+struct RemoteArgs {
+    /// Offload analysis to an `audiosync serve` instance (e.g. http://host:7878)
+    #[arg(long)]
+    remote: Option<String>,
+
+    /// Place clips from a sync map (see --sync-map) instead of analyzing them
+    #[arg(long, value_name = "PATH", conflicts_with = "remote")]
+    from_sync_map: Option<String>,
+
+    /// Shared secret for verifying the server's signed result manifest
+    #[arg(long, requires = "remote")]
+    remote_secret: Option<String>,
+}
+
+/// Timeline files to write next to (or instead of) the audio.
+#[derive(Args)]
+struct TimelineArgs {
+    /// Export FCPXML timeline
+    #[arg(long)]
+    fcpxml: Option<String>,
+
+    /// Export EDL timeline
+    #[arg(long)]
+    edl: Option<String>,
+
+    /// Export a JSON timeline (offsets, waveform peaks, media URLs) for web review players
+    #[arg(long)]
+    timeline_json: Option<String>,
+
+    /// Write a sync map: clip hashes and offsets only, for other tools
+    #[arg(long, value_name = "PATH")]
+    sync_map: Option<String>,
+}
+
+/// What `sync` imports, how it analyzes, and what it exports.
+#[derive(Args)]
+struct SyncArgs {
+    /// Audio/video files to sync
+    #[arg(required = true)]
+    files: Vec<String>,
+
+    /// Output directory
+    #[arg(short, long, default_value = "./audiosync_output")]
+    output_dir: String,
+
+    /// Export format: wav, aiff, flac, mp3
+    #[arg(long, default_value = "wav")]
+    format: String,
+
+    /// Bit depth: 16, 24, 32
+    #[arg(long, default_value = "24")]
+    bit_depth: u32,
+
+    /// Mix every track down to mono (by default each keeps the channels
+    /// of its widest clip)
+    #[arg(long)]
+    mono: bool,
+
+    /// Maximum offset in seconds [default: the spread of the clips' creation
+    /// timestamps plus slack, or unbounded when a clip has none]
+    #[arg(long)]
+    max_offset: Option<f64>,
+
+    /// Band-pass the audio before correlating, e.g. 200-3500 to ignore
+    /// wind rumble and hum
+    #[arg(long, value_name = "LOW-HIGH", value_parser = parse_band)]
+    band_pass: Option<(f64, f64)>,
+
+    /// Weight one device type's audio before correlating, e.g.
+    /// "camera=150-" to cut camera-mic rumble or "dialog=-:+6" to lift
+    /// the 1-4 kHz speech band
+    #[arg(long = "device-band", value_name = "ROLE=[LOW]-[HIGH][:DB]", value_parser = parse_device_band)]
+    device_bands: Vec<(TrackRole, BandWeighting)>,
+
+    /// Subtract each clip's steady background noise (wind, motor, hiss)
+    /// before correlating, for noisy action-camera and drone audio
+    #[arg(long)]
+    denoise: bool,
+
+    /// Use clapperboard slates to pin down clips the audio alone can't
+    /// place (music beds, repetitive noise)
+    #[arg(long)]
+    slate: bool,
+
+    /// Place clips by the SMPTE LTC recorded on this channel (1 = first)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    ltc_channel: Option<u32>,
+
+    /// Place a clip by hand, e.g. --offset "A001.mov=00:01:23.5"
+    /// (seconds, MM:SS or HH:MM:SS); analysis leaves it there
+    #[arg(long = "offset", value_name = "CLIP=TIME", value_parser = parse_clip_offset)]
+    offsets: Vec<(String, f64)>,
+
+    /// Put a track in a separate sync group, e.g. --sync-group "Zoom B=Room 2";
+    /// each group is synced against its own reference and exported apart
+    #[arg(long = "sync-group", value_name = "TRACK=GROUP")]
+    sync_groups: Vec<String>,
+
+    /// Move tracks that only match each other (e.g. another room) into
+    /// their own sync group automatically
+    #[arg(long)]
+    auto_groups: bool,
+
+    /// Split a folder of several shoots into sessions, each on its own
+    /// timeline, wherever nothing was recording for this many minutes
+    #[arg(long, value_name = "MINUTES")]
+    session_gap: Option<f64>,
+
+    /// Pick the reference track by recording span (coverage), total
+    /// duration, cleanest audio (quality), or by name with track:NAME,
+    /// which wins even over a track marked as reference [default: coverage]
+    #[arg(long, value_name = "STRATEGY", value_parser = parse_reference)]
+    reference: Option<ReferenceStrategy>,
+
+    /// Order and space the reference track's clips by creation time
+    /// (metadata) or by their overlapping audio (content) [default: auto,
+    /// content when any clip lacks a timestamp]
+    #[arg(long, value_enum, value_name = "MODE")]
+    reference_layout: Option<RefLayout>,
+
+    /// Skip matching each placed clip again on a second frequency band
+    /// to catch confident but wrong placements
+    #[arg(long)]
+    no_offset_check: bool,
+
+    /// Reuse earlier analyses of the same media, from any project: as hints
+    /// for ambiguous matches, or to skip correlating clips placed together before
+    #[arg(long, value_enum, value_name = "MODE")]
+    reuse_analysis: Option<ReuseMode>,
+
+    /// Disable automatic clock drift correction
+    #[arg(long)]
+    no_drift_correction: bool,
+
+    /// Don't copy a track's measured drift to its clips too short to measure
+    #[arg(long)]
+    no_drift_inherit: bool,
+
+    /// Leave clips whose timestamps put them before the reference unplaced
+    /// instead of extending the timeline back to fit them
+    #[arg(long)]
+    no_pre_roll: bool,
+
+    /// Keep the analysis placements instead of re-correlating each clip at
+    /// the export rate for sample-accurate alignment
+    #[arg(long)]
+    no_refine: bool,
+
+    /// Drift correction resampler [default: balanced, fast for previews]
+    #[arg(long, value_enum)]
+    drift_quality: Option<DriftQuality>,
+
+    /// How overlapping clips of a track are combined [default: crossfade]
+    #[arg(long, value_enum)]
+    overlap: Option<Overlap>,
+
+    /// Render only the first N minutes to check the result
+    #[arg(long)]
+    preview_minutes: Option<f64>,
+
+    /// Organize exports into subfolders, e.g. "{project}/{date}/{format}"
+    /// (tokens: project, date, format, sr, track)
+    #[arg(long)]
+    folder_template: Option<String>,
+
+    /// Exported file name, e.g. "{role}_{track}" (tokens as for --folder-template, plus role)
+    #[arg(long)]
+    filename_template: Option<String>,
+
+    /// Set a track's role, e.g. --role "Zoom H6=DIALOG" (DIALOG, MUSIC, FX, CAMERA);
+    /// unset tracks infer one from the device name
+    #[arg(long = "role", value_name = "TRACK=ROLE")]
+    roles: Vec<String>,
+
+    /// Embed project, track, sync offset and version tags in exported files
+    #[arg(long)]
+    embed_metadata: bool,
+
+    /// Decode each exported file and check its length and level
+    #[arg(long)]
+    verify: bool,
+
+    /// Shell command to run after export; sees AUDIOSYNC_FILES,
+    /// AUDIOSYNC_REPORT and AUDIOSYNC_OUTPUT_DIR in its environment
+    #[arg(long)]
+    post_cmd: Option<String>,
+
+    /// Maximum files decoded at once (lower for NAS/SD-card media)
+    #[arg(long, default_value_t = 2)]
+    io_parallelism: usize,
+
+    /// Decode every file again instead of reusing cached analysis audio
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Save project file (.audiosync.json)
+    #[arg(long)]
+    save: Option<String>,
+
+    /// Export an audio-only AAF placing each clip as its own region (for
+    /// conforming in Pro Tools), with the region media beside it
+    #[arg(long)]
+    aaf: Option<String>,
+
+    /// Write each clip's correlation curve as JSON into DIR (for bug reports)
+    #[arg(long, value_name = "DIR")]
+    debug_correlation: Option<String>,
+}
+
+/// How to use earlier analyses of the same media.
+#[derive(Clone, Copy, ValueEnum)]
+enum ReuseMode {
+    Priors,
+    Skip,
+}
+
+impl From<ReuseMode> for PriorReuse {
+    fn from(mode: ReuseMode) -> Self {
+        match mode {
+            ReuseMode::Priors => PriorReuse::Priors,
+            ReuseMode::Skip => PriorReuse::Skip,
+        }
+    }
+}
+
+/// Lowest warning severity that makes the command exit non-zero.
+#[derive(Clone, Copy, ValueEnum)]
+enum FailOn {
+    Warning,
+    Error,
+}
+
+/// Resampler used for drift correction.
+#[derive(Clone, Copy, ValueEnum)]
+enum DriftQuality {
+    Fast,
+    Cubic,
+    Balanced,
+    High,
+}
+
+/// How overlapping clips of a track are combined.
+#[derive(Clone, Copy, ValueEnum)]
+enum Overlap {
+    Crossfade,
+    PreferEarlier,
+    PreferLater,
+    SumWithLimiter,
+}
+
+impl From<Overlap> for OverlapPolicy {
+    fn from(overlap: Overlap) -> Self {
+        match overlap {
+            Overlap::Crossfade => OverlapPolicy::Crossfade,
+            Overlap::PreferEarlier => OverlapPolicy::PreferEarlier,
+            Overlap::PreferLater => OverlapPolicy::PreferLater,
+            Overlap::SumWithLimiter => OverlapPolicy::SumWithLimiter,
+        }
+    }
+}
+
+/// How a multi-clip reference track is laid out.
+#[derive(Clone, Copy, ValueEnum)]
+enum RefLayout {
+    Auto,
+    Metadata,
+    Content,
+}
+
+impl From<RefLayout> for ReferenceLayout {
+    fn from(layout: RefLayout) -> Self {
+        match layout {
+            RefLayout::Auto => ReferenceLayout::Auto,
+            RefLayout::Metadata => ReferenceLayout::Metadata,
+            RefLayout::Content => ReferenceLayout::Content,
+        }
+    }
+}
+
+impl From<DriftQuality> for ResampleQuality {
+    fn from(quality: DriftQuality) -> Self {
+        match quality {
+            DriftQuality::Fast => ResampleQuality::Fast,
+            DriftQuality::Cubic => ResampleQuality::Cubic,
+            DriftQuality::Balanced => ResampleQuality::Balanced,
+            DriftQuality::High => ResampleQuality::High,
+        }
+    }
+}
+
+/// Parse a `LOW-HIGH` frequency range in Hz.
+fn parse_band(s: &str) -> Result<(f64, f64), String> {
+    let (low, high) = s
+        .split_once('-')
+        .ok_or_else(|| format!("expected LOW-HIGH in Hz, got '{}'", s))?;
+    let parse = |v: &str| v.trim().parse::<f64>().map_err(|e| format!("'{}': {}", v, e));
+    let (low, high) = (parse(low)?, parse(high)?);
+    if low < 0.0 || high <= low {
+        return Err(format!("'{}' is not a range (LOW must be below HIGH)", s));
+    }
+    Ok((low, high))
+}
+
+/// Parse a device profile, `ROLE=[LOW]-[HIGH][:SPEECH_DB]`: e.g.
+/// `camera=150-` cuts a camera mic's rumble, `dialog=-8000:+6` also lifts
+/// the speech band by 6 dB.
+fn parse_device_band(s: &str) -> Result<(TrackRole, BandWeighting), String> {
+    let (role, spec) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected ROLE=[LOW]-[HIGH][:SPEECH_DB], got '{}'", s))?;
+    let role: TrackRole = role.parse()?;
+    let (range, speech) = match spec.split_once(':') {
+        Some((range, db)) => (range, Some(db)),
+        None => (spec, None),
+    };
+    let (low, high) = range
+        .split_once('-')
+        .ok_or_else(|| format!("expected LOW-HIGH in Hz (either may be empty), got '{}'", range))?;
+    let parse = |v: &str| {
+        let v = v.trim();
+        v.strip_prefix('+').unwrap_or(v).parse::<f64>().map_err(|e| format!("'{}': {}", v, e))
+    };
+    let edge = |v: &str| if v.trim().is_empty() { Ok(None) } else { parse(v).map(Some) };
+    let weighting = BandWeighting {
+        high_pass_hz: edge(low)?,
+        low_pass_hz: edge(high)?,
+        speech_emphasis_db: speech.map(parse).transpose()?.unwrap_or(0.0),
+    };
+    if let (Some(low), Some(high)) = (weighting.high_pass_hz, weighting.low_pass_hz)
+        && high <= low
+    {
+        return Err(format!("'{}' is not a range (LOW must be below HIGH)", range));
+    }
+    Ok((role, weighting))
+}
+
+/// Parse a reference strategy: `coverage`, `duration`, `quality` or
+/// `track:NAME`.
+fn parse_reference(s: &str) -> Result<ReferenceStrategy, String> {
+    if let Some(name) = s.strip_prefix("track:") {
+        return Ok(ReferenceStrategy::Track(name.trim().to_string()));
+    }
+    match s {
+        "coverage" => Ok(ReferenceStrategy::Coverage),
+        "duration" => Ok(ReferenceStrategy::Duration),
+        "quality" => Ok(ReferenceStrategy::Quality),
+        _ => Err(format!(
+            "expected coverage, duration, quality or track:NAME, got '{}'",
+            s
+        )),
+    }
+}
+
+/// Parse a date for `query --after/--before` into Unix seconds.
+fn parse_date_arg(s: &str) -> Result<f64, String> {
+    parse_date(s).ok_or_else(|| format!("expected YYYY-MM-DD, YYYY-MM-DD HH:MM or RFC 3339, got '{}'", s))
+}
+
+/// Parse `CLIP=TIME`, TIME being seconds, `MM:SS(.s)` or `HH:MM:SS(.s)`.
+fn parse_clip_offset(s: &str) -> Result<(String, f64), String> {
+    let (clip, time) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected CLIP=TIME, got '{}'", s))?;
+    Ok((clip.trim().to_string(), parse_time(time)?))
+}
+
+/// Parse seconds, `MM:SS(.s)` or `HH:MM:SS(.s)`, optionally negative.
+fn parse_time(time: &str) -> Result<f64, String> {
+    let trimmed = time.trim();
+    let (sign, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, trimmed),
+    };
+    let mut seconds = 0.0;
+    for part in unsigned.split(':') {
+        let value: f64 = part.parse().map_err(|e| format!("'{}': {}", time, e))?;
+        seconds = seconds * 60.0 + value;
+    }
+    if unsigned.split(':').count() > 3 {
+        return Err(format!("'{}' is not a time (HH:MM:SS at most)", time));
+    }
+    Ok(sign * seconds)
+}
+
+/// Parse `auto` or a time (see [`parse_time`]).
+fn parse_null_offset(s: &str) -> Result<NullOffset, String> {
+    if s.eq_ignore_ascii_case("auto") {
+        Ok(NullOffset::Auto)
+    } else {
+        parse_time(s).map(NullOffset::Fixed)
+    }
+}
+
+impl From<FailOn> for WarningSeverity {
+    fn from(level: FailOn) -> Self {
+        match level {
+            FailOn::Warning => WarningSeverity::Warning,
+            FailOn::Error => WarningSeverity::Error,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Run analysis on audio/video files (no export)
@@ -39,73 +467,141 @@ enum Commands {
         #[arg(required = true)]
         files: Vec<String>,
 
-        /// Maximum offset in seconds
+        /// Maximum offset in seconds [default: the spread of the clips' creation
+        /// timestamps plus slack, or unbounded when a clip has none]
         #[arg(long)]
         max_offset: Option<f64>,
 
-        /// Output results as JSON to stdout
-        #[arg(long)]
-        json: bool,
+        /// Band-pass the audio before correlating, e.g. 200-3500 to ignore
+        /// wind rumble and hum
+        #[arg(long, value_name = "LOW-HIGH", value_parser = parse_band)]
+        band_pass: Option<(f64, f64)>,
 
-        /// Save project file (.audiosync.json)
-        #[arg(long)]
-        save: Option<String>,
+        /// Weight one device type's audio before correlating, e.g.
+        /// "camera=150-" to cut camera-mic rumble or "dialog=-:+6" to lift
+        /// the 1-4 kHz speech band
+        #[arg(long = "device-band", value_name = "ROLE=[LOW]-[HIGH][:DB]", value_parser = parse_device_band)]
+        device_bands: Vec<(TrackRole, BandWeighting)>,
 
-        /// Export FCPXML timeline
+        /// Subtract each clip's steady background noise (wind, motor, hiss)
+        /// before correlating, for noisy action-camera and drone audio
         #[arg(long)]
-        fcpxml: Option<String>,
+        denoise: bool,
 
-        /// Export EDL timeline
+        /// Use clapperboard slates to pin down clips the audio alone can't
+        /// place (music beds, repetitive noise)
         #[arg(long)]
-        edl: Option<String>,
+        slate: bool,
 
-        /// Verbose logging
-        #[arg(short, long)]
-        verbose: bool,
-    },
+        /// Place clips by the SMPTE LTC recorded on this channel (1 = first)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        ltc_channel: Option<u32>,
 
-    /// Analyze, sync, and export aligned audio files
-    Sync {
-        /// Audio/video files to sync
-        #[arg(required = true)]
-        files: Vec<String>,
+        /// Place a clip by hand, e.g. --offset "A001.mov=00:01:23.5"
+        /// (seconds, MM:SS or HH:MM:SS); analysis leaves it there
+        #[arg(long = "offset", value_name = "CLIP=TIME", value_parser = parse_clip_offset)]
+        offsets: Vec<(String, f64)>,
 
-        /// Output directory
-        #[arg(short, long, default_value = "./audiosync_output")]
-        output_dir: String,
+        /// Put a track in a separate sync group, e.g. --sync-group "Zoom B=Room 2";
+        /// each group is synced against its own reference and exported apart
+        #[arg(long = "sync-group", value_name = "TRACK=GROUP")]
+        sync_groups: Vec<String>,
 
-        /// Export format: wav, aiff, flac, mp3
-        #[arg(long, default_value = "wav")]
-        format: String,
+        /// Move tracks that only match each other (e.g. another room) into
+        /// their own sync group automatically
+        #[arg(long)]
+        auto_groups: bool,
+
+        /// Split a folder of several shoots into sessions, each on its own
+        /// timeline, wherever nothing was recording for this many minutes
+        #[arg(long, value_name = "MINUTES")]
+        session_gap: Option<f64>,
+
+        /// Pick the reference track by recording span (coverage), total
+        /// duration, cleanest audio (quality), or by name with track:NAME,
+        /// which wins even over a track marked as reference [default: coverage]
+        #[arg(long, value_name = "STRATEGY", value_parser = parse_reference)]
+        reference: Option<ReferenceStrategy>,
+
+        /// Order and space the reference track's clips by creation time
+        /// (metadata) or by their overlapping audio (content) [default: auto,
+        /// content when any clip lacks a timestamp]
+        #[arg(long, value_enum, value_name = "MODE")]
+        reference_layout: Option<RefLayout>,
+
+        /// Skip matching each placed clip again on a second frequency band
+        /// to catch confident but wrong placements
+        #[arg(long)]
+        no_offset_check: bool,
 
-        /// Bit depth: 16, 24, 32
-        #[arg(long, default_value = "24")]
-        bit_depth: u32,
+        /// Reuse earlier analyses of the same media, from any project: as hints
+        /// for ambiguous matches, or to skip correlating clips placed together before
+        #[arg(long, value_enum, value_name = "MODE")]
+        reuse_analysis: Option<ReuseMode>,
 
-        /// Maximum offset in seconds
+        /// Maximum files decoded at once (lower for NAS/SD-card media)
+        #[arg(long, default_value_t = 2)]
+        io_parallelism: usize,
+
+        /// Decode every file again instead of reusing cached analysis audio
         #[arg(long)]
-        max_offset: Option<f64>,
+        no_cache: bool,
 
-        /// Disable automatic clock drift correction
+        /// Output results as JSON to stdout
         #[arg(long)]
-        no_drift_correction: bool,
+        json: bool,
 
         /// Save project file (.audiosync.json)
         #[arg(long)]
         save: Option<String>,
 
-        /// Export FCPXML timeline
+        #[command(flatten)]
+        timelines: TimelineArgs,
+
+        /// Exit with an error if analysis reports warnings at this severity or above
+        #[arg(long, value_enum)]
+        fail_on: Option<FailOn>,
+
+        /// Write each clip's correlation curve as JSON into DIR (for bug reports)
+        #[arg(long, value_name = "DIR")]
+        debug_correlation: Option<String>,
+
+        /// Don't copy a track's measured drift to its clips too short to measure
         #[arg(long)]
-        fcpxml: Option<String>,
+        no_drift_inherit: bool,
 
-        /// Export EDL timeline
+        /// Leave clips whose timestamps put them before the reference unplaced
+        /// instead of extending the timeline back to fit them
         #[arg(long)]
-        edl: Option<String>,
+        no_pre_roll: bool,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Analyze, sync, and export aligned audio files
+    Sync {
+        #[command(flatten)]
+        args: SyncArgs,
+
+        #[command(flatten)]
+        timelines: TimelineArgs,
 
         /// Output results as JSON to stdout
         #[arg(long)]
         json: bool,
 
+        /// Exit with an error if analysis reports warnings at this severity or above
+        #[arg(long, value_enum)]
+        fail_on: Option<FailOn>,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
+
         /// Verbose logging
         #[arg(short, long)]
         verbose: bool,
@@ -136,6 +632,55 @@ enum Commands {
         #[arg(required = true)]
         files: Vec<String>,
 
+        /// Probe codec, bit depth, duration and camera of each file
+        #[arg(long)]
+        deep: bool,
+
+        /// Output as JSON to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Decode files into the analysis cache ahead of time, so importing them
+    /// later (e.g. the morning after an overnight ingest) starts instantly
+    Precache {
+        /// Audio/video files to cache
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Maximum files decoded at once (lower for NAS/SD-card media)
+        #[arg(long, default_value_t = 2)]
+        io_parallelism: usize,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Pre-flight: check that every source file in a project is readable
+    Check {
+        /// Project file (.audiosync.json)
+        project: String,
+
+        /// Output as JSON to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Summarize a project file — tracks, clips, placements, warnings and
+    /// settings — without reading any media
+    Show {
+        /// Project file (.audiosync.json)
+        project: String,
+
         /// Output as JSON to stdout
         #[arg(long)]
         json: bool,
@@ -144,6 +689,169 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
     },
+
+    /// Export one clip of an analyzed project on its own, padded with
+    /// silence so it starts where it belongs on the timeline
+    ExportClip {
+        /// Project file (.audiosync.json) with an analysis
+        project: String,
+
+        /// Clip to export (file name or path)
+        #[arg(long)]
+        clip: String,
+
+        /// Output directory
+        #[arg(short, long, default_value = "./audiosync_output")]
+        output_dir: String,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// List the clips of a project matching every given condition
+    Query {
+        /// Project file (.audiosync.json)
+        project: String,
+
+        /// Clip name contains this (case-insensitive)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Track name or the camera make/model the file reports contains
+        /// this (case-insensitive)
+        #[arg(long)]
+        device: Option<String>,
+
+        /// Recorded at or after this date (YYYY-MM-DD, YYYY-MM-DD HH:MM or
+        /// RFC 3339)
+        #[arg(long, value_name = "DATE", value_parser = parse_date_arg)]
+        after: Option<f64>,
+
+        /// Recorded before this date
+        #[arg(long, value_name = "DATE", value_parser = parse_date_arg)]
+        before: Option<f64>,
+
+        /// Analyzed with a match score (0-100) below this
+        #[arg(long, value_name = "SCORE")]
+        confidence_below: Option<f64>,
+
+        /// Analyzed with clock drift above this many ppm either way
+        #[arg(long, value_name = "PPM")]
+        drift_above: Option<f64>,
+
+        /// Output as JSON to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run an analysis server that other machines can offload work to
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        bind: String,
+
+        /// Shared secret used to sign result manifests
+        #[arg(long)]
+        secret: Option<String>,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Align two files, subtract them and report what is left (null test)
+    NullTest {
+        /// Original file
+        a: String,
+
+        /// File to compare against it (re-export, drift-corrected render...)
+        b: String,
+
+        /// Where B starts relative to A: `auto`, or a time (seconds, MM:SS)
+        #[arg(long, default_value = "auto", allow_hyphen_values = true, value_parser = parse_null_offset)]
+        offset: NullOffset,
+
+        /// Write the difference waveform (A - B over the overlap) as a WAV file
+        #[arg(long, value_name = "WAV")]
+        difference: Option<String>,
+
+        /// Output results as JSON to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Print a JSON report of ffmpeg, codec, directory and CPU capabilities
+    Doctor {
+        /// Also check that this export directory is writable
+        #[arg(short, long)]
+        output_dir: Option<String>,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run a simulated long job that reports progress (debug builds only)
+    #[cfg(debug_assertions)]
+    #[command(hide = true)]
+    Simulate {
+        /// Total running time in seconds
+        #[arg(long, default_value_t = 10.0)]
+        duration: f64,
+
+        /// Number of progress steps
+        #[arg(long, default_value_t = 20)]
+        steps: usize,
+
+        /// Cancel after this many steps, as if the user had pressed Cancel
+        #[arg(long)]
+        cancel_after: Option<usize>,
+
+        /// Fail with an error after this many steps
+        #[arg(long)]
+        fail_after: Option<usize>,
+
+        /// Print progress events as JSON lines to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Analyze (and optionally export) in a live terminal UI
+    Tui {
+        /// Audio/video files to sync
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Export synced tracks here once analysis finishes
+        #[arg(short, long)]
+        output_dir: Option<String>,
+
+        /// Export format: wav, aiff, flac, mp3
+        #[arg(long, default_value = "wav")]
+        format: String,
+
+        /// Maximum offset in seconds [default: the spread of the clips' creation
+        /// timestamps plus slack, or unbounded when a clip has none]
+        #[arg(long)]
+        max_offset: Option<f64>,
+
+        /// Maximum files decoded at once (lower for NAS/SD-card media)
+        #[arg(long, default_value_t = 2)]
+        io_parallelism: usize,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -154,50 +862,106 @@ fn main() -> anyhow::Result<()> {
         Commands::Analyze { verbose, .. }
         | Commands::Sync { verbose, .. }
         | Commands::Drift { verbose, .. }
-        | Commands::Info { verbose, .. } => *verbose,
+        | Commands::Info { verbose, .. }
+        | Commands::Precache { verbose, .. }
+        | Commands::Check { verbose, .. }
+        | Commands::Show { verbose, .. }
+        | Commands::ExportClip { verbose, .. }
+        | Commands::Query { verbose, .. }
+        | Commands::Serve { verbose, .. }
+        | Commands::NullTest { verbose, .. }
+        | Commands::Doctor { verbose, .. } => *verbose,
+        #[cfg(debug_assertions)]
+        Commands::Simulate { verbose, .. } => *verbose,
+        Commands::Tui { .. } => false,
     };
     let level = if verbose { "debug" } else { "info" };
     // SAFETY: Called before any threads are spawned, at program start.
     unsafe {
         std::env::set_var("RUST_LOG", format!("audiosync={}", level));
     }
-    env_logger::init();
+    // Log lines would scribble over the TUI's screen.
+    if !matches!(cli.command, Commands::Tui { .. }) {
+        env_logger::init();
+    }
+
+    // Without a usable GPU correlations stay on the CPU.
+    #[cfg(feature = "gpu")]
+    if let Some(backend) = audiosync_core::engine::WgpuBackend::new() {
+        audiosync_core::engine::set_correlation_backend(Some(std::sync::Arc::new(backend)));
+    }
 
     match cli.command {
         Commands::Analyze {
             files,
             max_offset,
+            band_pass,
+            device_bands,
+            denoise,
+            slate,
+            ltc_channel,
+            offsets,
+            sync_groups,
+            auto_groups,
+            session_gap,
+            reference,
+            reference_layout,
+            no_offset_check,
+            reuse_analysis,
+            io_parallelism,
+            no_cache,
             json,
             save,
-            fcpxml,
-            edl,
+            timelines,
+            fail_on,
+            debug_correlation,
+            no_drift_inherit,
+            no_pre_roll,
+            remote,
             ..
-        } => cmd_analyze(files, max_offset, json, save, fcpxml, edl),
+        } => {
+            let mut config = SyncConfig {
+                max_offset_s: max_offset,
+                correlation_band_hz: band_pass,
+                device_band_weighting: device_bands.into_iter().collect(),
+                denoise,
+                slate_prior: slate,
+                ltc_channel: ltc_channel.map(|c| c - 1),
+                auto_sync_groups: auto_groups,
+                session_gap_s: session_gap.map(|m| m * 60.0),
+                reference_strategy: reference.unwrap_or_default(),
+                reference_layout: reference_layout.map(Into::into).unwrap_or_default(),
+                verify_offsets: !no_offset_check,
+                io_parallelism,
+                debug_correlation_dir: debug_correlation,
+                inherit_drift: !no_drift_inherit,
+                place_pre_roll: !no_pre_roll,
+                ..Default::default()
+            };
+            if no_cache {
+                config.analysis_cache_max_mb = 0;
+            }
+            let t0 = Instant::now();
+            let mut session = SessionController::new(config);
+            import_files(&mut session, &files)?;
+            apply_sync_groups(&mut session, &sync_groups)?;
+            apply_offset_overrides(&mut session, &offsets)?;
+            apply_prior_reuse(&mut session, reuse_analysis);
+            let result = cmd_analyze(session, t0, json, save, timelines, remote)?;
+            check_fail_on(&result, fail_on)
+        }
 
         Commands::Sync {
-            files,
-            output_dir,
-            format,
-            bit_depth,
-            max_offset,
-            no_drift_correction,
-            save,
-            fcpxml,
-            edl,
+            args,
+            timelines,
             json,
+            fail_on,
+            remote,
             ..
-        } => cmd_sync(
-            files,
-            output_dir,
-            format,
-            bit_depth,
-            max_offset,
-            no_drift_correction,
-            save,
-            fcpxml,
-            edl,
-            json,
-        ),
+        } => {
+            let result = cmd_sync(args, timelines, json, remote)?;
+            check_fail_on(&result, fail_on)
+        }
 
         Commands::Drift {
             reference,
@@ -206,7 +970,97 @@ fn main() -> anyhow::Result<()> {
             ..
         } => cmd_drift(reference, target, json),
 
-        Commands::Info { files, json, .. } => cmd_info(files, json),
+        Commands::Info {
+            files, deep, json, ..
+        } => cmd_info(files, deep, json),
+
+        Commands::Precache {
+            files, io_parallelism, ..
+        } => cmd_precache(files, io_parallelism),
+
+        Commands::Check { project, json, .. } => cmd_check(project, json),
+
+        Commands::Show { project, json, .. } => cmd_show(&project, json),
+
+        Commands::ExportClip {
+            project,
+            clip,
+            output_dir,
+            ..
+        } => cmd_export_clip(&project, &clip, output_dir),
+
+        Commands::Query {
+            project,
+            name,
+            device,
+            after,
+            before,
+            confidence_below,
+            drift_above,
+            json,
+            ..
+        } => {
+            let query = ClipQuery {
+                name,
+                device,
+                created_after: after,
+                created_before: before,
+                confidence_below,
+                drift_above_ppm: drift_above,
+            };
+            cmd_query(&project, &query, json)
+        }
+
+        Commands::Serve { bind, secret, .. } => serve::run(&bind, secret),
+
+        Commands::NullTest {
+            a,
+            b,
+            offset,
+            difference,
+            json,
+            ..
+        } => cmd_null_test(&a, &b, offset, difference, json),
+
+        Commands::Doctor { output_dir, .. } => {
+            let report = capability_report(output_dir.as_deref());
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+
+        #[cfg(debug_assertions)]
+        Commands::Simulate {
+            duration,
+            steps,
+            cancel_after,
+            fail_after,
+            json,
+            ..
+        } => {
+            let job = SimulatedJob {
+                duration_s: duration,
+                steps,
+                cancel_after,
+                fail_after,
+            };
+            cmd_simulate(&job, json)
+        }
+
+        Commands::Tui {
+            files,
+            output_dir,
+            format,
+            max_offset,
+            io_parallelism,
+        } => {
+            let config = SyncConfig {
+                max_offset_s: max_offset,
+                export_format: format,
+                io_parallelism,
+                ..Default::default()
+            };
+            tui::run(files, tui::TuiOptions { config, output_dir })
+        }
     }
 }
 
@@ -214,60 +1068,53 @@ fn main() -> anyhow::Result<()> {
 //  Commands
 // ---------------------------------------------------------------------------
 
-fn cmd_analyze(
-    files: Vec<String>,
-    max_offset: Option<f64>,
-    json: bool,
-    save: Option<String>,
-    fcpxml: Option<String>,
-    edl: Option<String>,
-) -> anyhow::Result<()> {
-    let t0 = Instant::now();
-
-    let mut tracks = load_files_into_tracks(&files)?;
-    if tracks.is_empty() {
-        anyhow::bail!("No supported files found.");
-    }
-
-    let config = SyncConfig {
-        max_offset_s: max_offset,
-        ..Default::default()
-    };
-
-    let progress: Option<ProgressCallback> = if !json {
-        Some(Box::new(|step, total, msg| {
-            eprintln!("[{}/{}] {}", step, total, msg);
-        }))
-    } else {
-        None
-    };
+/// Analyze an imported session; `t0` is when the import started.
+fn cmd_analyze(
+    mut session: SessionController,
+    t0: Instant,
+    json: bool,
+    save: Option<String>,
+    timelines: TimelineArgs,
+    remote: RemoteArgs,
+) -> anyhow::Result<SyncResult> {
+    let progress = progress_printer(json);
 
-    let result = analyze(&mut tracks, &config, &progress, &None)?;
+    let result = run_analysis(&mut session, &progress, &remote)?;
     let elapsed = t0.elapsed().as_secs_f64();
 
     // Save project if requested
     if let Some(ref path) = save {
-        save_project(path, &tracks, &config, Some(&result))?;
+        session.save(path)?;
         if !json {
             eprintln!("Project saved: {}", path);
         }
     }
 
-    // Export FCPXML
-    if let Some(ref path) = fcpxml {
-        export_fcpxml(&tracks, &result, path, None)?;
-        if !json {
+    // Export FCPXML / EDL / timeline JSON (one per sync group)
+    session.export_timelines(
+        timelines.fcpxml.as_deref(),
+        timelines.edl.as_deref(),
+        timelines.timeline_json.as_deref(),
+        None,
+    )?;
+    if !json {
+        if let Some(ref path) = timelines.fcpxml {
             eprintln!("FCPXML exported: {}", path);
         }
+        if let Some(ref path) = timelines.edl {
+            eprintln!("EDL exported: {}", path);
+        }
+        if let Some(ref path) = timelines.timeline_json {
+            eprintln!("Timeline JSON exported: {}", path);
+        }
     }
-
-    // Export EDL
-    if let Some(ref path) = edl {
-        export_edl(&tracks, &result, path, None)?;
+    if let Some(ref path) = timelines.sync_map {
+        session.save_sync_map(path)?;
         if !json {
-            eprintln!("EDL exported: {}", path);
+            eprintln!("Sync map saved: {}", path);
         }
     }
+    let tracks = &session.tracks;
 
     if json {
         let output = serde_json::json!({
@@ -275,6 +1122,7 @@ fn cmd_analyze(
             "tracks": tracks.iter().map(|t| serde_json::json!({
                 "name": t.name,
                 "is_reference": t.is_reference,
+                "sync_group": t.sync_group,
                 "clips": t.clips.iter().map(|c| serde_json::json!({
                     "name": c.name,
                     "file_path": c.file_path,
@@ -282,117 +1130,205 @@ fn cmd_analyze(
                     "offset_s": c.timeline_offset_s,
                     "offset_samples": c.timeline_offset_samples,
                     "confidence": c.confidence,
+                    "match_score": c.match_score,
                     "drift_ppm": c.drift_ppm,
                     "drift_confidence": c.drift_confidence,
+                    "drift_windows": c.drift_windows,
+                    "drift_segments": c.drift_segments,
+                    "timings": c.timings,
                 })).collect::<Vec<_>>(),
             })).collect::<Vec<_>>(),
+            "timings": timing_summary(tracks),
             "elapsed_s": elapsed,
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
-        print_analysis_report(&tracks, &result, elapsed);
+        print_analysis_report(tracks, &result, elapsed);
     }
 
-    Ok(())
+    Ok(result)
 }
 
 fn cmd_sync(
-    files: Vec<String>,
-    output_dir: String,
-    format: String,
-    bit_depth: u32,
-    max_offset: Option<f64>,
-    no_drift_correction: bool,
-    save: Option<String>,
-    fcpxml: Option<String>,
-    edl: Option<String>,
+    args: SyncArgs,
+    timelines: TimelineArgs,
     json: bool,
-) -> anyhow::Result<()> {
+    remote: RemoteArgs,
+) -> anyhow::Result<SyncResult> {
+    let SyncArgs {
+        files,
+        output_dir,
+        format,
+        bit_depth,
+        mono,
+        max_offset,
+        band_pass,
+        device_bands,
+        denoise,
+        slate,
+        ltc_channel,
+        offsets,
+        sync_groups,
+        auto_groups,
+        session_gap,
+        reference,
+        reference_layout,
+        no_offset_check,
+        reuse_analysis,
+        no_drift_correction,
+        no_drift_inherit,
+        no_pre_roll,
+        no_refine,
+        drift_quality,
+        overlap,
+        preview_minutes,
+        folder_template,
+        filename_template,
+        roles,
+        embed_metadata,
+        verify,
+        post_cmd,
+        io_parallelism,
+        no_cache,
+        save,
+        aaf,
+        debug_correlation,
+    } = args;
     let t0 = Instant::now();
 
-    let mut tracks = load_files_into_tracks(&files)?;
-    if tracks.is_empty() {
-        anyhow::bail!("No supported files found.");
-    }
-
     let mut config = SyncConfig {
         max_offset_s: max_offset,
+        correlation_band_hz: band_pass,
+        device_band_weighting: device_bands.into_iter().collect(),
+        denoise,
+        slate_prior: slate,
+        ltc_channel: ltc_channel.map(|c| c - 1),
+        auto_sync_groups: auto_groups,
+        session_gap_s: session_gap.map(|m| m * 60.0),
+        reference_strategy: reference.unwrap_or_default(),
+        reference_layout: reference_layout.map(Into::into).unwrap_or_default(),
+        verify_offsets: !no_offset_check,
         export_format: format.clone(),
         export_bit_depth: bit_depth,
+        export_channels: if mono { ChannelPolicy::Mono } else { ChannelPolicy::Preserve },
         drift_correction: !no_drift_correction,
+        inherit_drift: !no_drift_inherit,
+        place_pre_roll: !no_pre_roll,
+        refine_offsets: !no_refine,
+        // Previews only need to show the sync, so they take the fast path.
+        drift_resample_quality: match (drift_quality, preview_minutes) {
+            (Some(q), _) => q.into(),
+            (None, Some(_)) => ResampleQuality::Fast,
+            (None, None) => ResampleQuality::default(),
+        },
+        overlap_policy: overlap.map(Into::into).unwrap_or_default(),
+        io_parallelism,
+        export_range_s: preview_minutes.map(|m| (0.0, m * 60.0)),
+        export_folder_template: folder_template,
+        export_filename_template: filename_template,
+        post_export_command: post_cmd,
+        verify_export: verify,
+        debug_correlation_dir: debug_correlation,
         ..Default::default()
     };
+    if no_cache {
+        config.analysis_cache_max_mb = 0;
+    }
+    let mut session = SessionController::new(config);
+    import_files(&mut session, &files)?;
+    apply_role_overrides(&mut session, &roles)?;
+    apply_sync_groups(&mut session, &sync_groups)?;
+    apply_offset_overrides(&mut session, &offsets)?;
+    apply_prior_reuse(&mut session, reuse_analysis);
 
-    let progress: Option<ProgressCallback> = if !json {
-        Some(Box::new(|step, total, msg| {
-            eprintln!("[{}/{}] {}", step, total, msg);
-        }))
-    } else {
-        None
-    };
+    let progress = progress_printer(json);
 
     // Phase 1: Analyze
-    let result = analyze(&mut tracks, &config, &progress, &None)?;
-
-    // Phase 2: Sync
-    sync(&mut tracks, &result, &mut config, &progress, &None)?;
-
-    // Phase 3: Export
-    std::fs::create_dir_all(&output_dir)?;
-    let export_sr = config.export_sr.unwrap_or(48000);
-    let mut exported_files: Vec<String> = Vec::new();
-
-    for track in &tracks {
-        let filename = format!(
-            "{}_{}.{}",
-            sanitize_filename(&track.name),
-            export_sr,
-            format
-        );
-        let output_path = Path::new(&output_dir).join(&filename);
-        let output_str = output_path.to_string_lossy().to_string();
-
-        if !json {
-            eprintln!("Exporting '{}'...", filename);
-        }
-
-        export_track(track, &output_str, &config)?;
-        exported_files.push(output_str);
-    }
+    let result = run_analysis(&mut session, &progress, &remote)?;
+
+    // Phase 2: Sync and export (unreadable clips are skipped, not fatal)
+    let project_name = save
+        .as_deref()
+        .and_then(|p| Path::new(p).file_name())
+        .map(|n| {
+            let name = n.to_string_lossy();
+            name.trim_end_matches(".json").trim_end_matches(".audiosync").to_string()
+        })
+        .unwrap_or_else(|| "audiosync".to_string());
+    let request = ExportRequest {
+        output_dir: output_dir.clone(),
+        project_name,
+        suffix: if preview_minutes.is_some() { "_preview" } else { "" }.to_string(),
+        embed_metadata,
+        fcpxml_path: timelines.fcpxml,
+        fcpxml_format: None,
+        edl_path: timelines.edl,
+        timeline_json_path: timelines.timeline_json,
+        aaf_path: aaf,
+    };
+    let sync_map = timelines.sync_map;
+    // The post-export hook only runs if every file passed verification
+    let outcome = session.export(&request, &progress, &None)?;
+    let failed_verification = outcome.failed_verification();
+    let ExportOutcome {
+        files: exported_files,
+        skipped,
+        verification,
+        hook_error,
+    } = outcome;
 
     let elapsed = t0.elapsed().as_secs_f64();
 
     // Save project if requested
     if let Some(ref path) = save {
-        save_project(path, &tracks, &config, Some(&result))?;
+        session.save(path)?;
     }
-
-    // Export FCPXML
-    if let Some(ref path) = fcpxml {
-        export_fcpxml(&tracks, &result, path, None)?;
+    if let Some(ref path) = sync_map {
+        session.save_sync_map(path)?;
     }
-
-    // Export EDL
-    if let Some(ref path) = edl {
-        export_edl(&tracks, &result, path, None)?;
+    if let Some(e) = hook_error {
+        anyhow::bail!(e);
     }
+    let tracks = &session.tracks;
 
     if json {
         let output = serde_json::json!({
             "result": result,
             "exported_files": exported_files,
+            "skipped": skipped,
+            "verification": verification,
+            "timings": timing_summary(tracks),
             "elapsed_s": elapsed,
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
-        print_analysis_report(&tracks, &result, elapsed);
+        print_analysis_report(tracks, &result, elapsed);
         eprintln!("\nExported {} files to '{}'", exported_files.len(), output_dir);
         for f in &exported_files {
             eprintln!("  {}", f);
         }
+        if !skipped.is_empty() {
+            eprintln!("\nSkipped {} unreadable clip(s) (left silent):", skipped.len());
+            for s in &skipped {
+                eprintln!("  ⚠ {} / {} — {}", s.track_name, s.clip_name, s.reason);
+            }
+        }
+        if !verification.is_empty() {
+            eprintln!("\nVerification:");
+            for v in &verification {
+                if v.ok {
+                    eprintln!("  ✓ {}", v.file_path);
+                } else {
+                    eprintln!("  ✖ {} — {}", v.file_path, v.message);
+                }
+            }
+        }
     }
 
-    Ok(())
+    if failed_verification > 0 {
+        anyhow::bail!("{} exported file(s) failed verification", failed_verification);
+    }
+    Ok(result)
 }
 
 fn cmd_drift(reference: String, target: String, json: bool) -> anyhow::Result<()> {
@@ -407,24 +1343,21 @@ fn cmd_drift(reference: String, target: String, json: bool) -> anyhow::Result<()
     let mut tgt_clip = load_clip(&target, &None)?;
 
     // First find the delay
-    let (delay, conf) = compute_delay(
-        &ref_clip.samples,
-        &tgt_clip.samples,
-        ANALYSIS_SR,
-        None,
-    );
+    let m = match_delay(&ref_clip.samples, &tgt_clip.samples, ANALYSIS_SR, None);
+    let (delay, conf) = (m.delay_samples, m.confidence);
 
     tgt_clip.timeline_offset_samples = delay;
-    tgt_clip.timeline_offset_s = delay as f64 / ANALYSIS_SR as f64;
+    tgt_clip.timeline_offset_s = m.delay_s;
     tgt_clip.confidence = conf;
+    tgt_clip.match_score = m.score;
     tgt_clip.analyzed = true;
 
     if !json {
         eprintln!(
-            "Delay: {:.3} s ({} samples), confidence: {:.1}",
+            "Delay: {:.3} s ({} samples), confidence: {:.0}/100",
             tgt_clip.timeline_offset_s,
             delay,
-            conf
+            m.score
         );
     }
 
@@ -432,7 +1365,14 @@ fn cmd_drift(reference: String, target: String, json: bool) -> anyhow::Result<()
     let ref_timeline = ref_clip.samples.clone();
 
     // Measure drift
-    let (drift_ppm, r_sq) = measure_drift(&ref_timeline, &tgt_clip, ANALYSIS_SR);
+    let drift = measure_drift(&ref_timeline, &tgt_clip, ANALYSIS_SR, &None);
+    let (drift_ppm, r_sq) = (drift.drift_ppm, drift.r_squared);
+    let peak_ppm = drift
+        .segments
+        .iter()
+        .map(|s| s.ppm.abs())
+        .fold(drift_ppm.abs(), f64::max);
+    let significant = peak_ppm > 0.3 && drift.confidence > 0.5;
 
     if json {
         let output = serde_json::json!({
@@ -441,16 +1381,37 @@ fn cmd_drift(reference: String, target: String, json: bool) -> anyhow::Result<()
             "delay_samples": delay,
             "delay_s": tgt_clip.timeline_offset_s,
             "confidence": conf,
+            "match_score": m.score,
             "drift_ppm": drift_ppm,
             "drift_r_squared": r_sq,
-            "drift_significant": drift_ppm.abs() > 0.3 && r_sq > 0.5,
+            "drift_confidence": drift.confidence,
+            "drift_windows": drift.windows,
+            "drift_inliers": drift.inliers,
+            "drift_window_s": drift.window_s,
+            "drift_segments": drift.segments,
+            "drift_significant": significant,
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
         eprintln!("\n--- Drift Measurement ---");
         eprintln!("Drift:       {:+.2} ppm", drift_ppm);
         eprintln!("R-squared:   {:.4}", r_sq);
-        if drift_ppm.abs() > 0.3 && r_sq > 0.5 {
+        eprintln!(
+            "Windows:     {} × {:.0} s ({} inliers)",
+            drift.windows, drift.window_s, drift.inliers
+        );
+        if !drift.segments.is_empty() {
+            eprintln!("Piecewise:   {} segments", drift.segments.len());
+            for seg in &drift.segments {
+                eprintln!("             {:+.2} ppm from {:.1} s", seg.ppm, seg.start_s);
+            }
+        }
+        if drift.windows == 0 {
+            eprintln!(
+                "Status:      Not measured — overlap shorter than {:.0} s",
+                MIN_DRIFT_OVERLAP_S
+            );
+        } else if significant {
             eprintln!("Status:      DRIFT DETECTED — correction recommended");
         } else if r_sq < 0.3 {
             eprintln!("Status:      Measurement inconclusive (low R²)");
@@ -462,19 +1423,33 @@ fn cmd_drift(reference: String, target: String, json: bool) -> anyhow::Result<()
     Ok(())
 }
 
-fn cmd_info(files: Vec<String>, json: bool) -> anyhow::Result<()> {
+fn cmd_info(files: Vec<String>, deep: bool, json: bool) -> anyhow::Result<()> {
     let supported: Vec<String> = files
         .into_iter()
         .filter(|f| is_supported_file(f))
         .collect();
 
     let groups = group_files_by_device(&supported);
+    let details: HashMap<&str, StreamMetadata> = if deep {
+        supported
+            .iter()
+            .map(|p| (p.as_str(), probe_stream_metadata(p)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
 
     if json {
-        let output = serde_json::json!({
+        let mut output = serde_json::json!({
             "supported_files": supported.len(),
             "groups": groups,
         });
+        if deep {
+            output["files"] = supported
+                .iter()
+                .map(|p| serde_json::json!({ "file_path": p, "metadata": details[p.as_str()] }))
+                .collect();
+        }
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
         eprintln!("AudioSync Pro — File Info");
@@ -491,6 +1466,9 @@ fn cmd_info(files: Vec<String>, json: bool) -> anyhow::Result<()> {
                     .unwrap_or_default()
                     .to_string_lossy();
                 eprintln!("    {}", fname);
+                if let Some(m) = details.get(p.as_str()) {
+                    eprintln!("      {}", describe_stream(m));
+                }
             }
         }
     }
@@ -498,50 +1476,491 @@ fn cmd_info(files: Vec<String>, json: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
-// ---------------------------------------------------------------------------
-//  Helpers
-// ---------------------------------------------------------------------------
-
-fn load_files_into_tracks(files: &[String]) -> anyhow::Result<Vec<Track>> {
-    let supported: Vec<String> = files
-        .iter()
-        .filter(|f| is_supported_file(f))
-        .cloned()
+/// One-line summary of probed stream metadata for `info --deep`.
+fn describe_stream(m: &StreamMetadata) -> String {
+    let mut parts = vec![m.codec.clone().unwrap_or_else(|| "unknown codec".to_string())];
+    if let Some(bits) = m.bit_depth {
+        parts.push(format!("{}-bit", bits));
+    }
+    if let Some(d) = m.container_duration_s {
+        parts.push(format!("{:.1}s", d));
+    }
+    let camera: Vec<&str> = [m.camera_make.as_deref(), m.camera_model.as_deref()]
+        .into_iter()
+        .flatten()
         .collect();
+    if !camera.is_empty() {
+        parts.push(camera.join(" "));
+    }
+    parts.join(", ")
+}
 
+fn cmd_precache(files: Vec<String>, io_parallelism: usize) -> anyhow::Result<()> {
+    let t0 = Instant::now();
+    let cache = AnalysisCache::from_config(&SyncConfig::default())
+        .ok_or_else(|| anyhow::anyhow!("No cache directory available on this system."))?;
+    let supported: Vec<String> = files.iter().filter(|f| is_supported_file(f)).cloned().collect();
     if supported.is_empty() {
-        anyhow::bail!(
-            "No supported audio/video files found. \
-             Supported: WAV, AIFF, FLAC, MP3, OGG, OPUS, MP4, MOV, MKV, AVI, etc."
-        );
+        anyhow::bail!("No supported files found.");
+    }
+    if supported.len() < files.len() {
+        eprintln!("Skipping {} unsupported file(s)", files.len() - supported.len());
     }
 
-    let groups = group_files_by_device(&supported);
-    let mut tracks = Vec::new();
+    let outcomes = precache_files(&supported, io_parallelism, &cache, &None, |_, path| {
+        eprintln!("Caching: {}", Path::new(path).file_name().unwrap_or_default().to_string_lossy());
+    });
+    let mut fresh = 0;
+    let mut failed = 0;
+    for (path, outcome) in supported.iter().zip(&outcomes) {
+        match outcome {
+            Ok(true) => {}
+            Ok(false) => fresh += 1,
+            Err(e) => {
+                failed += 1;
+                eprintln!("  ✗ {}: {}", path, e);
+            }
+        }
+    }
+    eprintln!(
+        "Cached {} file(s), {} already cached, in {:.1}s ({})",
+        fresh,
+        supported.len() - fresh - failed,
+        t0.elapsed().as_secs_f64(),
+        cache.dir().display()
+    );
+    if failed > 0 {
+        anyhow::bail!("{} file(s) could not be cached", failed);
+    }
+    Ok(())
+}
+
+fn cmd_null_test(
+    a: &str,
+    b: &str,
+    offset: NullOffset,
+    difference: Option<String>,
+    json: bool,
+) -> anyhow::Result<()> {
+    if !json {
+        eprintln!("Comparing {} against {}", b, a);
+    }
+    let report = null_test(a, b, offset, difference.as_deref(), &None)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    eprintln!("\n--- Null Test ---");
+    match report.match_score {
+        Some(score) => eprintln!(
+            "Offset:      {:+.6} s ({} samples at {} Hz, match {:.0}/100)",
+            report.offset_s, report.offset_samples, report.sample_rate, score
+        ),
+        None => eprintln!(
+            "Offset:      {:+.6} s ({} samples at {} Hz)",
+            report.offset_s, report.offset_samples, report.sample_rate
+        ),
+    }
+    eprintln!("Overlap:     {:.1} s", report.overlap_s);
+    eprintln!("Level (A):   {:.1} dBFS RMS", report.rms_a_db);
+    eprintln!(
+        "Residual:    {:.1} dBFS RMS, {:.1} dBFS peak",
+        report.residual_rms_db, report.residual_peak_db
+    );
+    eprintln!("Null depth:  {:.1} dB", report.null_depth_db);
+    if let Some(ref path) = report.difference_path {
+        eprintln!("Difference:  {}", path);
+    }
+    Ok(())
+}
+
+fn cmd_check(project: String, json: bool) -> anyhow::Result<()> {
+    let project_file = load_project(&project)?;
+    let missing = preflight_check(&project_file.tracks);
+    let total_clips: usize = project_file.tracks.iter().map(|t| t.clip_count()).sum();
+
+    if json {
+        let output = serde_json::json!({
+            "project": project,
+            "total_clips": total_clips,
+            "missing": missing,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        eprintln!("Checked {} clip(s) in '{}'", total_clips, project);
+        for m in &missing {
+            eprintln!("  ✗ {} / {} — {} ({})", m.track_name, m.clip_name, m.reason, m.file_path);
+        }
+    }
+
+    if !missing.is_empty() {
+        anyhow::bail!("{} source file(s) unavailable", missing.len());
+    }
+    if !json {
+        eprintln!("All source files are available.");
+    }
+    Ok(())
+}
+
+fn cmd_show(project: &str, json: bool) -> anyhow::Result<()> {
+    let file = load_project(project)?;
+    let tracks = &file.tracks;
+    let comments: Vec<_> = file.comments.iter().filter(|c| !c.deleted).collect();
+
+    if json {
+        let output = serde_json::json!({
+            "project": project,
+            "version": file.version,
+            "app_version": file.app_version,
+            "saved_at": file.saved_at,
+            "config": file.config,
+            "result": file.result,
+            "tracks": tracks.iter().map(|t| serde_json::json!({
+                "name": t.name,
+                "role": t.role(),
+                "is_reference": t.is_reference,
+                "locked": t.locked,
+                "sync_group": t.sync_group,
+                "clips": t.clips.iter().map(|c| serde_json::json!({
+                    "name": c.name,
+                    "file_path": c.file_path,
+                    "duration_s": c.duration_s,
+                    "creation_time": c.creation_time,
+                    "analyzed": c.analyzed,
+                    "unplaced": c.unplaced,
+                    "excluded": c.excluded,
+                    "locked": c.locked,
+                    "offset_s": c.timeline_offset_s,
+                    "offset_override_s": c.offset_override_s,
+                    "match_score": c.match_score,
+                    "drift_ppm": c.drift_ppm,
+                    "drift_override": c.drift_override,
+                    "drift_inherited": c.drift_inherited,
+                    "gain_db": c.gain_db,
+                    "trim_in_s": c.trim_in_s,
+                    "trim_out_s": c.trim_out_s,
+                    "polarity_inverted": c.polarity_inverted,
+                })).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+            "comments": comments.len(),
+            "open_comments": comments.iter().filter(|c| !c.resolved).count(),
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let config = &file.config;
+    let total_clips: usize = tracks.iter().map(|t| t.clip_count()).sum();
+    eprintln!("Project:          {}", project);
+    eprintln!("Saved:            {} (AudioSync {}, format v{})", file.saved_at, file.app_version, file.version);
+    eprintln!("Tracks:           {}", tracks.len());
+    eprintln!("Total clips:      {}", total_clips);
+    if !comments.is_empty() {
+        let open = comments.iter().filter(|c| !c.resolved).count();
+        eprintln!("Comments:         {} ({} open)", comments.len(), open);
+    }
+    match &file.result {
+        Some(result) => print_result_summary(tracks, result),
+        None => eprintln!("Analysis:         not analyzed"),
+    }
+
+    eprintln!("\nSettings:");
+    eprintln!(
+        "  export {} {}-bit at {}, channels {:?}, overlaps {:?}",
+        config.export_format,
+        config.export_bit_depth,
+        config.export_sr.map_or("source rate".to_string(), |sr| format!("{} Hz", sr)),
+        config.export_channels,
+        config.overlap_policy
+    );
+    eprintln!(
+        "  drift correction {} (threshold {} ppm, {:?} resampler, inherit {})",
+        if config.drift_correction { "on" } else { "off" },
+        config.drift_threshold_ppm,
+        config.drift_resample_quality,
+        if config.inherit_drift { "on" } else { "off" }
+    );
+    eprintln!(
+        "  reference {:?}, layout {:?}, max offset {}, band-pass {}",
+        config.reference_strategy,
+        config.reference_layout,
+        config.max_offset_s.map_or("none".to_string(), |s| format!("{} s", s)),
+        config
+            .correlation_band_hz
+            .map_or("none".to_string(), |(lo, hi)| format!("{}-{} Hz", lo, hi))
+    );
+    if let Some(anchor) = &config.anchor_clip {
+        eprintln!("  anchor clip {}", anchor);
+    }
+
+    print_track_listing(tracks, file.result.as_ref());
+    if let Some(result) = &file.result {
+        print_findings(tracks, result);
+    }
+    Ok(())
+}
+
+fn cmd_export_clip(project: &str, clip: &str, output_dir: String) -> anyhow::Result<()> {
+    let mut session = SessionController::open(project)?;
+    let (ti, ci) = session
+        .tracks
+        .iter()
+        .enumerate()
+        .find_map(|(ti, t)| {
+            t.clips
+                .iter()
+                .position(|c| c.name == clip || c.file_path == clip)
+                .map(|ci| (ti, ci))
+        })
+        .ok_or_else(|| anyhow::anyhow!("--clip: no clip named '{}' in '{}'", clip, project))?;
+    let project_name = Path::new(project)
+        .file_name()
+        .map(|n| {
+            let name = n.to_string_lossy();
+            name.trim_end_matches(".json").trim_end_matches(".audiosync").to_string()
+        })
+        .unwrap_or_else(|| "audiosync".to_string());
+    let request = ExportRequest {
+        output_dir,
+        project_name,
+        ..Default::default()
+    };
+    let outcome = session.export_clip(ti, ci, &request, &None, &None)?;
+    for f in &outcome.files {
+        eprintln!("Exported '{}' aligned to timeline zero:\n  {}", clip, f);
+    }
+    for s in &outcome.skipped {
+        eprintln!("  ⚠ {} / {} — {}", s.track_name, s.clip_name, s.reason);
+    }
+    if outcome.failed_verification() > 0 {
+        anyhow::bail!("{} failed verification", outcome.files.join(", "));
+    }
+    Ok(())
+}
+
+fn cmd_query(project: &str, query: &ClipQuery, json: bool) -> anyhow::Result<()> {
+    let project_file = load_project(project)?;
+    let tracks = &project_file.tracks;
+    let matches = query_clips(tracks, query);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+    } else {
+        for m in &matches {
+            let track = &tracks[m.track_index];
+            let clip = &track.clips[m.clip_index];
+            println!("{} / {}\t{}", track.name, clip.name, m.clip_id);
+        }
+        let total: usize = tracks.iter().map(|t| t.clip_count()).sum();
+        eprintln!("{} of {} clip(s) match", matches.len(), total);
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+//  Helpers
+// ---------------------------------------------------------------------------
 
-    for (device_name, paths) in groups {
-        let mut track = Track::new(device_name.clone());
-        for path in &paths {
+/// Load `files` into the session, one track per device, reporting on stderr.
+fn import_files(session: &mut SessionController, files: &[String]) -> anyhow::Result<()> {
+    let failures = session.import_files(
+        files,
+        &None,
+        |_, path| {
             eprintln!("Loading: {}", Path::new(path).file_name().unwrap_or_default().to_string_lossy());
-            match load_clip(path, &None) {
-                Ok(clip) => {
-                    eprintln!(
-                        "  {} — {:.1}s, {} Hz, {} ch",
-                        clip.name, clip.duration_s, clip.original_sr, clip.original_channels
-                    );
-                    track.clips.push(clip);
-                }
-                Err(e) => {
-                    eprintln!("  WARNING: Failed to load {}: {}", path, e);
-                }
+        },
+        |meter| {
+            if meter.done && meter.peak < SILENT_PEAK {
+                eprintln!(
+                    "  ⚠ {} appears to be silent",
+                    Path::new(&meter.file_path).file_name().unwrap_or_default().to_string_lossy()
+                );
+            }
+        },
+    )?;
+    for clip in session.tracks.iter().flat_map(|t| &t.clips) {
+        eprintln!(
+            "  {} — {:.1}s, {} Hz, {} ch",
+            clip.name, clip.duration_s, clip.original_sr, clip.original_channels
+        );
+    }
+    for failure in &failures {
+        eprintln!("  WARNING: Failed to load {}: {}", failure.path, failure.error);
+    }
+    if session.tracks.is_empty() {
+        anyhow::bail!("No supported files found.");
+    }
+    Ok(())
+}
+
+/// Run a simulated job; with `--json`, each progress event is a JSON line on
+/// stdout instead of a progress line on stderr.
+#[cfg(debug_assertions)]
+fn cmd_simulate(job: &SimulatedJob, json: bool) -> anyhow::Result<()> {
+    let progress: Option<ProgressCallback> = if json {
+        Some(Box::new(|event: &ProgressEvent| {
+            if let Ok(line) = serde_json::to_string(event) {
+                println!("{}", line);
             }
+        }))
+    } else {
+        progress_printer(false)
+    };
+    let steps = run_simulated_job(job, &progress, &None)?;
+    eprintln!("Simulated job finished: {} steps.", steps);
+    Ok(())
+}
+
+/// Progress lines on stderr, none with `--json`:
+/// `[ 40%] Pass 1: correlating 'a.wav'... (3/12, ~1m 05s left)`.
+fn progress_printer(json: bool) -> Option<ProgressCallback> {
+    if json {
+        return None;
+    }
+    Some(Box::new(|event: &ProgressEvent| {
+        let mut detail = Vec::new();
+        if event.total > 0 {
+            detail.push(format!("{}/{}", event.current, event.total));
+        }
+        if let Some(eta_s) = event.eta_s {
+            detail.push(format!("~{} left", format_eta(eta_s)));
+        }
+        let detail = if detail.is_empty() { String::new() } else { format!(" ({})", detail.join(", ")) };
+        eprintln!("[{:>3.0}%] {}{}", event.fraction * 100.0, event.message, detail);
+    }))
+}
+
+fn format_eta(eta_s: f64) -> String {
+    let s = eta_s.round() as u64;
+    if s >= 3600 {
+        format!("{}h {:02}m", s / 3600, s % 3600 / 60)
+    } else if s >= 60 {
+        format!("{}m {:02}s", s / 60, s % 60)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Analyze locally, offload to a serve-mode instance when `--remote` is set,
+/// or place clips from `--from-sync-map`.
+fn run_analysis(
+    session: &mut SessionController,
+    progress: &Option<ProgressCallback>,
+    remote: &RemoteArgs,
+) -> anyhow::Result<SyncResult> {
+    if let Some(path) = &remote.from_sync_map {
+        eprintln!("Placing clips from sync map {}...", path);
+        return Ok(session.apply_sync_map(path)?.clone());
+    }
+    match remote.remote {
+        Some(ref endpoint) => {
+            eprintln!("Offloading analysis to {}...", endpoint);
+            let runtime = tokio::runtime::Runtime::new()?;
+            let result = runtime.block_on(analyze_remote(
+                endpoint,
+                &mut session.tracks,
+                &session.config,
+                remote.remote_secret.as_deref(),
+            ))?;
+            session.result = Some(result.clone());
+            Ok(result)
+        }
+        None => session.analyze(progress, &None).cloned(),
+    }
+}
+
+/// Apply `--role TRACK=ROLE` overrides by track name.
+fn apply_role_overrides(session: &mut SessionController, roles: &[String]) -> anyhow::Result<()> {
+    for spec in roles {
+        let (name, role) = spec
+            .rsplit_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid --role '{}', expected TRACK=ROLE", spec))?;
+        let role: TrackRole = role.parse().map_err(anyhow::Error::msg)?;
+        session
+            .set_track_role_by_name(name.trim(), role)
+            .map_err(|e| anyhow::anyhow!("--role: {}", e))?;
+    }
+    Ok(())
+}
+
+fn apply_sync_groups(session: &mut SessionController, groups: &[String]) -> anyhow::Result<()> {
+    for spec in groups {
+        let (name, group) = spec
+            .rsplit_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid --sync-group '{}', expected TRACK=GROUP", spec))?;
+        session
+            .set_track_sync_group_by_name(name.trim(), group)
+            .map_err(|e| anyhow::anyhow!("--sync-group: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Apply `--offset CLIP=TIME` placements, matching clips by name or path.
+fn apply_offset_overrides(session: &mut SessionController, offsets: &[(String, f64)]) -> anyhow::Result<()> {
+    for (clip, offset_s) in offsets {
+        let (ti, ci) = session
+            .tracks
+            .iter()
+            .enumerate()
+            .find_map(|(ti, t)| {
+                t.clips
+                    .iter()
+                    .position(|c| &c.name == clip || &c.file_path == clip)
+                    .map(|ci| (ti, ci))
+            })
+            .ok_or_else(|| anyhow::anyhow!("--offset: no clip named '{}'", clip))?;
+        session.set_clip_offset(ti, ci, Some(*offset_s))?;
+    }
+    Ok(())
+}
+
+/// Apply `--reuse-analysis`, or point it out when earlier analyses of these
+/// files exist. Nothing to reuse is not an error.
+fn apply_prior_reuse(session: &mut SessionController, mode: Option<ReuseMode>) {
+    let Some(mode) = mode else {
+        if let Some(run) = session.reusable_analyses().first() {
+            eprintln!(
+                "  {} of these clips were synced together before ({}); \
+                 --reuse-analysis skip keeps that placement",
+                run.clip_ids.len(),
+                run.analyzed_at
+            );
         }
-        if !track.clips.is_empty() {
-            tracks.push(track);
+        return;
+    };
+    match session.reuse_prior_analysis(mode.into()) {
+        Ok(count) if matches!(mode, ReuseMode::Skip) => {
+            eprintln!("  Kept the earlier placement of {} clip(s)", count);
+            session.config.incremental = true;
         }
+        Ok(count) => eprintln!("  {} clip(s) have earlier placements to guide ambiguous matches", count),
+        Err(e) => eprintln!("  Not reusing earlier analyses: {}", e),
+    }
+}
+
+/// Fail the command when `--fail-on` is set and a warning reaches that severity.
+fn check_fail_on(result: &SyncResult, fail_on: Option<FailOn>) -> anyhow::Result<()> {
+    let Some(level) = fail_on else {
+        return Ok(());
+    };
+    let threshold = WarningSeverity::from(level);
+    if result.has_warnings_at(threshold) {
+        let count = result.warnings.iter().filter(|w| w.severity >= threshold).count();
+        anyhow::bail!("{} warning(s) at or above '{}' severity", count, threshold);
     }
+    Ok(())
+}
 
-    Ok(tracks)
+/// Totals of per-clip stage timings plus the dominant stage, for JSON reports.
+fn timing_summary(tracks: &[Track]) -> serde_json::Value {
+    let totals = ClipTimings::sum(tracks.iter().flat_map(|t| t.clips.iter().map(|c| &c.timings)));
+    serde_json::json!({
+        "decode_s": totals.decode_s,
+        "resample_s": totals.resample_s,
+        "correlation_s": totals.correlation_s,
+        "bottleneck": totals.bottleneck(),
+    })
 }
 
 fn print_analysis_report(tracks: &[Track], result: &SyncResult, elapsed_s: f64) {
@@ -552,27 +1971,75 @@ fn print_analysis_report(tracks: &[Track], result: &SyncResult, elapsed_s: f64)
     let total_clips: usize = tracks.iter().map(|t| t.clip_count()).sum();
     eprintln!("Tracks:           {}", tracks.len());
     eprintln!("Total clips:      {}", total_clips);
+    print_result_summary(tracks, result);
+    eprintln!("Elapsed:          {:.2} s", elapsed_s);
+    let totals = ClipTimings::sum(tracks.iter().flat_map(|t| t.clips.iter().map(|c| &c.timings)));
+    eprintln!(
+        "Time breakdown:   decode {:.2} s, resample {:.2} s, correlation {:.2} s",
+        totals.decode_s, totals.resample_s, totals.correlation_s
+    );
+
+    print_track_listing(tracks, Some(result));
+    print_findings(tracks, result);
+}
+
+fn print_result_summary(tracks: &[Track], result: &SyncResult) {
     eprintln!(
         "Timeline:         {:.1} s",
         result.total_timeline_s
     );
+    for group in &result.groups {
+        eprintln!(
+            "  group {:<10} {} track(s), timeline {:.1} s, reference '{}'",
+            group.name,
+            group.track_indices.len(),
+            group.total_timeline_s,
+            tracks[group.reference_track_index].name
+        );
+    }
     eprintln!("Avg confidence:   {:.1}", result.avg_confidence);
     eprintln!(
         "Drift detected:   {}",
         if result.drift_detected { "YES" } else { "No" }
     );
-    eprintln!("Elapsed:          {:.2} s", elapsed_s);
+}
 
+/// Tracks and their clips; placements only once analyzed.
+fn print_track_listing(tracks: &[Track], result: Option<&SyncResult>) {
     for track in tracks {
+        let group = if result.is_none_or(|r| r.groups.is_empty()) {
+            String::new()
+        } else {
+            format!(" ({})", track.sync_group_name())
+        };
         eprintln!(
-            "\n  {} {}",
+            "\n  {} {}{}",
             if track.is_reference { "[REF]" } else { "     " },
-            track.name
+            track.name,
+            group
         );
         for clip in &track.clips {
+            if clip.excluded {
+                eprintln!("    {} — {:.1}s, excluded", clip.name, clip.duration_s);
+                continue;
+            }
+            if clip.unplaced {
+                eprintln!("    {} — {:.1}s, unplaced (matches nothing in this session)", clip.name, clip.duration_s);
+                continue;
+            }
+            if result.is_none() {
+                eprintln!("    {} — {:.1}s", clip.name, clip.duration_s);
+                continue;
+            }
             let offset_str = format!("{:+.3}s", clip.timeline_offset_s);
-            let conf_str = format!("conf={:.1}", clip.confidence);
-            let drift_str = if clip.drift_ppm.abs() > 0.1 {
+            let conf_str = format!("conf={:.0}/100", clip.match_score);
+            let drift_str = if clip.drift_segments.len() > 1 {
+                format!(
+                    ", drift={:+.1}ppm piecewise, {} segments",
+                    clip.drift_ppm,
+                    clip.drift_segments.len()
+                )
+            } else if clip.drift_ppm.abs() > 0.1 {
                 format!(", drift={:+.1}ppm", clip.drift_ppm)
             } else {
                 String::new()
@@ -583,23 +2050,36 @@ fn print_analysis_report(tracks: &[Track], result: &SyncResult, elapsed_s: f64)
             );
         }
     }
+}
 
+fn print_findings(tracks: &[Track], result: &SyncResult) {
     if !result.warnings.is_empty() {
         eprintln!("\nWarnings:");
-        for w in &result.warnings {
-            eprintln!("  ⚠ {}", w);
+        let mut warnings: Vec<&SyncWarning> = result.warnings.iter().collect();
+        warnings.sort_by_key(|w| std::cmp::Reverse(w.severity));
+        for w in warnings {
+            let icon = match w.severity {
+                WarningSeverity::Error => "✖",
+                WarningSeverity::Warning => "⚠",
+                WarningSeverity::Info => "ℹ",
+            };
+            eprintln!("  {} [{}] {}", icon, w.severity, w);
+            if let Some(ref hint) = w.suggestion {
+                eprintln!("      → {}", hint);
+            }
         }
     }
-}
 
-fn sanitize_filename(name: &str) -> String {
-    name.chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
-                c
-            } else {
-                '_'
+    if !result.suggestions.is_empty() {
+        eprintln!("\nSuggestions for unmatched clips:");
+        for clip in tracks.iter().flat_map(|t| t.clips.iter()) {
+            let mut hints = result.suggestions.iter().filter(|s| s.clip_id == clip.file_path);
+            if let Some(first) = hints.next() {
+                eprintln!("  {}", clip.name);
+                for s in std::iter::once(first).chain(hints) {
+                    eprintln!("    → {}", s.message);
+                }
             }
-        })
-        .collect()
+        }
+    }
 }
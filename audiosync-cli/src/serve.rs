@@ -0,0 +1,169 @@
+//! `audiosync serve` — minimal HTTP server for remote analysis offload.
+//!
+//! Endpoints:
+//!     GET  /health   → {"status": "ok", "version": "..."}
+//!     POST /analyze  → AnalysisRequest JSON in, AnalysisResponse JSON out
+//!
+//! Deliberately dependency-free (std::net + one thread per connection, a few
+//! at a time); it is meant for a trusted LAN or an SSH tunnel, not the open
+//! internet.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use audiosync_core::remote::{handle_analysis_request, AnalysisRequest, ANALYZE_PATH};
+
+/// Refuse request bodies larger than this: about three hours of recordings
+/// (8 kHz PCM16 is 58 MB an hour, 77 MB once base64-encoded).
+const MAX_BODY_BYTES: usize = 256 * 1024 * 1024;
+
+/// Connections handled at once; more are turned away with 503 rather than
+/// each getting a thread and a body buffer.
+const MAX_CONNECTIONS: usize = 4;
+
+/// A client that sends nothing for this long loses its connection slot.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The body is read in pieces of this size, so memory follows what actually
+/// arrives rather than what `Content-Length` claims.
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Serve forever. With `secret` set, result manifests are HMAC-signed.
+pub fn run(bind: &str, secret: Option<String>) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(bind)?;
+    eprintln!("AudioSync analysis server listening on http://{}", bind);
+    if secret.is_none() {
+        eprintln!("No --secret given: result manifests will be unsigned.");
+    }
+
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        match stream {
+            Ok(mut stream) => {
+                if open.fetch_add(1, Ordering::AcqRel) >= MAX_CONNECTIONS {
+                    open.fetch_sub(1, Ordering::AcqRel);
+                    log::warn!("Turning away {}: {} requests running", peer(&stream), MAX_CONNECTIONS);
+                    let _ = respond(&mut stream, "503 Service Unavailable", &error_json("Server busy"));
+                    continue;
+                }
+                let slot = Slot(open.clone());
+                let secret = secret.clone();
+                std::thread::spawn(move || {
+                    let _slot = slot;
+                    if let Err(e) = handle_connection(stream, secret.as_deref()) {
+                        log::warn!("Request failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => log::warn!("Connection failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// One of the `MAX_CONNECTIONS`, given back when the connection's thread ends.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, secret: Option<&str>) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return respond(&mut stream, "413 Payload Too Large", &error_json("Request too large"));
+    }
+
+    let body = read_body(&mut reader, content_length)?;
+
+    match (method.as_str(), path.as_str()) {
+        ("GET", "/health") => {
+            let body = serde_json::json!({
+                "status": "ok",
+                "version": env!("CARGO_PKG_VERSION"),
+            });
+            respond(&mut stream, "200 OK", &body.to_string())
+        }
+        ("POST", p) if p == ANALYZE_PATH => {
+            let request: AnalysisRequest = match serde_json::from_slice(&body) {
+                Ok(r) => r,
+                Err(e) => {
+                    return respond(
+                        &mut stream,
+                        "400 Bad Request",
+                        &error_json(&format!("Invalid request: {}", e)),
+                    );
+                }
+            };
+            log::info!("Analyzing {} tracks for {}", request.tracks.len(), peer(&stream));
+            match handle_analysis_request(request, secret) {
+                Ok(response) => respond(&mut stream, "200 OK", &serde_json::to_string(&response)?),
+                Err(e) => respond(
+                    &mut stream,
+                    "422 Unprocessable Entity",
+                    &error_json(&e.to_string()),
+                ),
+            }
+        }
+        _ => respond(&mut stream, "404 Not Found", &error_json("Not found")),
+    }
+}
+
+/// Read exactly `len` bytes of body, growing the buffer as they arrive.
+fn read_body(reader: &mut impl Read, len: usize) -> anyhow::Result<Vec<u8>> {
+    let mut body = Vec::with_capacity(len.min(READ_CHUNK));
+    reader.take(len as u64).read_to_end(&mut body)?;
+    if body.len() < len {
+        anyhow::bail!("Connection closed after {} of {} body bytes", body.len(), len);
+    }
+    Ok(body)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn peer(stream: &TcpStream) -> String {
+    stream
+        .peer_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
@@ -0,0 +1,472 @@
+//! `audiosync tui` — live terminal view of an analysis (and optional export).
+//!
+//! The session runs on a worker thread and reports back over a channel; the
+//! main thread owns the terminal and redraws on every event or tick. Works
+//! over SSH — all it needs is a terminal that speaks ANSI.
+//!
+//! Keys: `q` / `Esc` quits (cancelling any work in progress).
+
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, Paragraph, Row, Table};
+use ratatui::Frame;
+
+use audiosync_core::audio_io::is_supported_file;
+use audiosync_core::models::*;
+use audiosync_core::session::{ExportRequest, SessionController};
+
+/// How often the screen refreshes while nothing is happening.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Width of the per-clip progress bars, in cells.
+const BAR_WIDTH: usize = 12;
+
+/// Scores at or above this are shown green (below `CONFIDENCE_THRESHOLD`, red).
+const GOOD_SCORE: f64 = 50.0;
+
+/// What the TUI should do once analysis finishes.
+pub struct TuiOptions {
+    pub config: SyncConfig,
+    /// Export synced tracks here after analysis; analysis only when `None`.
+    pub output_dir: Option<String>,
+}
+
+/// Run the TUI until the user quits. Fails before touching the terminal if
+/// none of `files` can be loaded.
+pub fn run(files: Vec<String>, options: TuiOptions) -> anyhow::Result<()> {
+    if !files.iter().any(|f| is_supported_file(f)) {
+        anyhow::bail!("No supported files found.");
+    }
+
+    let cancel = new_cancel_token();
+    let (tx, rx) = mpsc::channel();
+    let mut app = App::new(&files);
+    {
+        let cancel = cancel.clone();
+        std::thread::spawn(move || work(files, options, tx, cancel));
+    }
+
+    let mut terminal = ratatui::init();
+    let outcome = event_loop(&mut terminal, &mut app, &rx);
+    ratatui::restore();
+
+    // Stop the worker at its next checkpoint; its result is no longer wanted.
+    cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    outcome?;
+
+    if let Some(ref e) = app.error {
+        anyhow::bail!("{}", e);
+    }
+    Ok(())
+}
+
+fn event_loop(terminal: &mut ratatui::DefaultTerminal, app: &mut App, rx: &Receiver<Update>) -> anyhow::Result<()> {
+    loop {
+        while let Ok(update) = rx.try_recv() {
+            app.apply(update);
+        }
+        terminal.draw(|frame| draw(frame, app))?;
+
+        if event::poll(TICK)?
+            && let TermEvent::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        {
+            return Ok(());
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Worker
+// ---------------------------------------------------------------------------
+
+/// Progress sent from the worker thread to the UI.
+#[derive(Debug)]
+enum Update {
+    Loading(String),
+    Meter(MeterSnapshot),
+    Loaded { groups: Vec<GroupView>, failures: Vec<SyncWarning> },
+    Progress(ProgressEvent),
+    Analyzed { groups: Vec<GroupView>, warnings: Vec<SyncWarning> },
+    Exported(Vec<String>),
+    Done,
+    Failed(String),
+}
+
+fn work(files: Vec<String>, options: TuiOptions, tx: Sender<Update>, cancel: CancelToken) {
+    let result = (|| -> anyhow::Result<()> {
+        let mut session = SessionController::new(options.config);
+        let token = Some(cancel);
+        let (loading, meter) = (tx.clone(), tx.clone());
+        let failures = session.import_files(
+            &files,
+            &token,
+            |_, path| {
+                let _ = loading.send(Update::Loading(path.to_string()));
+            },
+            |snapshot| {
+                let _ = meter.send(Update::Meter(snapshot.clone()));
+            },
+        )?;
+        if session.tracks.is_empty() {
+            anyhow::bail!("No supported files could be loaded.");
+        }
+        let failures = failures
+            .iter()
+            .map(|f| {
+                let message = format!("Failed to load {}: {}", file_name(&f.path), f.error);
+                SyncWarning::new(WarningCode::Other, WarningSeverity::Error, message)
+            })
+            .collect();
+        let _ = tx.send(Update::Loaded {
+            groups: group_views(&session.tracks),
+            failures,
+        });
+
+        let progress = progress_sender(&tx);
+        let warnings = session.analyze(&progress, &token)?.warnings.clone();
+        let _ = tx.send(Update::Analyzed {
+            groups: group_views(&session.tracks),
+            warnings,
+        });
+
+        if let Some(output_dir) = options.output_dir {
+            let request = ExportRequest {
+                output_dir,
+                project_name: "audiosync".to_string(),
+                suffix: String::new(),
+                embed_metadata: false,
+                fcpxml_path: None,
+                fcpxml_format: None,
+                edl_path: None,
+                timeline_json_path: None,
+                aaf_path: None,
+            };
+            let outcome = session.export(&request, &progress, &token)?;
+            let _ = tx.send(Update::Exported(outcome.files));
+        }
+        Ok(())
+    })();
+    let _ = tx.send(match result {
+        Ok(()) => Update::Done,
+        Err(e) => Update::Failed(e.to_string()),
+    });
+}
+
+fn progress_sender(tx: &Sender<Update>) -> Option<ProgressCallback> {
+    let tx = tx.clone();
+    Some(Box::new(move |event: &ProgressEvent| {
+        let _ = tx.send(Update::Progress(event.clone()));
+    }))
+}
+
+// ---------------------------------------------------------------------------
+//  State
+// ---------------------------------------------------------------------------
+
+/// Where a clip is in the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Queued,
+    Decoding,
+    Loaded,
+    Correlating,
+    Placed,
+    Unplaced,
+}
+
+impl Stage {
+    fn fraction(self) -> f64 {
+        match self {
+            Stage::Queued => 0.0,
+            Stage::Decoding => 0.25,
+            Stage::Loaded => 0.5,
+            Stage::Correlating => 0.75,
+            Stage::Placed | Stage::Unplaced => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClipView {
+    name: String,
+    path: String,
+    stage: Stage,
+    decoded_s: f64,
+    offset_s: Option<f64>,
+    score: Option<f64>,
+    drift_ppm: Option<f64>,
+}
+
+/// A track: the clips grouped onto one device.
+#[derive(Debug, Clone)]
+struct GroupView {
+    name: String,
+    is_reference: bool,
+    clips: Vec<ClipView>,
+}
+
+fn group_views(tracks: &[Track]) -> Vec<GroupView> {
+    tracks
+        .iter()
+        .map(|t| GroupView {
+            name: t.name.clone(),
+            is_reference: t.is_reference,
+            clips: t
+                .clips
+                .iter()
+                .map(|c| ClipView {
+                    name: c.name.clone(),
+                    path: c.file_path.clone(),
+                    stage: match (c.analyzed, c.match_score >= CONFIDENCE_THRESHOLD || t.is_reference) {
+                        (false, _) => Stage::Loaded,
+                        (true, true) => Stage::Placed,
+                        (true, false) => Stage::Unplaced,
+                    },
+                    decoded_s: c.duration_s,
+                    offset_s: c.analyzed.then_some(c.timeline_offset_s),
+                    score: c.analyzed.then_some(c.match_score),
+                    drift_ppm: c.analyzed.then_some(c.drift_ppm),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+struct App {
+    groups: Vec<GroupView>,
+    /// Share of the current analysis or export done, 0.0–1.0.
+    fraction: f64,
+    status: String,
+    warnings: Vec<SyncWarning>,
+    error: Option<String>,
+    /// The worker has stopped (finished or failed).
+    finished: bool,
+}
+
+impl App {
+    /// Before grouping, every file sits in a single pending group.
+    fn new(files: &[String]) -> Self {
+        let clips = files
+            .iter()
+            .filter(|f| is_supported_file(f))
+            .map(|f| ClipView {
+                name: file_name(f),
+                path: f.clone(),
+                stage: Stage::Queued,
+                decoded_s: 0.0,
+                offset_s: None,
+                score: None,
+                drift_ppm: None,
+            })
+            .collect();
+        Self {
+            groups: vec![GroupView {
+                name: "Loading".to_string(),
+                is_reference: false,
+                clips,
+            }],
+            fraction: 0.0,
+            status: "Loading files...".to_string(),
+            warnings: Vec::new(),
+            error: None,
+            finished: false,
+        }
+    }
+
+    fn clip_mut(&mut self, path: &str) -> Option<&mut ClipView> {
+        self.groups.iter_mut().flat_map(|g| &mut g.clips).find(|c| c.path == path)
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Loading(path) => {
+                self.status = format!("Loading {}...", file_name(&path));
+                if let Some(clip) = self.clip_mut(&path) {
+                    clip.stage = Stage::Decoding;
+                }
+            }
+            Update::Meter(snapshot) => {
+                if let Some(clip) = self.clip_mut(&snapshot.file_path) {
+                    clip.decoded_s = snapshot.position_s;
+                    if snapshot.done {
+                        clip.stage = Stage::Loaded;
+                    }
+                }
+            }
+            Update::Loaded { groups, failures } => {
+                self.groups = groups;
+                self.warnings = failures;
+                self.status = "Analyzing...".to_string();
+            }
+            Update::Progress(event) => {
+                // Engine messages name the clip being worked on in quotes.
+                if event.phase == ProgressPhase::Correlating
+                    && let Some(name) = event.message.split('\'').nth(1)
+                {
+                    for clip in self.groups.iter_mut().flat_map(|g| &mut g.clips) {
+                        if clip.name == name && clip.stage == Stage::Loaded {
+                            clip.stage = Stage::Correlating;
+                        }
+                    }
+                }
+                self.fraction = event.fraction;
+                self.status = event.message;
+            }
+            Update::Analyzed { groups, warnings } => {
+                self.groups = groups;
+                self.warnings.extend(warnings);
+                self.status = "Analysis complete.".to_string();
+            }
+            Update::Exported(files) => {
+                self.status = format!("Exported {} file(s).", files.len());
+            }
+            Update::Done => self.finished = true,
+            Update::Failed(e) => {
+                self.status = format!("Failed: {}", e);
+                self.error = Some(e);
+                self.finished = true;
+            }
+        }
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+// ---------------------------------------------------------------------------
+//  Rendering
+// ---------------------------------------------------------------------------
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(app.warnings.len().min(5) as u16 + 3),
+    ])
+    .areas(frame.area());
+
+    let gauge = Gauge::default()
+        .block(Block::bordered().title(" AudioSync Pro "))
+        .gauge_style(Style::default().fg(if app.error.is_some() { Color::Red } else { Color::Cyan }))
+        .ratio(app.fraction.clamp(0.0, 1.0))
+        .label(app.status.as_str());
+    frame.render_widget(gauge, header);
+
+    let mut rows = Vec::new();
+    for group in &app.groups {
+        let tag = if group.is_reference { "[REF] " } else { "" };
+        let drift = group_drift(group);
+        rows.push(
+            Row::new(vec![
+                Line::from(format!("{}{}", tag, group.name)),
+                Line::from(""),
+                Line::from(""),
+                Line::from(""),
+                Line::from(drift),
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        for clip in &group.clips {
+            rows.push(Row::new(vec![
+                Line::from(format!("  {}", clip.name)),
+                progress_bar(clip),
+                Line::from(clip.offset_s.map(|o| format!("{:+.3} s", o)).unwrap_or_default()),
+                score_cell(clip.score),
+                Line::from(clip.drift_ppm.map(format_drift).unwrap_or_default()),
+            ]));
+        }
+    }
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(BAR_WIDTH as u16 + 2),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(14),
+        ],
+    )
+    .header(
+        Row::new(vec!["Clip", "Progress", "Offset", "Score", "Drift"])
+            .style(Style::default().add_modifier(Modifier::UNDERLINED)),
+    )
+    .block(Block::bordered().title(" Groups "));
+    frame.render_widget(table, body);
+
+    let mut lines: Vec<Line> = app
+        .warnings
+        .iter()
+        .take(5)
+        .map(|w| {
+            let color = match w.severity {
+                WarningSeverity::Error => Color::Red,
+                WarningSeverity::Warning => Color::Yellow,
+                WarningSeverity::Info => Color::Blue,
+            };
+            Line::from(Span::styled(w.to_string(), Style::default().fg(color)))
+        })
+        .collect();
+    let hint = if app.finished { "q: quit" } else { "q: cancel and quit" };
+    lines.push(Line::from(Span::styled(hint, Style::default().add_modifier(Modifier::DIM))));
+    let title = format!(" Warnings ({}) ", app.warnings.len());
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), footer);
+}
+
+fn progress_bar(clip: &ClipView) -> Line<'static> {
+    let filled = (clip.stage.fraction() * BAR_WIDTH as f64).round() as usize;
+    let color = match clip.stage {
+        Stage::Placed => Color::Green,
+        Stage::Unplaced => Color::Red,
+        _ => Color::Cyan,
+    };
+    let mut spans = vec![
+        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::styled("░".repeat(BAR_WIDTH - filled), Style::default().add_modifier(Modifier::DIM)),
+    ];
+    if clip.stage == Stage::Decoding {
+        spans.push(Span::raw(format!(" {:.0}s", clip.decoded_s)));
+    }
+    Line::from(spans)
+}
+
+fn score_cell(score: Option<f64>) -> Line<'static> {
+    let Some(score) = score else {
+        return Line::from("");
+    };
+    let color = if score >= GOOD_SCORE {
+        Color::Green
+    } else if score >= CONFIDENCE_THRESHOLD {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    Line::from(Span::styled(format!("{:.0}/100", score), Style::default().fg(color)))
+}
+
+fn format_drift(ppm: f64) -> String {
+    if ppm.abs() > 0.1 {
+        format!("{:+.1} ppm", ppm)
+    } else {
+        "none".to_string()
+    }
+}
+
+/// Drift summary for a group: the largest correction among its clips.
+fn group_drift(group: &GroupView) -> String {
+    group
+        .clips
+        .iter()
+        .filter_map(|c| c.drift_ppm)
+        .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .map(format_drift)
+        .unwrap_or_default()
+}
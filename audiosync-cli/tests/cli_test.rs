@@ -49,6 +49,21 @@ fn test_analyze_help() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("--json"));
     assert!(stdout.contains("--max-offset"));
+    assert!(stdout.contains("--fail-on"));
+    assert!(stdout.contains("--no-pre-roll"));
+    assert!(stdout.contains("--device-band"));
+    assert!(stdout.contains("--denoise"));
+}
+
+#[test]
+fn test_analyze_rejects_inverted_band() {
+    let output = audiosync_bin()
+        .args(["analyze", "--band-pass", "3500-200", "a.wav"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--band-pass"), "got: {}", stderr);
 }
 
 #[test]
@@ -62,6 +77,15 @@ fn test_sync_help() {
     assert!(stdout.contains("--format"));
     assert!(stdout.contains("--bit-depth"));
     assert!(stdout.contains("--output-dir"));
+    assert!(stdout.contains("--preview-minutes"));
+    assert!(stdout.contains("--post-cmd"));
+    assert!(stdout.contains("--embed-metadata"));
+    assert!(stdout.contains("--verify"));
+    assert!(stdout.contains("--filename-template"));
+    assert!(stdout.contains("--role"));
+    assert!(stdout.contains("--sync-map"));
+    assert!(stdout.contains("--from-sync-map"));
+    assert!(stdout.contains("--no-refine"));
 }
 
 #[test]
@@ -114,6 +138,22 @@ fn test_info_json() {
     assert!(parsed.get("groups").is_some());
 }
 
+#[test]
+fn test_info_deep_json() {
+    let output = audiosync_bin()
+        .args(["info", "--deep", "--json", "CamA_001.wav", "Zoom_001.wav"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Output should be valid JSON");
+    let files = parsed["files"].as_array().expect("deep info lists files");
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0]["file_path"], "CamA_001.wav");
+    // Nothing to probe in a missing file.
+    assert!(files[0]["metadata"]["codec"].is_null());
+}
+
 #[test]
 fn test_analyze_no_files() {
     let output = audiosync_bin()
@@ -122,3 +162,191 @@ fn test_analyze_no_files() {
         .expect("Failed to run audiosync");
     assert!(!output.status.success(), "Should fail without files");
 }
+
+#[test]
+fn test_serve_help() {
+    let output = audiosync_bin()
+        .args(["serve", "--help"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("--bind"));
+}
+
+#[test]
+fn test_tui_rejects_unsupported_files() {
+    // Must fail before taking over the terminal.
+    let output = audiosync_bin()
+        .args(["tui", "notes.txt"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No supported files"), "got: {}", stderr);
+}
+
+#[test]
+fn test_doctor_json() {
+    let output = audiosync_bin()
+        .args(["doctor", "--output-dir", "."])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(output.status.success());
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("doctor should print JSON");
+    assert!(report["ffmpeg"]["found"].is_boolean());
+    assert_eq!(report["directories"][1]["role"], "output");
+    assert_eq!(report["directories"][1]["writable"], true);
+    assert!(report["cpu"]["logical_cores"].as_u64().unwrap() >= 1);
+}
+
+#[test]
+fn test_check_reports_missing_files() {
+    let dir = std::env::temp_dir().join(format!("audiosync_check_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let project = dir.join("missing.audiosync.json");
+    std::fs::write(
+        &project,
+        r#"{
+  "version": 1, "app_version": "3.1.1", "saved_at": "2026-01-01T00:00:00Z",
+  "tracks": [{
+    "name": "Cam A",
+    "is_reference": true,
+    "clips": [{
+      "file_path": "/nonexistent/clip.wav", "name": "clip.wav",
+      "sample_rate": 8000, "original_sr": 48000, "original_channels": 1,
+      "duration_s": 1.0, "is_video": false, "creation_time": null,
+      "timeline_offset_samples": 0, "timeline_offset_s": 0.0,
+      "confidence": 0.0, "analyzed": false,
+      "drift_ppm": 0.0, "drift_confidence": 0.0, "drift_corrected": false
+    }]
+  }],
+  "config": {},
+  "result": null
+}"#,
+    )
+    .unwrap();
+
+    let output = audiosync_bin()
+        .args(["check", project.to_str().unwrap(), "--json"])
+        .output()
+        .expect("Failed to run audiosync");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("clip.wav"));
+    assert!(stdout.contains("File not found"));
+}
+
+#[test]
+fn test_show_summarizes_project_without_media() {
+    let dir = std::env::temp_dir().join(format!("audiosync_show_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let project = dir.join("received.audiosync.json");
+    std::fs::write(
+        &project,
+        r#"{
+  "version": 2, "app_version": "3.1.1", "saved_at": "2026-01-01T00:00:00Z",
+  "tracks": [{
+    "name": "Zoom",
+    "is_reference": true,
+    "clips": [{
+      "file_path": "/elsewhere/ZOOM0001.WAV", "name": "ZOOM0001.WAV",
+      "sample_rate": 8000, "original_sr": 48000, "original_channels": 2,
+      "duration_s": 90.0, "is_video": false, "creation_time": null,
+      "timeline_offset_samples": 16000, "timeline_offset_s": 2.0,
+      "confidence": 12.0, "match_score": 87.0, "analyzed": true,
+      "drift_ppm": 0.0, "drift_confidence": 0.0, "drift_corrected": false
+    }]
+  }],
+  "config": {},
+  "result": null
+}"#,
+    )
+    .unwrap();
+
+    let text = audiosync_bin()
+        .args(["show", project.to_str().unwrap()])
+        .output()
+        .expect("Failed to run audiosync");
+    let json = audiosync_bin()
+        .args(["show", project.to_str().unwrap(), "--json"])
+        .output()
+        .expect("Failed to run audiosync");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(text.status.success());
+    let stderr = String::from_utf8_lossy(&text.stderr);
+    assert!(stderr.contains("ZOOM0001.WAV"), "got: {}", stderr);
+    assert!(stderr.contains("not analyzed"), "got: {}", stderr);
+
+    assert!(json.status.success());
+    let parsed: serde_json::Value = serde_json::from_slice(&json.stdout).expect("Invalid JSON");
+    assert_eq!(parsed["tracks"][0]["clips"][0]["match_score"], 87.0);
+    assert_eq!(parsed["config"]["export_format"], "wav");
+}
+
+#[test]
+fn test_precache_reports_unreadable_files() {
+    let output = audiosync_bin()
+        .args(["precache", "/nonexistent/take1.wav", "notes.txt"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Skipping 1 unsupported"), "got: {}", stderr);
+    assert!(stderr.contains("could not be cached"), "got: {}", stderr);
+}
+
+#[test]
+fn test_analyze_rejects_malformed_offset() {
+    let output = audiosync_bin()
+        .args(["analyze", "a.wav", "--offset", "a.wav=1:xx"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--offset"), "got: {}", stderr);
+}
+
+#[test]
+fn test_null_test_rejects_malformed_offset() {
+    let output = audiosync_bin()
+        .args(["null-test", "a.wav", "b.wav", "--offset", "soon"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--offset"), "got: {}", stderr);
+}
+
+// `simulate` only exists in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+fn test_simulate_progress_and_cancel() {
+    let output = audiosync_bin()
+        .args(["simulate", "--duration", "0.1", "--steps", "4", "--json"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(output.status.success());
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line should be a progress event"))
+        .collect();
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[0]["phase"], "preparing");
+    assert_eq!(events[4]["phase"], "done");
+    assert_eq!(events[4]["fraction"], 1.0);
+
+    let output = audiosync_bin()
+        .args(["simulate", "--duration", "0.1", "--steps", "4", "--cancel-after", "2"])
+        .output()
+        .expect("Failed to run audiosync");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Simulated step 2 of 4"));
+    assert!(!stderr.contains("Simulated step 3 of 4"));
+    assert!(stderr.contains("cancelled"), "got: {}", stderr);
+}
@@ -0,0 +1,574 @@
+//! Audio-only AAF export — one region per clip, for conforming in a DAW.
+//!
+//! The stitched tracks are what most deliveries want, but an editor
+//! conforming in Pro Tools wants the takes themselves: each clip a region
+//! at the position the analysis found, still free to be slipped, trimmed
+//! or swapped. Each region is cut from a media file of its own, linked
+//! (not embedded) from the AAF.
+//!
+//! Written as AAF structured storage with 4 KiB sectors, the flavour Avid
+//! and Pro Tools read. Per region there is a file source mob describing the
+//! media and a master mob for the clip; the composition has one slot per
+//! channel of each track, and an extra lane per track wherever its clips
+//! overlap, since a slot's regions cannot.
+
+use anyhow::{Context, Result};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::timeline_export::file_url;
+
+/// A clip placed on an AAF track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AafRegion {
+    pub name: String,
+    /// WAV file holding the region's audio (and any handles).
+    pub media_path: String,
+    /// Frames in the media file.
+    pub media_len: u64,
+    pub channels: u32,
+    pub bit_depth: u32,
+    /// First frame of the media that is heard.
+    pub source_start: u64,
+    /// Where that frame lands on the timeline.
+    pub position: u64,
+    pub len: u64,
+}
+
+/// A track of regions, laid out in lanes when they overlap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AafTrack {
+    pub name: String,
+    pub channels: u32,
+    pub regions: Vec<AafRegion>,
+}
+
+/// Write `tracks` as an audio-only AAF composition named `project`, with
+/// every position and length in samples at `sample_rate`.
+pub fn export_aaf(tracks: &[AafTrack], sample_rate: u32, project: &str, output_path: &str) -> Result<()> {
+    if let Some(dir) = Path::new(output_path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let root = build(tracks, sample_rate, project);
+    let mut file = cfb::create(output_path).with_context(|| format!("Cannot create '{}'", output_path))?;
+    write_object(&mut file, Path::new("/"), &root)?;
+    let mut referenced = file.create_stream("/referenced properties")?;
+    referenced.write_all(&referenced_properties())?;
+    drop(referenced);
+    file.flush()?;
+    log::info!("AAF exported: {}", output_path);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+//  Object model
+// ---------------------------------------------------------------------------
+
+/// Stored forms of the properties written here.
+const SF_DATA: u16 = 0x82;
+const SF_STRONG_REFERENCE: u16 = 0x22;
+const SF_STRONG_REFERENCE_VECTOR: u16 = 0x32;
+const SF_STRONG_REFERENCE_SET: u16 = 0x3A;
+const SF_WEAK_REFERENCE: u16 = 0x02;
+
+/// Little-endian property streams, format version 32.
+const BYTE_ORDER_LE: u8 = 0x4C;
+const PROPERTY_FORMAT: u8 = 0x20;
+
+const ROOT: Uuid = Uuid::from_fields(0xb3b398a5, 0x1c90, 0x11d4, &[0x80, 0x53, 0x08, 0x00, 0x36, 0x21, 0x08, 0x04]);
+const META_DICTIONARY: Uuid = Uuid::from_fields(0x0d010101, 0x0225, 0x0000, &SMPTE_CLASS);
+const SEQUENCE: Uuid = class(0x0f00);
+const SOURCE_CLIP: Uuid = class(0x1100);
+const FILLER: Uuid = class(0x0900);
+const CONTENT_STORAGE: Uuid = class(0x1800);
+const DATA_DEFINITION: Uuid = class(0x1b00);
+const CONTAINER_DEFINITION: Uuid = class(0x2000);
+const DICTIONARY: Uuid = class(0x2200);
+const HEADER: Uuid = class(0x2f00);
+const IDENTIFICATION: Uuid = class(0x3000);
+const NETWORK_LOCATOR: Uuid = class(0x3200);
+const COMPOSITION_MOB: Uuid = class(0x3500);
+const MASTER_MOB: Uuid = class(0x3600);
+const SOURCE_MOB: Uuid = class(0x3700);
+const TIMELINE_MOB_SLOT: Uuid = class(0x3b00);
+const PCM_DESCRIPTOR: Uuid = class(0x4800);
+
+const SMPTE_CLASS: [u8; 8] = [0x06, 0x0e, 0x2b, 0x34, 0x02, 0x06, 0x01, 0x01];
+
+const DATA_DEF_SOUND: Uuid = Uuid::from_fields(0x01030202, 0x0200, 0x0000, &[0x06, 0x0e, 0x2b, 0x34, 0x04, 0x01, 0x01, 0x01]);
+const CONTAINER_EXTERNAL: Uuid = Uuid::from_fields(0x4313b572, 0xd8ba, 0x11d2, &[0x80, 0x9b, 0x00, 0x60, 0x08, 0x14, 0x3e, 0x6f]);
+const OP_EDIT_PROTOCOL: Uuid = Uuid::from_fields(0x0d011201, 0x0100, 0x0000, &[0x06, 0x0e, 0x2b, 0x34, 0x04, 0x01, 0x01, 0x05]);
+const PRODUCT_ID: Uuid = Uuid::from_fields(0x41535950, 0x726f, 0x0001, &[0x80, 0x00, 0x41, 0x75, 0x64, 0x53, 0x79, 0x6e]);
+
+/// Paths (from the root) of the definition sets weak references point into,
+/// in the order of the "referenced properties" table.
+const DATA_DEFINITIONS: u16 = 0;
+const CONTAINER_DEFINITIONS: u16 = 1;
+const REFERENCED_PATHS: [&[u16]; 2] = [&[0x0002, 0x3b04, 0x2605], &[0x0002, 0x3b04, 0x2608]];
+
+/// Key of every definition, which weak references name their target by.
+const PID_IDENTIFICATION: u16 = 0x1b01;
+const PID_MOB_ID: u16 = 0x4401;
+
+/// Baseline class `0d010101.0101.<id>`.
+const fn class(id: u16) -> Uuid {
+    Uuid::from_fields(0x0d010101, 0x0101, id, &SMPTE_CLASS)
+}
+
+#[derive(Debug, Clone)]
+struct Object {
+    class: Uuid,
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    pid: u16,
+    name: &'static str,
+    value: Value,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Data(Vec<u8>),
+    Strong(Box<Object>),
+    Vector(Vec<Object>),
+    /// Keyed by each element's `key_pid` property.
+    Set(u16, Vec<Object>),
+    /// A definition in the `REFERENCED_PATHS` set at the index, by its
+    /// identification.
+    Weak(u16, Uuid),
+}
+
+impl Object {
+    fn new(class: Uuid) -> Self {
+        Self { class, properties: Vec::new() }
+    }
+
+    fn with(mut self, pid: u16, name: &'static str, value: Value) -> Self {
+        self.properties.push(Property { pid, name, value });
+        self
+    }
+
+    fn data(self, pid: u16, name: &'static str, bytes: impl Into<Vec<u8>>) -> Self {
+        self.with(pid, name, Value::Data(bytes.into()))
+    }
+
+    fn key(&self, pid: u16) -> &[u8] {
+        match self.properties.iter().find(|p| p.pid == pid).map(|p| &p.value) {
+            Some(Value::Data(bytes)) => bytes,
+            _ => &[],
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Building the composition
+// ---------------------------------------------------------------------------
+
+fn build(tracks: &[AafTrack], sample_rate: u32, project: &str) -> Object {
+    let now = timestamp(&chrono::Local::now().naive_local());
+    let rate = rational(sample_rate as i32, 1);
+    let mob = |class: Uuid, id: [u8; 32], name: &str, slots: Vec<Object>| {
+        Object::new(class)
+            .data(PID_MOB_ID, "MobID", id)
+            .data(0x4402, "Name", string(name))
+            .with(0x4403, "Slots", Value::Vector(slots))
+            .data(0x4404, "LastModified", now)
+            .data(0x4405, "CreationTime", now)
+    };
+    let slot = |id: u32, name: Option<&str>, segment: Object| {
+        let slot = Object::new(TIMELINE_MOB_SLOT).data(0x4801, "SlotID", id.to_le_bytes());
+        let slot = match name {
+            Some(name) => slot.data(0x4802, "SlotName", string(name)),
+            None => slot,
+        };
+        slot.with(0x4803, "Segment", Value::Strong(Box::new(segment)))
+            .data(0x4804, "PhysicalTrackNumber", id.to_le_bytes())
+            .data(0x4b01, "EditRate", rate)
+            .data(0x4b02, "Origin", 0i64.to_le_bytes())
+    };
+
+    let mut mobs = Vec::new();
+    let mut composition_slots = Vec::new();
+    for track in tracks {
+        for (lane, regions) in lanes(&track.regions).iter().enumerate() {
+            let mut sequences = vec![Vec::new(); track.channels.max(1) as usize];
+            let mut at = 0;
+            for region in regions {
+                let file_id = mob_id();
+                let master_id = mob_id();
+                let media = Path::new(&region.media_path);
+                let absolute = std::fs::canonicalize(media).unwrap_or_else(|_| media.to_path_buf());
+                let file_slots = (1..=region.channels)
+                    .map(|c| slot(c, None, source_clip(region.media_len, &[0; 32], 0, 0)))
+                    .collect();
+                let file_name = media.file_name().unwrap_or_default().to_string_lossy();
+                mobs.push(
+                    mob(SOURCE_MOB, file_id, &file_name, file_slots)
+                        .with(0x4701, "EssenceDescription", Value::Strong(Box::new(descriptor(region, sample_rate, &absolute)))),
+                );
+                let master_slots = (1..=region.channels)
+                    .map(|c| slot(c, None, source_clip(region.media_len, &file_id, c, 0)))
+                    .collect();
+                mobs.push(mob(MASTER_MOB, master_id, &region.name, master_slots));
+
+                // Mono regions play on every channel; narrower ones leave the
+                // rest silent, as in the stitched export.
+                for (ch, sequence) in sequences.iter_mut().enumerate() {
+                    if region.position > at {
+                        sequence.push(filler(region.position - at));
+                    }
+                    let source_slot = if region.channels == 1 { 1 } else { ch as u32 + 1 };
+                    sequence.push(if source_slot <= region.channels {
+                        source_clip(region.len, &master_id, source_slot, region.source_start)
+                    } else {
+                        filler(region.len)
+                    });
+                }
+                at = region.position + region.len;
+            }
+            let channels = sequences.len();
+            for (ch, components) in sequences.into_iter().enumerate() {
+                let mut name = track.name.clone();
+                if lane > 0 {
+                    name = format!("{} {}", name, lane + 1);
+                }
+                if channels > 1 {
+                    name = format!("{}.{}", name, ch + 1);
+                }
+                let sequence = Object::new(SEQUENCE)
+                    .with(0x0201, "DataDefinition", Value::Weak(DATA_DEFINITIONS, DATA_DEF_SOUND))
+                    .data(0x0202, "Length", (at as i64).to_le_bytes())
+                    .with(0x1001, "Components", Value::Vector(components));
+                composition_slots.push(slot(composition_slots.len() as u32 + 1, Some(&name), sequence));
+            }
+        }
+    }
+    mobs.push(mob(COMPOSITION_MOB, mob_id(), project, composition_slots));
+
+    let definition = |class: Uuid, id: Uuid, name: &str| {
+        Object::new(class)
+            .data(PID_IDENTIFICATION, "Identification", auid(id))
+            .data(0x1b02, "Name", string(name))
+    };
+    let dictionary = Object::new(DICTIONARY)
+        .with(
+            0x2605,
+            "DataDefinitions",
+            Value::Set(PID_IDENTIFICATION, vec![definition(DATA_DEFINITION, DATA_DEF_SOUND, "Sound")]),
+        )
+        .with(
+            0x2608,
+            "ContainerDefinitions",
+            Value::Set(PID_IDENTIFICATION, vec![definition(CONTAINER_DEFINITION, CONTAINER_EXTERNAL, "External")]),
+        );
+    let identification = Object::new(IDENTIFICATION)
+        .data(0x3c01, "CompanyName", string("AudioSync Pro"))
+        .data(0x3c02, "ProductName", string("AudioSync Pro"))
+        .data(0x3c04, "ProductVersionString", string(env!("CARGO_PKG_VERSION")))
+        .data(0x3c05, "ProductID", auid(PRODUCT_ID))
+        .data(0x3c06, "Date", now)
+        .data(0x3c09, "GenerationAUID", auid(Uuid::new_v4()));
+    let header = Object::new(HEADER)
+        .data(0x3b01, "ByteOrder", 0x4949i16.to_le_bytes())
+        .data(0x3b02, "LastModified", now)
+        .with(
+            0x3b03,
+            "Content",
+            Value::Strong(Box::new(Object::new(CONTENT_STORAGE).with(0x1901, "Mobs", Value::Set(PID_MOB_ID, mobs)))),
+        )
+        .with(0x3b04, "Dictionary", Value::Strong(Box::new(dictionary)))
+        .data(0x3b05, "Version", [1u8, 1])
+        .with(0x3b06, "IdentificationList", Value::Vector(vec![identification]))
+        .data(0x3b07, "ObjectModelVersion", 1u32.to_le_bytes())
+        .data(0x3b09, "OperationalPattern", auid(OP_EDIT_PROTOCOL))
+        .data(0x3b0a, "EssenceContainers", auid(CONTAINER_EXTERNAL));
+
+    Object::new(ROOT)
+        .with(0x0001, "MetaDictionary", Value::Strong(Box::new(Object::new(META_DICTIONARY))))
+        .with(0x0002, "Header", Value::Strong(Box::new(header)))
+}
+
+/// Split regions into lanes, first fit by position, so none in a lane
+/// overlap. Each lane comes back in timeline order.
+fn lanes(regions: &[AafRegion]) -> Vec<Vec<&AafRegion>> {
+    let mut sorted: Vec<&AafRegion> = regions.iter().collect();
+    sorted.sort_by_key(|r| r.position);
+    let mut lanes: Vec<Vec<&AafRegion>> = Vec::new();
+    for region in sorted {
+        match lanes
+            .iter_mut()
+            .find(|lane| lane.last().is_none_or(|last| last.position + last.len <= region.position))
+        {
+            Some(lane) => lane.push(region),
+            None => lanes.push(vec![region]),
+        }
+    }
+    lanes
+}
+
+/// Linked PCM media of a file source mob.
+fn descriptor(region: &AafRegion, sample_rate: u32, absolute: &Path) -> Object {
+    let block_align = region.channels * region.bit_depth.div_ceil(8);
+    let locator = Object::new(NETWORK_LOCATOR).data(0x4001, "URLString", string(&file_url(absolute)));
+    Object::new(PCM_DESCRIPTOR)
+        .with(0x2f01, "Locator", Value::Vector(vec![locator]))
+        .data(0x3001, "SampleRate", rational(sample_rate as i32, 1))
+        .data(0x3002, "Length", (region.media_len as i64).to_le_bytes())
+        .with(0x3004, "ContainerFormat", Value::Weak(CONTAINER_DEFINITIONS, CONTAINER_EXTERNAL))
+        .data(0x3d03, "AudioSamplingRate", rational(sample_rate as i32, 1))
+        .data(0x3d07, "Channels", region.channels.to_le_bytes())
+        .data(0x3d01, "QuantizationBits", region.bit_depth.to_le_bytes())
+        .data(0x3d0a, "BlockAlign", (block_align as u16).to_le_bytes())
+        .data(0x3d09, "AverageBPS", (block_align * sample_rate).to_le_bytes())
+}
+
+fn source_clip(len: u64, source: &[u8; 32], source_slot: u32, start: u64) -> Object {
+    Object::new(SOURCE_CLIP)
+        .with(0x0201, "DataDefinition", Value::Weak(DATA_DEFINITIONS, DATA_DEF_SOUND))
+        .data(0x0202, "Length", (len as i64).to_le_bytes())
+        .data(0x1101, "SourceID", *source)
+        .data(0x1102, "SourceMobSlotID", source_slot.to_le_bytes())
+        .data(0x1201, "StartTime", (start as i64).to_le_bytes())
+}
+
+fn filler(len: u64) -> Object {
+    Object::new(FILLER)
+        .with(0x0201, "DataDefinition", Value::Weak(DATA_DEFINITIONS, DATA_DEF_SOUND))
+        .data(0x0202, "Length", (len as i64).to_le_bytes())
+}
+
+// ---------------------------------------------------------------------------
+//  Encoding
+// ---------------------------------------------------------------------------
+
+/// Store `object` in the storage at `path`: its properties in a
+/// "properties" stream, each strongly referenced object in a storage of
+/// its own beside it.
+fn write_object<F: Read + Write + Seek>(file: &mut cfb::CompoundFile<F>, path: &Path, object: &Object) -> Result<()> {
+    file.set_storage_clsid(path, object.class)?;
+    let mut entries = Vec::new();
+    let mut values = Vec::new();
+    for property in &object.properties {
+        let name = storage_name(property.name, property.pid);
+        let (form, value) = match &property.value {
+            Value::Data(bytes) => (SF_DATA, bytes.clone()),
+            Value::Strong(child) => {
+                let child_path = path.join(&name);
+                file.create_storage(&child_path)?;
+                write_object(file, &child_path, child)?;
+                (SF_STRONG_REFERENCE, string(&name))
+            }
+            Value::Vector(elements) => {
+                let mut index = header_u32s(elements.len());
+                for (key, element) in elements.iter().enumerate() {
+                    write_element(file, path, &name, key, element)?;
+                    index.extend((key as u32).to_le_bytes());
+                }
+                write_stream(file, &path.join(format!("{} index", name)), &index)?;
+                (SF_STRONG_REFERENCE_VECTOR, string(&name))
+            }
+            Value::Set(key_pid, elements) => {
+                let key_size = elements.first().map_or(0, |e| e.key(*key_pid).len());
+                let mut index = header_u32s(elements.len());
+                index.extend(key_pid.to_le_bytes());
+                index.push(key_size as u8);
+                for (key, element) in elements.iter().enumerate() {
+                    write_element(file, path, &name, key, element)?;
+                    index.extend((key as u32).to_le_bytes());
+                    index.extend(1u32.to_le_bytes());
+                    index.extend(element.key(*key_pid));
+                }
+                write_stream(file, &path.join(format!("{} index", name)), &index)?;
+                (SF_STRONG_REFERENCE_SET, string(&name))
+            }
+            Value::Weak(table, id) => {
+                let mut bytes = table.to_le_bytes().to_vec();
+                bytes.extend(PID_IDENTIFICATION.to_le_bytes());
+                bytes.push(16);
+                bytes.extend(auid(*id));
+                (SF_WEAK_REFERENCE, bytes)
+            }
+        };
+        entries.extend(property.pid.to_le_bytes());
+        entries.extend(form.to_le_bytes());
+        entries.extend((value.len() as u16).to_le_bytes());
+        values.extend(value);
+    }
+    let mut stream = vec![BYTE_ORDER_LE, PROPERTY_FORMAT];
+    stream.extend((object.properties.len() as u16).to_le_bytes());
+    stream.extend(entries);
+    stream.extend(values);
+    write_stream(file, &path.join("properties"), &stream)
+}
+
+/// Element `key` of the collection `name` (a vector or set) of the object
+/// at `path`.
+fn write_element<F: Read + Write + Seek>(
+    file: &mut cfb::CompoundFile<F>,
+    path: &Path,
+    name: &str,
+    key: usize,
+    element: &Object,
+) -> Result<()> {
+    let element_path: PathBuf = path.join(format!("{}{{{:x}}}", name, key));
+    file.create_storage(&element_path)?;
+    write_object(file, &element_path, element)
+}
+
+fn write_stream<F: Read + Write + Seek>(file: &mut cfb::CompoundFile<F>, path: &Path, bytes: &[u8]) -> Result<()> {
+    file.create_stream(path)?.write_all(bytes)?;
+    Ok(())
+}
+
+/// Collection index header: element count, next free key, last free key.
+fn header_u32s(count: usize) -> Vec<u8> {
+    [count as u32, count as u32, u32::MAX].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// The table weak references index: each path's pids, zero-terminated.
+fn referenced_properties() -> Vec<u8> {
+    let pids: usize = REFERENCED_PATHS.iter().map(|p| p.len() + 1).sum();
+    let mut bytes = vec![BYTE_ORDER_LE];
+    bytes.extend((REFERENCED_PATHS.len() as u16).to_le_bytes());
+    bytes.extend((pids as u32).to_le_bytes());
+    for path in REFERENCED_PATHS {
+        path.iter().chain(&[0]).for_each(|pid| bytes.extend(pid.to_le_bytes()));
+    }
+    bytes
+}
+
+/// Storage name of a property: its name, cut to fit, and its pid in hex.
+fn storage_name(name: &str, pid: u16) -> String {
+    let pid = format!("{:x}", pid);
+    let keep = 31 - pid.len() - 1;
+    format!("{}-{}", &name[..name.len().min(keep)], pid)
+}
+
+/// UTF-16LE, zero-terminated.
+fn string(s: &str) -> Vec<u8> {
+    s.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect()
+}
+
+fn auid(id: Uuid) -> [u8; 16] {
+    let (d1, d2, d3, d4) = id.as_fields();
+    let mut bytes = [0u8; 16];
+    bytes[..4].copy_from_slice(&d1.to_le_bytes());
+    bytes[4..6].copy_from_slice(&d2.to_le_bytes());
+    bytes[6..8].copy_from_slice(&d3.to_le_bytes());
+    bytes[8..].copy_from_slice(d4);
+    bytes
+}
+
+fn rational(numerator: i32, denominator: i32) -> [u8; 8] {
+    let mut bytes = [0u8; 8];
+    bytes[..4].copy_from_slice(&numerator.to_le_bytes());
+    bytes[4..].copy_from_slice(&denominator.to_le_bytes());
+    bytes
+}
+
+fn timestamp(t: &chrono::NaiveDateTime) -> [u8; 8] {
+    use chrono::{Datelike, Timelike};
+    let year = (t.year() as i16).to_le_bytes();
+    [
+        year[0],
+        year[1],
+        t.month() as u8,
+        t.day() as u8,
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+        (t.nanosecond() / 10_000_000).min(99) as u8,
+    ]
+}
+
+/// A fresh SMPTE UMID with a random material number.
+fn mob_id() -> [u8; 32] {
+    let mut id = [0u8; 32];
+    id[..12].copy_from_slice(&[0x06, 0x0a, 0x2b, 0x34, 0x01, 0x01, 0x01, 0x05, 0x01, 0x01, 0x0f, 0x20]);
+    id[12] = 0x13;
+    id[16..].copy_from_slice(&auid(Uuid::new_v4()));
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Properties of the object stored at `path`, by pid.
+    fn properties(file: &mut cfb::CompoundFile<std::fs::File>, path: &str) -> Vec<(u16, u16, Vec<u8>)> {
+        let mut bytes = Vec::new();
+        file.open_stream(format!("{}/properties", path)).unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes[..2], [BYTE_ORDER_LE, PROPERTY_FORMAT]);
+        let count = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+        let field = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let mut at = 4 + count * 6;
+        (0..count)
+            .map(|i| {
+                let (pid, form, len) = (field(4 + i * 6), field(6 + i * 6), field(8 + i * 6) as usize);
+                at += len;
+                (pid, form, bytes[at - len..at].to_vec())
+            })
+            .collect()
+    }
+
+    fn value(props: &[(u16, u16, Vec<u8>)], pid: u16) -> &[u8] {
+        &props.iter().find(|p| p.0 == pid).unwrap().2
+    }
+
+    #[test]
+    fn test_aaf_places_regions_in_lanes() {
+        let dir = std::env::temp_dir().join(format!("audiosync_aaf_{}", Uuid::new_v4()));
+        let region = |name: &str, position: u64, len: u64| AafRegion {
+            name: name.into(),
+            media_path: dir.join(format!("{}.wav", name)).to_string_lossy().to_string(),
+            media_len: len + 4800,
+            channels: 2,
+            bit_depth: 24,
+            source_start: 4800,
+            position,
+            len,
+        };
+        let tracks = vec![AafTrack {
+            name: "Zoom".into(),
+            channels: 2,
+            // The second take starts before the first ends.
+            regions: vec![region("A", 48_000, 96_000), region("B", 120_000, 48_000), region("C", 200_000, 10)],
+        }];
+        let path = dir.join("conform.aaf").to_string_lossy().to_string();
+        export_aaf(&tracks, 48_000, "Conform", &path).unwrap();
+
+        let mut file = cfb::open(&path).unwrap();
+        assert_eq!(*file.root_entry().clsid(), ROOT);
+        assert_eq!(*file.entry("/Header-2").unwrap().clsid(), HEADER);
+        let mobs = "/Header-2/Content-3b03/Mobs-1901";
+        // A file source mob and a master mob per region, then the composition.
+        assert_eq!(*file.entry(format!("{}{{6}}", mobs)).unwrap().clsid(), COMPOSITION_MOB);
+        assert!(!file.exists(format!("{}{{7}}", mobs)));
+        assert_eq!(*file.entry(format!("{}{{0}}/EssenceDescription-4701", mobs)).unwrap().clsid(), PCM_DESCRIPTOR);
+
+        // Two lanes of two channels.
+        let slots = format!("{}{{6}}/Slots-4403", mobs);
+        assert!(file.exists(format!("{}{{3}}", slots)) && !file.exists(format!("{}{{4}}", slots)));
+        let name = value(&properties(&mut file, &format!("{}{{2}}", slots)), 0x4802).to_vec();
+        assert_eq!(name, string("Zoom 2.1"));
+
+        // Lane one: filler up to A, A, filler, C.
+        let components = format!("{}{{0}}/Segment-4803/Components-1001", slots);
+        let classes: Vec<Uuid> =
+            (0..4).map(|i| *file.entry(format!("{}{{{}}}", components, i)).unwrap().clsid()).collect();
+        assert_eq!(classes, [FILLER, SOURCE_CLIP, FILLER, SOURCE_CLIP]);
+        let clip = properties(&mut file, &format!("{}{{1}}", components));
+        assert_eq!(value(&clip, 0x0202), 96_000i64.to_le_bytes());
+        assert_eq!(value(&clip, 0x1201), 4800i64.to_le_bytes());
+        assert_eq!(value(&clip, 0x1102), 1u32.to_le_bytes());
+        let weak = clip.iter().find(|p| p.0 == 0x0201).unwrap();
+        assert_eq!(weak.1, SF_WEAK_REFERENCE);
+        assert_eq!(weak.2[5..], auid(DATA_DEF_SOUND));
+        let gap = properties(&mut file, &format!("{}{{2}}", components));
+        assert_eq!(value(&gap, 0x0202), (200_000i64 - 144_000).to_le_bytes());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -6,15 +6,17 @@
 //! - On export: re-read original files at full resolution, one clip at a time.
 
 use anyhow::{anyhow, Context, Result};
-use log::{debug, info};
+use log::{debug, info, warn};
 use rubato::{FftFixedIn, Resampler};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-use crate::metadata::{probe_audio_info, probe_creation_time};
+use crate::metadata::{probe_audio_info, probe_creation_time, probe_stream_metadata};
 use crate::models::{
-    CancelToken, Clip, SyncConfig, Track, ANALYSIS_SR,
-    check_cancelled,
+    CancelToken, Clip, ClipTimings, ExportVerification, MeterSnapshot, PriorAnalysis, SyncConfig,
+    Track, UnavailableClip, ANALYSIS_SR, CONFIDENCE_THRESHOLD, check_cancelled,
 };
 
 // ---------------------------------------------------------------------------
@@ -22,29 +24,37 @@ use crate::models::{
 // ---------------------------------------------------------------------------
 
 pub const AUDIO_EXTENSIONS: &[&str] = &[
-    ".wav", ".aiff", ".aif", ".flac", ".mp3", ".ogg", ".opus",
+    ".wav", ".aiff", ".aif", ".flac", ".mp3", ".ogg", ".opus", ".m4a",
 ];
 
+/// Audio-only phone recordings symphonia can't decode (AMR speech codecs),
+/// extracted through ffmpeg like video.
+pub const FFMPEG_AUDIO_EXTENSIONS: &[&str] = &[".3gp", ".amr"];
+
 pub const VIDEO_EXTENSIONS: &[&str] = &[
     ".mp4", ".mov", ".mkv", ".avi", ".webm", ".mts", ".m4v", ".mxf",
 ];
 
-pub fn is_audio_file(path: &str) -> bool {
-    let ext = Path::new(path)
+fn extension(path: &str) -> String {
+    Path::new(path)
         .extension()
         .and_then(|e| e.to_str())
         .map(|e| format!(".{}", e.to_lowercase()))
-        .unwrap_or_default();
-    AUDIO_EXTENSIONS.contains(&ext.as_str())
+        .unwrap_or_default()
+}
+
+pub fn is_audio_file(path: &str) -> bool {
+    let ext = extension(path);
+    AUDIO_EXTENSIONS.contains(&ext.as_str()) || FFMPEG_AUDIO_EXTENSIONS.contains(&ext.as_str())
 }
 
 pub fn is_video_file(path: &str) -> bool {
-    let ext = Path::new(path)
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| format!(".{}", e.to_lowercase()))
-        .unwrap_or_default();
-    VIDEO_EXTENSIONS.contains(&ext.as_str())
+    VIDEO_EXTENSIONS.contains(&extension(path).as_str())
+}
+
+/// Whether decoding the file needs ffmpeg rather than symphonia.
+pub fn decodes_via_ffmpeg(path: &str) -> bool {
+    is_video_file(path) || FFMPEG_AUDIO_EXTENSIONS.contains(&extension(path).as_str())
 }
 
 pub fn is_supported_file(path: &str) -> bool {
@@ -55,7 +65,7 @@ pub fn is_supported_file(path: &str) -> bool {
 //  ffmpeg helpers
 // ---------------------------------------------------------------------------
 
-fn find_ffmpeg() -> Result<String> {
+pub(crate) fn find_ffmpeg() -> Result<String> {
     // Check common paths on macOS
     for path in &[
         "ffmpeg",
@@ -129,14 +139,19 @@ fn extract_audio_from_video(
     Ok(())
 }
 
-/// Extract full-quality audio from video for export.
+/// Extract full-quality audio from video for export: all of it, or the
+/// `(start, length)` stretch in seconds of `range`.
 fn extract_audio_full_quality(
     video_path: &str,
     output_wav: &str,
     target_sr: u32,
+    range: Option<(f64, f64)>,
     cancel: &Option<CancelToken>,
 ) -> Result<()> {
     let ffmpeg = find_ffmpeg()?;
+    let range_args: Vec<String> = range.map_or_else(Vec::new, |(start_s, len_s)| {
+        vec!["-ss".into(), format!("{:.6}", start_s), "-t".into(), format!("{:.6}", len_s)]
+    });
 
     // Try 24-bit first, fall back to 16-bit
     let sr_str = target_sr.to_string();
@@ -149,7 +164,9 @@ fn extract_audio_full_quality(
 
     let mut last_error = String::new();
     for args in &attempts {
-        let args_owned: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let mut args_owned: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        // Seeking ahead of the input decodes only the stretch wanted.
+        args_owned.splice(1..1, range_args.iter().cloned());
         let output = Command::new(&ffmpeg)
             .args(&args_owned)
             .output()
@@ -173,13 +190,20 @@ fn extract_audio_full_quality(
 // ---------------------------------------------------------------------------
 
 /// Load an audio file and return (interleaved_samples, sample_rate, channels).
-fn load_audio_symphonia(path: &str) -> Result<(Vec<f32>, u32, u32)> {
+/// With `range` (`start_s`, `len_s`), only that stretch: the decoder seeks
+/// to it and stops after it.
+fn load_audio_symphonia(
+    path: &str,
+    mut meter: Option<&mut LevelMeter>,
+    range: Option<(f64, f64)>,
+) -> Result<(Vec<f32>, u32, u32)> {
     use symphonia::core::audio::Signal;
     use symphonia::core::codecs::DecoderOptions;
-    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
     use symphonia::core::io::MediaSourceStream;
     use symphonia::core::meta::MetadataOptions;
     use symphonia::core::probe::Hint;
+    use symphonia::core::units::Time;
 
     let file = std::fs::File::open(path)
         .with_context(|| format!("Cannot open file: {}", path))?;
@@ -210,6 +234,26 @@ fn load_audio_symphonia(path: &str) -> Result<(Vec<f32>, u32, u32)> {
         .make(&codec_params, &DecoderOptions::default())
         .with_context(|| format!("Cannot create decoder for {}", path))?;
 
+    // Frames wanted, and the frame a packet starts at from its timestamp.
+    let window = range.map(|(start_s, len_s)| {
+        let first = (start_s.max(0.0) * sample_rate as f64).round() as u64;
+        (first, first + (len_s.max(0.0) * sample_rate as f64).round() as u64)
+    });
+    let frame_at = |ts: u64| match codec_params.time_base {
+        Some(tb) => {
+            let time = tb.calc_time(ts);
+            ((time.seconds as f64 + time.frac) * sample_rate as f64).round() as u64
+        }
+        None => ts,
+    };
+    if let Some((first, _)) = window.filter(|&(first, _)| first > 0) {
+        let time = Time::from(first as f64 / sample_rate as f64);
+        // Unseekable streams are decoded from the start instead.
+        if format.seek(SeekMode::Accurate, SeekTo::Time { time, track_id: Some(track_id) }).is_ok() {
+            decoder.reset();
+        }
+    }
+
     let mut all_samples: Vec<f32> = Vec::new();
 
     loop {
@@ -218,8 +262,13 @@ fn load_audio_symphonia(path: &str) -> Result<(Vec<f32>, u32, u32)> {
                 if packet.track_id() != track_id {
                     continue;
                 }
+                let packet_frame = frame_at(packet.ts());
+                if window.is_some_and(|(_, end)| packet_frame >= end) {
+                    break;
+                }
                 match decoder.decode(&packet) {
                     Ok(buf) => {
+                        let decoded_from = all_samples.len();
                         let ch = buf.spec().channels.count();
                         let frames = buf.frames();
                         match buf {
@@ -259,6 +308,18 @@ fn load_audio_symphonia(path: &str) -> Result<(Vec<f32>, u32, u32)> {
                                 debug!("Unsupported sample format, skipping packet");
                             }
                         }
+                        if let Some(meter) = meter.as_deref_mut() {
+                            meter.feed(&all_samples[decoded_from..], sample_rate, channels);
+                        }
+                        if let Some((first, end)) = window {
+                            // Keep only the packet's frames inside the window.
+                            let ch = ch.max(1);
+                            let frames = (all_samples.len() - decoded_from) / ch;
+                            let keep_to = (end.saturating_sub(packet_frame) as usize).min(frames);
+                            let keep_from = (first.saturating_sub(packet_frame) as usize).min(keep_to);
+                            all_samples.truncate(decoded_from + keep_to * ch);
+                            all_samples.drain(decoded_from..decoded_from + keep_from * ch);
+                        }
                     }
                     Err(symphonia::core::errors::Error::DecodeError(msg)) => {
                         debug!("Decode error (skipping): {}", msg);
@@ -279,6 +340,9 @@ fn load_audio_symphonia(path: &str) -> Result<(Vec<f32>, u32, u32)> {
         }
     }
 
+    if let Some(meter) = meter {
+        meter.finish();
+    }
     Ok((all_samples, sample_rate, channels))
 }
 
@@ -309,6 +373,36 @@ fn load_wav_file(path: &str) -> Result<(Vec<f32>, u32, u32)> {
     Ok((samples, sample_rate, channels))
 }
 
+/// `len_s` seconds of a WAV file from `start_s`, read without decoding the
+/// rest. Returns (interleaved_samples, sample_rate, channels).
+fn load_wav_window(path: &str, start_s: f64, len_s: f64) -> Result<(Vec<f32>, u32, u32)> {
+    let mut reader = hound::WavReader::open(path)
+        .with_context(|| format!("Cannot open WAV: {}", path))?;
+    let spec = reader.spec();
+    let start = ((start_s * spec.sample_rate as f64).round() as u32).min(reader.duration());
+    reader.seek(start)?;
+    let count = (len_s * spec.sample_rate as f64).round() as usize * spec.channels as usize;
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .take(count)
+            .filter_map(|s| s.ok())
+            .collect(),
+        hound::SampleFormat::Int => {
+            let max_val = (1u32 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .take(count)
+                .filter_map(|s| s.ok())
+                .map(|s| s as f32 / max_val)
+                .collect()
+        }
+    };
+
+    Ok((samples, spec.sample_rate, spec.channels as u32))
+}
+
 // ---------------------------------------------------------------------------
 //  Resampling
 // ---------------------------------------------------------------------------
@@ -389,6 +483,16 @@ fn to_mono(samples: &[f32], channels: u32) -> Vec<f32> {
 
 /// Load an audio or video file as a Clip with 8 kHz mono analysis samples.
 pub fn load_clip(path: &str, cancel: &Option<CancelToken>) -> Result<Clip> {
+    load_clip_metered(path, cancel, &|_| {})
+}
+
+/// Like [`load_clip`], reporting input levels through `on_meter` while the
+/// file decodes (see [`MeterSnapshot`]).
+pub fn load_clip_metered(
+    path: &str,
+    cancel: &Option<CancelToken>,
+    on_meter: &(dyn Fn(&MeterSnapshot) + Sync),
+) -> Result<Clip> {
     let path = std::fs::canonicalize(path)
         .unwrap_or_else(|_| std::path::PathBuf::from(path));
     let path_str = path.to_string_lossy().to_string();
@@ -401,15 +505,9 @@ pub fn load_clip(path: &str, cancel: &Option<CancelToken>) -> Result<Clip> {
 
     check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
 
-    let (orig_sr, orig_channels) = if is_video {
-        probe_audio_info(&path_str).unwrap_or((48000, 2))
-    } else {
-        // Try to get info from the file
-        probe_audio_info(&path_str).unwrap_or((48000, 2))
-    };
-
-    let (raw_samples, file_sr, file_ch) = if is_video {
-        // Extract audio from video via ffmpeg to a temp WAV
+    let decode_start = std::time::Instant::now();
+    let (raw_samples, file_sr, file_ch) = if decodes_via_ffmpeg(&path_str) {
+        // Extract audio from video (or AMR) via ffmpeg to a temp WAV
         let temp_dir = std::env::temp_dir();
         let temp_wav = temp_dir.join(format!("audiosync_{}.wav", uuid::Uuid::new_v4().as_hyphenated()));
         let temp_path = temp_wav.to_string_lossy().to_string();
@@ -417,14 +515,31 @@ pub fn load_clip(path: &str, cancel: &Option<CancelToken>) -> Result<Clip> {
         extract_audio_from_video(&path_str, &temp_path, ANALYSIS_SR, cancel)?;
         let result = load_wav_file(&temp_path);
         let _ = std::fs::remove_file(&temp_path);
-        result?
+        // ffmpeg did the decoding, so there is only the final level to report.
+        let (samples, sr, ch) = result?;
+        let mut meter = LevelMeter::new(&path_str, on_meter);
+        meter.feed(&samples, sr, ch);
+        meter.finish();
+        (samples, sr, ch)
     } else {
-        load_audio_symphonia(&path_str)?
+        let mut meter = LevelMeter::new(&path_str, on_meter);
+        load_audio_symphonia(&path_str, Some(&mut meter), None)?
     };
 
+    let decode_s = decode_start.elapsed().as_secs_f64();
+
     check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
 
+    // Without ffprobe, what the decoder saw; ffmpeg extracts video audio at
+    // the analysis format, so guess the usual camera audio for those.
+    let (orig_sr, orig_channels) = probe_audio_info(&path_str).unwrap_or(if decodes_via_ffmpeg(&path_str) {
+        (48000, 2)
+    } else {
+        (file_sr, file_ch)
+    });
+
     // Convert to mono
+    let resample_start = std::time::Instant::now();
     let mono = to_mono(&raw_samples, file_ch);
 
     // Resample to analysis SR if needed
@@ -433,58 +548,688 @@ pub fn load_clip(path: &str, cancel: &Option<CancelToken>) -> Result<Clip> {
     } else {
         mono
     };
+    let resample_s = resample_start.elapsed().as_secs_f64();
 
     let duration_s = analysis_samples.len() as f64 / ANALYSIS_SR as f64;
     let creation_time = probe_creation_time(&path_str);
 
     let mut clip = Clip::new(path_str, name, orig_sr, orig_channels);
+    clip.content_hash = Some(hash_samples(&analysis_samples));
     clip.samples = analysis_samples;
     clip.duration_s = duration_s;
     clip.is_video = is_video;
     clip.creation_time = creation_time;
+    clip.stream_metadata = probe_stream_metadata(&clip.file_path);
+    if clip.stream_metadata.channel_mask.is_none() && !decodes_via_ffmpeg(&clip.file_path) {
+        clip.stream_metadata.channel_mask =
+            symphonia_channel_mask(&clip.file_path).filter(|mask| mask.count_ones() == orig_channels);
+    }
+    clip.timings = ClipTimings {
+        decode_s,
+        resample_s,
+        correlation_s: 0.0,
+    };
+
+    debug!(
+        "Loaded '{}': decode {:.2}s, resample {:.2}s",
+        clip.name, decode_s, resample_s
+    );
 
     Ok(clip)
 }
 
-/// Re-read a clip's original file at full resolution, resampled to target_sr.
-/// Returns mono f64 samples. Used only during export.
+/// Load many files with at most `io_parallelism` decodes in flight, reusing
+/// `cache` entries where the file hasn't changed since it was last decoded.
+///
+/// Results come back in input order. `on_start(index, path)` fires as each
+/// file begins loading and `on_meter` with level snapshots while it decodes
+/// (both from worker threads).
+pub fn load_clips<F, M>(
+    paths: &[String],
+    io_parallelism: usize,
+    cache: &Option<AnalysisCache>,
+    cancel: &Option<CancelToken>,
+    on_start: F,
+    on_meter: M,
+) -> Vec<Result<Clip>>
+where
+    F: Fn(usize, &str) + Sync,
+    M: Fn(&MeterSnapshot) + Sync,
+{
+    map_io_bounded(paths, io_parallelism, |i, path| {
+        on_start(i, path);
+        load_clip_cached(path, cache, cancel, &on_meter)
+    })
+}
+
+/// [`load_clip_metered`] through `cache`: a hit skips decoding and
+/// resampling, a miss stores the freshly loaded clip.
+fn load_clip_cached(
+    path: &str,
+    cache: &Option<AnalysisCache>,
+    cancel: &Option<CancelToken>,
+    on_meter: &(dyn Fn(&MeterSnapshot) + Sync),
+) -> Result<Clip> {
+    let Some(cache) = cache else {
+        return load_clip_metered(path, cancel, on_meter);
+    };
+    let start = std::time::Instant::now();
+    if let Some(mut clip) = cache.get(path) {
+        // Levels of the analysis samples stand in for the source's.
+        let mut meter = LevelMeter::new(path, on_meter);
+        meter.feed(&clip.samples, ANALYSIS_SR, 1);
+        meter.finish();
+        clip.timings.decode_s = start.elapsed().as_secs_f64();
+        debug!("Loaded '{}' from the analysis cache", clip.name);
+        return Ok(clip);
+    }
+    let clip = load_clip_metered(path, cancel, on_meter)?;
+    cache.put(&clip);
+    Ok(clip)
+}
+
+/// Decode `paths` into `cache` ahead of an import — overnight on an ingest
+/// machine, say — without keeping their samples in memory. Returns, per
+/// file, whether it was cached already. Files past the cache's size limit
+/// evict earlier ones, so size the limit for the whole batch.
+pub fn precache_files<F>(
+    paths: &[String],
+    io_parallelism: usize,
+    cache: &AnalysisCache,
+    cancel: &Option<CancelToken>,
+    on_start: F,
+) -> Vec<Result<bool>>
+where
+    F: Fn(usize, &str) + Sync,
+{
+    map_io_bounded(paths, io_parallelism, |i, path| {
+        on_start(i, path);
+        if cache.contains(path) {
+            return Ok(true);
+        }
+        let clip = load_clip_metered(path, cancel, &|_| {})?;
+        cache.try_put(&clip)?;
+        Ok(false)
+    })
+}
+
+// ---------------------------------------------------------------------------
+//  Analysis cache
+// ---------------------------------------------------------------------------
+
+/// Leading bytes of a cache entry. Bump the version when the entry layout or
+/// the analysis resampling changes, so old entries stop matching.
+const CACHE_MAGIC: &[u8; 8] = b"ASYNCAC2";
+
+/// On-disk store of decoded 8 kHz analysis samples and the metadata probed
+/// on import, so importing the same media again skips decoding it.
+///
+/// Entries are keyed by canonical path, file size and modification time:
+/// replacing or editing a file misses. Past the size limit the least
+/// recently used entries are deleted.
+///
+/// Alongside, the latest placement of each analyzed clip is kept by content
+/// hash (`analyses/<hash>.json`), so media analyzed in one project can be
+/// recognized in another.
+#[derive(Debug, Clone)]
+pub struct AnalysisCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl AnalysisCache {
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+        }
+    }
+
+    /// The cache `config` describes: `None` when `analysis_cache_max_mb` is 0,
+    /// or no directory is set and the platform has no cache directory.
+    pub fn from_config(config: &SyncConfig) -> Option<Self> {
+        if config.analysis_cache_max_mb == 0 {
+            return None;
+        }
+        let dir = match &config.analysis_cache_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => dirs::cache_dir()?.join("audiosync").join("analysis"),
+        };
+        Some(Self::new(dir, config.analysis_cache_max_mb * 1024 * 1024))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Entry file for `path` as it currently is on disk.
+    fn entry_path(&self, path: &str) -> Option<PathBuf> {
+        use sha2::{Digest, Sha256};
+
+        let canonical = std::fs::canonicalize(path).ok()?;
+        let meta = std::fs::metadata(&canonical).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+        let mut hasher = Sha256::new();
+        hasher.update(canonical.to_string_lossy().as_bytes());
+        hasher.update(meta.len().to_le_bytes());
+        hasher.update(mtime.as_nanos().to_le_bytes());
+        Some(self.dir.join(format!("{}.bin", hex::encode(hasher.finalize()))))
+    }
+
+    /// Whether `path`, as it is now, has an entry.
+    pub fn contains(&self, path: &str) -> bool {
+        self.entry_path(path).is_some_and(|entry| entry.is_file())
+    }
+
+    /// The clip cached for `path` — samples and import metadata, no analysis
+    /// results — if the file is unchanged since it was stored.
+    pub fn get(&self, path: &str) -> Option<Clip> {
+        let entry = self.entry_path(path)?;
+        let bytes = std::fs::read(&entry).ok()?;
+        let Some(clip) = decode_cache_entry(&bytes) else {
+            warn!("Discarding unreadable analysis cache entry {}", entry.display());
+            let _ = std::fs::remove_file(&entry);
+            return None;
+        };
+        // Touch the entry so eviction sees it as recently used.
+        if let Ok(file) = std::fs::File::options().write(true).open(&entry) {
+            let _ = file.set_modified(std::time::SystemTime::now());
+        }
+        Some(clip)
+    }
+
+    /// Store a freshly loaded clip, then evict entries past the size limit.
+    /// Failures are only logged: a cache that can't be written costs time,
+    /// not correctness.
+    pub fn put(&self, clip: &Clip) {
+        if let Err(e) = self.try_put(clip) {
+            warn!("Could not cache analysis samples of {}: {}", clip.name, e);
+        }
+    }
+
+    fn try_put(&self, clip: &Clip) -> Result<()> {
+        let entry = self
+            .entry_path(&clip.file_path)
+            .ok_or_else(|| anyhow!("cannot read file metadata"))?;
+        std::fs::create_dir_all(&self.dir)?;
+        // Written aside and renamed so concurrent imports never read half an entry.
+        let temp = entry.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().as_simple()));
+        std::fs::write(&temp, encode_cache_entry(clip)?)?;
+        std::fs::rename(&temp, &entry).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })?;
+        self.evict()
+    }
+
+    /// Remember where analysis placed each clip of `tracks` that it placed
+    /// with confidence, replacing what earlier runs recorded for the same
+    /// content. Each sync group is its own run, being its own timeline.
+    pub fn record_analysis(&self, tracks: &[Track]) {
+        let run = uuid::Uuid::new_v4().as_simple().to_string();
+        let analyzed_at = chrono::Utc::now().to_rfc3339();
+        let dir = self.dir.join("analyses");
+        for track in tracks {
+            for (ci, clip) in track.clips.iter().enumerate() {
+                let Some(hash) = &clip.content_hash else { continue };
+                let placed = track.is_clip_locked(ci) || clip.match_score >= CONFIDENCE_THRESHOLD;
+                if !clip.analyzed || !placed {
+                    continue;
+                }
+                let record = PriorAnalysis {
+                    run_id: format!("{}/{}", run, track.sync_group_name()),
+                    offset_s: clip.timeline_offset_s,
+                    drift_ppm: clip.drift_ppm,
+                    drift_segments: clip.drift_segments.clone(),
+                    confidence: clip.confidence,
+                    match_score: clip.match_score,
+                    analyzed_at: analyzed_at.clone(),
+                };
+                let path = dir.join(format!("{}.json", hash));
+                let written = std::fs::create_dir_all(&dir)
+                    .and_then(|_| std::fs::write(&path, serde_json::to_vec(&record)?));
+                if let Err(e) = written {
+                    warn!("Could not record the analysis of {}: {}", clip.name, e);
+                }
+            }
+        }
+    }
+
+    /// The latest recorded analysis of content hashing to `content_hash`.
+    pub fn prior_analysis(&self, content_hash: &str) -> Option<PriorAnalysis> {
+        let path = self.dir.join("analyses").join(format!("{}.json", content_hash));
+        serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+    }
+
+    /// Delete least recently used entries until the cache fits its limit.
+    fn evict(&self) -> Result<()> {
+        let mut entries: Vec<(std::time::SystemTime, u64, PathBuf)> = std::fs::read_dir(&self.dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "bin"))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), e.path()))
+            })
+            .collect();
+        let mut total: u64 = entries.iter().map(|e| e.1).sum();
+        entries.sort_by_key(|e| e.0);
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                debug!("Evicted {} from the analysis cache", path.display());
+                total -= len;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Magic, little-endian u32 length of the clip's JSON, the JSON (which leaves
+/// out samples), then the samples as little-endian f32.
+fn encode_cache_entry(clip: &Clip) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(clip)?;
+    let mut bytes = Vec::with_capacity(CACHE_MAGIC.len() + 4 + json.len() + clip.samples.len() * 4);
+    bytes.extend_from_slice(CACHE_MAGIC);
+    bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&json);
+    for s in &clip.samples {
+        bytes.extend_from_slice(&s.to_le_bytes());
+    }
+    Ok(bytes)
+}
+
+fn decode_cache_entry(bytes: &[u8]) -> Option<Clip> {
+    let rest = bytes.strip_prefix(CACHE_MAGIC)?;
+    let (len, rest) = rest.split_first_chunk::<4>()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    if rest.len() < len || !(rest.len() - len).is_multiple_of(4) {
+        return None;
+    }
+    let (json, samples) = rest.split_at(len);
+    let mut clip: Clip = serde_json::from_slice(json).ok()?;
+    clip.samples = samples
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Some(clip)
+}
+
+// ---------------------------------------------------------------------------
+//  Input metering
+// ---------------------------------------------------------------------------
+
+/// Minimum wall-clock time between meter snapshots of one file.
+const METER_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Peak/RMS accumulator fed with interleaved samples as a file decodes.
+struct LevelMeter<'a> {
+    file_path: &'a str,
+    on_meter: &'a (dyn Fn(&MeterSnapshot) + Sync),
+    position_s: f64,
+    last_emit: std::time::Instant,
+    /// (peak, sum of squares, sample count) since the last snapshot.
+    window: (f32, f64, usize),
+    /// Same, over the whole file.
+    total: (f32, f64, usize),
+}
+
+impl<'a> LevelMeter<'a> {
+    fn new(file_path: &'a str, on_meter: &'a (dyn Fn(&MeterSnapshot) + Sync)) -> Self {
+        Self {
+            file_path,
+            on_meter,
+            position_s: 0.0,
+            last_emit: std::time::Instant::now(),
+            window: (0.0, 0.0, 0),
+            total: (0.0, 0.0, 0),
+        }
+    }
+
+    fn feed(&mut self, interleaved: &[f32], sample_rate: u32, channels: u32) {
+        let peak = interleaved.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+        let sum_sq: f64 = interleaved.iter().map(|&x| x as f64 * x as f64).sum();
+        for acc in [&mut self.window, &mut self.total] {
+            acc.0 = acc.0.max(peak);
+            acc.1 += sum_sq;
+            acc.2 += interleaved.len();
+        }
+        self.position_s +=
+            interleaved.len() as f64 / channels.max(1) as f64 / sample_rate.max(1) as f64;
+        if self.last_emit.elapsed() >= METER_INTERVAL {
+            self.emit(self.window, false);
+            self.window = (0.0, 0.0, 0);
+            self.last_emit = std::time::Instant::now();
+        }
+    }
+
+    fn finish(&self) {
+        self.emit(self.total, true);
+    }
+
+    fn emit(&self, (peak, sum_sq, n): (f32, f64, usize), done: bool) {
+        if n == 0 && !done {
+            return;
+        }
+        (self.on_meter)(&MeterSnapshot {
+            file_path: self.file_path.to_string(),
+            position_s: self.position_s,
+            peak,
+            rms: (sum_sq / n.max(1) as f64).sqrt() as f32,
+            done,
+        });
+    }
+}
+
+/// Speaker positions symphonia reports for a file's default track, as WAV
+/// `dwChannelMask` bits (symphonia numbers them the same way).
+fn symphonia_channel_mask(path: &str) -> Option<u32> {
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = std::fs::File::open(path).ok()?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = Path::new(path).extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .ok()?;
+    let channels = probed.format.default_track()?.codec_params.channels?;
+    Some(channels.bits())
+}
+
+/// Re-read a clip's original file at full resolution, resampled to target_sr,
+/// with the clip's trim and gain applied. Returns mono f64 samples.
 pub fn read_clip_full_res(
     clip: &Clip,
     target_sr: u32,
     cancel: &Option<CancelToken>,
 ) -> Result<Vec<f64>> {
+    let channels = read_clip_full_res_channels(clip, target_sr, cancel)?;
+    let n = channels.len() as f64;
+    Ok((0..channels[0].len()).map(|i| channels.iter().map(|c| c[i]).sum::<f64>() / n).collect())
+}
+
+/// [`read_clip_full_res`] keeping the file's channels apart: one `Vec` per
+/// channel, all the same length. Used during export.
+pub fn read_clip_full_res_channels(
+    clip: &Clip,
+    target_sr: u32,
+    cancel: &Option<CancelToken>,
+) -> Result<Vec<Vec<f64>>> {
     check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
 
-    let (raw_samples, file_sr, file_ch) = if clip.is_video {
+    let (raw_samples, file_sr, file_ch) = if decodes_via_ffmpeg(&clip.file_path) {
         let temp_dir = std::env::temp_dir();
         let temp_wav = temp_dir.join(format!("audiosync_full_{}.wav", uuid::Uuid::new_v4().as_hyphenated()));
         let temp_path = temp_wav.to_string_lossy().to_string();
 
-        extract_audio_full_quality(&clip.file_path, &temp_path, target_sr, cancel)?;
+        extract_audio_full_quality(&clip.file_path, &temp_path, target_sr, None, cancel)?;
         let result = load_wav_file(&temp_path);
         let _ = std::fs::remove_file(&temp_path);
         result?
     } else {
-        load_audio_symphonia(&clip.file_path)?
+        load_audio_symphonia(&clip.file_path, None, None)?
     };
 
     check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
 
-    // Convert to mono f64
-    let ch = file_ch as usize;
-    let frames = raw_samples.len() / ch.max(1);
-    let mut mono = Vec::with_capacity(frames);
-    for i in 0..frames {
-        let sum: f64 = (0..ch).map(|c| raw_samples[i * ch + c] as f64).sum();
-        mono.push(sum / ch as f64);
-    }
+    // Deinterleave to f64
+    let ch = (file_ch as usize).max(1);
+    let frames = raw_samples.len() / ch;
+    let mut channels: Vec<Vec<f64>> = (0..ch)
+        .map(|c| (0..frames).map(|i| raw_samples[i * ch + c] as f64).collect())
+        .collect();
 
     // Resample to target SR if needed
     if file_sr != target_sr {
-        resample_mono_f64(&mono, file_sr, target_sr)
+        channels = channels
+            .iter()
+            .map(|audio| resample_mono_f64(audio, file_sr, target_sr))
+            .collect::<Result<_>>()?;
+    }
+
+    let gain = clip.gain_factor();
+    for audio in &mut channels {
+        let head = ((clip.trim_in_s.max(0.0) * target_sr as f64).round() as usize).min(audio.len());
+        let tail = (clip.trim_out_s.max(0.0) * target_sr as f64).round() as usize;
+        audio.truncate(audio.len().saturating_sub(tail).max(head));
+        audio.drain(..head);
+        if gain != 1.0 {
+            audio.iter_mut().for_each(|s| *s *= gain);
+        }
+    }
+    Ok(channels)
+}
+
+/// Re-read `frames` frames of a clip's original file from frame `start`,
+/// counted at the clip's `original_sr`, mixed to mono and resampled to
+/// `target_sr`, without trim or gain. Only that stretch is decoded. Used to
+/// refine placements at the export rate.
+pub fn read_clip_window(
+    clip: &Clip,
+    target_sr: u32,
+    (start, frames): (usize, usize),
+    cancel: &Option<CancelToken>,
+) -> Result<Vec<f64>> {
+    let source_sr = clip.original_sr.max(1) as f64;
+    let (start_s, len_s) = (start as f64 / source_sr, frames as f64 / source_sr);
+    let (raw_samples, file_sr, file_ch) = load_clip_stretch(clip, target_sr, start_s, len_s, cancel)?;
+
+    let mono: Vec<f64> = to_mono(&raw_samples, file_ch).iter().map(|&s| s as f64).collect();
+    let mut audio = resample_mono_f64(&mono, file_sr, target_sr)?;
+    audio.resize((len_s * target_sr as f64).round() as usize, 0.0);
+    Ok(audio)
+}
+
+/// Target-rate frames read either side of a resampled export window and
+/// dropped: the resampler rings where its input starts and ends.
+const WINDOW_MARGIN: usize = 4096;
+
+/// [`read_clip_full_res_channels`] for `frames` frames from frame `start`,
+/// both at `target_sr` and counted from the trimmed head; shorter at the
+/// trimmed tail or the end of the file. Only that stretch (and a margin
+/// when resampling) is decoded. Export streams long clips this way.
+pub fn read_clip_full_res_window(
+    clip: &Clip,
+    target_sr: u32,
+    (start, frames): (usize, usize),
+    cancel: &Option<CancelToken>,
+) -> Result<Vec<Vec<f64>>> {
+    let sr = target_sr as f64;
+    let head = (clip.trim_in_s.max(0.0) * sr).round() as usize;
+    let tail = (clip.trim_out_s.max(0.0) * sr).round() as usize;
+    let kept = clip.length_at_sr(target_sr).saturating_sub(head + tail);
+    let frames = frames.min(kept.saturating_sub(start));
+    let wanted = head + start;
+
+    // Start on a frame where source and target samples coincide, so what
+    // is kept lands exactly where a whole-file read puts it.
+    let source_sr = clip.original_sr.max(1);
+    let (margin, step) = if source_sr == target_sr {
+        (0, 1)
     } else {
-        Ok(mono)
+        (WINDOW_MARGIN, (target_sr / gcd(source_sr, target_sr)) as usize)
+    };
+    let from = wanted.saturating_sub(margin) / step * step;
+    let len = wanted - from + frames + margin;
+    let (raw_samples, file_sr, file_ch) =
+        load_clip_stretch(clip, target_sr, from as f64 / sr, len as f64 / sr, cancel)?;
+
+    let ch = (file_ch as usize).max(1);
+    let gain = clip.gain_factor();
+    (0..ch)
+        .map(|c| {
+            let channel: Vec<f64> = raw_samples.iter().skip(c).step_by(ch).map(|&s| s as f64).collect();
+            let mut audio = resample_mono_f64(&channel, file_sr, target_sr)?;
+            audio.truncate(wanted - from + frames);
+            audio.drain(..(wanted - from).min(audio.len()));
+            if gain != 1.0 {
+                audio.iter_mut().for_each(|s| *s *= gain);
+            }
+            Ok(audio)
+        })
+        .collect()
+}
+
+/// `len_s` seconds of a clip's file from `start_s`, decoded on its own:
+/// extracted at `target_sr` by ffmpeg, read from just that stretch of a WAV,
+/// or seeked to by symphonia. Returns (interleaved_samples, sample_rate,
+/// channels).
+fn load_clip_stretch(
+    clip: &Clip,
+    target_sr: u32,
+    start_s: f64,
+    len_s: f64,
+    cancel: &Option<CancelToken>,
+) -> Result<(Vec<f32>, u32, u32)> {
+    check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
+
+    let stretch = if decodes_via_ffmpeg(&clip.file_path) {
+        let temp_dir = std::env::temp_dir();
+        let temp_wav = temp_dir.join(format!("audiosync_window_{}.wav", uuid::Uuid::new_v4().as_hyphenated()));
+        let temp_path = temp_wav.to_string_lossy().to_string();
+
+        extract_audio_full_quality(&clip.file_path, &temp_path, target_sr, Some((start_s, len_s)), cancel)?;
+        let result = load_wav_file(&temp_path);
+        let _ = std::fs::remove_file(&temp_path);
+        result?
+    } else {
+        // hound can't read every WAV (RF64, for one); symphonia can.
+        let window = is_wav(&clip.file_path)
+            .then(|| load_wav_window(&clip.file_path, start_s, len_s))
+            .and_then(Result::ok);
+        match window {
+            Some(window) => window,
+            None => load_audio_symphonia(&clip.file_path, None, Some((start_s, len_s)))?,
+        }
+    };
+
+    check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
+    Ok(stretch)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Re-read one channel (0-based) of a clip's original file at its own
+/// sample rate (48 kHz for video). Used for timecode decoding, which needs
+/// a channel on its own rather than the analysis downmix.
+pub fn read_clip_channel(
+    clip: &Clip,
+    channel: u32,
+    cancel: &Option<CancelToken>,
+) -> Result<(Vec<f32>, u32)> {
+    check_cancelled(cancel).map_err(|e| anyhow!(e.to_string()))?;
+
+    let (raw_samples, file_sr, file_ch) = if decodes_via_ffmpeg(&clip.file_path) {
+        let temp_dir = std::env::temp_dir();
+        let temp_wav = temp_dir.join(format!("audiosync_channel_{}.wav", uuid::Uuid::new_v4().as_hyphenated()));
+        let temp_path = temp_wav.to_string_lossy().to_string();
+
+        extract_audio_full_quality(&clip.file_path, &temp_path, 48000, None, cancel)?;
+        let result = load_wav_file(&temp_path);
+        let _ = std::fs::remove_file(&temp_path);
+        result?
+    } else {
+        load_audio_symphonia(&clip.file_path, None, None)?
+    };
+
+    if channel >= file_ch {
+        return Err(anyhow!(
+            "'{}' has {} channel(s), no channel {}",
+            clip.name,
+            file_ch,
+            channel + 1
+        ));
     }
+    let samples = raw_samples
+        .iter()
+        .skip(channel as usize)
+        .step_by(file_ch as usize)
+        .copied()
+        .collect();
+    Ok((samples, file_sr))
+}
+
+// ---------------------------------------------------------------------------
+//  Pre-flight
+// ---------------------------------------------------------------------------
+
+/// Check that every clip's source file still exists and can be opened.
+///
+/// Run before export so ejected cards or unmounted shares are reported up
+/// front rather than discovered halfway through stitching.
+pub fn preflight_check(tracks: &[Track]) -> Vec<UnavailableClip> {
+    let mut missing = Vec::new();
+    for track in tracks {
+        for clip in &track.clips {
+            let reason = match std::fs::metadata(&clip.file_path) {
+                Err(_) => Some("File not found".to_string()),
+                Ok(meta) if !meta.is_file() => Some("Not a regular file".to_string()),
+                Ok(_) => std::fs::File::open(&clip.file_path)
+                    .err()
+                    .map(|e| format!("Cannot open: {}", e)),
+            };
+            if let Some(reason) = reason {
+                missing.push(UnavailableClip {
+                    track_name: track.name.clone(),
+                    clip_name: clip.name.clone(),
+                    file_path: clip.file_path.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+    missing
+}
+
+// ---------------------------------------------------------------------------
+//  IO scheduling
+// ---------------------------------------------------------------------------
+
+/// Run `f` over `items` with at most `limit` calls in flight, preserving order.
+///
+/// File reads and ffmpeg extraction are I/O-bound; on network mounts too many
+/// concurrent readers thrash, so this is capped separately from CPU work.
+pub fn map_io_bounded<T, R, F>(items: &[T], limit: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(usize, &T) -> R + Sync,
+{
+    let workers = limit.max(1).min(items.len());
+    if workers <= 1 {
+        return items.iter().enumerate().map(|(i, t)| f(i, t)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= items.len() {
+                    break;
+                }
+                let r = f(i, &items[i]);
+                slots.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(r);
+            });
+        }
+    });
+
+    slots
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|r| r.expect("IO worker finished every item"))
+        .collect()
 }
 
 // ---------------------------------------------------------------------------
@@ -493,106 +1238,426 @@ pub fn read_clip_full_res(
 
 /// Export a track's synced audio to disk as WAV.
 pub fn export_track(track: &Track, output_path: &str, config: &SyncConfig) -> Result<String> {
+    export_track_tagged(track, output_path, config, None)
+}
+
+/// Descriptive tags embedded into exported files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportTags {
+    pub project: String,
+    pub track: String,
+    /// Timeline position of the track's first clip, in seconds.
+    pub sync_offset_s: f64,
+    pub software: String,
+}
+
+impl ExportTags {
+    pub fn for_track(track: &Track, project: &str) -> Self {
+        let sync_offset_s = track
+            .clips
+            .iter()
+            .map(|c| c.timeline_offset_s)
+            .reduce(f64::min)
+            .unwrap_or(0.0);
+        Self {
+            project: project.to_string(),
+            track: track.name.clone(),
+            sync_offset_s,
+            software: format!("AudioSync Pro {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    fn comment(&self) -> String {
+        format!("Sync offset {:+.3} s", self.sync_offset_s)
+    }
+
+    /// `-metadata` arguments for ffmpeg.
+    fn ffmpeg_args(&self) -> Vec<String> {
+        [
+            ("title", self.track.clone()),
+            ("artist", self.track.clone()),
+            ("album", self.project.clone()),
+            ("comment", self.comment()),
+            ("encoded_by", self.software.clone()),
+        ]
+        .into_iter()
+        .flat_map(|(key, value)| ["-metadata".to_string(), format!("{}={}", key, value)])
+        .collect()
+    }
+
+    /// RIFF `LIST/INFO` chunk for WAV files.
+    fn riff_info_chunk(&self) -> Vec<u8> {
+        let mut info = b"INFO".to_vec();
+        for (id, value) in [
+            (b"INAM", self.track.as_str()),
+            (b"IPRD", self.project.as_str()),
+            (b"ICMT", self.comment().as_str()),
+            (b"ISFT", self.software.as_str()),
+        ] {
+            let mut data = value.as_bytes().to_vec();
+            data.push(0);
+            info.extend_from_slice(id);
+            info.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            info.extend_from_slice(&data);
+            if data.len() % 2 == 1 {
+                info.push(0);
+            }
+        }
+        let mut chunk = b"LIST".to_vec();
+        chunk.extend_from_slice(&(info.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(&info);
+        chunk
+    }
+}
+
+/// Export a track, embedding `tags` when given (WAV `LIST/INFO` chunk or
+/// ffmpeg `-metadata` for FLAC/MP3/AIFF).
+pub fn export_track_tagged(
+    track: &Track,
+    output_path: &str,
+    config: &SyncConfig,
+    tags: Option<&ExportTags>,
+) -> Result<String> {
     let audio = track
         .synced_audio
         .as_ref()
         .ok_or_else(|| anyhow!("Track '{}' has no synced audio — run sync first", track.name))?;
 
-    let output_path = std::fs::canonicalize(Path::new(output_path).parent().unwrap_or(Path::new(".")))
-        .unwrap_or_default()
-        .join(Path::new(output_path).file_name().unwrap_or_default());
-    let output_str = output_path.to_string_lossy().to_string();
+    let sample_rate = config.export_sr.unwrap_or(48000);
+    let mut writer = TrackWriter::create(
+        output_path,
+        config,
+        sample_rate,
+        track.synced_channels,
+        track.synced_channel_mask,
+    )?;
+    writer.write(audio)?;
+    Ok(writer.finish(tags)?.0)
+}
 
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)?;
+/// Length and level of audio as it is written, to check the file against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioTally {
+    /// Samples per channel.
+    pub samples: usize,
+    pub channels: u32,
+    sum_sq: f64,
+}
+
+impl AudioTally {
+    pub fn new(channels: u32) -> Self {
+        Self { samples: 0, channels: channels.max(1), sum_sq: 0.0 }
     }
 
-    let sample_rate = config.export_sr.unwrap_or(48000);
+    /// Tally interleaved `audio` of `channels` channels.
+    pub fn of(audio: &[f64], channels: u32) -> Self {
+        let mut tally = Self::new(channels);
+        tally.add(audio);
+        tally
+    }
 
-    if config.is_lossy() {
-        export_track_via_ffmpeg(audio, &output_str, sample_rate, config)?;
-    } else {
-        export_track_wav(audio, &output_str, sample_rate, config)?;
+    pub fn add(&mut self, block: &[f64]) {
+        self.samples += block.len() / self.channels as usize;
+        self.sum_sq += block.iter().map(|x| x * x).sum::<f64>();
     }
 
-    Ok(output_str)
+    /// RMS over all channels.
+    pub fn rms(&self) -> f64 {
+        let n = self.samples * self.channels as usize;
+        if n == 0 { 0.0 } else { (self.sum_sq / n as f64).sqrt() }
+    }
 }
 
-fn export_track_wav(
-    audio: &[f64],
-    output_path: &str,
-    sample_rate: u32,
-    config: &SyncConfig,
-) -> Result<()> {
-    let (bits, sample_format) = match config.export_bit_depth {
-        16 => (16, hound::SampleFormat::Int),
-        32 => (32, hound::SampleFormat::Float),
-        _ => (24, hound::SampleFormat::Int),
-    };
+/// Writes one exported track a block at a time, so a track never has to be
+/// held in memory whole. Blocks are interleaved frames of the track's
+/// channels. WAV is written directly; other formats go through a temporary
+/// 24-bit WAV that ffmpeg converts on [`TrackWriter::finish`], carrying the
+/// speaker layout across.
+pub struct TrackWriter {
+    output_path: String,
+    wav_path: String,
+    wav: hound::WavWriter<std::io::BufWriter<std::fs::File>>,
+    bit_depth: u16,
+    channel_mask: Option<u32>,
+    config: SyncConfig,
+    tally: AudioTally,
+}
 
-    let spec = hound::WavSpec {
-        channels: 1,
-        sample_rate,
-        bits_per_sample: bits,
-        sample_format,
-    };
+impl TrackWriter {
+    /// `channel_mask` gives the speaker positions (WAV `dwChannelMask` bits)
+    /// when known; otherwise the first channels are assumed.
+    pub fn create(
+        output_path: &str,
+        config: &SyncConfig,
+        sample_rate: u32,
+        channels: u32,
+        channel_mask: Option<u32>,
+    ) -> Result<Self> {
+        let output_path = std::fs::canonicalize(Path::new(output_path).parent().unwrap_or(Path::new(".")))
+            .unwrap_or_default()
+            .join(Path::new(output_path).file_name().unwrap_or_default());
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output_path = output_path.to_string_lossy().to_string();
 
-    let mut writer = hound::WavWriter::create(output_path, spec)?;
+        let (wav_path, bit_depth) = if config.is_lossy() {
+            let temp_wav = std::env::temp_dir()
+                .join(format!("audiosync_export_{}.wav", uuid::Uuid::new_v4().as_hyphenated()));
+            (temp_wav.to_string_lossy().to_string(), 24)
+        } else {
+            (output_path.clone(), config.export_bit_depth)
+        };
+        let (bits, sample_format) = match bit_depth {
+            16 => (16, hound::SampleFormat::Int),
+            32 => (32, hound::SampleFormat::Float),
+            _ => (24, hound::SampleFormat::Int),
+        };
+        let spec = hound::WavSpec {
+            channels: channels.max(1) as u16,
+            sample_rate,
+            bits_per_sample: bits,
+            sample_format,
+        };
+        let wav = hound::WavWriter::create(&wav_path, spec)?;
+        Ok(Self {
+            output_path,
+            wav_path,
+            wav,
+            bit_depth: bits,
+            channel_mask,
+            config: config.clone(),
+            tally: AudioTally::new(channels),
+        })
+    }
 
-    match config.export_bit_depth {
-        16 => {
-            let max = i16::MAX as f64;
-            for &s in audio {
-                let clamped = s.clamp(-1.0, 1.0);
-                writer.write_sample((clamped * max) as i16)?;
+    pub fn write(&mut self, block: &[f64]) -> Result<()> {
+        self.tally.add(block);
+        match self.bit_depth {
+            16 => {
+                let max = i16::MAX as f64;
+                for &s in block {
+                    self.wav.write_sample((s.clamp(-1.0, 1.0) * max) as i16)?;
+                }
             }
+            32 => {
+                for &s in block {
+                    self.wav.write_sample(s.clamp(-1.0, 1.0) as f32)?;
+                }
+            }
+            _ => {
+                // 24-bit: write as i32 with 24-bit range
+                let max = (1i32 << 23) as f64 - 1.0;
+                for &s in block {
+                    self.wav.write_sample((s.clamp(-1.0, 1.0) * max) as i32)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the file, embedding `tags` when given. Returns the path written
+    /// and what went into it.
+    pub fn finish(self, tags: Option<&ExportTags>) -> Result<(String, AudioTally)> {
+        self.wav.finalize()?;
+        if let Some(mask) = self.channel_mask {
+            set_wav_channel_mask(&self.wav_path, mask)?;
         }
-        32 => {
-            for &s in audio {
-                writer.write_sample(s.clamp(-1.0, 1.0) as f32)?;
+        if self.wav_path == self.output_path {
+            if let Some(tags) = tags {
+                append_riff_info(&self.output_path, tags)?;
             }
+            info!("Exported WAV: {}", self.output_path);
+        } else {
+            let converted = convert_with_ffmpeg(&self.wav_path, &self.output_path, &self.config, tags);
+            let _ = std::fs::remove_file(&self.wav_path);
+            converted?;
+        }
+        Ok((self.output_path, self.tally))
+    }
+}
+
+/// Where exported track files go and what they are called.
+///
+/// Files land flat in `output_dir` as `{track}_{sr}`, unless the config
+/// sets a folder template such as `{project}/{date}/{format}` or a filename
+/// template such as `{role}_{track}`. Template tokens: `{project}`, `{date}`
+/// (YYYY-MM-DD), `{format}`, `{sr}`, `{track}`, `{role}` and `{group}` (the
+/// track's sync group). With a folder template, existing files are never
+/// overwritten — a `_2`, `_3`… suffix is added instead.
+#[derive(Debug, Clone)]
+pub struct ExportLayout {
+    pub output_dir: PathBuf,
+    pub folder_template: Option<String>,
+    pub filename_template: Option<String>,
+    pub project: String,
+    pub date: String,
+    /// The project has several sync groups: without a folder template, each
+    /// group's files go in a `{group}` subfolder so timelines stay apart.
+    pub grouped: bool,
+}
+
+impl ExportLayout {
+    pub fn new(output_dir: &str, config: &SyncConfig, project: &str) -> Self {
+        let template = |t: &Option<String>| {
+            t.as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            output_dir: PathBuf::from(output_dir),
+            folder_template: template(&config.export_folder_template),
+            filename_template: template(&config.export_filename_template),
+            project: project.to_string(),
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            grouped: false,
         }
-        _ => {
-            // 24-bit: write as i32 with 24-bit range
-            let max = (1i32 << 23) as f64 - 1.0;
-            for &s in audio {
-                let clamped = s.clamp(-1.0, 1.0);
-                writer.write_sample((clamped * max) as i32)?;
+    }
+
+    /// Output path for one track, creating its folder.
+    pub fn track_path(
+        &self,
+        track: &Track,
+        format: &str,
+        sample_rate: u32,
+        suffix: &str,
+    ) -> Result<PathBuf> {
+        let expand = |template: &str| {
+            template
+                .replace("{project}", &sanitize_path_part(&self.project))
+                .replace("{date}", &self.date)
+                .replace("{format}", &sanitize_path_part(format))
+                .replace("{sr}", &sample_rate.to_string())
+                .replace("{track}", &sanitize_path_part(&track.name))
+                .replace("{role}", track.role().as_str())
+                .replace("{group}", &sanitize_path_part(track.sync_group_name()))
+        };
+
+        let stem = match self.filename_template {
+            Some(ref template) => sanitize_path_part(&expand(template)),
+            None => format!("{}_{}", sanitize_path_part(&track.name), sample_rate),
+        };
+        let filename = format!("{}{}.{}", stem, suffix, format);
+
+        let template = match self.folder_template {
+            Some(ref template) => template.as_str(),
+            None if self.grouped => "{group}",
+            None => {
+                std::fs::create_dir_all(&self.output_dir)?;
+                return Ok(self.output_dir.join(filename));
+            }
+        };
+
+        let mut dir = self.output_dir.clone();
+        for part in expand(template).split(['/', '\\']) {
+            // Templates may only descend into output_dir.
+            if part.is_empty() || part == "." || part == ".." {
+                continue;
             }
+            dir.push(sanitize_path_part(part));
         }
+        std::fs::create_dir_all(&dir)?;
+        Ok(unique_path(dir.join(filename)))
+    }
+}
+
+pub(crate) fn sanitize_path_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `path`, or the first free `stem_N.ext` next to it.
+fn unique_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
     }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string());
+    (2..)
+        .map(|n| {
+            let name = match ext {
+                Some(ref ext) => format!("{}_{}.{}", stem, n, ext),
+                None => format!("{}_{}", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|p| !p.exists())
+        .expect("unbounded search finds a free name")
+}
+
+/// Append a `LIST/INFO` chunk after the audio data and fix up the RIFF size.
+fn append_riff_info(path: &str, tags: &ExportTags) -> Result<()> {
+    use std::io::{Seek, SeekFrom, Write};
 
-    writer.finalize()?;
-    info!("Exported WAV: {}", output_path);
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let mut len = file.seek(SeekFrom::End(0))?;
+    // RIFF chunks are word-aligned
+    if len % 2 == 1 {
+        file.write_all(&[0])?;
+        len += 1;
+    }
+    let chunk = tags.riff_info_chunk();
+    file.write_all(&chunk)?;
+    let riff_size = u32::try_from(len + chunk.len() as u64 - 8)
+        .map_err(|_| anyhow!("WAV too large for a RIFF INFO chunk"))?;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&riff_size.to_le_bytes())?;
     Ok(())
 }
 
-fn export_track_via_ffmpeg(
-    audio: &[f64],
+/// Overwrite the speaker mask hound wrote (always the first N positions)
+/// in a `WAVE_FORMAT_EXTENSIBLE` header. Plain PCM headers (16-bit mono or
+/// stereo) carry no mask and are left alone.
+fn set_wav_channel_mask(path: &str, mask: u32) -> Result<()> {
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(anyhow!("'{}' is not a WAV file", path));
+    }
+    loop {
+        let mut chunk = [0u8; 8];
+        file.read_exact(&mut chunk)?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as i64;
+        if &chunk[0..4] != b"fmt " {
+            file.seek(SeekFrom::Current(size + size % 2))?;
+            continue;
+        }
+        let mut format = [0u8; 2];
+        file.read_exact(&mut format)?;
+        if size >= 40 && u16::from_le_bytes(format) == 0xFFFE {
+            // dwChannelMask sits 20 bytes into the chunk.
+            file.seek(SeekFrom::Current(18))?;
+            file.write_all(&mask.to_le_bytes())?;
+        }
+        return Ok(());
+    }
+}
+
+/// Convert the WAV at `wav_path` to the export format with ffmpeg.
+fn convert_with_ffmpeg(
+    wav_path: &str,
     output_path: &str,
-    sample_rate: u32,
     config: &SyncConfig,
+    tags: Option<&ExportTags>,
 ) -> Result<()> {
     let ffmpeg = find_ffmpeg()?;
 
-    // Write temp WAV
-    let temp_dir = std::env::temp_dir();
-    let temp_wav = temp_dir.join(format!("audiosync_export_{}.wav", uuid::Uuid::new_v4().as_hyphenated()));
-    let temp_path = temp_wav.to_string_lossy().to_string();
-
-    let temp_config = SyncConfig {
-        export_bit_depth: 24,
-        export_format: "wav".to_string(),
-        ..config.clone()
-    };
-    export_track_wav(audio, &temp_path, sample_rate, &temp_config)?;
-
-    // Convert with ffmpeg
     let format = config.export_format.to_lowercase();
     let mut args = vec![
         "-y".to_string(),
         "-i".to_string(),
-        temp_path.clone(),
+        wav_path.to_string(),
     ];
 
     match format.as_str() {
@@ -621,6 +1686,9 @@ fn export_track_via_ffmpeg(
         _ => {}
     }
 
+    if let Some(tags) = tags {
+        args.extend(tags.ffmpeg_args());
+    }
     args.push(output_path.to_string());
 
     let output = Command::new(&ffmpeg)
@@ -628,8 +1696,6 @@ fn export_track_via_ffmpeg(
         .output()
         .context("Failed to run ffmpeg for export")?;
 
-    let _ = std::fs::remove_file(&temp_path);
-
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(anyhow!("ffmpeg export failed:\n{}", &stderr[stderr.len().saturating_sub(500)..]));
@@ -639,6 +1705,124 @@ fn export_track_via_ffmpeg(
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+//  Export verification
+// ---------------------------------------------------------------------------
+
+/// Allowed length difference: encoder padding for lossy formats.
+const VERIFY_LENGTH_TOLERANCE_S: f64 = 0.05;
+
+/// Allowed RMS difference in dB (lossy codecs shift the level slightly).
+const VERIFY_RMS_TOLERANCE_DB: f64 = 1.0;
+
+/// RMS below which both buffers count as silence.
+const VERIFY_SILENCE_RMS: f64 = 1e-5;
+
+/// Decode an exported file and compare its length and RMS with the
+/// `expected` audio it was written from at `sample_rate`.
+pub fn verify_export(path: &str, expected: &AudioTally, sample_rate: u32) -> ExportVerification {
+    let expected_rms = expected.rms();
+    let mut report = ExportVerification {
+        file_path: path.to_string(),
+        ok: false,
+        expected_samples: expected.samples,
+        decoded_samples: 0,
+        expected_rms,
+        decoded_rms: 0.0,
+        message: String::new(),
+    };
+
+    let decoded = if is_wav(path) {
+        load_wav_file(path)
+    } else {
+        load_audio_symphonia(path, None, None)
+    };
+    let (samples, sr, channels) = match decoded {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            report.message = format!("Cannot decode: {}", e);
+            return report;
+        }
+    };
+
+    report.decoded_samples = samples.len() / channels.max(1) as usize;
+    report.decoded_rms = rms_f64(samples.iter().map(|&x| x as f64));
+
+    let tolerance = (VERIFY_LENGTH_TOLERANCE_S * sample_rate as f64) as usize;
+    report.message = if sr != sample_rate {
+        format!("Sample rate {} Hz, expected {} Hz", sr, sample_rate)
+    } else if channels != expected.channels {
+        format!("{} channel(s), expected {}", channels, expected.channels)
+    } else if report.decoded_samples.abs_diff(report.expected_samples) > tolerance {
+        format!(
+            "Length {} samples, expected {}",
+            report.decoded_samples, report.expected_samples
+        )
+    } else if !rms_matches(report.decoded_rms, expected_rms) {
+        format!(
+            "Level {:.1} dBFS RMS, expected {:.1} dBFS",
+            20.0 * report.decoded_rms.max(1e-12).log10(),
+            20.0 * expected_rms.max(1e-12).log10()
+        )
+    } else {
+        String::new()
+    };
+    report.ok = report.message.is_empty();
+    if !report.ok {
+        warn!("Export verification failed for '{}': {}", path, report.message);
+    }
+    report
+}
+
+fn is_wav(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("wav"))
+}
+
+fn rms_f64(samples: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = samples.fold((0.0, 0usize), |(sum, n), x| (sum + x * x, n + 1));
+    if n == 0 { 0.0 } else { (sum / n as f64).sqrt() }
+}
+
+fn rms_matches(decoded: f64, expected: f64) -> bool {
+    if decoded < VERIFY_SILENCE_RMS || expected < VERIFY_SILENCE_RMS {
+        return decoded < VERIFY_SILENCE_RMS && expected < VERIFY_SILENCE_RMS;
+    }
+    (20.0 * (decoded / expected).log10()).abs() <= VERIFY_RMS_TOLERANCE_DB
+}
+
+/// SHA-256 of analysis samples (as little-endian f32), hex-encoded.
+pub fn hash_samples(samples: &[f32]) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    for chunk in samples.chunks(1 << 16) {
+        let bytes: Vec<u8> = chunk.iter().flat_map(|s| s.to_le_bytes()).collect();
+        hasher.update(&bytes);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// SHA-256 of a file's full contents, hex-encoded.
+pub fn hash_file(path: &str) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Cannot open file for hashing: {}", path))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
 /// Detect the highest original sample rate across all clips.
 pub fn detect_project_sample_rate(tracks: &[Track]) -> u32 {
     let mut max_sr = 44100u32;
@@ -656,12 +1840,272 @@ pub fn detect_project_sample_rate(tracks: &[Track]) -> u32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_preflight_reports_missing_files() {
+        let present = std::env::temp_dir().join(format!("audiosync_pf_{}.wav", uuid::Uuid::new_v4()));
+        std::fs::write(&present, b"x").unwrap();
+        let mut track = Track::new("Cam".into());
+        track.clips.push(Clip::new(present.to_string_lossy().to_string(), "a.wav".into(), 48000, 1));
+        track.clips.push(Clip::new("/nonexistent/b.wav".into(), "b.wav".into(), 48000, 1));
+
+        let missing = preflight_check(&[track]);
+        let _ = std::fs::remove_file(&present);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].clip_name, "b.wav");
+        assert_eq!(missing[0].track_name, "Cam");
+    }
+
+    #[test]
+    fn test_export_layout_template_and_collisions() {
+        let root = std::env::temp_dir().join(format!("audiosync_layout_{}", uuid::Uuid::new_v4()));
+        let mut config = SyncConfig {
+            export_folder_template: Some("{project}/../{date}/{format}".into()),
+            ..Default::default()
+        };
+        let layout = ExportLayout::new(root.to_str().unwrap(), &config, "Wedding Day");
+        let track = Track::new("Cam A".into());
+
+        let first = layout.track_path(&track, "wav", 48000, "").unwrap();
+        let expected_dir = root.join("Wedding_Day").join(&layout.date).join("wav");
+        assert_eq!(first, expected_dir.join("Cam_A_48000.wav"));
+        assert!(expected_dir.is_dir());
+
+        std::fs::write(&first, b"taken").unwrap();
+        let second = layout.track_path(&track, "wav", 48000, "").unwrap();
+
+        config.export_folder_template = None;
+        config.export_filename_template = Some("{role}_{track}".into());
+        let named = ExportLayout::new(root.to_str().unwrap(), &config, "p")
+            .track_path(&Track::new("Zoom H6".into()), "wav", 48000, "_preview")
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+        assert_eq!(second, expected_dir.join("Cam_A_48000_2.wav"));
+        assert_eq!(named, root.join("DIALOG_Zoom_H6_preview.wav"));
+    }
+
+    #[test]
+    fn test_wav_export_embeds_info_tags() {
+        let path = std::env::temp_dir().join(format!("audiosync_tags_{}.wav", uuid::Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+        let mut track = Track::new("Zoom H6".into());
+        track.synced_audio = Some(vec![0.25; 801]);
+        let mut clip = Clip::new("/z.wav".into(), "z.wav".into(), 48000, 1);
+        clip.timeline_offset_s = 12.5;
+        track.clips.push(clip);
+        let config = SyncConfig {
+            export_sr: Some(8000),
+            ..Default::default()
+        };
+
+        let tags = ExportTags::for_track(&track, "Interview");
+        export_track_tagged(&track, &path_str, &config, Some(&tags)).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let reader = hound::WavReader::open(&path).map(|r| r.len());
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(reader.unwrap(), 801);
+        let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_size, bytes.len() - 8);
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.contains("LIST"));
+        assert!(text.contains("INAM"));
+        assert!(text.contains("Zoom H6\0"));
+        assert!(text.contains("Sync offset +12.500 s"));
+    }
+
+    #[test]
+    fn test_wav_export_writes_channel_mask() {
+        let path = std::env::temp_dir().join(format!("audiosync_mask_{}.wav", uuid::Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+        let mut track = Track::new("Ambience".into());
+        track.synced_audio = Some(vec![0.1; 6 * 400]);
+        track.synced_channels = 6;
+        track.synced_channel_mask = Some(0x60F);
+        let config = SyncConfig {
+            export_sr: Some(8000),
+            ..Default::default()
+        };
+
+        export_track(&track, &path_str, &config).unwrap();
+        let spec = hound::WavReader::open(&path).map(|r| r.spec());
+        let mask = symphonia_channel_mask(&path_str);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(spec.unwrap().channels, 6);
+        assert_eq!(mask, Some(0x60F));
+    }
+
+    #[test]
+    fn test_verify_export_catches_truncated_files() {
+        let path = std::env::temp_dir().join(format!("audiosync_verify_{}.wav", uuid::Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+        let mut track = Track::new("Rec".into());
+        let audio: Vec<f64> = (0..16000).map(|i| 0.5 * (i as f64 * 0.05).sin()).collect();
+        track.synced_audio = Some(audio.clone());
+        let config = SyncConfig {
+            export_sr: Some(8000),
+            ..Default::default()
+        };
+        export_track(&track, &path_str, &config).unwrap();
+
+        let written = AudioTally::of(&audio, 1);
+        let good = verify_export(&path_str, &written, 8000);
+        let longer: Vec<f64> = audio.iter().chain(audio.iter()).copied().collect();
+        let short = verify_export(&path_str, &AudioTally::of(&longer, 1), 8000);
+        let wrong_sr = verify_export(&path_str, &written, 48000);
+        let stereo = verify_export(&path_str, &AudioTally::of(&longer, 2), 8000);
+        let quiet: Vec<f64> = audio.iter().map(|x| x * 0.1).collect();
+        let level = verify_export(&path_str, &AudioTally::of(&quiet, 1), 8000);
+        let _ = std::fs::remove_file(&path);
+        let missing = verify_export(&path_str, &written, 8000);
+
+        assert!(good.ok, "{}", good.message);
+        assert_eq!(good.decoded_samples, 16000);
+        assert!(!short.ok && short.message.contains("Length"));
+        assert!(!wrong_sr.ok && wrong_sr.message.contains("Sample rate"));
+        assert!(!stereo.ok && stereo.message.contains("channel"));
+        assert!(!level.ok && level.message.contains("Level"));
+        assert!(!missing.ok && missing.message.contains("Cannot decode"));
+    }
+
+    #[test]
+    fn test_load_clip_metered_reports_levels() {
+        let path = std::env::temp_dir().join(format!("audiosync_meter_{}.wav", uuid::Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+        let mut track = Track::new("Rec".into());
+        track.synced_audio = Some((0..16000).map(|i| 0.5 * (i as f64 * 0.05).sin()).collect());
+        let config = SyncConfig {
+            export_sr: Some(8000),
+            export_bit_depth: 16,
+            ..Default::default()
+        };
+        export_track(&track, &path_str, &config).unwrap();
+
+        let seen = Mutex::new(Vec::new());
+        let clip = load_clip_metered(&path_str, &None, &|m| seen.lock().unwrap().push(m.clone()));
+        let _ = std::fs::remove_file(&path);
+        clip.unwrap();
+
+        let seen = seen.into_inner().unwrap();
+        let last = seen.last().unwrap();
+        assert!(last.done);
+        assert_eq!(seen.iter().filter(|m| m.done).count(), 1);
+        assert!((last.position_s - 2.0).abs() < 0.01);
+        assert!((last.peak - 0.5).abs() < 0.01);
+        assert!((last.rms - 0.5 / 2f32.sqrt()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_analysis_cache_reuses_unchanged_files() {
+        let temp = std::env::temp_dir().join(format!("audiosync_cache_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp).unwrap();
+        let path = temp.join("rec.wav").to_string_lossy().to_string();
+        let mut track = Track::new("Rec".into());
+        track.synced_audio = Some((0..16000).map(|i| 0.5 * (i as f64 * 0.05).sin()).collect());
+        let config = SyncConfig {
+            export_sr: Some(8000),
+            export_bit_depth: 16,
+            ..Default::default()
+        };
+        export_track(&track, &path, &config).unwrap();
+
+        let cache = Some(AnalysisCache::new(temp.join("cache"), 1 << 20));
+        let load = || load_clips(std::slice::from_ref(&path), 1, &cache, &None, |_, _| {}, |_| {}).remove(0);
+        let decoded = load().unwrap();
+        let cached = cache.as_ref().unwrap().get(&path).expect("stored on first load");
+        assert_eq!(cached.samples, decoded.samples);
+        assert_eq!((cached.original_sr, cached.duration_s), (decoded.original_sr, decoded.duration_s));
+        assert_eq!(load().unwrap().samples, decoded.samples);
+
+        // A rewritten file is a different entry.
+        track.synced_audio = Some(vec![0.25; 8000]);
+        export_track(&track, &path, &config).unwrap();
+        assert!(cache.as_ref().unwrap().get(&path).is_none());
+        assert_eq!(load().unwrap().samples.len(), 8000);
+
+        // Precaching decodes only what isn't cached yet.
+        let pre = AnalysisCache::new(temp.join("pre"), 1 << 20);
+        let precache = || precache_files(std::slice::from_ref(&path), 1, &pre, &None, |_, _| {});
+        assert!(matches!(precache()[..], [Ok(false)]));
+        assert!(matches!(precache()[..], [Ok(true)]));
+
+        // Over the limit, the least recently used entries go first.
+        let tiny = AnalysisCache::new(temp.join("tiny"), 1);
+        tiny.put(&decoded);
+        assert!(tiny.get(&path).is_none());
+        let _ = std::fs::remove_dir_all(&temp);
+    }
+
+    #[test]
+    fn test_window_reads_match_whole_file() {
+        // Stereo at 44.1 kHz, read back at 48 kHz: resampled windows line up
+        // with the whole file, trim and gain included.
+        let path = std::env::temp_dir().join(format!("audiosync_window_{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..44100 * 3 {
+            let t = i as f32 / 44100.0;
+            writer.write_sample((t * 440.0 * std::f32::consts::TAU).sin() * 0.5).unwrap();
+            writer.write_sample((t * 1000.0 * std::f32::consts::TAU).sin() * 0.25).unwrap();
+        }
+        writer.finalize().unwrap();
+        let path_str = path.to_string_lossy().to_string();
+        let mut clip = Clip::new(path_str.clone(), "w.wav".into(), 44100, 2);
+        clip.duration_s = 3.0;
+        clip.trim_in_s = 0.25;
+        clip.gain_db = -6.0;
+
+        let whole = read_clip_full_res_channels(&clip, 48000, &None).unwrap();
+        for (start, frames) in [(0, 10_000), (30_001, 20_000), (120_000, 50_000)] {
+            let window = read_clip_full_res_window(&clip, 48000, (start, frames), &None).unwrap();
+            assert_eq!(window.len(), 2);
+            let expected = frames.min(whole[0].len() - start);
+            for (w, c) in window.iter().zip(&whole) {
+                assert!((w.len() as i64 - expected as i64).abs() <= 1, "{} frames from {}", w.len(), start);
+                let worst = w.iter().zip(&c[start..]).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+                assert!(worst < 1e-3, "{} from {}", worst, start);
+            }
+        }
+
+        // symphonia seeks to a stretch rather than decoding up to it.
+        let (all, ..) = load_audio_symphonia(&path_str, None, None).unwrap();
+        let (stretch, sr, ch) = load_audio_symphonia(&path_str, None, Some((1.5, 0.5))).unwrap();
+        assert_eq!((sr, ch), (44100, 2));
+        assert_eq!(stretch.len(), 22050 * 2);
+        assert_eq!(stretch[..], all[66150 * 2..88200 * 2]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_map_io_bounded_limits_concurrency() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let items: Vec<usize> = (0..12).collect();
+        let out = map_io_bounded(&items, 3, |i, &v| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            i + v
+        });
+        assert_eq!(out, (0..12).map(|v| v * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+    }
+
     #[test]
     fn test_is_audio_file() {
         assert!(is_audio_file("test.wav"));
         assert!(is_audio_file("path/to/file.MP3"));
         assert!(is_audio_file("file.flac"));
         assert!(is_audio_file("file.aiff"));
+        assert!(is_audio_file("Voice Memo.m4a"));
+        assert!(is_audio_file("REC20240501-103015.3GP"));
+        assert!(is_audio_file("call.amr"));
         assert!(!is_audio_file("file.mp4"));
         assert!(!is_audio_file("file.txt"));
         assert!(!is_audio_file(""));
@@ -719,6 +2163,18 @@ mod tests {
         assert_eq!(detect_project_sample_rate(&tracks), 44100);
     }
 
+    #[test]
+    fn test_hash_file() {
+        let path = std::env::temp_dir().join(format!("audiosync_hash_{}.bin", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"abc").unwrap();
+        let hash = hash_file(&path.to_string_lossy()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn test_resample_mono_same_rate() {
         let data = vec![1.0f32, 2.0, 3.0, 4.0];
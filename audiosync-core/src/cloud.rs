@@ -2,8 +2,12 @@
 //!
 //! Phase 3+ implementation. Provides the public interface used by CLI and Tauri.
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use log::info;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::models::Comment;
 
 /// Cloud service configuration.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -36,3 +40,168 @@ pub async fn download_project(
     info!("Cloud download not yet implemented (Phase 3+)");
     Ok(())
 }
+
+// ---------------------------------------------------------------------------
+//  Comments
+// ---------------------------------------------------------------------------
+
+/// Response envelope used by the AudioSync cloud API.
+#[derive(Debug, Deserialize)]
+struct CommentsResponse {
+    success: bool,
+    #[serde(default)]
+    comments: Vec<Comment>,
+    error: Option<String>,
+}
+
+fn comments_url(config: &CloudConfig, project_id: &str) -> String {
+    format!(
+        "{}/audiosync/projects/{}/comments",
+        config.endpoint.trim_end_matches('/'),
+        project_id
+    )
+}
+
+fn authorized(config: &CloudConfig, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match config.api_key {
+        Some(ref key) => req.bearer_auth(key),
+        None => req,
+    }
+}
+
+fn unwrap_comments(resp: CommentsResponse) -> Result<Vec<Comment>> {
+    if resp.success {
+        Ok(resp.comments)
+    } else {
+        Err(anyhow!(
+            "Cloud comments request failed: {}",
+            resp.error.unwrap_or_else(|| "unknown error".to_string())
+        ))
+    }
+}
+
+/// Fetch all comments of a cloud project.
+pub async fn pull_comments(config: &CloudConfig, project_id: &str) -> Result<Vec<Comment>> {
+    let client = reqwest::Client::new();
+    let resp: CommentsResponse = authorized(config, client.get(comments_url(config, project_id)))
+        .send()
+        .await
+        .context("Failed to reach cloud service")?
+        .json()
+        .await
+        .context("Invalid response from cloud service")?;
+    unwrap_comments(resp)
+}
+
+/// Replace the comments of a cloud project with `comments`.
+pub async fn push_comments(
+    config: &CloudConfig,
+    project_id: &str,
+    comments: &[Comment],
+) -> Result<()> {
+    let client = reqwest::Client::new();
+    let resp: CommentsResponse = authorized(config, client.put(comments_url(config, project_id)))
+        .json(&serde_json::json!({ "comments": comments }))
+        .send()
+        .await
+        .context("Failed to reach cloud service")?
+        .json()
+        .await
+        .context("Invalid response from cloud service")?;
+    unwrap_comments(resp).map(|_| ())
+}
+
+/// Two-way comment sync: pull remote comments, merge with local, push the merge.
+///
+/// Returns the merged set, which callers should store as the new local state.
+pub async fn sync_comments(
+    config: &CloudConfig,
+    project_id: &str,
+    local: &[Comment],
+) -> Result<Vec<Comment>> {
+    let remote = pull_comments(config, project_id).await?;
+    let merged = merge_comments(local, &remote);
+    push_comments(config, project_id, &merged).await?;
+    info!(
+        "Synced {} comments for cloud project {} ({} local, {} remote)",
+        merged.len(),
+        project_id,
+        local.len(),
+        remote.len()
+    );
+    Ok(merged)
+}
+
+/// Merge two comment sets by id — the copy with the later `updated_at` wins.
+/// Deleted comments stay as tombstones, so a deletion newer than the other
+/// side's copy wins too.
+///
+/// The result is ordered by creation time so threads render chronologically.
+pub fn merge_comments(local: &[Comment], remote: &[Comment]) -> Vec<Comment> {
+    let mut by_id: HashMap<&str, &Comment> = HashMap::new();
+    for c in remote.iter().chain(local.iter()) {
+        match by_id.get(c.id.as_str()) {
+            Some(existing) if existing.updated_at >= c.updated_at => {}
+            _ => {
+                by_id.insert(c.id.as_str(), c);
+            }
+        }
+    }
+
+    let mut merged: Vec<Comment> = by_id.into_values().cloned().collect();
+    merged.sort_by(|a, b| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentAnchor;
+
+    fn comment(id: &str, body: &str, created: &str, updated: &str) -> Comment {
+        Comment {
+            id: id.to_string(),
+            parent_id: None,
+            author: "tester".to_string(),
+            body: body.to_string(),
+            anchor: CommentAnchor::Timeline { position_s: 0.0 },
+            created_at: created.to_string(),
+            updated_at: updated.to_string(),
+            resolved: false,
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_comments_union_and_newest_wins() {
+        let local = vec![
+            comment("a", "local edit", "2026-01-01T00:00:00Z", "2026-01-03T00:00:00Z"),
+            comment("b", "only local", "2026-01-02T00:00:00Z", "2026-01-02T00:00:00Z"),
+        ];
+        let remote = vec![
+            comment("a", "stale remote", "2026-01-01T00:00:00Z", "2026-01-02T00:00:00Z"),
+            comment("c", "only remote", "2025-12-31T00:00:00Z", "2025-12-31T00:00:00Z"),
+        ];
+
+        let merged = merge_comments(&local, &remote);
+        let ids: Vec<&str> = merged.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b"]);
+        assert_eq!(merged[1].body, "local edit");
+    }
+
+    #[test]
+    fn test_merge_comments_keeps_newer_deletion() {
+        let mut deleted = comment("a", "gone", "2026-01-01T00:00:00Z", "2026-01-01T00:00:00Z");
+        deleted.delete();
+        let remote = vec![comment("a", "gone", "2026-01-01T00:00:00Z", "2026-01-01T00:00:00Z")];
+
+        let merged = merge_comments(&[deleted.clone()], &remote);
+        assert!(merged[0].deleted);
+        // Either way round: the tombstone is newer than the remote copy.
+        assert!(merge_comments(&remote, &[deleted])[0].deleted);
+    }
+}
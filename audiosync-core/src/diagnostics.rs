@@ -0,0 +1,274 @@
+//! Capability report — what this machine can decode, export and run.
+//!
+//! Backs `audiosync doctor` and the app's diagnostics screen. Every check is
+//! best-effort: a missing tool or unwritable directory is reported in the
+//! result, never returned as an error.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::audio_io::{find_ffmpeg, AUDIO_EXTENSIONS, FFMPEG_AUDIO_EXTENSIONS, VIDEO_EXTENSIONS};
+use crate::engine::correlation_backend_name;
+
+/// Export formats and the ffmpeg encoder each needs (`None`: written natively).
+const EXPORT_ENCODERS: &[(&str, Option<&str>)] = &[
+    ("wav", None),
+    ("aiff", Some("pcm_s24be")),
+    ("flac", Some("flac")),
+    ("mp3", Some("libmp3lame")),
+];
+
+/// An external tool and the version it reports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolStatus {
+    pub found: bool,
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Whether an export format can be written, and how.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormatSupport {
+    pub format: String,
+    pub available: bool,
+    /// `"native"` or the ffmpeg encoder used.
+    pub via: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodecSupport {
+    /// Audio file extensions decoded in-process (symphonia).
+    pub native_audio: Vec<String>,
+    /// Audio file extensions that need ffmpeg to decode (AMR phone recordings).
+    pub ffmpeg_audio: Vec<String>,
+    /// Video file extensions, which need ffmpeg to extract audio.
+    pub video: Vec<String>,
+    pub video_available: bool,
+    pub export: Vec<FormatSupport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryCheck {
+    /// What the directory is for, e.g. `"temp"` or `"output"`.
+    pub role: String,
+    pub path: String,
+    pub exists: bool,
+    /// Files can be created there (or, if it doesn't exist yet, in the
+    /// nearest existing parent it would be created under).
+    pub writable: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuInfo {
+    pub arch: String,
+    pub logical_cores: usize,
+    /// SIMD extensions detected at runtime (the FFT uses the best available).
+    pub simd: Vec<String>,
+    /// Where correlations run: `realfft`, or the installed GPU backend (see
+    /// `engine::set_correlation_backend`).
+    #[serde(default)]
+    pub correlation_backend: String,
+}
+
+/// Everything `audiosync doctor` reports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityReport {
+    pub version: String,
+    pub os: String,
+    pub ffmpeg: ToolStatus,
+    pub ffprobe: ToolStatus,
+    pub codecs: CodecSupport,
+    pub directories: Vec<DirectoryCheck>,
+    pub cpu: CpuInfo,
+    /// Human-readable problems found; empty when everything checked out.
+    pub problems: Vec<String>,
+    pub ok: bool,
+}
+
+/// Run every check. `output_dir` is checked for writability alongside the
+/// temp directory when given.
+pub fn capability_report(output_dir: Option<&str>) -> CapabilityReport {
+    let ffmpeg = match find_ffmpeg() {
+        Ok(path) => probe_tool(&path),
+        Err(_) => ToolStatus::default(),
+    };
+    let ffprobe = probe_tool("ffprobe");
+
+    let encoders = if ffmpeg.found {
+        ffmpeg_encoders(ffmpeg.path.as_deref().unwrap_or("ffmpeg"))
+    } else {
+        Vec::new()
+    };
+    let export = EXPORT_ENCODERS
+        .iter()
+        .map(|&(format, encoder)| FormatSupport {
+            format: format.to_string(),
+            available: encoder.is_none_or(|e| encoders.iter().any(|have| have == e)),
+            via: encoder.unwrap_or("native").to_string(),
+        })
+        .collect();
+    let codecs = CodecSupport {
+        native_audio: AUDIO_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        ffmpeg_audio: FFMPEG_AUDIO_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        video: VIDEO_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        video_available: ffmpeg.found,
+        export,
+    };
+
+    let mut directories = vec![check_directory("temp", &std::env::temp_dir())];
+    if let Some(dir) = output_dir {
+        directories.push(check_directory("output", Path::new(dir)));
+    }
+
+    let mut problems = Vec::new();
+    if !ffmpeg.found {
+        problems.push("ffmpeg not found: video, 3GP and AMR files can't be imported and only WAV can be exported".to_string());
+    }
+    if !ffprobe.found {
+        problems.push("ffprobe not found: creation times and stream details won't be read".to_string());
+    }
+    if ffmpeg.found {
+        for f in codecs.export.iter().filter(|f| !f.available) {
+            problems.push(format!("ffmpeg lacks the {} encoder: {} export unavailable", f.via, f.format));
+        }
+    }
+    for d in directories.iter().filter(|d| !d.writable) {
+        problems.push(format!("{} directory '{}' is not writable", d.role, d.path));
+    }
+
+    CapabilityReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        ffmpeg,
+        ffprobe,
+        codecs,
+        directories,
+        cpu: cpu_info(),
+        ok: problems.is_empty(),
+        problems,
+    }
+}
+
+/// Run `<tool> -version` and keep its version string.
+fn probe_tool(path: &str) -> ToolStatus {
+    match Command::new(path).arg("-version").output() {
+        Ok(output) if output.status.success() => ToolStatus {
+            found: true,
+            path: Some(path.to_string()),
+            version: parse_version(&String::from_utf8_lossy(&output.stdout)),
+        },
+        _ => ToolStatus::default(),
+    }
+}
+
+/// `"ffmpeg version 6.1.1-3ubuntu5 Copyright ..."` → `"6.1.1-3ubuntu5"`.
+fn parse_version(banner: &str) -> Option<String> {
+    let line = banner.lines().next()?;
+    let mut words = line.split_whitespace();
+    words.find(|w| *w == "version")?;
+    words.next().map(str::to_string)
+}
+
+/// Names of the audio encoders ffmpeg was built with.
+fn ffmpeg_encoders(ffmpeg: &str) -> Vec<String> {
+    let Ok(output) = Command::new(ffmpeg).args(["-hide_banner", "-encoders"]).output() else {
+        return Vec::new();
+    };
+    parse_encoders(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Encoder lines look like `" A....D flac    FLAC (Free Lossless Audio Codec)"`.
+fn parse_encoders(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let flags = fields.next()?;
+            (flags.len() == 6 && flags.starts_with('A')).then(|| fields.next().map(str::to_string))?
+        })
+        .collect()
+}
+
+fn check_directory(role: &str, path: &Path) -> DirectoryCheck {
+    let exists = path.is_dir();
+    let probe_in: Option<PathBuf> = path.ancestors().find(|p| p.is_dir()).map(Path::to_path_buf);
+    let probe = probe_in
+        .ok_or_else(|| "no existing parent directory".to_string())
+        .and_then(|dir| {
+            let file = dir.join(format!(".audiosync_write_test_{}", uuid::Uuid::new_v4().as_hyphenated()));
+            std::fs::write(&file, b"").map_err(|e| e.to_string())?;
+            let _ = std::fs::remove_file(&file);
+            Ok(())
+        });
+    DirectoryCheck {
+        role: role.to_string(),
+        path: path.to_string_lossy().to_string(),
+        exists,
+        writable: probe.is_ok(),
+        error: probe.err(),
+    }
+}
+
+fn cpu_info() -> CpuInfo {
+    #[allow(unused_mut)]
+    let mut simd: Vec<String> = Vec::new();
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        macro_rules! detect {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_x86_feature_detected!($feature) {
+                    simd.push($feature.to_string());
+                })*
+            };
+        }
+        detect!("sse2", "sse4.1", "avx", "avx2", "fma", "avx512f");
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            simd.push("neon".to_string());
+        }
+    }
+    CpuInfo {
+        arch: std::env::consts::ARCH.to_string(),
+        logical_cores: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        simd,
+        correlation_backend: correlation_backend_name(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_output() {
+        assert_eq!(
+            parse_version("ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023\nbuilt with gcc").as_deref(),
+            Some("6.1.1-3ubuntu5")
+        );
+        assert_eq!(parse_version("command not found"), None);
+
+        let listing = "Encoders:\n V..... = Video\n ------\n V....D libx264  H.264\n A....D flac     FLAC\n A..... pcm_s24be  PCM\n";
+        assert_eq!(parse_encoders(listing), vec!["flac", "pcm_s24be"]);
+    }
+
+    #[test]
+    fn test_directory_checks() {
+        let temp = std::env::temp_dir();
+        let check = check_directory("temp", &temp);
+        assert!(check.exists && check.writable, "{:?}", check);
+
+        // Not there yet, but creatable under the temp directory.
+        let pending = check_directory("output", &temp.join("audiosync_doctor_missing").join("nested"));
+        assert!(!pending.exists && pending.writable);
+
+        let report = capability_report(None);
+        assert_eq!(report.ok, report.problems.is_empty());
+        assert_eq!(report.codecs.export[0].via, "native");
+        assert!(report.codecs.export[0].available);
+        assert!(report.cpu.logical_cores >= 1);
+    }
+}
@@ -6,28 +6,218 @@
 //! 3. Build reference timeline from metadata gaps.
 //! 4. Cross-correlate non-reference clips (Pass 1).
 //! 5. Enhanced timeline retry for low-confidence clips (Pass 2).
-//! 6. Metadata fallback for remaining unmatched.
+//! 6. Metadata fallback for remaining unmatched; clips that match nothing in
+//!    the session are left unplaced instead.
 //! 7. Normalize timeline so earliest offset is zero.
 //! 8. Clock drift detection via windowed cross-correlation.
+//!
+//! In incremental mode (`SyncConfig::incremental`), clips analyzed before
+//! keep their offsets and stand in for steps 3–4's reference: they are
+//! stitched into one timeline and only new clips are correlated against it.
+//!
+//! With an LTC channel configured, clips carrying timecode skip the open
+//! search in step 4: they go where their timecode says, refined by
+//! correlating just around that spot (see [`ltc`]).
+
+mod denoise;
+mod discovery;
+mod fft_backend;
+pub mod ltc;
+mod onset;
+mod reference_layout;
+mod refine;
+mod sparse;
+mod verify;
+mod stitch;
+
+pub use discovery::{assign_proposed_groups, split_sessions};
+pub use fft_backend::{correlation_backend_name, set_correlation_backend, CorrelationBackend};
+#[cfg(feature = "gpu")]
+pub use fft_backend::WgpuBackend;
+pub use refine::refine_offsets;
+pub use stitch::{
+    output_channel_mask, output_channels, resolve_export_sr, stitched_clip_span, sync, sync_streaming, Stitched,
+    STITCH_BLOCK,
+};
 
 use anyhow::{anyhow, Result};
 use log::{debug, info, warn};
-use rustfft::{num_complex::Complex, FftPlanner};
-use std::collections::HashMap;
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex};
 
-use crate::audio_io::{detect_project_sample_rate, read_clip_full_res};
+use crate::audio_io::{map_io_bounded, sanitize_path_part};
+use crate::metadata::probe_video_info;
 use crate::models::*;
+use crate::suggestions::{suggest_for_clip, AMBIGUOUS_PEAK_RATIO};
 
 // ---------------------------------------------------------------------------
 //  Public API
 // ---------------------------------------------------------------------------
 
 /// Full analysis pipeline — runs entirely at 8 kHz.
+///
+/// With several sync groups (`Track::sync_group`) each group is analyzed on
+/// its own, against its own reference; the result lists them in `groups`.
+/// Tracks that only match each other are proposed as new groups, and with
+/// `auto_sync_groups` moved into them and analyzed again.
 pub fn analyze(
     tracks: &mut [Track],
     config: &SyncConfig,
     progress: &Option<ProgressCallback>,
     cancel: &Option<CancelToken>,
+) -> Result<SyncResult> {
+    // Excluded clips sit out analysis and keep whatever placement they had.
+    let excluded: Vec<Vec<Clip>> = tracks
+        .iter_mut()
+        .map(|t| {
+            let (excluded, kept) = std::mem::take(&mut t.clips).into_iter().partition(|c| c.excluded);
+            t.clips = kept;
+            excluded
+        })
+        .collect();
+
+    let result = (|| {
+        let result = analyze_groups(tracks, config, progress, cancel)?;
+        if !config.auto_sync_groups || result.proposed_groups.is_empty() {
+            return Ok(result);
+        }
+        let names = assign_proposed_groups(tracks, &result.proposed_groups);
+        info!("Split off sync group(s) {:?}; analyzing again", names);
+        analyze_groups(tracks, config, progress, cancel)
+    })();
+
+    for (track, excluded) in tracks.iter_mut().zip(excluded) {
+        if !excluded.is_empty() {
+            track.clips.extend(excluded);
+            track.sort_clips_by_time();
+        }
+    }
+    result
+}
+
+fn analyze_groups(
+    tracks: &mut [Track],
+    config: &SyncConfig,
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+) -> Result<SyncResult> {
+    let progress = ProgressReporter::new(progress);
+    let groups = sync_groups(tracks);
+    if groups.len() <= 1 {
+        return analyze_group(tracks, config, &progress, cancel);
+    }
+
+    // Each group gets an equal share of the progress bar.
+    let groups: Vec<_> = groups
+        .into_iter()
+        .filter(|(_, indices)| indices.iter().any(|&i| !tracks[i].clips.is_empty()))
+        .collect();
+    let mut results: Vec<(String, Vec<usize>, SyncResult)> = Vec::new();
+    for (gi, (name, indices)) in groups.iter().enumerate() {
+        info!("Analyzing sync group '{}' ({} tracks)", name, indices.len());
+        let part = progress.part(gi, groups.len());
+        let result = with_tracks(tracks, indices, |group| analyze_group(group, config, &part, cancel))?;
+        results.push((name.clone(), indices.clone(), result));
+    }
+    if results.is_empty() {
+        return Err(anyhow!("No clips loaded in any track."));
+    }
+
+    // Merge, mapping group-local track indices back to project indices.
+    let total_clips: usize = tracks.iter().map(|t| t.clip_count()).sum();
+    let first = &results[0];
+    let mut merged = SyncResult {
+        reference_track_index: first.1[first.2.reference_track_index],
+        total_timeline_samples: 0,
+        total_timeline_s: 0.0,
+        sample_rate: first.2.sample_rate,
+        clip_offsets: HashMap::new(),
+        avg_confidence: 0.0,
+        drift_detected: false,
+        warnings: Vec::new(),
+        suggestions: Vec::new(),
+        groups: Vec::new(),
+        proposed_groups: Vec::new(),
+        unplaced_clips: Vec::new(),
+        diagnostics: HashMap::new(),
+    };
+    for (name, indices, result) in results {
+        let clips: usize = indices.iter().map(|&i| tracks[i].clip_count()).sum();
+        merged.total_timeline_samples = merged.total_timeline_samples.max(result.total_timeline_samples);
+        merged.avg_confidence += result.avg_confidence * clips as f64 / total_clips.max(1) as f64;
+        merged.drift_detected |= result.drift_detected;
+        merged.clip_offsets.extend(result.clip_offsets);
+        merged.warnings.extend(result.warnings);
+        merged.suggestions.extend(result.suggestions);
+        merged.proposed_groups.extend(result.proposed_groups.into_iter().map(|mut p| {
+            p.track_indices = p.track_indices.iter().map(|&i| indices[i]).collect();
+            p
+        }));
+        merged.unplaced_clips.extend(result.unplaced_clips);
+        merged.diagnostics.extend(result.diagnostics);
+        merged.groups.push(SyncGroupResult {
+            name,
+            reference_track_index: indices[result.reference_track_index],
+            track_indices: indices,
+            total_timeline_samples: result.total_timeline_samples,
+            total_timeline_s: result.total_timeline_s,
+            avg_confidence: result.avg_confidence,
+        });
+    }
+    merged.total_timeline_s = merged.total_timeline_samples as f64 / merged.sample_rate as f64;
+    progress.report(ProgressPhase::Done, 1.0, (0, 0), "Analysis complete.");
+    Ok(merged)
+}
+
+/// Track indices per sync group, groups in order of first appearance.
+pub fn sync_groups(tracks: &[Track]) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (i, track) in tracks.iter().enumerate() {
+        let name = track.sync_group_name();
+        match groups.iter_mut().find(|(g, _)| g == name) {
+            Some((_, indices)) => indices.push(i),
+            None => groups.push((name.to_string(), vec![i])),
+        }
+    }
+    groups
+}
+
+/// Run `f` on the tracks at `indices` as one contiguous slice, putting them
+/// back afterwards (whatever `f` returns).
+pub(crate) fn with_tracks<R>(
+    tracks: &mut [Track],
+    indices: &[usize],
+    f: impl FnOnce(&mut [Track]) -> R,
+) -> R {
+    let mut subset: Vec<Track> = indices
+        .iter()
+        .map(|&i| std::mem::replace(&mut tracks[i], Track::new(String::new())))
+        .collect();
+    let out = f(&mut subset);
+    for (&i, track) in indices.iter().zip(subset) {
+        tracks[i] = track;
+    }
+    out
+}
+
+/// Shares of an analysis's progress, `(start, end)`, spent on each pass
+/// over the clips. Verifying and normalizing fall between pass 3 and drift.
+const PASS1_SPAN: (f64, f64) = (0.05, 0.6);
+const PASS2_SPAN: (f64, f64) = (0.6, 0.7);
+const PASS3_SPAN: (f64, f64) = (0.7, 0.75);
+const DRIFT_SPAN: (f64, f64) = (0.8, 1.0);
+
+/// Progress at the start of item `i` (1-based) of `n` within `span`.
+fn phase_fraction((start, end): (f64, f64), i: usize, n: usize) -> f64 {
+    start + (end - start) * i.saturating_sub(1) as f64 / n.max(1) as f64
+}
+
+fn analyze_group(
+    tracks: &mut [Track],
+    config: &SyncConfig,
+    progress: &ProgressReporter,
+    cancel: &Option<CancelToken>,
 ) -> Result<SyncResult> {
     if tracks.is_empty() {
         return Err(anyhow!("No tracks to analyze."));
@@ -39,28 +229,56 @@ pub fn analyze(
     }
 
     let sr = ANALYSIS_SR;
-    let total_steps = total_clips + 4;
 
-    macro_rules! prog {
-        ($step:expr, $msg:expr) => {
-            if let Some(cb) = progress {
-                cb($step, total_steps, $msg);
-            }
-        };
+    // Remember the anchor's current placement so Phase 7 can restore it.
+    let anchor_before: Option<(String, i64)> = config.anchor_clip.as_ref().and_then(|path| {
+        tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .find(|c| &c.file_path == path && c.analyzed)
+            .map(|c| (path.clone(), c.timeline_offset_samples))
+    });
+
+    // Clips placed by hand go exactly where they were put (and are pinned
+    // there like locked clips from here on). Clips left unplaced last time
+    // get another chance.
+    for clip in tracks.iter_mut().flat_map(|t| t.clips.iter_mut()) {
+        clip.unplaced = false;
+        if clip.offset_override_s.is_some() {
+            clip.set_offset_override(clip.offset_override_s);
+        }
+    }
+
+    // Incremental mode: everything analyzed before stays put.
+    let kept: HashSet<String> = if config.incremental {
+        tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .filter(|c| c.analyzed)
+            .map(|c| c.file_path.clone())
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let incremental = !kept.is_empty();
+    if incremental {
+        info!("Incremental analysis: keeping {} placed clip(s)", kept.len());
     }
 
     // Phase 1: Sort clips
-    prog!(0, "Sorting clips by creation time...");
+    progress.report(ProgressPhase::Preparing, 0.0, (0, 0), "Sorting clips by creation time...");
     check_cancelled(cancel)?;
     for track in tracks.iter_mut() {
         track.sort_clips_by_time();
     }
 
     // Phase 2: Select reference track
-    prog!(1, "Selecting reference track...");
+    progress.report(ProgressPhase::Preparing, 0.01, (0, 0), "Selecting reference track...");
     check_cancelled(cancel)?;
-    let ref_idx = select_reference_index(tracks);
-    tracks[ref_idx].is_reference = true;
+    let ref_idx = select_reference_index(tracks, &config.reference_strategy);
+    for (i, track) in tracks.iter_mut().enumerate() {
+        track.is_reference = i == ref_idx;
+    }
     info!(
         "Reference track: '{}' (index {}, {} clips)",
         tracks[ref_idx].name,
@@ -68,62 +286,235 @@ pub fn analyze(
         tracks[ref_idx].clip_count()
     );
 
+    // A device switching sample rate mid-shoot runs on a different clock
+    // per rate; drift is only shared between clips of the same rate.
+    let mut warnings: Vec<SyncWarning> = Vec::new();
+    for track in tracks.iter() {
+        let rates = track.sample_rates();
+        if rates.len() > 1 {
+            let listed: Vec<String> = rates.iter().map(|r| format!("{} Hz", r)).collect();
+            push_warning(
+                &mut warnings,
+                SyncWarning::new(
+                    WarningCode::MixedSampleRates,
+                    WarningSeverity::Warning,
+                    format!("'{}' mixes clips recorded at {}", track.name, listed.join(", ")),
+                ),
+            );
+        }
+    }
+
+    // Without a bound given, the clips' timestamps set one; `unbounded` keeps
+    // the search without it for clips that don't match within it.
+    let estimated_config;
+    let (config, unbounded) = match config.max_offset_s {
+        None if config.estimate_max_offset => match estimate_max_offset_s(tracks) {
+            Some(bound) => {
+                info!("Searching offsets within ±{:.0} s, from the clips' creation timestamps", bound);
+                push_warning(
+                    &mut warnings,
+                    SyncWarning::new(
+                        WarningCode::MaxOffsetEstimated,
+                        WarningSeverity::Info,
+                        format!("Offsets searched within ±{:.0} s, from the creation timestamps' spread", bound),
+                    ),
+                );
+                estimated_config = SyncConfig {
+                    max_offset_s: Some(bound),
+                    ..config.clone()
+                };
+                (&estimated_config, Some(config))
+            }
+            None => {
+                debug!("Not every clip has a creation timestamp; searching all offsets");
+                (config, None)
+            }
+        },
+        _ => (config, None),
+    };
+
     // Phase 3: Build reference timeline
-    prog!(2, &format!("Building timeline from '{}' metadata...", tracks[ref_idx].name));
-    check_cancelled(cancel)?;
-    let ref_audio = build_reference_from_metadata(&mut tracks[ref_idx], sr)?;
+    let ref_audio = if incremental {
+        progress.report(ProgressPhase::Preparing, 0.02, (0, 0), "Building timeline from placed clips...");
+        check_cancelled(cancel)?;
+        stitch_clips(tracks.iter().flat_map(|t| t.clips.iter()).filter(|c| kept.contains(&c.file_path)))
+    } else {
+        let track = &mut tracks[ref_idx];
+        let by_content = match config.reference_layout {
+            ReferenceLayout::Metadata => false,
+            ReferenceLayout::Content => true,
+            ReferenceLayout::Auto => {
+                track.clips.len() > 1 && track.clips.iter().any(|c| c.creation_time.is_none())
+            }
+        };
+        let source = if by_content { "audio" } else { "metadata" };
+        let message = format!("Building timeline from '{}' {}...", track.name, source);
+        progress.report(ProgressPhase::Preparing, 0.02, (0, 0), &message);
+        check_cancelled(cancel)?;
+        build_reference(track, sr, by_content, &mut warnings)?
+    };
     info!(
         "Reference timeline: {:.1} s ({} samples)",
         ref_audio.len() as f64 / sr as f64,
         ref_audio.len()
     );
 
+    // Phase 3.5: Decode LTC for clips that haven't been decoded yet
+    if let Some(channel) = config.ltc_channel {
+        decode_timecodes(tracks, channel, config.io_parallelism, cancel);
+        check_cancelled(cancel)?;
+    }
+
     // Phase 4: Cross-correlate non-reference clips (Pass 1)
-    let mut warnings: Vec<String> = Vec::new();
     let mut confidences: Vec<f64> = Vec::new();
     let mut clip_offsets: HashMap<String, i64> = HashMap::new();
     let mut placed_clips: Vec<(usize, usize)> = Vec::new(); // (track_idx, clip_idx)
     let mut unplaced_clips: Vec<(usize, usize)> = Vec::new();
+    // How alike each correlated clip's best match was, however ambiguous
+    // or silent.
+    let mut similarity: HashMap<(usize, usize), f64> = HashMap::new();
+    let mut diagnostics: HashMap<String, ClipDiagnostics> = HashMap::new();
 
-    // Record reference clip offsets
-    for clip in &tracks[ref_idx].clips {
-        clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
-        confidences.push(clip.confidence);
+    let ref_origin = if incremental {
+        placed_time_origin(&tracks[ref_idx], &kept)
+    } else {
+        get_track_time_origin(&tracks[ref_idx])
+    };
+    let ref_weighting = config.band_weighting(&tracks[ref_idx]);
+    let ref_gated = correlation_input(&ref_audio, sr, config, ref_weighting);
+    let ref_claps = slate_claps(&ref_audio, sr, config);
+
+    // Record reference clip offsets (new reference clips are correlated
+    // like any other in incremental mode)
+    if !incremental {
+        for clip in &tracks[ref_idx].clips {
+            clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
+            confidences.push(clip.confidence);
+        }
     }
 
-    let mut step = 2usize;
+    let pass1_clips: usize = (0..tracks.len())
+        .filter(|&ti| ti != ref_idx || incremental)
+        .map(|ti| tracks[ti].clips.len())
+        .sum();
+    let mut step = 0usize;
     for ti in 0..tracks.len() {
-        if ti == ref_idx {
+        if ti == ref_idx && !incremental {
             continue;
         }
         for ci in 0..tracks[ti].clips.len() {
             step += 1;
             let clip_name = tracks[ti].clips[ci].name.clone();
-            prog!(step, &format!("Pass 1: correlating '{}'...", clip_name));
+            progress.report(
+                ProgressPhase::Correlating,
+                phase_fraction(PASS1_SPAN, step, pass1_clips),
+                (step, pass1_clips),
+                &format!("Pass 1: correlating '{}'...", clip_name),
+            );
             check_cancelled(cancel)?;
 
-            let (delay, conf) = compute_delay(
-                &ref_audio,
-                &tracks[ti].clips[ci].samples,
-                sr,
-                config.max_offset_s,
+            if tracks[ti].is_clip_locked(ci) || kept.contains(&tracks[ti].clips[ci].file_path) {
+                let clip = &tracks[ti].clips[ci];
+                clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
+                confidences.push(clip.confidence);
+                placed_clips.push((ti, ci));
+                let mut diag = match_diagnostics(MatchMethod::Kept, clip.confidence, None);
+                measure_placement(&mut diag, &ref_audio, clip, clip.timeline_offset_samples);
+                diagnostics.insert(clip.file_path.clone(), diag);
+                debug!("Keeping placed clip '{}' in place", clip_name);
+                continue;
+            }
+
+            let t_corr = std::time::Instant::now();
+            if config.ltc_channel.is_some()
+                && let Some(expected) = timecode_delay(tracks, ref_idx, ti, ci, sr)
+            {
+                let weighting = config.band_weighting(&tracks[ti]);
+                let clip = &tracks[ti].clips[ci];
+                let (m, note) = match_on_timecode(&ref_gated, clip, expected, (config, weighting), cancel);
+                check_cancelled(cancel)?;
+                let clip = &mut tracks[ti].clips[ci];
+                clip.timings.correlation_s = t_corr.elapsed().as_secs_f64();
+                clip.timeline_offset_samples = m.delay_samples;
+                clip.timeline_offset_s = m.delay_s;
+                clip.confidence = m.confidence;
+                clip.match_score = m.score;
+                clip.polarity_inverted = m.inverted && m.score >= CONFIDENCE_THRESHOLD;
+                clip.analyzed = true;
+                clip_offsets.insert(clip.file_path.clone(), m.delay_samples);
+                confidences.push(m.confidence);
+                placed_clips.push((ti, ci));
+                let mut diag = match_diagnostics(MatchMethod::Timecode, m.confidence, None);
+                measure_placement(&mut diag, &ref_audio, clip, m.delay_samples);
+                diagnostics.insert(clip.file_path.clone(), diag);
+                if let Some(note) = note {
+                    push_warning(&mut warnings, note);
+                }
+                continue;
+            }
+            let (m, mut diag, note) = match_within_estimate(
+                &ref_gated,
+                &ref_claps,
+                tracks,
+                (ti, ci),
+                ref_origin,
+                (config, unbounded),
+                cancel,
             );
+            check_cancelled(cancel)?;
+            let (delay, conf) = (m.delay_samples, m.confidence);
+            similarity.insert((ti, ci), m.ncc);
+            tracks[ti].clips[ci].timings.correlation_s = t_corr.elapsed().as_secs_f64();
+            if let Some(dir) = &config.debug_correlation_dir {
+                dump_correlation(dir, 1, &tracks[ti], ci, &ref_audio, sr, config.max_offset_s);
+            }
+
+            // A peak found in silence is noise, however strong it looks.
+            measure_placement(&mut diag, &ref_audio, &tracks[ti].clips[ci], delay);
+            diagnostics.insert(tracks[ti].clips[ci].file_path.clone(), diag);
+            let shared_s = diag.overlap_s;
+            if shared_s < min_shared_audio_s(&tracks[ti].clips[ci]) {
+                let clip = &mut tracks[ti].clips[ci];
+                clip.confidence = 0.0;
+                clip.match_score = 0.0;
+                clip.analyzed = true;
+                clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
+                confidences.push(0.0);
+                unplaced_clips.push((ti, ci));
+                push_warning(
+                    &mut warnings,
+                    insufficient_shared_audio(&clip_name, shared_s).for_clip(&clip.file_path),
+                );
+                continue;
+            }
+            if let Some(note) = note {
+                push_warning(&mut warnings, note);
+            }
 
             tracks[ti].clips[ci].timeline_offset_samples = delay;
-            tracks[ti].clips[ci].timeline_offset_s = delay as f64 / sr as f64;
+            tracks[ti].clips[ci].timeline_offset_s = m.delay_s;
             tracks[ti].clips[ci].confidence = conf;
+            tracks[ti].clips[ci].match_score = m.score;
+            // A peak too weak to place the clip says nothing about polarity.
+            tracks[ti].clips[ci].polarity_inverted = m.inverted && m.score >= CONFIDENCE_THRESHOLD;
             tracks[ti].clips[ci].analyzed = true;
 
             clip_offsets.insert(tracks[ti].clips[ci].file_path.clone(), delay);
             confidences.push(conf);
 
-            if conf >= CONFIDENCE_THRESHOLD {
+            if m.score >= CONFIDENCE_THRESHOLD {
                 placed_clips.push((ti, ci));
             } else {
                 unplaced_clips.push((ti, ci));
-                let msg = format!("Low confidence ({:.1}) for '{}'", conf, clip_name);
-                warnings.push(msg.clone());
-                warn!("{}", msg);
+                push_warning(
+                    &mut warnings,
+                    SyncWarning::new(
+                        WarningCode::LowConfidence,
+                        WarningSeverity::Warning,
+                        format!("Low confidence ({:.0}/100) for '{}'", m.score, clip_name),
+                    )
+                    .for_clip(&tracks[ti].clips[ci].file_path),
+                );
             }
         }
     }
@@ -132,36 +523,63 @@ pub fn analyze(
 
     // Phase 5: Enhanced timeline for unmatched clips (Pass 2)
     if !unplaced_clips.is_empty() {
-        prog!(step + 1, "Pass 2: building enhanced timeline...");
+        let message = "Pass 2: building enhanced timeline...";
+        progress.report(ProgressPhase::Correlating, PASS2_SPAN.0, (0, 0), message);
         check_cancelled(cancel)?;
 
         let enhanced = stitch_enhanced_timeline(&ref_audio, tracks, &placed_clips, sr);
+        let enhanced_gated = correlation_input(&enhanced, sr, config, ref_weighting);
+        let enhanced_claps = slate_claps(&enhanced, sr, config);
 
-        for &(ti, ci) in &unplaced_clips {
-            step += 1;
+        for (i, &(ti, ci)) in unplaced_clips.iter().enumerate() {
             let clip_name = tracks[ti].clips[ci].name.clone();
-            prog!(step, &format!("Pass 2: retrying '{}'...", clip_name));
+            progress.report(
+                ProgressPhase::Correlating,
+                phase_fraction(PASS2_SPAN, i + 1, unplaced_clips.len()),
+                (i + 1, unplaced_clips.len()),
+                &format!("Pass 2: retrying '{}'...", clip_name),
+            );
             check_cancelled(cancel)?;
 
-            let (delay, conf) = compute_delay(
-                &enhanced,
-                &tracks[ti].clips[ci].samples,
-                sr,
-                config.max_offset_s,
+            let t_corr = std::time::Instant::now();
+            let (m, mut diag, note) = match_within_estimate(
+                &enhanced_gated,
+                &enhanced_claps,
+                tracks,
+                (ti, ci),
+                ref_origin,
+                (config, unbounded),
+                cancel,
             );
+            check_cancelled(cancel)?;
+            let delay = m.delay_samples;
+            similarity.entry((ti, ci)).and_modify(|s| *s = s.max(m.ncc)).or_insert(m.ncc);
+            tracks[ti].clips[ci].timings.correlation_s += t_corr.elapsed().as_secs_f64();
+            if let Some(dir) = &config.debug_correlation_dir {
+                dump_correlation(dir, 2, &tracks[ti], ci, &enhanced, sr, config.max_offset_s);
+            }
 
-            if conf > tracks[ti].clips[ci].confidence {
+            measure_placement(&mut diag, &enhanced, &tracks[ti].clips[ci], delay);
+            let shared_s = diag.overlap_s;
+            if m.score > tracks[ti].clips[ci].match_score && shared_s >= min_shared_audio_s(&tracks[ti].clips[ci]) {
                 tracks[ti].clips[ci].timeline_offset_samples = delay;
-                tracks[ti].clips[ci].timeline_offset_s = delay as f64 / sr as f64;
-                tracks[ti].clips[ci].confidence = conf;
+                tracks[ti].clips[ci].timeline_offset_s = m.delay_s;
+                tracks[ti].clips[ci].confidence = m.confidence;
+                tracks[ti].clips[ci].match_score = m.score;
+                tracks[ti].clips[ci].polarity_inverted = m.inverted && m.score >= CONFIDENCE_THRESHOLD;
                 clip_offsets.insert(tracks[ti].clips[ci].file_path.clone(), delay);
+                diagnostics.insert(tracks[ti].clips[ci].file_path.clone(), diag);
 
-                if conf >= CONFIDENCE_THRESHOLD {
+                if m.score >= CONFIDENCE_THRESHOLD {
                     info!(
-                        "Pass 2 improved '{}': confidence {:.1}",
-                        clip_name, conf
+                        "Pass 2 improved '{}': confidence {:.0}/100",
+                        clip_name, m.score
                     );
-                    warnings.retain(|w| !w.contains(&clip_name));
+                    let path = &tracks[ti].clips[ci].file_path;
+                    warnings.retain(|w| w.clip_id.as_ref() != Some(path));
+                }
+                if let Some(note) = note {
+                    push_warning(&mut warnings, note);
                 }
             }
         }
@@ -169,32 +587,215 @@ pub fn analyze(
 
     check_cancelled(cancel)?;
 
-    // Phase 6: Metadata fallback
-    let ref_origin = get_track_time_origin(&tracks[ref_idx]);
+    // Phase 5.5: Onset envelopes for clips the waveform couldn't place (Pass 3)
+    let onset_clips: Vec<(usize, usize)> = unplaced_clips
+        .iter()
+        .copied()
+        .filter(|&(ti, ci)| tracks[ti].clips[ci].match_score < CONFIDENCE_THRESHOLD)
+        .collect();
+    if !onset_clips.is_empty() {
+        let ref_onsets = onset::onset_envelope(&ref_gated, sr);
+        for (i, &(ti, ci)) in onset_clips.iter().enumerate() {
+            let clip_name = tracks[ti].clips[ci].name.clone();
+            progress.report(
+                ProgressPhase::Correlating,
+                phase_fraction(PASS3_SPAN, i + 1, onset_clips.len()),
+                (i + 1, onset_clips.len()),
+                &format!("Pass 3: matching onsets for '{}'...", clip_name),
+            );
+            check_cancelled(cancel)?;
+
+            let t_corr = std::time::Instant::now();
+            let target = correlation_input(
+                &tracks[ti].clips[ci].samples,
+                sr,
+                config,
+                config.band_weighting(&tracks[ti]),
+            );
+            let found = onset::match_onsets(&ref_gated, &ref_onsets, &target, sr, config, cancel);
+            check_cancelled(cancel)?;
+            tracks[ti].clips[ci].timings.correlation_s += t_corr.elapsed().as_secs_f64();
+            let Some(m) = found else {
+                continue;
+            };
+
+            let mut diag = match_diagnostics(MatchMethod::Onset, m.confidence, None);
+            measure_placement(&mut diag, &ref_audio, &tracks[ti].clips[ci], m.delay_samples);
+            if diag.overlap_s < min_shared_audio_s(&tracks[ti].clips[ci]) {
+                continue;
+            }
+            let clip = &mut tracks[ti].clips[ci];
+            clip.timeline_offset_samples = m.delay_samples;
+            clip.timeline_offset_s = m.delay_s;
+            clip.confidence = m.confidence;
+            clip.match_score = m.score;
+            clip.polarity_inverted = m.inverted;
+            clip_offsets.insert(clip.file_path.clone(), m.delay_samples);
+            diagnostics.insert(clip.file_path.clone(), diag);
+            warnings.retain(|w| w.clip_id.as_ref() != Some(&clip.file_path));
+            info!("Pass 3 placed '{}' by onsets: confidence {:.0}/100", clip_name, m.score);
+        }
+    }
+
+    check_cancelled(cancel)?;
+
+    // Phase 6: Metadata fallback, except for clips that match nothing in
+    // the session: those are left unplaced rather than guessed.
+    let unmatched: Vec<(usize, usize)> = unplaced_clips
+        .iter()
+        .copied()
+        .filter(|&(ti, ci)| tracks[ti].clips[ci].match_score < CONFIDENCE_THRESHOLD)
+        .collect();
+    let match_sets = discovery::match_sets(tracks, &unmatched, config);
+    let outsiders = discovery::outsiders(tracks, &match_sets, &similarity);
+    let mut not_in_session: Vec<String> = Vec::new();
+    for &(ti, ci) in &outsiders {
+        let clip = &mut tracks[ti].clips[ci];
+        clip.unplaced = true;
+        clip.analyzed = false;
+        clip.confidence = 0.0;
+        clip.timeline_offset_samples = 0;
+        clip.timeline_offset_s = 0.0;
+        clip_offsets.remove(&clip.file_path);
+        if let Some(diag) = diagnostics.get_mut(&clip.file_path) {
+            diag.method = MatchMethod::Unplaced;
+        }
+        warnings.retain(|w| w.clip_id.as_ref() != Some(&clip.file_path));
+        push_warning(
+            &mut warnings,
+            SyncWarning::new(
+                WarningCode::NotInSession,
+                WarningSeverity::Warning,
+                format!("'{}' matches nothing in this session; left unplaced", clip.name),
+            )
+            .for_clip(&clip.file_path),
+        );
+        info!("'{}' matches nothing in the session, leaving it unplaced", clip.name);
+        not_in_session.push(clip.file_path.clone());
+    }
     for &(ti, ci) in &unplaced_clips {
         let clip = &tracks[ti].clips[ci];
-        if clip.confidence < CONFIDENCE_THRESHOLD {
+        if clip.match_score < CONFIDENCE_THRESHOLD && !clip.unplaced {
+            let path = clip.file_path.clone();
             if let (Some(ct), Some(origin)) = (clip.creation_time, ref_origin) {
                 let time_diff = ct - origin;
                 let estimated_offset = (time_diff * sr as f64) as i64;
-                if estimated_offset >= 0 {
+                // Pre-roll lands before zero; normalizing shifts it back.
+                if estimated_offset >= 0 || config.place_pre_roll {
                     let name = clip.name.clone();
-                    let conf = clip.confidence;
+                    let score = clip.match_score;
                     tracks[ti].clips[ci].timeline_offset_samples = estimated_offset;
                     tracks[ti].clips[ci].timeline_offset_s = estimated_offset as f64 / sr as f64;
-                    clip_offsets.insert(
-                        tracks[ti].clips[ci].file_path.clone(),
-                        estimated_offset,
+                    clip_offsets.insert(path.clone(), estimated_offset);
+                    if let Some(diag) = diagnostics.get_mut(&path) {
+                        diag.method = MatchMethod::CreationTime;
+                        measure_placement(diag, &ref_audio, &tracks[ti].clips[ci], estimated_offset);
+                    }
+                    push_warning(
+                        &mut warnings,
+                        SyncWarning::new(
+                            WarningCode::MetadataFallback,
+                            WarningSeverity::Warning,
+                            format!(
+                                "'{}' placed via metadata fallback (confidence {:.0}/100)",
+                                name, score
+                            ),
+                        )
+                        .for_clip(&path),
                     );
-                    let msg = format!(
-                        "'{}' placed via metadata fallback (confidence {:.1})",
-                        name, conf
-                    );
-                    warnings.push(msg.clone());
-                    warn!("{}", msg);
+                    continue;
+                }
+                push_warning(
+                    &mut warnings,
+                    SyncWarning::new(
+                        WarningCode::BeforeReference,
+                        WarningSeverity::Warning,
+                        format!(
+                            "'{}' was recorded {:.1} s before the reference starts; left unplaced",
+                            clip.name, -time_diff
+                        ),
+                    )
+                    .for_clip(&path),
+                );
+            }
+            // Neither correlation nor metadata could place this clip.
+            for w in warnings.iter_mut() {
+                if w.code == WarningCode::LowConfidence && w.clip_id.as_ref() == Some(&path) {
+                    w.severity = WarningSeverity::Error;
+                }
+            }
+        }
+    }
+
+    for clip in tracks.iter().flat_map(|t| &t.clips).filter(|c| c.polarity_inverted && !c.unplaced) {
+        push_warning(
+            &mut warnings,
+            SyncWarning::new(
+                WarningCode::PolarityInverted,
+                WarningSeverity::Info,
+                format!("'{}' is polarity-inverted against the reference; flipped on export", clip.name),
+            )
+            .for_clip(&clip.file_path),
+        );
+    }
+
+    // Phase 6.1: Suggest remedies for clips that are still below threshold
+    let mut suggestions: Vec<ClipSuggestion> = Vec::new();
+    for &(ti, ci) in &unplaced_clips {
+        let clip = &tracks[ti].clips[ci];
+        if clip.match_score >= CONFIDENCE_THRESHOLD || clip.unplaced {
+            continue;
+        }
+        let found = suggest_for_clip(clip, &ref_audio, ref_origin, sr, config.max_offset_s);
+        if let Some(first) = found.first() {
+            for w in warnings.iter_mut() {
+                if w.clip_id.as_ref() == Some(&clip.file_path) {
+                    w.suggestion = Some(first.message.clone());
                 }
             }
         }
+        suggestions.extend(found);
+    }
+
+    // Phase 6.2: Tracks that only match each other are a separate recording;
+    // report them once as a proposed sync group rather than clip by clip.
+    let proposed_groups = discovery::propose_groups(tracks, &match_sets);
+    for group in &proposed_groups {
+        let ids = &group.clip_ids;
+        warnings.retain(|w| w.clip_id.as_ref().is_none_or(|id| !ids.contains(id)));
+        suggestions.retain(|s| !ids.contains(&s.clip_id));
+        push_warning(
+            &mut warnings,
+            SyncWarning::new(
+                WarningCode::SeparateRecording,
+                WarningSeverity::Warning,
+                format!(
+                    "{} match each other but not '{}': likely a separate recording",
+                    group.track_names.iter().map(|n| format!("'{}'", n)).collect::<Vec<_>>().join(", "),
+                    tracks[ref_idx].name
+                ),
+            ),
+        );
+    }
+
+    // Phase 6.3: Check each correlated placement with a second measurement
+    if config.verify_offsets {
+        progress.report(ProgressPhase::Verifying, PASS3_SPAN.1, (0, 0), "Verifying placements...");
+        let checked: Vec<(usize, usize)> = placed_clips
+            .iter()
+            .copied()
+            .filter(|&(ti, ci)| {
+                let clip = &tracks[ti].clips[ci];
+                // Locked and kept clips weren't correlated; clips already
+                // reported (slates, timecode, repeats) were placed on purpose.
+                !tracks[ti].is_clip_locked(ci)
+                    && !kept.contains(&clip.file_path)
+                    && !warnings.iter().any(|w| w.clip_id.as_ref() == Some(&clip.file_path))
+            })
+            .collect();
+        for warning in verify::verify_offsets(tracks, &checked, &ref_audio, config, cancel)? {
+            push_warning(&mut warnings, warning);
+        }
     }
 
     // Phase 6.5: Enforce non-overlap within each track
@@ -202,37 +803,84 @@ pub fn analyze(
     // the same track must be sequential — never overlapping.
     check_cancelled(cancel)?;
     for ti in 0..tracks.len() {
-        if ti == ref_idx {
+        if ti == ref_idx && !incremental {
             continue;
         }
-        fix_intra_track_overlaps(&mut tracks[ti], sr, &mut clip_offsets, &mut warnings);
+        fix_intra_track_overlaps(&mut tracks[ti], sr, &kept, &mut clip_offsets, &mut warnings);
     }
 
     // Phase 7: Normalize timeline
-    prog!(total_steps - 1, "Normalizing timeline...");
+    progress.report(ProgressPhase::Normalizing, DRIFT_SPAN.0, (0, 0), "Normalizing timeline...");
     check_cancelled(cancel)?;
 
     let mut min_offset: i64 = 0;
-    let mut max_end: i64 = 0;
     for track in tracks.iter() {
         for clip in &track.clips {
             min_offset = min_offset.min(clip.timeline_offset_samples);
-            max_end = max_end.max(clip.end_samples());
         }
     }
 
-    if min_offset < 0 {
-        let shift = -min_offset;
+    // With an anchor, shift so it lands back where it was; otherwise shift
+    // the earliest clip to zero. Locked clips never move.
+    let any_locked = tracks.iter().any(|t| t.has_locked_clips());
+    let anchor_shift = anchor_before.as_ref().and_then(|(path, before)| {
+        tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .find(|c| &c.file_path == path)
+            .map(|c| before - c.timeline_offset_samples)
+    });
+    let shift = match anchor_shift {
+        Some(shift) => {
+            info!("Normalizing around anchor clip (shift {} samples)", shift);
+            shift
+        }
+        None if min_offset < 0 && any_locked => {
+            push_warning(
+                &mut warnings,
+                SyncWarning::new(
+                    WarningCode::NotNormalized,
+                    WarningSeverity::Warning,
+                    "Timeline starts before zero but was not normalized: locked clips keep their positions",
+                ),
+            );
+            0
+        }
+        None => -min_offset,
+    };
+
+    if shift != 0 {
         for track in tracks.iter_mut() {
-            for clip in &mut track.clips {
-                clip.timeline_offset_samples += shift;
-                clip.timeline_offset_s = clip.timeline_offset_samples as f64 / sr as f64;
+            let track_locked = track.locked;
+            for clip in track.clips.iter_mut().filter(|c| !c.unplaced) {
+                if !(track_locked || clip.is_pinned()) {
+                    clip.timeline_offset_samples += shift;
+                    clip.timeline_offset_s += shift as f64 / sr as f64;
+                }
                 clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
             }
         }
-        max_end += shift;
+        if anchor_shift.is_some() && min_offset + shift < 0 {
+            push_warning(
+                &mut warnings,
+                SyncWarning::new(
+                    WarningCode::AnchorBeforeZero,
+                    WarningSeverity::Info,
+                    "Some clips start before zero to keep the anchor clip in place",
+                ),
+            );
+        }
     }
 
+    let max_end: i64 = tracks
+        .iter()
+        .flat_map(|t| t.clips.iter())
+        .filter(|c| !c.unplaced)
+        .map(|c| c.end_samples())
+        .max()
+        .unwrap_or(0)
+        .max(0);
+
     let avg_conf = if confidences.is_empty() {
         0.0
     } else {
@@ -240,41 +888,89 @@ pub fn analyze(
     };
 
     // Phase 8: Clock drift detection
-    prog!(total_steps - 1, "Measuring clock drift...");
+    progress.report(ProgressPhase::MeasuringDrift, DRIFT_SPAN.0, (0, 0), "Measuring clock drift...");
     check_cancelled(cancel)?;
 
-    let ref_audio_norm = build_reference_from_metadata(&mut tracks[ref_idx], sr)?;
+    // Inherited drift is re-estimated from this run's measurements.
+    for clip in tracks.iter_mut().flat_map(|t| t.clips.iter_mut()) {
+        if clip.drift_inherited && !kept.contains(&clip.file_path) {
+            clip.drift_ppm = 0.0;
+            clip.drift_confidence = 0.0;
+            clip.drift_inherited = false;
+        }
+    }
+
+    // Reference audio in normalized timeline coordinates
+    let ref_audio_norm = stitch_clips(tracks[ref_idx].clips.iter());
     let mut drift_detected = false;
+    let drift_clips = total_clips - tracks[ref_idx].clips.len();
+    let mut step = 0usize;
 
     for ti in 0..tracks.len() {
         if ti == ref_idx {
             continue;
         }
         for ci in 0..tracks[ti].clips.len() {
-            if !tracks[ti].clips[ci].analyzed {
+            step += 1;
+            let clip = &tracks[ti].clips[ci];
+            if !clip.analyzed || clip.drift_override || kept.contains(&clip.file_path) {
                 continue;
             }
             if tracks[ti].clips[ci].duration_s < MIN_DRIFT_OVERLAP_S {
                 continue;
             }
 
-            let (drift_ppm, r_sq) =
-                measure_drift(&ref_audio_norm, &tracks[ti].clips[ci], sr);
-
-            if r_sq > 0.5 && drift_ppm.abs() > config.drift_threshold_ppm {
-                tracks[ti].clips[ci].drift_ppm = drift_ppm;
-                tracks[ti].clips[ci].drift_confidence = r_sq;
+            progress.report(
+                ProgressPhase::MeasuringDrift,
+                phase_fraction(DRIFT_SPAN, step, drift_clips),
+                (step, drift_clips),
+                &format!("Measuring drift of '{}'...", tracks[ti].clips[ci].name),
+            );
+            check_cancelled(cancel)?;
+            let t_corr = std::time::Instant::now();
+            let drift = measure_drift(&ref_audio_norm, &tracks[ti].clips[ci], sr, cancel);
+            check_cancelled(cancel)?;
+            tracks[ti].clips[ci].timings.correlation_s += t_corr.elapsed().as_secs_f64();
+            tracks[ti].clips[ci].drift_windows = drift.windows;
+            tracks[ti].clips[ci].drift_segments.clear();
+
+            let peak_ppm = drift
+                .segments
+                .iter()
+                .map(|s| s.ppm.abs())
+                .fold(drift.drift_ppm.abs(), f64::max);
+            if drift.confidence > 0.5 && peak_ppm > config.drift_threshold_ppm {
+                tracks[ti].clips[ci].drift_ppm = drift.drift_ppm;
+                tracks[ti].clips[ci].drift_confidence = drift.confidence;
                 drift_detected = true;
                 info!(
-                    "Drift detected for '{}': {:.2} ppm (R²={:.3})",
-                    tracks[ti].clips[ci].name, drift_ppm, r_sq
+                    "Drift detected for '{}': {:.2} ppm (R²={:.3}, {} × {:.0} s windows{})",
+                    tracks[ti].clips[ci].name,
+                    drift.drift_ppm,
+                    drift.r_squared,
+                    drift.windows,
+                    drift.window_s,
+                    if drift.segments.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", piecewise in {} segments", drift.segments.len())
+                    }
                 );
+                tracks[ti].clips[ci].drift_segments = drift.segments;
             }
         }
     }
 
-    if drift_detected {
-        inherit_drift_for_short_clips(tracks, ref_idx);
+    if config.inherit_drift {
+        if drift_detected {
+            propagate_track_drift(tracks, ref_idx);
+        }
+        drift_detected |= estimate_session_drift(tracks, ref_idx, sr, config.drift_threshold_ppm);
+    }
+
+    // Phase 8.1: Video clips whose audio the export will stretch
+    for warning in conform_warnings(tracks, config) {
+        push_warning(&mut warnings, warning);
     }
 
     let result = SyncResult {
@@ -286,9 +982,14 @@ pub fn analyze(
         avg_confidence: avg_conf,
         drift_detected,
         warnings,
+        suggestions,
+        groups: Vec::new(),
+        proposed_groups,
+        unplaced_clips: not_in_session,
+        diagnostics,
     };
 
-    prog!(total_steps, "Analysis complete.");
+    progress.report(ProgressPhase::Done, 1.0, (0, 0), "Analysis complete.");
     info!(
         "Analysis complete: {} clips, timeline {:.1} s, avg confidence {:.1}, drift={}",
         total_clips,
@@ -300,103 +1001,114 @@ pub fn analyze(
     Ok(result)
 }
 
-/// Stitch each track into a single continuous audio array at export SR.
-pub fn sync(
+/// Re-correlate a single clip against the rest of the timeline, searching
+/// only start positions inside `[window_start_s, window_end_s]`.
+///
+/// Used to fix stragglers ("it belongs somewhere between 00:40 and 00:55")
+/// without re-running full analysis. Only this clip's placement changes.
+/// Returns the new (offset_samples, match score).
+pub fn reanalyze_clip_in_window(
     tracks: &mut [Track],
-    result: &SyncResult,
-    config: &mut SyncConfig,
-    progress: &Option<ProgressCallback>,
-    cancel: &Option<CancelToken>,
-) -> Result<()> {
-    let export_sr = match config.export_sr {
-        Some(sr) => sr,
-        None => {
-            let sr = detect_project_sample_rate(tracks);
-            config.export_sr = Some(sr);
-            sr
-        }
-    };
-
-    let total_len = (result.total_timeline_s * export_sr as f64).round() as usize;
-    let total_steps: usize = tracks.iter().map(|t| t.clip_count()).sum();
-    let mut step = 0usize;
-
-    for ti in 0..tracks.len() {
-        check_cancelled(cancel)?;
-
-        if tracks[ti].clips.is_empty() {
-            tracks[ti].synced_audio = Some(vec![0.0f64; total_len]);
-            tracks[ti].synced_channels = 1;
-            continue;
-        }
-
-        let mut output = vec![0.0f64; total_len];
-
-        for ci in 0..tracks[ti].clips.len() {
-            step += 1;
-            let clip_name = tracks[ti].clips[ci].name.clone();
-            if let Some(cb) = progress {
-                cb(step, total_steps, &format!("Stitching '{}'...", clip_name));
-            }
-            check_cancelled(cancel)?;
+    track_index: usize,
+    clip_index: usize,
+    window_start_s: f64,
+    window_end_s: f64,
+) -> Result<(i64, f64)> {
+    let clip = tracks
+        .get(track_index)
+        .and_then(|t| t.clips.get(clip_index))
+        .ok_or_else(|| anyhow!("Clip index out of range"))?;
+    if clip.samples.is_empty() {
+        return Err(anyhow!("Clip '{}' has no analysis audio", clip.name));
+    }
+    if tracks[track_index].is_clip_locked(clip_index) {
+        return Err(anyhow!("Clip '{}' is locked", clip.name));
+    }
+    let sr = ANALYSIS_SR;
+    let (lo_s, hi_s) = (window_start_s.min(window_end_s), window_start_s.max(window_end_s));
+
+    let timeline = stitch_other_tracks(tracks, track_index);
+    let start = ((lo_s.max(0.0) * sr as f64) as usize).min(timeline.len());
+    let search = ((hi_s.max(0.0) - lo_s.max(0.0)) * sr as f64) as usize;
+    let end = (start + search + clip.samples.len()).min(timeline.len());
+    let segment = &timeline[start..end];
+    if segment.iter().all(|&x| x == 0.0) {
+        return Err(anyhow!(
+            "No placed audio from other tracks between {:.1} s and {:.1} s",
+            lo_s,
+            hi_s
+        ));
+    }
 
-            // Re-read at full resolution
-            let mut audio = read_clip_full_res(&tracks[ti].clips[ci], export_sr, cancel)?;
+    // Peak search restricted to non-negative lags up to the window width.
+    let correlation = fft_correlate(segment, &clip.samples, &None);
+    let center = clip.samples.len() - 1;
+    let hi = (center + search + 1).min(correlation.len());
+    let peak_idx = (center..hi)
+        .max_by(|&a, &b| correlation[a].abs().partial_cmp(&correlation[b].abs()).unwrap())
+        .unwrap_or(center);
+    let mean: f64 = correlation.iter().map(|x| x.abs() as f64).sum::<f64>()
+        / correlation.len() as f64;
+    let confidence = correlation[peak_idx].abs() as f64 / (mean + 1e-10);
+    let score = match_score(segment, &clip.samples, &correlation, peak_idx, (center, hi), sr);
+    let offset = start as i64 + (peak_idx - center) as i64;
+    let offset_s = start as f64 / sr as f64
+        + refined_delay_s(|i| correlation[i], peak_idx, correlation.len(), center, sr);
+
+    let clip = &mut tracks[track_index].clips[clip_index];
+    clip.timeline_offset_samples = offset;
+    clip.timeline_offset_s = offset_s;
+    clip.confidence = confidence;
+    clip.match_score = score;
+    clip.polarity_inverted = correlation[peak_idx] < 0.0 && score >= CONFIDENCE_THRESHOLD;
+    clip.analyzed = true;
+    clip.unplaced = false;
+    info!(
+        "Re-analyzed '{}' in {:.1}–{:.1} s: offset {:.3} s, confidence {:.0}/100",
+        clip.name, lo_s, hi_s, clip.timeline_offset_s, score
+    );
 
-            // Apply drift correction if enabled
-            if config.drift_correction
-                && tracks[ti].clips[ci].drift_ppm.abs() >= config.drift_threshold_ppm
-                && tracks[ti].clips[ci].drift_confidence > 0.5
-            {
-                if let Some(cb) = progress {
-                    cb(
-                        step,
-                        total_steps,
-                        &format!(
-                            "Correcting drift ({:+.1} ppm) for '{}'...",
-                            tracks[ti].clips[ci].drift_ppm, clip_name
-                        ),
-                    );
-                }
-                audio = apply_drift_correction_f64(&audio, tracks[ti].clips[ci].drift_ppm);
-                tracks[ti].clips[ci].drift_corrected = true;
-                info!(
-                    "Applied drift correction {:.2} ppm to '{}'",
-                    tracks[ti].clips[ci].drift_ppm, clip_name
-                );
-            }
+    Ok((offset, score))
+}
 
-            // Convert offset from analysis SR to export SR
-            let start = tracks[ti].clips[ci].timeline_offset_at_sr(export_sr).max(0) as usize;
-            let end = (start + audio.len()).min(total_len);
-            if start >= total_len {
-                continue;
-            }
+/// Mix every placed clip outside `exclude_track`, but in its sync group, onto
+/// one 8 kHz timeline.
+fn stitch_other_tracks(tracks: &[Track], exclude_track: usize) -> Vec<f32> {
+    let group = tracks[exclude_track].sync_group_name();
+    stitch_clips(
+        tracks
+            .iter()
+            .enumerate()
+            .filter(|(ti, t)| *ti != exclude_track && t.sync_group_name() == group)
+            .flat_map(|(_, t)| t.clips.iter())
+            .filter(|c| c.on_timeline() && (c.analyzed || c.timeline_offset_samples != 0)),
+    )
+}
 
-            let seg_len = end - start;
-            for i in 0..seg_len {
-                let existing = output[start + i];
-                let new_val = audio[i];
-                if existing.abs() > 1e-10 {
-                    // Mix where both have audio
-                    output[start + i] = (existing + new_val) / 2.0;
-                } else {
-                    output[start + i] = new_val;
-                }
+/// Lay clips out at their current offsets; earlier clips win where they overlap.
+fn stitch_clips<'a>(clips: impl Iterator<Item = &'a Clip> + Clone) -> Vec<f32> {
+    let len = clips
+        .clone()
+        .map(|c| c.end_samples().max(0) as usize)
+        .max()
+        .unwrap_or(0);
+    let mut timeline = vec![0.0f32; len];
+    for c in clips {
+        let start = c.timeline_offset_samples.max(0) as usize;
+        let skip = (-c.timeline_offset_samples).max(0) as usize;
+        for (j, &s) in c.samples.iter().skip(skip).enumerate() {
+            let slot = &mut timeline[start + j];
+            if slot.abs() < 1e-10 {
+                *slot = s;
             }
         }
-
-        tracks[ti].synced_audio = Some(output);
-        tracks[ti].synced_channels = 1;
     }
-
-    info!("Sync complete: {} tracks stitched at {} Hz", tracks.len(), export_sr);
-    Ok(())
+    timeline
 }
 
 /// Auto-select reference track index.
-pub fn auto_select_reference(tracks: &[Track]) -> usize {
-    select_reference_index(tracks)
+pub fn auto_select_reference(tracks: &[Track], strategy: &ReferenceStrategy) -> usize {
+    select_reference_index(tracks, strategy)
 }
 
 // ---------------------------------------------------------------------------
@@ -404,58 +1116,163 @@ pub fn auto_select_reference(tracks: &[Track]) -> usize {
 // ---------------------------------------------------------------------------
 
 /// FFT cross-correlation to find the delay of `target` relative to `reference`.
+///
+/// Returns (delay_samples, raw peak/mean confidence); see [`match_delay`]
+/// for the calibrated score.
 pub fn compute_delay(
     reference: &[f32],
     target: &[f32],
     sr: u32,
     max_offset_s: Option<f64>,
 ) -> (i64, f64) {
-    if reference.is_empty() || target.is_empty() {
-        return (0, 0.0);
-    }
-
-    // Normalize
-    let ref_max = reference.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-    let tgt_max = target.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+    let m = match_delay(reference, target, sr, max_offset_s);
+    (m.delay_samples, m.confidence)
+}
 
-    let ref_norm: Vec<f32> = if ref_max > 1e-10 {
-        reference.iter().map(|x| x / ref_max).collect()
-    } else {
-        reference.to_vec()
-    };
-    let tgt_norm: Vec<f32> = if tgt_max > 1e-10 {
-        target.iter().map(|x| x / tgt_max).collect()
-    } else {
-        target.to_vec()
-    };
+/// A correlation match with both confidence measures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DelayMatch {
+    pub delay_samples: i64,
+    /// Delay in seconds, refined between samples by parabolic interpolation
+    /// of the peak; `delay_samples` is its nearest whole sample.
+    pub delay_s: f64,
+    /// Raw peak/mean ratio of the correlation (unbounded).
+    pub confidence: f64,
+    /// Calibrated 0–100 score, comparable with `CONFIDENCE_THRESHOLD`.
+    pub score: f64,
+    /// Normalized cross-correlation at the peak, 0–1: how alike the
+    /// overlapping audio is, however many places it matches equally well.
+    pub ncc: f64,
+    /// The peak is negative: the target's polarity is flipped against the
+    /// reference.
+    pub inverted: bool,
+}
 
-    // FFT cross-correlation (equivalent to fftconvolve(ref, tgt[::-1], mode="full"))
-    let correlation = fft_correlate(&ref_norm, &tgt_norm);
+/// Like [`compute_delay`], also scoring the match on the 0–100 scale.
+/// The correlation is normalized by overlap energy (see
+/// [`CorrelationNormalization`]).
+pub fn match_delay(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+) -> DelayMatch {
+    let normalization = CorrelationNormalization::default();
+    match_delay_candidates(reference, target, sr, max_offset_s, 1, normalization).0
+}
 
-    let n = correlation.len();
-    let center = target.len() - 1;
+/// One of the strongest correlation peaks, kept so analysis can choose
+/// between near-equal matches (repetitive music) using metadata.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DelayCandidate {
+    pub delay_samples: i64,
+    /// Sub-sample delay in seconds, as for [`DelayMatch::delay_s`].
+    pub delay_s: f64,
+    /// Peak height relative to the strongest peak (1.0 for the best).
+    pub relative_peak: f64,
+    /// Normalized cross-correlation at this lag, 0–1.
+    pub ncc: f64,
+    /// The peak is negative, as for [`DelayMatch::inverted`].
+    pub inverted: bool,
+}
 
-    let peak_idx = if let Some(max_s) = max_offset_s {
-        let max_samples = (max_s * sr as f64) as usize;
-        let lo = center.saturating_sub(max_samples);
-        let hi = (center + max_samples + 1).min(n);
-        let region = &correlation[lo..hi];
-        let local_peak = region
-            .iter()
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.abs().partial_cmp(&b.abs()).unwrap())
-            .map(|(i, _)| i)
-            .unwrap_or(0);
-        local_peak + lo
-    } else {
-        correlation
-            .iter()
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.abs().partial_cmp(&b.abs()).unwrap())
-            .map(|(i, _)| i)
-            .unwrap_or(0)
+/// Like [`match_delay`], also returning up to `count` peaks at least 0.25 s
+/// apart, strongest first. The first candidate is the match itself.
+///
+/// References of `COARSE_MIN_REFERENCE_S` or more are searched
+/// coarse-to-fine (see [`coarse_to_fine_match`]).
+pub fn match_delay_candidates(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    count: usize,
+    normalization: CorrelationNormalization,
+) -> (DelayMatch, Vec<DelayCandidate>) {
+    search_delay(reference, target, sr, max_offset_s, count, normalization, &None)
+}
+
+/// [`match_delay_candidates`] polling `cancel` as it goes. A cancelled
+/// search returns early with a meaningless match; callers check `cancel`
+/// before using it.
+fn search_delay(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    count: usize,
+    normalization: CorrelationNormalization,
+    cancel: &Option<CancelToken>,
+) -> (DelayMatch, Vec<DelayCandidate>) {
+    if reference.len() >= COARSE_MIN_REFERENCE_S * sr as usize
+        && let Some(found) =
+            coarse_to_fine_match(reference, target, sr, max_offset_s, count, normalization, cancel)
+    {
+        return found;
+    }
+    full_rate_match(reference, target, sr, max_offset_s, count, normalization, cancel)
+}
+
+/// [`match_delay_candidates`] searching every lag at the full rate.
+fn full_rate_match(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    count: usize,
+    normalization: CorrelationNormalization,
+    cancel: &Option<CancelToken>,
+) -> (DelayMatch, Vec<DelayCandidate>) {
+    if reference.is_empty() || target.is_empty() {
+        let none = DelayMatch {
+            delay_samples: 0,
+            delay_s: 0.0,
+            confidence: 0.0,
+            score: 0.0,
+            ncc: 0.0,
+            inverted: false,
+        };
+        return (none, Vec::new());
+    }
+
+    if (reference.len() + target.len() - 1).next_power_of_two() > MAX_CORRELATION_FFT_LEN {
+        return segmented_match(
+            reference,
+            target,
+            sr,
+            max_offset_s,
+            (SEGMENT_FFT_LEN, count),
+            normalization,
+            cancel,
+        );
+    }
+
+    let ref_norm = peak_normalized(reference);
+    let tgt_norm = peak_normalized(target);
+
+    // FFT cross-correlation (equivalent to fftconvolve(ref, tgt[::-1], mode="full"))
+    let mut correlation = fft_correlate(&ref_norm, &tgt_norm, cancel);
+    let normalized = normalization == CorrelationNormalization::Overlap;
+    if normalized {
+        let mut energy = OverlapEnergy::new(&ref_norm, &tgt_norm, sr);
+        for (idx, c) in correlation.iter_mut().enumerate() {
+            *c *= energy.scale(idx);
+        }
+    }
+    let ncc = |value: f32, idx: usize| {
+        if normalized {
+            (value.abs() as f64).min(1.0)
+        } else {
+            normalized_peak(&ref_norm, &tgt_norm, value, idx)
+        }
     };
 
+    let n = correlation.len();
+    let center = target.len() - 1;
+
+    let (lo, hi) = search_region(n, center, sr, max_offset_s);
+    let peak_idx = overlap_peak(&correlation, (lo, hi), reference.len(), target.len());
+
     let delay_samples = peak_idx as i64 - (target.len() as i64 - 1);
 
     // Confidence: peak / mean ratio
@@ -463,394 +1280,2635 @@ pub fn compute_delay(
     let mean_corr: f64 = abs_corr.iter().map(|&x| x as f64).sum::<f64>() / abs_corr.len() as f64;
     let confidence = abs_corr[peak_idx] as f64 / (mean_corr + 1e-10);
 
-    (delay_samples, confidence)
+    let peaks = top_peaks(
+        (lo..hi).map(|i| (i, correlation[i])),
+        (peak_idx, correlation[peak_idx]),
+        (sr / 4) as usize,
+        count,
+    );
+    let candidates = peaks
+        .into_iter()
+        .map(|(idx, v)| DelayCandidate {
+            delay_samples: idx as i64 - center as i64,
+            delay_s: refined_delay_s(|i| correlation[i], idx, n, center, sr),
+            relative_peak: (v.abs() / correlation[peak_idx].abs().max(1e-30)) as f64,
+            ncc: ncc(v, idx),
+            inverted: v < 0.0,
+        })
+        .collect();
+
+    let peak_ncc = ncc(correlation[peak_idx], peak_idx);
+    let m = DelayMatch {
+        delay_samples,
+        delay_s: refined_delay_s(|i| correlation[i], peak_idx, n, center, sr),
+        confidence,
+        score: calibrated_score(peak_ncc, runner_up_ratio(&correlation, (lo, hi), peak_idx, sr)),
+        ncc: peak_ncc,
+        inverted: correlation[peak_idx] < 0.0,
+    };
+    (m, candidates)
+}
+
+/// [`match_delay`] restricted to delays `min..=max` samples, correlating
+/// only the part of `reference` those delays reach. Runner-up peaks are
+/// looked for inside the range alone, so for a narrow range the score rates
+/// similarity rather than uniqueness.
+pub fn match_delay_between(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    (min_delay, max_delay): (i64, i64),
+) -> DelayMatch {
+    let none = DelayMatch {
+        delay_samples: 0,
+        delay_s: 0.0,
+        confidence: 0.0,
+        score: 0.0,
+        ncc: 0.0,
+        inverted: false,
+    };
+    let start = min_delay.clamp(0, reference.len() as i64) as usize;
+    let end = (max_delay + target.len() as i64)
+        .clamp(start as i64, reference.len() as i64) as usize;
+    if start == end || target.is_empty() || max_delay < min_delay {
+        return none;
+    }
+
+    let segment = peak_normalized(&reference[start..end]);
+    let tgt_norm = peak_normalized(target);
+    let correlation = fft_correlate(&segment, &tgt_norm, &None);
+    let n = correlation.len();
+    let center = target.len() - 1;
+    // Lag index k is delay `start + k - center` on the full reference.
+    let lag = |delay: i64| (delay - start as i64 + center as i64).clamp(0, n as i64) as usize;
+    let (lo, hi) = (lag(min_delay), lag(max_delay + 1));
+    if hi <= lo {
+        return none;
+    }
+
+    let peak_idx = abs_peak(&correlation[lo..hi]) + lo;
+    let mean = correlation.iter().map(|x| x.abs() as f64).sum::<f64>() / n as f64;
+    let ncc = normalized_peak(&segment, &tgt_norm, correlation[peak_idx], peak_idx);
+    DelayMatch {
+        delay_samples: start as i64 + peak_idx as i64 - center as i64,
+        delay_s: start as f64 / sr as f64
+            + refined_delay_s(|i| correlation[i], peak_idx, n, center, sr),
+        confidence: correlation[peak_idx].abs() as f64 / (mean + 1e-10),
+        score: calibrated_score(ncc, runner_up_ratio(&correlation, (lo, hi), peak_idx, sr)),
+        ncc,
+        inverted: correlation[peak_idx] < 0.0,
+    }
 }
 
-/// FFT-based cross-correlation (equivalent to scipy fftconvolve(a, b[::-1], "full")).
-fn fft_correlate(reference: &[f32], target: &[f32]) -> Vec<f32> {
-    let n = reference.len() + target.len() - 1;
-    let fft_len = n.next_power_of_two();
+/// References at least this long (seconds) are searched coarse-to-fine.
+const COARSE_MIN_REFERENCE_S: usize = 600;
 
-    let mut planner = FftPlanner::<f32>::new();
-    let fft = planner.plan_fft_forward(fft_len);
-    let ifft = planner.plan_fft_inverse(fft_len);
+/// Decimation of the coarse pass: 8 kHz analysis audio is searched at 2 kHz.
+const COARSE_DECIMATION: usize = 4;
 
-    // Pad reference
-    let mut ref_c: Vec<Complex<f32>> = reference
+/// Coarse peaks refined at the full rate. The strongest refined peak wins,
+/// so a coarse pass that ranks the true match second still finds it.
+const COARSE_CANDIDATES: usize = 4;
+
+/// Half-width (seconds) of the full-rate search around each coarse peak.
+const COARSE_WINDOW_S: f64 = 0.05;
+
+/// Find roughly where `target` sits by correlating both signals decimated
+/// to 2 kHz (a sixteenth of the FFT work), then correlate at the full rate
+/// only within a few milliseconds of the strongest coarse peaks.
+///
+/// Returns `None` when the result scores below `CONFIDENCE_THRESHOLD`; the
+/// caller then searches at the full rate, which costs the time saved but
+/// never places a clip worse than a full-rate search would.
+fn coarse_to_fine_match(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    count: usize,
+    normalization: CorrelationNormalization,
+    cancel: &Option<CancelToken>,
+) -> Option<(DelayMatch, Vec<DelayCandidate>)> {
+    // Under a second of target leaves too little to find at 2 kHz.
+    if target.len() < sr as usize {
+        return None;
+    }
+    let coarse_sr = sr / COARSE_DECIMATION as u32;
+    let (coarse, coarse_peaks) = full_rate_match(
+        &decimate(reference, COARSE_DECIMATION),
+        &decimate(target, COARSE_DECIMATION),
+        coarse_sr,
+        max_offset_s,
+        count.max(COARSE_CANDIDATES),
+        normalization,
+        cancel,
+    );
+    if is_cancelled(cancel) {
+        return None;
+    }
+
+    let window = (COARSE_WINDOW_S * sr as f64) as i64;
+    let lag_index = |delay: i64| (delay + target.len() as i64 - 1) as usize;
+    // Ranked by raw correlation or by NCC, as the coarse pass was.
+    let mut refined: Vec<(DelayMatch, f64, f64)> = coarse_peaks
         .iter()
-        .map(|&x| Complex::new(x, 0.0))
+        .map(|c| {
+            let around = c.delay_samples * COARSE_DECIMATION as i64;
+            match_delay_between(reference, target, sr, (around - window, around + window))
+        })
+        .filter(|fine| fine.confidence > 0.0)
+        .map(|fine| {
+            let idx = lag_index(fine.delay_samples);
+            let value = lag_value(reference, target, idx);
+            let ncc = normalized_peak(reference, target, value, idx);
+            let rank = match normalization {
+                CorrelationNormalization::Raw => value.abs() as f64,
+                CorrelationNormalization::Overlap => ncc,
+            };
+            (fine, rank, ncc)
+        })
         .collect();
-    ref_c.resize(fft_len, Complex::new(0.0, 0.0));
+    refined.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let &(best, best_rank, _) = refined.first()?;
 
-    // Reverse target for correlation (same as fftconvolve(ref, tgt[::-1]))
-    let mut tgt_c: Vec<Complex<f32>> = target
+    let candidates: Vec<DelayCandidate> = refined
         .iter()
-        .rev()
-        .map(|&x| Complex::new(x, 0.0))
+        .map(|&(fine, rank, ncc)| DelayCandidate {
+            delay_samples: fine.delay_samples,
+            delay_s: fine.delay_s,
+            relative_peak: if best_rank > 0.0 { rank / best_rank } else { 0.0 },
+            ncc,
+            inverted: fine.inverted,
+        })
         .collect();
-    tgt_c.resize(fft_len, Complex::new(0.0, 0.0));
+    let runner_up = candidates.iter().skip(1).map(|c| c.relative_peak).fold(0.0, f64::max);
+    let m = DelayMatch {
+        score: calibrated_score(candidates[0].ncc, runner_up),
+        confidence: coarse.confidence,
+        ncc: candidates[0].ncc,
+        ..best
+    };
+    if m.score < CONFIDENCE_THRESHOLD {
+        debug!("Coarse search found no clear match ({:.0}/100); searching at the full rate", m.score);
+        return None;
+    }
+    Some((m, candidates.into_iter().take(count).collect()))
+}
 
-    // FFT both
-    fft.process(&mut ref_c);
-    fft.process(&mut tgt_c);
+/// Average each run of `factor` samples — a crude low-pass, plenty for
+/// finding roughly where a clip belongs.
+fn decimate(samples: &[f32], factor: usize) -> Vec<f32> {
+    samples
+        .chunks(factor)
+        .map(|c| c.iter().sum::<f32>() / c.len() as f32)
+        .collect()
+}
 
-    // Multiply in frequency domain
-    let mut result: Vec<Complex<f32>> = ref_c
+/// `samples` scaled so the largest magnitude is 1 (unchanged if silent).
+fn peak_normalized(samples: &[f32]) -> Vec<f32> {
+    let max = samples.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+    if max > 1e-10 {
+        samples.iter().map(|x| x / max).collect()
+    } else {
+        samples.to_vec()
+    }
+}
+
+/// Greedily pick up to `count` strongest `(lag index, value)` points, each
+/// more than `exclusion` lags from those already picked, starting with `best`.
+fn top_peaks(
+    points: impl Iterator<Item = (usize, f32)> + Clone,
+    best: (usize, f32),
+    exclusion: usize,
+    count: usize,
+) -> Vec<(usize, f32)> {
+    let mut picked = vec![best];
+    while picked.len() < count {
+        let next = points
+            .clone()
+            .filter(|(i, _)| picked.iter().all(|(p, _)| i.abs_diff(*p) > exclusion))
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+        match next {
+            Some(p) if p.1 != 0.0 => picked.push(p),
+            _ => break,
+        }
+    }
+    picked.truncate(count);
+    picked
+}
+
+/// Largest FFT a single-pass correlation may use (64 MB per buffer). Longer
+/// reference timelines are searched block by block instead.
+const MAX_CORRELATION_FFT_LEN: usize = 1 << 23;
+
+/// Minimum block FFT length for segmented correlation; blocks grow to twice
+/// the target length when the target is longer.
+const SEGMENT_FFT_LEN: usize = 1 << 20;
+
+/// [`match_delay`] for long references: overlap-save correlation over
+/// blocks of `block_len` (or more) lags, merging the per-block peaks.
+///
+/// Memory is bounded by the block FFT, not the timeline length. Only blocks
+/// inside the `max_offset_s` search region are computed, so the raw
+/// confidence is relative to the mean over that region.
+fn segmented_match(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    (block_len, count): (usize, usize),
+    normalization: CorrelationNormalization,
+    cancel: &Option<CancelToken>,
+) -> (DelayMatch, Vec<DelayCandidate>) {
+    let center = target.len() - 1;
+    let n = reference.len() + target.len() - 1;
+    let (lo, hi) = search_region(n, center, sr, max_offset_s);
+    let exclusion = (sr / 4) as usize;
+    let normalized = normalization == CorrelationNormalization::Overlap;
+    let bucket = exclusion.max(1);
+    let energy = normalized.then(|| OverlapEnergy::new(reference, target, sr));
+    let scan = segmented_correlate(reference, target, (lo, hi), block_len, bucket, energy, cancel);
+    let ncc = |value: f32, idx: usize| {
+        if normalized {
+            (value.abs() as f64).min(1.0)
+        } else {
+            normalized_peak(reference, target, value, idx)
+        }
+    };
+
+    // Runner-up from the per-bucket maxima; a bucket whose maximum falls in
+    // the exclusion zone around the peak is skipped as a whole.
+    let runner_up = scan
+        .buckets
         .iter()
-        .zip(tgt_c.iter())
-        .map(|(a, b)| a * b)
+        .filter(|(i, _)| i.abs_diff(scan.peak_idx) > exclusion)
+        .map(|(_, v)| v.abs() as f64)
+        .fold(0.0, f64::max);
+    let peak = scan.peak.abs() as f64;
+    let runner_up = if peak > 0.0 { runner_up / peak } else { 0.0 };
+
+    let peaks = top_peaks(
+        scan.buckets.iter().copied(),
+        (scan.peak_idx, scan.peak),
+        exclusion,
+        count,
+    );
+    let candidates = peaks
+        .into_iter()
+        .map(|(idx, v)| DelayCandidate {
+            delay_samples: idx as i64 - center as i64,
+            delay_s: refined_delay_s(|i| lag_value(reference, target, i), idx, n, center, sr),
+            relative_peak: if peak > 0.0 { v.abs() as f64 / peak } else { 0.0 },
+            ncc: ncc(v, idx),
+            inverted: v < 0.0,
+        })
         .collect();
 
-    // IFFT
-    ifft.process(&mut result);
+    let m = DelayMatch {
+        delay_samples: scan.peak_idx as i64 - center as i64,
+        delay_s: refined_delay_s(
+            |i| lag_value(reference, target, i),
+            scan.peak_idx,
+            n,
+            center,
+            sr,
+        ),
+        confidence: peak / (scan.mean_abs + 1e-10),
+        score: calibrated_score(ncc(scan.peak, scan.peak_idx), runner_up),
+        ncc: ncc(scan.peak, scan.peak_idx),
+        inverted: scan.peak < 0.0,
+    };
+    (m, candidates)
+}
 
-    // Normalize and extract real part
-    let norm = 1.0 / fft_len as f32;
-    result.iter().take(n).map(|c| c.re * norm).collect()
+/// Correlation at a single lag index (as in `fft_correlate` output),
+/// computed directly.
+fn lag_value(reference: &[f32], target: &[f32], idx: usize) -> f32 {
+    let delay = idx as i64 - (target.len() as i64 - 1);
+    let start = (-delay).max(0) as usize;
+    let end = (reference.len() as i64 - delay).clamp(0, target.len() as i64) as usize;
+    (start..end)
+        .map(|i| target[i] as f64 * reference[(i as i64 + delay) as usize] as f64)
+        .sum::<f64>() as f32
 }
 
-// ---------------------------------------------------------------------------
-//  Clock drift detection
-// ---------------------------------------------------------------------------
+/// Correlation values either side of a peak used for band-limited
+/// interpolation when refining it.
+const REFINE_HALF_WIDTH: usize = 16;
 
-/// Measure clock drift of a clip relative to the reference timeline.
-pub fn measure_drift(
-    ref_timeline: &[f32],
-    clip: &Clip,
+/// Delay in seconds of the peak at lag index `peak_idx` of an `n`-lag
+/// correlation, refined between samples.
+///
+/// Parabolic interpolation alone is biased by up to ~0.1 sample on sharp
+/// peaks (a full sample at 96 kHz), so its estimate seeds a search over the
+/// windowed-sinc interpolated correlation.
+fn refined_delay_s(
+    value_at: impl Fn(usize) -> f32,
+    peak_idx: usize,
+    n: usize,
+    center: usize,
     sr: u32,
-) -> (f64, f64) {
-    let window_s = 30.0f64;
-    let stride_s = 15.0f64;
-    let win_samples = (window_s * sr as f64) as usize;
-    let stride_samples = (stride_s * sr as f64) as usize;
-
-    let clip_start = clip.timeline_offset_samples;
-    let clip_end = clip_start + clip.length_samples() as i64;
-    let ref_len = ref_timeline.len() as i64;
-
-    let overlap_start = clip_start.max(0) as usize;
-    let overlap_end = clip_end.min(ref_len) as usize;
-    let overlap_len = if overlap_end > overlap_start {
-        overlap_end - overlap_start
+) -> f64 {
+    let idx = if peak_idx == 0 || peak_idx + 1 >= n {
+        peak_idx as f64
     } else {
-        0
+        let lo = peak_idx.saturating_sub(REFINE_HALF_WIDTH);
+        let hi = (peak_idx + REFINE_HALF_WIDTH + 1).min(n);
+        let values: Vec<f32> = (lo..hi).map(&value_at).collect();
+        let rel = peak_idx - lo;
+        let parabolic = subsample_peak(&values, rel);
+        lo as f64 + refine_sinc_peak(&values, rel, parabolic)
     };
+    (idx - center as f64) / sr as f64
+}
 
-    if overlap_len < win_samples * 2 {
-        return (0.0, 0.0);
-    }
+/// Maximize the Hann-windowed sinc interpolation of `values` (in the peak's
+/// polarity) within half a sample of `start`, by golden-section search.
+fn refine_sinc_peak(values: &[f32], peak: usize, start: f64) -> f64 {
+    let sign = values[peak].signum() as f64;
+    let half = REFINE_HALF_WIDTH as f64 + 1.0;
+    let interp = |x: f64| -> f64 {
+        values
+            .iter()
+            .enumerate()
+            .map(|(k, &v)| {
+                let d = x - k as f64;
+                if d.abs() >= half {
+                    return 0.0;
+                }
+                let sinc = if d.abs() < 1e-9 {
+                    1.0
+                } else {
+                    (std::f64::consts::PI * d).sin() / (std::f64::consts::PI * d)
+                };
+                let window = 0.5 + 0.5 * (std::f64::consts::PI * d / half).cos();
+                v as f64 * sinc * window
+            })
+            .sum::<f64>()
+            * sign
+    };
 
-    let mut times: Vec<f64> = Vec::new();
-    let mut offsets: Vec<f64> = Vec::new();
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (start - 0.5, start + 0.5);
+    let mut c = b - ratio * (b - a);
+    let mut d = a + ratio * (b - a);
+    let (mut fc, mut fd) = (interp(c), interp(d));
+    for _ in 0..40 {
+        if fc > fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - ratio * (b - a);
+            fc = interp(c);
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + ratio * (b - a);
+            fd = interp(d);
+        }
+    }
+    (a + b) / 2.0
+}
 
-    let mut pos = overlap_start;
-    while pos + win_samples <= overlap_end {
-        let ref_win = &ref_timeline[pos..pos + win_samples];
+/// Overlaps shorter than this (seconds), or than half the shorter signal,
+/// aren't normalized: a handful of samples can line up by chance.
+const NCC_MIN_OVERLAP_S: f64 = 1.0;
+
+/// Energies of the `reference` and `target` samples overlapping at each lag
+/// index (as in `fft_correlate` output), for turning correlation values into
+/// normalized cross-correlation. Lag indices must be visited in increasing
+/// order; the overlap windows slide along, so no per-sample table of a long
+/// reference is kept.
+struct OverlapEnergy<'a> {
+    reference: &'a [f32],
+    target: &'a [f32],
+    ref_window: (usize, usize),
+    tgt_window: (usize, usize),
+    ref_energy: f64,
+    tgt_energy: f64,
+    min_overlap: usize,
+    /// Overlap energy below this counts as silence, not audio to compare.
+    floor: f64,
+}
 
-        let clip_local = pos as i64 - clip_start;
-        if clip_local < 0 || (clip_local as usize + win_samples) > clip.length_samples() {
-            pos += stride_samples;
-            continue;
+impl<'a> OverlapEnergy<'a> {
+    fn new(reference: &'a [f32], target: &'a [f32], sr: u32) -> Self {
+        let shorter = reference.len().min(target.len());
+        let min_overlap = (shorter / 2).min((NCC_MIN_OVERLAP_S * sr as f64) as usize).max(1);
+        let peak = |x: &[f32]| x.iter().fold(0.0f32, |m, v| m.max(v.abs())) as f64;
+        let level = SILENCE_FLOOR_RMS as f64 * peak(reference).max(peak(target));
+        OverlapEnergy {
+            reference,
+            target,
+            ref_window: (0, 0),
+            tgt_window: (target.len(), target.len()),
+            ref_energy: 0.0,
+            tgt_energy: 0.0,
+            min_overlap,
+            floor: level * level * min_overlap as f64,
         }
-        let cl = clip_local as usize;
-        let clip_win = &clip.samples[cl..cl + win_samples];
+    }
 
-        // Skip silent windows
-        let ref_energy: f32 = ref_win.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-        let clip_energy: f32 = clip_win.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-        if ref_energy < 1e-6 || clip_energy < 1e-6 {
-            pos += stride_samples;
-            continue;
+    /// `1 / sqrt(E_ref · E_tgt)` over the overlap at lag index `idx`, or 0
+    /// where the overlap is too short or silent.
+    fn scale(&mut self, idx: usize) -> f32 {
+        let (r_len, t_len) = (self.reference.len() as i64, self.target.len() as i64);
+        let delay = idx as i64 - (t_len - 1);
+        let ref_window =
+            (delay.clamp(0, r_len) as usize, (delay + t_len).clamp(0, r_len) as usize);
+        let tgt_window =
+            ((-delay).clamp(0, t_len) as usize, (r_len - delay).clamp(0, t_len) as usize);
+
+        // The reference window only moves right and the target window only
+        // left as the lag grows.
+        let sq = |x: &[f32]| x.iter().map(|&v| v as f64 * v as f64).sum::<f64>();
+        let (old, new) = (self.ref_window, ref_window);
+        if new.0 >= old.1 {
+            self.ref_energy = sq(&self.reference[new.0..new.1]);
+        } else {
+            self.ref_energy +=
+                sq(&self.reference[old.1..new.1]) - sq(&self.reference[old.0..new.0]);
         }
+        let (old, new) = (self.tgt_window, tgt_window);
+        if new.1 <= old.0 {
+            self.tgt_energy = sq(&self.target[new.0..new.1]);
+        } else {
+            self.tgt_energy += sq(&self.target[new.0..old.0]) - sq(&self.target[new.1..old.1]);
+        }
+        self.ref_window = ref_window;
+        self.tgt_window = tgt_window;
 
-        let offset = windowed_offset(ref_win, clip_win);
-        let time_s = (pos - overlap_start) as f64 / sr as f64;
-        times.push(time_s);
-        offsets.push(offset);
-
-        pos += stride_samples;
+        let overlap = ref_window.1 - ref_window.0;
+        let (e_ref, e_tgt) = (self.ref_energy.max(0.0), self.tgt_energy.max(0.0));
+        if overlap < self.min_overlap || e_ref < self.floor || e_tgt < self.floor {
+            0.0
+        } else {
+            (1.0 / (e_ref * e_tgt).sqrt()) as f32
+        }
     }
+}
 
-    if times.len() < MIN_DRIFT_WINDOWS {
-        return (0.0, 0.0);
-    }
+/// Summary of a correlation computed block by block.
+struct SegmentedScan {
+    /// Lag index (as in `fft_correlate` output) and value of the peak.
+    peak_idx: usize,
+    peak: f32,
+    /// Largest-magnitude lag index and value per `bucket` lags of the region.
+    buckets: Vec<(usize, f32)>,
+    /// Mean |correlation| over the region.
+    mean_abs: f64,
+}
 
-    // Linear regression: offset = slope * time + intercept
-    let n = times.len() as f64;
-    let sum_t: f64 = times.iter().sum();
-    let sum_o: f64 = offsets.iter().sum();
-    let sum_tt: f64 = times.iter().map(|t| t * t).sum();
-    let sum_to: f64 = times.iter().zip(offsets.iter()).map(|(t, o)| t * o).sum();
+/// Overlap-save cross-correlation of `target` against `reference` over lag
+/// indices `[lo, hi)`, keeping only the peak, per-bucket maxima and mean.
+/// With `energy`, each lag is divided by its overlap energy (NCC).
+///
+/// `cancel` is polled before each block; a cancelled scan stops there.
+fn segmented_correlate(
+    reference: &[f32],
+    target: &[f32],
+    (lo, hi): (usize, usize),
+    block_len: usize,
+    bucket: usize,
+    mut energy: Option<OverlapEnergy>,
+    cancel: &Option<CancelToken>,
+) -> SegmentedScan {
+    let t_len = target.len();
+    let fft_len = block_len.max(2 * t_len).next_power_of_two();
+    // Lags per block whose circular correlation does not wrap.
+    let step = fft_len - t_len + 1;
 
-    let denom = n * sum_tt - sum_t * sum_t;
-    if denom.abs() < 1e-30 {
-        return (0.0, 0.0);
-    }
+    let (fft, ifft) = fft_plans(fft_len);
+    let mut scratch = vec![Complex::new(0.0f32, 0.0); fft.get_scratch_len().max(ifft.get_scratch_len())];
 
-    let slope = (n * sum_to - sum_t * sum_o) / denom;
-    let intercept = (sum_o - slope * sum_t) / n;
+    let mut tgt_spec = fft.make_output_vec();
+    let mut padded = fft.make_input_vec();
+    padded[..t_len].copy_from_slice(target);
+    fft.process_with_scratch(&mut padded, &mut tgt_spec, &mut scratch)
+        .expect("buffers sized by the planner");
 
-    // R-squared
-    let mean_o = sum_o / n;
-    let ss_res: f64 = times
-        .iter()
-        .zip(offsets.iter())
-        .map(|(t, o)| {
-            let predicted = slope * t + intercept;
-            (o - predicted).powi(2)
-        })
-        .sum();
-    let ss_tot: f64 = offsets.iter().map(|o| (o - mean_o).powi(2)).sum();
-    let r_squared = (1.0 - ss_res / (ss_tot + 1e-30)).clamp(0.0, 1.0);
+    let norm = 1.0 / fft_len as f32;
+    let mut block = fft.make_input_vec();
+    let mut spec = fft.make_output_vec();
+    let mut out = ifft.make_output_vec();
+    let mut scan = SegmentedScan {
+        peak_idx: lo,
+        peak: 0.0,
+        buckets: vec![(lo, 0.0); (hi - lo).div_ceil(bucket)],
+        mean_abs: 0.0,
+    };
+    let mut sum_abs = 0.0f64;
 
-    // Convert slope (samples/second at analysis SR) to ppm
-    let drift_ppm = (slope / sr as f64) * 1e6;
+    for start in (lo..hi).step_by(step) {
+        if is_cancelled(cancel) {
+            break;
+        }
+        // Lag index k correlates target[i] with reference[i + k - (t_len - 1)].
+        let ref_start = start as i64 - (t_len as i64 - 1);
+        for (j, slot) in block.iter_mut().enumerate() {
+            let r = ref_start + j as i64;
+            *slot = if r >= 0 && (r as usize) < reference.len() {
+                reference[r as usize]
+            } else {
+                0.0
+            };
+        }
+        fft.process_with_scratch(&mut block, &mut spec, &mut scratch)
+            .expect("buffers sized by the planner");
+        for (b, t) in spec.iter_mut().zip(&tgt_spec) {
+            *b *= t.conj();
+        }
+        inverse_real(&*ifft, &mut spec, &mut out, &mut scratch);
+
+        for (j, &c) in out.iter().take(step.min(hi - start)).enumerate() {
+            let k = start + j;
+            let v = c * norm * energy.as_mut().map_or(1.0, |e| e.scale(k));
+            sum_abs += v.abs() as f64;
+            if v.abs() > scan.peak.abs() {
+                scan.peak = v;
+                scan.peak_idx = k;
+            }
+            let slot = &mut scan.buckets[(k - lo) / bucket];
+            if v.abs() > slot.1.abs() {
+                *slot = (k, v);
+            }
+        }
+    }
 
-    (drift_ppm, r_squared)
+    scan.mean_abs = sum_abs / (hi - lo).max(1) as f64;
+    scan
 }
 
-/// Sub-sample cross-correlation offset for a single window pair.
-fn windowed_offset(ref_segment: &[f32], clip_segment: &[f32]) -> f64 {
-    // Normalize
-    let ref_max = ref_segment.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-    let tgt_max = clip_segment.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+/// Normalized cross-correlation at which a match scores full marks; real
+/// recordings from different devices rarely correlate better than this.
+const NCC_FULL_SCORE: f64 = 0.5;
+
+/// Calibrated 0–100 match score from the normalized cross-correlation at the
+/// peak (how alike the overlapping audio is) and the runner-up peak ratio
+/// (how unambiguous the match is).
+pub fn calibrated_score(ncc: f64, runner_up_ratio: f64) -> f64 {
+    let similarity = (ncc / NCC_FULL_SCORE).clamp(0.0, 1.0).sqrt();
+    let distinctness = (1.0 - runner_up_ratio).clamp(0.0, 1.0);
+    100.0 * similarity * distinctness
+}
 
-    let r: Vec<f32> = if ref_max > 1e-10 {
-        ref_segment.iter().map(|x| x / ref_max).collect()
-    } else {
-        ref_segment.to_vec()
-    };
-    let t: Vec<f32> = if tgt_max > 1e-10 {
-        clip_segment.iter().map(|x| x / tgt_max).collect()
+/// Score the peak at `peak_idx` of `correlation = fft_correlate(reference,
+/// target)`, looking for runner-up peaks within `region`.
+fn match_score(
+    reference: &[f32],
+    target: &[f32],
+    correlation: &[f32],
+    peak_idx: usize,
+    region: (usize, usize),
+    sr: u32,
+) -> f64 {
+    let ncc = normalized_peak(reference, target, correlation[peak_idx], peak_idx);
+    calibrated_score(ncc, runner_up_ratio(correlation, region, peak_idx, sr))
+}
+
+/// Correlation value at lag index `peak_idx` divided by the energy of the
+/// two overlapping segments, giving a 0–1 similarity.
+fn normalized_peak(reference: &[f32], target: &[f32], value: f32, peak_idx: usize) -> f64 {
+    let delay = peak_idx as i64 - (target.len() as i64 - 1);
+    let ref_start = delay.max(0) as usize;
+    let ref_end = (delay + target.len() as i64).clamp(0, reference.len() as i64) as usize;
+    if ref_end <= ref_start {
+        return 0.0;
+    }
+    let tgt_start = (ref_start as i64 - delay) as usize;
+    let tgt_end = (ref_end as i64 - delay) as usize;
+    let energy = |x: &[f32]| x.iter().map(|&v| v as f64 * v as f64).sum::<f64>();
+    let denom = (energy(&reference[ref_start..ref_end]) * energy(&target[tgt_start..tgt_end])).sqrt();
+    if denom > 1e-12 {
+        (value.abs() as f64 / denom).min(1.0)
     } else {
-        clip_segment.to_vec()
-    };
+        0.0
+    }
+}
 
-    let corr = fft_correlate(&r, &t);
-    let abs_corr: Vec<f32> = corr.iter().map(|x| x.abs()).collect();
-    let peak_idx = abs_corr
+/// Strongest correlation in `[lo, hi)` more than 0.25 s from `peak_idx`,
+/// relative to the peak.
+fn runner_up_ratio(correlation: &[f32], (lo, hi): (usize, usize), peak_idx: usize, sr: u32) -> f64 {
+    let peak = correlation[peak_idx].abs() as f64;
+    let exclusion = (sr / 4) as usize;
+    let runner_up = correlation[lo..hi]
         .iter()
         .enumerate()
-        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-        .map(|(i, _)| i)
-        .unwrap_or(0);
-
-    // Sub-sample precision via parabolic interpolation
-    let refined = subsample_peak(&abs_corr, peak_idx);
-    refined - (t.len() as f64 - 1.0)
+        .filter(|(i, _)| (i + lo).abs_diff(peak_idx) > exclusion)
+        .map(|(_, v)| v.abs() as f64)
+        .fold(0.0, f64::max);
+    if peak > 0.0 { runner_up / peak } else { 0.0 }
 }
 
-/// Parabolic interpolation around peak for sub-sample precision.
-fn subsample_peak(correlation: &[f32], peak_idx: usize) -> f64 {
-    let n = correlation.len();
-    if peak_idx == 0 || peak_idx >= n - 1 {
-        return peak_idx as f64;
+/// Correlation lags `[lo, hi)` allowed by `max_offset_s` around zero delay.
+fn search_region(n: usize, center: usize, sr: u32, max_offset_s: Option<f64>) -> (usize, usize) {
+    match max_offset_s {
+        Some(max_s) => {
+            let max_samples = (max_s * sr as f64) as usize;
+            (center.saturating_sub(max_samples), (center + max_samples + 1).min(n))
+        }
+        None => (0, n),
     }
+}
 
-    let alpha = correlation[peak_idx - 1] as f64;
-    let beta = correlation[peak_idx] as f64;
-    let gamma = correlation[peak_idx + 1] as f64;
+/// Index of the largest-magnitude value.
+fn abs_peak(values: &[f32]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.abs().partial_cmp(&b.abs()).unwrap())
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
 
-    let denom = alpha - 2.0 * beta + gamma;
-    if denom.abs() < 1e-30 {
-        return peak_idx as f64;
-    }
+/// Relative difference below which two correlation peaks are a tie: FFT
+/// implementations round differently in the last bits.
+const PEAK_TIE_TOLERANCE: f32 = 1e-5;
+
+/// [`abs_peak`] over lag indices `[lo, hi)`, breaking ties in favour of the
+/// lag where `t_len` target samples overlap the most of the reference — for
+/// periodic audio, which matches equally well a period away, the fullest
+/// alignment.
+fn overlap_peak(correlation: &[f32], (lo, hi): (usize, usize), ref_len: usize, t_len: usize) -> usize {
+    let best = correlation[abs_peak(&correlation[lo..hi]) + lo].abs();
+    let overlap = |idx: usize| {
+        let delay = idx as i64 - (t_len as i64 - 1);
+        (delay + t_len as i64).min(ref_len as i64) - delay.max(0)
+    };
+    (lo..hi)
+        .filter(|&i| correlation[i].abs() >= best * (1.0 - PEAK_TIE_TOLERANCE))
+        .max_by_key(|&i| overlap(i))
+        .unwrap_or(lo)
+}
 
-    let adjustment = 0.5 * (alpha - gamma) / denom;
-    peak_idx as f64 + adjustment
+/// Shape of the correlation peak, used to explain a poor match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeakDiagnostics {
+    pub delay_samples: i64,
+    /// Delay (seconds) of a much stronger peak outside the `max_offset_s`
+    /// window, if there is one.
+    pub stronger_outside_s: Option<f64>,
+    /// Strongest peak more than 0.25 s away, relative to the best peak.
+    pub runner_up_ratio: f64,
 }
 
-/// Apply drift correction by resampling.
-pub fn apply_drift_correction(audio: &[f32], drift_ppm: f64) -> Vec<f32> {
-    if drift_ppm.abs() < 1e-6 {
-        return audio.to_vec();
+/// Correlate like `compute_delay` and describe the resulting peak.
+pub fn peak_diagnostics(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+) -> PeakDiagnostics {
+    if reference.is_empty() || target.is_empty() {
+        return PeakDiagnostics {
+            delay_samples: 0,
+            stronger_outside_s: None,
+            runner_up_ratio: 0.0,
+        };
     }
 
-    let original_len = audio.len();
-    let corrected_len = (original_len as f64 / (1.0 + drift_ppm * 1e-6)).round() as usize;
+    let correlation = fft_correlate(reference, target, &None);
+    let center = target.len() - 1;
+    let (lo, hi) = search_region(correlation.len(), center, sr, max_offset_s);
+    let peak_idx = abs_peak(&correlation[lo..hi]) + lo;
+    let peak = correlation[peak_idx].abs() as f64;
+
+    let global_idx = abs_peak(&correlation);
+    let stronger_outside_s = (!(lo..hi).contains(&global_idx)
+        && correlation[global_idx].abs() as f64 >= 1.5 * peak)
+        .then(|| (global_idx as i64 - center as i64) as f64 / sr as f64);
+
+    PeakDiagnostics {
+        delay_samples: peak_idx as i64 - center as i64,
+        stronger_outside_s,
+        runner_up_ratio: runner_up_ratio(&correlation, (lo, hi), peak_idx, sr),
+    }
+}
 
-    if corrected_len == original_len || corrected_len < 1 {
-        return audio.to_vec();
+/// Compare two clips and return the match together with the correlation
+/// curve (at most `curve_points` points) and overlap statistics.
+///
+/// Uses the same peak and confidence measure as [`compute_delay`].
+pub fn compare_clips(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    curve_points: usize,
+) -> ClipComparison {
+    let reference_duration_s = reference.len() as f64 / sr as f64;
+    let target_duration_s = target.len() as f64 / sr as f64;
+    if reference.is_empty() || target.is_empty() {
+        return ClipComparison {
+            delay_samples: 0,
+            delay_s: 0.0,
+            confidence: 0.0,
+            score: 0.0,
+            runner_up_ratio: 0.0,
+            curve: Vec::new(),
+            overlap: OverlapStats {
+                reference_duration_s,
+                target_duration_s,
+                overlap_s: 0.0,
+                overlap_ratio: 0.0,
+            },
+        };
     }
 
-    // Simple linear interpolation resampling
-    let ratio = original_len as f64 / corrected_len as f64;
-    let mut result = Vec::with_capacity(corrected_len);
-    for i in 0..corrected_len {
-        let pos = i as f64 * ratio;
-        let idx = pos as usize;
-        let frac = (pos - idx as f64) as f32;
-        if idx + 1 < original_len {
-            result.push(audio[idx] * (1.0 - frac) + audio[idx + 1] * frac);
-        } else if idx < original_len {
-            result.push(audio[idx]);
-        }
+    let mut correlation = fft_correlate(reference, target, &None);
+    let mut energy = OverlapEnergy::new(reference, target, sr);
+    for (idx, c) in correlation.iter_mut().enumerate() {
+        *c *= energy.scale(idx);
+    }
+    let center = target.len() - 1;
+    let (lo, hi) = search_region(correlation.len(), center, sr, max_offset_s);
+    let peak_idx = abs_peak(&correlation[lo..hi]) + lo;
+    let peak = correlation[peak_idx].abs();
+    let delay_samples = peak_idx as i64 - center as i64;
+
+    let mean_corr =
+        correlation.iter().map(|x| x.abs() as f64).sum::<f64>() / correlation.len() as f64;
+    let confidence = peak as f64 / (mean_corr + 1e-10);
+    let runner_up = runner_up_ratio(&correlation, (lo, hi), peak_idx, sr);
+    let ncc = (peak as f64).min(1.0);
+
+    let bucket = (hi - lo).div_ceil(curve_points.max(1));
+    let scale = if peak > 0.0 { 1.0 / peak } else { 0.0 };
+    let curve = (lo..hi)
+        .step_by(bucket)
+        .map(|start| {
+            let values = &correlation[start..(start + bucket).min(hi)];
+            let idx = abs_peak(values) + start;
+            CorrelationPoint {
+                lag_s: (idx as i64 - center as i64) as f64 / sr as f64,
+                value: correlation[idx].abs() * scale,
+            }
+        })
+        .collect();
+
+    let overlap_start = delay_samples.max(0);
+    let overlap_end = (delay_samples + target.len() as i64).min(reference.len() as i64);
+    let overlap_s = (overlap_end - overlap_start).max(0) as f64 / sr as f64;
+
+    ClipComparison {
+        delay_samples,
+        delay_s: refined_delay_s(|i| correlation[i], peak_idx, correlation.len(), center, sr),
+        confidence,
+        score: calibrated_score(ncc, runner_up),
+        runner_up_ratio: runner_up,
+        curve,
+        overlap: OverlapStats {
+            reference_duration_s,
+            target_duration_s,
+            overlap_s,
+            overlap_ratio: overlap_s / reference_duration_s.min(target_duration_s),
+        },
     }
-    result
 }
 
-fn apply_drift_correction_f64(audio: &[f64], drift_ppm: f64) -> Vec<f64> {
-    if drift_ppm.abs() < 1e-6 {
-        return audio.to_vec();
+/// FFT-based cross-correlation (equivalent to scipy fftconvolve(a, b[::-1], "full")),
+/// on the installed [`CorrelationBackend`] if there is one.
+///
+/// A full-length transform of a long timeline takes seconds, so `cancel` is
+/// polled around the transforms; a cancelled call returns all zeros.
+fn fft_correlate(reference: &[f32], target: &[f32], cancel: &Option<CancelToken>) -> Vec<f32> {
+    let n = reference.len() + target.len() - 1;
+    if is_cancelled(cancel) {
+        return vec![0.0; n];
     }
+    if let Some(mut offloaded) = fft_backend::offload(&[(reference, target)]) {
+        return offloaded.remove(0);
+    }
+    cpu_correlate(reference, target, cancel)
+}
 
-    let original_len = audio.len();
-    let corrected_len = (original_len as f64 / (1.0 + drift_ppm * 1e-6)).round() as usize;
+/// Correlate each `(reference, target)` pair like `fft_correlate`, handing
+/// the whole batch to the installed [`CorrelationBackend`] at once (a GPU
+/// pays its transfer and launch costs per batch, not per pair). A cancelled
+/// batch returns all zeros for the pairs not yet done.
+pub fn correlate_batch(pairs: &[(&[f32], &[f32])], cancel: &Option<CancelToken>) -> Vec<Vec<f32>> {
+    if !is_cancelled(cancel)
+        && let Some(offloaded) = fft_backend::offload(pairs)
+    {
+        return offloaded;
+    }
+    pairs.iter().map(|(r, t)| cpu_correlate(r, t, cancel)).collect()
+}
 
-    if corrected_len == original_len || corrected_len < 1 {
-        return audio.to_vec();
+/// Forward and inverse real FFT plans of length `len`. Planning costs more
+/// than running a small transform, so plans are made once per length and
+/// shared by every correlation and thread; lengths are powers of two, which
+/// keeps the cache small.
+fn fft_plans(len: usize) -> (Arc<dyn RealToComplex<f32>>, Arc<dyn ComplexToReal<f32>>) {
+    static PLANNER: LazyLock<Mutex<RealFftPlanner<f32>>> =
+        LazyLock::new(|| Mutex::new(RealFftPlanner::new()));
+    let mut planner = PLANNER.lock().unwrap_or_else(|e| e.into_inner());
+    (planner.plan_fft_forward(len), planner.plan_fft_inverse(len))
+}
+
+/// [`fft_correlate`] with realfft.
+fn cpu_correlate(reference: &[f32], target: &[f32], cancel: &Option<CancelToken>) -> Vec<f32> {
+    let n = reference.len() + target.len() - 1;
+    if is_cancelled(cancel) {
+        return vec![0.0; n];
     }
+    let fft_len = n.next_power_of_two();
 
-    let ratio = original_len as f64 / corrected_len as f64;
-    let mut result = Vec::with_capacity(corrected_len);
-    for i in 0..corrected_len {
-        let pos = i as f64 * ratio;
-        let idx = pos as usize;
-        let frac = pos - idx as f64;
-        if idx + 1 < original_len {
-            result.push(audio[idx] * (1.0 - frac) + audio[idx + 1] * frac);
-        } else if idx < original_len {
-            result.push(audio[idx]);
-        }
+    // Real-to-complex transforms: half the work and memory of complex FFTs
+    // on zero-imaginary input, with the same result.
+    let (fft, ifft) = fft_plans(fft_len);
+    let mut scratch = vec![Complex::new(0.0f32, 0.0); fft.get_scratch_len().max(ifft.get_scratch_len())];
+
+    // Pad reference
+    let mut ref_in = fft.make_input_vec();
+    ref_in[..reference.len()].copy_from_slice(reference);
+    let mut ref_spec = fft.make_output_vec();
+
+    // Reverse target for correlation (same as fftconvolve(ref, tgt[::-1]))
+    let mut tgt_in = fft.make_input_vec();
+    for (slot, &x) in tgt_in.iter_mut().zip(target.iter().rev()) {
+        *slot = x;
+    }
+    let mut tgt_spec = fft.make_output_vec();
+
+    // FFT both
+    fft.process_with_scratch(&mut ref_in, &mut ref_spec, &mut scratch)
+        .expect("buffers sized by the planner");
+    if is_cancelled(cancel) {
+        return vec![0.0; n];
+    }
+    fft.process_with_scratch(&mut tgt_in, &mut tgt_spec, &mut scratch)
+        .expect("buffers sized by the planner");
+    if is_cancelled(cancel) {
+        return vec![0.0; n];
+    }
+
+    // Multiply in frequency domain
+    for (a, b) in ref_spec.iter_mut().zip(&tgt_spec) {
+        *a *= b;
     }
+
+    // IFFT
+    let mut result = ifft.make_output_vec();
+    inverse_real(&*ifft, &mut ref_spec, &mut result, &mut scratch);
+
+    // Normalize
+    let norm = 1.0 / fft_len as f32;
+    result.truncate(n);
+    result.iter_mut().for_each(|x| *x *= norm);
     result
 }
 
+/// Inverse real FFT of a product of real-signal spectra. The DC and Nyquist
+/// bins are real in theory; rounding can leave a sign-flipped zero or a
+/// tiny residue there, which realfft would reject.
+fn inverse_real(
+    ifft: &dyn ComplexToReal<f32>,
+    spectrum: &mut [Complex<f32>],
+    output: &mut [f32],
+    scratch: &mut [Complex<f32>],
+) {
+    spectrum[0].im = 0.0;
+    if ifft.len() % 2 == 0
+        && let Some(last) = spectrum.last_mut()
+    {
+        last.im = 0.0;
+    }
+    ifft.process_with_scratch(spectrum, output, scratch)
+        .expect("buffers sized by the planner");
+}
+
 // ---------------------------------------------------------------------------
-//  Internal helpers
+//  Clock drift detection
 // ---------------------------------------------------------------------------
 
-fn select_reference_index(tracks: &[Track]) -> usize {
-    // Check for user override
-    for (i, t) in tracks.iter().enumerate() {
-        if t.is_reference {
-            return i;
+/// Result of a drift measurement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DriftMeasurement {
+    pub drift_ppm: f64,
+    /// Goodness of the linear fit of offset against time.
+    pub r_squared: f64,
+    /// `r_squared` scaled down for shorter windows, whose per-window offsets
+    /// are noisier. Equal to `r_squared` at `MAX_DRIFT_WINDOW_S`.
+    pub confidence: f64,
+    /// Windows measured.
+    pub windows: usize,
+    /// Windows consistent with the fitted line; the rest were rejected as
+    /// outliers (noise bursts, dropouts).
+    pub inliers: usize,
+    pub window_s: f64,
+    /// Piecewise model (clip-local breakpoints) when the clock rate changed
+    /// measurably during the take; empty when one rate explains it.
+    pub segments: Vec<DriftSegment>,
+}
+
+/// Window length for an overlap of `overlap_s`: a quarter of the overlap,
+/// clamped to `[MIN_DRIFT_WINDOW_S, MAX_DRIFT_WINDOW_S]`.
+fn drift_window_s(overlap_s: f64) -> f64 {
+    (overlap_s / 4.0).clamp(MIN_DRIFT_WINDOW_S, MAX_DRIFT_WINDOW_S)
+}
+
+/// Measure clock drift of a clip relative to the reference timeline.
+///
+/// Windows adapt to the overlap (stride is half a window); overlaps shorter
+/// than `MIN_DRIFT_OVERLAP_S` return an empty measurement. `cancel` is
+/// polled per window; a cancelled measurement stops early and is discarded.
+pub fn measure_drift(
+    ref_timeline: &[f32],
+    clip: &Clip,
+    sr: u32,
+    cancel: &Option<CancelToken>,
+) -> DriftMeasurement {
+    let clip_start = clip.timeline_offset_samples;
+    let clip_end = clip_start + clip.length_samples() as i64;
+    let ref_len = ref_timeline.len() as i64;
+
+    let overlap_start = clip_start.max(0) as usize;
+    let overlap_end = clip_end.min(ref_len) as usize;
+    let overlap_len = if overlap_end > overlap_start {
+        overlap_end - overlap_start
+    } else {
+        0
+    };
+
+    let overlap_s = overlap_len as f64 / sr as f64;
+    if overlap_s < MIN_DRIFT_OVERLAP_S {
+        return DriftMeasurement::default();
+    }
+    let window_s = drift_window_s(overlap_s);
+    let win_samples = (window_s * sr as f64) as usize;
+    let stride_samples = win_samples / 2;
+
+    // Find the windows with audio on both sides first, then correlate them
+    // in batches.
+    let mut times: Vec<f64> = Vec::new();
+    let mut spans: Vec<(usize, usize)> = Vec::new(); // (ref start, clip start)
+
+    let mut pos = overlap_start;
+    while pos + win_samples <= overlap_end {
+        let ref_win = &ref_timeline[pos..pos + win_samples];
+
+        let clip_local = pos as i64 - clip_start;
+        if clip_local < 0 || (clip_local as usize + win_samples) > clip.length_samples() {
+            pos += stride_samples;
+            continue;
+        }
+        let cl = clip_local as usize;
+        let clip_win = &clip.samples[cl..cl + win_samples];
+
+        // Skip silent windows
+        let ref_energy: f32 = ref_win.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+        let clip_energy: f32 = clip_win.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+        if ref_energy < 1e-6 || clip_energy < 1e-6 {
+            pos += stride_samples;
+            continue;
+        }
+
+        times.push((pos - overlap_start) as f64 / sr as f64);
+        spans.push((pos, cl));
+
+        pos += stride_samples;
+    }
+
+    let mut offsets: Vec<f64> = Vec::with_capacity(spans.len());
+    for batch in spans.chunks(DRIFT_BATCH_WINDOWS) {
+        if is_cancelled(cancel) {
+            return DriftMeasurement::default();
+        }
+        let windows: Vec<(Vec<f32>, Vec<f32>)> = batch
+            .iter()
+            .map(|&(r, c)| {
+                (
+                    peak_normalized(&ref_timeline[r..r + win_samples]),
+                    peak_normalized(&clip.samples[c..c + win_samples]),
+                )
+            })
+            .collect();
+        let pairs: Vec<(&[f32], &[f32])> = windows.iter().map(|(r, t)| (&r[..], &t[..])).collect();
+        for corr in correlate_batch(&pairs, cancel) {
+            offsets.push(window_peak_offset(&corr, win_samples));
         }
     }
+    if is_cancelled(cancel) {
+        return DriftMeasurement::default();
+    }
 
-    // Try metadata-based coverage span
-    let mut best_idx = 0;
-    let mut best_span = 0.0f64;
-    for (i, t) in tracks.iter().enumerate() {
-        let span = get_coverage_span(t);
-        if span > best_span {
-            best_span = span;
-            best_idx = i;
+    if times.len() < MIN_DRIFT_WINDOWS {
+        return DriftMeasurement::default();
+    }
+
+    let Some(fit) = fit_drift(&times, &offsets) else {
+        // Windows were measured but too few agree on a line.
+        return DriftMeasurement {
+            windows: times.len(),
+            window_s,
+            ..Default::default()
+        };
+    };
+
+    // Convert slope (samples/second at analysis SR) to ppm
+    let drift_ppm = (fit.slope / sr as f64) * 1e6;
+
+    // Offsets average over a window, so a window stands for its centre;
+    // breakpoints move from overlap time to clip-local time.
+    let local_shift = (overlap_start as i64 - clip_start) as f64 / sr as f64 + window_s / 2.0;
+    let (r_squared, inliers, segments) = match fit_piecewise_drift(&times, &offsets) {
+        Some(pw) => {
+            let segments = pw
+                .slopes
+                .iter()
+                .enumerate()
+                .map(|(k, slope)| DriftSegment {
+                    start_s: if k == 0 { 0.0 } else { pw.breakpoints[k - 1] + local_shift },
+                    ppm: slope / sr as f64 * 1e6,
+                })
+                .collect();
+            (pw.r_squared, pw.inliers, segments)
         }
+        None => (fit.r_squared, fit.inliers, Vec::new()),
+    };
+    let inlier_fraction = inliers as f64 / times.len() as f64;
+
+    DriftMeasurement {
+        drift_ppm,
+        r_squared,
+        confidence: r_squared * inlier_fraction * (window_s / MAX_DRIFT_WINDOW_S).sqrt(),
+        windows: times.len(),
+        inliers,
+        window_s,
+        segments,
     }
+}
 
-    // Fallback: longest total duration
-    if best_span <= 0.0 {
-        let mut best_dur = 0.0f64;
-        for (i, t) in tracks.iter().enumerate() {
-            let dur = t.total_duration_s();
-            if dur > best_dur {
-                best_dur = dur;
-                best_idx = i;
+/// Robust straight-line fit of window offsets against time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DriftFit {
+    /// Samples per second.
+    slope: f64,
+    intercept: f64,
+    /// R² over the inliers only.
+    r_squared: f64,
+    inliers: usize,
+}
+
+/// Smallest residual (samples) still counted as an inlier; keeps a perfect
+/// fit (MAD of zero) from rejecting sub-sample jitter.
+const MIN_INLIER_TOLERANCE: f64 = 0.25;
+
+/// Theil–Sen fit: the slope is the median of all pairwise slopes, so a few
+/// windows hit by noise bursts cannot drag it. Windows further than 3 MADs
+/// from the line are outliers and excluded from R².
+fn fit_drift(times: &[f64], offsets: &[f64]) -> Option<DriftFit> {
+    let mut slopes = Vec::with_capacity(times.len() * times.len() / 2);
+    for i in 0..times.len() {
+        for j in i + 1..times.len() {
+            let dt = times[j] - times[i];
+            if dt.abs() > 1e-12 {
+                slopes.push((offsets[j] - offsets[i]) / dt);
             }
         }
     }
+    let slope = median(&mut slopes)?;
+    let mut intercepts: Vec<f64> = times
+        .iter()
+        .zip(offsets)
+        .map(|(t, o)| o - slope * t)
+        .collect();
+    let intercept = median(&mut intercepts)?;
 
-    best_idx
-}
+    let residuals: Vec<f64> = times
+        .iter()
+        .zip(offsets)
+        .map(|(t, o)| o - (slope * t + intercept))
+        .collect();
+    let mut abs_res: Vec<f64> = residuals.iter().map(|r| r.abs()).collect();
+    let mad = median(&mut abs_res)?;
+    let tolerance = (3.0 * 1.4826 * mad).max(MIN_INLIER_TOLERANCE);
 
-fn get_coverage_span(track: &Track) -> f64 {
-    let times: Vec<(f64, f64)> = track
-        .clips
+    let inlier_offsets: Vec<(f64, f64)> = residuals
         .iter()
-        .filter_map(|c| c.creation_time.map(|ct| (ct, c.duration_s)))
+        .zip(offsets)
+        .filter(|(r, _)| r.abs() <= tolerance)
+        .map(|(r, &o)| (*r, o))
         .collect();
-    if times.is_empty() {
-        return 0.0;
+    let inliers = inlier_offsets.len();
+    if inliers < MIN_DRIFT_WINDOWS {
+        return None;
     }
-    let earliest = times.iter().map(|(t, _)| *t).fold(f64::INFINITY, f64::min);
-    let latest = times.iter().map(|(t, d)| t + d).fold(f64::NEG_INFINITY, f64::max);
-    latest - earliest
+
+    let mean_o = inlier_offsets.iter().map(|(_, o)| o).sum::<f64>() / inliers as f64;
+    let ss_res: f64 = inlier_offsets.iter().map(|(r, _)| r * r).sum();
+    let ss_tot: f64 = inlier_offsets.iter().map(|(_, o)| (o - mean_o).powi(2)).sum();
+    let r_squared = (1.0 - ss_res / (ss_tot + 1e-30)).clamp(0.0, 1.0);
+
+    Some(DriftFit {
+        slope,
+        intercept,
+        r_squared,
+        inliers,
+    })
 }
 
-fn get_track_time_origin(track: &Track) -> Option<f64> {
-    track
-        .clips
-        .iter()
-        .filter_map(|c| c.creation_time)
-        .reduce(f64::min)
+/// Most segments a piecewise drift model may use.
+const MAX_DRIFT_SEGMENTS: usize = 4;
+
+/// Fewest windows supporting each segment of a piecewise fit.
+const MIN_SEGMENT_WINDOWS: usize = 6;
+
+/// Piecewise model must move some offset by at least this much (samples)
+/// against the straight line to be worth using.
+const MIN_PIECEWISE_DEVIATION: f64 = 0.5;
+
+/// Per-window offset variance floor (samples²) for model selection; keeps
+/// noise-free fits from rewarding meaningless extra segments.
+const OFFSET_VARIANCE_FLOOR: f64 = 1e-4;
+
+/// Continuous piecewise-linear fit of window offsets against time.
+#[derive(Debug, Clone, PartialEq)]
+struct PiecewiseFit {
+    /// Window times (seconds into the overlap) where the slope changes.
+    breakpoints: Vec<f64>,
+    /// Slope (samples per second) of each segment, one more than breakpoints.
+    slopes: Vec<f64>,
+    r_squared: f64,
+    inliers: usize,
 }
 
-fn build_reference_from_metadata(track: &mut Track, sr: u32) -> Result<Vec<f32>> {
-    let clips = &mut track.clips;
-    if clips.is_empty() {
-        return Err(anyhow!("Reference track '{}' has no clips.", track.name));
+/// Fit a piecewise-linear drift curve for clocks whose rate wanders.
+///
+/// Outliers are rejected locally — a window must continue the line through
+/// its two neighbours on at least one side, which a bend in the curve does
+/// and a noise burst does not — then breakpoints are added greedily at window
+/// times while the BIC improves. Returns `None` when a straight line explains
+/// the offsets as well.
+fn fit_piecewise_drift(times: &[f64], offsets: &[f64]) -> Option<PiecewiseFit> {
+    let n = times.len();
+    if n < 2 * MIN_SEGMENT_WINDOWS {
+        return None;
     }
 
-    // Single clip: trivial
-    if clips.len() == 1 {
-        clips[0].timeline_offset_samples = 0;
-        clips[0].timeline_offset_s = 0.0;
-        clips[0].confidence = 100.0;
-        clips[0].analyzed = true;
-        return Ok(clips[0].samples.clone());
+    let extrapolate = |a: usize, b: usize, i: usize| {
+        let dt = times[b] - times[a];
+        if dt.abs() < 1e-12 {
+            return f64::INFINITY;
+        }
+        let predicted = offsets[b] + (offsets[b] - offsets[a]) * (times[i] - times[b]) / dt;
+        (offsets[i] - predicted).abs()
+    };
+    let local: Vec<f64> = (0..n)
+        .map(|i| {
+            let left = if i >= 2 { extrapolate(i - 2, i - 1, i) } else { f64::INFINITY };
+            let right = if i + 2 < n { extrapolate(i + 2, i + 1, i) } else { f64::INFINITY };
+            left.min(right)
+        })
+        .collect();
+    let mut abs_local = local.clone();
+    let tolerance = (3.0 * 1.4826 * median(&mut abs_local)?).max(MIN_INLIER_TOLERANCE);
+    let (t, o): (Vec<f64>, Vec<f64>) = (0..n)
+        .filter(|&i| local[i].abs() <= tolerance)
+        .map(|i| (times[i], offsets[i]))
+        .unzip();
+    let m = t.len();
+    if m < 2 * MIN_SEGMENT_WINDOWS {
+        return None;
     }
 
-    // Place clips using metadata gaps
-    clips[0].timeline_offset_samples = 0;
-    clips[0].timeline_offset_s = 0.0;
-    clips[0].confidence = 100.0;
-    clips[0].analyzed = true;
-
-    for i in 1..clips.len() {
-        let gap_s = if let (Some(prev_ct), Some(curr_ct)) =
-            (clips[i - 1].creation_time, clips[i].creation_time)
-        {
-            let gap = curr_ct - (prev_ct + clips[i - 1].duration_s);
-            gap.max(0.0)
-        } else {
-            0.5 // No metadata: assume small gap
+    let bic = |rss: f64, params: usize| {
+        m as f64 * (rss / m as f64 + OFFSET_VARIANCE_FLOOR).ln() + params as f64 * (m as f64).ln()
+    };
+    let (linear, linear_rss) = fit_hinges(&t, &o, &[])?;
+    let mut best: (Vec<f64>, Vec<f64>, f64) = (Vec::new(), linear.clone(), linear_rss);
+    let mut best_bic = bic(linear_rss, 2);
+
+    while best.0.len() + 1 < MAX_DRIFT_SEGMENTS {
+        let mut round: Option<(Vec<f64>, Vec<f64>, f64)> = None;
+        for &b in &t {
+            let mut breaks = best.0.clone();
+            if breaks.contains(&b) {
+                continue;
+            }
+            breaks.push(b);
+            breaks.sort_by(|a, c| a.total_cmp(c));
+            // Every segment needs enough windows of its own.
+            let supported = (0..=breaks.len()).all(|k| {
+                let lo = if k == 0 { f64::NEG_INFINITY } else { breaks[k - 1] };
+                let hi = breaks.get(k).copied().unwrap_or(f64::INFINITY);
+                t.iter().filter(|&&x| x >= lo && x < hi).count() >= MIN_SEGMENT_WINDOWS
+            });
+            if !supported {
+                continue;
+            }
+            if let Some((coef, rss)) = fit_hinges(&t, &o, &breaks)
+                && round.as_ref().is_none_or(|r| rss < r.2)
+            {
+                round = Some((breaks, coef, rss));
+            }
+        }
+        let Some(candidate) = round else {
+            break;
         };
-
-        let offset = clips[i - 1].timeline_offset_samples
-            + clips[i - 1].length_samples() as i64
-            + (gap_s * sr as f64) as i64;
-        clips[i].timeline_offset_samples = offset;
-        clips[i].timeline_offset_s = offset as f64 / sr as f64;
-        clips[i].confidence = 100.0;
-        clips[i].analyzed = true;
+        // Each breakpoint adds a slope change and its own position.
+        let candidate_bic = bic(candidate.2, 2 + 2 * candidate.0.len());
+        if candidate_bic >= best_bic {
+            break;
+        }
+        best_bic = candidate_bic;
+        best = candidate;
     }
 
-    // Stitch into a single array
-    let max_end = clips
+    let (breakpoints, coef, rss) = best;
+    if breakpoints.is_empty() {
+        return None;
+    }
+    let deviation = t
         .iter()
-        .map(|c| c.end_samples())
-        .max()
-        .unwrap_or(0) as usize;
-    let mut ref_audio = vec![0.0f32; max_end];
+        .map(|&x| (eval_hinges(&coef, &breakpoints, x) - (linear[0] + linear[1] * x)).abs())
+        .fold(0.0, f64::max);
+    if deviation < MIN_PIECEWISE_DEVIATION {
+        return None;
+    }
 
-    for c in clips.iter() {
-        let start = c.timeline_offset_samples as usize;
-        let seg_len = c.samples.len().min(max_end.saturating_sub(start));
-        for j in 0..seg_len {
-            ref_audio[start + j] = c.samples[j];
+    let mean_o = o.iter().sum::<f64>() / m as f64;
+    let ss_tot: f64 = o.iter().map(|x| (x - mean_o).powi(2)).sum();
+    let mut slopes = vec![coef[1]];
+    for c in &coef[2..] {
+        slopes.push(slopes.last().unwrap() + c);
+    }
+    Some(PiecewiseFit {
+        breakpoints,
+        slopes,
+        r_squared: (1.0 - rss / (ss_tot + 1e-30)).clamp(0.0, 1.0),
+        inliers: m,
+    })
+}
+
+/// Least-squares fit of `o ≈ c0 + c1·t + Σ c(k+2)·max(t − b_k, 0)`, returning
+/// the coefficients and residual sum of squares.
+fn fit_hinges(t: &[f64], o: &[f64], breaks: &[f64]) -> Option<(Vec<f64>, f64)> {
+    let p = 2 + breaks.len();
+    let basis = |x: f64| {
+        let mut row = vec![1.0, x];
+        row.extend(breaks.iter().map(|&b| (x - b).max(0.0)));
+        row
+    };
+    // Normal equations, augmented with the right-hand side.
+    let mut a = vec![vec![0.0; p + 1]; p];
+    for (&x, &y) in t.iter().zip(o) {
+        let row = basis(x);
+        for i in 0..p {
+            for j in 0..p {
+                a[i][j] += row[i] * row[j];
+            }
+            a[i][p] += row[i] * y;
         }
     }
+    let coef = solve_linear(a)?;
+    let rss = t
+        .iter()
+        .zip(o)
+        .map(|(&x, &y)| (y - eval_hinges(&coef, breaks, x)).powi(2))
+        .sum();
+    Some((coef, rss))
+}
 
-    Ok(ref_audio)
+fn eval_hinges(coef: &[f64], breaks: &[f64], x: f64) -> f64 {
+    coef[0]
+        + coef[1] * x
+        + breaks
+            .iter()
+            .zip(&coef[2..])
+            .map(|(&b, c)| c * (x - b).max(0.0))
+            .sum::<f64>()
 }
 
-fn stitch_enhanced_timeline(
-    ref_audio: &[f32],
-    tracks: &[Track],
-    placed_clips: &[(usize, usize)],
-    _sr: u32,
-) -> Vec<f32> {
-    if placed_clips.is_empty() {
-        return ref_audio.to_vec();
+/// Gaussian elimination with partial pivoting on an augmented `p × (p+1)`
+/// matrix; `None` when singular.
+fn solve_linear(mut a: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let p = a.len();
+    for col in 0..p {
+        let pivot = (col..p).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower {
+            let factor = row[col] / pivot_row[col];
+            for (x, &y) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * y;
+            }
+        }
     }
+    let mut x = vec![0.0; p];
+    for row in (0..p).rev() {
+        let tail: f64 = (row + 1..p).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (a[row][p] - tail) / a[row][row];
+    }
+    Some(x)
+}
 
-    let mut max_end = ref_audio.len();
-    for &(ti, ci) in placed_clips {
-        let end = tracks[ti].clips[ci].end_samples() as usize;
-        if end > max_end {
-            max_end = end;
-        }
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Drift windows correlated per batch: enough to keep a GPU backend busy,
+/// few enough that an hour-long clip's windows needn't all be in memory.
+const DRIFT_BATCH_WINDOWS: usize = 16;
+
+/// Sub-sample offset of a drift window pair from their correlation
+/// (`t_len` being the clip window's length).
+fn window_peak_offset(corr: &[f32], t_len: usize) -> f64 {
+    let abs_corr: Vec<f32> = corr.iter().map(|x| x.abs()).collect();
+    let peak_idx = abs_corr
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    // Sub-sample precision via parabolic interpolation
+    let refined = subsample_peak(&abs_corr, peak_idx);
+    refined - (t_len as f64 - 1.0)
+}
+
+/// Parabolic interpolation around peak for sub-sample precision.
+fn subsample_peak(correlation: &[f32], peak_idx: usize) -> f64 {
+    let n = correlation.len();
+    if peak_idx == 0 || peak_idx >= n - 1 {
+        return peak_idx as f64;
+    }
+
+    let alpha = correlation[peak_idx - 1] as f64;
+    let beta = correlation[peak_idx] as f64;
+    let gamma = correlation[peak_idx + 1] as f64;
+
+    let denom = alpha - 2.0 * beta + gamma;
+    if denom.abs() < 1e-30 {
+        return peak_idx as f64;
+    }
+
+    let adjustment = 0.5 * (alpha - gamma) / denom;
+    peak_idx as f64 + adjustment
+}
+
+/// Apply drift correction by resampling.
+pub fn apply_drift_correction(audio: &[f32], drift_ppm: f64) -> Vec<f32> {
+    if drift_ppm.abs() < 1e-6 {
+        return audio.to_vec();
+    }
+
+    let original_len = audio.len();
+    let corrected_len = (original_len as f64 / (1.0 + drift_ppm * 1e-6)).round() as usize;
+
+    if corrected_len == original_len || corrected_len < 1 {
+        return audio.to_vec();
+    }
+
+    // Simple linear interpolation resampling
+    let ratio = original_len as f64 / corrected_len as f64;
+    let mut result = Vec::with_capacity(corrected_len);
+    for i in 0..corrected_len {
+        let pos = i as f64 * ratio;
+        let idx = pos as usize;
+        let frac = (pos - idx as f64) as f32;
+        if idx + 1 < original_len {
+            result.push(audio[idx] * (1.0 - frac) + audio[idx + 1] * frac);
+        } else if idx < original_len {
+            result.push(audio[idx]);
+        }
+    }
+    result
+}
+
+/// `audio` at fractional position `pos`: linear between the two nearest
+/// samples, or with `cubic` a Catmull-Rom spline through the four nearest
+/// (edges repeated), which stays flat much further up the spectrum. The last
+/// sample as is; `None` past the end.
+fn interpolate(audio: &[f64], pos: f64, cubic: bool) -> Option<f64> {
+    let idx = pos as usize;
+    let frac = pos - idx as f64;
+    if idx + 1 >= audio.len() {
+        return audio.get(idx).copied();
+    }
+    let (p1, p2) = (audio[idx], audio[idx + 1]);
+    if !cubic {
+        return Some(p1 * (1.0 - frac) + p2 * frac);
+    }
+    let p0 = audio[idx.saturating_sub(1)];
+    let p3 = audio[(idx + 2).min(audio.len() - 1)];
+    let c1 = p2 - p0;
+    let c2 = 2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3;
+    let c3 = 3.0 * (p1 - p2) + p3 - p0;
+    Some(p1 + 0.5 * frac * (c1 + frac * (c2 + frac * c3)))
+}
+
+/// Resample `audio` for a constant drift, interpolating linearly or (with
+/// `cubic`) by Catmull-Rom.
+pub fn apply_drift_correction_f64(audio: &[f64], drift_ppm: f64, cubic: bool) -> Vec<f64> {
+    if drift_ppm.abs() < 1e-6 {
+        return audio.to_vec();
+    }
+
+    let original_len = audio.len();
+    let corrected_len = (original_len as f64 / (1.0 + drift_ppm * 1e-6)).round() as usize;
+
+    if corrected_len == original_len || corrected_len < 1 {
+        return audio.to_vec();
+    }
+
+    let ratio = original_len as f64 / corrected_len as f64;
+    (0..corrected_len).map_while(|i| interpolate(audio, i as f64 * ratio, cubic)).collect()
+}
+
+/// Apply a piecewise drift model by resampling: output sample `i` reads the
+/// source at `drift_source_time(segments, i / sr)`, interpolated as in
+/// [`apply_drift_correction_f64`].
+pub fn apply_drift_segments_f64(audio: &[f64], segments: &[DriftSegment], sr: u32, cubic: bool) -> Vec<f64> {
+    let sr = sr as f64;
+    let out_len = (drift_timeline_time(segments, audio.len() as f64 / sr) * sr).round() as usize;
+    let mut result = Vec::with_capacity(out_len);
+    let mut src_start = 0.0;
+    for (k, seg) in segments.iter().enumerate() {
+        let rate = 1.0 + seg.ppm * 1e-6;
+        let end_s = segments.get(k + 1).map_or(f64::INFINITY, |s| s.start_s);
+        let end = ((end_s * sr).ceil() as usize).min(out_len);
+        while result.len() < end {
+            let pos = (src_start + (result.len() as f64 / sr - seg.start_s) * rate) * sr;
+            match interpolate(audio, pos, cubic) {
+                Some(v) => result.push(v),
+                None => return result,
+            }
+        }
+        src_start += (end_s - seg.start_s) * rate;
+    }
+    result
+}
+
+/// Input frames per resampler call in [`DriftSinc`].
+const DRIFT_RESAMPLE_CHUNK: usize = 4096;
+
+/// Apply a drift model with rubato's windowed-sinc resampler
+/// (`SincFixedIn`), the whole clip at once. Export streams clips through
+/// [`DriftSinc`] instead.
+pub fn resample_drift_sinc(
+    audio: &[f64],
+    segments: &[DriftSegment],
+    sr: u32,
+    quality: ResampleQuality,
+) -> Result<Vec<f64>> {
+    let out_len =
+        (drift_timeline_time(segments, audio.len() as f64 / sr as f64) * sr as f64).round() as usize;
+    if audio.is_empty() || out_len == 0 {
+        return Ok(Vec::new());
+    }
+    let mut resampler = DriftSinc::new(segments, sr, quality, 1)?;
+    let mut out = resampler.push(&[audio])?.swap_remove(0);
+    while out.len() < out_len {
+        out.extend(resampler.flush()?.swap_remove(0));
+    }
+    out.truncate(out_len);
+    Ok(out)
+}
+
+/// Drift correction with rubato's `SincFixedIn`, fed a stretch of source
+/// at a time so export can stream a clip.
+///
+/// The filtering is all rubato's. It resamples at one ratio, changed
+/// between calls, so this only cuts the input into chunks and calls
+/// `set_resample_ratio` as the output passes each segment start: a
+/// piecewise model is applied in one continuous pass, with no restart (and
+/// no filter ramp) at the joins.
+pub(super) struct DriftSinc {
+    resampler: rubato::SincFixedIn<f64>,
+    segments: Vec<DriftSegment>,
+    segment: usize,
+    sr: f64,
+    /// Frames put out so far.
+    produced: usize,
+    /// Input short of a full chunk, one `Vec` per channel.
+    pending: Vec<Vec<f64>>,
+}
+
+impl DriftSinc {
+    pub(super) fn new(
+        segments: &[DriftSegment],
+        sr: u32,
+        quality: ResampleQuality,
+        channels: usize,
+    ) -> Result<Self> {
+        use rubato::{SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
+
+        let (sinc_len, interpolation) = match quality {
+            ResampleQuality::High => (256, SincInterpolationType::Cubic),
+            _ => (128, SincInterpolationType::Linear),
+        };
+        let params = SincInterpolationParameters {
+            sinc_len,
+            f_cutoff: 0.95,
+            oversampling_factor: 256,
+            interpolation,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        // Every later segment's ratio must stay within this factor of the first.
+        let first = drift_ratio(segments[0].ppm);
+        let max_relative = segments
+            .iter()
+            .map(|s| (drift_ratio(s.ppm) / first).max(first / drift_ratio(s.ppm)))
+            .fold(1.0, f64::max);
+        let resampler =
+            SincFixedIn::<f64>::new(first, max_relative * 1.001, params, DRIFT_RESAMPLE_CHUNK, channels)
+                .map_err(|e| anyhow!("Failed to create drift resampler: {}", e))?;
+        Ok(Self {
+            resampler,
+            segments: segments.to_vec(),
+            segment: 0,
+            sr: sr as f64,
+            produced: 0,
+            pending: vec![Vec::new(); channels],
+        })
+    }
+
+    /// Feed the next source frames; returns the corrected frames completed.
+    /// SincFixedIn starts half a filter before the input, so its output is
+    /// already aligned: output n is centred on input n / ratio.
+    pub(super) fn push(&mut self, input: &[&[f64]]) -> Result<Vec<Vec<f64>>> {
+        for (pending, channel) in self.pending.iter_mut().zip(input) {
+            pending.extend_from_slice(channel);
+        }
+        let mut pending = std::mem::take(&mut self.pending);
+        let mut out = vec![Vec::new(); pending.len()];
+        let mut used = 0;
+        while pending[0].len() - used >= DRIFT_RESAMPLE_CHUNK {
+            let chunk: Vec<&[f64]> = pending.iter().map(|c| &c[used..used + DRIFT_RESAMPLE_CHUNK]).collect();
+            self.process(Some(&chunk), &mut out)?;
+            used += DRIFT_RESAMPLE_CHUNK;
+        }
+        pending.iter_mut().for_each(|c| drop(c.drain(..used)));
+        self.pending = pending;
+        Ok(out)
+    }
+
+    /// The input has ended: run what is left of it, or a chunk of silence,
+    /// through the filter.
+    pub(super) fn flush(&mut self) -> Result<Vec<Vec<f64>>> {
+        let channels = self.pending.len();
+        let pending = std::mem::replace(&mut self.pending, vec![Vec::new(); channels]);
+        let mut out = vec![Vec::new(); pending.len()];
+        if pending[0].is_empty() {
+            self.process(None, &mut out)?;
+        } else {
+            let chunk: Vec<&[f64]> = pending.iter().map(Vec::as_slice).collect();
+            self.process(Some(&chunk), &mut out)?;
+        }
+        Ok(out)
+    }
+
+    fn process(&mut self, chunk: Option<&[&[f64]]>, out: &mut [Vec<f64>]) -> Result<()> {
+        use rubato::Resampler;
+
+        // Switch rate once the output reaches the next segment.
+        let t = self.produced as f64 / self.sr;
+        while self.segment + 1 < self.segments.len() && t >= self.segments[self.segment + 1].start_s {
+            self.segment += 1;
+            self.resampler
+                .set_resample_ratio(drift_ratio(self.segments[self.segment].ppm), false)
+                .map_err(|e| anyhow!("Drift resampler: {}", e))?;
+        }
+        let resampled = self
+            .resampler
+            .process_partial(chunk, None)
+            .map_err(|e| anyhow!("Drift resampler: {}", e))?;
+        self.produced += resampled[0].len();
+        for (out, channel) in out.iter_mut().zip(resampled) {
+            out.extend(channel);
+        }
+        Ok(())
+    }
+}
+
+/// Resampling ratio (output over input) that undoes `ppm` of drift.
+fn drift_ratio(ppm: f64) -> f64 {
+    1.0 / (1.0 + ppm * 1e-6)
+}
+
+// ---------------------------------------------------------------------------
+//  Internal helpers
+// ---------------------------------------------------------------------------
+
+/// Slack added to the timestamp spread for clocks set by hand: a share of
+/// the spread, and at least this many seconds.
+const ESTIMATED_OFFSET_SLACK: f64 = 0.1;
+const ESTIMATED_OFFSET_MIN_SLACK_S: f64 = 120.0;
+
+/// Search bound (seconds) from the creation timestamps: from the first clip
+/// starting to the last one ending, no clip can lie further from the
+/// reference, give or take the devices' clock error. `None` unless every
+/// clip has a timestamp.
+fn estimate_max_offset_s(tracks: &[Track]) -> Option<f64> {
+    let clips: Vec<&Clip> = tracks.iter().flat_map(|t| &t.clips).collect();
+    if clips.len() < 2 {
+        return None;
+    }
+    let mut first = f64::INFINITY;
+    let mut last = f64::NEG_INFINITY;
+    for clip in clips {
+        let start = clip.creation_time?;
+        first = first.min(start);
+        last = last.max(start + clip.duration_s);
+    }
+    let spread = last - first;
+    Some(spread + (spread * ESTIMATED_OFFSET_SLACK).max(ESTIMATED_OFFSET_MIN_SLACK_S))
+}
+
+fn select_reference_index(tracks: &[Track], strategy: &ReferenceStrategy) -> usize {
+    // A named track beats even a hand-marked one: it is how a project pins
+    // the mixer as reference for every analysis.
+    if let ReferenceStrategy::Track(name) = strategy {
+        match tracks.iter().position(|t| t.name.eq_ignore_ascii_case(name)) {
+            Some(i) => return i,
+            None => warn!("Reference track '{}' not found; picking by coverage", name),
+        }
+    }
+
+    // Check for user override
+    for (i, t) in tracks.iter().enumerate() {
+        if t.is_reference {
+            return i;
+        }
+    }
+
+    match strategy {
+        ReferenceStrategy::Duration => return longest_track_index(tracks),
+        ReferenceStrategy::Quality => {
+            let snr: Vec<Option<f64>> = tracks.iter().map(track_snr_db).collect();
+            if let Some((i, _)) = snr
+                .iter()
+                .enumerate()
+                .filter_map(|(i, s)| s.map(|s| (i, s)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+            {
+                return i;
+            }
+        }
+        ReferenceStrategy::Coverage | ReferenceStrategy::Track(_) => {}
+    }
+
+    // Try metadata-based coverage span
+    let mut best_idx = 0;
+    let mut best_span = 0.0f64;
+    for (i, t) in tracks.iter().enumerate() {
+        let span = get_coverage_span(t);
+        if span > best_span {
+            best_span = span;
+            best_idx = i;
+        }
+    }
+
+    // Fallback: longest total duration
+    if best_span <= 0.0 {
+        best_idx = longest_track_index(tracks);
+    }
+
+    best_idx
+}
+
+fn longest_track_index(tracks: &[Track]) -> usize {
+    let mut best_idx = 0;
+    let mut best_dur = 0.0f64;
+    for (i, t) in tracks.iter().enumerate() {
+        let dur = t.total_duration_s();
+        if dur > best_dur {
+            best_dur = dur;
+            best_idx = i;
+        }
+    }
+    best_idx
+}
+
+/// Signal-to-noise estimate (dB) of a track: the loud level (95th-percentile
+/// frame RMS) over the noise floor (10th percentile), averaged over its clips
+/// by length. `None` when no clip has audio loaded.
+fn track_snr_db(track: &Track) -> Option<f64> {
+    let mut weighted = 0.0;
+    let mut total = 0usize;
+    for clip in &track.clips {
+        if let Some(snr) = clip_snr_db(&clip.samples) {
+            weighted += snr * clip.samples.len() as f64;
+            total += clip.samples.len();
+        }
+    }
+    (total > 0).then(|| weighted / total as f64)
+}
+
+/// Signal-to-noise estimate (dB) of one clip's analysis audio, as for
+/// [`track_snr_db`]. `None` when empty.
+fn clip_snr_db(samples: &[f32]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let frame = ((VAD_FRAME_S * ANALYSIS_SR as f64) as usize).max(1);
+    let mut rms: Vec<f32> = samples
+        .chunks(frame)
+        .map(|c| (c.iter().map(|x| x * x).sum::<f32>() / c.len() as f32).sqrt())
+        .collect();
+    rms.sort_by(|a, b| a.total_cmp(b));
+    let loud = rms[(rms.len() - 1) * 95 / 100].max(1e-6);
+    let floor = rms[(rms.len() - 1) / 10].max(1e-6);
+    Some(20.0 * (loud / floor).log10() as f64)
+}
+
+fn get_coverage_span(track: &Track) -> f64 {
+    let times: Vec<(f64, f64)> = track
+        .clips
+        .iter()
+        .filter_map(|c| c.creation_time.map(|ct| (ct, c.duration_s)))
+        .collect();
+    if times.is_empty() {
+        return 0.0;
+    }
+    let earliest = times.iter().map(|(t, _)| *t).fold(f64::INFINITY, f64::min);
+    let latest = times.iter().map(|(t, d)| t + d).fold(f64::NEG_INFINITY, f64::max);
+    latest - earliest
+}
+
+fn get_track_time_origin(track: &Track) -> Option<f64> {
+    track
+        .clips
+        .iter()
+        .filter_map(|c| c.creation_time)
+        .reduce(f64::min)
+}
+
+/// Creation time at timeline zero, going by the track's earliest placed
+/// clip (incremental analysis, where the timeline is already laid out).
+fn placed_time_origin(track: &Track, placed: &HashSet<String>) -> Option<f64> {
+    track
+        .clips
+        .iter()
+        .filter(|c| placed.contains(&c.file_path))
+        .filter_map(|c| c.creation_time.map(|ct| (ct, c.timeline_offset_s)))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(ct, offset_s)| ct - offset_s)
+}
+
+/// Correlation peaks considered when disambiguating a repetitive match.
+const DELAY_CANDIDATES: usize = 5;
+
+/// How far (seconds) a candidate may sit from where the creation times put
+/// the clip and still count as agreeing with them.
+const DISAMBIGUATION_TOLERANCE_S: f64 = 2.0;
+
+/// Same for where an earlier analysis of the same media put the clip, which
+/// is off only by the drift between the two timelines.
+const PRIOR_TOLERANCE_S: f64 = 0.25;
+
+/// [`match_with_metadata`] within the search bound `config` estimated from the
+/// creation timestamps, searching again with `unbounded` when the match is
+/// too weak to place the clip: its device's clock is off by more than the
+/// slack.
+fn match_within_estimate(
+    reference: &[f32],
+    ref_claps: &[Transient],
+    tracks: &[Track],
+    (ti, ci): (usize, usize),
+    ref_origin: Option<f64>,
+    (config, unbounded): (&SyncConfig, Option<&SyncConfig>),
+    cancel: &Option<CancelToken>,
+) -> (DelayMatch, ClipDiagnostics, Option<SyncWarning>) {
+    let bounded = match_with_metadata(reference, ref_claps, tracks, (ti, ci), ref_origin, config, cancel);
+    let Some(unbounded) = unbounded.filter(|_| bounded.0.score < CONFIDENCE_THRESHOLD) else {
+        return bounded;
+    };
+    let retry = match_with_metadata(reference, ref_claps, tracks, (ti, ci), ref_origin, unbounded, cancel);
+    if retry.0.score <= bounded.0.score {
+        return bounded;
+    }
+    info!(
+        "'{}' matched outside the estimated search bound ({:.0}/100 instead of {:.0}/100)",
+        tracks[ti].clips[ci].name, retry.0.score, bounded.0.score
+    );
+    retry
+}
+
+/// Correlate clip `ci` of track `ti` against `reference`. When several peaks
+/// are near-equal, pick the one that agrees with an earlier analysis of the
+/// same media or the creation times, and return an `AmbiguousMatch` note to
+/// record if the match is used. The diagnostics leave `overlap_s` and
+/// `snr_db` to [`measure_placement`].
+fn match_with_metadata(
+    reference: &[f32],
+    ref_claps: &[Transient],
+    tracks: &[Track],
+    (ti, ci): (usize, usize),
+    ref_origin: Option<f64>,
+    config: &SyncConfig,
+    cancel: &Option<CancelToken>,
+) -> (DelayMatch, ClipDiagnostics, Option<SyncWarning>) {
+    let sr = ANALYSIS_SR;
+    let clip = &tracks[ti].clips[ci];
+    let target = correlation_input(&clip.samples, sr, config, config.band_weighting(&tracks[ti]));
+    let sparse = if config.drift_resample_quality == ResampleQuality::Fast {
+        let normalization = config.correlation_normalization;
+        sparse::sparse_match(reference, &target, sr, config.max_offset_s, normalization, cancel)
+    } else {
+        None
+    };
+    let (m, candidates) = sparse.unwrap_or_else(|| {
+        search_delay(
+            reference,
+            &target,
+            sr,
+            config.max_offset_s,
+            DELAY_CANDIDATES,
+            config.correlation_normalization,
+            cancel,
+        )
+    });
+    if let Some((slate, clap_s)) =
+        match_on_slate(reference, ref_claps, &clip.samples, &target, sr, config.max_offset_s)
+    {
+        let window = (SLATE_WINDOW_S * sr as f64) as i64;
+        let secondary = secondary_peak_ratio(&slate, &candidates, sr);
+        let diag = match_diagnostics(MatchMethod::Slate, slate.confidence, secondary);
+        if (slate.delay_samples - m.delay_samples).abs() <= window {
+            return (slate, diag, None);
+        }
+        info!(
+            "'{}': anchored on the slate at {:.2} s ({:.0}/100) instead of the strongest match",
+            clip.name, clap_s, slate.score
+        );
+        let note = SyncWarning::new(
+            WarningCode::SlateAligned,
+            WarningSeverity::Info,
+            format!(
+                "'{}' was placed at {:.1} s by its slate clap ({:.2} s into the clip); \
+                 the strongest match was at {:.1} s",
+                clip.name, slate.delay_s, clap_s, m.delay_s
+            ),
+        )
+        .for_clip(&clip.file_path);
+        return (slate, diag, Some(note));
+    }
+    let strongest = || {
+        let secondary = secondary_peak_ratio(&m, &candidates, sr);
+        (m, match_diagnostics(MatchMethod::Correlation, m.confidence, secondary), None)
+    };
+    let (expected, tolerance_s, basis) = match prior_offset(tracks, ti, ci, sr) {
+        Some(expected) => (expected, PRIOR_TOLERANCE_S, "an earlier analysis of the same media"),
+        None => match expected_offset(&tracks[ti], ci, ref_origin, sr) {
+            Some(expected) => (expected, DISAMBIGUATION_TOLERANCE_S, "its creation time"),
+            None => return strongest(),
+        },
+    };
+    let tolerance = (tolerance_s * sr as f64) as i64;
+    let Some(chosen) = disambiguate(&m, &candidates, expected, tolerance) else {
+        return strongest();
+    };
+
+    let rivals = candidates
+        .iter()
+        .filter(|c| c.relative_peak >= AMBIGUOUS_PEAK_RATIO)
+        .count();
+    info!(
+        "'{}': {} near-equal matches; chose {:.3} s from {} ({:.0}/100)",
+        clip.name,
+        rivals,
+        chosen.delay_samples as f64 / sr as f64,
+        basis,
+        chosen.score
+    );
+    let note = SyncWarning::new(
+        WarningCode::AmbiguousMatch,
+        WarningSeverity::Info,
+        format!(
+            "'{}' matched {} places almost equally well; chose {:.1} s, which agrees with {}",
+            clip.name,
+            rivals,
+            chosen.delay_samples as f64 / sr as f64,
+            basis
+        ),
+    )
+    .for_clip(&clip.file_path);
+    let secondary = secondary_peak_ratio(&chosen, &candidates, sr);
+    let diag = match_diagnostics(MatchMethod::Disambiguated, chosen.confidence, secondary);
+    (chosen, diag, Some(note))
+}
+
+/// Diagnostics for a clip placed by `method` on a peak of the given
+/// sharpness, before [`measure_placement`].
+fn match_diagnostics(
+    method: MatchMethod,
+    peak_sharpness: f64,
+    secondary_peak_ratio: Option<f64>,
+) -> ClipDiagnostics {
+    ClipDiagnostics {
+        method,
+        peak_sharpness,
+        secondary_peak_ratio,
+        overlap_s: 0.0,
+        snr_db: None,
+    }
+}
+
+/// Fill in the audio `clip` shares with `reference` when placed at `delay`,
+/// and the clip's own signal-to-noise ratio.
+fn measure_placement(diag: &mut ClipDiagnostics, reference: &[f32], clip: &Clip, delay: i64) {
+    diag.overlap_s = shared_audio_s(reference, &clip.samples, delay, ANALYSIS_SR);
+    diag.snr_db = clip_snr_db(&clip.samples);
+}
+
+/// Strongest of `candidates` more than 0.25 s from `chosen`, relative to
+/// `chosen`'s own peak. `None` when `chosen` isn't among the candidates.
+fn secondary_peak_ratio(chosen: &DelayMatch, candidates: &[DelayCandidate], sr: u32) -> Option<f64> {
+    let exclusion = (sr / 4) as i64;
+    let near = |c: &&DelayCandidate| (c.delay_samples - chosen.delay_samples).abs() <= exclusion;
+    let own = candidates.iter().find(near)?.relative_peak;
+    let other = candidates
+        .iter()
+        .filter(|c| !near(c))
+        .map(|c| c.relative_peak)
+        .fold(0.0, f64::max);
+    Some(if own > 0.0 { other / own } else { 0.0 })
+}
+
+/// Frame length (seconds) of the silence mask.
+const VAD_FRAME_S: f64 = 0.02;
+
+/// Frames below this RMS (-60 dBFS) are silent outright.
+const SILENCE_FLOOR_RMS: f32 = 1e-3;
+
+/// Frames more than 20 dB below the clip's loud level (95th-percentile frame
+/// RMS) are pauses, not content.
+const PAUSE_RATIO: f32 = 0.1;
+
+/// Least non-silent audio (seconds) a clip must share with the reference for
+/// its correlation peak to mean anything. Short clips need only share half
+/// their length.
+const MIN_SHARED_AUDIO_S: f64 = 2.0;
+
+fn min_shared_audio_s(clip: &Clip) -> f64 {
+    MIN_SHARED_AUDIO_S.min(clip.samples.len() as f64 / ANALYSIS_SR as f64 / 2.0)
+}
+
+/// Per-frame activity (`VAD_FRAME_S` frames): true where there is signal
+/// rather than silence or a pause.
+pub fn activity_mask(samples: &[f32], sr: u32) -> Vec<bool> {
+    let frame = ((VAD_FRAME_S * sr as f64) as usize).max(1);
+    let rms: Vec<f32> = samples
+        .chunks(frame)
+        .map(|c| (c.iter().map(|x| x * x).sum::<f32>() / c.len() as f32).sqrt())
+        .collect();
+    if rms.is_empty() {
+        return Vec::new();
+    }
+    let mut sorted = rms.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let loud = sorted[(sorted.len() - 1) * 95 / 100];
+    let threshold = SILENCE_FLOOR_RMS.max(loud * PAUSE_RATIO);
+    rms.iter().map(|&r| r >= threshold).collect()
+}
+
+/// Copy of `samples` with silent frames zeroed, so silence neither adds
+/// correlation nor dilutes it.
+fn gate_silence(samples: &[f32], sr: u32) -> Vec<f32> {
+    let frame = ((VAD_FRAME_S * sr as f64) as usize).max(1);
+    let mask = activity_mask(samples, sr);
+    samples
+        .chunks(frame)
+        .zip(&mask)
+        .flat_map(|(c, &active)| c.iter().map(move |&x| if active { x } else { 0.0 }))
+        .collect()
+}
+
+/// Seconds where both `target` (placed at `delay` samples) and `reference`
+/// carry audio.
+fn shared_audio_s(reference: &[f32], target: &[f32], delay: i64, sr: u32) -> f64 {
+    let start = delay.max(0) as usize;
+    let end = ((delay + target.len() as i64).max(0) as usize).min(reference.len());
+    if end <= start {
+        return 0.0;
+    }
+    let skip = (start as i64 - delay) as usize;
+    let ref_mask = activity_mask(&reference[start..end], sr);
+    let tgt_mask = activity_mask(&target[skip..skip + (end - start)], sr);
+    let shared = ref_mask.iter().zip(&tgt_mask).filter(|(a, b)| **a && **b).count();
+    shared as f64 * VAD_FRAME_S
+}
+
+/// Analysis audio as the correlator sees it: denoised when `denoise` is
+/// set, band-limited when `correlation_band_hz` is, shaped by the device's
+/// `weighting`, with silence gated out.
+fn correlation_input(
+    samples: &[f32],
+    sr: u32,
+    config: &SyncConfig,
+    weighting: Option<BandWeighting>,
+) -> Vec<f32> {
+    let cleaned = if config.denoise {
+        denoise::spectral_denoise(samples)
+    } else {
+        samples.to_vec()
+    };
+    let banded = match config.correlation_band_hz {
+        Some((low, high)) => band_pass(&cleaned, sr, low, high),
+        None => cleaned,
+    };
+    match weighting {
+        Some(w) => gate_silence(&weight_bands(&banded, sr, &w), sr),
+        None => gate_silence(&banded, sr),
+    }
+}
+
+/// Apply a device's [`BandWeighting`]: its high- and low-pass, then the
+/// speech band boosted (or cut) by `speech_emphasis_db`, all zero-phase.
+fn weight_bands(samples: &[f32], sr: u32, weighting: &BandWeighting) -> Vec<f32> {
+    let (low, high) = (weighting.high_pass_hz.unwrap_or(0.0), weighting.low_pass_hz.unwrap_or(0.0));
+    let mut out = band_pass(samples, sr, low, high);
+    if weighting.speech_emphasis_db != 0.0 {
+        let gain = 10f64.powf(weighting.speech_emphasis_db / 20.0) as f32 - 1.0;
+        let speech = band_pass(&out, sr, SPEECH_BAND_HZ.0, SPEECH_BAND_HZ.1);
+        out.iter_mut().zip(speech).for_each(|(x, s)| *x += gain * s);
+    }
+    out
+}
+
+/// Zero-phase band-pass: 2nd-order Butterworth high- and low-pass sections
+/// run forward then backward (24 dB/octave skirts, no shift of the
+/// correlation peak). Edges outside `(0, sr/2)` are ignored.
+pub fn band_pass(samples: &[f32], sr: u32, low_hz: f64, high_hz: f64) -> Vec<f32> {
+    let nyquist = sr as f64 / 2.0;
+    let mut sections = Vec::new();
+    if low_hz > 0.0 && low_hz < nyquist {
+        sections.push(Biquad::butterworth(low_hz / sr as f64, true));
+    }
+    if high_hz > 0.0 && high_hz < nyquist {
+        sections.push(Biquad::butterworth(high_hz / sr as f64, false));
+    }
+
+    let mut out: Vec<f64> = samples.iter().map(|&x| x as f64).collect();
+    for section in &sections {
+        section.run(&mut out);
+        out.reverse();
+        section.run(&mut out);
+        out.reverse();
+    }
+    out.into_iter().map(|x| x as f32).collect()
+}
+
+/// Biquad coefficients normalized by `a0` (RBJ cookbook).
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    /// Butterworth (Q = 1/√2) high- or low-pass at `freq` cycles per sample.
+    fn butterworth(freq: f64, high_pass: bool) -> Self {
+        let w = std::f64::consts::TAU * freq;
+        let alpha = w.sin() * std::f64::consts::FRAC_1_SQRT_2;
+        let cos = w.cos();
+        let a0 = 1.0 + alpha;
+        let b = if high_pass {
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0]
+        } else {
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0]
+        };
+        Self {
+            b: b.map(|c| c / a0),
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        }
+    }
+
+    /// Filter `x` in place (transposed direct form II).
+    fn run(&self, x: &mut [f64]) {
+        let (mut z1, mut z2) = (0.0, 0.0);
+        for v in x.iter_mut() {
+            let y = self.b[0] * *v + z1;
+            z1 = self.b[1] * *v - self.a[0] * y + z2;
+            z2 = self.b[2] * *v - self.a[1] * y;
+            *v = y;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Slate / clap detection
+// ---------------------------------------------------------------------------
+
+/// Frame length (seconds) of the transient detector.
+const CLAP_FRAME_S: f64 = 0.005;
+
+/// Background measured over this long before an onset, skipping the two
+/// frames right before it (a clap can straddle a frame boundary).
+const CLAP_BACKGROUND_S: f64 = 0.1;
+
+/// A clap's peak frame stands at least this far above its background (dB)…
+const CLAP_ONSET_DB: f64 = 20.0;
+
+/// …and has dropped at least this far (dB) 50–100 ms later: a clap rings
+/// out quickly where a note or a word carries on.
+const CLAP_DECAY_DB: f64 = 10.0;
+const CLAP_DECAY_S: (f64, f64) = (0.05, 0.1);
+
+/// Transients closer than this are one event.
+const CLAP_MIN_SPACING_S: f64 = 0.5;
+
+/// Strongest claps paired up per reference timeline and per clip.
+const MAX_REFERENCE_CLAPS: usize = 32;
+const MAX_CLIP_CLAPS: usize = 2;
+
+/// Correlation is searched this far either side of a clap-implied offset.
+const SLATE_WINDOW_S: f64 = 0.1;
+
+/// Clip audio (seconds before, after a clap) used to rank clap pairings.
+const SLATE_EXCERPT_S: (f64, f64) = (1.0, 3.0);
+
+/// A clap-like impulse: a sudden onset that dies away quickly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transient {
+    /// Time of the loudest sample of the onset.
+    pub time_s: f64,
+    /// Onset level over the preceding background, dB.
+    pub strength_db: f64,
+}
+
+/// Clap-like transients in `samples`, strongest first, at least
+/// `CLAP_MIN_SPACING_S` apart.
+pub fn detect_transients(samples: &[f32], sr: u32) -> Vec<Transient> {
+    let frame = ((CLAP_FRAME_S * sr as f64) as usize).max(1);
+    let energy: Vec<f64> = samples
+        .chunks(frame)
+        .map(|c| c.iter().map(|&x| x as f64 * x as f64).sum::<f64>() / c.len() as f64)
+        .collect();
+    let frames = |s: f64| (s / CLAP_FRAME_S).round() as usize;
+    let background = frames(CLAP_BACKGROUND_S);
+    let decay = (frames(CLAP_DECAY_S.0), frames(CLAP_DECAY_S.1));
+    let floor = SILENCE_FLOOR_RMS as f64 * SILENCE_FLOOR_RMS as f64;
+    let mean = |r: &[f64]| r.iter().sum::<f64>() / r.len().max(1) as f64;
+    let db = |a: f64, b: f64| 10.0 * (a / (b + floor)).log10();
+
+    let mut found = Vec::new();
+    for i in background + 2..energy.len().saturating_sub(decay.1) {
+        let e = energy[i];
+        if e < floor || energy[i - 1] > e || energy[i + 1] > e {
+            continue;
+        }
+        let onset_db = db(e, mean(&energy[i - 2 - background..i - 2]));
+        let decay_db = db(e, mean(&energy[i + decay.0..i + decay.1]));
+        if onset_db < CLAP_ONSET_DB || decay_db < CLAP_DECAY_DB {
+            continue;
+        }
+        let span = &samples[(i - 1) * frame..((i + 1) * frame).min(samples.len())];
+        let loudest = abs_peak(span) + (i - 1) * frame;
+        found.push(Transient {
+            time_s: loudest as f64 / sr as f64,
+            strength_db: onset_db,
+        });
+    }
+
+    found.sort_by(|a, b| b.strength_db.total_cmp(&a.strength_db));
+    let mut kept: Vec<Transient> = Vec::new();
+    for t in found {
+        if kept.iter().all(|k| (k.time_s - t.time_s).abs() >= CLAP_MIN_SPACING_S) {
+            kept.push(t);
+        }
+    }
+    kept
+}
+
+/// Reference claps for the slate prior; none unless it is enabled.
+fn slate_claps(reference: &[f32], sr: u32, config: &SyncConfig) -> Vec<Transient> {
+    if !config.slate_prior {
+        return Vec::new();
+    }
+    let mut claps = detect_transients(reference, sr);
+    claps.truncate(MAX_REFERENCE_CLAPS);
+    debug!("Slate prior: {} clap(s) on the reference", claps.len());
+    claps
+}
+
+/// The best match that lines one of the clip's claps up with a reference
+/// clap, correlating only within `SLATE_WINDOW_S` of each pairing. Pairings
+/// are ranked on a few seconds around the clap, then the winner is matched
+/// with the whole clip. Returns the match and the clap's time in the clip.
+fn match_on_slate(
+    reference: &[f32],
+    ref_claps: &[Transient],
+    raw_target: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+) -> Option<(DelayMatch, f64)> {
+    if ref_claps.is_empty() {
+        return None;
+    }
+    let samples = |s: f64| (s * sr as f64).round() as i64;
+    let window = samples(SLATE_WINDOW_S);
+
+    let mut best: Option<(f64, i64, f64)> = None;
+    for clap in detect_transients(raw_target, sr).iter().take(MAX_CLIP_CLAPS) {
+        let at = samples(clap.time_s);
+        let start = (at - samples(SLATE_EXCERPT_S.0)).max(0);
+        let end = (at + samples(SLATE_EXCERPT_S.1)).min(target.len() as i64);
+        let excerpt = &target[start as usize..end as usize];
+        for r in ref_claps {
+            let delay = samples(r.time_s) - at;
+            if max_offset_s.is_some_and(|max| delay.abs() - window > samples(max)) {
+                continue;
+            }
+            let range = (delay + start - window, delay + start + window);
+            let m = match_delay_between(reference, excerpt, sr, range);
+            if best.is_none_or(|(score, _, _)| m.score > score) {
+                best = Some((m.score, delay, clap.time_s));
+            }
+        }
+    }
+
+    let (_, delay, clap_s) = best?;
+    let m = match_delay_between(reference, target, sr, (delay - window, delay + window));
+    (m.score >= CONFIDENCE_THRESHOLD).then_some((m, clap_s))
+}
+
+fn insufficient_shared_audio(clip_name: &str, shared_s: f64) -> SyncWarning {
+    SyncWarning::new(
+        WarningCode::InsufficientSharedAudio,
+        WarningSeverity::Warning,
+        format!(
+            "Insufficient shared audio for '{}': only {:.1} s of sound overlaps the reference, so no offset was taken",
+            clip_name, shared_s
+        ),
+    )
+}
+
+/// Correlation around a timecode placement is searched this far either side
+/// (timecode jam-synced to within a frame or two).
+const TIMECODE_WINDOW_S: f64 = 0.1;
+
+/// Decode LTC on `channel` for every clip not decoded yet. Clips whose file
+/// can't be read (or has no such channel) keep correlating as usual.
+pub(crate) fn decode_timecodes(tracks: &mut [Track], channel: u32, io_parallelism: usize, cancel: &Option<CancelToken>) {
+    let pending: Vec<(usize, usize)> = tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(ti, t)| (0..t.clips.len()).map(move |ci| (ti, ci)))
+        .filter(|&(ti, ci)| tracks[ti].clips[ci].ltc_start_s.is_none())
+        .collect();
+    let decoded = map_io_bounded(&pending, io_parallelism, |_, &(ti, ci)| {
+        let clip = &tracks[ti].clips[ci];
+        match ltc::read_clip_timecode(clip, channel, cancel) {
+            Ok(found) => found.map(|ltc| ltc.start_s),
+            Err(e) => {
+                warn!("LTC: skipping '{}': {}", clip.name, e);
+                None
+            }
+        }
+    });
+    for (&(ti, ci), start_s) in pending.iter().zip(decoded) {
+        tracks[ti].clips[ci].ltc_start_s = start_s;
+    }
+}
+
+/// Where clip `ci`'s timecode puts it on the reference timeline, measured
+/// from the reference clip with timecode nearest to it. `None` unless both
+/// carry timecode.
+fn timecode_delay(tracks: &[Track], ref_idx: usize, ti: usize, ci: usize, sr: u32) -> Option<i64> {
+    let tc = tracks[ti].clips[ci].ltc_start_s?;
+    let distance = |c: &Clip, start: f64| {
+        if tc < start {
+            start - tc
+        } else {
+            (tc - start - c.duration_s).max(0.0)
+        }
+    };
+    let (anchor, anchor_tc) = tracks[ref_idx]
+        .clips
+        .iter()
+        .filter_map(|c| Some((c, c.ltc_start_s?)))
+        .min_by(|a, b| distance(a.0, a.1).total_cmp(&distance(b.0, b.1)))?;
+    Some(anchor.timeline_offset_samples + ((tc - anchor_tc) * sr as f64).round() as i64)
+}
+
+/// Place a clip at its timecode position `expected`, refined by correlating
+/// within `TIMECODE_WINDOW_S`. When the audio can't confirm the timecode,
+/// the timecode stands and the returned note says whether the audio had no
+/// opinion or pointed somewhere else.
+fn match_on_timecode(
+    reference: &[f32],
+    clip: &Clip,
+    expected: i64,
+    (config, weighting): (&SyncConfig, Option<BandWeighting>),
+    cancel: &Option<CancelToken>,
+) -> (DelayMatch, Option<SyncWarning>) {
+    let sr = ANALYSIS_SR;
+    let target = correlation_input(&clip.samples, sr, config, weighting);
+    let window = (TIMECODE_WINDOW_S * sr as f64) as i64;
+    let near = match_delay_between(reference, &target, sr, (expected - window, expected + window));
+    if near.score >= CONFIDENCE_THRESHOLD {
+        debug!(
+            "'{}': timecode confirmed, refined by {:+.1} ms",
+            clip.name,
+            (near.delay_samples - expected) as f64 / sr as f64 * 1000.0
+        );
+        return (near, None);
+    }
+
+    let placed = DelayMatch {
+        delay_samples: expected,
+        delay_s: expected as f64 / sr as f64,
+        inverted: false,
+        ..near
+    };
+    let (m, _) = search_delay(
+        reference,
+        &target,
+        sr,
+        config.max_offset_s,
+        1,
+        config.correlation_normalization,
+        cancel,
+    );
+    let note = if m.score >= CONFIDENCE_THRESHOLD {
+        SyncWarning::new(
+            WarningCode::TimecodeMismatch,
+            WarningSeverity::Warning,
+            format!(
+                "'{}' has timecode for {:.2} s but its audio matches at {:.2} s ({:.0}/100); kept the timecode",
+                clip.name, placed.delay_s, m.delay_s, m.score
+            ),
+        )
+    } else {
+        SyncWarning::new(
+            WarningCode::TimecodePlaced,
+            WarningSeverity::Info,
+            format!("'{}' was placed at {:.2} s by timecode alone", clip.name, placed.delay_s),
+        )
+    };
+    (placed, Some(note.for_clip(&clip.file_path)))
+}
+
+/// Where the creation times put clip `ci` on the reference timeline: next
+/// to the previous clip of the same device if that one is placed (clock
+/// errors cancel), otherwise relative to the reference's first recording.
+fn expected_offset(track: &Track, ci: usize, ref_origin: Option<f64>, sr: u32) -> Option<i64> {
+    let ct = track.clips[ci].creation_time?;
+    if ci > 0 {
+        let prev = &track.clips[ci - 1];
+        let placed = track.is_clip_locked(ci - 1)
+            || (prev.analyzed && prev.match_score >= CONFIDENCE_THRESHOLD);
+        if let (true, Some(prev_ct)) = (placed, prev.creation_time) {
+            return Some(prev.timeline_offset_samples + ((ct - prev_ct) * sr as f64) as i64);
+        }
+    }
+    Some(((ct - ref_origin?) * sr as f64) as i64)
+}
+
+/// Where the clip's prior analysis puts clip `ci` of track `ti`: the same
+/// distance from a clip placed in that run as it was then, measured from
+/// wherever that clip is placed now.
+fn prior_offset(tracks: &[Track], ti: usize, ci: usize, sr: u32) -> Option<i64> {
+    let prior = tracks[ti].clips[ci].prior.as_ref()?;
+    tracks.iter().enumerate().find_map(|(tj, track)| {
+        track.clips.iter().enumerate().find_map(|(cj, other)| {
+            let placed = track.is_clip_locked(cj)
+                || (other.analyzed && other.match_score >= CONFIDENCE_THRESHOLD);
+            let other_prior = other.prior.as_ref().filter(|p| p.run_id == prior.run_id)?;
+            ((tj, cj) != (ti, ci) && placed).then(|| {
+                other.timeline_offset_samples + ((prior.offset_s - other_prior.offset_s) * sr as f64).round() as i64
+            })
+        })
+    })
+}
+
+/// Among candidates within `AMBIGUOUS_PEAK_RATIO` of the best peak, return
+/// the single one within `tolerance` samples of `expected`, rescored with
+/// only the peaks metadata cannot rule out as rivals. `None` if the match is
+/// not ambiguous or metadata does not single out one candidate.
+fn disambiguate(
+    m: &DelayMatch,
+    candidates: &[DelayCandidate],
+    expected: i64,
+    tolerance: i64,
+) -> Option<DelayMatch> {
+    let rivals: Vec<&DelayCandidate> = candidates
+        .iter()
+        .filter(|c| c.relative_peak >= AMBIGUOUS_PEAK_RATIO)
+        .collect();
+    if rivals.len() < 2 {
+        return None;
+    }
+    let near = |c: &DelayCandidate| (c.delay_samples - expected).abs() <= tolerance;
+    let mut consistent = rivals.iter().filter(|c| near(c));
+    let chosen = *consistent.next()?;
+    if consistent.next().is_some() {
+        return None;
+    }
+
+    let runner_up = candidates
+        .iter()
+        .filter(|c| c.delay_samples != chosen.delay_samples && near(c))
+        .map(|c| c.relative_peak / chosen.relative_peak)
+        .fold(0.0, f64::max);
+    Some(DelayMatch {
+        delay_samples: chosen.delay_samples,
+        delay_s: chosen.delay_s,
+        confidence: m.confidence * chosen.relative_peak,
+        score: calibrated_score(chosen.ncc, runner_up),
+        ncc: chosen.ncc,
+        inverted: chosen.inverted,
+    })
+}
+
+/// Lay out the reference track's clips and stitch them into one timeline:
+/// spaced by creation-time gaps, or with `by_content`, ordered and spaced by
+/// their overlapping edges (see [`reference_layout`]).
+///
+/// Clips whose creation times overlap (two recorders on one track) are
+/// placed overlapping and reported; see [`stitch_reference`] for which
+/// audio the overlap keeps.
+fn build_reference(
+    track: &mut Track,
+    sr: u32,
+    by_content: bool,
+    warnings: &mut Vec<SyncWarning>,
+) -> Result<Vec<f32>> {
+    let track_locked = track.locked;
+    let clips = &mut track.clips;
+    if clips.is_empty() {
+        return Err(anyhow!("Reference track '{}' has no clips.", track.name));
+    }
+
+    // Locked clips keep their offsets; the rest are chained around them.
+    if !(track_locked || clips[0].is_pinned()) {
+        clips[0].timeline_offset_samples = 0;
+        clips[0].timeline_offset_s = 0.0;
+    }
+    clips[0].confidence = 100.0;
+    clips[0].match_score = 100.0;
+    clips[0].analyzed = true;
+
+    // Single clip at zero: trivial
+    if clips.len() == 1 && clips[0].timeline_offset_samples == 0 {
+        return Ok(clips[0].samples.clone());
+    }
+
+    for clip in clips.iter_mut().skip(1) {
+        clip.confidence = 100.0;
+        clip.match_score = 100.0;
+        clip.analyzed = true;
+    }
+
+    if by_content {
+        for (i, placed) in reference_layout::layout_by_content(clips, sr) {
+            if track_locked || clips[i].is_pinned() {
+                continue;
+            }
+            let offset = placed.map_or(0, |(prev, delta)| clips[prev].timeline_offset_samples + delta);
+            clips[i].timeline_offset_samples = offset;
+            clips[i].timeline_offset_s = offset as f64 / sr as f64;
+        }
+    }
+
+    // Place clips using metadata gaps
+    for i in 1..clips.len() {
+        if by_content || track_locked || clips[i].is_pinned() {
+            continue;
+        }
+
+        let gap_s = if let (Some(prev_ct), Some(curr_ct)) =
+            (clips[i - 1].creation_time, clips[i].creation_time)
+        {
+            let gap = curr_ct - (prev_ct + clips[i - 1].duration_s);
+            // Timestamps are whole seconds: a slight overlap is rounding.
+            if gap > -REFERENCE_OVERLAP_TOLERANCE_S { gap.max(0.0) } else { gap }
+        } else {
+            0.5 // No metadata: assume small gap
+        };
+
+        let offset = (clips[i - 1].timeline_offset_samples
+            + clips[i - 1].length_samples() as i64
+            + (gap_s * sr as f64) as i64)
+            .max(0);
+        clips[i].timeline_offset_samples = offset;
+        clips[i].timeline_offset_s = offset as f64 / sr as f64;
+    }
+
+    // Split files of one recording overlap by design when laid out by content.
+    if !by_content {
+        warn_reference_overlaps(clips, sr, warnings);
+    }
+    Ok(stitch_reference(clips))
+}
+
+/// Creation-time overlaps shorter than this (seconds) are taken as rounding
+/// and closed up.
+const REFERENCE_OVERLAP_TOLERANCE_S: f64 = 1.0;
+
+/// Rank of a clip as a source for the reference timeline: dedicated audio
+/// over a camera's, then bit depth, sample rate and channels.
+fn source_quality(clip: &Clip) -> (bool, u32, u32, u32) {
+    (
+        !clip.is_video,
+        clip.stream_metadata.bit_depth.unwrap_or(0),
+        clip.original_sr,
+        clip.original_channels,
+    )
+}
+
+/// Stitch placed clips into one timeline. Where clips overlap, the
+/// higher-quality source (see [`source_quality`]) is used alone; sources of
+/// equal quality are mixed.
+fn stitch_reference(clips: &[Clip]) -> Vec<f32> {
+    let span = |c: &Clip| {
+        let start = c.timeline_offset_samples.max(0) as usize;
+        (start, start + c.samples.len())
+    };
+    let len = clips.iter().map(|c| span(c).1).max().unwrap_or(0);
+    let mut bounds: Vec<usize> = clips.iter().flat_map(|c| [span(c).0, span(c).1]).collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut ref_audio = vec![0.0f32; len];
+    for pair in bounds.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let covering: Vec<&Clip> = clips
+            .iter()
+            .filter(|c| span(c).0 <= a && b <= span(c).1)
+            .collect();
+        let Some(best) = covering.iter().map(|c| source_quality(c)).max() else {
+            continue;
+        };
+        let sources: Vec<&Clip> = covering.into_iter().filter(|c| source_quality(c) == best).collect();
+        let weight = 1.0 / sources.len() as f32;
+        for c in sources {
+            let start = span(c).0;
+            for (out, &x) in ref_audio[a..b].iter_mut().zip(&c.samples[a - start..b - start]) {
+                *out += x * weight;
+            }
+        }
+    }
+    ref_audio
+}
+
+/// Report each pair of reference clips that overlap, with the time range
+/// and how [`stitch_reference`] resolves it.
+fn warn_reference_overlaps(clips: &[Clip], sr: u32, warnings: &mut Vec<SyncWarning>) {
+    for (i, x) in clips.iter().enumerate() {
+        for y in &clips[i + 1..] {
+            let start = x.timeline_offset_samples.max(y.timeline_offset_samples);
+            let end = x.end_samples().min(y.end_samples());
+            if end <= start {
+                continue;
+            }
+            let kept = match source_quality(x).cmp(&source_quality(y)) {
+                std::cmp::Ordering::Greater => format!("using '{}', the higher-quality source", x.name),
+                std::cmp::Ordering::Less => format!("using '{}', the higher-quality source", y.name),
+                std::cmp::Ordering::Equal => "mixing both".to_string(),
+            };
+            let warning = SyncWarning::new(
+                WarningCode::ReferenceOverlap,
+                WarningSeverity::Warning,
+                format!(
+                    "Reference clips '{}' and '{}' overlap at {:.1}–{:.1} s; {}",
+                    x.name,
+                    y.name,
+                    start as f64 / sr as f64,
+                    end as f64 / sr as f64,
+                    kept
+                ),
+            )
+            .for_clip(&y.file_path);
+            push_warning(warnings, warning);
+        }
+    }
+}
+
+fn stitch_enhanced_timeline(
+    ref_audio: &[f32],
+    tracks: &[Track],
+    placed_clips: &[(usize, usize)],
+    _sr: u32,
+) -> Vec<f32> {
+    if placed_clips.is_empty() {
+        return ref_audio.to_vec();
+    }
+
+    let mut max_end = ref_audio.len();
+    for &(ti, ci) in placed_clips {
+        let end = tracks[ti].clips[ci].end_samples() as usize;
+        if end > max_end {
+            max_end = end;
+        }
     }
 
     let mut enhanced = vec![0.0f32; max_end];
@@ -858,441 +3916,2410 @@ fn stitch_enhanced_timeline(
         enhanced[i] = val;
     }
 
-    for &(ti, ci) in placed_clips {
-        let clip = &tracks[ti].clips[ci];
-        let start = clip.timeline_offset_samples.max(0) as usize;
-        let seg_len = clip.samples.len().min(max_end.saturating_sub(start));
-        if seg_len == 0 {
-            continue;
-        }
+    for &(ti, ci) in placed_clips {
+        let clip = &tracks[ti].clips[ci];
+        let start = clip.timeline_offset_samples.max(0) as usize;
+        let seg_len = clip.samples.len().min(max_end.saturating_sub(start));
+        if seg_len == 0 {
+            continue;
+        }
+
+        for j in 0..seg_len {
+            let existing = enhanced[start + j];
+            let new_val = clip.samples[j];
+            if existing.abs() < 1e-10 {
+                enhanced[start + j] = new_val;
+            } else {
+                enhanced[start + j] = (existing + new_val) / 2.0;
+            }
+        }
+    }
+
+    enhanced
+}
+
+/// Log a warning and add it to the analysis result.
+/// Points per curve written by [`dump_correlation`].
+const DEBUG_CURVE_POINTS: usize = 4000;
+
+/// Write the correlation of clip `ci` against `reference` to
+/// `<dir>/<track>__<clip>.pass<N>.json` as a [`ClipComparison`].
+///
+/// Debug aid only: failures are logged, never fatal.
+fn dump_correlation(
+    dir: &str,
+    pass: u8,
+    track: &Track,
+    ci: usize,
+    reference: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+) {
+    let clip = &track.clips[ci];
+    let comparison = compare_clips(reference, &clip.samples, sr, max_offset_s, DEBUG_CURVE_POINTS);
+    let path = std::path::Path::new(dir).join(format!(
+        "{}__{}.pass{}.json",
+        sanitize_path_part(&track.name),
+        sanitize_path_part(&clip.name),
+        pass
+    ));
+    let written = std::fs::create_dir_all(dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Ok(std::fs::write(&path, serde_json::to_string(&comparison)?)?));
+    match written {
+        Ok(()) => debug!("Wrote correlation curve to {}", path.display()),
+        Err(e) => warn!("Could not write correlation curve {}: {}", path.display(), e),
+    }
+}
+
+fn push_warning(warnings: &mut Vec<SyncWarning>, warning: SyncWarning) {
+    warn!("{}", warning.message);
+    warnings.push(warning);
+}
+
+/// Enforce non-overlap constraint within a single track.
+///
+/// After cross-correlation some clips from the same device may land at
+/// overlapping positions, which is physically impossible (a single camera
+/// records sequentially).  When overlap is detected the track is
+/// re-sequenced using the best-placed clip as an anchor and creation-time
+/// gaps for the rest.
+///
+/// Pinned clips and those in `kept` (incremental analysis) stay where they
+/// are.
+fn fix_intra_track_overlaps(
+    track: &mut Track,
+    sr: u32,
+    kept: &HashSet<String>,
+    clip_offsets: &mut HashMap<String, i64>,
+    warnings: &mut Vec<SyncWarning>,
+) {
+    // Unplaced clips have no position to overlap anything.
+    if track.clips.iter().any(|c| c.unplaced) {
+        let (unplaced, placed): (Vec<Clip>, Vec<Clip>) =
+            std::mem::take(&mut track.clips).into_iter().partition(|c| c.unplaced);
+        track.clips = placed;
+        fix_intra_track_overlaps(track, sr, kept, clip_offsets, warnings);
+        track.clips.extend(unplaced);
+        track.sort_clips_by_time();
+        return;
+    }
+    if track.clips.len() < 2 || track.locked {
+        return;
+    }
+
+    // Sort clips by creation_time (then by name as tiebreaker)
+    track.sort_clips_by_time();
+
+    // Check for overlaps
+    let mut has_overlap = false;
+    for i in 0..track.clips.len() - 1 {
+        let end_i = track.clips[i].timeline_offset_samples
+            + track.clips[i].length_samples() as i64;
+        let start_next = track.clips[i + 1].timeline_offset_samples;
+        if end_i > start_next {
+            has_overlap = true;
+            break;
+        }
+    }
+
+    if !has_overlap {
+        return;
+    }
+
+    let fixed = |c: &Clip| c.is_pinned() || kept.contains(&c.file_path);
+
+    // Anchor on a locked clip if there is one, else the most confident clip
+    let anchor_idx = track
+        .clips
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| {
+            fixed(a).cmp(&fixed(b)).then(
+                a.confidence
+                    .partial_cmp(&b.confidence)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    push_warning(
+        warnings,
+        SyncWarning::new(
+            WarningCode::TrackOverlap,
+            WarningSeverity::Warning,
+            format!(
+                "Track '{}': overlap detected — re-sequencing using '{}' as anchor",
+                track.name, track.clips[anchor_idx].name
+            ),
+        ),
+    );
+
+    // Re-build offsets: walk forward from anchor, then backward
+    // Forward pass: anchor_idx+1 .. end
+    for i in (anchor_idx + 1)..track.clips.len() {
+        if fixed(&track.clips[i]) {
+            continue;
+        }
+        let gap_s = if let (Some(prev_ct), Some(curr_ct)) = (
+            track.clips[i - 1].creation_time,
+            track.clips[i].creation_time,
+        ) {
+            let gap = curr_ct - (prev_ct + track.clips[i - 1].duration_s);
+            gap.max(0.0)
+        } else {
+            0.5
+        };
+
+        let offset = track.clips[i - 1].timeline_offset_samples
+            + track.clips[i - 1].length_samples() as i64
+            + (gap_s * sr as f64) as i64;
+        track.clips[i].timeline_offset_samples = offset;
+        track.clips[i].timeline_offset_s = offset as f64 / sr as f64;
+        clip_offsets.insert(track.clips[i].file_path.clone(), offset);
+    }
+
+    // Backward pass: anchor_idx-1 .. 0
+    for i in (0..anchor_idx).rev() {
+        if fixed(&track.clips[i]) {
+            continue;
+        }
+        let gap_s = if let (Some(curr_ct), Some(next_ct)) = (
+            track.clips[i].creation_time,
+            track.clips[i + 1].creation_time,
+        ) {
+            let gap = next_ct - (curr_ct + track.clips[i].duration_s);
+            gap.max(0.0)
+        } else {
+            0.5
+        };
+
+        let offset = track.clips[i + 1].timeline_offset_samples
+            - track.clips[i].length_samples() as i64
+            - (gap_s * sr as f64) as i64;
+        track.clips[i].timeline_offset_samples = offset;
+        track.clips[i].timeline_offset_s = offset as f64 / sr as f64;
+        clip_offsets.insert(track.clips[i].file_path.clone(), offset);
+    }
+
+    info!(
+        "Track '{}': re-sequenced {} clips around anchor '{}'",
+        track.name,
+        track.clips.len(),
+        track.clips[anchor_idx].name
+    );
+}
+
+/// Frame rate assumed for video that can't be probed (29.97).
+const DEFAULT_VIDEO_FPS: f64 = 30000.0 / 1001.0;
+
+/// A `VideoNotRetimed` warning for each video clip that export will drift
+/// correct by a frame or more: its picture keeps the camera's clock unless
+/// the NLE retimes it.
+fn conform_warnings(tracks: &[Track], config: &SyncConfig) -> Vec<SyncWarning> {
+    let mut warnings = Vec::new();
+    for clip in tracks.iter().flat_map(|t| t.clips.iter()) {
+        if !clip.is_video || !clip.on_timeline() || !stitch::corrects_drift(clip, config) {
+            continue;
+        }
+        let fps = probe_video_info(&clip.file_path)
+            .map_or(DEFAULT_VIDEO_FPS, |(_, _, num, den)| num as f64 / den as f64);
+        let frames = clip.conform_error_frames(fps);
+        if frames.abs() < CONFORM_WARN_FRAMES {
+            continue;
+        }
+        warnings.push(
+            SyncWarning::new(
+                WarningCode::VideoNotRetimed,
+                WarningSeverity::Warning,
+                format!(
+                    "Drift correction ({:+.1} ppm) leaves the picture of '{}' {:.1} frames off its audio \
+                     by the end ({:.3} fps) unless the video is retimed too",
+                    clip.drift_ppm, clip.name, frames.abs(), fps
+                ),
+            )
+            .for_clip(&clip.file_path),
+        );
+    }
+    warnings
+}
+
+/// Give each track's clips too short to measure the track's drift: per
+/// clock (sample rate), the mean of its measured clips' drift weighted by
+/// confidence and length, so one noisy measurement can't speak for the
+/// device. Hand-set values neither give nor take.
+fn propagate_track_drift(tracks: &mut [Track], ref_idx: usize) {
+    for (ti, track) in tracks.iter_mut().enumerate() {
+        if ti == ref_idx {
+            continue;
+        }
+        for rate in track.sample_rates() {
+            let (mut weight, mut ppm, mut conf) = (0.0, 0.0, 0.0);
+            let mut measured = 0;
+            for c in track.clips.iter().filter(|c| c.original_sr == rate) {
+                if c.drift_override || c.drift_inherited || c.drift_ppm.abs() <= 1e-6 || c.drift_confidence <= 0.5 {
+                    continue;
+                }
+                let w = c.drift_confidence * c.duration_s.max(1e-3);
+                weight += w;
+                ppm += w * c.drift_ppm;
+                conf += w * c.drift_confidence;
+                measured += 1;
+            }
+            if weight <= 0.0 {
+                continue;
+            }
+            let (ppm, conf) = (ppm / weight, conf / weight);
+            debug!(
+                "Drift of '{}' at {} Hz: {:.2} ppm from {} measured clip(s)",
+                track.name, rate, ppm, measured
+            );
+            for clip in track.clips.iter_mut().filter(|c| c.original_sr == rate) {
+                if !clip.drift_override && clip.drift_ppm.abs() < 1e-6 && clip.drift_confidence == 0.0 {
+                    clip.drift_ppm = ppm;
+                    clip.drift_confidence = conf;
+                    clip.drift_inherited = true;
+                    debug!("Inherited drift {:.2} ppm for short clip '{}'", ppm, clip.name);
+                }
+            }
+        }
+    }
+}
+
+/// Clips (of one clock) needed to estimate drift across a session.
+const MIN_SESSION_DRIFT_CLIPS: usize = 4;
+
+/// Drift of devices that only record clips too short to measure, from the
+/// whole session: a recorder whose clock runs fast stamps each take a little
+/// later than the reference places it, by an amount that grows with the
+/// session. Regressing the stamp-minus-placement of a track's clips against
+/// their placement recovers the clock rate — assuming, as on most recorders,
+/// that the timestamps come from the same clock as the audio.
+///
+/// Only clips with no drift of their own (measured, inherited or pinned)
+/// take the estimate, and only when it is well above its uncertainty
+/// (whole-second timestamps need a long session and a large drift).
+/// Returns whether any did.
+fn estimate_session_drift(tracks: &mut [Track], ref_idx: usize, sr: u32, threshold_ppm: f64) -> bool {
+    let mut estimated = false;
+    for (ti, track) in tracks.iter_mut().enumerate() {
+        if ti == ref_idx {
+            continue;
+        }
+        for rate in track.sample_rates() {
+            let clock = |c: &&Clip| c.original_sr == rate && c.on_timeline() && c.analyzed;
+            let needs_drift = |c: &Clip| !c.drift_override && c.drift_ppm.abs() < 1e-6 && c.drift_confidence == 0.0;
+            if !track.clips.iter().filter(clock).any(needs_drift) {
+                continue;
+            }
+            let points: Vec<(f64, f64)> = track
+                .clips
+                .iter()
+                .filter(clock)
+                .filter(|c| c.offset_override_s.is_none())
+                .filter_map(|c| Some((c.timeline_offset_s, c.creation_time?)))
+                .collect();
+            if points.len() < MIN_SESSION_DRIFT_CLIPS {
+                continue;
+            }
+            let first_stamp = points[0].1;
+            let times: Vec<f64> = points.iter().map(|p| p.0).collect();
+            let offsets: Vec<f64> = points
+                .iter()
+                .map(|(t, stamp)| (stamp - first_stamp - t) * sr as f64)
+                .collect();
+            let Some(fit) = fit_drift(&times, &offsets) else {
+                continue;
+            };
+
+            // Standard error of the slope, with a robust (MAD) residual spread.
+            let mut abs_res: Vec<f64> = times
+                .iter()
+                .zip(&offsets)
+                .map(|(t, o)| (o - (fit.slope * t + fit.intercept)).abs())
+                .collect();
+            let sigma = 1.4826 * median(&mut abs_res).unwrap_or(0.0);
+            let mean_t = times.iter().sum::<f64>() / times.len() as f64;
+            let spread: f64 = times.iter().map(|t| (t - mean_t).powi(2)).sum();
+            if spread <= 0.0 {
+                continue;
+            }
+            let ppm = fit.slope / sr as f64 * 1e6;
+            let se_ppm = sigma / spread.sqrt() / sr as f64 * 1e6;
+            if ppm.abs() <= threshold_ppm || se_ppm * 3.0 > ppm.abs() {
+                debug!(
+                    "Session drift of '{}' at {} Hz: {:.2} ± {:.2} ppm from {} clips, not used",
+                    track.name,
+                    rate,
+                    ppm,
+                    se_ppm,
+                    points.len()
+                );
+                continue;
+            }
+
+            let confidence = 1.0 - se_ppm / ppm.abs();
+            info!(
+                "Session drift of '{}' at {} Hz: {:.2} ± {:.2} ppm from {} clips' timestamps",
+                track.name,
+                rate,
+                ppm,
+                se_ppm,
+                points.len()
+            );
+            for clip in track.clips.iter_mut().filter(|c| c.original_sr == rate) {
+                if needs_drift(clip) {
+                    clip.drift_ppm = ppm;
+                    clip.drift_confidence = confidence;
+                    clip.drift_inherited = true;
+                    estimated = true;
+                }
+            }
+        }
+    }
+    estimated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_delay_identical() {
+        // A broadband signal correlated with itself should have delay = 0
+        // Use a complex waveform (multiple frequencies) for a sharp correlation peak.
+        let signal: Vec<f32> = (0..4000)
+            .map(|i| {
+                let t = i as f32 / 8000.0;
+                (t * 440.0 * std::f32::consts::TAU).sin()
+                    + 0.7 * (t * 1200.0 * std::f32::consts::TAU).sin()
+                    + 0.3 * (t * 3500.0 * std::f32::consts::TAU).cos()
+                    + 0.5 * (t * 780.0 * std::f32::consts::TAU).sin()
+            })
+            .collect();
+        let (delay, conf) = compute_delay(&signal, &signal, 8000, None);
+        assert_eq!(delay, 0);
+        assert!(conf > 2.0, "Confidence {} should be reasonable for identical signals", conf);
+    }
+
+    #[test]
+    fn test_compute_delay_shifted() {
+        // Create a reference and a delayed copy
+        let sr = 8000u32;
+        let delay_samples = 400i64; // 50ms at 8kHz
+        let len = 4000;
+
+        let reference: Vec<f32> = (0..len + delay_samples as usize)
+            .map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.13).cos() * 0.5)
+            .collect();
+        let target: Vec<f32> = reference[delay_samples as usize..].to_vec();
+
+        let (detected_delay, conf) = compute_delay(&reference, &target, sr, None);
+        assert!(
+            (detected_delay - delay_samples).abs() <= 1,
+            "Expected delay ~{}, got {}",
+            delay_samples,
+            detected_delay
+        );
+        assert!(conf > 3.0, "Confidence should be reasonable");
+    }
+
+    #[test]
+    fn test_subsample_peak() {
+        let data = vec![0.0f32, 0.5, 1.0, 0.8, 0.2];
+        let peak = subsample_peak(&data, 2);
+        assert!(peak > 1.5 && peak < 2.5, "Subsample peak = {}", peak);
+    }
+
+    #[test]
+    fn test_compute_delay_empty_reference() {
+        let reference: Vec<f32> = vec![];
+        let target: Vec<f32> = vec![1.0, 2.0, 3.0];
+        let (delay, conf) = compute_delay(&reference, &target, 8000, None);
+        assert_eq!(delay, 0);
+        assert_eq!(conf, 0.0);
+    }
+
+    #[test]
+    fn test_compute_delay_empty_target() {
+        let reference: Vec<f32> = vec![1.0, 2.0, 3.0];
+        let target: Vec<f32> = vec![];
+        let (delay, conf) = compute_delay(&reference, &target, 8000, None);
+        assert_eq!(delay, 0);
+        assert_eq!(conf, 0.0);
+    }
+
+    #[test]
+    fn test_compute_delay_with_max_offset() {
+        let sr = 8000u32;
+        let delay_samples = 400i64;
+        let len = 4000;
+
+        let reference: Vec<f32> = (0..len + delay_samples as usize)
+            .map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.13).cos() * 0.5)
+            .collect();
+        let target: Vec<f32> = reference[delay_samples as usize..].to_vec();
+
+        // With sufficient max_offset, should find the delay
+        let (detected, _) = compute_delay(&reference, &target, sr, Some(1.0));
+        assert!(
+            (detected - delay_samples).abs() <= 1,
+            "Expected ~{}, got {}",
+            delay_samples,
+            detected
+        );
+
+        // With very small max_offset, might not find the correct delay
+        let (detected_limited, _) = compute_delay(&reference, &target, sr, Some(0.01));
+        // The result should still be valid (not crash), though may not match
+        let _ = detected_limited;
+    }
+
+    #[test]
+    fn test_compute_delay_negative_delay() {
+        // Target starts before reference in the correlation
+        let sr = 8000u32;
+        let len = 4000;
+
+        let signal: Vec<f32> = (0..len)
+            .map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.13).cos() * 0.5)
+            .collect();
+
+        // Reference is a subset that starts later
+        let reference = signal[200..].to_vec();
+        let target = signal.clone();
+
+        let (delay, _conf) = compute_delay(&reference, &target, sr, None);
+        // Delay should be negative (target needs to shift left)
+        assert!(delay < 0, "Expected negative delay, got {}", delay);
+    }
+
+    #[test]
+    fn test_apply_drift_correction_identity() {
+        let audio = vec![1.0f32, 2.0, 3.0, 4.0, 5.0];
+        let result = apply_drift_correction(&audio, 0.0);
+        assert_eq!(result.len(), audio.len());
+    }
+
+    #[test]
+    fn test_apply_drift_correction_positive() {
+        let audio: Vec<f32> = (0..10000).map(|i| (i as f32 * 0.01).sin()).collect();
+        let result = apply_drift_correction(&audio, 100.0); // 100 ppm
+        // Corrected should be slightly shorter
+        assert!(result.len() < audio.len(), "Expected shorter output");
+        assert!(result.len() > audio.len() - 10, "Should be close to original length");
+    }
+
+    #[test]
+    fn test_apply_drift_correction_negative() {
+        let audio: Vec<f32> = (0..10000).map(|i| (i as f32 * 0.01).sin()).collect();
+        let result = apply_drift_correction(&audio, -100.0); // -100 ppm
+        // Corrected should be slightly longer
+        assert!(result.len() > audio.len(), "Expected longer output");
+        assert!(result.len() < audio.len() + 10, "Should be close to original length");
+    }
+
+    #[test]
+    fn test_select_reference_index_by_duration() {
+        let mut tracks = vec![
+            Track::new("Short".into()),
+            Track::new("Long".into()),
+        ];
+        let mut c1 = Clip::new("a.wav".into(), "a.wav".into(), 48000, 1);
+        c1.duration_s = 5.0;
+        c1.samples = vec![0.0; 40000];
+        tracks[0].clips.push(c1);
+
+        let mut c2 = Clip::new("b.wav".into(), "b.wav".into(), 48000, 1);
+        c2.duration_s = 60.0;
+        c2.samples = vec![0.0; 480000];
+        tracks[1].clips.push(c2);
+
+        let idx = select_reference_index(&tracks, &ReferenceStrategy::Coverage);
+        assert_eq!(idx, 1, "Longer track should be reference");
+    }
+
+    #[test]
+    fn test_select_reference_user_override() {
+        let mut tracks = vec![
+            Track::new("Short".into()),
+            Track::new("Long".into()),
+        ];
+        tracks[0].is_reference = true;
+        let mut c = Clip::new("a.wav".into(), "a.wav".into(), 48000, 1);
+        c.duration_s = 5.0;
+        tracks[0].clips.push(c);
+        let mut c2 = Clip::new("b.wav".into(), "b.wav".into(), 48000, 1);
+        c2.duration_s = 60.0;
+        tracks[1].clips.push(c2);
+
+        let idx = select_reference_index(&tracks, &ReferenceStrategy::Coverage);
+        assert_eq!(idx, 0, "User override should win");
+    }
+
+    #[test]
+    fn test_select_reference_by_strategy() {
+        let mut tracks = vec![
+            Track::new("Mixer".into()),
+            Track::new("Cam".into()),
+        ];
+        // Mixer: short and clean. Cam: long, with a noisy floor.
+        let mut c1 = Clip::new("a.wav".into(), "a.wav".into(), 48000, 1);
+        c1.duration_s = 5.0;
+        c1.samples = (0..40000)
+            .map(|i| if (i / 4000) % 2 == 0 { (i as f32 * 0.3).sin() * 0.5 } else { 0.0 })
+            .collect();
+        tracks[0].clips.push(c1);
+        let mut c2 = Clip::new("b.wav".into(), "b.wav".into(), 48000, 1);
+        c2.duration_s = 60.0;
+        c2.samples = (0..480000)
+            .map(|i| (i as f32 * 0.3).sin() * 0.5 + ((i % 7) as f32 - 3.0) * 0.05)
+            .collect();
+        tracks[1].clips.push(c2);
+
+        assert_eq!(select_reference_index(&tracks, &ReferenceStrategy::Duration), 1);
+        assert_eq!(select_reference_index(&tracks, &ReferenceStrategy::Quality), 0);
+        let named = ReferenceStrategy::Track("mixer".into());
+        tracks[1].is_reference = true;
+        assert_eq!(select_reference_index(&tracks, &named), 0, "Named track beats the mark");
+        let missing = ReferenceStrategy::Track("Boom".into());
+        assert_eq!(select_reference_index(&tracks, &missing), 1, "Falls back to the mark");
+    }
+
+    #[test]
+    fn test_analyze_empty_tracks() {
+        let mut tracks: Vec<Track> = vec![];
+        let config = SyncConfig::default();
+        let result = analyze(&mut tracks, &config, &None, &None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_analyze_single_track_single_clip() {
+        let mut tracks = vec![Track::new("Cam".into())];
+        let mut clip = Clip::new("test.wav".into(), "test.wav".into(), 48000, 1);
+        clip.duration_s = 2.0;
+        clip.samples = (0..16000)
+            .map(|i| (i as f32 * 0.05).sin())
+            .collect();
+        tracks[0].clips.push(clip);
+
+        let config = SyncConfig::default();
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+
+        assert_eq!(result.reference_track_index, 0);
+        assert!(tracks[0].is_reference);
+        assert!(tracks[0].clips[0].analyzed);
+        assert_eq!(tracks[0].clips[0].timeline_offset_samples, 0);
+    }
+
+    #[test]
+    fn test_analyze_reports_progress() {
+        let signal = noise(9, 40_000);
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        for (ti, range) in [(0, 0..40_000), (1, 8_000..24_000), (1, 24_000..36_000)] {
+            let mut clip = Clip::new(format!("{}-{}.wav", ti, range.start), "c.wav".into(), 48000, 1);
+            clip.samples = signal[range].to_vec();
+            clip.duration_s = clip.samples.len() as f64 / ANALYSIS_SR as f64;
+            tracks[ti].clips.push(clip);
+        }
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let progress: Option<ProgressCallback> =
+            Some(Box::new(move |event: &ProgressEvent| sink.lock().unwrap().push(event.clone())));
+        analyze(&mut tracks, &SyncConfig::default(), &progress, &None).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].phase, ProgressPhase::Preparing);
+        assert!(events.windows(2).all(|w| w[1].fraction >= w[0].fraction));
+        let correlating: Vec<_> = events
+            .iter()
+            .filter(|e| e.phase == ProgressPhase::Correlating)
+            .map(|e| (e.current, e.total))
+            .collect();
+        assert_eq!(correlating, [(1, 2), (2, 2)]);
+        let last = events.last().unwrap();
+        assert_eq!((last.phase, last.fraction, last.eta_s), (ProgressPhase::Done, 1.0, None));
+    }
+
+    #[test]
+    fn test_analyze_reports_progress_once_across_sync_groups() {
+        let signal = noise(10, 40_000);
+        let mut tracks: Vec<Track> = ["Room A", "Cam A", "Room B", "Cam B"]
+            .iter()
+            .map(|name| Track::new(name.to_string()))
+            .collect();
+        for (ti, range) in [(0, 0..40_000), (1, 8_000..24_000), (2, 0..40_000), (3, 16_000..32_000)] {
+            let mut clip = Clip::new(format!("{}.wav", ti), "c.wav".into(), 48000, 1);
+            clip.samples = signal[range].to_vec();
+            clip.duration_s = clip.samples.len() as f64 / ANALYSIS_SR as f64;
+            tracks[ti].clips.push(clip);
+        }
+        tracks[2].sync_group = Some("B".into());
+        tracks[3].sync_group = Some("B".into());
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let progress: Option<ProgressCallback> =
+            Some(Box::new(move |event: &ProgressEvent| sink.lock().unwrap().push(event.clone())));
+        let result = analyze(&mut tracks, &SyncConfig::default(), &progress, &None).unwrap();
+        assert_eq!(result.groups.len(), 2);
+
+        let events = events.lock().unwrap();
+        assert!(events.windows(2).all(|w| w[1].fraction >= w[0].fraction));
+        let done: Vec<_> = events.iter().filter(|e| e.phase == ProgressPhase::Done).collect();
+        assert_eq!(done.len(), 1);
+        assert_eq!(events.last().unwrap().phase, ProgressPhase::Done);
+        assert!(events.iter().any(|e| e.phase != ProgressPhase::Done && e.fraction >= 0.5));
+    }
+
+    #[test]
+    fn test_metadata_fallback_pre_roll() {
+        let signal = noise(5, 40_000);
+        // A silent clip can't be correlated, so only its creation time
+        // places it: 10 s before the reference started.
+        let session = || {
+            let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+            let clips = [(0, "ref.wav", signal.clone(), 1_000.0), (1, "pre.wav", vec![0.0; 16_000], 990.0)];
+            for (ti, path, samples, ct) in clips {
+                let mut clip = Clip::new(path.into(), path.into(), 48000, 1);
+                clip.samples = samples;
+                clip.duration_s = clip.samples.len() as f64 / ANALYSIS_SR as f64;
+                clip.creation_time = Some(ct);
+                tracks[ti].clips.push(clip);
+            }
+            tracks
+        };
+
+        let mut tracks = session();
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(tracks[1].clips[0].timeline_offset_s, 0.0);
+        assert_eq!(tracks[0].clips[0].timeline_offset_s, 10.0);
+        assert!(result.warnings.iter().any(|w| w.code == WarningCode::MetadataFallback));
+
+        let config = SyncConfig {
+            place_pre_roll: false,
+            ..SyncConfig::default()
+        };
+        let mut tracks = session();
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+        assert_eq!(tracks[0].clips[0].timeline_offset_s, 0.0);
+        let warning = result.warnings.iter().find(|w| w.code == WarningCode::BeforeReference).unwrap();
+        assert_eq!(warning.clip_id.as_deref(), Some("pre.wav"));
+        assert!(warning.message.contains("10.0 s before"), "{}", warning.message);
+    }
+
+    #[test]
+    fn test_conform_warnings_for_drift_corrected_video() {
+        let mut track = Track::new("Cam".into());
+        let clips = [("a.mov", true, 600.0), ("b.mov", true, 60.0), ("c.wav", false, 600.0)];
+        for (path, is_video, duration_s) in clips {
+            let mut clip = Clip::new(path.into(), path.into(), 48000, 2);
+            clip.is_video = is_video;
+            clip.duration_s = duration_s;
+            clip.drift_ppm = 100.0;
+            clip.drift_confidence = 0.9;
+            track.clips.push(clip);
+        }
+        let tracks = vec![track];
+        let config = SyncConfig::default();
+
+        // Only the long video clip drifts a frame or more by its end.
+        let warnings = conform_warnings(&tracks, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, WarningCode::VideoNotRetimed);
+        assert_eq!(warnings[0].clip_id.as_deref(), Some("a.mov"));
+        assert!(warnings[0].message.contains("1.8 frames"), "{}", warnings[0].message);
+
+        let off = SyncConfig {
+            drift_correction: false,
+            ..SyncConfig::default()
+        };
+        assert!(conform_warnings(&tracks, &off).is_empty());
+    }
+
+    #[test]
+    fn test_analyze_records_diagnostics() {
+        let signal = noise(9, 40_000);
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        for (ti, path, samples) in [
+            (0, "ref.wav", signal.clone()),
+            (1, "match.wav", signal[8_000..24_000].to_vec()),
+            (1, "other.wav", noise(77, 16_000)),
+        ] {
+            let mut clip = Clip::new(path.into(), path.into(), 48000, 1);
+            clip.samples = samples;
+            clip.duration_s = clip.samples.len() as f64 / ANALYSIS_SR as f64;
+            tracks[ti].clips.push(clip);
+        }
+
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert!(!result.diagnostics.contains_key("ref.wav"));
+        let matched = &result.diagnostics["match.wav"];
+        assert_eq!(matched.method, MatchMethod::Correlation);
+        assert!((matched.overlap_s - 2.0).abs() < 0.1, "overlap {}", matched.overlap_s);
+        assert!(matched.secondary_peak_ratio.unwrap() < 0.5);
+        assert!(matched.peak_sharpness > 10.0);
+        assert!(matched.snr_db.is_some());
+        let other = &result.diagnostics["other.wav"];
+        assert_ne!(other.method, MatchMethod::Correlation);
+        assert!(other.peak_sharpness < matched.peak_sharpness);
+    }
+
+    #[test]
+    fn test_analyze_two_tracks_synthetic() {
+        // Create two tracks with related signals
+        let sr = ANALYSIS_SR;
+        let len = 32000usize; // 4 seconds at 8kHz
+        let delay_samples = 800i64; // 100ms
+
+        let signal: Vec<f32> = (0..len + delay_samples as usize)
+            .map(|i| {
+                let t = i as f32 / sr as f32;
+                (t * 440.0 * std::f32::consts::TAU).sin()
+                    + 0.5 * (t * 1100.0 * std::f32::consts::TAU).sin()
+                    + 0.3 * (t * 2200.0 * std::f32::consts::TAU).cos()
+            })
+            .collect();
+
+        let ref_samples = signal.clone();
+        let tgt_samples: Vec<f32> = signal[delay_samples as usize..].to_vec();
+
+        let mut tracks = vec![
+            Track::new("RefDev".into()),
+            Track::new("Target".into()),
+        ];
+
+        let mut ref_clip = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        ref_clip.duration_s = ref_samples.len() as f64 / sr as f64;
+        ref_clip.samples = ref_samples;
+        tracks[0].clips.push(ref_clip);
+
+        let mut tgt_clip = Clip::new("tgt.wav".into(), "tgt.wav".into(), 48000, 1);
+        tgt_clip.duration_s = tgt_samples.len() as f64 / sr as f64;
+        tgt_clip.samples = tgt_samples;
+        tracks[1].clips.push(tgt_clip);
+
+        let config = SyncConfig::default();
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+
+        // Reference should be track 0 (longer)
+        assert_eq!(result.reference_track_index, 0);
+
+        // Target clip should have offset close to delay_samples
+        let tgt_offset = tracks[1].clips[0].timeline_offset_samples;
+        assert!(
+            (tgt_offset - delay_samples).abs() <= 2,
+            "Expected offset ~{}, got {}",
+            delay_samples,
+            tgt_offset
+        );
+
+        // Confidence should be reasonable
+        assert!(
+            tracks[1].clips[0].confidence > 2.0,
+            "Confidence {} too low",
+            tracks[1].clips[0].confidence
+        );
+    }
+
+    #[test]
+    fn test_analyze_flags_mostly_silent_clip() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(7, sr as usize * 20);
+        let delay = sr as usize * 5;
+
+        // 8 s clip: half a second of shared sound, the rest digital silence.
+        let mut silent = vec![0.0f32; sr as usize * 8];
+        silent[..sr as usize / 2].copy_from_slice(&reference[delay..delay + sr as usize / 2]);
+        // 8 s clip that is sound throughout.
+        let full = reference[delay..delay + sr as usize * 8].to_vec();
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Quiet".into()), Track::new("Full".into())];
+        for (ti, samples) in [(0, reference), (1, silent), (2, full)] {
+            let name = format!("clip{}.wav", ti);
+            let mut clip = Clip::new(name.clone(), name, 48000, 1);
+            clip.duration_s = samples.len() as f64 / sr as f64;
+            clip.samples = samples;
+            tracks[ti].clips.push(clip);
+        }
+
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+
+        let quiet = &tracks[1].clips[0];
+        assert_eq!(quiet.match_score, 0.0);
+        assert!(result.warnings.iter().any(|w| w.code == WarningCode::InsufficientSharedAudio
+            && w.clip_id.as_deref() == Some("clip1.wav")));
+
+        let full = &tracks[2].clips[0];
+        assert!((full.timeline_offset_samples - delay as i64).abs() <= 2);
+        assert!(full.match_score >= CONFIDENCE_THRESHOLD);
+        assert!(!result.warnings.iter().any(|w| w.clip_id.as_deref() == Some("clip2.wav")));
+    }
+
+    #[test]
+    fn test_analyze_leaves_clips_from_another_session_unplaced() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(11, sr as usize * 20);
+        let delay = sr as usize * 5;
+        let cam = reference[delay..delay + sr as usize * 8].to_vec();
+        // Recorded the next day: nothing in common, but a creation time the
+        // metadata fallback would have used.
+        let stray = noise(12, sr as usize * 8);
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into()), Track::new("Stray".into())];
+        for (ti, samples, ct) in [(0, reference, 1000.0), (1, cam, 1005.0), (2, stray, 1000.0 + 86400.0)] {
+            let name = format!("clip{}.wav", ti);
+            let mut clip = Clip::new(name.clone(), name, 48000, 1);
+            clip.duration_s = samples.len() as f64 / sr as f64;
+            clip.samples = samples;
+            clip.creation_time = Some(ct);
+            tracks[ti].clips.push(clip);
+        }
+
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+
+        let stray = &tracks[2].clips[0];
+        assert!(stray.unplaced && !stray.analyzed);
+        assert_eq!(result.unplaced_clips, ["clip2.wav"]);
+        assert!(!result.clip_offsets.contains_key("clip2.wav"));
+        let codes: Vec<WarningCode> = result
+            .warnings
+            .iter()
+            .filter(|w| w.clip_id.as_deref() == Some("clip2.wav"))
+            .map(|w| w.code)
+            .collect();
+        assert_eq!(codes, [WarningCode::NotInSession]);
+        // The timeline is the session's, not stretched to the next day.
+        assert!((result.total_timeline_s - 20.0).abs() < 0.01);
+        assert!(!tracks[1].clips[0].unplaced);
+        assert!((tracks[1].clips[0].timeline_offset_samples - delay as i64).abs() <= 2);
+    }
+
+    #[test]
+    fn test_band_pass_response() {
+        let sr = ANALYSIS_SR;
+        let tone = |hz: f32| -> Vec<f32> {
+            (0..sr as usize * 2)
+                .map(|i| (i as f32 / sr as f32 * hz * std::f32::consts::TAU).sin())
+                .collect()
+        };
+        // RMS over the middle second, away from the filter's edge transients.
+        let rms = |x: &[f32]| {
+            let mid = &x[sr as usize / 2..sr as usize * 3 / 2];
+            (mid.iter().map(|v| v * v).sum::<f32>() / mid.len() as f32).sqrt()
+        };
+        let gain_db = |hz: f32| {
+            let input = tone(hz);
+            20.0 * (rms(&band_pass(&input, sr, 200.0, 3500.0)) / rms(&input)).log10()
+        };
+        assert!(gain_db(1000.0).abs() < 1.0, "passband {:.1} dB", gain_db(1000.0));
+        assert!(gain_db(50.0) < -40.0, "hum only {:.1} dB down", gain_db(50.0));
+    }
+
+    #[test]
+    fn test_band_pass_ignores_hum_when_correlating() {
+        let sr = ANALYSIS_SR;
+        let speech: Vec<f32> = noise(31, sr as usize * 12).into_iter().map(|s| 0.1 * s).collect();
+        // Each device picks up the same 40 Hz hum, but out of phase.
+        let hum = |i: usize, phase: f32| {
+            (i as f32 / sr as f32 * 40.0 * std::f32::consts::TAU + phase).sin()
+        };
+        let delay = sr as usize * 3;
+        let reference: Vec<f32> = speech.iter().enumerate().map(|(i, s)| s + hum(i, 0.0)).collect();
+        let target: Vec<f32> = speech[delay..delay + sr as usize * 6]
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s + hum(i, 2.0))
+            .collect();
 
-        for j in 0..seg_len {
-            let existing = enhanced[start + j];
-            let new_val = clip.samples[j];
-            if existing.abs() < 1e-10 {
-                enhanced[start + j] = new_val;
-            } else {
-                enhanced[start + j] = (existing + new_val) / 2.0;
+        let run = |band: Option<(f64, f64)>| {
+            let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+            for (ti, samples) in [(0, reference.clone()), (1, target.clone())] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.samples = samples;
+                tracks[ti].clips.push(clip);
             }
-        }
+            let config = SyncConfig {
+                correlation_band_hz: band,
+                ..Default::default()
+            };
+            analyze(&mut tracks, &config, &None, &None).unwrap();
+            tracks[1].clips[0].timeline_offset_samples
+        };
+
+        assert!((run(None) - delay as i64).abs() > 2, "hum should mislead the full-band match");
+        assert!((run(Some((200.0, 3500.0))) - delay as i64).abs() <= 2);
     }
 
-    enhanced
-}
+    #[test]
+    fn test_device_band_weighting_ignores_hum() {
+        let sr = ANALYSIS_SR;
+        let speech: Vec<f32> = noise(37, sr as usize * 12).into_iter().map(|s| 0.1 * s).collect();
+        let hum = |i: usize, phase: f32| {
+            (i as f32 / sr as f32 * 40.0 * std::f32::consts::TAU + phase).sin()
+        };
+        let delay = sr as usize * 3;
+        let reference: Vec<f32> = speech.iter().enumerate().map(|(i, s)| s + hum(i, 0.0)).collect();
+        let target: Vec<f32> = speech[delay..delay + sr as usize * 6]
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s + hum(i, 2.0))
+            .collect();
 
-/// Enforce non-overlap constraint within a single track.
-///
-/// After cross-correlation some clips from the same device may land at
-/// overlapping positions, which is physically impossible (a single camera
-/// records sequentially).  When overlap is detected the track is
-/// re-sequenced using the best-placed clip as an anchor and creation-time
-/// gaps for the rest.
-fn fix_intra_track_overlaps(
-    track: &mut Track,
-    sr: u32,
-    clip_offsets: &mut HashMap<String, i64>,
-    warnings: &mut Vec<String>,
-) {
-    if track.clips.len() < 2 {
-        return;
+        let run = |profiles: &[(TrackRole, BandWeighting)], own: Option<BandWeighting>| {
+            let mut tracks = vec![Track::new("Zoom".into()), Track::new("Cam A".into())];
+            for (ti, samples) in [(0, reference.clone()), (1, target.clone())] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.samples = samples;
+                tracks[ti].clips.push(clip);
+            }
+            tracks[1].band_weighting = own;
+            let config = SyncConfig {
+                device_band_weighting: profiles.iter().copied().collect(),
+                ..Default::default()
+            };
+            analyze(&mut tracks, &config, &None, &None).unwrap();
+            tracks[1].clips[0].timeline_offset_samples
+        };
+        let cut = BandWeighting { high_pass_hz: Some(150.0), ..Default::default() };
+        let speech_up = BandWeighting { speech_emphasis_db: 6.0, ..cut };
+
+        assert!((run(&[], None) - delay as i64).abs() > 2, "hum should mislead the flat match");
+        let profiles = [(TrackRole::Dialog, cut), (TrackRole::Camera, speech_up)];
+        assert!((run(&profiles, None) - delay as i64).abs() <= 2);
+        // The track's own weighting overrides its role's profile.
+        let flat = BandWeighting::default();
+        assert!((run(&profiles, Some(flat)) - delay as i64).abs() > 2);
     }
 
-    // Sort clips by creation_time (then by name as tiebreaker)
-    track.sort_clips_by_time();
+    #[test]
+    fn test_detects_inverted_polarity() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(41, sr as usize * 10);
+        let delay = sr as usize * 2 + 321;
+        let run = |sign: f32| {
+            let target: Vec<f32> =
+                reference[delay..delay + sr as usize * 4].iter().map(|s| sign * s).collect();
+            let mut tracks = vec![Track::new("Recorder".into()), Track::new("Cam".into())];
+            for (ti, samples) in [(0, reference.clone()), (1, target)] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.samples = samples;
+                tracks[ti].clips.push(clip);
+            }
+            let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+            let flagged = result.warnings.iter().any(|w| w.code == WarningCode::PolarityInverted);
+            (tracks.remove(1).clips.remove(0), flagged)
+        };
 
-    // Check for overlaps
-    let mut has_overlap = false;
-    for i in 0..track.clips.len() - 1 {
-        let end_i = track.clips[i].timeline_offset_samples
-            + track.clips[i].length_samples() as i64;
-        let start_next = track.clips[i + 1].timeline_offset_samples;
-        if end_i > start_next {
-            has_overlap = true;
-            break;
-        }
+        let (upright, flagged) = run(1.0);
+        assert!(!upright.polarity_inverted && !flagged);
+        let (inverted, flagged) = run(-1.0);
+        assert_eq!(inverted.timeline_offset_samples, delay as i64);
+        assert!(inverted.polarity_inverted && flagged);
+        // Stitching multiplies by the gain factor, which flips it back.
+        assert_eq!(inverted.gain_factor(), -1.0);
     }
 
-    if !has_overlap {
-        return;
-    }
+    #[test]
+    fn test_estimates_max_offset_from_timestamps() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(43, sr as usize * 10);
+        let delay = sr as usize * 2 + 99;
+        let run = |stamps: [Option<f64>; 2], max_offset_s: Option<f64>| {
+            let mut tracks = vec![Track::new("Recorder".into()), Track::new("Cam".into())];
+            let target = reference[delay..delay + sr as usize * 4].to_vec();
+            for (ti, samples) in [(0, reference.clone()), (1, target)] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.creation_time = stamps[ti];
+                clip.samples = samples;
+                tracks[ti].clips.push(clip);
+            }
+            let config = SyncConfig { max_offset_s, ..Default::default() };
+            let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+            assert_eq!(tracks[1].clips[0].timeline_offset_samples, delay as i64);
+            result.warnings.into_iter().find(|w| w.code == WarningCode::MaxOffsetEstimated)
+        };
 
-    // Find the clip with the best (highest) confidence to use as anchor
-    let anchor_idx = track
-        .clips
-        .iter()
-        .enumerate()
-        .max_by(|(_, a), (_, b)| {
-            a.confidence
-                .partial_cmp(&b.confidence)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        })
-        .map(|(i, _)| i)
-        .unwrap_or(0);
+        // Ten seconds from first start to last end, plus two minutes' slack.
+        let warning = run([Some(1000.0), Some(1002.0)], None).unwrap();
+        assert_eq!(warning.severity, WarningSeverity::Info);
+        assert!(warning.message.contains("±130 s"), "{}", warning.message);
+        // A clip without a timestamp, or a bound given, leaves it alone.
+        assert!(run([Some(1000.0), None], None).is_none());
+        assert!(run([Some(1000.0), Some(1002.0)], Some(60.0)).is_none());
+    }
 
-    let msg = format!(
-        "Track '{}': overlap detected — re-sequencing using '{}' as anchor",
-        track.name, track.clips[anchor_idx].name
-    );
-    warnings.push(msg.clone());
-    warn!("{}", msg);
+    #[test]
+    fn test_match_within_estimate_retries_unbounded() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(44, sr as usize * 10);
+        let delay = sr as usize * 6 + 99;
+        let mut tracks = vec![Track::new("Cam".into())];
+        let mut clip = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        clip.samples = reference[delay..delay + sr as usize * 3].to_vec();
+        tracks[0].clips.push(clip);
 
-    // Re-build offsets: walk forward from anchor, then backward
-    // Forward pass: anchor_idx+1 .. end
-    for i in (anchor_idx + 1)..track.clips.len() {
-        let gap_s = if let (Some(prev_ct), Some(curr_ct)) = (
-            track.clips[i - 1].creation_time,
-            track.clips[i].creation_time,
-        ) {
-            let gap = curr_ct - (prev_ct + track.clips[i - 1].duration_s);
-            gap.max(0.0)
-        } else {
-            0.5
+        // A clock off by more than the slack puts the clip outside the bound.
+        let bounded = SyncConfig { max_offset_s: Some(2.0), ..Default::default() };
+        let unbounded = SyncConfig::default();
+        let run = |fallback| {
+            match_within_estimate(&reference, &[], &tracks, (0, 0), None, (&bounded, fallback), &None)
         };
+        assert!(run(None).0.score < CONFIDENCE_THRESHOLD);
+        let (m, _, _) = run(Some(&unbounded));
+        assert_eq!(m.delay_samples, delay as i64);
+        assert!(m.score >= CONFIDENCE_THRESHOLD);
+    }
 
-        let offset = track.clips[i - 1].timeline_offset_samples
-            + track.clips[i - 1].length_samples() as i64
-            + (gap_s * sr as f64) as i64;
-        track.clips[i].timeline_offset_samples = offset;
-        track.clips[i].timeline_offset_s = offset as f64 / sr as f64;
-        clip_offsets.insert(track.clips[i].file_path.clone(), offset);
+    /// Mix a clap (a sharp burst ringing out over ~5 ms) in at `clap_s`.
+    fn add_clap(samples: &mut [f32], sr: u32, clap_s: f64, gain: f32) {
+        let at = (clap_s * sr as f64) as usize;
+        for (i, s) in samples[at..at + sr as usize / 10].iter_mut().enumerate() {
+            let t = i as f32 / sr as f32;
+            *s += gain * (-t / 0.005).exp() * if i % 2 == 0 { 1.0 } else { -0.8 };
+        }
     }
 
-    // Backward pass: anchor_idx-1 .. 0
-    for i in (0..anchor_idx).rev() {
-        let gap_s = if let (Some(curr_ct), Some(next_ct)) = (
-            track.clips[i].creation_time,
-            track.clips[i + 1].creation_time,
-        ) {
-            let gap = next_ct - (curr_ct + track.clips[i].duration_s);
-            gap.max(0.0)
-        } else {
-            0.5
+    #[test]
+    fn test_detect_transients_finds_clap() {
+        let sr = ANALYSIS_SR;
+        let mut samples: Vec<f32> = noise(3, sr as usize * 8).into_iter().map(|s| 0.02 * s).collect();
+        add_clap(&mut samples, sr, 3.25, 1.0);
+        let claps = detect_transients(&samples, sr);
+        assert_eq!(claps.len(), 1, "{:?}", claps);
+        assert!((claps[0].time_s - 3.25).abs() < 0.005);
+        assert!(claps[0].strength_db > CLAP_ONSET_DB);
+
+        // A sustained tone starting suddenly is not a clap.
+        let tone: Vec<f32> = (0..sr as usize * 4)
+            .map(|i| if i < sr as usize { 0.0 } else { (i as f32 * 0.3).sin() })
+            .collect();
+        assert!(detect_transients(&tone, sr).is_empty());
+    }
+
+    #[test]
+    fn test_slate_prior_resolves_repeating_audio() {
+        let sr = ANALYSIS_SR;
+        // A 2 s loop (think a generator or a music bed) with one slate clap.
+        let period: Vec<f32> = noise(11, sr as usize * 2).into_iter().map(|s| 0.3 * s).collect();
+        let mut reference: Vec<f32> = period.iter().cycle().take(period.len() * 8).copied().collect();
+        add_clap(&mut reference, sr, 9.3, 2.0);
+        let delay = sr as usize * 7;
+        let target = reference[delay..delay + sr as usize * 6].to_vec();
+
+        let run = |slate_prior: bool| {
+            let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+            for (ti, samples) in [(0, reference.clone()), (1, target.clone())] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.samples = samples;
+                tracks[ti].clips.push(clip);
+            }
+            let config = SyncConfig {
+                slate_prior,
+                ..Default::default()
+            };
+            analyze(&mut tracks, &config, &None, &None).unwrap();
+            let clip = &tracks[1].clips[0];
+            (clip.timeline_offset_samples, clip.match_score)
         };
 
-        let offset = track.clips[i + 1].timeline_offset_samples
-            - track.clips[i].length_samples() as i64
-            - (gap_s * sr as f64) as i64;
-        track.clips[i].timeline_offset_samples = offset;
-        track.clips[i].timeline_offset_s = offset as f64 / sr as f64;
-        clip_offsets.insert(track.clips[i].file_path.clone(), offset);
+        let (_, plain_score) = run(false);
+        assert!(plain_score < CONFIDENCE_THRESHOLD, "loop should be ambiguous: {}", plain_score);
+        let (offset, score) = run(true);
+        assert!((offset - delay as i64).abs() <= 2, "offset {}", offset);
+        assert!(score >= CONFIDENCE_THRESHOLD, "score {}", score);
     }
 
-    info!(
-        "Track '{}': re-sequenced {} clips around anchor '{}'",
-        track.name,
-        track.clips.len(),
-        track.clips[anchor_idx].name
-    );
-}
-
-fn inherit_drift_for_short_clips(tracks: &mut [Track], ref_idx: usize) {
-    for ti in 0..tracks.len() {
-        if ti == ref_idx {
-            continue;
+    #[test]
+    fn test_overlap_normalization_removes_long_overlap_bias() {
+        let sr = 8000u32;
+        // Both devices pick up the same mains hum. The target started 6 s
+        // before the reference, so only its last 2 s are shared.
+        let hum = |i: usize| 0.3 * (2.0 * std::f32::consts::PI * 50.0 * i as f32 / sr as f32).sin();
+        let reference: Vec<f32> =
+            noise(11, sr as usize * 10).into_iter().enumerate().map(|(i, x)| x + hum(i)).collect();
+        let lead = sr as usize * 6;
+        let mut target: Vec<f32> =
+            noise(12, lead).into_iter().enumerate().map(|(i, x)| x + hum(i)).collect();
+        target.extend_from_slice(&reference[..sr as usize * 2]);
+
+        // Raw sums favour lags where all 8 s overlap and only the hum lines up.
+        let (raw, _) =
+            full_rate_match(&reference, &target, sr, None, 1, CorrelationNormalization::Raw, &None);
+        assert_ne!(raw.delay_samples, -(lead as i64));
+        let (m, candidates) =
+            full_rate_match(&reference, &target, sr, None, 1, CorrelationNormalization::Overlap, &None);
+        assert_eq!(m.delay_samples, -(lead as i64));
+        assert!(candidates[0].ncc > 0.99, "ncc {}", candidates[0].ncc);
+        assert!(m.score >= CONFIDENCE_THRESHOLD, "score {}", m.score);
+
+        // Streaming overlap energies agree with computing each lag directly.
+        let correlation = fft_correlate(&reference, &target, &None);
+        let mut energy = OverlapEnergy::new(&reference, &target, sr);
+        for idx in (0..correlation.len()).step_by(997) {
+            let scale = energy.scale(idx);
+            if scale > 0.0 {
+                let direct = normalized_peak(&reference, &target, correlation[idx], idx);
+                let streamed = (correlation[idx] * scale).abs() as f64;
+                assert!((streamed - direct).abs() < 1e-3, "lag {}", idx);
+            }
         }
+    }
 
-        // Find best measured drift for this track
-        let best = tracks[ti]
-            .clips
-            .iter()
-            .filter(|c| c.drift_ppm.abs() > 1e-6 && c.drift_confidence > 0.5)
-            .max_by(|a, b| {
-                a.drift_confidence
-                    .partial_cmp(&b.drift_confidence)
-                    .unwrap()
+    #[test]
+    fn test_coarse_to_fine_matches_full_rate_search() {
+        let sr = ANALYSIS_SR;
+        let reference: Vec<f32> = noise(29, sr as usize * 90)
+            .into_iter()
+            .enumerate()
+            .map(|(i, noise)| {
+                // Speech-like: a low tone wandering in pitch, plus noise.
+                0.4 * (i as f32 * (0.05 + 0.02 * (i as f32 / 9000.0).sin())).sin() + 0.3 * noise
             })
-            .map(|c| (c.drift_ppm, c.drift_confidence));
+            .collect();
+        let delay = 412_345usize;
+        let target = reference[delay..delay + sr as usize * 12].to_vec();
+
+        let norm = CorrelationNormalization::default();
+        let (full, _) = full_rate_match(&reference, &target, sr, None, 1, norm, &None);
+        let (fast, candidates) =
+            coarse_to_fine_match(&reference, &target, sr, None, 3, norm, &None).expect("clear match");
+        assert_eq!(full.delay_samples, delay as i64);
+        assert_eq!(fast.delay_samples, full.delay_samples);
+        assert!((fast.delay_s - full.delay_s).abs() < 1e-4);
+        assert!(fast.score >= CONFIDENCE_THRESHOLD, "score {}", fast.score);
+        assert_eq!(candidates[0].delay_samples, fast.delay_samples);
+
+        // Nothing to find: left to the full-rate search.
+        let unrelated: Vec<f32> = (0..sr as usize * 5).map(|i| (i as f32 * 1.7).sin() * 0.01).collect();
+        assert!(coarse_to_fine_match(&reference, &unrelated, sr, None, 1, norm, &None).is_none());
+    }
 
-        if let Some((ppm, conf)) = best {
-            for clip in &mut tracks[ti].clips {
-                if clip.drift_ppm.abs() < 1e-6 && clip.drift_confidence == 0.0 {
-                    clip.drift_ppm = ppm;
-                    clip.drift_confidence = conf;
-                    debug!(
-                        "Inherited drift {:.2} ppm for short clip '{}'",
-                        ppm, clip.name
-                    );
-                }
+    #[test]
+    fn test_prior_analysis_resolves_repeating_audio() {
+        let sr = ANALYSIS_SR;
+        let period: Vec<f32> = noise(11, sr as usize * 2).into_iter().map(|s| 0.3 * s).collect();
+        let reference: Vec<f32> = period.iter().cycle().take(period.len() * 8).copied().collect();
+        let delay = sr as usize * 7;
+        let target = reference[delay..delay + sr as usize * 6].to_vec();
+
+        let run = |with_prior: bool| {
+            let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+            for (ti, samples, offset_s) in [(0, reference.clone(), 3.0), (1, target.clone(), 10.0)] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.samples = samples;
+                // Another project had both, 7 s apart on its own timeline.
+                clip.prior = with_prior.then(|| PriorAnalysis {
+                    run_id: "earlier/default".into(),
+                    offset_s,
+                    drift_ppm: 0.0,
+                    drift_segments: Vec::new(),
+                    confidence: 20.0,
+                    match_score: 90.0,
+                    analyzed_at: "2026-01-01T00:00:00+00:00".into(),
+                });
+                tracks[ti].clips.push(clip);
             }
+            let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+            let clip = &tracks[1].clips[0];
+            (clip.timeline_offset_samples, clip.match_score, result)
+        };
+
+        let (_, plain_score, _) = run(false);
+        assert!(plain_score < CONFIDENCE_THRESHOLD, "loop should be ambiguous: {}", plain_score);
+        let (offset, score, result) = run(true);
+        assert!((offset - delay as i64).abs() <= 2, "offset {}", offset);
+        assert!(score >= CONFIDENCE_THRESHOLD, "score {}", score);
+        assert!(result.warnings.iter().any(|w| w.message.contains("earlier analysis")));
+    }
+
+    #[test]
+    fn test_analyze_places_clips_by_timecode() {
+        let sr = ANALYSIS_SR;
+        let reference: Vec<f32> = noise(17, sr as usize * 12).into_iter().map(|s| 0.3 * s).collect();
+        let unrelated: Vec<f32> = noise(99, sr as usize * 3).into_iter().map(|s| 0.3 * s).collect();
+        // Timecode 20 ms late, as from a generator a frame off.
+        let clips = [
+            ("Ref", reference.clone(), 36000.0),
+            ("Cam", reference[sr as usize * 5..sr as usize * 9].to_vec(), 36005.02),
+            ("Lav", unrelated, 36008.0),
+        ];
+        let mut tracks = Vec::new();
+        for (name, samples, tc) in clips {
+            let mut track = Track::new(name.into());
+            let mut clip = Clip::new(format!("{}.wav", name), format!("{}.wav", name), 48000, 2);
+            clip.duration_s = samples.len() as f64 / sr as f64;
+            clip.samples = samples;
+            clip.ltc_start_s = Some(tc);
+            track.clips.push(clip);
+            tracks.push(track);
         }
+        let config = SyncConfig {
+            ltc_channel: Some(1),
+            ..Default::default()
+        };
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+
+        // Correlation refines the timecode placement to the sample.
+        assert!((tracks[1].clips[0].timeline_offset_samples - sr as i64 * 5).abs() <= 1);
+        assert!(tracks[1].clips[0].match_score >= CONFIDENCE_THRESHOLD);
+        // Audio that matches nothing still goes where its timecode says.
+        assert_eq!(tracks[2].clips[0].timeline_offset_samples, sr as i64 * 8);
+        assert!(result.warnings.iter().any(|w| w.code == WarningCode::TimecodePlaced
+            && w.clip_id.as_deref() == Some("Lav.wav")));
+        assert!(!result.warnings.iter().any(|w| w.code == WarningCode::LowConfidence));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_analyze_dumps_debug_correlation() {
+        let sr = ANALYSIS_SR;
+        let signal = noise(5, sr as usize * 6);
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam A".into())];
+        let mut ref_clip = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        ref_clip.duration_s = 6.0;
+        ref_clip.samples = signal.clone();
+        tracks[0].clips.push(ref_clip);
+        let mut tgt_clip = Clip::new("tgt.wav".into(), "tgt.wav".into(), 48000, 1);
+        tgt_clip.duration_s = 3.0;
+        tgt_clip.samples = signal[sr as usize..sr as usize * 4].to_vec();
+        tracks[1].clips.push(tgt_clip);
+
+        let dir = std::env::temp_dir().join(format!("audiosync_corr_{}", uuid::Uuid::new_v4()));
+        let config = SyncConfig {
+            debug_correlation_dir: Some(dir.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        analyze(&mut tracks, &config, &None, &None).unwrap();
+        let dumped = std::fs::read_to_string(dir.join("Cam_A__tgt.wav.pass1.json"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let cmp: ClipComparison = serde_json::from_str(&dumped.unwrap()).unwrap();
+        assert_eq!(cmp.delay_samples, sr as i64);
+        assert!(!cmp.curve.is_empty() && cmp.curve.len() <= DEBUG_CURVE_POINTS);
+    }
 
     #[test]
-    fn test_compute_delay_identical() {
-        // A broadband signal correlated with itself should have delay = 0
-        // Use a complex waveform (multiple frequencies) for a sharp correlation peak.
-        let signal: Vec<f32> = (0..4000)
-            .map(|i| {
-                let t = i as f32 / 8000.0;
-                (t * 440.0 * std::f32::consts::TAU).sin()
-                    + 0.7 * (t * 1200.0 * std::f32::consts::TAU).sin()
-                    + 0.3 * (t * 3500.0 * std::f32::consts::TAU).cos()
-                    + 0.5 * (t * 780.0 * std::f32::consts::TAU).sin()
-            })
-            .collect();
-        let (delay, conf) = compute_delay(&signal, &signal, 8000, None);
-        assert_eq!(delay, 0);
-        assert!(conf > 2.0, "Confidence {} should be reasonable for identical signals", conf);
+    fn test_analyze_cancellation() {
+        let mut tracks = vec![Track::new("Test".into())];
+        let mut clip = Clip::new("t.wav".into(), "t.wav".into(), 48000, 1);
+        clip.duration_s = 1.0;
+        clip.samples = vec![0.0; 8000];
+        tracks[0].clips.push(clip);
+
+        let config = SyncConfig::default();
+        let cancel = new_cancel_token();
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+
+        let result = analyze(&mut tracks, &config, &None, &Some(cancel));
+        assert!(result.is_err());
     }
 
     #[test]
-    fn test_compute_delay_shifted() {
-        // Create a reference and a delayed copy
+    fn test_sync_skips_unreadable_clips() {
+        let path = std::env::temp_dir().join(format!("audiosync_sync_{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..8000 {
+            writer.write_sample(((i % 100) as i16 - 50) * 100).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut tracks = vec![Track::new("Good".into()), Track::new("Ejected".into())];
+        let mut good = Clip::new(path.to_string_lossy().to_string(), "good.wav".into(), 8000, 1);
+        good.duration_s = 1.0;
+        tracks[0].clips.push(good);
+        let mut gone = Clip::new("/nonexistent/gone.wav".into(), "gone.wav".into(), 8000, 1);
+        gone.duration_s = 1.0;
+        tracks[1].clips.push(gone);
+
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 8000,
+            total_timeline_s: 1.0,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let mut config = SyncConfig {
+            export_sr: Some(8000),
+            ..Default::default()
+        };
+
+        let skipped = sync(&mut tracks, &result, &mut config, &None, &None).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].clip_name, "gone.wav");
+        let good_audio = tracks[0].synced_audio.as_ref().unwrap();
+        assert!(good_audio.iter().any(|s| s.abs() > 0.0));
+        assert!(tracks[1].synced_audio.as_ref().unwrap().iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_sync_renders_only_export_range() {
+        let path = std::env::temp_dir().join(format!("audiosync_range_{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..8000 {
+            writer.write_sample((i as i16 - 4000) * 8).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut tracks = vec![Track::new("Cam".into()), Track::new("Later".into())];
+        let mut clip = Clip::new(path.to_string_lossy().to_string(), "cam.wav".into(), 8000, 1);
+        clip.duration_s = 1.0;
+        tracks[0].clips.push(clip);
+        // Outside the range, so never read (it would be reported as skipped).
+        let mut later = Clip::new("/nonexistent/later.wav".into(), "later.wav".into(), 8000, 1);
+        later.duration_s = 1.0;
+        later.timeline_offset_s = 2.5;
+        later.timeline_offset_samples = 20000;
+        tracks[1].clips.push(later);
+
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 28000,
+            total_timeline_s: 3.5,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let mut config = SyncConfig {
+            export_sr: Some(8000),
+            export_range_s: Some((0.5, 1.5)),
+            ..Default::default()
+        };
+
+        let skipped = sync(&mut tracks, &result, &mut config, &None, &None).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(skipped.is_empty());
+        let audio = tracks[0].synced_audio.as_ref().unwrap();
+        assert_eq!(audio.len(), 8000);
+        // Output starts half-way into the clip, which ends mid-range.
+        assert!(audio[0].abs() < 1e-3);
+        assert!(audio[3999] > 0.9);
+        assert!(audio[4000..].iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_sync_applies_clip_trim_and_gain() {
+        let path = std::env::temp_dir().join(format!("audiosync_trim_{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..8000 {
+            writer.write_sample((i as f32 * 0.01).sin() * 0.8).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        // The same drifting recording twice, as recorded and trimmed at -6 dB.
+        let mut tracks = vec![Track::new("Full".into()), Track::new("Trimmed".into())];
+        for track in &mut tracks {
+            let mut clip = Clip::new(path.to_string_lossy().to_string(), "rec.wav".into(), 8000, 1);
+            clip.duration_s = 1.0;
+            clip.timeline_offset_s = 0.5;
+            clip.timeline_offset_samples = 4000;
+            clip.drift_ppm = 1000.0;
+            clip.drift_confidence = 1.0;
+            track.clips.push(clip);
+        }
+        let trimmed = &mut tracks[1].clips[0];
+        trimmed.gain_db = -20.0 * 2f64.log10();
+        trimmed.trim_in_s = 0.25;
+        trimmed.trim_out_s = 0.25;
+
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 16000,
+            total_timeline_s: 2.0,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: true,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let mut config = SyncConfig {
+            export_sr: Some(8000),
+            ..Default::default()
+        };
+
+        let skipped = sync(&mut tracks, &result, &mut config, &None, &None).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(skipped.is_empty());
+        let full = tracks[0].synced_audio.as_ref().unwrap();
+        let trimmed = tracks[1].synced_audio.as_ref().unwrap();
+        // The kept audio lands where it played untrimmed, at half the level.
+        let kept: Vec<usize> = (0..trimmed.len()).filter(|&i| trimmed[i] != 0.0).collect();
+        let (first, last) = (kept[0], *kept.last().unwrap());
+        assert!((first as i64 - 5998).abs() <= 1, "first kept sample {}", first);
+        assert!((last as i64 - 9993).abs() <= 1, "last kept sample {}", last);
+        // Away from the cut edges, where the resampler rings.
+        for i in first + 32..last - 32 {
+            assert!((trimmed[i] - 0.5 * full[i]).abs() < 1e-3, "sample {}", i);
+        }
+    }
+
+    #[test]
+    fn test_sync_streams_long_clips_in_windows() {
+        // Longer than a couple of read windows, with drift corrected across
+        // their joins.
         let sr = 8000u32;
-        let delay_samples = 400i64; // 50ms at 8kHz
-        let len = 4000;
+        let frames = 1_200_000;
+        let source = noise(13, frames);
+        let path = std::env::temp_dir().join(format!("audiosync_stream_{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: sr,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        source.iter().for_each(|&s| writer.write_sample(s).unwrap());
+        writer.finalize().unwrap();
+        let source: Vec<f64> = source.into_iter().map(f64::from).collect();
+
+        let segments = vec![
+            DriftSegment { start_s: 0.0, ppm: 150.0 },
+            DriftSegment { start_s: 70.0, ppm: -80.0 },
+        ];
+        let duration_s = frames as f64 / sr as f64;
+        let mut clip = Clip::new(path.to_string_lossy().to_string(), "long.wav".into(), sr, 1);
+        clip.duration_s = duration_s;
+        clip.drift_ppm = 150.0;
+        clip.drift_segments = segments.clone();
+        clip.drift_confidence = 0.9;
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: frames as i64,
+            total_timeline_s: duration_s,
+            sample_rate: sr,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: true,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let stitch = |drift_correction, drift_resample_quality| {
+            let mut tracks = vec![Track::new("Recorder".into())];
+            tracks[0].clips.push(clip.clone());
+            let mut config = SyncConfig {
+                export_sr: Some(sr),
+                drift_correction,
+                drift_resample_quality,
+                ..Default::default()
+            };
+            assert!(sync(&mut tracks, &result, &mut config, &None, &None).unwrap().is_empty());
+            tracks[0].synced_audio.take().unwrap()
+        };
+        let matches = |streamed: &[f64], whole: &[f64]| {
+            let n = streamed.len().min(whole.len());
+            assert!(n > frames - 1000, "{}", n);
+            let worst = (0..n).map(|i| (streamed[i] - whole[i]).abs()).fold(0.0, f64::max);
+            assert!(worst < 1e-6, "{}", worst);
+        };
 
-        let reference: Vec<f32> = (0..len + delay_samples as usize)
-            .map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.13).cos() * 0.5)
-            .collect();
-        let target: Vec<f32> = reference[delay_samples as usize..].to_vec();
+        matches(&stitch(false, ResampleQuality::Balanced), &source);
+        let cubic = apply_drift_segments_f64(&source, &segments, sr, true);
+        matches(&stitch(true, ResampleQuality::Cubic), &cubic);
+        let sinc = resample_drift_sinc(&source, &segments, sr, ResampleQuality::Balanced).unwrap();
+        matches(&stitch(true, ResampleQuality::Balanced), &sinc);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_sync_keeps_clip_channels() {
+        let dir = std::env::temp_dir().join(format!("audiosync_channels_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, channels: u16, sample: &dyn Fn(usize, u16) -> f32| {
+            let path = dir.join(name);
+            let spec = hound::WavSpec {
+                channels,
+                sample_rate: 8000,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            for i in 0..4000 {
+                for c in 0..channels {
+                    writer.write_sample(sample(i, c)).unwrap();
+                }
+            }
+            writer.finalize().unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let stereo = |i: usize, c: u16| (i as f32 * 0.01).sin() * if c == 0 { 0.8 } else { -0.4 };
+        let mono = |i: usize, _: u16| (i as f32 * 0.03).cos() * 0.5;
+
+        // A stereo recorder's clip, then a mono one on the same track.
+        let mut track = Track::new("Recorder".into());
+        for (i, (name, channels)) in [("stereo.wav", 2u32), ("mono.wav", 1)].into_iter().enumerate() {
+            let path = write(name, channels as u16, if channels == 2 { &stereo } else { &mono });
+            let mut clip = Clip::new(path, name.into(), 8000, channels);
+            clip.duration_s = 0.5;
+            clip.timeline_offset_s = i as f64 * 0.5;
+            clip.timeline_offset_samples = i as i64 * 4000;
+            track.clips.push(clip);
+        }
+        let mut tracks = vec![track];
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 8000,
+            total_timeline_s: 1.0,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let mut config = SyncConfig { export_sr: Some(8000), ..Default::default() };
+
+        sync(&mut tracks, &result, &mut config, &None, &None).unwrap();
+        assert_eq!(tracks[0].synced_channels, 2);
+        let audio = tracks[0].synced_audio.clone().unwrap();
+        assert_eq!(audio.len(), 2 * 8000);
+        for i in (0..4000).step_by(97) {
+            assert!((audio[2 * i] - stereo(i, 0) as f64).abs() < 1e-6, "frame {}", i);
+            assert!((audio[2 * i + 1] - stereo(i, 1) as f64).abs() < 1e-6, "frame {}", i);
+            // The mono clip plays on both channels.
+            let m = mono(i, 0) as f64;
+            assert!((audio[2 * (4000 + i)] - m).abs() < 1e-6 && (audio[2 * (4000 + i) + 1] - m).abs() < 1e-6);
+        }
 
-        let (detected_delay, conf) = compute_delay(&reference, &target, sr, None);
-        assert!(
-            (detected_delay - delay_samples).abs() <= 1,
-            "Expected delay ~{}, got {}",
-            delay_samples,
-            detected_delay
-        );
-        assert!(conf > 3.0, "Confidence should be reasonable");
+        let path = dir.join("out.wav").to_string_lossy().to_string();
+        crate::audio_io::export_track(&tracks[0], &path, &config).unwrap();
+        assert_eq!(hound::WavReader::open(&path).unwrap().spec().channels, 2);
+
+        // Mixed down on request.
+        config.export_channels = ChannelPolicy::Mono;
+        sync(&mut tracks, &result, &mut config, &None, &None).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(tracks[0].synced_channels, 1);
+        let audio = tracks[0].synced_audio.as_ref().unwrap();
+        assert_eq!(audio.len(), 8000);
+        assert!((audio[100] - 0.2 * (1.0f32).sin() as f64).abs() < 1e-6);
     }
 
     #[test]
-    fn test_subsample_peak() {
-        let data = vec![0.0f32, 0.5, 1.0, 0.8, 0.2];
-        let peak = subsample_peak(&data, 2);
-        assert!(peak > 1.5 && peak < 2.5, "Subsample peak = {}", peak);
+    fn test_reanalyze_clip_in_window() {
+        let signal = noise(777, 40000);
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = signal.clone();
+        r.analyzed = true;
+        tracks[0].clips.push(r);
+        // True position: 2.5 s. Misplaced at 0.
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = signal[20000..26000].to_vec();
+        tracks[1].clips.push(c);
+
+        let (offset, conf) = reanalyze_clip_in_window(&mut tracks, 1, 0, 2.0, 3.0).unwrap();
+        assert_eq!(offset, 20000);
+        assert!(conf > CONFIDENCE_THRESHOLD);
+        assert!((tracks[1].clips[0].timeline_offset_s - 2.5).abs() < 1e-6);
+        assert_eq!(tracks[0].clips[0].timeline_offset_samples, 0, "other clips untouched");
+
+        assert!(reanalyze_clip_in_window(&mut tracks, 1, 5, 0.0, 1.0).is_err());
+        assert!(!tracks[1].clips[0].polarity_inverted);
+
+        let flipped: Vec<f32> = signal[20000..26000].iter().map(|x| -x).collect();
+        tracks[1].clips[0].samples = flipped;
+        let (offset, _) = reanalyze_clip_in_window(&mut tracks, 1, 0, 2.0, 3.0).unwrap();
+        assert_eq!(offset, 20000);
+        assert!(tracks[1].clips[0].polarity_inverted);
     }
 
     #[test]
-    fn test_compute_delay_empty_reference() {
-        let reference: Vec<f32> = vec![];
-        let target: Vec<f32> = vec![1.0, 2.0, 3.0];
-        let (delay, conf) = compute_delay(&reference, &target, 8000, None);
-        assert_eq!(delay, 0);
-        assert_eq!(conf, 0.0);
+    fn test_reanalyze_clip_in_window_ignores_off_timeline_and_other_groups() {
+        let signal = noise(778, 40000);
+
+        let mut tracks = vec![
+            Track::new("Ref".into()),
+            Track::new("Cam".into()),
+            Track::new("Other".into()),
+        ];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = signal.clone();
+        r.analyzed = true;
+        r.excluded = true;
+        tracks[0].clips.push(r);
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = signal[20000..26000].to_vec();
+        tracks[1].clips.push(c);
+        let mut o = Clip::new("other.wav".into(), "other.wav".into(), 48000, 1);
+        o.samples = signal.clone();
+        o.analyzed = true;
+        tracks[2].clips.push(o);
+        tracks[2].sync_group = Some("B".into());
+
+        assert!(reanalyze_clip_in_window(&mut tracks, 1, 0, 2.0, 3.0).is_err());
+
+        tracks[0].clips[0].excluded = false;
+        let (offset, _) = reanalyze_clip_in_window(&mut tracks, 1, 0, 2.0, 3.0).unwrap();
+        assert_eq!(offset, 20000);
     }
 
     #[test]
-    fn test_compute_delay_empty_target() {
-        let reference: Vec<f32> = vec![1.0, 2.0, 3.0];
-        let target: Vec<f32> = vec![];
-        let (delay, conf) = compute_delay(&reference, &target, 8000, None);
-        assert_eq!(delay, 0);
-        assert_eq!(conf, 0.0);
+    fn test_analyze_keeps_locked_clips_in_place() {
+        let signal = noise(4242, 24000);
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = signal.clone();
+        r.duration_s = 3.0;
+        tracks[0].clips.push(r);
+        // Correlation would put this at 1.0 s; the user locked it at 1.5 s.
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = signal[8000..16000].to_vec();
+        c.duration_s = 1.0;
+        c.timeline_offset_samples = 12000;
+        c.timeline_offset_s = 1.5;
+        c.locked = true;
+        tracks[1].clips.push(c);
+
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(tracks[1].clips[0].timeline_offset_samples, 12000);
+        assert_eq!(result.clip_offsets["cam.wav"], 12000);
+
+        tracks[1].clips[0].locked = false;
+        analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert!((tracks[1].clips[0].timeline_offset_samples - 8000).abs() <= 2);
     }
 
     #[test]
-    fn test_compute_delay_with_max_offset() {
-        let sr = 8000u32;
-        let delay_samples = 400i64;
-        let len = 4000;
+    fn test_analyze_lays_out_untimed_reference_by_content() {
+        let take = noise(31, 8000 * 20);
+        // The recorder split the take into files overlapping by 2 s; no
+        // timestamps survived and the second file is listed first.
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        for (name, range) in [("ref_2.wav", 80000..160000), ("ref_1.wav", 0..96000)] {
+            let mut c = Clip::new(name.into(), name.into(), 48000, 1);
+            c.samples = take[range].to_vec();
+            c.duration_s = c.samples.len() as f64 / 8000.0;
+            tracks[0].clips.push(c);
+        }
+        tracks[0].is_reference = true;
+        let mut cam = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        cam.samples = take[64000..112000].to_vec();
+        cam.duration_s = 6.0;
+        tracks[1].clips.push(cam);
+
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(result.clip_offsets["ref_1.wav"], 0);
+        assert_eq!(result.clip_offsets["ref_2.wav"], 80000);
+        assert!((result.clip_offsets["cam.wav"] - 64000).abs() <= 2);
+
+        // Metadata layout can only guess a short gap between them.
+        let config = SyncConfig { reference_layout: ReferenceLayout::Metadata, ..Default::default() };
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+        assert_eq!(result.clip_offsets["ref_2.wav"], 96000 + 4000);
+    }
 
-        let reference: Vec<f32> = (0..len + delay_samples as usize)
-            .map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.13).cos() * 0.5)
-            .collect();
-        let target: Vec<f32> = reference[delay_samples as usize..].to_vec();
+    #[test]
+    fn test_reference_overlap_prefers_higher_quality_source() {
+        // A 24-bit recorder and a phone on one track; the phone started 3 s
+        // before the recorder stopped.
+        let mut track = Track::new("Ref".into());
+        for (name, seed, start_s) in [("zoom.wav", 1, 1000.0), ("phone.m4a", 2, 1007.0)] {
+            let mut c = Clip::new(name.into(), name.into(), 48000, 1);
+            c.samples = noise(seed, 80000);
+            c.duration_s = 10.0;
+            c.creation_time = Some(start_s);
+            track.clips.push(c);
+        }
+        track.clips[0].stream_metadata.bit_depth = Some(24);
+
+        let mut warnings = Vec::new();
+        let audio = build_reference(&mut track, 8000, false, &mut warnings).unwrap();
+        assert_eq!(track.clips[1].timeline_offset_samples, 56000);
+        assert_eq!(audio.len(), 136000);
+        assert_eq!(audio[56000..80000], track.clips[0].samples[56000..]);
+        assert_eq!(audio[80000..], track.clips[1].samples[24000..]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, WarningCode::ReferenceOverlap);
+        assert!(warnings[0].message.contains("7.0–10.0 s") && warnings[0].message.contains("'zoom.wav'"));
+
+        // Equal sources are mixed.
+        track.clips[1].stream_metadata.bit_depth = Some(24);
+        let audio = build_reference(&mut track, 8000, false, &mut Vec::new()).unwrap();
+        let (a, b) = (track.clips[0].samples[60000], track.clips[1].samples[4000]);
+        assert!((audio[60000] - (a + b) / 2.0).abs() < 1e-6);
+
+        // An overlap within timestamp rounding is closed up.
+        track.clips[1].creation_time = Some(1009.5);
+        let mut warnings = Vec::new();
+        build_reference(&mut track, 8000, false, &mut warnings).unwrap();
+        assert_eq!(track.clips[1].timeline_offset_samples, 80000);
+        assert!(warnings.is_empty());
+    }
 
-        // With sufficient max_offset, should find the delay
-        let (detected, _) = compute_delay(&reference, &target, sr, Some(1.0));
-        assert!(
-            (detected - delay_samples).abs() <= 1,
-            "Expected ~{}, got {}",
-            delay_samples,
-            detected
-        );
+    #[test]
+    fn test_incremental_analysis_only_correlates_new_clips() {
+        let signal = noise(5150, 48000);
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = signal[..32000].to_vec();
+        r.duration_s = 4.0;
+        tracks[0].clips.push(r);
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = signal[16000..].to_vec();
+        c.duration_s = 4.0;
+        tracks[1].clips.push(c);
+        analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert!((tracks[1].clips[0].timeline_offset_samples - 16000).abs() <= 2);
+
+        // A late recorder only hears what the camera heard after the
+        // reference stopped; the camera's earlier result must not be redone.
+        tracks[1].clips[0].match_score = 77.0;
+        let mut zoom = Track::new("Zoom".into());
+        let mut z = Clip::new("zoom.wav".into(), "zoom.wav".into(), 48000, 1);
+        z.samples = signal[36000..].to_vec();
+        z.duration_s = 1.5;
+        zoom.clips.push(z);
+        tracks.push(zoom);
+
+        let config = SyncConfig { incremental: true, ..Default::default() };
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+        assert_eq!(tracks[0].clips[0].timeline_offset_samples, 0);
+        assert!((tracks[1].clips[0].timeline_offset_samples - 16000).abs() <= 2);
+        assert_eq!(tracks[1].clips[0].match_score, 77.0);
+        assert!((tracks[2].clips[0].timeline_offset_samples - 36000).abs() <= 2);
+        assert!(tracks[2].clips[0].match_score >= CONFIDENCE_THRESHOLD);
+        assert_eq!(result.clip_offsets.len(), 3);
+    }
 
-        // With very small max_offset, might not find the correct delay
-        let (detected_limited, _) = compute_delay(&reference, &target, sr, Some(0.01));
-        // The result should still be valid (not crash), though may not match
-        let _ = detected_limited;
+    #[test]
+    fn test_normalization_preserves_anchor_clip() {
+        let signal = noise(99, 24000);
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = signal[4000..].to_vec();
+        r.duration_s = 2.5;
+        tracks[0].clips.push(r);
+        // Cam starts 0.5 s before the reference recording.
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = signal[..12000].to_vec();
+        c.duration_s = 1.5;
+        tracks[1].clips.push(c);
+
+        analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(tracks[1].clips[0].timeline_offset_samples, 0);
+        assert_eq!(tracks[0].clips[0].timeline_offset_samples, 4000);
+
+        // Previously exported with cam at 10 s — keep it there on re-analysis.
+        tracks[1].clips[0].timeline_offset_samples = 80000;
+        let config = SyncConfig {
+            anchor_clip: Some("cam.wav".into()),
+            ..Default::default()
+        };
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+        assert_eq!(tracks[1].clips[0].timeline_offset_samples, 80000);
+        assert_eq!(tracks[0].clips[0].timeline_offset_samples, 84000);
+        assert_eq!(result.clip_offsets["ref.wav"], 84000);
+        assert_eq!(result.total_timeline_samples, 84000 + 20000);
     }
 
     #[test]
-    fn test_compute_delay_negative_delay() {
-        // Target starts before reference in the correlation
+    fn test_compare_clips_curve_and_overlap() {
         let sr = 8000u32;
-        let len = 4000;
-
-        let signal: Vec<f32> = (0..len)
-            .map(|i| (i as f32 * 0.05).sin() + (i as f32 * 0.13).cos() * 0.5)
-            .collect();
-
-        // Reference is a subset that starts later
-        let reference = signal[200..].to_vec();
-        let target = signal.clone();
-
-        let (delay, _conf) = compute_delay(&reference, &target, sr, None);
-        // Delay should be negative (target needs to shift left)
-        assert!(delay < 0, "Expected negative delay, got {}", delay);
+        let reference = noise(11, sr as usize * 10);
+        // Target covers 8..12 s of a 10 s reference: 2 s overlap.
+        let mut target = reference[(sr * 8) as usize..].to_vec();
+        target.extend(std::iter::repeat_n(0.0, (sr * 2) as usize));
+
+        let cmp = compare_clips(&reference, &target, sr, None, 500);
+        let (delay, conf) = compute_delay(&reference, &target, sr, None);
+        assert_eq!(cmp.delay_samples, 8 * sr as i64);
+        assert_eq!(cmp.delay_samples, delay);
+        assert!((cmp.confidence - conf).abs() / conf < 1e-3);
+        assert!(cmp.curve.len() <= 500);
+        let best = cmp.curve.iter().max_by(|a, b| a.value.total_cmp(&b.value)).unwrap();
+        assert_eq!(best.value, 1.0);
+        assert!((best.lag_s - 8.0).abs() < 1e-9);
+        assert!(cmp.runner_up_ratio < 0.5);
+        assert!((cmp.overlap.overlap_s - 2.0).abs() < 1e-9);
+        assert!((cmp.overlap.overlap_ratio - 0.5).abs() < 1e-9);
+
+        let windowed = compare_clips(&reference, &target, sr, Some(1.0), 500);
+        assert!(windowed.curve.iter().all(|p| p.lag_s.abs() <= 1.0));
     }
 
     #[test]
-    fn test_apply_drift_correction_identity() {
-        let audio = vec![1.0f32, 2.0, 3.0, 4.0, 5.0];
-        let result = apply_drift_correction(&audio, 0.0);
-        assert_eq!(result.len(), audio.len());
+    fn test_measure_drift_adapts_window_to_short_clips() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(23, sr as usize * 40);
+        let mut clip = Clip::new("c.wav".into(), "c.wav".into(), 48000, 1);
+        clip.samples = apply_drift_correction(&reference, -50.0);
+        clip.samples.truncate(reference.len());
+
+        // 40 s overlap: 10 s windows, 5 s stride.
+        let drift = measure_drift(&reference, &clip, sr, &None);
+        assert_eq!(drift.window_s, MIN_DRIFT_WINDOW_S);
+        assert_eq!(drift.windows, 7);
+        assert!((drift.drift_ppm.abs() - 50.0).abs() < 5.0, "{:?}", drift);
+        assert!(drift.r_squared > 0.9);
+        assert!(drift.confidence < drift.r_squared);
+
+        clip.samples.truncate((sr as f64 * (MIN_DRIFT_OVERLAP_S - 1.0)) as usize);
+        assert_eq!(measure_drift(&reference, &clip, sr, &None).windows, 0);
     }
 
     #[test]
-    fn test_apply_drift_correction_positive() {
-        let audio: Vec<f32> = (0..10000).map(|i| (i as f32 * 0.01).sin()).collect();
-        let result = apply_drift_correction(&audio, 100.0); // 100 ppm
-        // Corrected should be slightly shorter
-        assert!(result.len() < audio.len(), "Expected shorter output");
-        assert!(result.len() > audio.len() - 10, "Should be close to original length");
+    fn test_match_score_is_calibrated() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(1, sr as usize * 10);
+
+        // Exact excerpt: unambiguous, fully similar.
+        let excerpt = reference[sr as usize * 3..sr as usize * 6].to_vec();
+        let exact = match_delay(&reference, &excerpt, sr, None);
+        assert_eq!(exact.delay_samples, sr as i64 * 3);
+        assert!(exact.score > 90.0, "{:?}", exact);
+
+        // Same excerpt buried in as much independent noise still places.
+        let other = noise(2, excerpt.len());
+        let noisy: Vec<f32> = excerpt.iter().zip(&other).map(|(a, b)| a + b).collect();
+        let buried = match_delay(&reference, &noisy, sr, None);
+        assert!(buried.score >= CONFIDENCE_THRESHOLD && buried.score < exact.score);
+
+        // Unrelated audio scores below the threshold even though the raw
+        // peak/mean ratio is well above 1.
+        let unrelated = match_delay(&reference, &noise(3, sr as usize * 3), sr, None);
+        assert!(unrelated.score < CONFIDENCE_THRESHOLD, "{:?}", unrelated);
+
+        assert_eq!(calibrated_score(1.0, 0.0), 100.0);
+        assert_eq!(calibrated_score(0.9, 1.2), 0.0);
     }
 
     #[test]
-    fn test_apply_drift_correction_negative() {
-        let audio: Vec<f32> = (0..10000).map(|i| (i as f32 * 0.01).sin()).collect();
-        let result = apply_drift_correction(&audio, -100.0); // -100 ppm
-        // Corrected should be slightly longer
-        assert!(result.len() > audio.len(), "Expected longer output");
-        assert!(result.len() < audio.len() + 10, "Should be close to original length");
+    fn test_track_drift_weighs_measured_clips() {
+        let clip = |name: &str, rate: u32, duration_s: f64, ppm: f64, conf: f64| {
+            let mut c = Clip::new(format!("/{name}.wav"), name.into(), rate, 1);
+            c.duration_s = duration_s;
+            c.drift_ppm = ppm;
+            c.drift_confidence = conf;
+            c
+        };
+        let mut track = Track::new("recorder".into());
+        track.clips.push(clip("long", 48000, 600.0, 20.0, 0.9));
+        track.clips.push(clip("noisy", 48000, 60.0, 80.0, 0.6));
+        track.clips.push(clip("short", 48000, 5.0, 0.0, 0.0));
+        track.clips.push(clip("other_clock", 44100, 5.0, 0.0, 0.0));
+        let mut tracks = vec![Track::new("ref".into()), track];
+
+        propagate_track_drift(&mut tracks, 0);
+        let short = &tracks[1].clips[2];
+        // (540 × 20 + 36 × 80) / 576
+        assert!((short.drift_ppm - 23.75).abs() < 1e-9, "got {}", short.drift_ppm);
+        assert!(short.drift_inherited);
+        assert!(!tracks[1].clips[0].drift_inherited);
+        assert_eq!(tracks[1].clips[3].drift_ppm, 0.0);
+
+        // Inherited values are never passed on as measurements.
+        tracks[1].clips[0].set_drift_override(Some(0.0));
+        tracks[1].clips[1].set_drift_override(Some(0.0));
+        tracks[1].clips.push(clip("short2", 48000, 5.0, 0.0, 0.0));
+        propagate_track_drift(&mut tracks, 0);
+        assert_eq!(tracks[1].clips[4].drift_ppm, 0.0);
     }
 
     #[test]
-    fn test_select_reference_index_by_duration() {
-        let mut tracks = vec![
-            Track::new("Short".into()),
-            Track::new("Long".into()),
-        ];
-        let mut c1 = Clip::new("a.wav".into(), "a.wav".into(), 48000, 1);
-        c1.duration_s = 5.0;
-        c1.samples = vec![0.0; 40000];
-        tracks[0].clips.push(c1);
-
-        let mut c2 = Clip::new("b.wav".into(), "b.wav".into(), 48000, 1);
-        c2.duration_s = 60.0;
-        c2.samples = vec![0.0; 480000];
-        tracks[1].clips.push(c2);
+    fn test_inherit_drift_skips_overridden_clips() {
+        let clip = |name: &str, ppm: f64, conf: f64| {
+            let mut c = Clip::new(format!("/{name}.wav"), name.into(), 48000, 1);
+            c.drift_ppm = ppm;
+            c.drift_confidence = conf;
+            c
+        };
+        let mut reference = Track::new("ref".into());
+        reference.clips.push(clip("r", 0.0, 0.0));
+        let mut track = Track::new("recorder".into());
+        track.clips.push(clip("long", 40.0, 0.9));
+        track.clips.push(clip("short", 0.0, 0.0));
+        let mut pinned = clip("pinned", 0.0, 0.0);
+        pinned.set_drift_override(Some(0.0));
+        track.clips.push(pinned);
+        let mut tracks = vec![reference, track];
+
+        propagate_track_drift(&mut tracks, 0);
+        assert_eq!(tracks[1].clips[1].drift_ppm, 40.0);
+        assert_eq!(tracks[1].clips[2].drift_ppm, 0.0);
+        assert!(tracks[1].clips[2].drift_override);
+
+        // A pinned value is never used as the track's donor either.
+        tracks[1].clips[0].set_drift_override(Some(-12.0));
+        tracks[1].clips[1].set_drift_override(None);
+        propagate_track_drift(&mut tracks, 0);
+        assert_eq!(tracks[1].clips[1].drift_ppm, 0.0);
+        assert!(!tracks[1].clips[1].drift_override);
+    }
 
-        let idx = select_reference_index(&tracks);
-        assert_eq!(idx, 1, "Longer track should be reference");
+    #[test]
+    fn test_session_drift_from_short_clips() {
+        // A recorder 30 ppm fast: over four hours its timestamps (to the
+        // centisecond) run 0.43 s ahead of the reference.
+        let mut reference = Track::new("ref".into());
+        reference.clips.push(Clip::new("/r.wav".into(), "r".into(), 48000, 1));
+        let mut track = Track::new("recorder".into());
+        for k in 0..40 {
+            let t = k as f64 * 360.0 + 17.0;
+            let mut c = Clip::new(format!("/c{k}.wav"), format!("c{k}"), 48000, 1);
+            c.duration_s = 20.0;
+            c.analyzed = true;
+            c.timeline_offset_s = t;
+            c.creation_time = Some(((1_700_000_000.0 + t * (1.0 + 30e-6)) * 100.0).round() / 100.0);
+            track.clips.push(c);
+        }
+        track.clips[3].set_drift_override(Some(0.0));
+        let mut tracks = vec![reference, track];
+
+        assert!(estimate_session_drift(&mut tracks, 0, ANALYSIS_SR, 0.3));
+        let clip = &tracks[1].clips[0];
+        assert!((clip.drift_ppm - 30.0).abs() < 10.0, "got {}", clip.drift_ppm);
+        assert!(clip.drift_confidence > 0.5);
+        assert_eq!(tracks[1].clips[3].drift_ppm, 0.0);
+
+        // Whole-second stamps can't tell that drift from their rounding.
+        for c in &mut tracks[1].clips {
+            c.creation_time = c.creation_time.map(f64::round);
+            c.drift_ppm = 0.0;
+            c.drift_confidence = 0.0;
+        }
+        assert!(!estimate_session_drift(&mut tracks, 0, ANALYSIS_SR, 0.3));
     }
 
     #[test]
-    fn test_select_reference_user_override() {
-        let mut tracks = vec![
-            Track::new("Short".into()),
-            Track::new("Long".into()),
-        ];
-        tracks[0].is_reference = true;
-        let mut c = Clip::new("a.wav".into(), "a.wav".into(), 48000, 1);
-        c.duration_s = 5.0;
-        tracks[0].clips.push(c);
-        let mut c2 = Clip::new("b.wav".into(), "b.wav".into(), 48000, 1);
-        c2.duration_s = 60.0;
-        tracks[1].clips.push(c2);
+    fn test_analyze_disambiguates_repetitive_match_with_creation_time() {
+        let sr = ANALYSIS_SR as usize;
+        // A 5 s loop repeated over 20 s; the copy at 5–10 s is clean, the
+        // rest are slightly quieter and noisier, so correlation alone
+        // prefers 5 s.
+        let bar = noise(31, sr * 5);
+        let looped: Vec<f32> = bar.iter().cycle().take(sr * 20).copied().collect();
+        let reference: Vec<f32> = looped
+            .iter()
+            .zip(noise(32, sr * 20))
+            .enumerate()
+            .map(|(i, (&x, n))| if (sr * 5..sr * 10).contains(&i) { x } else { 0.9 * x + 0.1 * n })
+            .collect();
 
-        let idx = select_reference_index(&tracks);
-        assert_eq!(idx, 0, "User override should win");
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = reference;
+        r.duration_s = 20.0;
+        r.creation_time = Some(1_000.0);
+        tracks[0].clips.push(r);
+        // Recorded at 15 s according to its clock.
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = looped[sr * 15..sr * 19].to_vec();
+        c.duration_s = 4.0;
+        c.creation_time = Some(1_015.4);
+        tracks[1].clips.push(c);
+
+        let (m, candidates) = match_delay_candidates(
+            &tracks[0].clips[0].samples,
+            &tracks[1].clips[0].samples,
+            ANALYSIS_SR,
+            None,
+            DELAY_CANDIDATES,
+            CorrelationNormalization::default(),
+        );
+        assert_eq!(m.delay_samples, (sr * 5) as i64);
+        assert!(m.score < CONFIDENCE_THRESHOLD);
+        assert!(candidates.iter().any(|c| c.delay_samples == (sr * 15) as i64));
+
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        let clip = &tracks[1].clips[0];
+        assert!((clip.timeline_offset_samples - (sr * 15) as i64).abs() <= 2);
+        assert!(clip.match_score >= CONFIDENCE_THRESHOLD, "{}", clip.match_score);
+        assert!(result.warnings.iter().any(|w| w.code == WarningCode::AmbiguousMatch));
+
+        // Without a creation time there is nothing to disambiguate with.
+        tracks[1].clips[0].creation_time = None;
+        analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(tracks[1].clips[0].timeline_offset_samples, (sr * 5) as i64);
     }
 
     #[test]
-    fn test_analyze_empty_tracks() {
-        let mut tracks: Vec<Track> = vec![];
-        let config = SyncConfig::default();
-        let result = analyze(&mut tracks, &config, &None, &None);
-        assert!(result.is_err());
+    fn test_mixed_sample_rates_on_one_track() {
+        let write_wav = |sr: u32, value: i16| {
+            let path =
+                std::env::temp_dir().join(format!("audiosync_sr_{}.wav", uuid::Uuid::new_v4()));
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: sr,
+                bits_per_sample: 16,
+                sample_format: hound::SampleFormat::Int,
+            };
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            for _ in 0..sr {
+                writer.write_sample(value).unwrap();
+            }
+            writer.finalize().unwrap();
+            path
+        };
+        let a = write_wav(44100, 16384);
+        let b = write_wav(48000, -16384);
+
+        // One second at 44.1 kHz at 0 s, then one at 48 kHz at 2 s.
+        let mut track = Track::new("Recorder".into());
+        let mut first = Clip::new(a.to_string_lossy().to_string(), "a.wav".into(), 44100, 1);
+        first.duration_s = 1.0;
+        first.samples = vec![0.1; 8000];
+        first.drift_ppm = 30.0;
+        first.drift_confidence = 0.9;
+        track.clips.push(first);
+        let mut second = Clip::new(b.to_string_lossy().to_string(), "b.wav".into(), 48000, 1);
+        second.duration_s = 1.0;
+        second.samples = vec![0.1; 8000];
+        second.timeline_offset_samples = 16000;
+        second.timeline_offset_s = 2.0;
+        track.clips.push(second);
+        assert_eq!(track.sample_rates(), vec![44100, 48000]);
+
+        let mut reference = Track::new("Ref".into());
+        reference.clips.push(Clip::new("/r.wav".into(), "r.wav".into(), 48000, 1));
+        let mut tracks = vec![reference, track];
+
+        // The 48 kHz clip runs on another clock and inherits nothing.
+        propagate_track_drift(&mut tracks, 0);
+        assert_eq!(tracks[1].clips[1].drift_ppm, 0.0);
+
+        let mut tracks = vec![tracks.remove(1)];
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 24000,
+            total_timeline_s: 3.0,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let mut config = SyncConfig {
+            export_sr: Some(48000),
+            drift_correction: false,
+            ..Default::default()
+        };
+        let skipped = sync(&mut tracks, &result, &mut config, &None, &None).unwrap();
+        let _ = std::fs::remove_file(&a);
+        let _ = std::fs::remove_file(&b);
+
+        assert!(skipped.is_empty());
+        let audio = tracks[0].synced_audio.as_ref().unwrap();
+        assert_eq!(audio.len(), 144000);
+        assert!((audio[24000] - 0.5).abs() < 0.01);
+        assert!(audio[48100..95900].iter().all(|s| s.abs() < 1e-3));
+        assert!((audio[96100] + 0.5).abs() < 0.01);
+        assert!((audio[143000] + 0.5).abs() < 0.01);
+
+        // Analysis flags the track.
+        let mut reference = Track::new("Ref".into());
+        let mut r = Clip::new("/r.wav".into(), "r.wav".into(), 48000, 1);
+        r.samples = vec![0.1; 32000];
+        r.duration_s = 4.0;
+        reference.clips.push(r);
+        tracks.insert(0, reference);
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        let mixed: Vec<_> = result
+            .warnings
+            .iter()
+            .filter(|w| w.code == WarningCode::MixedSampleRates)
+            .collect();
+        assert_eq!(mixed.len(), 1);
+        assert!(mixed[0].message.contains("44100 Hz, 48000 Hz"));
     }
 
     #[test]
-    fn test_analyze_single_track_single_clip() {
-        let mut tracks = vec![Track::new("Cam".into())];
-        let mut clip = Clip::new("test.wav".into(), "test.wav".into(), 48000, 1);
-        clip.duration_s = 2.0;
-        clip.samples = (0..16000)
-            .map(|i| (i as f32 * 0.05).sin())
+    fn test_offsets_resolve_between_analysis_samples() {
+        // Band-limited signal, so it can be evaluated at any fractional time.
+        let partials: Vec<(f64, f64)> = noise(2024, 120)
+            .chunks(2)
+            .map(|p| {
+                let (f, phase) = (p[0] as f64 + 0.5, p[1] as f64 + 0.5);
+                (50.0 + f * 3400.0, phase * std::f64::consts::TAU)
+            })
+            .collect();
+        let signal = |t: f64| -> f32 {
+            partials
+                .iter()
+                .map(|(f, p)| (std::f64::consts::TAU * f * t + p).sin())
+                .sum::<f64>() as f32
+        };
+        let sr = ANALYSIS_SR as f64;
+        let truth_s = 7.123_456;
+        let reference: Vec<f32> = (0..(sr * 20.0) as usize).map(|i| signal(i as f64 / sr)).collect();
+        let target: Vec<f32> = (0..(sr * 5.0) as usize)
+            .map(|i| signal(i as f64 / sr + truth_s))
             .collect();
-        tracks[0].clips.push(clip);
-
-        let config = SyncConfig::default();
-        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
 
-        assert_eq!(result.reference_track_index, 0);
-        assert!(tracks[0].is_reference);
-        assert!(tracks[0].clips[0].analyzed);
-        assert_eq!(tracks[0].clips[0].timeline_offset_samples, 0);
+        let m = match_delay(&reference, &target, ANALYSIS_SR, None);
+        assert_eq!(m.delay_samples, (truth_s * sr).round() as i64);
+        assert!((m.delay_s - truth_s).abs() < 0.05 / sr, "{} vs {}", m.delay_s, truth_s);
+
+        // Carried through analysis: lands within a sample at 48 and 96 kHz.
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Cam".into())];
+        let mut r = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
+        r.samples = reference;
+        r.duration_s = 20.0;
+        tracks[0].clips.push(r);
+        let mut c = Clip::new("cam.wav".into(), "cam.wav".into(), 48000, 1);
+        c.samples = target;
+        c.duration_s = 5.0;
+        tracks[1].clips.push(c);
+        analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        let clip = &tracks[1].clips[0];
+        for export_sr in [48000u32, 96000] {
+            let truth = truth_s * export_sr as f64;
+            assert!((clip.timeline_offset_at_sr(export_sr) as f64 - truth).abs() <= 1.0);
+        }
     }
 
     #[test]
-    fn test_analyze_two_tracks_synthetic() {
-        // Create two tracks with related signals
-        let sr = ANALYSIS_SR;
-        let len = 32000usize; // 4 seconds at 8kHz
-        let delay_samples = 800i64; // 100ms
+    fn test_fit_drift_ignores_outlier_windows() {
+        // 2 samples/s of drift with sub-sample jitter.
+        let times: Vec<f64> = (0..10).map(|i| i as f64 * 5.0).collect();
+        let mut offsets: Vec<f64> = times
+            .iter()
+            .enumerate()
+            .map(|(i, t)| 3.0 + 2.0 * t + if i % 2 == 0 { 0.05 } else { -0.05 })
+            .collect();
+        let clean = fit_drift(&times, &offsets).unwrap();
+        assert!((clean.slope - 2.0).abs() < 0.01);
+        assert_eq!(clean.inliers, 10);
+
+        // Two windows wrecked by noise bursts.
+        offsets[3] += 400.0;
+        offsets[7] -= 250.0;
+        let robust = fit_drift(&times, &offsets).unwrap();
+        assert!((robust.slope - 2.0).abs() < 0.01, "{:?}", robust);
+        assert!((robust.intercept - 3.0).abs() < 0.1);
+        assert_eq!(robust.inliers, 8);
+        assert!(robust.r_squared > 0.99);
+
+        // Too few consistent windows to trust.
+        assert!(fit_drift(&times[..2], &offsets[..2]).is_none());
+    }
 
-        let signal: Vec<f32> = (0..len + delay_samples as usize)
-            .map(|i| {
-                let t = i as f32 / sr as f32;
-                (t * 440.0 * std::f32::consts::TAU).sin()
-                    + 0.5 * (t * 1100.0 * std::f32::consts::TAU).sin()
-                    + 0.3 * (t * 2200.0 * std::f32::consts::TAU).cos()
+    #[test]
+    fn test_piecewise_drift_follows_clock_rate_change() {
+        // +2 samples/s for 300 s, then −1 samples/s, with jitter and a burst.
+        let times: Vec<f64> = (0..60).map(|i| i as f64 * 10.0).collect();
+        let mut offsets: Vec<f64> = times
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| {
+                let bent = if t <= 300.0 { 2.0 * t } else { 600.0 - (t - 300.0) };
+                bent + if i % 2 == 0 { 0.05 } else { -0.05 }
             })
             .collect();
+        offsets[40] += 300.0;
+            let pw = fit_piecewise_drift(&times, &offsets).unwrap();
+        assert_eq!(pw.breakpoints.len(), 1, "{:?}", pw);
+        assert!((pw.breakpoints[0] - 300.0).abs() <= 10.0);
+        assert!((pw.slopes[0] - 2.0).abs() < 0.05 && (pw.slopes[1] + 1.0).abs() < 0.05);
+        assert_eq!(pw.inliers, 59);
+
+        // A steady clock stays a single line.
+        let linear: Vec<f64> = times
+            .iter()
+            .enumerate()
+            .map(|(i, t)| 1.5 * t + if i % 3 == 0 { 0.1 } else { -0.05 })
+            .collect();
+        assert!(fit_piecewise_drift(&times, &linear).is_none());
+    }
 
-        let ref_samples = signal.clone();
-        let tgt_samples: Vec<f32> = signal[delay_samples as usize..].to_vec();
+    #[test]
+    fn test_sinc_drift_correction_keeps_high_frequencies() {
+        // 15 kHz tone at 48 kHz, corrected for 200 ppm over 3 s.
+        let sr = 48000u32;
+        let w = 2.0 * std::f64::consts::PI * 15000.0 / sr as f64;
+        let audio: Vec<f64> = (0..sr as usize * 3).map(|i| (w * i as f64).sin()).collect();
+        let segments = [DriftSegment { start_s: 0.0, ppm: 200.0 }];
+        let ideal = |i: usize| (w * i as f64 * (1.0 + 200e-6)).sin();
+        // Skip the edges, where the filters run out of input.
+        let rms_error = |out: &[f64]| {
+            let range = 1000..out.len() - 1000;
+            let n = range.len() as f64;
+            (range.map(|i| (out[i] - ideal(i)).powi(2)).sum::<f64>() / n).sqrt()
+        };
 
-        let mut tracks = vec![
-            Track::new("RefDev".into()),
-            Track::new("Target".into()),
+        let fast = apply_drift_correction_f64(&audio, 200.0, false);
+        let sinc = resample_drift_sinc(&audio, &segments, sr, ResampleQuality::Balanced).unwrap();
+        let high = resample_drift_sinc(&audio, &segments, sr, ResampleQuality::High).unwrap();
+        assert_eq!(sinc.len(), fast.len());
+        assert_eq!(high.len(), fast.len());
+        assert!(rms_error(&fast) > 0.1, "{}", rms_error(&fast));
+        assert!(rms_error(&sinc) < 0.01, "{}", rms_error(&sinc));
+        assert!(rms_error(&high) < 0.01, "{}", rms_error(&high));
+
+        // A piecewise model comes out the same length as the fast path.
+        let piecewise = [
+            DriftSegment { start_s: 0.0, ppm: 300.0 },
+            DriftSegment { start_s: 1.5, ppm: -300.0 },
         ];
+        let sinc = resample_drift_sinc(&audio, &piecewise, sr, ResampleQuality::Balanced).unwrap();
+        assert_eq!(sinc.len(), apply_drift_segments_f64(&audio, &piecewise, sr, false).len());
+    }
 
-        let mut ref_clip = Clip::new("ref.wav".into(), "ref.wav".into(), 48000, 1);
-        ref_clip.duration_s = ref_samples.len() as f64 / sr as f64;
-        ref_clip.samples = ref_samples;
-        tracks[0].clips.push(ref_clip);
-
-        let mut tgt_clip = Clip::new("tgt.wav".into(), "tgt.wav".into(), 48000, 1);
-        tgt_clip.duration_s = tgt_samples.len() as f64 / sr as f64;
-        tgt_clip.samples = tgt_samples;
-        tracks[1].clips.push(tgt_clip);
-
-        let config = SyncConfig::default();
-        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
-
-        // Reference should be track 0 (longer)
-        assert_eq!(result.reference_track_index, 0);
-
-        // Target clip should have offset close to delay_samples
-        let tgt_offset = tracks[1].clips[0].timeline_offset_samples;
-        assert!(
-            (tgt_offset - delay_samples).abs() <= 2,
-            "Expected offset ~{}, got {}",
-            delay_samples,
-            tgt_offset
-        );
+    #[test]
+    fn test_cubic_drift_correction_beats_linear() {
+        // 8 kHz tone in a 96 kHz master, corrected for 200 ppm over 1 s.
+        let sr = 96000u32;
+        let w = 2.0 * std::f64::consts::PI * 8000.0 / sr as f64;
+        let audio: Vec<f64> = (0..sr as usize).map(|i| (w * i as f64).sin()).collect();
+        let linear = apply_drift_correction_f64(&audio, 200.0, false);
+        let cubic = apply_drift_correction_f64(&audio, 200.0, true);
+        assert_eq!(cubic.len(), linear.len());
+
+        // Output sample i reads the source at i × ratio.
+        let rms_error = |out: &[f64], ratio: f64| {
+            let range = 10..out.len() - 10;
+            let n = range.len() as f64;
+            (range.map(|i| (out[i] - (w * i as f64 * ratio).sin()).powi(2)).sum::<f64>() / n).sqrt()
+        };
+        // The constant-drift path rounds its length, so its ratio is inexact.
+        let ratio = audio.len() as f64 / linear.len() as f64;
+        assert!(rms_error(&linear, ratio) > 0.01, "{}", rms_error(&linear, ratio));
+        assert!(rms_error(&cubic, ratio) < rms_error(&linear, ratio) / 5.0, "{}", rms_error(&cubic, ratio));
+
+        // The piecewise path interpolates the same way.
+        let single = [DriftSegment { start_s: 0.0, ppm: 200.0 }];
+        let segmented = apply_drift_segments_f64(&audio, &single, sr, true);
+        assert!(rms_error(&segmented, 1.0 + 200e-6) < rms_error(&linear, ratio) / 5.0);
+    }
 
-        // Confidence should be reasonable
-        assert!(
-            tracks[1].clips[0].confidence > 2.0,
-            "Confidence {} too low",
-            tracks[1].clips[0].confidence
-        );
+    #[test]
+    fn test_apply_drift_segments() {
+        let sr = 1000;
+        let audio: Vec<f64> = (0..100_000).map(|i| (i as f64 * 0.01).sin()).collect();
+        let single = [DriftSegment { start_s: 0.0, ppm: 500.0 }];
+        let linear = apply_drift_correction_f64(&audio, 500.0, false);
+        let segmented = apply_drift_segments_f64(&audio, &single, sr, false);
+        assert_eq!(segmented.len(), linear.len());
+        assert!(segmented.iter().zip(&linear).all(|(a, b)| (a - b).abs() < 1e-3));
+
+        // 50 s at +1000 ppm then −1000 ppm: the source is used up after
+        // 50 s + (100 − 50.05) s / 0.999 of timeline.
+        let segments = [
+            DriftSegment { start_s: 0.0, ppm: 1000.0 },
+            DriftSegment { start_s: 50.0, ppm: -1000.0 },
+        ];
+        let out = apply_drift_segments_f64(&audio, &segments, sr, false);
+        let expected = (50.0 + (100.0 - 50.05) / 0.999) * sr as f64;
+        assert!((out.len() as f64 - expected).abs() <= 1.0, "{}", out.len());
+        // Sample at 60 s reads source 50.05 + 10 × 0.999 s.
+        let src = (50.05 + 10.0 * 0.999) * sr as f64;
+        assert!((out[60_000] - (src * 0.01).sin()).abs() < 1e-3);
     }
 
     #[test]
-    fn test_analyze_cancellation() {
-        let mut tracks = vec![Track::new("Test".into())];
-        let mut clip = Clip::new("t.wav".into(), "t.wav".into(), 48000, 1);
-        clip.duration_s = 1.0;
-        clip.samples = vec![0.0; 8000];
-        tracks[0].clips.push(clip);
+    fn test_segmented_match_agrees_with_single_pass() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(21, sr as usize * 90);
+        let start = (sr as f64 * 61.3) as usize;
+        let mut target = reference[start..start + sr as usize * 5].to_vec();
+        for (x, n) in target.iter_mut().zip(noise(8, sr as usize * 5)) {
+            *x += 0.5 * n;
+        }
 
-        let config = SyncConfig::default();
-        let cancel = new_cancel_token();
-        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        // Small blocks so the 90 s reference spans several of them.
+        let full = match_delay(&reference, &target, sr, None);
+        let norm = CorrelationNormalization::default();
+        let (seg, _) = segmented_match(&reference, &target, sr, None, (1 << 12, 1), norm, &None);
+        assert_eq!(full.delay_samples, start as i64);
+        assert_eq!(seg.delay_samples, full.delay_samples);
+        assert!((seg.score - full.score).abs() < 1.0, "{} vs {}", seg.score, full.score);
+        assert!((seg.confidence - full.confidence).abs() / full.confidence < 1e-3);
+
+        // A search window that excludes the true lag still picks its best peak inside.
+        let (near, _) =
+            segmented_match(&reference, &target, sr, Some(10.0), (1 << 12, 1), norm, &None);
+        assert_eq!(near.delay_samples, match_delay(&reference, &target, sr, Some(10.0)).delay_samples);
+        assert!(near.score < 20.0);
+    }
 
-        let result = analyze(&mut tracks, &config, &None, &Some(cancel));
-        assert!(result.is_err());
+    #[test]
+    fn test_cancel_stops_correlation() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(5, sr as usize * 60);
+        let target = reference[sr as usize * 20..sr as usize * 30].to_vec();
+        let norm = CorrelationNormalization::default();
+        let (found, _) = search_delay(&reference, &target, sr, None, 1, norm, &None);
+        assert_eq!(found.delay_samples, sr as i64 * 20);
+
+        // A cancelled search computes no blocks and finds nothing.
+        let cancel = Some(new_cancel_token());
+        cancel.as_ref().unwrap().store(true, std::sync::atomic::Ordering::Relaxed);
+        let (seg, _) = segmented_match(&reference, &target, sr, None, (1 << 12, 1), norm, &cancel);
+        assert_eq!(seg.score, 0.0);
+        let (m, _) = search_delay(&reference, &target, sr, None, 1, norm, &cancel);
+        assert_eq!(m.score, 0.0);
+        assert!(fft_correlate(&reference, &target, &cancel).iter().all(|&c| c == 0.0));
+
+        let mut clip = Clip::new("c.wav".into(), "c.wav".into(), sr, 1);
+        clip.samples = reference.clone();
+        assert!(measure_drift(&reference, &clip, sr, &None).windows > 0);
+        assert_eq!(measure_drift(&reference, &clip, sr, &cancel).windows, 0);
     }
 
     #[test]
@@ -1300,7 +6327,7 @@ mod tests {
         // Simple known case: correlate [1,0,0] with reversed [0,0,1] = convolve [1,0,0] with [1,0,0]
         let a = vec![1.0f32, 0.0, 0.0, 0.0];
         let b = vec![1.0f32, 0.0, 0.0, 0.0];
-        let corr = fft_correlate(&a, &b);
+        let corr = fft_correlate(&a, &b, &None);
         // Full convolution length = 4 + 4 - 1 = 7
         assert_eq!(corr.len(), 7);
         // Peak should be near the center
@@ -1314,6 +6341,45 @@ mod tests {
         assert_eq!(peak_idx, 3);
     }
 
+    #[test]
+    fn test_real_fft_correlation_matches_direct() {
+        let reference = noise(11, 1001);
+        let target = noise(12, 237);
+        // fftconvolve(reference, target[::-1], "full"), term by term.
+        let direct: Vec<f32> = (0..reference.len() + target.len() - 1)
+            .map(|k| {
+                let shift = k as i64 - (target.len() as i64 - 1);
+                target
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &t)| {
+                        let r = reference.get(usize::try_from(i as i64 + shift).ok()?)?;
+                        Some(r * t)
+                    })
+                    .sum()
+            })
+            .collect();
+
+        let full = cpu_correlate(&reference, &target, &None);
+        assert_eq!(full.len(), direct.len());
+        assert!(full.iter().zip(&direct).all(|(a, b)| (a - b).abs() < 1e-3));
+
+        // Overlap-save blocks, every lag its own bucket.
+        let scan = segmented_correlate(&reference, &target, (0, direct.len()), 512, 1, None, &None);
+        assert!(scan.buckets.iter().all(|&(k, v)| (v - direct[k]).abs() < 1e-3));
+        let peak = full.iter().copied().max_by(|a, b| a.abs().total_cmp(&b.abs())).unwrap();
+        assert_eq!(full[scan.peak_idx], peak);
+    }
+
+    #[test]
+    fn test_fft_plans_are_shared() {
+        let (fft, ifft) = fft_plans(1 << 10);
+        let handle = std::thread::spawn(|| fft_plans(1 << 10));
+        let (fft2, ifft2) = handle.join().unwrap();
+        assert!(Arc::ptr_eq(&fft, &fft2) && Arc::ptr_eq(&ifft, &ifft2));
+        assert_eq!(fft_plans(1 << 11).0.len(), 1 << 11);
+    }
+
     #[test]
     fn test_subsample_peak_edge_cases() {
         let data = vec![1.0f32]; // Single element
@@ -0,0 +1,142 @@
+//! Spectral noise reduction for the analysis copy of noisy recordings.
+//!
+//! Action-camera and drone microphones bury the scene under wind, motor and
+//! preamp hiss that correlates with nothing, so the true peak drowns. Each
+//! clip's own noise spectrum is estimated from its quietest frames and
+//! subtracted from its overall spectrum; what is left sets how much of each
+//! frequency is kept. Pauses that were only noise then fall quiet enough for
+//! the silence gate. Only the 8 kHz analysis audio is cleaned; exported audio
+//! is untouched.
+
+use super::fft_plans;
+use realfft::num_complex::Complex;
+
+/// STFT frame length (samples): 64 ms at 8 kHz.
+const FRAME: usize = 512;
+
+/// Hop between frames; a periodic Hann window at half overlap sums to one.
+const HOP: usize = FRAME / 2;
+
+/// Share of the frames holding any sound, quietest first, taken as noise.
+const NOISE_SHARE: f64 = 0.2;
+
+/// Least gain left in any bin (-40 dB).
+const GAIN_FLOOR: f32 = 0.01;
+
+/// `samples` with their stationary noise subtracted bin by bin. Audio too
+/// short for a frame, or without quiet frames to learn the noise from, comes
+/// back as it was.
+pub fn spectral_denoise(samples: &[f32]) -> Vec<f32> {
+    if samples.len() < FRAME {
+        return samples.to_vec();
+    }
+    let window: Vec<f32> = (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / FRAME as f32).cos())
+        .collect();
+    let (forward, inverse) = fft_plans(FRAME);
+
+    // Pad by a hop on both sides so every sample is covered by two frames.
+    let mut padded = vec![0.0f32; HOP];
+    padded.extend_from_slice(samples);
+    padded.resize(padded.len().div_ceil(HOP) * HOP + HOP, 0.0);
+    let frames = (padded.len() - FRAME) / HOP + 1;
+
+    let spectra: Vec<Vec<Complex<f32>>> = (0..frames)
+        .map(|f| {
+            let mut input: Vec<f32> =
+                padded[f * HOP..f * HOP + FRAME].iter().zip(&window).map(|(x, w)| x * w).collect();
+            let mut spectrum = forward.make_output_vec();
+            forward.process(&mut input, &mut spectrum).expect("frame length matches the plan");
+            spectrum
+        })
+        .collect();
+
+    // The noise spectrum is the mean of the quietest frames that hold any
+    // sound at all (gaps between a reference's clips are digital zero).
+    // Frames are ranked by mean log power across bins, so a loud but narrow
+    // rumble can't pick the frames where it happens to dip.
+    let active: Vec<&Vec<Complex<f32>>> =
+        spectra.iter().filter(|s| s.iter().any(|c| c.norm_sqr() > 0.0)).collect();
+    if active.len() < 2 {
+        return samples.to_vec();
+    }
+    let loudness = |s: &[Complex<f32>]| s.iter().map(|c| (c.norm_sqr() + 1e-20).ln()).sum::<f32>();
+    let mut quiet: Vec<&Vec<Complex<f32>>> = active.clone();
+    quiet.sort_by(|a, b| loudness(a).total_cmp(&loudness(b)));
+    quiet.truncate(((active.len() as f64 * NOISE_SHARE).ceil() as usize).max(1));
+    let mean_power = |frames: &[&Vec<Complex<f32>>], bin: usize| {
+        frames.iter().map(|s| s[bin].norm_sqr()).sum::<f32>() / frames.len() as f32
+    };
+
+    // One gain per bin for the whole clip: its power less the noise's, as a
+    // share of its power. Unlike gains set frame by frame, this filters
+    // linearly, leaving the waveform's correlation intact.
+    let gain: Vec<f32> = (0..=FRAME / 2)
+        .map(|bin| {
+            let total = mean_power(&active, bin);
+            if total > 0.0 {
+                (1.0 - mean_power(&quiet, bin) / total).max(GAIN_FLOOR)
+            } else {
+                1.0
+            }
+        })
+        .collect();
+
+    let mut out = vec![0.0f32; padded.len()];
+    let mut frame = inverse.make_output_vec();
+    for (f, mut spectrum) in spectra.into_iter().enumerate() {
+        spectrum.iter_mut().zip(&gain).for_each(|(c, g)| *c *= g);
+        inverse.process(&mut spectrum, &mut frame).expect("frame length matches the plan");
+        for (o, x) in out[f * HOP..f * HOP + FRAME].iter_mut().zip(&frame) {
+            *o += x / FRAME as f32;
+        }
+    }
+    out[HOP..HOP + samples.len()].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{analyze, band_pass};
+    use crate::models::*;
+
+    #[test]
+    fn test_denoise_rescues_noisy_camera_match() {
+        let sr = ANALYSIS_SR;
+        // Bursts of mid-band sound with pauses, as speech has.
+        let len = sr as usize * 30;
+        let raw = noise(23, len);
+        let mut scene = band_pass(&raw, sr, 300.0, 3000.0);
+        for (i, s) in scene.iter_mut().enumerate() {
+            if (i / (sr as usize * 3 / 10)) % 3 == 2 {
+                *s = 0.0;
+            }
+        }
+        // Wind rumble and hiss far louder than the scene, all the time.
+        let delay = sr as usize * 7 + 1234;
+        let hiss = noise(24, sr as usize * 12);
+        let rumble = band_pass(&noise(25, hiss.len()), sr, 0.0, 150.0);
+        let camera: Vec<f32> = scene[delay..delay + hiss.len()]
+            .iter()
+            .zip(hiss.iter().zip(&rumble))
+            .map(|(s, (h, r))| s + 0.8 * h + 160.0 * r)
+            .collect();
+
+        let run = |denoise: bool| {
+            let mut tracks = vec![Track::new("Recorder".into()), Track::new("GoPro".into())];
+            for (ti, samples) in [(0, scene.clone()), (1, camera.clone())] {
+                let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+                clip.duration_s = samples.len() as f64 / sr as f64;
+                clip.samples = samples;
+                tracks[ti].clips.push(clip);
+            }
+            let config = SyncConfig { denoise, ..Default::default() };
+            analyze(&mut tracks, &config, &None, &None).unwrap();
+            let clip = &tracks[1].clips[0];
+            (clip.timeline_offset_samples, clip.match_score)
+        };
+        assert!(run(false).1 < CONFIDENCE_THRESHOLD);
+        let (offset, score) = run(true);
+        assert_eq!(offset, delay as i64);
+        assert!(score >= CONFIDENCE_THRESHOLD, "{}", score);
+    }
+}
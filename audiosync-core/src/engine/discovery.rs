@@ -0,0 +1,402 @@
+//! Sync-group discovery — spotting recordings that don't belong together.
+//!
+//! After analysis, clips that matched nothing on the reference timeline are
+//! correlated with each other. Clips that do match form connected sets (a
+//! second room, a different event); where such a set covers whole tracks,
+//! those tracks are proposed as a sync group of their own instead of being
+//! reported clip by clip as low confidence. A clip that matches nothing at
+//! all — not the timeline, not another unmatched clip — is an outsider
+//! (typically a file from another day) and is left unplaced.
+//!
+//! Before analysis, a folder holding several shoots can be split into
+//! sessions, each its own sync group and timeline: clips are clustered by
+//! creation time, gaps are bridged where the clips either side share audio,
+//! and clips without a time join the session they correlate with.
+
+use std::collections::HashMap;
+
+use log::info;
+
+use super::{activity_mask, match_delay_candidates, min_shared_audio_s, shared_audio_s, VAD_FRAME_S};
+use crate::models::*;
+
+/// Pairwise correlation is quadratic; with more unmatched clips than this,
+/// discovery is skipped.
+const MAX_DISCOVERY_CLIPS: usize = 48;
+
+/// Normalized cross-correlation below which a clip's best match is no
+/// better than chance: nothing on the timeline resembles it.
+const UNRELATED_NCC: f64 = 0.1;
+
+/// Clips of a session correlated against when bridging a gap, or placing a
+/// clip without a creation time.
+const SESSION_PROBES: usize = 6;
+
+/// Split unmatched clips (`(track, clip)` indices) into sets connected by
+/// what they match among themselves. Empty when there are too many to
+/// compare.
+pub(crate) fn match_sets(
+    tracks: &[Track],
+    unmatched: &[(usize, usize)],
+    config: &SyncConfig,
+) -> Vec<Vec<(usize, usize)>> {
+    let n = unmatched.len();
+    if n > MAX_DISCOVERY_CLIPS {
+        return Vec::new();
+    }
+
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for a in 0..n {
+        for b in (a + 1)..n {
+            let ((ta, ca), (tb, cb)) = (unmatched[a], unmatched[b]);
+            // One device never records two clips at once.
+            if ta == tb {
+                continue;
+            }
+            if clips_match(&tracks[ta].clips[ca], &tracks[tb].clips[cb], config) {
+                let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+                parent[ra] = rb;
+            }
+        }
+    }
+
+    let mut sets: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+    for (i, &clip) in unmatched.iter().enumerate() {
+        let r = root(&mut parent, i);
+        match sets.iter_mut().find(|(s, _)| *s == r) {
+            Some((_, members)) => members.push(clip),
+            None => sets.push((r, vec![clip])),
+        }
+    }
+    sets.into_iter().map(|(_, members)| members).collect()
+}
+
+/// Whether two clips share audio: a confident correlation peak over enough
+/// audio active in both.
+fn clips_match(x: &Clip, y: &Clip, config: &SyncConfig) -> bool {
+    let (reference, target) = if x.samples.len() >= y.samples.len() { (x, y) } else { (y, x) };
+    let (m, _) = match_delay_candidates(
+        &reference.samples,
+        &target.samples,
+        ANALYSIS_SR,
+        config.max_offset_s,
+        1,
+        config.correlation_normalization,
+    );
+    let shared_s = shared_audio_s(&reference.samples, &target.samples, m.delay_samples, ANALYSIS_SR);
+    m.score >= CONFIDENCE_THRESHOLD && shared_s >= min_shared_audio_s(target)
+}
+
+/// Propose each of the [`match_sets`] spanning two or more whole tracks as
+/// a sync group.
+pub(crate) fn propose_groups(tracks: &[Track], sets: &[Vec<(usize, usize)>]) -> Vec<ProposedSyncGroup> {
+    let mut proposed = Vec::new();
+    for members in sets {
+        let mut track_indices: Vec<usize> = members.iter().map(|&(ti, _)| ti).collect();
+        track_indices.sort_unstable();
+        track_indices.dedup();
+        // Only tracks that moved over entirely; a device that switched rooms
+        // mid-shoot can't go in either group.
+        track_indices.retain(|&ti| {
+            members.iter().filter(|&&(t, _)| t == ti).count() == tracks[ti].clips.len()
+        });
+        if track_indices.len() < 2 {
+            continue;
+        }
+        let group = ProposedSyncGroup {
+            track_names: track_indices.iter().map(|&ti| tracks[ti].name.clone()).collect(),
+            clip_ids: track_indices
+                .iter()
+                .flat_map(|&ti| tracks[ti].clips.iter().map(|c| c.file_path.clone()))
+                .collect(),
+            track_indices,
+        };
+        info!("Tracks {:?} match each other but not the reference", group.track_names);
+        proposed.push(group);
+    }
+    proposed
+}
+
+/// Clips alone in their match set whose best match on the timeline
+/// (`similarity`, the peak normalized cross-correlation over both passes)
+/// was no better than chance. Clips with too little audio of their own to
+/// judge are never outsiders.
+pub(crate) fn outsiders(
+    tracks: &[Track],
+    sets: &[Vec<(usize, usize)>],
+    similarity: &HashMap<(usize, usize), f64>,
+) -> Vec<(usize, usize)> {
+    sets.iter()
+        .filter(|members| members.len() == 1)
+        .map(|members| members[0])
+        .filter(|&(ti, ci)| {
+            let clip = &tracks[ti].clips[ci];
+            let active_s = activity_mask(&clip.samples, ANALYSIS_SR).iter().filter(|&&a| a).count() as f64
+                * VAD_FRAME_S;
+            similarity.get(&(ti, ci)).is_some_and(|&ncc| ncc < UNRELATED_NCC)
+                && active_s >= min_shared_audio_s(clip)
+        })
+        .collect()
+}
+
+/// Move each proposed group's tracks into a new sync group, named `group-2`,
+/// `group-3`… past any names already in use. Returns the names given.
+pub fn assign_proposed_groups(tracks: &mut [Track], proposed: &[ProposedSyncGroup]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut next = 2;
+    for group in proposed {
+        let name = loop {
+            let candidate = format!("group-{}", next);
+            next += 1;
+            if !tracks.iter().any(|t| t.sync_group_name() == candidate) {
+                break candidate;
+            }
+        };
+        for &ti in &group.track_indices {
+            if let Some(track) = tracks.get_mut(ti) {
+                track.sync_group = Some(name.clone());
+                track.is_reference = false;
+                for clip in &mut track.clips {
+                    clip.analyzed = clip.is_pinned();
+                }
+            }
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Split the clips of tracks in the default sync group into sessions —
+/// sync groups `session-1`, `session-2`… in order of time, each analyzed
+/// onto a timeline of its own. A track with clips in several sessions is
+/// split with them. Returns the session names; empty, with nothing moved,
+/// when all the clips make one session.
+pub fn split_sessions(tracks: &mut Vec<Track>, gap_s: f64, config: &SyncConfig) -> Vec<String> {
+    let default: Vec<usize> = (0..tracks.len())
+        .filter(|&ti| tracks[ti].sync_group_name() == DEFAULT_SYNC_GROUP)
+        .collect();
+    let clips: Vec<(usize, usize)> =
+        default.iter().flat_map(|&ti| (0..tracks[ti].clips.len()).map(move |ci| (ti, ci))).collect();
+    let sessions = cluster_sessions(tracks, &clips, gap_s, config);
+    if sessions.len() <= 1 {
+        return Vec::new();
+    }
+
+    let mut names = Vec::new();
+    let mut next = 1;
+    while names.len() < sessions.len() {
+        let candidate = format!("session-{}", next);
+        next += 1;
+        if !tracks.iter().any(|t| t.sync_group_name() == candidate) {
+            names.push(candidate);
+        }
+    }
+    let home: HashMap<(usize, usize), usize> =
+        sessions.iter().enumerate().flat_map(|(s, members)| members.iter().map(move |&c| (c, s))).collect();
+
+    let mut taken: HashMap<usize, Vec<Option<Clip>>> = default
+        .iter()
+        .map(|&ti| (ti, std::mem::take(&mut tracks[ti].clips).into_iter().map(Some).collect()))
+        .collect();
+    let mut split = Vec::new();
+    for (s, name) in names.iter().enumerate() {
+        for &ti in &default {
+            let clips: Vec<Clip> = taken
+                .get_mut(&ti)
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .filter(|(ci, _)| home.get(&(ti, *ci)) == Some(&s))
+                .filter_map(|(_, clip)| clip.take())
+                .map(|mut clip| {
+                    clip.analyzed = clip.is_pinned();
+                    clip
+                })
+                .collect();
+            if clips.is_empty() {
+                continue;
+            }
+            let mut track = tracks[ti].clone();
+            track.sync_group = Some(name.clone());
+            track.is_reference = false;
+            track.clips = clips;
+            split.push(track);
+        }
+    }
+    let mut ti = 0;
+    tracks.retain(|_| {
+        ti += 1;
+        !default.contains(&(ti - 1))
+    });
+    tracks.extend(split);
+    info!("Split {} clips into sessions {:?}", clips.len(), names);
+    names
+}
+
+/// Cluster `clips` into sessions: a new one wherever none was recording
+/// (by creation time) for `gap_s`, unless clips either side of the gap
+/// correlate. Clips without a creation time join the first session they
+/// correlate with, or the largest.
+fn cluster_sessions(
+    tracks: &[Track],
+    clips: &[(usize, usize)],
+    gap_s: f64,
+    config: &SyncConfig,
+) -> Vec<Vec<(usize, usize)>> {
+    let clip = |&(ti, ci): &(usize, usize)| &tracks[ti].clips[ci];
+    let mut timed: Vec<(usize, usize)> = clips.iter().copied().filter(|c| clip(c).creation_time.is_some()).collect();
+    let start = |c: &(usize, usize)| clip(c).creation_time.unwrap_or(0.0);
+    timed.sort_by(|a, b| start(a).total_cmp(&start(b)));
+
+    let mut sessions: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut end = f64::NEG_INFINITY;
+    for c in timed {
+        if sessions.is_empty() || start(&c) > end + gap_s {
+            sessions.push(Vec::new());
+        }
+        end = end.max(start(&c) + clip(&c).duration_s);
+        sessions.last_mut().unwrap().push(c);
+    }
+
+    // A recorder with its clock off lands in a gap of its own; bridged by
+    // the audio it shares with the session before.
+    let shares_audio = |a: &[(usize, usize)], b: &[(usize, usize)]| {
+        a.iter().any(|x| b.iter().any(|y| x.0 != y.0 && clips_match(clip(x), clip(y), config)))
+    };
+    let mut k = 0;
+    while k + 1 < sessions.len() {
+        let before = &sessions[k][sessions[k].len().saturating_sub(SESSION_PROBES)..];
+        let after = &sessions[k + 1][..sessions[k + 1].len().min(SESSION_PROBES)];
+        if shares_audio(before, after) {
+            let next = sessions.remove(k + 1);
+            sessions[k].extend(next);
+        } else {
+            k += 1;
+        }
+    }
+    if sessions.len() <= 1 {
+        return vec![clips.to_vec()];
+    }
+
+    for c in clips.iter().copied().filter(|c| clip(c).creation_time.is_none()) {
+        let probes = |members: &[(usize, usize)]| {
+            let mut longest = members.to_vec();
+            longest.sort_by(|a, b| clip(b).duration_s.total_cmp(&clip(a).duration_s));
+            longest.truncate(SESSION_PROBES);
+            longest
+        };
+        let home = sessions
+            .iter()
+            .position(|members| shares_audio(&[c], &probes(members)))
+            .unwrap_or_else(|| (0..sessions.len()).max_by_key(|&s| sessions[s].len()).unwrap_or(0));
+        sessions[home].push(c);
+    }
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(name: &str, samples: Vec<f32>) -> Track {
+        let mut t = Track::new(name.into());
+        let path = format!("{}.wav", name);
+        let mut c = Clip::new(path.clone(), path, 48000, 1);
+        c.duration_s = samples.len() as f64 / ANALYSIS_SR as f64;
+        c.samples = samples;
+        t.clips.push(c);
+        t
+    }
+
+    #[test]
+    fn test_two_shoots_split_into_sessions() {
+        const DAY: f64 = 86_400.0;
+        let t0 = 1_700_000_000.0;
+        let (shoot_a, shoot_b) = (noise(11, 48000), noise(42, 48000));
+        let timed = |name: &str, samples: Vec<f32>, at: Option<f64>| {
+            let mut t = track(name, samples);
+            t.clips[0].creation_time = at;
+            t
+        };
+        let mut cam = timed("Cam", shoot_a.clone(), Some(t0));
+        cam.clips.push(timed("Cam2", shoot_b.clone(), Some(t0 + DAY)).clips.remove(0));
+        let mut zoom = timed("Zoom", shoot_a[8000..40000].to_vec(), Some(t0 + 1.0));
+        zoom.clips.push(timed("Zoom2", shoot_b[4000..36000].to_vec(), Some(t0 + DAY + 0.5)).clips.remove(0));
+        let mut tracks = vec![
+            cam,
+            zoom,
+            // A recorder whose clock is five hours out, and one without a clock.
+            timed("Rec", shoot_a[16000..44000].to_vec(), Some(t0 + 5.0 * 3600.0)),
+            timed("Lav", shoot_b[10000..30000].to_vec(), None),
+        ];
+
+        let names = split_sessions(&mut tracks, 3600.0, &SyncConfig::default());
+        assert_eq!(names, ["session-1", "session-2"]);
+        let layout: Vec<(&str, &str, usize)> = tracks
+            .iter()
+            .map(|t| (t.name.as_str(), t.sync_group_name(), t.clips.len()))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                ("Cam", "session-1", 1),
+                ("Zoom", "session-1", 1),
+                ("Rec", "session-1", 1),
+                ("Cam", "session-2", 1),
+                ("Zoom", "session-2", 1),
+                ("Lav", "session-2", 1),
+            ]
+        );
+        assert_eq!(tracks[3].clips[0].name, "Cam2.wav");
+
+        // Each shoot on a timeline of its own.
+        let result = super::super::analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(result.groups.len(), 2);
+        assert!(result.total_timeline_s < 10.0);
+        let cam2 = result.clip_offsets["Cam2.wav"];
+        assert!((result.clip_offsets["Lav.wav"] - cam2 - 10000).abs() <= 2);
+        assert!((result.clip_offsets["Rec.wav"] - result.clip_offsets["Cam.wav"] - 16000).abs() <= 2);
+
+        // Already split, nothing is left to split.
+        assert!(split_sessions(&mut tracks, 3600.0, &SyncConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_second_room_is_proposed_as_a_group() {
+        let (room_a, room_b) = (noise(3, 48000), noise(77, 40000));
+        let mut tracks = vec![
+            track("A1", room_a.clone()),
+            track("A2", room_a[8000..40000].to_vec()),
+            track("B1", room_b.clone()),
+            track("B2", room_b[4000..36000].to_vec()),
+            track("Lone", noise(999, 16000)),
+        ];
+
+        let result = super::super::analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert_eq!(result.proposed_groups.len(), 1);
+        assert_eq!(result.proposed_groups[0].track_names, ["B1", "B2"]);
+        // One warning for the group instead of one per clip.
+        assert!(result.warnings.iter().any(|w| w.code == WarningCode::SeparateRecording));
+        assert!(!result.warnings.iter().any(|w| w.clip_id.as_deref() == Some("B2.wav")));
+        assert!(result.warnings.iter().any(|w| w.clip_id.as_deref() == Some("Lone.wav")));
+        // Matching nothing at all, the lone clip is left off the timeline.
+        assert_eq!(result.unplaced_clips, ["Lone.wav"]);
+
+        // Applied automatically, room B is synced against itself.
+        let config = SyncConfig { auto_sync_groups: true, ..Default::default() };
+        let result = super::super::analyze(&mut tracks, &config, &None, &None).unwrap();
+        assert!(result.proposed_groups.is_empty());
+        assert_eq!(tracks[2].sync_group.as_deref(), Some("group-2"));
+        assert_eq!(result.groups.len(), 2);
+        let b1 = result.clip_offsets["B1.wav"];
+        assert!((result.clip_offsets["B2.wav"] - b1 - 4000).abs() <= 2);
+    }
+}
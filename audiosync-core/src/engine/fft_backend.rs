@@ -0,0 +1,132 @@
+//! Pluggable correlation backends.
+//!
+//! Large multicam sessions spend most of their analysis time in FFT
+//! cross-correlation. A front end with a GPU can install a
+//! [`CorrelationBackend`] (wgpu compute, cuFFT…) with
+//! [`set_correlation_backend`]; the engine then hands it every correlation,
+//! batching those it can (drift windows), and falls back to realfft on the
+//! CPU whenever the backend declines or none is installed.
+//!
+//! Built with the `gpu` feature, [`WgpuBackend`] provides one on any GPU
+//! wgpu supports (Vulkan, Metal, DirectX 12).
+
+use log::{debug, warn};
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "gpu")]
+mod gpu;
+
+#[cfg(feature = "gpu")]
+pub use gpu::WgpuBackend;
+
+/// An engine for full cross-correlations.
+pub trait CorrelationBackend: Send + Sync {
+    /// Name for logs and diagnostics, e.g. `wgpu (Apple M2)`.
+    fn name(&self) -> &str;
+
+    /// Correlate each `(reference, target)` pair as
+    /// `fftconvolve(reference, target[::-1], "full")`: `reference.len() +
+    /// target.len() - 1` values, zero lag at `target.len() - 1`. `None` when
+    /// the batch can't run here (no device, out of memory); the CPU then
+    /// takes the whole batch.
+    fn correlate_batch(&self, pairs: &[(&[f32], &[f32])]) -> Option<Vec<Vec<f32>>>;
+}
+
+static BACKEND: RwLock<Option<Arc<dyn CorrelationBackend>>> = RwLock::new(None);
+
+/// Install `backend` for all later correlations, or go back to the CPU with
+/// `None`.
+pub fn set_correlation_backend(backend: Option<Arc<dyn CorrelationBackend>>) {
+    if let Some(b) = &backend {
+        debug!("Correlation backend: {}", b.name());
+    }
+    *BACKEND.write().unwrap_or_else(|e| e.into_inner()) = backend;
+}
+
+/// Name of the installed backend, `realfft` when correlating on the CPU.
+pub fn correlation_backend_name() -> String {
+    BACKEND
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map_or_else(|| "realfft".to_string(), |b| b.name().to_string())
+}
+
+/// Run `pairs` on the installed backend. `None` when there is none, it
+/// declined, or it returned the wrong shape.
+pub(super) fn offload(pairs: &[(&[f32], &[f32])]) -> Option<Vec<Vec<f32>>> {
+    let backend = BACKEND.read().unwrap_or_else(|e| e.into_inner()).clone()?;
+    let results = backend.correlate_batch(pairs)?;
+    let fits = results.len() == pairs.len()
+        && results
+            .iter()
+            .zip(pairs)
+            .all(|(c, (r, t))| c.len() == (r.len() + t.len()).saturating_sub(1));
+    if !fits {
+        warn!("Correlation backend '{}' returned a malformed batch; using the CPU", backend.name());
+        return None;
+    }
+    Some(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{correlate_batch, cpu_correlate, measure_drift};
+    use crate::models::{noise, Clip, ANALYSIS_SR};
+    use std::sync::Mutex;
+
+    /// Runs on the CPU like the default path, recording batch sizes.
+    struct Recording {
+        batches: Mutex<Vec<usize>>,
+        malformed: bool,
+    }
+
+    impl CorrelationBackend for Recording {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn correlate_batch(&self, pairs: &[(&[f32], &[f32])]) -> Option<Vec<Vec<f32>>> {
+            self.batches.lock().unwrap().push(pairs.len());
+            if self.malformed {
+                return Some(Vec::new());
+            }
+            Some(pairs.iter().map(|(r, t)| cpu_correlate(r, t, &None)).collect())
+        }
+    }
+
+    // One test: the backend is process-wide. Other tests running meanwhile
+    // get the same correlations through it.
+    #[test]
+    fn test_backend_takes_batches_and_falls_back() {
+        let signal = noise(3, ANALYSIS_SR as usize * 40);
+        let pairs: Vec<(&[f32], &[f32])> =
+            vec![(&signal[..4000], &signal[100..900]), (&signal[..300], &signal[..20])];
+        let on_cpu = correlate_batch(&pairs, &None);
+        assert_eq!(correlation_backend_name(), "realfft");
+
+        let backend = Arc::new(Recording {
+            batches: Mutex::new(Vec::new()),
+            malformed: false,
+        });
+        set_correlation_backend(Some(backend.clone()));
+        assert_eq!(correlation_backend_name(), "recording");
+        assert_eq!(correlate_batch(&pairs, &None), on_cpu);
+        assert!(backend.batches.lock().unwrap().contains(&2));
+
+        // Drift windows go over as batches.
+        let mut clip = Clip::new("c.wav".into(), "c.wav".into(), ANALYSIS_SR, 1);
+        clip.samples = signal.clone();
+        assert!(measure_drift(&signal, &clip, ANALYSIS_SR, &None).windows > 1);
+        assert!(backend.batches.lock().unwrap().iter().any(|&n| n > 2));
+
+        // A backend that gets it wrong is ignored.
+        set_correlation_backend(Some(Arc::new(Recording {
+            batches: Mutex::new(Vec::new()),
+            malformed: true,
+        })));
+        assert_eq!(correlate_batch(&pairs, &None), on_cpu);
+        set_correlation_backend(None);
+    }
+}
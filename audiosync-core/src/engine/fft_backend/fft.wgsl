@@ -0,0 +1,55 @@
+// Radix-2 Stockham FFT passes and spectrum products for the wgpu
+// correlation backend. Complex values are vec2<f32> (re, im).
+
+struct Params {
+    // Transform length, a power of two.
+    n: u32,
+    // Length of the sub-transforms this pass merges: 1, 2, 4, … n / 2.
+    ns: u32,
+    // -1 forward, +1 inverse.
+    sign: f32,
+    pad: u32,
+}
+
+@group(0) @binding(0) var<storage, read> src: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read_write> dst: array<vec2<f32>>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+const PI: f32 = 3.14159265358979;
+
+fn mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// Dispatches wider than 65535 workgroups spill into y.
+fn index(id: vec3<u32>, groups: vec3<u32>) -> u32 {
+    return id.x + id.y * groups.x * 256u;
+}
+
+// One pass: merges pairs of length-ns transforms in src into length-2ns
+// transforms in dst. One invocation per butterfly, n / 2 in all.
+@compute @workgroup_size(256)
+fn butterfly(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let j = index(id, groups);
+    let half = params.n / 2u;
+    if (j >= half) {
+        return;
+    }
+    let k = j % params.ns;
+    let angle = params.sign * PI * f32(k) / f32(params.ns);
+    let a = src[j];
+    let b = mul(src[j + half], vec2<f32>(cos(angle), sin(angle)));
+    let d = (j - k) * 2u + k;
+    dst[d] = a + b;
+    dst[d + params.ns] = a - b;
+}
+
+// dst *= src, element by element. One invocation per element.
+@compute @workgroup_size(256)
+fn multiply(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let i = index(id, groups);
+    if (i >= params.n) {
+        return;
+    }
+    dst[i] = mul(dst[i], src[i]);
+}
@@ -0,0 +1,375 @@
+//! wgpu compute backend (`gpu` feature).
+//!
+//! Each pair becomes three complex radix-2 FFTs of the padded length (both
+//! inputs forward, the product back), run as one Stockham pass per stage of
+//! `fft.wgsl`. A batch goes to the device in chunks of bounded size, one
+//! submission and one readback wait per chunk.
+
+use log::{debug, warn};
+use std::sync::mpsc;
+use wgpu::util::DeviceExt;
+
+use super::CorrelationBackend;
+
+/// Device memory (bytes) one submission may hold across its pairs.
+const CHUNK_BYTES: u64 = 256 << 20;
+
+/// Invocations per workgroup, as declared in the shader.
+const WORKGROUP: u32 = 256;
+
+/// Bytes per complex value.
+const COMPLEX: u64 = 8;
+
+/// Correlations on the first GPU wgpu finds.
+pub struct WgpuBackend {
+    name: String,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    butterfly: wgpu::ComputePipeline,
+    multiply: wgpu::ComputePipeline,
+    /// Largest buffer a pass can bind.
+    max_buffer: u64,
+}
+
+/// Device buffers of one pair in a chunk.
+struct PairBuffers {
+    len: usize,
+    n: u32,
+    readback: wgpu::Buffer,
+}
+
+impl WgpuBackend {
+    /// Open the preferred GPU, or `None` when there is no usable adapter.
+    pub fn new() -> Option<Self> {
+        pollster::block_on(Self::open())
+    }
+
+    async fn open() -> Option<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await
+            .map_err(|e| debug!("No GPU for correlation: {}", e))
+            .ok()?;
+        let limits = adapter.limits();
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("audiosync correlation"),
+                required_limits: limits.clone(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| warn!("Could not open GPU '{}': {}", adapter.get_info().name, e))
+            .ok()?;
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("fft"),
+            entries: &[
+                storage(0, true),
+                storage(1, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("fft"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::include_wgsl!("fft.wgsl"));
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let (butterfly, multiply) = (pipeline("butterfly"), pipeline("multiply"));
+
+        Some(Self {
+            name: format!("wgpu ({})", adapter.get_info().name),
+            max_buffer: limits
+                .max_buffer_size
+                .min(limits.max_storage_buffer_binding_size as u64),
+            device,
+            queue,
+            layout,
+            butterfly,
+            multiply,
+        })
+    }
+
+    /// Correlate one chunk of pairs in a single submission.
+    fn run_chunk(&self, pairs: &[(&[f32], &[f32])]) -> Option<Vec<Vec<f32>>> {
+        self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        let buffers: Vec<PairBuffers> = pairs
+            .iter()
+            .map(|(r, t)| self.encode_pair(&mut encoder, r, t))
+            .collect();
+        self.queue.submit([encoder.finish()]);
+        let validation = pollster::block_on(self.device.pop_error_scope());
+        let out_of_memory = pollster::block_on(self.device.pop_error_scope());
+        if let Some(e) = validation.or(out_of_memory) {
+            warn!("GPU correlation failed: {}", e);
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        for pair in &buffers {
+            let tx = tx.clone();
+            pair.readback
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |mapped| drop(tx.send(mapped)));
+        }
+        drop(tx);
+        if let Err(e) = self.device.poll(wgpu::PollType::Wait) {
+            warn!("GPU correlation failed: {}", e);
+            return None;
+        }
+        if rx.iter().take(buffers.len()).any(|mapped| mapped.is_err()) {
+            return None;
+        }
+
+        let results = buffers
+            .iter()
+            .map(|pair| {
+                let scale = 1.0 / pair.n as f32;
+                let mapped = pair.readback.slice(..).get_mapped_range();
+                let values: &[[f32; 2]] = bytemuck::cast_slice(&mapped);
+                values[..pair.len].iter().map(|c| c[0] * scale).collect()
+            })
+            .collect();
+        Some(results)
+    }
+
+    /// Record one pair's transforms and the copy of its result into a
+    /// mappable buffer.
+    fn encode_pair(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        reference: &[f32],
+        target: &[f32],
+    ) -> PairBuffers {
+        let len = reference.len() + target.len() - 1;
+        let n = len.next_power_of_two() as u32;
+        let size = n as u64 * COMPLEX;
+        let upload = |label, samples: &mut dyn Iterator<Item = f32>| {
+            let mut complex = vec![0.0f32; n as usize * 2];
+            complex
+                .iter_mut()
+                .step_by(2)
+                .zip(samples)
+                .for_each(|(slot, x)| *slot = x);
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents: bytemuck::cast_slice(&complex),
+                    usage: wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_SRC
+                        | wgpu::BufferUsages::COPY_DST,
+                })
+        };
+        let spectrum = upload("reference", &mut reference.iter().copied());
+        // Reversed, as in fftconvolve(reference, target[::-1]).
+        let target_spectrum = upload("target", &mut target.iter().rev().copied());
+        let scratch = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("scratch"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: len as u64 * COMPLEX,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        self.encode_fft(encoder, &spectrum, &scratch, n, -1.0);
+        self.encode_fft(encoder, &target_spectrum, &scratch, n, -1.0);
+        self.dispatch(
+            encoder,
+            &self.multiply,
+            (&target_spectrum, &spectrum),
+            [n, 0, 0, 0],
+            n,
+        );
+        self.encode_fft(encoder, &spectrum, &scratch, n, 1.0);
+        encoder.copy_buffer_to_buffer(&spectrum, 0, &readback, 0, len as u64 * COMPLEX);
+        PairBuffers { len, n, readback }
+    }
+
+    /// Transform `data` in place (`sign` -1 forward, +1 unscaled inverse),
+    /// ping-ponging through `scratch`.
+    fn encode_fft(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        data: &wgpu::Buffer,
+        scratch: &wgpu::Buffer,
+        n: u32,
+        sign: f32,
+    ) {
+        let (mut src, mut dst) = (data, scratch);
+        let mut ns = 1;
+        while ns < n {
+            self.dispatch(
+                encoder,
+                &self.butterfly,
+                (src, dst),
+                [n, ns, sign.to_bits(), 0],
+                n / 2,
+            );
+            (src, dst) = (dst, src);
+            ns *= 2;
+        }
+        if !std::ptr::eq(src, data) {
+            encoder.copy_buffer_to_buffer(src, 0, data, 0, n as u64 * COMPLEX);
+        }
+    }
+
+    /// One compute pass of `invocations` over `(src, dst)` with `params`.
+    fn dispatch(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        (src, dst): (&wgpu::Buffer, &wgpu::Buffer),
+        params: [u32; 4],
+        invocations: u32,
+    ) {
+        let uniform = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: bytemuck::cast_slice(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: src.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: dst.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform.as_entire_binding(),
+                },
+            ],
+        });
+        // At most 65535 workgroups a dimension; the shader folds y back in.
+        let groups = invocations.div_ceil(WORKGROUP);
+        let (x, y) = (groups.min(65535), groups.div_ceil(65535));
+        let mut pass = encoder.begin_compute_pass(&Default::default());
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(x.max(1), y.max(1), 1);
+    }
+}
+
+impl CorrelationBackend for WgpuBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn correlate_batch(&self, pairs: &[(&[f32], &[f32])]) -> Option<Vec<Vec<f32>>> {
+        // Device memory per pair: both spectra and the scratch buffer at the
+        // padded length, and the readback.
+        let footprint = |(r, t): &(&[f32], &[f32])| {
+            let len = (r.len() + t.len()).saturating_sub(1) as u64;
+            (len.next_power_of_two() * 3 + len) * COMPLEX
+        };
+        let fits = pairs.iter().all(|(r, t)| {
+            let len = (r.len() + t.len()).saturating_sub(1) as u64;
+            !r.is_empty() && !t.is_empty() && len.next_power_of_two() * COMPLEX <= self.max_buffer
+        });
+        if !fits {
+            debug!(
+                "Correlation batch too large for {}; using the CPU",
+                self.name
+            );
+            return None;
+        }
+
+        let mut results = Vec::with_capacity(pairs.len());
+        let mut start = 0;
+        while start < pairs.len() {
+            let mut end = start + 1;
+            let mut bytes = footprint(&pairs[start]);
+            while end < pairs.len() && bytes + footprint(&pairs[end]) <= CHUNK_BYTES {
+                bytes += footprint(&pairs[end]);
+                end += 1;
+            }
+            results.extend(self.run_chunk(&pairs[start..end])?);
+            start = end;
+        }
+        Some(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::cpu_correlate;
+    use crate::models::noise;
+
+    #[test]
+    fn test_matches_cpu_correlation() {
+        // Machines without a GPU (CI) have nothing to check.
+        let Some(backend) = WgpuBackend::new() else {
+            return;
+        };
+        let signal = noise(5, 20_000);
+        // Odd and even numbers of passes, and a single sample.
+        let pairs: Vec<(&[f32], &[f32])> = vec![
+            (&signal, &signal[3000..7000]),
+            (&signal[..200], &signal[..30]),
+            (&signal[..1], &signal[..1]),
+        ];
+        let on_gpu = backend.correlate_batch(&pairs).unwrap();
+        for ((r, t), gpu) in pairs.iter().zip(&on_gpu) {
+            let cpu = cpu_correlate(r, t, &None);
+            assert_eq!(gpu.len(), cpu.len());
+            let peak = cpu.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+            assert!(
+                gpu.iter()
+                    .zip(&cpu)
+                    .all(|(g, c)| (g - c).abs() <= peak * 1e-3)
+            );
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! SMPTE linear timecode (LTC) decoding.
+//!
+//! LTC is an 80-bit frame per video frame, biphase-mark encoded: every bit
+//! starts with a transition and a 1 has a second one halfway through. The
+//! decoder slices the waveform into transitions, turns interval lengths back
+//! into bits, and locks onto the sync word that ends each frame.
+//!
+//! Decoding needs the full-rate audio of the LTC channel — at the 8 kHz
+//! analysis rate a 30 fps half-bit is under two samples long.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+
+use crate::audio_io::read_clip_channel;
+use crate::models::{CancelToken, Clip};
+
+/// Bits 64–79 of every frame, in transmission order.
+const SYNC_WORD: [bool; 16] = [
+    false, false, true, true, true, true, true, true, true, true, true, true, true, true, false, true,
+];
+
+/// Slicer hysteresis as a fraction of the channel's peak level.
+const SLICE_HYSTERESIS: f32 = 0.1;
+
+/// An interval within this fraction of a bit period counts as a full or half bit.
+const BIT_TOLERANCE: f64 = 0.25;
+
+/// Frames needed before a clip's timecode is trusted.
+const MIN_FRAMES: usize = 5;
+
+/// Frame rates LTC is recorded at, true rate first.
+const FRAME_RATES: [(f64, u32); 5] = [
+    (24000.0 / 1001.0, 24),
+    (24.0, 24),
+    (25.0, 25),
+    (30000.0 / 1001.0, 30),
+    (30.0, 30),
+];
+
+/// An `HH:MM:SS:FF` timecode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    /// Drop-frame counting (29.97 fps skips frame numbers 0 and 1 each
+    /// minute, except every tenth).
+    pub drop_frame: bool,
+}
+
+impl Timecode {
+    /// Frames since midnight at `nominal_fps` (24, 25 or 30).
+    pub fn frame_count(&self, nominal_fps: u32) -> i64 {
+        let seconds = (self.hours * 3600 + self.minutes * 60 + self.seconds) as i64;
+        let count = seconds * nominal_fps as i64 + self.frames as i64;
+        if self.drop_frame {
+            let minutes = (self.hours * 60 + self.minutes) as i64;
+            count - 2 * (minutes - minutes / 10)
+        } else {
+            count
+        }
+    }
+}
+
+impl std::fmt::Display for Timecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sep = if self.drop_frame { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours, self.minutes, self.seconds, sep, self.frames
+        )
+    }
+}
+
+/// One decoded frame and the sample its first bit starts on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LtcFrame {
+    pub timecode: Timecode,
+    pub sample: usize,
+}
+
+/// Timecode found on a clip.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LtcDecode {
+    /// Timecode at the clip's first sample, in seconds since midnight.
+    pub start_s: f64,
+    /// True frame rate, e.g. 29.97 where the timecode counts 30.
+    pub fps: f64,
+    /// The first frame decoded.
+    pub first: Timecode,
+    pub frames: usize,
+}
+
+/// Decode every complete, valid LTC frame in `samples`.
+pub fn decode_frames(samples: &[f32], sr: u32) -> Vec<LtcFrame> {
+    let edges = transitions(samples);
+    let intervals: Vec<f64> = edges.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+    let Some(mut period) = initial_bit_period(&intervals, sr) else {
+        return Vec::new();
+    };
+
+    let mut frames = Vec::new();
+    let mut bits: Vec<(bool, usize)> = Vec::with_capacity(80);
+    let mut i = 0;
+    while i < intervals.len() {
+        let d = intervals[i];
+        let start = edges[i];
+        let is_half = |d: f64, period: f64| (d - period / 2.0).abs() < period * BIT_TOLERANCE;
+        if (d - period).abs() < period * BIT_TOLERANCE {
+            bits.push((false, start));
+            period += (d - period) * 0.1;
+            i += 1;
+        } else if is_half(d, period) && intervals.get(i + 1).is_some_and(|&d2| is_half(d2, period)) {
+            bits.push((true, start));
+            period += (d + intervals[i + 1] - period) * 0.1;
+            i += 2;
+        } else {
+            // Noise or a dropout: whatever was half-read is lost.
+            bits.clear();
+            i += 1;
+            continue;
+        }
+
+        if bits.len() >= 80 && bits[bits.len() - 16..].iter().map(|b| b.0).eq(SYNC_WORD) {
+            let frame = &bits[bits.len() - 80..];
+            let word: Vec<bool> = frame.iter().map(|b| b.0).collect();
+            if let Some(timecode) = parse_frame(&word) {
+                frames.push(LtcFrame {
+                    timecode,
+                    sample: frame[0].1,
+                });
+            }
+            bits.clear();
+        }
+    }
+    frames
+}
+
+/// Work out the clip's start timecode from its decoded frames.
+pub fn decode(samples: &[f32], sr: u32) -> Option<LtcDecode> {
+    let frames = decode_frames(samples, sr);
+    if frames.len() < MIN_FRAMES {
+        return None;
+    }
+    let (first, last) = (frames[0], frames[frames.len() - 1]);
+    let drop_frame = first.timecode.drop_frame;
+
+    // The bit rate gives the nominal rate; telling 29.97 from 30 takes
+    // counting how many frames went by in how long.
+    let closest = |fps: f64, base: Option<u32>| {
+        FRAME_RATES
+            .iter()
+            .filter(|r| base.is_none_or(|b| r.1 == b))
+            .min_by(|a, b| (a.0 - fps).abs().total_cmp(&(b.0 - fps).abs()))
+            .copied()
+    };
+    let gaps: Vec<f64> = frames.windows(2).map(|w| (w[1].sample - w[0].sample) as f64).collect();
+    let (_, base) = closest(sr as f64 / median(&gaps)?, None)?;
+    let counted = last.timecode.frame_count(base) - first.timecode.frame_count(base);
+    let elapsed_s = (last.sample - first.sample) as f64 / sr as f64;
+    let fps = if drop_frame {
+        30000.0 / 1001.0
+    } else {
+        closest(counted as f64 / elapsed_s, Some(base))?.0
+    };
+
+    let starts: Vec<f64> = frames
+        .iter()
+        .map(|f| f.timecode.frame_count(base) as f64 / fps - f.sample as f64 / sr as f64)
+        .collect();
+    Some(LtcDecode {
+        start_s: median(&starts)?,
+        fps,
+        first: first.timecode,
+        frames: frames.len(),
+    })
+}
+
+/// Read `channel` (0-based) of the clip's source file and decode its LTC.
+pub fn read_clip_timecode(clip: &Clip, channel: u32, cancel: &Option<CancelToken>) -> Result<Option<LtcDecode>> {
+    let (samples, sr) = read_clip_channel(clip, channel, cancel)?;
+    if sr < 16_000 {
+        return Err(anyhow!("{} Hz is too low a sample rate to carry LTC", sr));
+    }
+    let found = decode(&samples, sr);
+    match found {
+        Some(ltc) => debug!(
+            "'{}': LTC {} at {:.3} fps ({} frames)",
+            clip.name, ltc.first, ltc.fps, ltc.frames
+        ),
+        None => debug!("'{}': no LTC on channel {}", clip.name, channel + 1),
+    }
+    Ok(found)
+}
+
+/// Sample positions where the sliced waveform changes polarity.
+fn transitions(samples: &[f32]) -> Vec<usize> {
+    let peak = samples.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
+    let threshold = peak * SLICE_HYSTERESIS;
+    if threshold <= 0.0 {
+        return Vec::new();
+    }
+    let mut high = samples[0] > 0.0;
+    let mut edges = Vec::new();
+    for (i, &x) in samples.iter().enumerate() {
+        if (high && x < -threshold) || (!high && x > threshold) {
+            high = !high;
+            edges.push(i);
+        }
+    }
+    edges
+}
+
+/// A full bit period in samples: zeros are common in every frame, so the
+/// long intervals at the top of the plausible range are whole bits.
+fn initial_bit_period(intervals: &[f64], sr: u32) -> Option<f64> {
+    let shortest_half = sr as f64 / (80.0 * 30.0) / 2.0 * (1.0 - BIT_TOLERANCE);
+    let longest_bit = sr as f64 / (80.0 * 24.0) * (1.0 + BIT_TOLERANCE);
+    let mut plausible: Vec<f64> = intervals
+        .iter()
+        .copied()
+        .filter(|&d| d >= shortest_half && d <= longest_bit)
+        .collect();
+    if plausible.len() < 80 {
+        return None;
+    }
+    plausible.sort_by(f64::total_cmp);
+    Some(plausible[plausible.len() * 9 / 10])
+}
+
+/// Unpack the BCD fields of an 80-bit frame (bits least significant first).
+fn parse_frame(bits: &[bool]) -> Option<Timecode> {
+    let field = |start: usize, len: usize| {
+        (0..len).fold(0u32, |acc, k| acc | ((bits[start + k] as u32) << k))
+    };
+    let bcd = |units: usize, tens: usize, tens_len: usize| {
+        let u = field(units, 4);
+        (u <= 9).then(|| field(tens, tens_len) * 10 + u)
+    };
+    let timecode = Timecode {
+        frames: bcd(0, 8, 2)?,
+        drop_frame: bits[10],
+        seconds: bcd(16, 24, 3)?,
+        minutes: bcd(32, 40, 3)?,
+        hours: bcd(48, 56, 2)?,
+    };
+    (timecode.frames < 30 && timecode.seconds < 60 && timecode.minutes < 60 && timecode.hours < 24)
+        .then_some(timecode)
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    Some(sorted[sorted.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::noise;
+
+    /// Biphase-mark encode frames from `start` on, `frames` long.
+    fn encode(start: Timecode, fps: f64, nominal: u32, frames: usize, sr: u32, lead_in: usize) -> Vec<f32> {
+        let mut out = vec![0.0f32; lead_in];
+        let bit_len = sr as f64 / (fps * 80.0);
+        let first = start.frame_count(nominal);
+        let mut level = -0.5f32;
+        let mut t = lead_in as f64;
+        for n in 0..frames as i64 {
+            let tc = from_count(first + n, nominal, start.drop_frame);
+            let mut bits = [false; 80];
+            let mut put = |start: usize, len: usize, v: u32| {
+                for k in 0..len {
+                    bits[start + k] = (v >> k) & 1 == 1;
+                }
+            };
+            put(0, 4, tc.frames % 10);
+            put(8, 2, tc.frames / 10);
+            put(10, 1, tc.drop_frame as u32);
+            put(16, 4, tc.seconds % 10);
+            put(24, 3, tc.seconds / 10);
+            put(32, 4, tc.minutes % 10);
+            put(40, 3, tc.minutes / 10);
+            put(48, 4, tc.hours % 10);
+            put(56, 2, tc.hours / 10);
+            bits[64..].copy_from_slice(&SYNC_WORD);
+            for bit in bits {
+                let halves = [t + bit_len / 2.0, t + bit_len];
+                level = -level;
+                for (h, &end) in halves.iter().enumerate() {
+                    if h == 1 && bit {
+                        level = -level;
+                    }
+                    while (out.len() as f64) < end {
+                        out.push(level);
+                    }
+                }
+                t += bit_len;
+            }
+        }
+        // The edge that would start the next frame closes the last bit.
+        out.extend(std::iter::repeat_n(-level, sr as usize / 1000));
+        out
+    }
+
+    fn from_count(count: i64, nominal: u32, drop_frame: bool) -> Timecode {
+        let mut count = count;
+        if drop_frame {
+            // Invert the drop-frame numbering (30 fps, 17982 frames per 10 min).
+            let tens = count / 17982;
+            let rem = count % 17982;
+            count += 18 * tens + if rem > 1 { 2 * ((rem - 2) / 1798) } else { 0 };
+        }
+        let n = nominal as i64;
+        Timecode {
+            hours: (count / (n * 3600)) as u32,
+            minutes: (count / (n * 60) % 60) as u32,
+            seconds: (count / n % 60) as u32,
+            frames: (count % n) as u32,
+            drop_frame,
+        }
+    }
+
+    #[test]
+    fn test_decode_25fps() {
+        let sr = 48000;
+        let start = Timecode {
+            hours: 10,
+            minutes: 0,
+            seconds: 59,
+            frames: 20,
+            drop_frame: false,
+        };
+        let lead_in = 12_345;
+        let audio = encode(start, 25.0, 25, 50, sr, lead_in);
+        let frames = decode_frames(&audio, sr);
+        assert_eq!(frames.len(), 50);
+        assert_eq!(frames[0].timecode, start);
+        assert_eq!(frames[5].timecode.to_string(), "10:01:00:00");
+
+        let ltc = decode(&audio, sr).unwrap();
+        assert_eq!(ltc.fps, 25.0);
+        let expected = start.frame_count(25) as f64 / 25.0 - lead_in as f64 / sr as f64;
+        assert!((ltc.start_s - expected).abs() < 1e-4, "{} vs {}", ltc.start_s, expected);
+    }
+
+    #[test]
+    fn test_decode_drop_frame_rolls_over_minute() {
+        let sr = 48000;
+        let start = Timecode {
+            hours: 1,
+            minutes: 0,
+            seconds: 59,
+            frames: 28,
+            drop_frame: true,
+        };
+        let fps = 30000.0 / 1001.0;
+        let audio = encode(start, fps, 30, 10, sr, 100);
+        let frames = decode_frames(&audio, sr);
+        assert_eq!(frames[2].timecode.to_string(), "01:01:00;02");
+        assert_eq!(frames[2].timecode.frame_count(30), start.frame_count(30) + 2);
+
+        let ltc = decode(&audio, sr).unwrap();
+        assert!((ltc.fps - fps).abs() < 1e-9);
+        let expected = start.frame_count(30) as f64 / fps - 100.0 / sr as f64;
+        assert!((ltc.start_s - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_decode_ignores_noise() {
+        let noise = noise(9, 48000 * 2);
+        assert!(decode(&noise, 48000).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+//! Onset-envelope matching — the last resort before metadata.
+//!
+//! Heavily compressed camera audio and a clean recorder can share every
+//! event yet correlate poorly sample for sample: limiting, clipping and
+//! different microphones reshape the waveform. When each event starts is
+//! kept, though. Both signals are reduced to an onset-strength envelope (how
+//! sharply the level rises, every 10 ms) and those are correlated instead;
+//! the match is then refined on the waveform within one envelope frame.
+
+use super::{full_rate_match, match_delay_between, DelayMatch};
+use crate::models::*;
+
+/// Envelope frames per second.
+pub const ONSET_RATE: u32 = 100;
+
+/// Frames quieter than this (dB) below the loudest frame count as silence,
+/// so noise in the gaps doesn't read as onsets.
+const ENERGY_FLOOR_DB: f32 = 60.0;
+
+/// Onset strength of `samples` at `ONSET_RATE`: the rise in log energy from
+/// one frame to the next, falls ignored, with its mean removed.
+pub fn onset_envelope(samples: &[f32], sr: u32) -> Vec<f32> {
+    let hop = (sr / ONSET_RATE).max(1) as usize;
+    let energy: Vec<f32> = samples
+        .chunks(hop)
+        .map(|frame| frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32)
+        .collect();
+    let peak = energy.iter().copied().fold(0.0f32, f32::max);
+    if peak <= 0.0 {
+        return vec![0.0; energy.len()];
+    }
+    let floor = peak * 10f32.powf(-ENERGY_FLOOR_DB / 10.0);
+    let level: Vec<f32> = energy.iter().map(|&e| 10.0 * (e.max(floor)).log10()).collect();
+
+    let mut envelope: Vec<f32> = std::iter::once(0.0)
+        .chain(level.windows(2).map(|w| (w[1] - w[0]).max(0.0)))
+        .collect();
+    let mean = envelope.iter().sum::<f32>() / envelope.len() as f32;
+    envelope.iter_mut().for_each(|v| *v -= mean);
+    envelope
+}
+
+/// Place `target` against `reference` by their onset envelopes. `None` when
+/// the envelopes don't match confidently either. `reference_envelope` is
+/// [`onset_envelope`] of `reference`, computed once for every clip.
+pub fn match_onsets(
+    reference: &[f32],
+    reference_envelope: &[f32],
+    target: &[f32],
+    sr: u32,
+    config: &SyncConfig,
+    cancel: &Option<CancelToken>,
+) -> Option<DelayMatch> {
+    let target_envelope = onset_envelope(target, sr);
+    // Steady sound or silence has no onsets to line up.
+    let silent = |envelope: &[f32]| envelope.iter().all(|&v| v == 0.0);
+    if silent(reference_envelope) || silent(&target_envelope) {
+        return None;
+    }
+    let (m, _) = full_rate_match(
+        reference_envelope,
+        &target_envelope,
+        ONSET_RATE,
+        config.max_offset_s,
+        1,
+        config.correlation_normalization,
+        cancel,
+    );
+    if m.score < CONFIDENCE_THRESHOLD {
+        return None;
+    }
+
+    // The envelope places the clip to within a frame; the waveform, however
+    // unlike, usually agrees on where inside it.
+    let hop = (sr / ONSET_RATE) as i64;
+    let coarse = (m.delay_s * sr as f64).round() as i64;
+    let fine = match_delay_between(reference, target, sr, (coarse - hop, coarse + hop));
+    let (delay_samples, delay_s, inverted) = if fine.ncc > 0.0 {
+        (fine.delay_samples, fine.delay_s, fine.inverted)
+    } else {
+        (coarse, coarse as f64 / sr as f64, false)
+    };
+    Some(DelayMatch {
+        delay_samples,
+        delay_s,
+        inverted,
+        ..m
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{analyze, match_delay};
+
+    #[test]
+    fn test_onsets_place_clip_waveform_cannot() {
+        let sr = ANALYSIS_SR;
+        // Decaying bursts at irregular times: the events both devices hear.
+        let len = sr as usize * 60;
+        let mut level = vec![0.0f32; len];
+        let mut at = sr as usize / 2;
+        let mut gaps = noise(11, len).into_iter();
+        while at < len {
+            for (i, l) in level[at..(at + sr as usize / 4).min(len)].iter_mut().enumerate() {
+                *l = (-(i as f32) / (sr as f32 * 0.05)).exp();
+            }
+            at += sr as usize / 3 + ((gaps.next().unwrap() + 0.5) * sr as f32) as usize;
+        }
+        let reference: Vec<f32> = level.iter().zip(noise(12, len)).map(|(l, n)| 0.5 * l * n).collect();
+        // Another microphone, crushed by a limiter: same onsets, unrelated waveform.
+        let delay = sr as usize * 17 + 2400;
+        let target: Vec<f32> = level[delay..delay + sr as usize * 20]
+            .iter()
+            .zip(noise(13, sr as usize * 20))
+            .map(|(l, n)| (20.0 * l * n).tanh())
+            .collect();
+        assert!(match_delay(&reference, &target, sr, None).score < CONFIDENCE_THRESHOLD);
+
+        let mut tracks = vec![Track::new("Recorder".into()), Track::new("Cam".into())];
+        for (ti, samples) in [(0, reference), (1, target)] {
+            let mut clip = Clip::new(format!("{}.wav", ti), format!("{}.wav", ti), 48000, 1);
+            clip.duration_s = samples.len() as f64 / sr as f64;
+            clip.samples = samples;
+            tracks[ti].clips.push(clip);
+        }
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        let clip = &tracks[1].clips[0];
+        assert!((clip.timeline_offset_samples - delay as i64).abs() <= (sr / ONSET_RATE) as i64);
+        assert!(clip.match_score >= CONFIDENCE_THRESHOLD);
+        assert_eq!(result.diagnostics["1.wav"].method, MatchMethod::Onset);
+    }
+}
@@ -0,0 +1,129 @@
+//! Content-only reference layout — ordering and spacing a multi-clip
+//! reference track from its audio, for media stripped of timestamps.
+//!
+//! The last seconds of each clip are correlated with the first seconds of
+//! the others. Where they match, the second clip started before the first
+//! stopped (a recorder splitting long takes with a little overlap, a take
+//! restarted over the tail of the last), and the two are chained at the
+//! matched lag. Clips that overlap nothing are butted end to end in clip
+//! order.
+
+use log::info;
+
+use super::{match_delay_candidates, shared_audio_s, MIN_SHARED_AUDIO_S};
+use crate::models::*;
+
+/// Seconds at each end of a clip compared with the other clips.
+const EDGE_S: f64 = 5.0;
+
+/// With more clips than this, only neighbours in clip order are compared.
+const MAX_PAIRWISE_CLIPS: usize = 32;
+
+/// The clips in layout order, each placed relative to the one before it:
+/// `(clip, Some((previous clip, start offset from its start in samples)))`,
+/// `None` for the first.
+pub(crate) fn layout_by_content(clips: &[Clip], sr: u32) -> Vec<(usize, Option<(usize, i64)>)> {
+    let n = clips.len();
+    let edge = (EDGE_S * sr as f64) as usize;
+    let pairs: Vec<(usize, usize)> = if n <= MAX_PAIRWISE_CLIPS {
+        (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j))).collect()
+    } else {
+        (1..n).flat_map(|j| [(j - 1, j), (j, j - 1)]).collect()
+    };
+
+    // (score, earlier clip, later clip, offset of the later from the earlier)
+    let mut links: Vec<(f64, usize, usize, i64)> = Vec::new();
+    for (i, j) in pairs {
+        let (a, b) = (&clips[i].samples, &clips[j].samples);
+        let tail = &a[a.len().saturating_sub(edge)..];
+        let head = &b[..b.len().min(edge)];
+        if tail.is_empty() || head.is_empty() {
+            continue;
+        }
+        // Only part of each edge overlaps, so the normalized correlation is
+        // needed whatever the project uses.
+        let (m, _) = match_delay_candidates(tail, head, sr, None, 1, CorrelationNormalization::Overlap);
+        let d = m.delay_samples;
+        // `b` must start inside the tail of `a` and carry on past its end.
+        if m.score < CONFIDENCE_THRESHOLD || d < 0 || d as usize >= tail.len() || d + b.len() as i64 <= tail.len() as i64 {
+            continue;
+        }
+        let overlap_s = (tail.len() - d as usize) as f64 / sr as f64;
+        if shared_audio_s(tail, head, d, sr) < MIN_SHARED_AUDIO_S.min(overlap_s / 2.0) {
+            continue;
+        }
+        links.push((m.score, i, j, (a.len() - tail.len()) as i64 + d));
+    }
+
+    // Strongest links first; each clip gets at most one clip on either side
+    // and chains never loop.
+    links.sort_by(|x, y| y.0.total_cmp(&x.0));
+    let mut next: Vec<Option<(usize, i64)>> = vec![None; n];
+    let mut prev: Vec<Option<usize>> = vec![None; n];
+    for (score, i, j, offset) in links {
+        if next[i].is_some() || prev[j].is_some() {
+            continue;
+        }
+        let mut k = j;
+        while let Some((after, _)) = next[k] {
+            k = after;
+        }
+        if k == i {
+            continue;
+        }
+        info!("'{}' overlaps the end of '{}' (score {:.0})", clips[j].name, clips[i].name, score);
+        next[i] = Some((j, offset));
+        prev[j] = Some(i);
+    }
+
+    let mut order: Vec<(usize, Option<(usize, i64)>)> = Vec::with_capacity(n);
+    for first in (0..n).filter(|&c| prev[c].is_none()) {
+        // A new chain starts where the last one ended.
+        let mut placed = order.last().map(|&(last, _)| (last, clips[last].length_samples() as i64));
+        let mut c = first;
+        loop {
+            order.push((c, placed));
+            match next[c] {
+                Some((after, offset)) => {
+                    placed = Some((c, offset));
+                    c = after;
+                }
+                None => break,
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(name: &str, samples: &[f32]) -> Clip {
+        let mut c = Clip::new(name.into(), name.into(), 48000, 1);
+        c.samples = samples.to_vec();
+        c.duration_s = samples.len() as f64 / ANALYSIS_SR as f64;
+        c
+    }
+
+    #[test]
+    fn test_layout_chains_overlapping_edges() {
+        let sr = ANALYSIS_SR as usize;
+        let take = noise(5, 30 * sr);
+        // One take split into files that overlap by 2 s, listed out of order,
+        // and a separate recording that overlaps nothing.
+        let clips = vec![
+            clip("c.wav", &take[19 * sr..]),
+            clip("a.wav", &take[..12 * sr]),
+            clip("other.wav", &noise(99, 4 * sr)),
+            clip("b.wav", &take[10 * sr..21 * sr]),
+        ];
+
+        let order = layout_by_content(&clips, ANALYSIS_SR);
+        let sr = sr as i64;
+        assert_eq!(
+            order,
+            [(1, None), (3, Some((1, 10 * sr))), (0, Some((3, 9 * sr))), (2, Some((0, 11 * sr)))]
+        );
+    }
+}
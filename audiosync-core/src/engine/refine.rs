@@ -0,0 +1,284 @@
+//! High-resolution refinement — placing clips to the export sample.
+//!
+//! Analysis runs at 8 kHz. Its interpolated peaks still leave tens of
+//! microseconds of error, a sample or more at 48 kHz, which comb-filters
+//! when a clip is mixed with the reference it was matched against. Before
+//! export, a couple of seconds of each correlated clip, and the reference
+//! audio under them, are read again at the export rate and correlated within
+//! a few analysis samples of the placement; the clip moves by what is left.
+
+use anyhow::Result;
+use log::{debug, info};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::match_delay_between;
+use super::stitch::corrects_drift;
+use crate::audio_io::{map_io_bounded, read_clip_window};
+use crate::models::*;
+
+/// Timeline seconds of each clip correlated.
+const WINDOW_S: f64 = 2.0;
+
+/// Least overlap (seconds) with the reference worth correlating.
+const MIN_WINDOW_S: f64 = 0.5;
+
+/// How far (seconds) either side of its placement a clip is looked for:
+/// four analysis samples.
+const SEARCH_S: f64 = 4.0 / ANALYSIS_SR as f64;
+
+/// A clip to refine, and the reference clip it overlaps most.
+struct Job {
+    track: usize,
+    clip: usize,
+    reference: (usize, usize),
+    /// Timeline time (seconds) of the middle of the stretch correlated.
+    centre_s: f64,
+    len_s: f64,
+}
+
+/// Re-correlate every correlated clip against the reference clip under it
+/// at `export_sr`, and move it by the residual offset. Clips placed by hand
+/// or by metadata, locked ones, and those overlapping no reference clip
+/// keep their placement, as do clips whose windows can't be read or don't
+/// match. Returns how many clips were moved.
+pub fn refine_offsets(
+    tracks: &mut [Track],
+    result: &mut SyncResult,
+    config: &SyncConfig,
+    export_sr: u32,
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+) -> Result<usize> {
+    let jobs = plan(tracks, result, config);
+    let shared: &[Track] = tracks;
+    let progress = ProgressReporter::new(progress);
+    let done = AtomicUsize::new(0);
+    let corrections = map_io_bounded(&jobs, config.io_parallelism.max(1), |_, job| {
+        let clip = &shared[job.track].clips[job.clip];
+        let step = done.fetch_add(1, Ordering::Relaxed) + 1;
+        progress.report(
+            ProgressPhase::Refining,
+            0.0,
+            (step, jobs.len()),
+            &format!("Refining '{}' at {} Hz...", clip.name, export_sr),
+        );
+        measure(shared, job, config, export_sr, cancel)
+    });
+    check_cancelled(cancel)?;
+
+    let mut moved = 0;
+    for (job, correction) in jobs.iter().zip(corrections) {
+        let clip = &mut tracks[job.track].clips[job.clip];
+        let correction_s = match correction {
+            Ok(Some(correction_s)) => correction_s,
+            Ok(None) => continue,
+            Err(e) => {
+                debug!("Not refining '{}': {}", clip.name, e);
+                continue;
+            }
+        };
+        debug!("Refined '{}' by {:+.1} µs", clip.name, correction_s * 1e6);
+        clip.timeline_offset_s += correction_s;
+        clip.timeline_offset_samples = (clip.timeline_offset_s * clip.sample_rate as f64).round() as i64;
+        result.clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
+        moved += 1;
+    }
+    info!("Refined {} of {} clip placement(s) at {} Hz", moved, jobs.len(), export_sr);
+    Ok(moved)
+}
+
+/// The clips to refine: correlated, movable clips off the reference track,
+/// each with the stretch it shares with its reference's clips.
+fn plan(tracks: &[Track], result: &SyncResult, config: &SyncConfig) -> Vec<Job> {
+    let mut jobs = Vec::new();
+    for (ti, track) in tracks.iter().enumerate() {
+        let ref_ti = result
+            .groups
+            .iter()
+            .find(|g| g.track_indices.contains(&ti))
+            .map_or(result.reference_track_index, |g| g.reference_track_index);
+        if ti == ref_ti || ref_ti >= tracks.len() || track.locked {
+            continue;
+        }
+        for (ci, clip) in track.clips.iter().enumerate() {
+            if !clip.on_timeline()
+                || !clip.analyzed
+                || clip.is_pinned()
+                || clip.match_score < CONFIDENCE_THRESHOLD
+            {
+                continue;
+            }
+            let (start, end) = timeline_span(clip, config);
+            let best = tracks[ref_ti]
+                .clips
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.on_timeline())
+                .map(|(rc, r)| {
+                    let (r_start, r_end) = timeline_span(r, config);
+                    (rc, start.max(r_start), end.min(r_end))
+                })
+                .max_by(|a, b| (a.2 - a.1).total_cmp(&(b.2 - b.1)));
+            let Some((rc, from, to)) = best else {
+                continue;
+            };
+            // The reference is read a search width beyond the clip's window.
+            let len_s = (to - from - 2.0 * SEARCH_S).min(WINDOW_S);
+            if len_s >= MIN_WINDOW_S {
+                jobs.push(Job {
+                    track: ti,
+                    clip: ci,
+                    reference: (ref_ti, rc),
+                    centre_s: (from + to) / 2.0,
+                    len_s,
+                });
+            }
+        }
+    }
+    jobs
+}
+
+/// Residual offset (seconds) of the job's clip, or `None` when its window
+/// doesn't match the reference's clearly within the search.
+fn measure(
+    tracks: &[Track],
+    job: &Job,
+    config: &SyncConfig,
+    export_sr: u32,
+    cancel: &Option<CancelToken>,
+) -> Result<Option<f64>> {
+    let clip = &tracks[job.track].clips[job.clip];
+    let reference = &tracks[job.reference.0].clips[job.reference.1];
+    let half = job.len_s / 2.0;
+
+    // Each window starts on a sample of its source, so where it starts is
+    // known exactly.
+    let window = |c: &Clip, from_s: f64, to_s: f64| {
+        let sr = c.original_sr.max(1) as f64;
+        let start = (source_s(c, config, from_s) * sr).round().max(0.0) as usize;
+        let end = (source_s(c, config, to_s) * sr).round() as usize;
+        (start, end.saturating_sub(start))
+    };
+    let clip_window = window(clip, job.centre_s - half, job.centre_s + half);
+    let reference_window = window(reference, job.centre_s - half - SEARCH_S, job.centre_s + half + SEARCH_S);
+    let clip_audio = read_clip_window(clip, export_sr, clip_window, cancel)?;
+    let reference_audio = read_clip_window(reference, export_sr, reference_window, cancel)?;
+    let to_f32 = |audio: Vec<f64>| audio.into_iter().map(|s| s as f32).collect::<Vec<f32>>();
+    let (clip_audio, reference_audio) = (to_f32(clip_audio), to_f32(reference_audio));
+
+    // Where the middle of the clip's window should fall in the reference's,
+    // as placed. The middle, not the start: with drift, the two run at
+    // slightly different rates and the correlation lines up their middles.
+    let sr = export_sr as f64;
+    let clip_start_s = clip_window.0 as f64 / clip.original_sr.max(1) as f64;
+    let reference_start_s = reference_window.0 as f64 / reference.original_sr.max(1) as f64;
+    let mid_s = clip_start_s + clip_window.1 as f64 / clip.original_sr.max(1) as f64 / 2.0;
+    let in_reference_s = source_s(reference, config, timeline_s(clip, config, mid_s)) - reference_start_s;
+    let expected = (in_reference_s - (mid_s - clip_start_s)) * sr;
+
+    let search = (SEARCH_S * sr).round() as i64;
+    let around = expected.round() as i64;
+    let m = match_delay_between(&reference_audio, &clip_audio, export_sr, (around - search, around + search));
+    // A peak at the edge of the search may only be the slope of one beyond it.
+    if m.score < CONFIDENCE_THRESHOLD || (m.delay_samples - around).abs() >= search {
+        return Ok(None);
+    }
+    Ok(Some(m.delay_s - expected / sr))
+}
+
+/// Timeline seconds at which source time `src_s` of `clip` plays, with the
+/// drift correction export applies.
+fn timeline_s(clip: &Clip, config: &SyncConfig, src_s: f64) -> f64 {
+    let offset_s = clip.offset_override_s.unwrap_or(clip.timeline_offset_s);
+    if corrects_drift(clip, config) {
+        offset_s + drift_timeline_time(&clip.drift_model(), src_s)
+    } else {
+        offset_s + src_s
+    }
+}
+
+/// Inverse of [`timeline_s`]: the source time of `clip` playing at timeline
+/// seconds `t_s`.
+fn source_s(clip: &Clip, config: &SyncConfig, t_s: f64) -> f64 {
+    let offset_s = clip.offset_override_s.unwrap_or(clip.timeline_offset_s);
+    if corrects_drift(clip, config) {
+        drift_source_time(&clip.drift_model(), t_s - offset_s)
+    } else {
+        t_s - offset_s
+    }
+}
+
+/// Timeline seconds `clip` covers, untrimmed.
+fn timeline_span(clip: &Clip, config: &SyncConfig) -> (f64, f64) {
+    (timeline_s(clip, config, 0.0), timeline_s(clip, config, clip.duration_s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_refines_placement_to_export_sample() {
+        let sr = 48000u32;
+        let reference = noise(31, sr as usize * 8);
+        // Starts 3 s plus five 48 kHz samples in: between analysis samples.
+        let delay = sr as usize * 3 + 5;
+        let write = |name: &str, samples: &[f32]| {
+            let path = std::env::temp_dir().join(format!("audiosync_refine_{}_{}.wav", name, uuid::Uuid::new_v4()));
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: sr,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            samples.iter().for_each(|&s| writer.write_sample(s).unwrap());
+            writer.finalize().unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let paths = [write("ref", &reference), write("clip", &reference[delay..delay + sr as usize * 4])];
+
+        let mut tracks = vec![Track::new("Recorder".into()), Track::new("Cam".into())];
+        for (ti, path) in paths.iter().enumerate() {
+            let mut clip = Clip::new(path.clone(), format!("{}.wav", ti), sr, 1);
+            clip.duration_s = if ti == 0 { 8.0 } else { 4.0 };
+            clip.analyzed = true;
+            clip.match_score = 90.0;
+            tracks[ti].clips.push(clip);
+        }
+        // Placed as 8 kHz analysis would leave it: a sample off at 48 kHz.
+        let placed_s = (delay + 1) as f64 / sr as f64;
+        tracks[1].clips[0].timeline_offset_s = placed_s;
+        tracks[1].clips[0].timeline_offset_samples = (placed_s * ANALYSIS_SR as f64).round() as i64;
+        let mut locked = tracks[1].clips[0].clone();
+        locked.locked = true;
+
+        let mut result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: ANALYSIS_SR as i64 * 8,
+            total_timeline_s: 8.0,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 90.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let config = SyncConfig::default();
+        let moved = refine_offsets(&mut tracks, &mut result, &config, sr, &None, &None).unwrap();
+        assert_eq!(moved, 1);
+        let refined = tracks[1].clips[0].timeline_offset_s * sr as f64;
+        assert!((refined - delay as f64).abs() < 0.05, "{}", refined);
+
+        // Locked clips stay where they are.
+        tracks[1].clips[0] = locked;
+        assert_eq!(refine_offsets(&mut tracks, &mut result, &config, sr, &None, &None).unwrap(), 0);
+        assert_eq!(tracks[1].clips[0].timeline_offset_s, placed_s);
+        paths.iter().for_each(|p| drop(std::fs::remove_file(p)));
+    }
+}
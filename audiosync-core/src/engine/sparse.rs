@@ -0,0 +1,149 @@
+//! Sparse correlation — placing a very long clip from a few of its parts.
+//!
+//! Correlating a whole hour-long take costs FFTs the length of the take and
+//! the reference together, then a full-rate refinement just as long. The
+//! loudest few ten-second stretches of the clip carry all the evidence
+//! needed: each is searched against the reference at 2 kHz, they vote on
+//! where the clip starts, and the winner is refined at the full rate on its
+//! own segment only. Segments that disagree (repetitive content, a clip that
+//! isn't in the reference) leave the decision to the full search, which can
+//! weigh metadata. Used at the fast quality level (`ResampleQuality::Fast`).
+
+use log::debug;
+
+use super::{
+    decimate, full_rate_match, match_delay_between, DelayCandidate, DelayMatch, COARSE_DECIMATION,
+    COARSE_WINDOW_S,
+};
+use crate::models::*;
+
+/// Targets at least this long (seconds) are matched sparsely.
+const SPARSE_MIN_TARGET_S: usize = 600;
+
+/// Length (seconds) of each segment searched.
+const SEGMENT_S: usize = 10;
+
+/// Loudest segments searched.
+const SEGMENTS: usize = 5;
+
+/// Segments that must agree on the offset for it to stand.
+const MIN_VOTES: usize = 3;
+
+/// Furthest apart (analysis samples) two segments' offsets may be and still
+/// agree: two samples of the 2 kHz search.
+const VOTE_TOLERANCE: i64 = 2 * COARSE_DECIMATION as i64;
+
+/// Place `target` from its loudest segments. `None` when it is too short,
+/// or the segments don't agree confidently; the caller then searches the
+/// whole clip.
+pub fn sparse_match(
+    reference: &[f32],
+    target: &[f32],
+    sr: u32,
+    max_offset_s: Option<f64>,
+    normalization: CorrelationNormalization,
+    cancel: &Option<CancelToken>,
+) -> Option<(DelayMatch, Vec<DelayCandidate>)> {
+    if target.len() < SPARSE_MIN_TARGET_S * sr as usize {
+        return None;
+    }
+    let segment_len = SEGMENT_S * sr as usize;
+    let coarse_sr = sr / COARSE_DECIMATION as u32;
+    let coarse_reference = decimate(reference, COARSE_DECIMATION);
+
+    // Each confident segment's vote: (clip delay, segment start, its match).
+    let mut votes: Vec<(i64, usize, DelayMatch)> = Vec::new();
+    for start in loudest_segments(target, segment_len) {
+        let segment = decimate(&target[start..start + segment_len], COARSE_DECIMATION);
+        let (m, _) = full_rate_match(&coarse_reference, &segment, coarse_sr, None, 1, normalization, cancel);
+        if is_cancelled(cancel) {
+            return None;
+        }
+        if m.score >= CONFIDENCE_THRESHOLD {
+            votes.push((m.delay_samples * COARSE_DECIMATION as i64 - start as i64, start, m));
+        }
+    }
+
+    let agreeing = |delay: i64| votes.iter().filter(move |v| (v.0 - delay).abs() <= VOTE_TOLERANCE);
+    let winner = votes.iter().map(|v| v.0).max_by_key(|&d| agreeing(d).count())?;
+    let count = agreeing(winner).count();
+    if count < MIN_VOTES {
+        debug!("Sparse search: only {} of {} segments agree; searching the whole clip", count, SEGMENTS);
+        return None;
+    }
+
+    // Refine on the agreeing segment that matched best.
+    let &(delay, start, coarse) = agreeing(winner).max_by(|a, b| a.2.score.total_cmp(&b.2.score))?;
+    let window = (COARSE_WINDOW_S * sr as f64) as i64;
+    let around = delay + start as i64;
+    let fine = match_delay_between(
+        reference,
+        &target[start..start + segment_len],
+        sr,
+        (around - window, around + window),
+    );
+    if fine.confidence <= 0.0 {
+        return None;
+    }
+    let m = DelayMatch {
+        delay_samples: fine.delay_samples - start as i64,
+        delay_s: fine.delay_s - start as f64 / sr as f64,
+        inverted: fine.inverted,
+        ..coarse
+    };
+    if max_offset_s.is_some_and(|max| m.delay_s.abs() > max) {
+        return None;
+    }
+    debug!("Sparse search: {} of {} segments agree on {:.3} s", count, SEGMENTS, m.delay_s);
+    let candidate = DelayCandidate {
+        delay_samples: m.delay_samples,
+        delay_s: m.delay_s,
+        relative_peak: 1.0,
+        ncc: m.ncc,
+        inverted: m.inverted,
+    };
+    Some((m, vec![candidate]))
+}
+
+/// Starts of the `SEGMENTS` loudest non-overlapping `len`-sample segments
+/// of `samples` that hold any sound.
+fn loudest_segments(samples: &[f32], len: usize) -> Vec<usize> {
+    let mut energies: Vec<(usize, f64)> = samples
+        .chunks_exact(len)
+        .enumerate()
+        .map(|(i, c)| (i * len, c.iter().map(|&x| x as f64 * x as f64).sum::<f64>()))
+        .filter(|&(_, e)| e > 0.0)
+        .collect();
+    energies.sort_by(|a, b| b.1.total_cmp(&a.1));
+    energies.into_iter().take(SEGMENTS).map(|(start, _)| start).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_match_places_long_clip() {
+        let sr = ANALYSIS_SR;
+        // Fourteen minutes of sound whose level swells and fades.
+        let reference: Vec<f32> = noise(29, sr as usize * 840)
+            .into_iter()
+            .enumerate()
+            .map(|(i, n)| n * (1.2 + (i as f32 / sr as f32 * 0.05).sin()))
+            .collect();
+        let delay = sr as usize * 125 + 77;
+        let target = &reference[delay..delay + sr as usize * 660];
+        let norm = CorrelationNormalization::Overlap;
+
+        let (m, candidates) = sparse_match(&reference, target, sr, None, norm, &None).unwrap();
+        assert_eq!(m.delay_samples, delay as i64);
+        assert!((m.delay_s - delay as f64 / sr as f64).abs() < 1e-4, "{}", m.delay_s);
+        assert!(m.score >= CONFIDENCE_THRESHOLD);
+        assert_eq!(candidates.len(), 1);
+
+        // Too short to bother, or from somewhere else: left to the full search.
+        assert!(sparse_match(&reference, &target[..sr as usize * 300], sr, None, norm, &None).is_none());
+        let elsewhere = noise(30, sr as usize * 660);
+        assert!(sparse_match(&reference, &elsewhere, sr, None, norm, &None).is_none());
+    }
+}
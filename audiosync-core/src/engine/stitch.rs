@@ -0,0 +1,912 @@
+//! Streaming stitch — each track's clips laid onto its timeline a block at a
+//! time.
+//!
+//! Where every clip lands, and which stretches of it overlap clips laid in
+//! before it, is worked out from the clips' durations before any audio is
+//! read. Each block then needs only the clips it touches, and of each only
+//! the window of audio around the block: memory stays at a block plus a
+//! window per clip in play, however long the event or its clips run.
+//!
+//! A track keeps the channels of its widest clip. Every channel of a clip is
+//! placed, drift-corrected and cut identically; mono clips play on all of
+//! the track's channels, narrower ones leave the rest silent.
+
+use anyhow::Result;
+use log::{info, warn};
+
+use super::{interpolate, DriftSinc};
+use crate::audio_io::{detect_project_sample_rate, map_io_bounded, read_clip_full_res_window};
+use crate::models::*;
+
+/// Frames per stitched block.
+pub const STITCH_BLOCK: usize = 1 << 16;
+
+/// Frames of a clip read at a time: a few blocks, so that reading (an
+/// ffmpeg run, for video) isn't repeated for every block.
+const CLIP_WINDOW: usize = 8 * STITCH_BLOCK;
+
+/// Ceiling of the limiter on summed overlaps (-0.2 dBFS), and how fast its
+/// gain falls ahead of a peak and recovers after it.
+const LIMITER_CEILING: f64 = 0.977;
+const LIMITER_ATTACK_S: f64 = 0.005;
+const LIMITER_RELEASE_S: f64 = 0.1;
+
+/// Stitched audio handed to the sink of [`sync_streaming`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stitched<'a> {
+    /// The next frames of the track, in order, channels interleaved.
+    Block(&'a [f64]),
+    /// The track is complete.
+    End,
+}
+
+/// A stretch of a clip that clips laid in before it also cover.
+#[derive(Debug, Clone, PartialEq)]
+struct Overlap {
+    start: usize,
+    end: usize,
+    /// The clip starts inside the earlier audio.
+    fade_in: bool,
+    /// The earlier audio outlasts the clip.
+    fade_out: bool,
+    /// Crossfade length in samples, shortened to fit the overlap.
+    fade: usize,
+}
+
+enum ClipAudio {
+    Pending,
+    /// Being read, a window at a time.
+    Streaming(Box<ClipStream>),
+    /// Stitched and released.
+    Done,
+    /// Unreadable or empty: left out as if it weren't on the track.
+    Dropped,
+}
+
+/// Where one clip lands in a track's stitched output.
+struct Placement {
+    clip: usize,
+    /// Output samples covered.
+    start: usize,
+    end: usize,
+    /// Samples of the clip's (trimmed, drift-corrected) audio before `start`,
+    /// cut off by the start of the rendered range.
+    skip: usize,
+    /// Planned length of that audio. What is read is padded or cut to it —
+    /// a few samples at most, as durations come from the analysis rate.
+    len: usize,
+    /// Drift model to correct the audio with, when correction applies.
+    drift: Option<Vec<DriftSegment>>,
+    overlaps: Vec<Overlap>,
+    audio: ClipAudio,
+}
+
+/// Stitch each track into a single continuous audio array at export SR.
+///
+/// This holds every track whole in `Track::synced_audio`, as much memory as
+/// the timeline is long: it suits short timelines and tests. Export goes
+/// through [`sync_streaming`] instead.
+///
+/// Clips whose source can no longer be read (e.g. an ejected card) are left
+/// as silence and returned, so the remaining tracks still export.
+pub fn sync(
+    tracks: &mut [Track],
+    result: &SyncResult,
+    config: &mut SyncConfig,
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+) -> Result<Vec<UnavailableClip>> {
+    resolve_export_sr(tracks, config);
+    let mut stitched: Vec<Vec<f64>> = vec![Vec::new(); tracks.len()];
+    let skipped = sync_streaming(tracks, result, config, progress, cancel, |ti, audio| {
+        if let Stitched::Block(block) = audio {
+            stitched[ti].extend_from_slice(block);
+        }
+        Ok(())
+    })?;
+    for (track, audio) in tracks.iter_mut().zip(stitched) {
+        track.synced_channels = output_channels(track, config);
+        track.synced_channel_mask = output_channel_mask(track, config);
+        track.synced_audio = Some(audio);
+    }
+    Ok(skipped)
+}
+
+/// Channels a track is stitched with: those of its widest clip, or one when
+/// the config mixes exports down to mono.
+pub fn output_channels(track: &Track, config: &SyncConfig) -> u32 {
+    match config.export_channels {
+        ChannelPolicy::Preserve => {
+            track.clips.iter().map(|c| c.original_channels).max().unwrap_or(1).max(1)
+        }
+        ChannelPolicy::Mono => 1,
+    }
+}
+
+/// Speaker layout of a track's [`output_channels`]: that of its first
+/// widest clip, if the clip's file names one.
+pub fn output_channel_mask(track: &Track, config: &SyncConfig) -> Option<u32> {
+    let channels = output_channels(track, config);
+    if config.export_channels == ChannelPolicy::Mono {
+        return None;
+    }
+    track
+        .clips
+        .iter()
+        .find(|c| c.original_channels == channels)
+        .and_then(|c| c.stream_metadata.channel_mask)
+        .filter(|mask| mask.count_ones() == channels)
+}
+
+/// The export sample rate: `config.export_sr`, or else the project's own
+/// rate, which is stored back into the config.
+pub fn resolve_export_sr(tracks: &[Track], config: &mut SyncConfig) -> u32 {
+    *config.export_sr.get_or_insert_with(|| detect_project_sample_rate(tracks))
+}
+
+/// Stitch each track at export SR and hand it to `sink` a block at a time,
+/// track by track, each followed by [`Stitched::End`]. Blocks carry
+/// [`output_channels`] channels. Nothing is kept in `Track::synced_audio`.
+///
+/// Clips whose source can no longer be read are left as silence and
+/// returned, as with [`sync`]; one that fails part way through falls silent
+/// from there.
+pub fn sync_streaming(
+    tracks: &mut [Track],
+    result: &SyncResult,
+    config: &SyncConfig,
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+    mut sink: impl FnMut(usize, Stitched<'_>) -> Result<()>,
+) -> Result<Vec<UnavailableClip>> {
+    let export_sr = config.export_sr.unwrap_or_else(|| detect_project_sample_rate(tracks));
+
+    // Each sync group renders its own timeline length.
+    let span = |timeline_s: f64| match config.export_range_s {
+        Some((start_s, end_s)) => {
+            let start_s = start_s.clamp(0.0, timeline_s);
+            let end_s = end_s.clamp(start_s, timeline_s);
+            (
+                (start_s * export_sr as f64).round() as i64,
+                ((end_s - start_s) * export_sr as f64).round() as usize,
+            )
+        }
+        None => (0, (timeline_s * export_sr as f64).round() as usize),
+    };
+    if let Some((start_s, end_s)) = config.export_range_s {
+        info!("Rendering timeline range {:.1}–{:.1} s", start_s, end_s);
+    }
+    let total_steps: usize = tracks.iter().map(|t| t.clip_count()).sum();
+    let mut step = 0usize;
+    let progress = ProgressReporter::new(progress);
+    let mut skipped: Vec<UnavailableClip> = Vec::new();
+    let fade = (config.crossfade_ms.max(0.0) / 1000.0 * export_sr as f64).round() as usize;
+    let batch = config.io_parallelism.max(1);
+
+    for ti in 0..tracks.len() {
+        check_cancelled(cancel)?;
+
+        let (range_start, total_len) = span(result.timeline_s_for(ti));
+        let mut placements = plan(&tracks[ti], config, export_sr, range_start, total_len);
+        find_overlaps(&mut placements, config.overlap_policy, fade);
+        // Clips outside the range are never read.
+        step += tracks[ti].clips.len() - placements.len();
+
+        // Clips open in order of where they start, as the blocks reach them.
+        let mut by_start: Vec<usize> = (0..placements.len()).collect();
+        by_start.sort_by_key(|&p| placements[p].start);
+        let mut next_open = 0;
+        let channels = output_channels(&tracks[ti], config) as usize;
+        let mut mixer = Mixer::new(config.overlap_policy, export_sr, channels);
+
+        let mut from = 0;
+        while from < total_len {
+            check_cancelled(cancel)?;
+            let to = (from + STITCH_BLOCK).min(total_len);
+
+            while next_open < by_start.len() && placements[by_start[next_open]].start < to {
+                let p = by_start[next_open];
+                next_open += 1;
+                step += 1;
+                let clip = &mut tracks[ti].clips[placements[p].clip];
+                progress.report(
+                    ProgressPhase::Stitching,
+                    (step - 1) as f64 / total_steps as f64,
+                    (step, total_steps),
+                    &format!("Stitching '{}'...", clip.name),
+                );
+                if placements[p].drift.is_some() {
+                    progress.report(
+                        ProgressPhase::CorrectingDrift,
+                        (step - 1) as f64 / total_steps as f64,
+                        (step, total_steps),
+                        &format!("Correcting drift ({:+.1} ppm) for '{}'...", clip.drift_ppm, clip.name),
+                    );
+                    clip.drift_corrected = true;
+                    info!("Applying drift correction {:.2} ppm to '{}'", clip.drift_ppm, clip.name);
+                }
+                let stream = ClipStream::new(&placements[p], config, export_sr, channels);
+                placements[p].audio = ClipAudio::Streaming(Box::new(stream));
+            }
+
+            // Read the next window of every clip that runs out within the
+            // block, up to `io_parallelism` at once.
+            loop {
+                let mut wanted = Vec::new();
+                for (p, placement) in placements.iter_mut().enumerate() {
+                    let need = placement.skip + to.min(placement.end).saturating_sub(placement.start);
+                    if let ClipAudio::Streaming(stream) = &mut placement.audio
+                        && let Some(window) = stream.wants(need)?
+                    {
+                        wanted.push((p, placement.clip, window));
+                    }
+                }
+                if wanted.is_empty() {
+                    break;
+                }
+                let track = &tracks[ti];
+                let reads = map_io_bounded(&wanted, batch, |_, &(_, clip, window)| {
+                    read_clip_full_res_window(&track.clips[clip], export_sr, window, cancel)
+                });
+                let mut dropped = false;
+                for (&(p, _, _), window) in wanted.iter().zip(reads) {
+                    let placement = &mut placements[p];
+                    let ClipAudio::Streaming(stream) = &mut placement.audio else {
+                        continue;
+                    };
+                    check_cancelled(cancel)?;
+                    match window {
+                        Ok(window) if window.first().is_some_and(|c| !c.is_empty()) || stream.started() => {
+                            stream.feed(window)?;
+                        }
+                        Ok(_) => {
+                            placement.audio = ClipAudio::Dropped;
+                            dropped = true;
+                        }
+                        Err(e) => {
+                            check_cancelled(cancel)?;
+                            let clip = &track.clips[placement.clip];
+                            warn!("Skipping '{}' during export: {}", clip.name, e);
+                            skipped.push(UnavailableClip {
+                                track_name: track.name.clone(),
+                                clip_name: clip.name.clone(),
+                                file_path: clip.file_path.clone(),
+                                reason: e.to_string(),
+                            });
+                            placement.audio = ClipAudio::Dropped;
+                            dropped = true;
+                        }
+                    }
+                }
+                if dropped {
+                    find_overlaps(&mut placements, config.overlap_policy, fade);
+                }
+            }
+
+            let block = mixer.mix(&placements, from, to - from);
+            if !block.is_empty() {
+                sink(ti, Stitched::Block(&block))?;
+            }
+            for p in placements.iter_mut() {
+                let ClipAudio::Streaming(stream) = &mut p.audio else {
+                    continue;
+                };
+                if p.end <= to {
+                    p.audio = ClipAudio::Done;
+                } else if p.start < to {
+                    stream.release(p.skip + to - p.start);
+                }
+            }
+            from = to;
+        }
+        let tail = mixer.finish();
+        if !tail.is_empty() {
+            sink(ti, Stitched::Block(&tail))?;
+        }
+        sink(ti, Stitched::End)?;
+    }
+
+    info!("Sync complete: {} tracks stitched at {} Hz", tracks.len(), export_sr);
+    if !skipped.is_empty() {
+        warn!("{} clip(s) skipped during export", skipped.len());
+    }
+    Ok(skipped)
+}
+
+/// Where each of the track's clips lands in `0..total_len`, the rendered
+/// stretch of its timeline starting at `range_start`. Clips outside it, and
+/// unplaced and excluded clips, are left out.
+fn plan(
+    track: &Track,
+    config: &SyncConfig,
+    export_sr: u32,
+    range_start: i64,
+    total_len: usize,
+) -> Vec<Placement> {
+    let sr = export_sr as f64;
+    let mut placements = Vec::new();
+    for (ci, clip) in track.clips.iter().enumerate().filter(|(_, c)| c.on_timeline()) {
+        let at = |s: f64| (s.max(0.0) * sr).round() as usize;
+        let kept = clip
+            .length_at_sr(export_sr)
+            .saturating_sub(at(clip.trim_in_s) + at(clip.trim_out_s));
+
+        // Timeline time (from the clip's start) of the first sample kept
+        // after trimming the head.
+        let mut head_s = clip.trim_in_s.max(0.0);
+        let mut len = kept;
+        let drift = corrects_drift(clip, config).then(|| {
+            let mut segments = clip.drift_model();
+            if head_s > 0.0 {
+                head_s = drift_timeline_time(&segments, head_s);
+                segments = drift_segments_from(&segments, head_s);
+            }
+            len = (drift_timeline_time(&segments, kept as f64 / sr) * sr).round() as usize;
+            segments
+        });
+
+        // Relative to the rendered range; audio before its start is skipped.
+        let start =
+            clip.timeline_offset_at_sr(export_sr).max(0) + (head_s * sr).round() as i64 - range_start;
+        let (first, end) = (start.max(0), (start + len as i64).min(total_len as i64));
+        if first >= end {
+            continue;
+        }
+        placements.push(Placement {
+            clip: ci,
+            start: first as usize,
+            end: end as usize,
+            skip: (first - start) as usize,
+            len,
+            drift,
+            overlaps: Vec::new(),
+            audio: ClipAudio::Pending,
+        });
+    }
+    placements
+}
+
+/// Samples (at `export_sr`) the track's clip `clip_index` covers once
+/// stitched, trim and drift correction included; `None` for an unplaced
+/// or excluded clip.
+pub fn stitched_clip_span(
+    track: &Track,
+    clip_index: usize,
+    config: &SyncConfig,
+    export_sr: u32,
+) -> Option<(usize, usize)> {
+    plan(track, config, export_sr, 0, i64::MAX as usize)
+        .iter()
+        .find(|p| p.clip == clip_index)
+        .map(|p| (p.start, p.end))
+}
+
+/// Whether export drift-corrects `clip`.
+pub(super) fn corrects_drift(clip: &Clip, config: &SyncConfig) -> bool {
+    config.drift_correction
+        && clip.peak_drift_ppm() >= config.drift_threshold_ppm
+        && clip.drift_confidence > 0.5
+}
+
+/// How a streamed clip is drift-corrected.
+enum StreamDrift {
+    None,
+    /// Interpolated at the source position of each output frame, linearly
+    /// or (`cubic`) by Catmull-Rom.
+    Interpolate { segments: Vec<DriftSegment>, cubic: bool },
+    /// Windowed-sinc, set up with the first window read.
+    Sinc(Option<Box<DriftSinc>>, ResampleQuality, Vec<DriftSegment>),
+}
+
+/// A placed clip's audio, read a window at a time as the blocks reach it:
+/// trimmed, drift-corrected as planned and fitted to the track's channels.
+///
+/// Frames are counted in the clip's (trimmed, drift-corrected) audio, so
+/// the placement's first frame is `Placement::skip`; source frames are at
+/// the export rate, from the trimmed head.
+struct ClipStream {
+    /// Output frames held, from frame `from`, one `Vec` per output channel.
+    from: usize,
+    audio: Vec<Vec<f64>>,
+    /// The placement's first frame, and the planned length the audio read
+    /// is padded to.
+    first: usize,
+    len: usize,
+    channels: usize,
+    sample_rate: u32,
+    drift: StreamDrift,
+    /// Source frames held for interpolation, from `source_from`.
+    source_from: usize,
+    source: Vec<Vec<f64>>,
+    /// Next source frame to read.
+    next_read: usize,
+    /// A window came back short: the source has no more.
+    exhausted: bool,
+}
+
+impl ClipStream {
+    fn new(placement: &Placement, config: &SyncConfig, export_sr: u32, channels: usize) -> Self {
+        let drift = match (placement.drift.clone(), config.drift_resample_quality) {
+            (None, _) => StreamDrift::None,
+            (Some(segments), quality @ (ResampleQuality::Fast | ResampleQuality::Cubic)) => {
+                StreamDrift::Interpolate { segments, cubic: quality == ResampleQuality::Cubic }
+            }
+            (Some(segments), quality) => StreamDrift::Sinc(None, quality, segments),
+        };
+        // Reading starts where the placement's first frame is found. The
+        // sinc resampler has to run from the start of the clip.
+        let (from, next_read) = match &drift {
+            StreamDrift::None => (placement.skip, placement.skip),
+            StreamDrift::Interpolate { segments, .. } => {
+                let pos = drift_source_time(segments, placement.skip as f64 / export_sr as f64);
+                (placement.skip, ((pos * export_sr as f64) as usize).saturating_sub(1))
+            }
+            StreamDrift::Sinc(..) => (0, 0),
+        };
+        Self {
+            from,
+            audio: vec![Vec::new(); channels],
+            first: placement.skip,
+            len: placement.len,
+            channels,
+            sample_rate: export_sr,
+            drift,
+            source_from: next_read,
+            source: Vec::new(),
+            next_read,
+            exhausted: false,
+        }
+    }
+
+    /// Whether any of the clip has been read yet.
+    fn started(&self) -> bool {
+        !self.source.is_empty()
+    }
+
+    /// One past the last output frame held.
+    fn end(&self) -> usize {
+        self.from + self.audio[0].len()
+    }
+
+    /// The source window (start, frames) to read next for output frames up
+    /// to `need`, or `None` when they are held. Once the source is
+    /// exhausted, the rest is made up without reading.
+    fn wants(&mut self, need: usize) -> Result<Option<(usize, usize)>> {
+        let need = need.min(self.len);
+        if self.end() >= need {
+            return Ok(None);
+        }
+        if !self.exhausted {
+            return Ok(Some((self.next_read, CLIP_WINDOW)));
+        }
+        self.produce(need)?;
+        Ok(None)
+    }
+
+    /// Take in the window read for [`ClipStream::wants`].
+    fn feed(&mut self, mut window: Vec<Vec<f64>>) -> Result<()> {
+        let frames = window.first().map_or(0, Vec::len);
+        self.exhausted = frames < CLIP_WINDOW;
+        self.next_read += frames;
+        // Downmixed first, drift is only corrected once.
+        if self.channels == 1 && window.len() > 1 {
+            window = conform_channels(window, 1);
+        }
+        if self.source.is_empty() {
+            self.source = vec![Vec::new(); window.len().max(1)];
+        }
+        for (held, channel) in self.source.iter_mut().zip(&window) {
+            held.extend_from_slice(channel);
+        }
+        self.produce(0)
+    }
+
+    /// Turn held source into output frames, up to `need` once the source
+    /// is exhausted.
+    fn produce(&mut self, need: usize) -> Result<()> {
+        let channels = self.source.len().max(1);
+        let held_to = self.end();
+        let mut made: Vec<Vec<f64>> = vec![Vec::new(); channels];
+        match &mut self.drift {
+            StreamDrift::None => {
+                made = std::mem::replace(&mut self.source, vec![Vec::new(); channels]);
+                self.source_from = self.next_read;
+            }
+            StreamDrift::Interpolate { segments, cubic } => {
+                let sr = self.sample_rate as f64;
+                let held = self.source_from + self.source.first().map_or(0, Vec::len);
+                let mut i = held_to;
+                loop {
+                    let pos = drift_source_time(segments, i as f64 / sr) * sr;
+                    // Cubic interpolation reads two frames ahead.
+                    if !self.exhausted && pos as usize + 2 >= held {
+                        break;
+                    }
+                    let at = pos - self.source_from as f64;
+                    let frame: Option<Vec<f64>> =
+                        self.source.iter().map(|c| interpolate(c, at.max(0.0), *cubic)).collect();
+                    match frame {
+                        Some(frame) if i < need || !self.exhausted => {
+                            made.iter_mut().zip(frame).for_each(|(c, x)| c.push(x));
+                        }
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                // Keep a frame before the next position for interpolation.
+                let pos = drift_source_time(segments, i as f64 / sr) * sr;
+                let keep_from = (pos as usize).saturating_sub(1).max(self.source_from);
+                let spent = (keep_from - self.source_from).min(self.source.first().map_or(0, Vec::len));
+                self.source.iter_mut().for_each(|c| drop(c.drain(..spent)));
+                self.source_from += spent;
+            }
+            StreamDrift::Sinc(resampler, quality, segments) => {
+                let resampler = match resampler {
+                    Some(resampler) => resampler,
+                    None => {
+                        let sinc = DriftSinc::new(segments, self.sample_rate, *quality, channels)?;
+                        resampler.insert(Box::new(sinc))
+                    }
+                };
+                let input: Vec<&[f64]> = self.source.iter().map(Vec::as_slice).collect();
+                made = resampler.push(&input)?;
+                self.source.iter_mut().for_each(Vec::clear);
+                self.source_from = self.next_read;
+                while self.exhausted && held_to + made[0].len() < need {
+                    let flushed = resampler.flush()?;
+                    made.iter_mut().zip(flushed).for_each(|(c, f)| c.extend(f));
+                }
+            }
+        }
+        let made = conform_channels(made, self.channels);
+        for (held, new) in self.audio.iter_mut().zip(made) {
+            held.extend(new);
+        }
+        if self.exhausted {
+            let end = need.min(self.len).saturating_sub(self.from);
+            self.audio.iter_mut().for_each(|c| c.resize(c.len().max(end), 0.0));
+        }
+        // The sinc resampler runs from the clip's start; what comes before
+        // the placement's first frame is never mixed.
+        self.release(self.first);
+        Ok(())
+    }
+
+    /// Output frame `i` of channel `c`.
+    fn frame(&self, c: usize, i: usize) -> f64 {
+        self.audio[c][i - self.from]
+    }
+
+    /// Let go of the output frames before `i`.
+    fn release(&mut self, i: usize) {
+        let n = i.saturating_sub(self.from).min(self.audio[0].len());
+        self.audio.iter_mut().for_each(|c| drop(c.drain(..n)));
+        self.from += n;
+    }
+}
+
+/// Fit a clip's channels to `n`: averaged for mono, a mono clip copied to
+/// each, missing channels silent and extra ones left out.
+fn conform_channels(mut audio: Vec<Vec<f64>>, n: usize) -> Vec<Vec<f64>> {
+    let len = audio[0].len();
+    match audio.len() {
+        have if have == n => audio,
+        have if n == 1 => {
+            vec![(0..len).map(|i| audio.iter().map(|c| c[i]).sum::<f64>() / have as f64).collect()]
+        }
+        1 => vec![audio.swap_remove(0); n],
+        _ => {
+            audio.resize(n, vec![0.0; len]);
+            audio
+        }
+    }
+}
+
+/// Work out, for each placement, where it overlaps the placements before it
+/// (in clip order, which is the order they are laid in). Dropped clips count
+/// as absent.
+fn find_overlaps(placements: &mut [Placement], policy: OverlapPolicy, fade: usize) {
+    // Preferring the later clip is a crossfade without the fade.
+    let fade = if policy == OverlapPolicy::PreferLater { 0 } else { fade };
+    for c in 0..placements.len() {
+        let (start, end) = (placements[c].start, placements[c].end);
+        let earlier = || placements[..c].iter().filter(|p| !matches!(p.audio, ClipAudio::Dropped));
+        let mut covered: Vec<(usize, usize)> = earlier()
+            .map(|p| (p.start.max(start), p.end.min(end)))
+            .filter(|(a, b)| a < b)
+            .collect();
+        covered.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (a, b) in covered {
+            match merged.last_mut() {
+                Some(last) if a <= last.1 => last.1 = last.1.max(b),
+                _ => merged.push((a, b)),
+            }
+        }
+        let outlasted = earlier().any(|p| p.start <= end && end < p.end);
+        placements[c].overlaps = merged
+            .into_iter()
+            .map(|(a, b)| {
+                let (fade_in, fade_out) = (a == start, b == end && outlasted);
+                let n = b - a;
+                Overlap {
+                    start: a,
+                    end: b,
+                    fade_in,
+                    fade_out,
+                    fade: fade.min(if fade_in && fade_out { n / 2 } else { n }),
+                }
+            })
+            .collect();
+    }
+}
+
+/// Weight of the new audio at sample `k` of an overlap: eased in and out
+/// (equal power) across the fades.
+fn crossfade_angle(overlap: &Overlap, k: usize) -> f64 {
+    let mut w: f64 = 1.0;
+    if overlap.fade > 0 && overlap.fade_in {
+        w = w.min(((k - overlap.start) as f64 + 0.5) / overlap.fade as f64);
+    }
+    if overlap.fade > 0 && overlap.fade_out {
+        w = w.min(((overlap.end - k) as f64 - 0.5) / overlap.fade as f64);
+    }
+    w.clamp(0.0, 1.0) * std::f64::consts::FRAC_PI_2
+}
+
+/// Mixes placed clips block by block as the overlap policy says, limiting
+/// summed overlaps on the way out.
+struct Mixer {
+    policy: OverlapPolicy,
+    channels: usize,
+    limiter: Option<Limiter>,
+}
+
+impl Mixer {
+    fn new(policy: OverlapPolicy, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            policy,
+            channels,
+            limiter: (policy == OverlapPolicy::SumWithLimiter).then(|| Limiter::new(sample_rate, channels)),
+        }
+    }
+
+    /// Mix output frames `from..from + len`, channels interleaved. Every
+    /// placement touching them must hold its audio for them. With the limiter, what comes
+    /// back lags behind.
+    fn mix(&mut self, placements: &[Placement], from: usize, len: usize) -> Vec<f64> {
+        let ch = self.channels;
+        let mut out = vec![0.0f64; len * ch];
+        // How many clips cover each frame, for the limiter.
+        let mut layers = vec![0u8; len];
+        for p in placements {
+            let (a, b) = (p.start.max(from), p.end.min(from + len));
+            let ClipAudio::Streaming(stream) = &p.audio else {
+                debug_assert!(a >= b || matches!(p.audio, ClipAudio::Dropped), "clip {} not loaded", p.clip);
+                continue;
+            };
+            let mut overlaps = p.overlaps.iter().peekable();
+            for k in a..b {
+                while overlaps.next_if(|o| o.end <= k).is_some() {}
+                let overlap = overlaps.peek().filter(|o| o.start <= k);
+                let (w_old, w_new) = match (self.policy, overlap) {
+                    (OverlapPolicy::SumWithLimiter, _) => (1.0, 1.0),
+                    (OverlapPolicy::PreferEarlier, Some(_)) => (1.0, 0.0),
+                    (_, None) => (0.0, 1.0),
+                    (_, Some(o)) => {
+                        let angle = crossfade_angle(o, k);
+                        (angle.cos(), angle.sin())
+                    }
+                };
+                let frame = &mut out[(k - from) * ch..(k - from + 1) * ch];
+                for (c, x) in frame.iter_mut().enumerate() {
+                    *x = *x * w_old + stream.frame(c, p.skip + k - p.start) * w_new;
+                }
+                layers[k - from] = layers[k - from].saturating_add(1);
+            }
+        }
+        match self.limiter.as_mut() {
+            Some(limiter) => limiter.push(&out, &layers),
+            None => out,
+        }
+    }
+
+    /// Whatever the limiter still holds.
+    fn finish(&mut self) -> Vec<f64> {
+        self.limiter.as_mut().map(Limiter::finish).unwrap_or_default()
+    }
+}
+
+/// Pulls summed overlaps (more than one layer) under [`LIMITER_CEILING`].
+/// The gain ramps down over the attack time ahead of each peak and back up
+/// over the release time after it, so nothing is clipped and nothing pumps.
+/// One gain applies to all channels of a frame, keeping the image steady.
+/// Frames are held back until the attack time after them has been seen.
+struct Limiter {
+    channels: usize,
+    attack_step: f64,
+    release_step: f64,
+    lookahead: usize,
+    /// Interleaved frames not yet let out.
+    held: Vec<f64>,
+    /// The gain each held frame needs on its own.
+    needed: Vec<f64>,
+    gain: Option<f64>,
+}
+
+impl Limiter {
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        let attack = (LIMITER_ATTACK_S * sample_rate as f64).max(1.0);
+        Self {
+            channels,
+            attack_step: 1.0 / attack,
+            release_step: 1.0 / (LIMITER_RELEASE_S * sample_rate as f64).max(1.0),
+            // Further ahead than this, even a zero gain has ramped back to 1.
+            lookahead: attack.ceil() as usize,
+            held: Vec::new(),
+            needed: Vec::new(),
+            gain: None,
+        }
+    }
+
+    fn push(&mut self, block: &[f64], layers: &[u8]) -> Vec<f64> {
+        self.held.extend_from_slice(block);
+        self.needed.extend(block.chunks(self.channels).zip(layers).map(|(frame, &l)| {
+            let peak = frame.iter().fold(0.0f64, |m, x| m.max(x.abs()));
+            if l > 1 && peak > LIMITER_CEILING { LIMITER_CEILING / peak } else { 1.0 }
+        }));
+        self.release(self.needed.len().saturating_sub(self.lookahead))
+    }
+
+    fn finish(&mut self) -> Vec<f64> {
+        self.release(self.needed.len())
+    }
+
+    /// Let the first `n` held frames out.
+    fn release(&mut self, n: usize) -> Vec<f64> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut ahead = vec![1.0; self.needed.len()];
+        let mut next = f64::INFINITY;
+        for (i, &g) in self.needed.iter().enumerate().rev() {
+            next = g.min(next + self.attack_step);
+            ahead[i] = next;
+        }
+        let ch = self.channels;
+        let mut out = Vec::with_capacity(n * ch);
+        for (i, frame) in self.held[..n * ch].chunks(ch).enumerate() {
+            let g = self.gain.map_or(ahead[i], |prev| ahead[i].min(prev + self.release_step));
+            self.gain = Some(g);
+            out.extend(frame.iter().map(|x| x * g));
+        }
+        self.held.drain(..n * ch);
+        self.needed.drain(..n);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stitch in-memory clips (`(start, audio)`, laid in that order) over
+    /// `total_len` samples, mixing in small blocks to cross block joins.
+    fn stitch(
+        clips: &[(usize, Vec<f64>)],
+        total_len: usize,
+        policy: OverlapPolicy,
+        fade: usize,
+    ) -> Vec<f64> {
+        let mut placements: Vec<Placement> = clips
+            .iter()
+            .enumerate()
+            .map(|(i, (start, audio))| Placement {
+                clip: i,
+                start: *start,
+                end: start + audio.len(),
+                skip: 0,
+                len: audio.len(),
+                drift: None,
+                overlaps: Vec::new(),
+                audio: ClipAudio::Streaming(Box::new(ClipStream {
+                    from: 0,
+                    audio: vec![audio.clone()],
+                    first: 0,
+                    len: audio.len(),
+                    channels: 1,
+                    sample_rate: 8000,
+                    drift: StreamDrift::None,
+                    source_from: 0,
+                    source: Vec::new(),
+                    next_read: 0,
+                    exhausted: true,
+                })),
+            })
+            .collect();
+        find_overlaps(&mut placements, policy, fade);
+        let mut mixer = Mixer::new(policy, 8000, 1);
+        let mut out = Vec::new();
+        for from in (0..total_len).step_by(7) {
+            out.extend(mixer.mix(&placements, from, 7.min(total_len - from)));
+        }
+        out.extend(mixer.finish());
+        out
+    }
+
+    #[test]
+    fn test_overlapping_clips_crossfade_at_equal_power() {
+        // Gain of the earlier (`old`) and later audio across a join.
+        let lay = |old: f64, new: f64, at: usize, len: usize| {
+            stitch(&[(0, vec![old; 100]), (at, vec![new; len])], 200, OverlapPolicy::Crossfade, 20)
+        };
+
+        // Starts inside earlier audio and outlasts it: fades in, then plays alone.
+        let g_old = lay(1.0, 0.0, 60, 80);
+        let g_new = lay(0.0, 1.0, 60, 80);
+        assert_eq!(g_old[59], 1.0);
+        assert!(g_old[60] > 0.99 && g_new[60] < 0.1);
+        for i in 60..80 {
+            assert!((g_old[i].powi(2) + g_new[i].powi(2) - 1.0).abs() < 1e-9, "sample {}", i);
+        }
+        assert!(g_old[80..140].iter().all(|&g| g.abs() < 1e-12));
+        assert!(g_new[80..140].iter().all(|&g| (g - 1.0).abs() < 1e-12));
+        assert!(g_new[140..].iter().all(|&g| g == 0.0));
+
+        // Inside earlier audio throughout: fades in and back out.
+        let g_old = lay(1.0, 0.0, 20, 50);
+        assert!(g_old[20] > 0.99 && g_old[69] > 0.99);
+        assert!(g_old[45].abs() < 1e-12);
+        assert_eq!(g_old[70], 1.0);
+    }
+
+    #[test]
+    fn test_overlap_policies() {
+        // An earlier clip at 0.3 over 0..100, a later one at 0.9 over 50..150.
+        let stitch = |policy| stitch(&[(0, vec![0.3; 100]), (50, vec![0.9; 100])], 8000, policy, 20);
+
+        let earlier = stitch(OverlapPolicy::PreferEarlier);
+        assert_eq!((earlier[49], earlier[99], earlier[100]), (0.3, 0.3, 0.9));
+        let later = stitch(OverlapPolicy::PreferLater);
+        assert_eq!((later[49], later[50], later[149]), (0.3, 0.9, 0.9));
+
+        let summed = stitch(OverlapPolicy::SumWithLimiter);
+        assert_eq!(summed.len(), 8000);
+        assert!(summed[50..100].iter().all(|&x| x <= LIMITER_CEILING + 1e-12));
+        assert!((summed[75] - LIMITER_CEILING).abs() < 1e-9);
+        // Outside the overlap the gain ramps back up; far from it, untouched.
+        assert!(summed[45] < 0.3 && summed[20] == 0.3);
+        assert!(summed[120] > summed[100] && summed[120] < 0.9);
+    }
+
+    #[test]
+    fn test_overlaps_follow_dropped_clips() {
+        let mut placements: Vec<Placement> = [(0, 100), (50, 150), (80, 120)]
+            .into_iter()
+            .enumerate()
+            .map(|(clip, (start, end))| Placement {
+                clip,
+                start,
+                end,
+                skip: 0,
+                len: end - start,
+                drift: None,
+                overlaps: Vec::new(),
+                audio: ClipAudio::Pending,
+            })
+            .collect();
+        find_overlaps(&mut placements, OverlapPolicy::Crossfade, 20);
+        let spans = |p: &Placement| p.overlaps.iter().map(|o| (o.start, o.end)).collect::<Vec<_>>();
+        assert_eq!(spans(&placements[1]), [(50, 100)]);
+        assert!(placements[1].overlaps[0].fade_in && !placements[1].overlaps[0].fade_out);
+        // Entirely inside the first two: fades in and back out.
+        let inner = &placements[2].overlaps;
+        assert_eq!(inner.len(), 1);
+        assert_eq!((inner[0].fade_in, inner[0].fade_out, inner[0].fade), (true, true, 20));
+
+        placements[1].audio = ClipAudio::Dropped;
+        find_overlaps(&mut placements, OverlapPolicy::Crossfade, 20);
+        assert_eq!(spans(&placements[2]), [(80, 100)]);
+        assert!(!placements[2].overlaps[0].fade_out);
+    }
+}
@@ -0,0 +1,137 @@
+//! Offset verification — a second, independent measurement of each placed
+//! clip.
+//!
+//! Correlation can lock onto a wrong peak and still look confident (a shared
+//! hum, a dominant low band). Each clip placed by correlation is matched
+//! again on a different frequency band: the 1–3.5 kHz band, or the full band
+//! when the project already correlates a band of its own. Where that match
+//! is confident too and lands more than a few milliseconds away, the clip is
+//! flagged. A weak second match proves nothing either way and is let pass.
+
+use log::debug;
+
+use super::{correlation_input, match_delay_candidates};
+use crate::models::*;
+
+/// Largest disagreement (seconds) between the two measurements that counts
+/// as agreement.
+const VERIFY_TOLERANCE_S: f64 = 0.005;
+
+/// Band checked when the project correlates the full band.
+const VERIFY_BAND_HZ: (f64, f64) = (1000.0, 3500.0);
+
+/// Match each of `clips` (`(track, clip)` indices, placed against
+/// `reference`) on the other band and warn about those that disagree.
+pub(crate) fn verify_offsets(
+    tracks: &[Track],
+    clips: &[(usize, usize)],
+    reference: &[f32],
+    config: &SyncConfig,
+    cancel: &Option<CancelToken>,
+) -> Result<Vec<SyncWarning>, CancelledError> {
+    if clips.is_empty() {
+        return Ok(Vec::new());
+    }
+    let band = match config.correlation_band_hz {
+        Some(_) => None,
+        None => Some(VERIFY_BAND_HZ),
+    };
+    let check = SyncConfig { correlation_band_hz: band, ..config.clone() };
+    let describe = |band: Option<(f64, f64)>| match band {
+        Some((low, high)) => format!("the {:.0}–{:.0} Hz band", low, high),
+        None => "the full band".to_string(),
+    };
+    let sr = ANALYSIS_SR;
+    let reference = correlation_input(reference, sr, &check, None);
+
+    let mut warnings = Vec::new();
+    for &(ti, ci) in clips {
+        check_cancelled(cancel)?;
+        let clip = &tracks[ti].clips[ci];
+        let target = correlation_input(&clip.samples, sr, &check, None);
+        let (m, _) = match_delay_candidates(
+            &reference,
+            &target,
+            sr,
+            config.max_offset_s,
+            1,
+            config.correlation_normalization,
+        );
+        if m.score < CONFIDENCE_THRESHOLD {
+            debug!("'{}': check on {} inconclusive ({:.0}/100)", clip.name, describe(band), m.score);
+            continue;
+        }
+        let apart_s = m.delay_s - clip.timeline_offset_s;
+        if apart_s.abs() <= VERIFY_TOLERANCE_S {
+            continue;
+        }
+        warnings.push(
+            SyncWarning::new(
+                WarningCode::OffsetMismatch,
+                WarningSeverity::Warning,
+                format!(
+                    "'{}' matched on {} lands {:+.1} ms from where {} placed it",
+                    clip.name,
+                    describe(band),
+                    apart_s * 1000.0,
+                    describe(config.correlation_band_hz)
+                ),
+            )
+            .for_clip(&clip.file_path),
+        );
+    }
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{analyze, band_pass};
+    use super::*;
+
+    fn track(name: &str, samples: Vec<f32>) -> Track {
+        let mut t = Track::new(name.into());
+        let path = format!("{}.wav", name);
+        let mut c = Clip::new(path.clone(), path, 48000, 1);
+        c.duration_s = samples.len() as f64 / ANALYSIS_SR as f64;
+        c.samples = samples;
+        t.clips.push(c);
+        t
+    }
+
+    #[test]
+    fn test_clip_matching_elsewhere_on_another_band_is_flagged() {
+        let sr = ANALYSIS_SR;
+        let low = band_pass(&noise(11, 4 * sr as usize), sr, 50.0, 300.0);
+        let high: Vec<f32> =
+            band_pass(&noise(12, 4 * sr as usize), sr, 1000.0, 3500.0).iter().map(|x| x * 3.0).collect();
+        // The reference has the low-band audio at 2 s and the (louder)
+        // high-band audio at 12 s; the clip carries both.
+        let mut reference = vec![0.0f32; 20 * sr as usize];
+        reference[2 * sr as usize..6 * sr as usize].copy_from_slice(&low);
+        reference[12 * sr as usize..16 * sr as usize].copy_from_slice(&high);
+        let clip: Vec<f32> = low.iter().zip(&high).map(|(l, h)| l + h).collect();
+        let mut tracks = vec![track("Ref", reference), track("Cam", clip)];
+        tracks[0].is_reference = true;
+
+        let flagged = |result: &SyncResult| {
+            result
+                .warnings
+                .iter()
+                .any(|w| w.code == WarningCode::OffsetMismatch && w.clip_id.as_deref() == Some("Cam.wav"))
+        };
+
+        // Full band: placed on the loud high band, which the check agrees with.
+        let result = analyze(&mut tracks, &SyncConfig::default(), &None, &None).unwrap();
+        assert!((result.clip_offsets["Cam.wav"] - 12 * sr as i64).abs() <= 2);
+        assert!(!flagged(&result), "{:?}", result.warnings);
+
+        // Correlating only the low band places it at 2 s; the full band disagrees.
+        let config = SyncConfig { correlation_band_hz: Some((50.0, 300.0)), ..Default::default() };
+        let result = analyze(&mut tracks, &config, &None, &None).unwrap();
+        assert!((result.clip_offsets["Cam.wav"] - 2 * sr as i64).abs() <= 2);
+        assert!(flagged(&result), "{:?}", result.warnings);
+
+        let config = SyncConfig { verify_offsets: false, ..config };
+        assert!(!flagged(&analyze(&mut tracks, &config, &None, &None).unwrap()));
+    }
+}
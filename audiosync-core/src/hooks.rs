@@ -0,0 +1,147 @@
+//! Post-export hook — run a user command after an export finishes.
+//!
+//! The command runs through the platform shell (`sh -c` / `cmd /C`) with
+//! the export described in environment variables, so scripts can upload,
+//! transcode or archive the results:
+//!
+//! - `AUDIOSYNC_OUTPUT_DIR` — export root directory
+//! - `AUDIOSYNC_FILES` — exported audio files, one per line
+//! - `AUDIOSYNC_FILE_COUNT` — number of exported files
+//! - `AUDIOSYNC_REPORT` — JSON report (result, files, skipped clips,
+//!   verification)
+//! - `AUDIOSYNC_FCPXML`, `AUDIOSYNC_EDL`, `AUDIOSYNC_TIMELINE_JSON`,
+//!   `AUDIOSYNC_AAF` — timeline files, when exported
+
+use anyhow::{bail, Context, Result};
+use log::info;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::models::{ExportVerification, SyncResult, UnavailableClip};
+
+/// Report file written next to the exports for the hook to read.
+pub const REPORT_FILENAME: &str = "audiosync_report.json";
+
+/// What an export produced.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportReport<'a> {
+    pub result: &'a SyncResult,
+    pub exported_files: &'a [String],
+    pub skipped: &'a [UnavailableClip],
+    /// Per-file decode check; empty unless verification was enabled.
+    pub verification: &'a [ExportVerification],
+    pub fcpxml: Option<&'a str>,
+    pub edl: Option<&'a str>,
+    pub timeline_json: Option<&'a str>,
+    pub aaf: Option<&'a str>,
+}
+
+/// Write `report` into `output_dir` and run `command` against it.
+///
+/// Fails if the command cannot be started or exits non-zero. Its output is
+/// sent to stderr so it never mixes with JSON on stdout.
+pub fn run_post_export(command: &str, output_dir: &str, report: &ExportReport) -> Result<()> {
+    let report_path = write_report(output_dir, report)?;
+    info!("Running post-export command: {}", command);
+
+    let mut cmd = shell_command(command);
+    cmd.stdout(std::io::stderr())
+        .env("AUDIOSYNC_OUTPUT_DIR", output_dir)
+        .env("AUDIOSYNC_FILES", report.exported_files.join("\n"))
+        .env("AUDIOSYNC_FILE_COUNT", report.exported_files.len().to_string())
+        .env("AUDIOSYNC_REPORT", &report_path);
+    if let Some(path) = report.fcpxml {
+        cmd.env("AUDIOSYNC_FCPXML", path);
+    }
+    if let Some(path) = report.edl {
+        cmd.env("AUDIOSYNC_EDL", path);
+    }
+    if let Some(path) = report.timeline_json {
+        cmd.env("AUDIOSYNC_TIMELINE_JSON", path);
+    }
+    if let Some(path) = report.aaf {
+        cmd.env("AUDIOSYNC_AAF", path);
+    }
+
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to run post-export command '{}'", command))?;
+    if !status.success() {
+        bail!("Post-export command exited with {}", status);
+    }
+    Ok(())
+}
+
+fn write_report(output_dir: &str, report: &ExportReport) -> Result<PathBuf> {
+    std::fs::create_dir_all(output_dir)?;
+    let path = Path::new(output_dir).join(REPORT_FILENAME);
+    std::fs::write(&path, serde_json::to_string_pretty(report)?)?;
+    Ok(path)
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_export_command_sees_export_env() {
+        let dir = std::env::temp_dir().join(format!("audiosync_hook_{}", uuid::Uuid::new_v4()));
+        let dir_str = dir.to_string_lossy().to_string();
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 0,
+            total_timeline_s: 0.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+        let files = vec!["/out/a.wav".to_string(), "/out/b.wav".to_string()];
+        let report = ExportReport {
+            result: &result,
+            exported_files: &files,
+            skipped: &[],
+            verification: &[],
+            fcpxml: None,
+            edl: None,
+            timeline_json: None,
+            aaf: None,
+        };
+
+        run_post_export(
+            "printf '%s|%s' \"$AUDIOSYNC_FILE_COUNT\" \"$AUDIOSYNC_REPORT\" > \"$AUDIOSYNC_OUTPUT_DIR/hook.txt\"",
+            &dir_str,
+            &report,
+        )
+        .unwrap();
+        let seen = std::fs::read_to_string(dir.join("hook.txt")).unwrap();
+        let report_json = std::fs::read_to_string(dir.join(REPORT_FILENAME)).unwrap();
+        let failed = run_post_export("exit 3", &dir_str, &report);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(seen, format!("2|{}", dir.join(REPORT_FILENAME).display()));
+        assert!(report_json.contains("/out/b.wav"));
+        assert!(failed.is_err());
+    }
+}
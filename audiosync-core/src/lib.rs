@@ -7,8 +7,20 @@
 //! - **grouping**: Auto-group files by device name.
 //! - **metadata**: Probe creation timestamps and audio info via ffprobe.
 //! - **project_io**: JSON project save/load.
+//! - **sync_map**: Analysis-only interchange file of clip hashes and offsets.
 //! - **timeline_export**: FCPXML and EDL generation.
-//! - **cloud**: Cloud API client (Phase 3+).
+//! - **aaf**: Audio-only AAF with a region per clip, for conforming in a DAW.
+//! - **cloud**: Cloud API client (Phase 3+) and project comment sync.
+//! - **remote**: Offload analysis to an `audiosync serve` instance.
+//! - **playback**: Preview mixer driven by the app's audio output.
+//! - **suggestions**: Remedies for clips that could not be placed confidently.
+//! - **hooks**: Post-export user command.
+//! - **stats**: Project summary for the dashboard.
+//! - **query**: Search a session's clips by name, device, date and analysis results.
+//! - **nulltest**: Align two recordings and measure the residual after subtraction.
+//! - **session**: Project state and operations shared by the CLI and app.
+//! - **diagnostics**: Capability report (tools, codecs, directories, CPU).
+//! - **simulate**: Simulated long operations for testing progress and cancellation.
 
 pub mod models;
 pub mod grouping;
@@ -16,8 +28,20 @@ pub mod metadata;
 pub mod audio_io;
 pub mod engine;
 pub mod project_io;
+pub mod sync_map;
 pub mod timeline_export;
+pub mod aaf;
 pub mod cloud;
+pub mod remote;
+pub mod playback;
+pub mod suggestions;
+pub mod hooks;
+pub mod stats;
+pub mod query;
+pub mod nulltest;
+pub mod session;
+pub mod diagnostics;
+pub mod simulate;
 
 // Re-export key types for convenience.
 pub use models::*;
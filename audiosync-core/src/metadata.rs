@@ -6,20 +6,32 @@ use anyhow::Result;
 use chrono::DateTime;
 use log::debug;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::process::Command;
 
+use crate::models::StreamMetadata;
+
 /// Extract creation_time as a Unix timestamp from an audio/video file.
 ///
 /// Fallback chain:
-///   1. `format_tags.creation_time` (most reliable for MP4/MOV)
-///   2. `stream_tags.creation_time` on the first audio stream
-///   3. File modification time
+///   1. `com.apple.quicktime.creationdate` (iPhone voice memos and video;
+///      local time with its UTC offset)
+///   2. `format_tags.creation_time` (most reliable for MP4/MOV)
+///   3. `format_tags.date` (Android 3GP/AMR recorders)
+///   4. `stream_tags.creation_time` on the first audio stream
+///   5. A `YYYYMMDD_HHMMSS` stamp in the file name (phone recorders)
+///   6. File modification time
 pub fn probe_creation_time(path: &str) -> Option<f64> {
     // Try ffprobe first
     if let Some(ts) = probe_creation_time_ffprobe(path) {
         return Some(ts);
     }
 
+    let name = std::path::Path::new(path).file_stem().and_then(|n| n.to_str());
+    if let Some(ts) = name.and_then(parse_filename_timestamp) {
+        return Some(ts);
+    }
+
     // Fallback to file modification time
     file_mtime(path)
 }
@@ -30,7 +42,7 @@ fn probe_creation_time_ffprobe(path: &str) -> Option<f64> {
             "-v", "quiet",
             "-print_format", "json",
             "-show_entries",
-            "format_tags=creation_time:stream_tags=creation_time",
+            "format_tags=com.apple.quicktime.creationdate,creation_time,date:stream_tags=creation_time",
             path,
         ])
         .output()
@@ -40,32 +52,69 @@ fn probe_creation_time_ffprobe(path: &str) -> Option<f64> {
         return None;
     }
 
-    let data: FfprobeOutput = serde_json::from_slice(&output.stdout).ok()?;
+    parse_creation_time(&output.stdout)
+}
+
+/// Format tag keys (lowercase) holding the recording time, in order of
+/// preference.
+const CREATION_TAGS: &[&str] = &["com.apple.quicktime.creationdate", "creation_time", "date"];
 
-    // Try format-level creation_time first
-    if let Some(ref format) = data.format {
-        if let Some(ref tags) = format.tags {
-            if let Some(ref ct) = tags.creation_time {
-                if let Some(ts) = parse_iso_timestamp(ct) {
-                    return Some(ts);
-                }
-            }
-        }
+/// Phones without a clock fix write 1904 (the QuickTime epoch) or 1970;
+/// anything before this is not a real recording time.
+const EARLIEST_PLAUSIBLE_TS: f64 = 315_532_800.0; // 1980-01-01
+
+fn parse_creation_time(json: &[u8]) -> Option<f64> {
+    let data: FfprobeOutput = serde_json::from_slice(json).ok()?;
+    let parse = |tags: &HashMap<String, String>, keys: &[&str]| {
+        let tags: HashMap<String, &String> = tags.iter().map(|(k, v)| (k.to_lowercase(), v)).collect();
+        keys.iter()
+            .filter_map(|k| tags.get(*k))
+            .filter_map(|v| parse_iso_timestamp(v))
+            .find(|&ts| ts >= EARLIEST_PLAUSIBLE_TS)
+    };
+
+    // Try format-level tags first
+    if let Some(ts) = data.format.as_ref().and_then(|f| parse(&f.tags, CREATION_TAGS)) {
+        return Some(ts);
     }
 
     // Try stream-level creation_time
-    if let Some(ref streams) = data.streams {
-        for stream in streams {
-            if let Some(ref tags) = stream.tags {
-                if let Some(ref ct) = tags.creation_time {
-                    if let Some(ts) = parse_iso_timestamp(ct) {
-                        return Some(ts);
-                    }
-                }
-            }
+    data.streams
+        .iter()
+        .flatten()
+        .find_map(|stream| parse(&stream.tags, &["creation_time"]))
+}
+
+/// Find a `YYYYMMDD HHMMSS` stamp in a file name, e.g. `Recording_20240501_103015`,
+/// `REC20240501-103015` or the iOS Voice Memos `20240501 103015-3F2A1B9C`.
+/// Phone recorders name files in local time.
+fn parse_filename_timestamp(name: &str) -> Option<f64> {
+    use chrono::TimeZone;
+
+    let bytes = name.as_bytes();
+    let digits = |from: usize, n: usize| {
+        bytes.get(from..from + n).is_some_and(|b| b.iter().all(u8::is_ascii_digit))
+    };
+    for start in 0..bytes.len() {
+        if (start > 0 && bytes[start - 1].is_ascii_digit()) || !digits(start, 8) {
+            continue;
+        }
+        let time_at = match bytes.get(start + 8) {
+            Some(b'_' | b'-' | b' ') => start + 9,
+            _ => start + 8,
+        };
+        if !digits(time_at, 6) || bytes.get(time_at + 6).is_some_and(u8::is_ascii_digit) {
+            continue;
+        }
+        let stamp = format!("{}{}", &name[start..start + 8], &name[time_at..time_at + 6]);
+        let Ok(naive) = chrono::NaiveDateTime::parse_from_str(&stamp, "%Y%m%d%H%M%S") else {
+            continue;
+        };
+        let ts = chrono::Local.from_local_datetime(&naive).earliest()?.timestamp() as f64;
+        if ts >= EARLIEST_PLAUSIBLE_TS {
+            return Some(ts);
         }
     }
-
     None
 }
 
@@ -87,6 +136,8 @@ fn parse_iso_timestamp(value: &str) -> Option<f64> {
         "%Y-%m-%dT%H:%M:%SZ",
         "%Y-%m-%dT%H:%M:%S%.f%:z",
         "%Y-%m-%dT%H:%M:%S%:z",
+        "%Y-%m-%dT%H:%M:%S%.f%z",
+        "%Y-%m-%dT%H:%M:%S%z",
         "%Y-%m-%dT%H:%M:%S%.f",
         "%Y-%m-%dT%H:%M:%S",
         "%Y-%m-%d %H:%M:%S",
@@ -120,17 +171,138 @@ struct FfprobeOutput {
 
 #[derive(Debug, Deserialize)]
 struct FfprobeFormat {
-    tags: Option<FfprobeTags>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct FfprobeStream {
-    tags: Option<FfprobeTags>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+/// Probe codec, bit depth, channel layout, container duration and camera
+/// make/model.
+///
+/// Returns empty metadata when ffprobe is unavailable or fails.
+pub fn probe_stream_metadata(path: &str) -> StreamMetadata {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v", "quiet",
+            "-print_format", "json",
+            "-select_streams", "a:0",
+            "-show_entries",
+            "format=duration:format_tags:stream=codec_name,bits_per_sample,bits_per_raw_sample,channels,channel_layout",
+            path,
+        ])
+        .output();
+
+    match output {
+        Ok(out) if out.status.success() => parse_stream_metadata(&out.stdout).unwrap_or_default(),
+        _ => StreamMetadata::default(),
+    }
+}
+
+/// Tag keys (lowercase) holding the camera make and model, in order of
+/// preference: QuickTime metadata keys first, then the plain MP4/MOV tags.
+const MAKE_TAGS: &[&str] = &["com.apple.quicktime.make", "make", "com.android.manufacturer"];
+const MODEL_TAGS: &[&str] = &["com.apple.quicktime.model", "model", "com.android.model"];
+
+fn parse_stream_metadata(json: &[u8]) -> Option<StreamMetadata> {
+    let data: FfprobeDetails = serde_json::from_slice(json).ok()?;
+    let stream = data.streams.into_iter().next().unwrap_or_default();
+    let format = data.format.unwrap_or_default();
+
+    // Tag keys vary in case between muxers.
+    let tags: HashMap<String, String> = format
+        .tags
+        .into_iter()
+        .map(|(k, v)| (k.to_lowercase(), v.trim().to_string()))
+        .filter(|(_, v)| !v.is_empty())
+        .collect();
+    let tag = |keys: &[&str]| keys.iter().find_map(|k| tags.get(*k).cloned());
+
+    // Compressed codecs report 0 bits; raw bits are a string in ffprobe JSON.
+    let bit_depth = stream
+        .bits_per_raw_sample
+        .and_then(|b| b.parse::<u32>().ok())
+        .or(stream.bits_per_sample)
+        .filter(|&b| b > 0);
+
+    // Only a layout naming every channel is worth carrying to the export.
+    let channel_mask = stream
+        .channel_layout
+        .as_deref()
+        .and_then(channel_mask_for_layout)
+        .filter(|mask| stream.channels.is_none_or(|n| mask.count_ones() == n));
+
+    Some(StreamMetadata {
+        codec: stream.codec_name,
+        bit_depth,
+        channel_mask,
+        container_duration_s: format.duration.and_then(|d| d.parse().ok()),
+        camera_make: tag(MAKE_TAGS),
+        camera_model: tag(MODEL_TAGS),
+    })
 }
 
 #[derive(Debug, Deserialize)]
-struct FfprobeTags {
-    creation_time: Option<String>,
+struct FfprobeDetails {
+    format: Option<FfprobeDetailsFormat>,
+    #[serde(default)]
+    streams: Vec<FfprobeDetailsStream>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FfprobeDetailsFormat {
+    duration: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FfprobeDetailsStream {
+    codec_name: Option<String>,
+    bits_per_sample: Option<u32>,
+    bits_per_raw_sample: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+}
+
+/// ffmpeg's names for common channel layouts and their WAV `dwChannelMask`
+/// speaker bits (FL 0x1, FR 0x2, FC 0x4, LFE 0x8, BL 0x10, BR 0x20, BC 0x100,
+/// SL 0x200, SR 0x400).
+pub const CHANNEL_LAYOUTS: &[(&str, u32)] = &[
+    ("mono", 0x4),
+    ("stereo", 0x3),
+    ("2.1", 0xB),
+    ("3.0", 0x7),
+    ("3.0(back)", 0x103),
+    ("3.1", 0xF),
+    ("4.0", 0x107),
+    ("quad", 0x33),
+    ("quad(side)", 0x603),
+    ("4.1", 0x10F),
+    ("5.0", 0x37),
+    ("5.0(side)", 0x607),
+    ("5.1", 0x3F),
+    ("5.1(side)", 0x60F),
+    ("6.0", 0x707),
+    ("6.1", 0x70F),
+    ("7.0", 0x637),
+    ("7.1", 0x63F),
+    ("7.1(wide)", 0xFF),
+    ("7.1(wide-side)", 0x6CF),
+];
+
+/// Speaker bits of an ffmpeg layout name such as `5.1(side)`.
+pub fn channel_mask_for_layout(name: &str) -> Option<u32> {
+    CHANNEL_LAYOUTS.iter().find(|(n, _)| *n == name).map(|&(_, mask)| mask)
+}
+
+/// ffmpeg's name for a speaker mask, when it has one.
+pub fn channel_layout_name(mask: u32) -> Option<&'static str> {
+    CHANNEL_LAYOUTS.iter().find(|&&(_, m)| m == mask).map(|&(name, _)| name)
 }
 
 /// Get (sample_rate, channels) from an audio/video file using ffprobe.
@@ -158,3 +330,114 @@ pub fn probe_audio_info(path: &str) -> Result<(u32, u32)> {
     // Safe fallback
     Ok((48000, 2))
 }
+
+/// Get (width, height, fps_num, fps_den) of the first video stream via ffprobe.
+pub fn probe_video_info(path: &str) -> Option<(u32, u32, u32, u32)> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v", "quiet",
+            "-select_streams", "v:0",
+            "-show_entries", "stream=width,height,r_frame_rate",
+            "-of", "csv=p=0",
+            path,
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let parts: Vec<&str> = stdout.trim().split(',').collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    let width: u32 = parts[0].parse().ok()?;
+    let height: u32 = parts[1].parse().ok()?;
+    let (num, den) = parts[2].split_once('/').unwrap_or((parts[2], "1"));
+    let fps_num: u32 = num.parse().ok()?;
+    let fps_den: u32 = den.parse().ok()?;
+    if fps_num == 0 || fps_den == 0 {
+        return None;
+    }
+    debug!("Video info for {}: {}x{} @ {}/{}", path, width, height, fps_num, fps_den);
+    Some((width, height, fps_num, fps_den))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_metadata() {
+        let json = br#"{
+            "streams": [{"codec_name": "pcm_s24le", "bits_per_sample": 24, "bits_per_raw_sample": "24"}],
+            "format": {"duration": "62.500000", "tags": {
+                "com.apple.quicktime.make": "Apple",
+                "Model": "iPhone 15 Pro",
+                "creation_time": "2024-05-01T10:00:00.000000Z"
+            }}
+        }"#;
+        let m = parse_stream_metadata(json).unwrap();
+        assert_eq!(m.codec.as_deref(), Some("pcm_s24le"));
+        assert_eq!(m.bit_depth, Some(24));
+        assert_eq!(m.container_duration_s, Some(62.5));
+        assert_eq!(m.camera_make.as_deref(), Some("Apple"));
+        assert_eq!(m.camera_model.as_deref(), Some("iPhone 15 Pro"));
+        assert_eq!(m.channel_mask, None);
+
+        // A 5.1 ambience recording keeps its speaker positions; a layout
+        // that doesn't match the channel count is ignored.
+        let json = br#"{"streams": [{"codec_name": "pcm_s24le", "channels": 6, "channel_layout": "5.1(side)"}]}"#;
+        let m = parse_stream_metadata(json).unwrap();
+        assert_eq!(m.channel_mask, Some(0x60F));
+        assert_eq!(channel_layout_name(0x60F), Some("5.1(side)"));
+        let json = br#"{"streams": [{"codec_name": "pcm_s24le", "channels": 8, "channel_layout": "5.1"}]}"#;
+        assert_eq!(parse_stream_metadata(json).unwrap().channel_mask, None);
+
+        // Lossy audio has no bit depth; files without tags have no camera.
+        let json = br#"{"streams": [{"codec_name": "aac", "bits_per_sample": 0}], "format": {"duration": "10.0"}}"#;
+        let m = parse_stream_metadata(json).unwrap();
+        assert_eq!(m.bit_depth, None);
+        assert_eq!(m.camera_make, None);
+    }
+
+    #[test]
+    fn test_parse_creation_time_phone_tags() {
+        // iPhone voice memo: the QuickTime date carries the local offset and
+        // wins over the UTC creation_time.
+        let json = br#"{"format": {"tags": {
+            "creation_time": "2024-05-01T08:00:05.000000Z",
+            "com.apple.quicktime.creationdate": "2024-05-01T10:00:00+0200"
+        }}}"#;
+        assert_eq!(parse_creation_time(json), Some(1714550400.0));
+
+        // Android 3GP: a 1904 creation_time from an unset clock is skipped
+        // in favour of the recorder's date tag.
+        let json = br#"{"format": {"tags": {
+            "creation_time": "1904-01-01T00:00:00.000000Z",
+            "date": "2024-05-01T08:00:00Z"
+        }}, "streams": [{"tags": {"creation_time": "2024-05-01T09:00:00Z"}}]}"#;
+        assert_eq!(parse_creation_time(json), Some(1714550400.0));
+
+        let json = br#"{"format": {}, "streams": [{"tags": {"creation_time": "2024-05-01T08:00:00Z"}}]}"#;
+        assert_eq!(parse_creation_time(json), Some(1714550400.0));
+        assert_eq!(parse_creation_time(br#"{"format": {"tags": {}}}"#), None);
+    }
+
+    #[test]
+    fn test_parse_filename_timestamp() {
+        use chrono::TimeZone;
+        let local = |y, mo, d, h, mi, s| {
+            chrono::Local.with_ymd_and_hms(y, mo, d, h, mi, s).earliest().unwrap().timestamp() as f64
+        };
+        let expected = local(2024, 5, 1, 10, 30, 15);
+        assert_eq!(parse_filename_timestamp("Recording_20240501_103015"), Some(expected));
+        assert_eq!(parse_filename_timestamp("REC20240501-103015"), Some(expected));
+        assert_eq!(parse_filename_timestamp("20240501 103015-3F2A1B9C"), Some(expected));
+        assert_eq!(parse_filename_timestamp("Voice 001"), None);
+        assert_eq!(parse_filename_timestamp("take_120240501_103015"), None);
+        assert_eq!(parse_filename_timestamp("20241301_103015"), None);
+    }
+}
@@ -10,15 +10,23 @@ use std::sync::Arc;
 /// Analysis sample rate — low-res mono used for cross-correlation only.
 pub const ANALYSIS_SR: u32 = 8000;
 
-/// Confidence threshold — clips below this are considered poorly matched.
-pub const CONFIDENCE_THRESHOLD: f64 = 3.0;
-
-/// Minimum overlap (seconds) to attempt drift measurement.
-pub const MIN_DRIFT_OVERLAP_S: f64 = 60.0;
+/// Match score (0–100, see `Clip::match_score`) below which a clip is
+/// considered poorly matched.
+pub const CONFIDENCE_THRESHOLD: f64 = 20.0;
 
 /// Minimum number of measurement windows for a reliable regression.
 pub const MIN_DRIFT_WINDOWS: usize = 3;
 
+/// Drift window length bounds (seconds). Windows shrink with the overlap so
+/// short clips still get `MIN_DRIFT_WINDOWS` measurements.
+pub const MIN_DRIFT_WINDOW_S: f64 = 10.0;
+pub const MAX_DRIFT_WINDOW_S: f64 = 30.0;
+
+/// Minimum overlap (seconds) to attempt drift measurement: room for
+/// `MIN_DRIFT_WINDOWS` of the shortest window at half-window stride.
+pub const MIN_DRIFT_OVERLAP_S: f64 =
+    MIN_DRIFT_WINDOW_S * (MIN_DRIFT_WINDOWS as f64 + 1.0) / 2.0;
+
 // ---------------------------------------------------------------------------
 //  Cancellation
 // ---------------------------------------------------------------------------
@@ -31,26 +39,296 @@ pub fn new_cancel_token() -> CancelToken {
     Arc::new(AtomicBool::new(false))
 }
 
+/// Whether `cancel` has been set, for loops that wind down rather than fail.
+pub fn is_cancelled(cancel: &Option<CancelToken>) -> bool {
+    cancel.as_ref().is_some_and(|token| token.load(Ordering::Relaxed))
+}
+
 /// Check if cancelled; return Err if so.
 pub fn check_cancelled(cancel: &Option<CancelToken>) -> Result<(), CancelledError> {
-    if let Some(token) = cancel {
-        if token.load(Ordering::Relaxed) {
-            return Err(CancelledError("Operation cancelled".to_string()));
-        }
+    if is_cancelled(cancel) {
+        return Err(CancelledError("Operation cancelled".to_string()));
     }
     Ok(())
 }
 
-/// Progress callback type: (current_step, total_steps, message).
-pub type ProgressCallback = Box<dyn Fn(usize, usize, &str) + Send + Sync>;
+/// Progress callback, called with each [`ProgressEvent`].
+pub type ProgressCallback = Box<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Stage of an analysis or export, for progress display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressPhase {
+    /// Sorting clips, picking the reference, building its timeline.
+    Preparing,
+    /// Matching clips against the reference (both passes).
+    Correlating,
+    /// Checking placements on a second frequency band.
+    Verifying,
+    /// Shifting the timeline to start at zero.
+    Normalizing,
+    MeasuringDrift,
+    /// Re-correlating placements at the export rate.
+    Refining,
+    /// Reading and placing clips at the export rate.
+    Stitching,
+    CorrectingDrift,
+    Done,
+}
+
+/// One progress update: what is happening, how far along the whole
+/// operation is, and roughly how long is left.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub phase: ProgressPhase,
+    /// Share of the whole operation done, 0.0–1.0.
+    pub fraction: f64,
+    /// Item being worked on in this phase (1-based) and how many there are
+    /// — clip 3 of 12. Both 0 for steps that aren't per item.
+    pub current: usize,
+    pub total: usize,
+    pub message: String,
+    /// Seconds left at the pace so far; `None` until there is enough to go on.
+    pub eta_s: Option<f64>,
+}
+
+/// Share of an operation that must be done before its pace predicts the rest.
+const MIN_ETA_FRACTION: f64 = 0.02;
+
+/// Sends [`ProgressEvent`]s for one operation, timing it to estimate how
+/// long is left.
+pub struct ProgressReporter<'a> {
+    callback: &'a Option<ProgressCallback>,
+    started: std::time::Instant,
+    /// Share of the whole operation this reporter covers.
+    span: (f64, f64),
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub fn new(callback: &'a Option<ProgressCallback>) -> Self {
+        Self {
+            callback,
+            started: std::time::Instant::now(),
+            span: (0.0, 1.0),
+        }
+    }
+
+    /// A reporter for part `index` (0-based) of `count` equal parts of this
+    /// operation: its fractions map into that part, and its `Done` is dropped
+    /// so the operation finishes only once, when this reporter says so.
+    pub fn part(&self, index: usize, count: usize) -> ProgressReporter<'a> {
+        let (start, end) = self.span;
+        let width = (end - start) / count.max(1) as f64;
+        ProgressReporter {
+            callback: self.callback,
+            started: self.started,
+            span: (start + width * index as f64, start + width * (index + 1) as f64),
+        }
+    }
+
+    /// Report `fraction` of the operation done, in `phase`, working on item
+    /// `current` of `total` (0 of 0 when not per item).
+    pub fn report(
+        &self,
+        phase: ProgressPhase,
+        fraction: f64,
+        (current, total): (usize, usize),
+        message: &str,
+    ) {
+        let Some(callback) = self.callback else {
+            return;
+        };
+        if phase == ProgressPhase::Done && self.span != (0.0, 1.0) {
+            return;
+        }
+        let (start, end) = self.span;
+        let fraction = start + (end - start) * fraction.clamp(0.0, 1.0);
+        let elapsed_s = self.started.elapsed().as_secs_f64();
+        let eta_s = (fraction >= MIN_ETA_FRACTION && phase != ProgressPhase::Done)
+            .then(|| elapsed_s * (1.0 - fraction) / fraction);
+        callback(&ProgressEvent {
+            phase,
+            fraction,
+            current,
+            total,
+            message: message.to_string(),
+            eta_s,
+        });
+    }
+}
+
+/// Level snapshot of a file being decoded, emitted periodically on import.
+///
+/// `peak` and `rms` are linear (0.0–1.0) over the audio decoded since the
+/// previous snapshot; the final snapshot (`done`) covers the whole file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeterSnapshot {
+    pub file_path: String,
+    /// Seconds of audio decoded so far.
+    pub position_s: f64,
+    pub peak: f32,
+    pub rms: f32,
+    pub done: bool,
+}
 
 // ---------------------------------------------------------------------------
 //  Clip
 // ---------------------------------------------------------------------------
 
+/// Stream and container details probed from a source file. Every field is
+/// optional: formats and devices report different subsets.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamMetadata {
+    /// Audio codec, e.g. `pcm_s24le` or `aac`.
+    pub codec: Option<String>,
+    /// Bits per sample for PCM and lossless codecs.
+    pub bit_depth: Option<u32>,
+    /// Speaker positions of the channels, as WAV `dwChannelMask` bits
+    /// (see `metadata::CHANNEL_LAYOUTS`), when the file names them.
+    #[serde(default)]
+    pub channel_mask: Option<u32>,
+    /// Duration the container reports, which may differ from the decoded
+    /// audio (edit lists, trailing video).
+    pub container_duration_s: Option<f64>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+}
+
+/// Frames of end-of-clip error from drift correction at which a video clip
+/// whose picture isn't retimed is visibly out of lip sync.
+pub const CONFORM_WARN_FRAMES: f64 = 1.0;
+
+/// One piece of a piecewise-linear drift model: from `start_s` (seconds into
+/// the clip on the timeline) until the next segment starts, the recorder's
+/// clock ran `ppm` off the reference.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DriftSegment {
+    pub start_s: f64,
+    pub ppm: f64,
+}
+
+/// Source time reached at timeline time `t` into a clip whose clock follows
+/// `segments` (sorted, the first starting at 0).
+pub fn drift_source_time(segments: &[DriftSegment], t: f64) -> f64 {
+    let mut src = 0.0;
+    for (i, seg) in segments.iter().enumerate() {
+        let end = segments.get(i + 1).map_or(f64::INFINITY, |s| s.start_s);
+        if t <= end {
+            return src + (t - seg.start_s) * (1.0 + seg.ppm * 1e-6);
+        }
+        src += (end - seg.start_s) * (1.0 + seg.ppm * 1e-6);
+    }
+    t
+}
+
+/// Inverse of [`drift_source_time`]: timeline time at which source time
+/// `src` plays.
+pub fn drift_timeline_time(segments: &[DriftSegment], src: f64) -> f64 {
+    let mut reached = 0.0;
+    for (i, seg) in segments.iter().enumerate() {
+        let rate = 1.0 + seg.ppm * 1e-6;
+        let end = segments.get(i + 1).map_or(f64::INFINITY, |s| s.start_s);
+        let seg_src = (end - seg.start_s) * rate;
+        if src <= reached + seg_src {
+            return seg.start_s + (src - reached) / rate;
+        }
+        reached += seg_src;
+    }
+    src
+}
+
+/// The drift model of the audio from timeline time `from_s` on, as when the
+/// clip's head is trimmed: segments re-based so that `from_s` becomes zero.
+pub fn drift_segments_from(segments: &[DriftSegment], from_s: f64) -> Vec<DriftSegment> {
+    let first = segments.iter().rposition(|s| s.start_s <= from_s).unwrap_or(0);
+    segments[first..]
+        .iter()
+        .enumerate()
+        .map(|(i, s)| DriftSegment {
+            start_s: if i == 0 { 0.0 } else { s.start_s - from_s },
+            ppm: s.ppm,
+        })
+        .collect()
+}
+
+/// Wall-clock time spent on each processing stage of a clip, in seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClipTimings {
+    /// File read + decode (including ffmpeg extraction for video).
+    pub decode_s: f64,
+    /// Downmix + resample to the analysis rate.
+    pub resample_s: f64,
+    /// Cross-correlation and drift windows during analysis.
+    pub correlation_s: f64,
+}
+
+impl ClipTimings {
+    pub fn total_s(&self) -> f64 {
+        self.decode_s + self.resample_s + self.correlation_s
+    }
+
+    /// Sum timings across many clips.
+    pub fn sum<'a>(items: impl IntoIterator<Item = &'a ClipTimings>) -> ClipTimings {
+        items.into_iter().fold(ClipTimings::default(), |acc, t| ClipTimings {
+            decode_s: acc.decode_s + t.decode_s,
+            resample_s: acc.resample_s + t.resample_s,
+            correlation_s: acc.correlation_s + t.correlation_s,
+        })
+    }
+
+    /// Name of the stage that took the most time ("decode", "resample", "correlation").
+    pub fn bottleneck(&self) -> &'static str {
+        if self.decode_s >= self.resample_s && self.decode_s >= self.correlation_s {
+            "decode"
+        } else if self.resample_s >= self.correlation_s {
+            "resample"
+        } else {
+            "correlation"
+        }
+    }
+}
+
+/// Where an earlier analysis — in this or another project — placed a clip
+/// with the same content. Clips sharing a `run_id` were placed on one
+/// timeline, so their offsets relative to each other still hold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriorAnalysis {
+    pub run_id: String,
+    pub offset_s: f64,
+    pub drift_ppm: f64,
+    #[serde(default)]
+    pub drift_segments: Vec<DriftSegment>,
+    pub confidence: f64,
+    pub match_score: f64,
+    /// RFC 3339 timestamp of the analysis.
+    pub analyzed_at: String,
+}
+
+/// Clips of the current session that one earlier analysis placed together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReusableAnalysis {
+    pub run_id: String,
+    pub analyzed_at: String,
+    pub clip_ids: Vec<String>,
+}
+
+/// How to use earlier analyses of the same media.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorReuse {
+    /// Analyze as usual; earlier placements settle ambiguous matches the
+    /// way creation times do.
+    Priors,
+    /// Keep the earlier placement of the largest set of clips analyzed
+    /// together, so an incremental analysis only correlates the rest.
+    Skip,
+}
+
 /// A single audio or video file imported into a track.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Clip {
+    /// Source file, and the clip's id: results, manifests and bulk edits key
+    /// clips by it, so a session holds each file once.
     pub file_path: String,
     pub name: String,
 
@@ -64,17 +342,94 @@ pub struct Clip {
     pub duration_s: f64,
     pub is_video: bool,
     pub creation_time: Option<f64>,
+    /// Codec, bit depth, container duration and camera, as probed on import.
+    #[serde(default)]
+    pub stream_metadata: StreamMetadata,
 
     // Populated after analysis
     pub timeline_offset_samples: i64,
     pub timeline_offset_s: f64,
+    /// Raw correlation peak/mean ratio (unbounded).
     pub confidence: f64,
+    /// Calibrated match quality, 0–100: normalized cross-correlation at the
+    /// peak, discounted by how close the runner-up peak comes.
+    #[serde(default)]
+    pub match_score: f64,
     pub analyzed: bool,
 
     // Clock drift
     pub drift_ppm: f64,
     pub drift_confidence: f64,
     pub drift_corrected: bool,
+    /// Windows that contributed to the drift regression (0 = not measured).
+    #[serde(default)]
+    pub drift_windows: usize,
+    /// Piecewise drift for clocks that wander over a long take; when set it
+    /// replaces the constant `drift_ppm` in the export correction.
+    #[serde(default)]
+    pub drift_segments: Vec<DriftSegment>,
+    /// Drift was set by hand: analysis neither measures nor inherits it.
+    #[serde(default)]
+    pub drift_override: bool,
+    /// Drift was not measured on this clip but taken from its track's
+    /// estimate; the next analysis estimates it afresh.
+    #[serde(default)]
+    pub drift_inherited: bool,
+
+    /// Per-stage processing time for bottleneck reporting.
+    #[serde(default)]
+    pub timings: ClipTimings,
+
+    /// Locked clips keep their placement through analysis and overlap fixes.
+    #[serde(default)]
+    pub locked: bool,
+
+    /// LTC timecode at the clip's first sample (seconds since midnight),
+    /// once decoded from the channel named by `SyncConfig::ltc_channel`.
+    #[serde(default)]
+    pub ltc_start_s: Option<f64>,
+
+    /// Timeline position (seconds) set by hand. Analysis keeps the clip
+    /// there and export uses it verbatim.
+    #[serde(default)]
+    pub offset_override_s: Option<f64>,
+
+    /// Level change (dB) applied when the clip is stitched.
+    #[serde(default)]
+    pub gain_db: f64,
+
+    /// Seconds cut from the head and tail of the clip when it is stitched
+    /// (a slate, a fumbled stop). What remains keeps its timeline position.
+    #[serde(default)]
+    pub trim_in_s: f64,
+    #[serde(default)]
+    pub trim_out_s: f64,
+
+    /// The clip's polarity is flipped against the reference (a recorder
+    /// that inverts phase); stitching flips it back.
+    #[serde(default)]
+    pub polarity_inverted: bool,
+
+    /// SHA-256 of the analysis samples: the same recording decodes to the
+    /// same samples wherever the file has been copied or renamed to.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+
+    /// An earlier analysis of the same content, once looked up (see
+    /// `SessionController::reuse_prior_analysis`).
+    #[serde(default)]
+    pub prior: Option<PriorAnalysis>,
+
+    /// Analysis found nothing in the session this clip overlaps (a file from
+    /// another day, say), so it has no place on the timeline and is left out
+    /// of export until placed by hand.
+    #[serde(default)]
+    pub unplaced: bool,
+
+    /// Left out by the user: analysis skips it and it is not exported,
+    /// but it stays in the project to be brought back.
+    #[serde(default)]
+    pub excluded: bool,
 }
 
 impl Clip {
@@ -89,14 +444,116 @@ impl Clip {
             duration_s: 0.0,
             is_video: false,
             creation_time: None,
+            stream_metadata: StreamMetadata::default(),
             timeline_offset_samples: 0,
             timeline_offset_s: 0.0,
             confidence: 0.0,
+            match_score: 0.0,
             analyzed: false,
             drift_ppm: 0.0,
             drift_confidence: 0.0,
             drift_corrected: false,
+            drift_windows: 0,
+            drift_inherited: false,
+            drift_segments: Vec::new(),
+            drift_override: false,
+            timings: ClipTimings::default(),
+            locked: false,
+            ltc_start_s: None,
+            offset_override_s: None,
+            gain_db: 0.0,
+            trim_in_s: 0.0,
+            trim_out_s: 0.0,
+            polarity_inverted: false,
+            content_hash: None,
+            prior: None,
+            unplaced: false,
+            excluded: false,
+        }
+    }
+
+    /// Pin the clip's drift to `ppm` so analysis leaves it alone; `Some(0.0)`
+    /// clears a wrong measurement or inherited value. `None` removes the pin
+    /// and zeroes the drift until the next analysis measures it again.
+    pub fn set_drift_override(&mut self, ppm: Option<f64>) {
+        self.drift_override = ppm.is_some();
+        self.drift_ppm = ppm.unwrap_or(0.0);
+        self.drift_confidence = if ppm.is_some() { 1.0 } else { 0.0 };
+        self.drift_windows = 0;
+        self.drift_inherited = false;
+        self.drift_segments.clear();
+    }
+
+    /// Place the clip at `offset_s` on the timeline and keep it there;
+    /// `None` removes the pin and leaves the clip where it is until the next
+    /// analysis places it.
+    pub fn set_offset_override(&mut self, offset_s: Option<f64>) {
+        self.offset_override_s = offset_s;
+        if let Some(offset_s) = offset_s {
+            self.timeline_offset_s = offset_s;
+            self.timeline_offset_samples = (offset_s * self.sample_rate as f64).round() as i64;
+            self.confidence = 100.0;
+            self.match_score = 100.0;
+            self.analyzed = true;
+            self.unplaced = false;
+        }
+    }
+
+    /// Whether the clip is stitched, played and exported: neither unplaced
+    /// nor excluded.
+    pub fn on_timeline(&self) -> bool {
+        !self.unplaced && !self.excluded
+    }
+
+    /// Whether analysis must leave the clip where it is: locked, or placed
+    /// by hand.
+    pub fn is_pinned(&self) -> bool {
+        self.locked || self.offset_override_s.is_some()
+    }
+
+    /// Drift model applied at export: the piecewise segments, or one segment
+    /// of `drift_ppm`.
+    pub fn drift_model(&self) -> Vec<DriftSegment> {
+        if self.drift_segments.is_empty() {
+            vec![DriftSegment {
+                start_s: 0.0,
+                ppm: self.drift_ppm,
+            }]
+        } else {
+            self.drift_segments.clone()
+        }
+    }
+
+    /// Largest drift (ppm, absolute) anywhere in the clip.
+    pub fn peak_drift_ppm(&self) -> f64 {
+        self.drift_segments
+            .iter()
+            .map(|s| s.ppm.abs())
+            .fold(self.drift_ppm.abs(), f64::max)
+    }
+
+    /// `(timeline, source)` time points of the export drift correction,
+    /// linear in between; empty when the clip was not drift corrected.
+    pub fn drift_time_map(&self) -> Vec<(f64, f64)> {
+        if !self.drift_corrected || self.peak_drift_ppm() < 1e-6 {
+            return Vec::new();
+        }
+        let model = self.drift_model();
+        let end = drift_timeline_time(&model, self.duration_s);
+        let mut points = vec![(0.0, 0.0)];
+        for seg in model.iter().skip(1).filter(|s| s.start_s < end) {
+            points.push((seg.start_s, drift_source_time(&model, seg.start_s)));
         }
+        points.push((end, self.duration_s));
+        points
+    }
+
+    /// Frames (at `fps`) by which drift-corrected audio ends after the
+    /// uncorrected source: how far a video clip's picture is off by its last
+    /// frame when only the audio is corrected. Negative when the audio ends
+    /// first.
+    pub fn conform_error_frames(&self, fps: f64) -> f64 {
+        (drift_timeline_time(&self.drift_model(), self.duration_s) - self.duration_s) * fps
     }
 
     pub fn length_samples(&self) -> usize {
@@ -109,6 +566,9 @@ impl Clip {
 
     /// Convert timeline offset from analysis SR to a target SR.
     pub fn timeline_offset_at_sr(&self, target_sr: u32) -> i64 {
+        if let Some(offset_s) = self.offset_override_s {
+            return (offset_s * target_sr as f64).round() as i64;
+        }
         if self.sample_rate == target_sr {
             return self.timeline_offset_samples;
         }
@@ -119,6 +579,12 @@ impl Clip {
     pub fn length_at_sr(&self, target_sr: u32) -> usize {
         (self.duration_s * target_sr as f64).round() as usize
     }
+
+    /// Linear factor for `gain_db`, negative when `polarity_inverted`.
+    pub fn gain_factor(&self) -> f64 {
+        let gain = 10f64.powf(self.gain_db / 20.0);
+        if self.polarity_inverted { -gain } else { gain }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -132,12 +598,36 @@ pub struct Track {
     pub clips: Vec<Clip>,
     pub is_reference: bool,
 
+    /// Locks every clip in the track in place.
+    #[serde(default)]
+    pub locked: bool,
+
+    /// User-assigned role; `None` infers one from the device name.
+    #[serde(default)]
+    pub role: Option<TrackRole>,
+
+    /// Sync group, for projects holding several independent recordings
+    /// (e.g. two conference rooms): each group is analyzed against its own
+    /// reference and exported as its own timeline. `None` is the default group.
+    #[serde(default)]
+    pub sync_group: Option<String>,
+
+    /// Frequency weighting for this track's analysis audio; `None` uses the
+    /// profile for its role (`SyncConfig::device_band_weighting`).
+    #[serde(default)]
+    pub band_weighting: Option<BandWeighting>,
+
     #[serde(skip)]
     pub synced_audio: Option<Vec<f64>>,
 
-    /// For multi-channel export: number of channels in synced audio.
+    /// Channels interleaved in `synced_audio`.
     #[serde(skip)]
     pub synced_channels: u32,
+
+    /// Speaker positions of those channels (WAV `dwChannelMask` bits), when
+    /// known.
+    #[serde(skip)]
+    pub synced_channel_mask: Option<u32>,
 }
 
 impl Track {
@@ -146,8 +636,31 @@ impl Track {
             name,
             clips: Vec::new(),
             is_reference: false,
+            locked: false,
+            role: None,
+            sync_group: None,
+            band_weighting: None,
             synced_audio: None,
             synced_channels: 1,
+            synced_channel_mask: None,
+        }
+    }
+
+    /// The track's settings, with no clips or synced audio: what another
+    /// process needs to treat the track as this one does. Every field is
+    /// listed, so a new setting has to be decided on here.
+    pub fn without_clips(&self) -> Track {
+        Track {
+            name: self.name.clone(),
+            clips: Vec::new(),
+            is_reference: self.is_reference,
+            locked: self.locked,
+            role: self.role,
+            sync_group: self.sync_group.clone(),
+            band_weighting: self.band_weighting,
+            synced_audio: None,
+            synced_channels: 1,
+            synced_channel_mask: None,
         }
     }
 
@@ -155,6 +668,36 @@ impl Track {
         self.clips.iter().map(|c| c.duration_s).sum()
     }
 
+    /// Whether clip `ci` must not be moved (locked itself or via its track).
+    pub fn is_clip_locked(&self, ci: usize) -> bool {
+        self.locked || self.clips.get(ci).is_some_and(Clip::is_pinned)
+    }
+
+    /// Distinct original sample rates of the track's clips, ascending.
+    pub fn sample_rates(&self) -> Vec<u32> {
+        let mut rates: Vec<u32> = self.clips.iter().map(|c| c.original_sr).collect();
+        rates.sort_unstable();
+        rates.dedup();
+        rates
+    }
+
+    /// Whether any clip in the track is locked.
+    pub fn has_locked_clips(&self) -> bool {
+        self.locked || self.clips.iter().any(Clip::is_pinned)
+    }
+
+    /// Assigned role, or one inferred from the track name and media type.
+    pub fn role(&self) -> TrackRole {
+        self.role.unwrap_or_else(|| {
+            TrackRole::infer(&self.name, self.clips.iter().any(|c| c.is_video))
+        })
+    }
+
+    /// Sync group name, `"default"` for tracks not assigned to one.
+    pub fn sync_group_name(&self) -> &str {
+        self.sync_group.as_deref().unwrap_or(DEFAULT_SYNC_GROUP)
+    }
+
     pub fn clip_count(&self) -> usize {
         self.clips.len()
     }
@@ -175,10 +718,190 @@ impl Track {
     }
 }
 
+/// Band the speech emphasis of a [`BandWeighting`] boosts, in Hz.
+pub const SPEECH_BAND_HZ: (f64, f64) = (1000.0, 4000.0);
+
+/// Frequency weighting of one device's analysis audio before correlation,
+/// for matching microphones that hear the scene differently — a camera's
+/// onboard mic full of handling rumble against a lavalier. Zero-phase, so
+/// it never moves the correlation peak.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandWeighting {
+    /// Cut below this, e.g. 150 Hz for camera mics.
+    pub high_pass_hz: Option<f64>,
+    /// Cut above this.
+    pub low_pass_hz: Option<f64>,
+    /// Gain in dB on the speech band ([`SPEECH_BAND_HZ`]) over the rest;
+    /// 0 leaves it flat.
+    pub speech_emphasis_db: f64,
+}
+
+impl BandWeighting {
+    /// Whether this changes the audio at all.
+    pub fn is_flat(&self) -> bool {
+        self.high_pass_hz.is_none() && self.low_pass_hz.is_none() && self.speech_emphasis_db == 0.0
+    }
+}
+
+/// What a track carries, used for stem naming and FCPXML audio roles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TrackRole {
+    Dialog,
+    Music,
+    Fx,
+    Camera,
+}
+
+impl TrackRole {
+    pub const ALL: [TrackRole; 4] = [Self::Dialog, Self::Music, Self::Fx, Self::Camera];
+
+    /// Guess a role from a device/track name such as "Zoom H6" or "GoPro".
+    ///
+    /// Camera brands win over everything else (a Sony FX3 is not an effects
+    /// track); unknown names fall back to CAMERA for video and DIALOG for
+    /// audio-only recorders.
+    pub fn infer(name: &str, has_video: bool) -> Self {
+        const CAMERA: &[&str] = &[
+            "cam", "gopro", "iphone", "sony", "canon", "nikon", "panasonic", "lumix", "fuji",
+            "blackmagic", "bmpcc", "dji", "osmo", "insta360", "fx3", "fx6", "fx30",
+        ];
+        const MUSIC: &[&str] = &["music", "song", "score", "playback", "band"];
+        const FX: &[&str] = &["sfx", "foley", "ambience", "ambient", "roomtone", "atmos"];
+        const DIALOG: &[&str] = &[
+            "zoom", "tascam", "h4n", "h5", "h6", "lav", "lavalier", "mic", "rode", "sennheiser",
+            "boom", "recorder", "dialog", "dialogue", "vo", "interview",
+        ];
+
+        let lower = name.to_lowercase();
+        let tokens: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).collect();
+        let has_token = |words: &[&str]| tokens.iter().any(|t| words.contains(t));
+        let has_prefix = |words: &[&str]| tokens.iter().any(|t| words.iter().any(|w| t.starts_with(w)));
+
+        if has_prefix(CAMERA) {
+            Self::Camera
+        } else if has_prefix(MUSIC) {
+            Self::Music
+        } else if has_token(&["fx"]) || has_prefix(FX) {
+            Self::Fx
+        } else if has_token(DIALOG) || has_prefix(&["zoom", "tascam", "lav", "mic", "dialog"]) {
+            Self::Dialog
+        } else if has_video {
+            Self::Camera
+        } else {
+            Self::Dialog
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dialog => "DIALOG",
+            Self::Music => "MUSIC",
+            Self::Fx => "FX",
+            Self::Camera => "CAMERA",
+        }
+    }
+
+    /// FCPXML `audioRole`: FCP's built-in roles, plus a custom camera role.
+    pub fn fcpxml_role(self) -> &'static str {
+        match self {
+            Self::Dialog => "dialogue",
+            Self::Music => "music",
+            Self::Fx => "effects",
+            Self::Camera => "Camera",
+        }
+    }
+}
+
+impl std::str::FromStr for TrackRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|r| r.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("Unknown track role '{}' (expected DIALOG, MUSIC, FX or CAMERA)", s))
+    }
+}
+
+impl std::fmt::Display for TrackRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 // ---------------------------------------------------------------------------
 //  SyncResult
 // ---------------------------------------------------------------------------
 
+/// Name of the sync group tracks belong to unless assigned one.
+pub const DEFAULT_SYNC_GROUP: &str = "default";
+
+/// One sync group's own timeline (see `Track::sync_group`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncGroupResult {
+    pub name: String,
+    pub track_indices: Vec<usize>,
+    pub reference_track_index: usize,
+    pub total_timeline_samples: i64,
+    pub total_timeline_s: f64,
+    pub avg_confidence: f64,
+}
+
+/// Tracks whose clips matched each other but nothing on the reference's
+/// timeline — most likely a separate recording that belongs in its own
+/// sync group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedSyncGroup {
+    pub track_indices: Vec<usize>,
+    pub track_names: Vec<String>,
+    /// File paths of the tracks' clips.
+    pub clip_ids: Vec<String>,
+}
+
+/// How analysis arrived at a clip's position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMethod {
+    /// The strongest correlation peak.
+    Correlation,
+    /// A slate clap lined up with one in the reference.
+    Slate,
+    /// One of several near-equal peaks, chosen by an earlier analysis of the
+    /// same media or by creation time.
+    Disambiguated,
+    /// Decoded timecode, refined by correlation where the audio agreed.
+    Timecode,
+    /// Locked or kept where an earlier analysis put it.
+    Kept,
+    /// Onset-strength envelopes, after waveform correlation failed.
+    Onset,
+    /// Creation time, after correlation failed.
+    CreationTime,
+    /// Not placed: the clip matches nothing in the session.
+    Unplaced,
+}
+
+/// Measurements behind one clip's placement, for explaining why it
+/// succeeded or failed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClipDiagnostics {
+    pub method: MatchMethod,
+    /// Correlation peak over the mean correlation (`Clip::confidence`).
+    pub peak_sharpness: f64,
+    /// Strongest other peak more than 0.25 s away, relative to the chosen
+    /// one: near 1.0 is ambiguous, above 1.0 means the clip was placed away
+    /// from its strongest peak. `None` when no search was run (timecode,
+    /// kept clips).
+    pub secondary_peak_ratio: Option<f64>,
+    /// Seconds of non-silent audio the clip shares with the reference where
+    /// it was placed.
+    pub overlap_s: f64,
+    /// The clip's loud level over its noise floor (dB); `None` without audio.
+    pub snr_db: Option<f64>,
+}
+
 /// Results produced by the analysis engine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncResult {
@@ -189,42 +912,553 @@ pub struct SyncResult {
     pub clip_offsets: HashMap<String, i64>,
     pub avg_confidence: f64,
     pub drift_detected: bool,
-    pub warnings: Vec<String>,
+    pub warnings: Vec<SyncWarning>,
+    /// Remedies for clips that ended below the confidence threshold.
+    #[serde(default)]
+    pub suggestions: Vec<ClipSuggestion>,
+    /// Per-group timelines when the project has more than one sync group;
+    /// empty otherwise. The totals above then span the longest group.
+    #[serde(default)]
+    pub groups: Vec<SyncGroupResult>,
+    /// Sync groups discovered from content, not yet applied.
+    #[serde(default)]
+    pub proposed_groups: Vec<ProposedSyncGroup>,
+    /// Clips that match nothing in the session, left off the timeline (see
+    /// `Clip::unplaced`).
+    #[serde(default)]
+    pub unplaced_clips: Vec<String>,
+    /// How each analyzed non-reference clip was placed, by file path.
+    #[serde(default)]
+    pub diagnostics: HashMap<String, ClipDiagnostics>,
+}
+
+impl SyncResult {
+    /// Length in seconds of the timeline track `track_index` is exported on.
+    pub fn timeline_s_for(&self, track_index: usize) -> f64 {
+        self.groups
+            .iter()
+            .find(|g| g.track_indices.contains(&track_index))
+            .map_or(self.total_timeline_s, |g| g.total_timeline_s)
+    }
+
+    /// Whether any warning is at or above `severity`.
+    pub fn has_warnings_at(&self, severity: WarningSeverity) -> bool {
+        self.warnings.iter().any(|w| w.severity >= severity)
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Warnings
+// ---------------------------------------------------------------------------
+
+/// How serious a sync warning is. Ordered so `Error > Warning > Info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for WarningSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Machine-readable warning kind, so the UI can offer a matching fix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCode {
+    /// Correlation confidence below `CONFIDENCE_THRESHOLD`.
+    LowConfidence,
+    /// Clip placed from its creation time rather than by correlation.
+    MetadataFallback,
+    /// Clips on one track overlapped and the track was re-sequenced.
+    TrackOverlap,
+    /// Timeline starts before zero because locked clips blocked normalizing.
+    NotNormalized,
+    /// Keeping the anchor clip in place pushed other clips before zero.
+    AnchorBeforeZero,
+    /// Several near-equal matches; creation times picked one of them.
+    AmbiguousMatch,
+    /// Clips on one track were recorded at different sample rates.
+    MixedSampleRates,
+    /// Too little non-silent audio overlaps the reference to trust the match.
+    InsufficientSharedAudio,
+    /// Placed on a matched slate clap rather than the strongest correlation peak.
+    SlateAligned,
+    /// Placed by LTC timecode alone; the audio couldn't confirm it.
+    TimecodePlaced,
+    /// LTC timecode and the audio disagree on where a clip belongs.
+    TimecodeMismatch,
+    /// Tracks match each other but not the reference: a separate recording
+    /// (see `SyncResult::proposed_groups`).
+    SeparateRecording,
+    /// Clips on the reference track overlap in time (two recorders on one
+    /// track); one was preferred or both were mixed.
+    ReferenceOverlap,
+    /// A second correlation on another frequency band disagrees with the
+    /// clip's placement.
+    OffsetMismatch,
+    /// The clip matches nothing in the session and was left unplaced rather
+    /// than guessed from its creation time.
+    NotInSession,
+    /// Drift correction changes the length of a video clip's audio, and its
+    /// picture won't follow unless the NLE retimes it too.
+    VideoNotRetimed,
+    /// Metadata fallback would have placed the clip before the reference
+    /// starts, and `SyncConfig::place_pre_roll` is off.
+    BeforeReference,
+    /// The clip's polarity is flipped against the reference; stitching
+    /// flips it back.
+    PolarityInverted,
+    /// No maximum offset was given, so the search was bounded by the spread
+    /// of the clips' creation timestamps.
+    MaxOffsetEstimated,
+    /// Warning loaded from an older project that stored plain strings.
+    Other,
+}
+
+impl WarningCode {
+    /// Default remedy shown next to the warning.
+    pub fn suggestion(self) -> Option<&'static str> {
+        match self {
+            Self::LowConfidence => Some(
+                "Check that the clip overlaps the reference recording, or raise the maximum offset",
+            ),
+            Self::MetadataFallback => {
+                Some("Verify the placement by ear — the device clock may be wrong")
+            }
+            Self::TrackOverlap => Some("Check that every clip on this track came from one device"),
+            Self::NotNormalized => Some("Unlock clips or set an anchor clip to normalize"),
+            Self::AnchorBeforeZero => Some("Choose an earlier clip as the anchor"),
+            Self::AmbiguousMatch => Some("Verify the placement by ear — the content repeats"),
+            Self::MixedSampleRates => Some(
+                "Check the device's sample-rate setting; clips at each rate are treated as separate clocks",
+            ),
+            Self::InsufficientSharedAudio => Some(
+                "The clip is mostly silence where it overlaps — place it by hand or from creation time",
+            ),
+            Self::SlateAligned => Some("Verify the placement by ear — check the clap lines up on the slate"),
+            Self::TimecodePlaced => Some("Check the timecode generators were jam-synced before the take"),
+            Self::TimecodeMismatch => Some("Re-jam the timecode generators, or turn off LTC to place this clip by audio"),
+            Self::SeparateRecording => Some("Put these tracks in their own sync group to sync them against each other"),
+            Self::ReferenceOverlap => Some("Check the clips' timestamps, or move the second recorder to a track of its own"),
+            Self::OffsetMismatch => Some("Listen to the clip against the reference; if it's off, place it by hand"),
+            Self::NotInSession => Some("Check the clip is from this shoot; remove it, or place it by hand"),
+            Self::VideoNotRetimed => Some(
+                "Export FCPXML, which retimes the clip, or retime the video in the NLE to match the audio",
+            ),
+            Self::BeforeReference => Some("Allow pre-roll placement, or place the clip by hand"),
+            Self::PolarityInverted => Some("Check the recorder's phase-invert setting for future takes"),
+            Self::MaxOffsetEstimated => {
+                Some("If a clip with a wrong clock fails to match, set a larger maximum offset")
+            }
+            Self::Other => None,
+        }
+    }
+}
+
+/// A typed analysis warning.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncWarning {
+    pub code: WarningCode,
+    pub severity: WarningSeverity,
+    /// File path of the affected clip, if the warning concerns one clip.
+    pub clip_id: Option<String>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl SyncWarning {
+    pub fn new(code: WarningCode, severity: WarningSeverity, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity,
+            clip_id: None,
+            message: message.into(),
+            suggestion: code.suggestion().map(str::to_string),
+        }
+    }
+
+    pub fn for_clip(mut self, file_path: &str) -> Self {
+        self.clip_id = Some(file_path.to_string());
+        self
+    }
+}
+
+impl std::fmt::Display for SyncWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Kind of remedy proposed for a clip that could not be placed confidently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// A stronger match lies outside the `max_offset_s` search window.
+    IncreaseMaxOffset,
+    /// Creation time is a whole number of hours off the reference.
+    CheckClockTimezone,
+    /// Several near-equal correlation peaks, typical of music or loops.
+    RepetitiveContent,
+    /// Nothing points at a placement; the clip likely lies outside the reference.
+    MayNotOverlap,
+    /// Clip is (nearly) silent.
+    SilentClip,
+}
+
+/// An actionable suggestion for one clip, derived from analysis diagnostics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClipSuggestion {
+    /// File path of the clip.
+    pub clip_id: String,
+    pub kind: SuggestionKind,
+    pub message: String,
+}
+
+/// One point of a downsampled cross-correlation curve.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CorrelationPoint {
+    /// Lag in seconds (target start relative to reference start).
+    pub lag_s: f64,
+    /// Correlation magnitude, normalized so the best peak is 1.0.
+    pub value: f32,
+}
+
+/// How two clips overlap at the matched offset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OverlapStats {
+    pub reference_duration_s: f64,
+    pub target_duration_s: f64,
+    pub overlap_s: f64,
+    /// Overlap as a fraction of the shorter clip (0.0–1.0).
+    pub overlap_ratio: f64,
+}
+
+/// Result of a standalone pairwise comparison between two clips.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClipComparison {
+    pub delay_samples: i64,
+    pub delay_s: f64,
+    pub confidence: f64,
+    /// Calibrated 0–100 match score.
+    pub score: f64,
+    /// Strongest peak more than 0.25 s from the best one, relative to it.
+    /// Values near 1.0 mean the match is ambiguous.
+    pub runner_up_ratio: f64,
+    /// Correlation over the searched lags, downsampled by max magnitude.
+    pub curve: Vec<CorrelationPoint>,
+    pub overlap: OverlapStats,
+}
+
+// Projects saved before typed warnings stored plain strings.
+impl<'de> Deserialize<'de> for SyncWarning {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Typed {
+            code: WarningCode,
+            severity: WarningSeverity,
+            #[serde(default)]
+            clip_id: Option<String>,
+            message: String,
+            #[serde(default)]
+            suggestion: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Typed(Typed),
+            Legacy(String),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Typed(t) => Self {
+                code: t.code,
+                severity: t.severity,
+                clip_id: t.clip_id,
+                message: t.message,
+                suggestion: t.suggestion,
+            },
+            Repr::Legacy(message) => Self::new(WarningCode::Other, WarningSeverity::Warning, message),
+        })
+    }
 }
 
 // ---------------------------------------------------------------------------
 //  SyncConfig
 // ---------------------------------------------------------------------------
 
+/// Resampler used for drift correction at export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResampleQuality {
+    /// Linear interpolation — instant, but dulls high frequencies; for
+    /// preview renders. Analysis at this level matches clips of ten minutes
+    /// or more from their loudest stretches only, far quicker on long takes.
+    Fast,
+    /// Catmull-Rom cubic interpolation — nearly as quick, and flat far
+    /// higher up than linear; a fast render for high-rate masters.
+    Cubic,
+    /// Windowed-sinc, 128 taps.
+    #[default]
+    Balanced,
+    /// Windowed-sinc, 256 taps with cubic interpolation between filter
+    /// phases; for final masters.
+    High,
+}
+
+/// How the cross-correlation is normalized before its peak is picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrelationNormalization {
+    /// Raw correlation sums. Lags where more audio overlaps add up more
+    /// terms, so long overlaps win over better but shorter ones.
+    Raw,
+    /// Each lag divided by the energy of the audio overlapping there (true
+    /// normalized cross-correlation), so peaks and confidences compare
+    /// across clip lengths and partial overlaps.
+    #[default]
+    Overlap,
+}
+
+/// How the clips of a multi-clip reference track are laid out before the
+/// other tracks are matched against it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceLayout {
+    /// `Content` when a clip has no creation time, `Metadata` otherwise.
+    #[default]
+    Auto,
+    /// In clip order, spaced by the gaps between creation times.
+    Metadata,
+    /// From the audio alone: clips whose edges overlap are chained at the
+    /// matched lag, the rest butted end to end in clip order.
+    Content,
+}
+
+/// How the reference track is picked. A track marked as the reference by
+/// hand wins over every strategy but `Track`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceStrategy {
+    /// The track spanning the most recording time by creation times, or the
+    /// longest total duration when no clip has a timestamp.
+    #[default]
+    Coverage,
+    /// The track with the longest total duration.
+    Duration,
+    /// The track with the cleanest audio: highest ratio of its loud level to
+    /// its noise floor, averaged over its clips.
+    Quality,
+    /// The track with this name (case-insensitive), e.g. the mixer's. Falls
+    /// back to `Coverage` with a warning when no track has the name.
+    Track(String),
+}
+
+/// Channels of exported tracks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChannelPolicy {
+    /// Each track keeps the channels of its widest clip, and their speaker
+    /// layout (5.1, quad…) when the clip's file names it. Mono clips play on
+    /// every channel.
+    #[default]
+    Preserve,
+    /// Every track is mixed down to one channel.
+    Mono,
+}
+
+/// How a track's overlapping clips are combined on export. "Earlier" and
+/// "later" follow the track's clip order (by recording time).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlapPolicy {
+    /// The later clip takes over with an equal-power crossfade of
+    /// `crossfade_ms`, handing back the same way if the earlier one outlasts it.
+    #[default]
+    Crossfade,
+    /// The earlier clip plays on; later clips only fill what it doesn't cover.
+    PreferEarlier,
+    /// The later clip cuts in and plays on top, no fade.
+    PreferLater,
+    /// Both play, summed, with a peak limiter on the overlap so the sum
+    /// doesn't clip.
+    SumWithLimiter,
+}
+
 /// Configuration for the sync engine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SyncConfig {
     pub max_offset_s: Option<f64>,
+    /// Without `max_offset_s`, bound the search by the spread of the clips'
+    /// creation timestamps plus slack for unsynced clocks. Clips without a
+    /// timestamp leave the search unbounded, and clips that score below
+    /// `CONFIDENCE_THRESHOLD` within the bound are searched again without it.
+    pub estimate_max_offset: bool,
     pub export_format: String,
     pub export_bit_depth: u32,
     pub export_bitrate_kbps: u32,
     pub export_sr: Option<u32>,
+    /// Whether exported tracks keep their channels or are mixed to mono.
+    pub export_channels: ChannelPolicy,
+    /// How overlapping clips of a track are combined on export.
+    pub overlap_policy: OverlapPolicy,
+    /// Length of the equal-power crossfade where overlapping clips of a
+    /// track join on export under [`OverlapPolicy::Crossfade`]; 0 cuts hard.
     pub crossfade_ms: f64,
+    /// Before export, re-read a couple of seconds of each correlated clip
+    /// and the reference under it at the export rate and re-correlate them,
+    /// placing the clip to the export sample rather than the analysis one.
+    pub refine_offsets: bool,
     pub drift_correction: bool,
     pub drift_threshold_ppm: f64,
+    /// Resampler used to apply drift correction.
+    pub drift_resample_quality: ResampleQuality,
+    /// Give clips too short to measure their track's drift: the measured
+    /// clips' drift weighted by confidence and length, or, with none
+    /// measured, an estimate from the clips' timestamps across the session. Turn off when a device changed sample
+    /// clock mid-shoot.
+    pub inherit_drift: bool,
+    /// Let metadata fallback place clips whose creation time falls before
+    /// the reference's first recording (pre-roll), extending the timeline
+    /// backwards. Off, they are left unplaced with a warning.
+    pub place_pre_roll: bool,
+    /// Maximum number of files decoded/extracted at once during import and
+    /// export. Keep low for NAS or SD-card media; 1 reads strictly in sequence.
+    pub io_parallelism: usize,
+    /// File path of a clip whose previous placement survives re-analysis:
+    /// normalization shifts the timeline to keep it where it was instead of
+    /// moving the earliest clip to zero.
+    pub anchor_clip: Option<String>,
+    /// Render only this `(start, end)` span of the timeline, in seconds —
+    /// a quick full-quality preview before a long export. Clips outside the
+    /// span are not read.
+    pub export_range_s: Option<(f64, f64)>,
+    /// Subfolder layout for exports, e.g. `{project}/{date}/{format}`; see
+    /// `audio_io::ExportLayout`. `None` writes files flat into the output dir.
+    pub export_folder_template: Option<String>,
+    /// Exported file name without extension, e.g. `{role}_{track}`.
+    /// `None` keeps `{track}_{sr}`.
+    pub export_filename_template: Option<String>,
+    /// Shell command run after a successful export; see `hooks`.
+    pub post_export_command: Option<String>,
+    /// Decode every exported file afterwards and compare it with the
+    /// stitched buffer, catching silent encoder failures or bad media.
+    pub verify_export: bool,
+    /// Write each clip's downsampled correlation curve to this directory
+    /// during analysis, for investigating sync failures.
+    pub debug_correlation_dir: Option<String>,
+    /// Band-pass the analysis audio to this `(low, high)` range in Hz before
+    /// correlating, e.g. `(200.0, 3500.0)` to keep wind rumble and HVAC hum
+    /// from dominating the match. `None` correlates the full band.
+    pub correlation_band_hz: Option<(f64, f64)>,
+    /// Device profiles: frequency weighting of each role's analysis audio,
+    /// applied after `correlation_band_hz`. A track's own
+    /// `Track::band_weighting` takes precedence.
+    pub device_band_weighting: HashMap<TrackRole, BandWeighting>,
+    /// Spectrally subtract each clip's noise floor, estimated from its
+    /// quietest stretches, from its analysis audio before correlating. For
+    /// hiss- and wind-laden camera mics (action cams, drones).
+    pub denoise: bool,
+    /// How correlation curves are normalized when matching clips.
+    pub correlation_normalization: CorrelationNormalization,
+    /// Match each correlated clip again on a different frequency band and
+    /// warn where the two placements disagree.
+    pub verify_offsets: bool,
+    /// How the reference track is picked.
+    pub reference_strategy: ReferenceStrategy,
+    /// How the reference track's clips are ordered and spaced.
+    pub reference_layout: ReferenceLayout,
+    /// Anchor clips on clapperboard slates: where a clip and the reference
+    /// both contain clap-like transients, search the correlation only around
+    /// the offsets that line the claps up.
+    pub slate_prior: bool,
+    /// Channel (0-based) carrying SMPTE LTC. Clips where it decodes are
+    /// placed by timecode, refined and cross-checked by correlation.
+    pub ltc_channel: Option<u32>,
+    /// Keep every already-analyzed clip where it is and only correlate new
+    /// ones, against a timeline stitched from the placed clips. Falls back
+    /// to a full analysis when nothing has been analyzed yet.
+    pub incremental: bool,
+    /// Apply proposed sync groups (tracks that only match each other) right
+    /// away and analyze again, instead of just reporting them.
+    pub auto_sync_groups: bool,
+    /// Before analysis, split the clips into separate sessions, each a sync
+    /// group with its own timeline, wherever no clip was recording for this
+    /// many seconds (unless the clips either side share audio). `None` keeps
+    /// them on one timeline.
+    pub session_gap_s: Option<f64>,
+    /// Where decoded analysis samples are cached between imports; `None`
+    /// uses the platform cache directory. See `audio_io::AnalysisCache`.
+    pub analysis_cache_dir: Option<String>,
+    /// Size limit of the analysis cache in MiB (about 115 MiB per hour of
+    /// media); 0 turns caching off.
+    pub analysis_cache_max_mb: u64,
 }
 
 impl Default for SyncConfig {
     fn default() -> Self {
         Self {
             max_offset_s: None,
+            estimate_max_offset: true,
             export_format: "wav".to_string(),
             export_bit_depth: 24,
             export_bitrate_kbps: 320,
             export_sr: None,
+            export_channels: ChannelPolicy::default(),
+            overlap_policy: OverlapPolicy::default(),
             crossfade_ms: 50.0,
+            refine_offsets: true,
             drift_correction: true,
             drift_threshold_ppm: 0.3,
+            drift_resample_quality: ResampleQuality::default(),
+            inherit_drift: true,
+            place_pre_roll: true,
+            io_parallelism: 2,
+            anchor_clip: None,
+            export_range_s: None,
+            export_folder_template: None,
+            export_filename_template: None,
+            post_export_command: None,
+            verify_export: false,
+            debug_correlation_dir: None,
+            correlation_band_hz: None,
+            device_band_weighting: HashMap::new(),
+            denoise: false,
+            correlation_normalization: CorrelationNormalization::default(),
+            verify_offsets: true,
+            reference_strategy: ReferenceStrategy::default(),
+            reference_layout: ReferenceLayout::default(),
+            slate_prior: false,
+            ltc_channel: None,
+            incremental: false,
+            auto_sync_groups: false,
+            session_gap_s: None,
+            analysis_cache_dir: None,
+            analysis_cache_max_mb: 4096,
         }
     }
 }
 
 impl SyncConfig {
+    /// Weighting for `track`'s analysis audio: its own, else its role's
+    /// profile. `None` when flat.
+    pub fn band_weighting(&self, track: &Track) -> Option<BandWeighting> {
+        track
+            .band_weighting
+            .or_else(|| self.device_band_weighting.get(&track.role()).copied())
+            .filter(|w| !w.is_flat())
+    }
+
     pub fn is_lossy(&self) -> bool {
         matches!(self.export_format.to_lowercase().as_str(), "mp3")
     }
@@ -240,6 +1474,109 @@ impl SyncConfig {
     }
 }
 
+// ---------------------------------------------------------------------------
+//  Comments
+// ---------------------------------------------------------------------------
+
+/// Where a comment is attached in the project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommentAnchor {
+    /// A position on the shared timeline, in seconds.
+    Timeline { position_s: f64 },
+    /// A clip (by file path), optionally at a position inside the clip.
+    Clip {
+        file_path: String,
+        position_s: Option<f64>,
+    },
+}
+
+/// A threaded project comment, synced with collaborators via the cloud.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: String,
+    /// Parent comment id for replies (None for a thread root).
+    pub parent_id: Option<String>,
+    pub author: String,
+    pub body: String,
+    pub anchor: CommentAnchor,
+    /// RFC 3339 timestamps — `updated_at` decides which copy wins on merge.
+    pub created_at: String,
+    pub updated_at: String,
+    pub resolved: bool,
+    /// Deleted, kept as a tombstone so the deletion wins the next sync
+    /// instead of the comment coming back from the other side.
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+impl Comment {
+    /// Start a new thread at the given anchor.
+    pub fn new(author: String, body: String, anchor: CommentAnchor) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            parent_id: None,
+            author,
+            body,
+            anchor,
+            created_at: now.clone(),
+            updated_at: now,
+            resolved: false,
+            deleted: false,
+        }
+    }
+
+    /// Reply to an existing comment — the reply shares the parent's anchor.
+    pub fn reply_to(parent: &Comment, author: String, body: String) -> Self {
+        let mut reply = Self::new(author, body, parent.anchor.clone());
+        reply.parent_id = Some(parent.id.clone());
+        reply
+    }
+
+    /// Mark the comment resolved/unresolved and bump `updated_at`.
+    pub fn set_resolved(&mut self, resolved: bool) {
+        self.resolved = resolved;
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+
+    /// Turn the comment into a tombstone: its text is dropped and
+    /// `updated_at` bumped so the deletion carries through merges.
+    pub fn delete(&mut self) {
+        self.body.clear();
+        self.deleted = true;
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Unavailable media
+// ---------------------------------------------------------------------------
+
+/// A clip whose source file could not be read — reported by the pre-flight
+/// check, or skipped during export instead of aborting the whole job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnavailableClip {
+    pub track_name: String,
+    pub clip_name: String,
+    pub file_path: String,
+    pub reason: String,
+}
+
+/// Result of decoding an exported file and comparing it with the buffer
+/// it was written from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportVerification {
+    pub file_path: String,
+    pub ok: bool,
+    pub expected_samples: usize,
+    pub decoded_samples: usize,
+    pub expected_rms: f64,
+    pub decoded_rms: f64,
+    /// Why verification failed; empty when `ok`.
+    pub message: String,
+}
+
 // ---------------------------------------------------------------------------
 //  Errors
 // ---------------------------------------------------------------------------
@@ -248,6 +1585,22 @@ impl SyncConfig {
 #[error("Operation cancelled: {0}")]
 pub struct CancelledError(pub String);
 
+// ---------------------------------------------------------------------------
+//  Test signals
+// ---------------------------------------------------------------------------
+
+/// `len` samples of white noise in [-0.5, 0.5) from a fixed LCG, so a test
+/// gets the same signal for the same `seed` on every run.
+#[cfg(test)]
+pub(crate) fn noise(mut seed: u32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -334,4 +1687,61 @@ mod tests {
         assert!((track.total_duration_s() - 15.0).abs() < 1e-6);
         assert_eq!(track.clip_count(), 2);
     }
+
+    #[test]
+    fn test_clip_timings_sum_and_bottleneck() {
+        let a = ClipTimings { decode_s: 4.0, resample_s: 1.0, correlation_s: 0.5 };
+        let b = ClipTimings { decode_s: 2.0, resample_s: 0.5, correlation_s: 1.5 };
+        let total = ClipTimings::sum([&a, &b]);
+        assert!((total.total_s() - 9.5).abs() < 1e-9);
+        assert_eq!(total.bottleneck(), "decode");
+        assert_eq!(b.bottleneck(), "decode");
+        assert_eq!(ClipTimings { correlation_s: 1.0, ..Default::default() }.bottleneck(), "correlation");
+    }
+
+    #[test]
+    fn test_sync_warning_roundtrip_and_legacy_strings() {
+        let w = SyncWarning::new(WarningCode::LowConfidence, WarningSeverity::Error, "Low")
+            .for_clip("/a.wav");
+        let json = serde_json::to_string(&w).unwrap();
+        assert!(json.contains("\"code\":\"low_confidence\""));
+        assert!(json.contains("\"severity\":\"error\""));
+        assert_eq!(serde_json::from_str::<SyncWarning>(&json).unwrap(), w);
+
+        let legacy: Vec<SyncWarning> = serde_json::from_str(r#"["old warning"]"#).unwrap();
+        assert_eq!(legacy[0].code, WarningCode::Other);
+        assert_eq!(legacy[0].message, "old warning");
+        assert!(WarningSeverity::Error > WarningSeverity::Warning);
+    }
+
+    #[test]
+    fn test_track_role_inference_and_override() {
+        assert_eq!(TrackRole::infer("Zoom H6", false), TrackRole::Dialog);
+        assert_eq!(TrackRole::infer("Sony FX3", true), TrackRole::Camera);
+        assert_eq!(TrackRole::infer("GoPro_Hero", true), TrackRole::Camera);
+        assert_eq!(TrackRole::infer("Band Playback", false), TrackRole::Music);
+        assert_eq!(TrackRole::infer("Foley 2", false), TrackRole::Fx);
+        assert_eq!(TrackRole::infer("DSC", true), TrackRole::Camera);
+        assert_eq!(TrackRole::infer("Track 3", false), TrackRole::Dialog);
+
+        let mut track = Track::new("Zoom H6".into());
+        assert_eq!(track.role(), TrackRole::Dialog);
+        track.role = Some(TrackRole::Music);
+        assert_eq!(track.role(), TrackRole::Music);
+        assert_eq!("fx".parse::<TrackRole>().unwrap(), TrackRole::Fx);
+        assert!("drums".parse::<TrackRole>().is_err());
+    }
+
+    #[test]
+    fn test_comment_reply_inherits_anchor() {
+        let root = Comment::new(
+            "editor".into(),
+            "Is cam B late here?".into(),
+            CommentAnchor::Timeline { position_s: 42.5 },
+        );
+        let reply = Comment::reply_to(&root, "mixer".into(), "Fixed.".into());
+        assert_eq!(reply.parent_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(reply.anchor, root.anchor);
+        assert_ne!(reply.id, root.id);
+    }
 }
@@ -0,0 +1,224 @@
+//! Null test — align two recordings, subtract one from the other and
+//! measure what is left.
+//!
+//! Backs `audiosync null-test`. A re-export, a drift-corrected render or a
+//! second delivery of the same mix should cancel to (near) silence against
+//! the original; the residual level says how closely they match, and the
+//! difference file shows where they don't. Both files are compared as mono
+//! downmixes at the higher of their sample rates.
+
+use anyhow::{bail, Result};
+use log::info;
+use serde::Serialize;
+
+use crate::audio_io::{load_clip, read_clip_full_res};
+use crate::engine::match_delay;
+use crate::models::{CancelToken, ANALYSIS_SR};
+
+/// Levels below this are reported as digital silence.
+const SILENCE_DB: f64 = -200.0;
+
+/// How far (in full-rate samples) around the 8 kHz match the alignment is
+/// refined, and how much audio the refinement compares.
+const REFINE_RADIUS: i64 = 16;
+const REFINE_WINDOW_S: f64 = 10.0;
+
+/// Where `b` starts relative to `a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NullOffset {
+    /// Found by correlation, refined to the sample at full rate.
+    Auto,
+    /// Seconds; positive when `b` starts later than `a`.
+    Fixed(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NullTestReport {
+    pub a: String,
+    pub b: String,
+    pub sample_rate: u32,
+    /// Start of `b` relative to `a`.
+    pub offset_s: f64,
+    pub offset_samples: i64,
+    /// Correlation score of the automatic alignment; `None` for a fixed offset.
+    pub match_score: Option<f64>,
+    /// Length of the stretch both files cover, which is all that is compared.
+    pub overlap_s: f64,
+    /// RMS of `a` over the overlap, in dBFS.
+    pub rms_a_db: f64,
+    /// RMS of `a - b` over the overlap, in dBFS.
+    pub residual_rms_db: f64,
+    pub residual_peak_db: f64,
+    /// Residual relative to `a`; the more negative, the better the null.
+    pub null_depth_db: f64,
+    /// The difference waveform, when one was written.
+    pub difference_path: Option<String>,
+}
+
+/// Align `b` against `a`, subtract and report the residual. Writes the
+/// difference (32-bit float WAV, overlap only) to `difference_path` if given.
+pub fn null_test(
+    a: &str,
+    b: &str,
+    offset: NullOffset,
+    difference_path: Option<&str>,
+    cancel: &Option<CancelToken>,
+) -> Result<NullTestReport> {
+    let clip_a = load_clip(a, cancel)?;
+    let clip_b = load_clip(b, cancel)?;
+    let sample_rate = clip_a.original_sr.max(clip_b.original_sr);
+    let full_a = read_clip_full_res(&clip_a, sample_rate, cancel)?;
+    let full_b = read_clip_full_res(&clip_b, sample_rate, cancel)?;
+
+    let (offset_samples, match_score) = match offset {
+        NullOffset::Fixed(s) => ((s * sample_rate as f64).round() as i64, None),
+        NullOffset::Auto => {
+            let m = match_delay(&clip_a.samples, &clip_b.samples, ANALYSIS_SR, None);
+            let scaled = m.delay_samples * sample_rate as i64 / ANALYSIS_SR as i64;
+            (refine_offset(&full_a, &full_b, scaled, sample_rate), Some(m.score))
+        }
+    };
+
+    let (start_a, start_b) = if offset_samples >= 0 {
+        (offset_samples as usize, 0)
+    } else {
+        (0, offset_samples.unsigned_abs() as usize)
+    };
+    let overlap = full_a
+        .len()
+        .saturating_sub(start_a)
+        .min(full_b.len().saturating_sub(start_b));
+    if overlap == 0 {
+        bail!("'{}' and '{}' don't overlap at an offset of {} samples", a, b, offset_samples);
+    }
+    let a_part = &full_a[start_a..start_a + overlap];
+    let difference: Vec<f64> = a_part
+        .iter()
+        .zip(&full_b[start_b..start_b + overlap])
+        .map(|(x, y)| x - y)
+        .collect();
+
+    let rms_a_db = to_db(rms(a_part));
+    let residual_rms_db = to_db(rms(&difference));
+    let residual_peak_db = to_db(difference.iter().fold(0.0f64, |m, d| m.max(d.abs())));
+
+    if let Some(path) = difference_path {
+        write_difference(path, &difference, sample_rate)?;
+        info!("Difference written: {}", path);
+    }
+
+    Ok(NullTestReport {
+        a: clip_a.file_path,
+        b: clip_b.file_path,
+        sample_rate,
+        offset_s: offset_samples as f64 / sample_rate as f64,
+        offset_samples,
+        match_score,
+        overlap_s: overlap as f64 / sample_rate as f64,
+        rms_a_db,
+        residual_rms_db,
+        residual_peak_db,
+        null_depth_db: residual_rms_db - rms_a_db,
+        difference_path: difference_path.map(str::to_string),
+    })
+}
+
+/// The lag within [`REFINE_RADIUS`] of `around` with the smallest residual
+/// energy over the first [`REFINE_WINDOW_S`] of overlap.
+fn refine_offset(a: &[f64], b: &[f64], around: i64, sample_rate: u32) -> i64 {
+    let window = (REFINE_WINDOW_S * sample_rate as f64) as usize;
+    let residual = |lag: i64| -> Option<f64> {
+        let (start_a, start_b) =
+            if lag >= 0 { (lag as usize, 0) } else { (0, lag.unsigned_abs() as usize) };
+        let n = a
+            .len()
+            .saturating_sub(start_a)
+            .min(b.len().saturating_sub(start_b))
+            .min(window);
+        (n > 0).then(|| {
+            let sum: f64 = (0..n).map(|i| (a[start_a + i] - b[start_b + i]).powi(2)).sum();
+            sum / n as f64
+        })
+    };
+    (around - REFINE_RADIUS..=around + REFINE_RADIUS)
+        .filter_map(|lag| residual(lag).map(|r| (lag, r)))
+        .min_by(|x, y| x.1.total_cmp(&y.1))
+        .map_or(around, |(lag, _)| lag)
+}
+
+fn write_difference(path: &str, difference: &[f64], sample_rate: u32) -> Result<()> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &d in difference {
+        writer.write_sample(d as f32)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+fn rms(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64).sqrt()
+}
+
+fn to_db(level: f64) -> f64 {
+    if level > 0.0 { (20.0 * level.log10()).max(SILENCE_DB) } else { SILENCE_DB }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::noise;
+
+    fn write_wav(path: &std::path::Path, samples: &[f32], sample_rate: u32) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &s in samples {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_null_test_cancels_shifted_copy() {
+        let dir = std::env::temp_dir().join(format!("audiosync_null_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let original: Vec<f32> = noise(7, 144000).into_iter().map(|s| 0.5 * s).collect();
+        let (a, b) = (dir.join("a.wav"), dir.join("b.wav"));
+        write_wav(&a, &original, 48000);
+        // The delivery starts 0.5 s in and has a little hiss added.
+        let delivery: Vec<f32> = original[24000..]
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s + 1e-3 * ((i % 2) as f32 - 0.5))
+            .collect();
+        write_wav(&b, &delivery, 48000);
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+        let diff = dir.join("diff.wav");
+
+        let report = null_test(a, b, NullOffset::Auto, diff.to_str(), &None).unwrap();
+        assert_eq!((report.sample_rate, report.offset_samples), (48000, 24000));
+        assert!(report.null_depth_db < -40.0, "{:?}", report);
+        assert!(diff.exists());
+
+        // A wrong offset doesn't null.
+        let report = null_test(a, b, NullOffset::Fixed(0.25), None, &None).unwrap();
+        assert!(report.null_depth_db > -10.0, "{:?}", report);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,441 @@
+//! Preview playback — mixes the aligned tracks for auditioning in the app.
+//!
+//! Preview runs off the 8 kHz analysis copies already in memory, so it is
+//! available as soon as analysis finishes (no full-resolution re-read). The
+//! `Player` is device-agnostic: the host pulls interleaved frames from
+//! `Player::render` at whatever rate the output device runs.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Track, ANALYSIS_SR};
+
+/// Length of the audio grain played by a scrub while paused.
+pub const SCRUB_GRAIN_S: f64 = 0.08;
+
+/// Allowed playback-rate range.
+pub const MIN_PLAYBACK_RATE: f64 = 0.25;
+pub const MAX_PLAYBACK_RATE: f64 = 4.0;
+
+/// Transport snapshot sent to the UI to drive the playhead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackStatus {
+    pub position_s: f64,
+    pub duration_s: f64,
+    pub playing: bool,
+    pub rate: f64,
+    /// Active loop region (start_s, end_s).
+    pub loop_region: Option<(f64, f64)>,
+    /// Track pair in phase-check mode (second one polarity-inverted).
+    pub phase_check: Option<(usize, usize)>,
+}
+
+/// Per-track preview audio laid out on the shared timeline.
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackTimeline {
+    pub sample_rate: u32,
+    pub track_names: Vec<String>,
+    pub tracks: Vec<Vec<f32>>,
+}
+
+impl PlaybackTimeline {
+    /// Place every placed clip's analysis samples at its timeline offset,
+    /// with its gain and polarity as stitching applies them.
+    pub fn from_tracks(tracks: &[Track]) -> Self {
+        let len = tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .filter(|c| c.on_timeline())
+            .map(|c| c.end_samples().max(0) as usize)
+            .max()
+            .unwrap_or(0);
+
+        let mut buffers = Vec::with_capacity(tracks.len());
+        for track in tracks {
+            let mut buf = vec![0.0f32; len];
+            for clip in track.clips.iter().filter(|c| c.on_timeline()) {
+                let start = clip.timeline_offset_samples.max(0) as usize;
+                let skip = (-clip.timeline_offset_samples).max(0) as usize;
+                let gain = clip.gain_factor() as f32;
+                for (j, &s) in clip.samples.iter().skip(skip).enumerate() {
+                    if start + j >= len {
+                        break;
+                    }
+                    buf[start + j] = s * gain;
+                }
+            }
+            buffers.push(buf);
+        }
+
+        Self {
+            sample_rate: ANALYSIS_SR,
+            track_names: tracks.iter().map(|t| t.name.clone()).collect(),
+            tracks: buffers,
+        }
+    }
+
+    pub fn len_samples(&self) -> usize {
+        self.tracks.first().map_or(0, |t| t.len())
+    }
+
+    pub fn duration_s(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.len_samples() as f64 / self.sample_rate as f64
+    }
+}
+
+/// Transport + mixer for preview playback.
+#[derive(Debug, Clone)]
+pub struct Player {
+    timeline: PlaybackTimeline,
+    /// Read position in timeline samples (fractional for resampling).
+    position: f64,
+    playing: bool,
+    muted: Vec<bool>,
+    rate: f64,
+    /// Loop region in timeline samples (start, end).
+    loop_region: Option<(f64, f64)>,
+    /// Samples left in the current scrub grain (pauses when exhausted).
+    scrub_remaining: Option<f64>,
+    /// Phase-check pair and the level-matching gain applied to the second track.
+    phase_check: Option<(usize, usize, f32)>,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            timeline: PlaybackTimeline::default(),
+            position: 0.0,
+            playing: false,
+            muted: Vec::new(),
+            rate: 1.0,
+            loop_region: None,
+            scrub_remaining: None,
+            phase_check: None,
+        }
+    }
+}
+
+impl Player {
+    /// Replace the loaded timeline, keeping the playhead where possible.
+    pub fn load(&mut self, timeline: PlaybackTimeline) {
+        self.muted = vec![false; timeline.tracks.len()];
+        self.position = self.position.min(timeline.len_samples() as f64);
+        self.timeline = timeline;
+        if let Some((a, b, _)) = self.phase_check {
+            self.set_phase_check(Some((a, b)));
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.timeline.len_samples() > 0
+    }
+
+    pub fn play(&mut self) {
+        if self.position >= self.timeline.len_samples() as f64 {
+            self.position = self.loop_region.map_or(0.0, |(start, _)| start);
+        }
+        self.scrub_remaining = None;
+        self.playing = self.is_loaded();
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+        self.scrub_remaining = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn seek(&mut self, position_s: f64) {
+        let max = self.timeline.len_samples() as f64;
+        self.position = (position_s * self.timeline.sample_rate as f64).clamp(0.0, max);
+    }
+
+    /// Move the playhead; while paused, also audition a short grain there.
+    pub fn scrub(&mut self, position_s: f64) {
+        self.seek(position_s);
+        if !self.playing && self.is_loaded() {
+            self.playing = true;
+            self.scrub_remaining = Some(SCRUB_GRAIN_S * self.timeline.sample_rate as f64);
+        }
+    }
+
+    /// Loop playback between `start_s` and `end_s`; `None` clears the loop.
+    pub fn set_loop(&mut self, region: Option<(f64, f64)>) {
+        let sr = self.timeline.sample_rate as f64;
+        let max = self.timeline.len_samples() as f64;
+        self.loop_region = region
+            .map(|(a, b)| ((a.min(b) * sr).clamp(0.0, max), (a.max(b) * sr).clamp(0.0, max)))
+            .filter(|(a, b)| b > a);
+        if let Some((start, end)) = self.loop_region
+            && (self.position < start || self.position >= end)
+        {
+            self.position = start;
+        }
+    }
+
+    /// Set the playback rate (clamped to 0.25×–4×). Pitch follows speed.
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE);
+    }
+
+    /// Solo two tracks with the second polarity-inverted; `None` returns to
+    /// the normal mix.
+    ///
+    /// When the pair is perfectly aligned their shared content cancels, so a
+    /// good sync sounds thin or near-silent. The second track is level-matched
+    /// to the first over their overlap to make the null as deep as possible.
+    pub fn set_phase_check(&mut self, pair: Option<(usize, usize)>) {
+        let n = self.timeline.tracks.len();
+        self.phase_check = pair
+            .filter(|&(a, b)| a != b && a < n && b < n)
+            .map(|(a, b)| (a, b, self.level_match(a, b)));
+    }
+
+    /// Gain that brings track `b` to the RMS of track `a` where both have audio.
+    fn level_match(&self, a: usize, b: usize) -> f32 {
+        let (mut sum_a, mut sum_b) = (0.0f64, 0.0f64);
+        for (&x, &y) in self.timeline.tracks[a].iter().zip(self.timeline.tracks[b].iter()) {
+            if x != 0.0 && y != 0.0 {
+                sum_a += (x as f64).powi(2);
+                sum_b += (y as f64).powi(2);
+            }
+        }
+        if sum_a <= 0.0 || sum_b <= 0.0 {
+            return 1.0;
+        }
+        (sum_a / sum_b).sqrt() as f32
+    }
+
+    pub fn status(&self) -> PlaybackStatus {
+        let sr = self.timeline.sample_rate.max(1) as f64;
+        PlaybackStatus {
+            position_s: self.position_s(),
+            duration_s: self.duration_s(),
+            playing: self.playing,
+            rate: self.rate,
+            loop_region: self.loop_region.map(|(a, b)| (a / sr, b / sr)),
+            phase_check: self.phase_check.map(|(a, b, _)| (a, b)),
+        }
+    }
+
+    pub fn position_s(&self) -> f64 {
+        if self.timeline.sample_rate == 0 {
+            return 0.0;
+        }
+        self.position / self.timeline.sample_rate as f64
+    }
+
+    pub fn duration_s(&self) -> f64 {
+        self.timeline.duration_s()
+    }
+
+    pub fn set_muted(&mut self, track_index: usize, muted: bool) {
+        if let Some(m) = self.muted.get_mut(track_index) {
+            *m = muted;
+        }
+    }
+
+    /// Fill an interleaved output buffer at `out_sr`, advancing the playhead.
+    ///
+    /// The mono mix is duplicated to every channel; silence when paused.
+    pub fn render(&mut self, out: &mut [f32], channels: usize, out_sr: u32) {
+        out.fill(0.0);
+        if !self.playing || channels == 0 || out_sr == 0 {
+            return;
+        }
+
+        let len = self.timeline.len_samples();
+        let step = self.rate * self.timeline.sample_rate as f64 / out_sr as f64;
+        // (track, gain) pairs; phase-check solos its pair with B inverted.
+        let active: Vec<(usize, f32)> = match self.phase_check {
+            Some((a, b, match_gain)) => vec![(a, 0.5), (b, -0.5 * match_gain)],
+            None => {
+                let unmuted: Vec<usize> = (0..self.timeline.tracks.len())
+                    .filter(|&i| !self.muted.get(i).copied().unwrap_or(false))
+                    .collect();
+                let gain = if unmuted.is_empty() { 0.0 } else { 1.0 / unmuted.len() as f32 };
+                unmuted.into_iter().map(|i| (i, gain)).collect()
+            }
+        };
+
+        for frame in out.chunks_mut(channels) {
+            if let Some((start, end)) = self.loop_region
+                && self.position >= end
+            {
+                self.position = start + (self.position - end);
+            }
+            if self.position >= len as f64 {
+                self.playing = false;
+                break;
+            }
+            if let Some(remaining) = self.scrub_remaining.as_mut() {
+                if *remaining <= 0.0 {
+                    self.playing = false;
+                    self.scrub_remaining = None;
+                    break;
+                }
+                *remaining -= step;
+            }
+            let idx = self.position as usize;
+            let frac = (self.position - idx as f64) as f32;
+            let mut mix = 0.0f32;
+            for &(ti, gain) in &active {
+                let buf = &self.timeline.tracks[ti];
+                let a = buf[idx];
+                let b = buf.get(idx + 1).copied().unwrap_or(a);
+                mix += (a + (b - a) * frac) * gain;
+            }
+            frame.fill(mix.clamp(-1.0, 1.0));
+            self.position += step;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Clip;
+
+    fn track_with(name: &str, offset: i64, samples: Vec<f32>) -> Track {
+        let mut t = Track::new(name.into());
+        let mut c = Clip::new(format!("{}.wav", name), format!("{}.wav", name), 48000, 1);
+        c.timeline_offset_samples = offset;
+        c.samples = samples;
+        t.clips.push(c);
+        t
+    }
+
+    #[test]
+    fn test_timeline_places_clips_at_offsets() {
+        let tracks = vec![
+            track_with("A", 0, vec![1.0; 4]),
+            track_with("B", 2, vec![0.5; 4]),
+        ];
+        let tl = PlaybackTimeline::from_tracks(&tracks);
+        assert_eq!(tl.len_samples(), 6);
+        assert_eq!(tl.tracks[1], vec![0.0, 0.0, 0.5, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_render_mixes_and_stops_at_end() {
+        let tracks = vec![
+            track_with("A", 0, vec![0.4; 8000]),
+            track_with("B", 0, vec![0.2; 8000]),
+        ];
+        let mut player = Player::default();
+        player.load(PlaybackTimeline::from_tracks(&tracks));
+
+        let mut out = vec![1.0f32; 8];
+        player.render(&mut out, 2, 8000);
+        assert!(out.iter().all(|&s| s == 0.0), "paused player renders silence");
+
+        player.play();
+        player.render(&mut out, 2, 8000);
+        assert!(out.iter().all(|&s| (s - 0.3).abs() < 1e-6));
+        assert!((player.position_s() - 4.0 / 8000.0).abs() < 1e-9);
+
+        // Upsampled output advances the playhead more slowly.
+        player.seek(0.0);
+        player.render(&mut out, 1, 48000);
+        assert!((player.position_s() - 8.0 / 48000.0).abs() < 1e-9);
+
+        player.seek(1.0);
+        player.play();
+        assert!(player.is_playing());
+        player.seek(player.duration_s());
+        player.render(&mut out, 1, 8000);
+        assert!(!player.is_playing());
+    }
+
+    #[test]
+    fn test_loop_rate_and_scrub() {
+        let tracks = vec![track_with("A", 0, (0..8000).map(|i| i as f32 / 8000.0).collect())];
+        let mut player = Player::default();
+        player.load(PlaybackTimeline::from_tracks(&tracks));
+
+        // Loop 0.1 s – 0.2 s: playhead jumps into the region and wraps.
+        player.set_loop(Some((0.2, 0.1)));
+        assert_eq!(player.status().loop_region, Some((0.1, 0.2)));
+        assert!((player.position_s() - 0.1).abs() < 1e-9);
+        player.play();
+        let mut out = vec![0.0f32; 1200];
+        player.render(&mut out, 1, 8000);
+        assert!(player.is_playing());
+        assert!(player.position_s() >= 0.1 && player.position_s() < 0.2);
+
+        // Double rate advances twice as fast.
+        player.set_loop(None);
+        player.seek(0.0);
+        player.set_rate(2.0);
+        player.render(&mut out[..100], 1, 8000);
+        assert!((player.position_s() - 200.0 / 8000.0).abs() < 1e-9);
+        player.set_rate(100.0);
+        assert_eq!(player.status().rate, MAX_PLAYBACK_RATE);
+
+        // Scrub while paused plays one short grain, then pauses again.
+        player.pause();
+        player.set_rate(1.0);
+        player.scrub(0.5);
+        assert!(player.is_playing());
+        player.render(&mut out, 1, 8000);
+        assert!(!player.is_playing());
+        let grain = SCRUB_GRAIN_S * 8000.0;
+        assert!((player.position_s() * 8000.0 - (4000.0 + grain)).abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_phase_check_nulls_aligned_tracks() {
+        let signal: Vec<f32> = (0..800).map(|i| ((i * 7919) % 200) as f32 / 400.0 - 0.25).collect();
+        let quiet: Vec<f32> = signal.iter().map(|s| s * 0.5).collect();
+        let tracks = vec![
+            track_with("A", 0, signal.clone()),
+            track_with("B", 0, quiet),
+            track_with("C", 0, vec![0.3; 800]),
+        ];
+        let mut player = Player::default();
+        player.load(PlaybackTimeline::from_tracks(&tracks));
+
+        player.set_phase_check(Some((0, 1)));
+        assert_eq!(player.status().phase_check, Some((0, 1)));
+        player.play();
+        let mut out = vec![0.0f32; 400];
+        player.render(&mut out, 1, 8000);
+        assert!(out.iter().all(|s| s.abs() < 1e-5), "aligned pair should cancel");
+
+        // Misaligned by a few samples: no null.
+        let tracks = vec![track_with("A", 0, signal.clone()), track_with("B", 3, signal)];
+        player.load(PlaybackTimeline::from_tracks(&tracks));
+        player.seek(0.0);
+        player.play();
+        player.render(&mut out, 1, 8000);
+        assert!(out.iter().any(|s| s.abs() > 0.01));
+
+        player.set_phase_check(Some((1, 1)));
+        assert_eq!(player.status().phase_check, None);
+    }
+
+    #[test]
+    fn test_phase_check_flips_inverted_clip_back() {
+        let signal: Vec<f32> = (0..800).map(|i| ((i * 7919) % 200) as f32 / 400.0 - 0.25).collect();
+        // B recorded with its polarity flipped and 6 dB hotter; analysis
+        // found both, and the timeline undoes them.
+        let mut inverted = track_with("B", 0, signal.iter().map(|s| -2.0 * s).collect());
+        inverted.clips[0].polarity_inverted = true;
+        inverted.clips[0].gain_db = -20.0 * 2f64.log10();
+        let tracks = vec![track_with("A", 0, signal.clone()), inverted];
+        let timeline = PlaybackTimeline::from_tracks(&tracks);
+        assert!(timeline.tracks[1].iter().zip(&signal).all(|(b, a)| (b - a).abs() < 1e-5));
+
+        let mut player = Player::default();
+        player.load(timeline);
+        player.set_phase_check(Some((0, 1)));
+        player.play();
+        let mut out = vec![0.0f32; 400];
+        player.render(&mut out, 1, 8000);
+        assert!(out.iter().all(|s| s.abs() < 1e-5), "inverted clip should still cancel");
+    }
+}
@@ -3,12 +3,17 @@
 //! Format: JSON object with tracks, config, result, and metadata.
 //! Compatible with the Python version's project file format.
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use hmac::{Hmac, Mac};
 use log::info;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
 use std::path::Path;
 
-use crate::models::{SyncConfig, SyncResult, Track};
+use crate::models::{Clip, Comment, DriftSegment, SyncConfig, SyncResult, Track};
 
 const PROJECT_VERSION: u32 = 2;
 
@@ -32,6 +37,10 @@ pub struct ProjectFile {
 
     /// Analysis result (None if not yet analyzed).
     pub result: Option<SyncResult>,
+
+    /// Collaborator comments attached to the timeline or clips.
+    #[serde(default)]
+    pub comments: Vec<Comment>,
 }
 
 impl ProjectFile {
@@ -44,6 +53,7 @@ impl ProjectFile {
             tracks,
             config,
             result,
+            comments: Vec::new(),
         }
     }
 }
@@ -54,6 +64,7 @@ pub fn save_project(
     tracks: &[Track],
     config: &SyncConfig,
     result: Option<&SyncResult>,
+    comments: &[Comment],
 ) -> Result<()> {
     let project = ProjectFile {
         version: PROJECT_VERSION,
@@ -62,6 +73,7 @@ pub fn save_project(
         tracks: tracks.to_vec(),
         config: config.clone(),
         result: result.cloned(),
+        comments: comments.to_vec(),
     };
 
     let json = serde_json::to_string_pretty(&project)
@@ -83,8 +95,8 @@ pub fn load_project(path: &str) -> Result<ProjectFile> {
     let json = std::fs::read_to_string(path)
         .with_context(|| format!("Cannot read project file: {}", path))?;
 
-    let project: ProjectFile = serde_json::from_str(&json)
-        .with_context(|| format!("Failed to parse project file: {}", path))?;
+    let project =
+        parse_project(&json).with_context(|| format!("Failed to parse project file: {}", path))?;
 
     if project.version > PROJECT_VERSION {
         anyhow::bail!(
@@ -104,6 +116,274 @@ pub fn load_project(path: &str) -> Result<ProjectFile> {
     Ok(project)
 }
 
+// ---------------------------------------------------------------------------
+//  Validation
+// ---------------------------------------------------------------------------
+
+/// Most problems listed when a project fails validation; the rest are counted.
+const MAX_REPORTED_PROBLEMS: usize = 20;
+
+/// Parse project JSON, reporting every problem by field path
+/// (`tracks[2].clips[0].duration_s must be >= 0`) instead of a serde
+/// line/column. Unknown fields are ignored: users hand-edit these files.
+pub fn parse_project(json: &str) -> Result<ProjectFile> {
+    let value: Value = serde_json::from_str(json).context("Not valid JSON")?;
+    let mut problems = structure_problems(&value);
+    if problems.is_empty()
+        && let Some(problem) = decode_problem::<ProjectFile>(&value, "")
+    {
+        problems.push(problem);
+    }
+    if problems.is_empty() {
+        let project: ProjectFile = serde_json::from_value(value)?;
+        problems = value_problems(&project);
+        if problems.is_empty() {
+            return Ok(project);
+        }
+    }
+
+    let mut report = problems.iter().take(MAX_REPORTED_PROBLEMS).cloned().collect::<Vec<_>>();
+    if problems.len() > MAX_REPORTED_PROBLEMS {
+        report.push(format!("... and {} more", problems.len() - MAX_REPORTED_PROBLEMS));
+    }
+    Err(anyhow!("Invalid project:\n  {}", report.join("\n  ")))
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    if path.is_empty() { field.to_string() } else { format!("{}.{}", path, field) }
+}
+
+/// Why `value` doesn't decode as `T`, naming the offending field of an
+/// object: the one whose removal makes the error go away (or turn into
+/// its own "missing field").
+fn decode_problem<T: DeserializeOwned>(value: &Value, path: &str) -> Option<String> {
+    let message = T::deserialize(value).err()?.to_string();
+    let at = |field: &str| field_path(path, field);
+    if let Some(field) = message.strip_prefix("missing field `").and_then(|m| m.strip_suffix('`')) {
+        return Some(format!("{} is missing", at(field)));
+    }
+    if let Some(object) = value.as_object() {
+        for key in object.keys() {
+            let mut without = object.clone();
+            without.remove(key);
+            let culprit = match T::deserialize(&Value::Object(without)) {
+                Ok(_) => true,
+                Err(e) => e.to_string() == format!("missing field `{}`", key),
+            };
+            if culprit {
+                return Some(format!("{}: {}", at(key), message));
+            }
+        }
+    }
+    let whole = if path.is_empty() { "project" } else { path };
+    Some(format!("{}: {}", whole, message))
+}
+
+/// Decoding problems inside the track, clip, config, result and comment
+/// lists, each reported at its own element.
+fn structure_problems(value: &Value) -> Vec<String> {
+    let Some(project) = value.as_object() else {
+        return vec!["project must be a JSON object".to_string()];
+    };
+    let mut problems = Vec::new();
+    if let Some(tracks) = project.get("tracks").and_then(Value::as_array) {
+        for (ti, track) in tracks.iter().enumerate() {
+            let path = format!("tracks[{}]", ti);
+            let mut track = track.clone();
+            if let Some(clips) = track.get_mut("clips").and_then(Value::as_array_mut) {
+                for (ci, clip) in clips.iter().enumerate() {
+                    let clip_path = format!("{}.clips[{}]", path, ci);
+                    problems.extend(decode_problem::<Clip>(clip, &clip_path));
+                }
+                clips.clear();
+            }
+            problems.extend(decode_problem::<Track>(&track, &path));
+        }
+    }
+    if let Some(comments) = project.get("comments").and_then(Value::as_array) {
+        for (i, comment) in comments.iter().enumerate() {
+            problems.extend(decode_problem::<Comment>(comment, &format!("comments[{}]", i)));
+        }
+    }
+    if let Some(config) = project.get("config") {
+        problems.extend(decode_problem::<SyncConfig>(config, "config"));
+    }
+    if let Some(result) = project.get("result") {
+        problems.extend(decode_problem::<Option<SyncResult>>(result, "result"));
+    }
+    problems
+}
+
+/// Values that decode but make no sense (negative durations, zero rates).
+fn value_problems(project: &ProjectFile) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (ti, track) in project.tracks.iter().enumerate() {
+        for (ci, clip) in track.clips.iter().enumerate() {
+            let at = |field: &str| format!("tracks[{}].clips[{}].{}", ti, ci, field);
+            let mut require = |ok: bool, field: &str, rule: &str| {
+                if !ok {
+                    problems.push(format!("{} {}", at(field), rule));
+                }
+            };
+            require(!clip.file_path.is_empty(), "file_path", "must not be empty");
+            require(clip.sample_rate > 0, "sample_rate", "must be > 0");
+            require(clip.original_sr > 0, "original_sr", "must be > 0");
+            require(clip.original_channels > 0, "original_channels", "must be > 0");
+            require(clip.duration_s >= 0.0, "duration_s", "must be >= 0");
+            require(clip.confidence >= 0.0, "confidence", "must be >= 0");
+            require((0.0..=100.0).contains(&clip.match_score), "match_score", "must be 0-100");
+            require((0.0..=1.0).contains(&clip.drift_confidence), "drift_confidence", "must be 0-1");
+            require(clip.trim_in_s >= 0.0, "trim_in_s", "must be >= 0");
+            require(clip.trim_out_s >= 0.0, "trim_out_s", "must be >= 0");
+            require(
+                clip.trim_in_s + clip.trim_out_s <= clip.duration_s,
+                "trim_in_s",
+                "plus trim_out_s must not exceed duration_s",
+            );
+        }
+    }
+    problems
+}
+
+// ---------------------------------------------------------------------------
+//  Result manifests (remote analysis)
+// ---------------------------------------------------------------------------
+
+/// One placed clip in a result manifest, bound to its source media by hash.
+/// Carries every placement and drift field a client applies, so the
+/// signature covers them all.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file_path: String,
+    /// SHA-256 of the original media file (hex).
+    pub sha256: String,
+    pub timeline_offset_samples: i64,
+    pub timeline_offset_s: f64,
+    pub confidence: f64,
+    pub match_score: f64,
+    pub analyzed: bool,
+    pub drift_ppm: f64,
+    pub drift_confidence: f64,
+    pub drift_windows: usize,
+    pub drift_inherited: bool,
+    pub drift_segments: Vec<DriftSegment>,
+    pub unplaced: bool,
+    pub polarity_inverted: bool,
+}
+
+impl ManifestEntry {
+    /// The entry for `clip` as placed, with its media hash.
+    pub fn for_clip(clip: &Clip, sha256: String) -> Self {
+        Self {
+            file_path: clip.file_path.clone(),
+            sha256,
+            timeline_offset_samples: clip.timeline_offset_samples,
+            timeline_offset_s: clip.timeline_offset_s,
+            confidence: clip.confidence,
+            match_score: clip.match_score,
+            analyzed: clip.analyzed,
+            drift_ppm: clip.drift_ppm,
+            drift_confidence: clip.drift_confidence,
+            drift_windows: clip.drift_windows,
+            drift_inherited: clip.drift_inherited,
+            drift_segments: clip.drift_segments.clone(),
+            unplaced: clip.unplaced,
+            polarity_inverted: clip.polarity_inverted,
+        }
+    }
+}
+
+/// Analysis results returned by a remote server, signed so they can only be
+/// applied to the exact media they were computed for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultManifest {
+    pub app_version: String,
+    pub created_at: String,
+    pub entries: Vec<ManifestEntry>,
+    /// Hex HMAC-SHA256 over `created_at` + `entries` (None if the server has no key).
+    pub signature: Option<String>,
+}
+
+impl ResultManifest {
+    /// Build a manifest from analyzed tracks and the client-supplied media hashes.
+    pub fn new(tracks: &[Track], hashes: &HashMap<String, String>) -> Result<Self> {
+        let mut entries = Vec::new();
+        for clip in tracks.iter().flat_map(|t| t.clips.iter()) {
+            let sha256 = hashes
+                .get(&clip.file_path)
+                .ok_or_else(|| anyhow!("No media hash supplied for '{}'", clip.name))?;
+            entries.push(ManifestEntry::for_clip(clip, sha256.clone()));
+        }
+        Ok(Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            entries,
+            signature: None,
+        })
+    }
+
+    fn signed_payload(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(&(&self.created_at, &self.entries))
+            .context("Failed to serialize manifest payload")
+    }
+
+    fn mac(key: &str) -> Result<Hmac<Sha256>> {
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).map_err(|e| anyhow!("Invalid key: {}", e))
+    }
+
+    /// Sign the manifest with a shared secret.
+    pub fn sign(&mut self, key: &str) -> Result<()> {
+        let mut mac = Self::mac(key)?;
+        mac.update(&self.signed_payload()?);
+        self.signature = Some(hex::encode(mac.finalize().into_bytes()));
+        Ok(())
+    }
+
+    /// Look up the entry for a file path.
+    pub fn entry(&self, file_path: &str) -> Option<&ManifestEntry> {
+        self.entries.iter().find(|e| e.file_path == file_path)
+    }
+}
+
+/// Verify a result manifest before applying it to local tracks.
+///
+/// With `key` set, the signature must be present and valid. Every local clip
+/// must have an entry whose hash matches `hashes`, the hashes (keyed by file
+/// path) of the media the request was built from.
+pub fn verify_manifest(
+    manifest: &ResultManifest,
+    tracks: &[Track],
+    hashes: &HashMap<String, String>,
+    key: Option<&str>,
+) -> Result<()> {
+    if let Some(key) = key {
+        let signature = manifest
+            .signature
+            .as_deref()
+            .ok_or_else(|| anyhow!("Result manifest is not signed"))?;
+        let expected = hex::decode(signature).context("Malformed manifest signature")?;
+        let mut mac = ResultManifest::mac(key)?;
+        mac.update(&manifest.signed_payload()?);
+        mac.verify_slice(&expected)
+            .map_err(|_| anyhow!("Result manifest signature does not match"))?;
+    }
+
+    for clip in tracks.iter().flat_map(|t| t.clips.iter()) {
+        let entry = manifest
+            .entry(&clip.file_path)
+            .ok_or_else(|| anyhow!("Result manifest has no entry for '{}'", clip.name))?;
+        if hashes.get(&clip.file_path) != Some(&entry.sha256) {
+            return Err(anyhow!(
+                "'{}' does not match the media the results were computed for",
+                clip.name
+            ));
+        }
+    }
+
+    info!("Result manifest verified ({} entries)", manifest.entries.len());
+    Ok(())
+}
+
 /// Get the default project directory.
 pub fn default_projects_dir() -> std::path::PathBuf {
     if let Some(docs) = dirs::document_dir() {
@@ -118,6 +398,7 @@ pub fn default_projects_dir() -> std::path::PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio_io::hash_file;
 
     #[test]
     fn test_roundtrip() {
@@ -136,4 +417,90 @@ mod tests {
         assert_eq!(loaded.tracks.len(), 1);
         assert_eq!(loaded.tracks[0].name, "Test");
     }
+
+    fn temp_media(contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("audiosync_media_{}.wav", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_manifest_verification() {
+        let path = temp_media(b"original media");
+        let mut track = Track::new("Cam".to_string());
+        track.clips.push(Clip::new(path.clone(), "cam.wav".into(), 48000, 1));
+        let tracks = vec![track];
+
+        let hashes = HashMap::from([(path.clone(), hash_file(&path).unwrap())]);
+        let mut manifest = ResultManifest::new(&tracks, &hashes).unwrap();
+        manifest.sign("secret").unwrap();
+
+        assert!(verify_manifest(&manifest, &tracks, &hashes, Some("secret")).is_ok());
+        assert!(verify_manifest(&manifest, &tracks, &hashes, Some("other")).is_err());
+
+        // Tampered offsets invalidate the signature.
+        let mut tampered = manifest.clone();
+        tampered.entries[0].timeline_offset_samples += 1;
+        assert!(verify_manifest(&tampered, &tracks, &hashes, Some("secret")).is_err());
+
+        // Different media fails the hash check.
+        std::fs::write(&path, b"replaced media").unwrap();
+        let replaced = HashMap::from([(path.clone(), hash_file(&path).unwrap())]);
+        assert!(verify_manifest(&manifest, &tracks, &replaced, None).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_load_without_comments_field() {
+        let mut value = serde_json::to_value(ProjectFile::new(
+            vec![Track::new("Test".to_string())],
+            SyncConfig::default(),
+            None,
+        ))
+        .unwrap();
+        value.as_object_mut().unwrap().remove("comments");
+
+        let loaded: ProjectFile = serde_json::from_value(value).unwrap();
+        assert!(loaded.comments.is_empty());
+    }
+
+    #[test]
+    fn test_parse_project_reports_field_paths() {
+        let mut track = Track::new("Zoom".to_string());
+        for name in ["a.wav", "b.wav"] {
+            let mut clip = Clip::new(format!("/m/{}", name), name.into(), 48000, 2);
+            clip.duration_s = 10.0;
+            track.clips.push(clip);
+        }
+        let project = ProjectFile::new(vec![Track::new("Cam".into()), track], SyncConfig::default(), None);
+        let good = serde_json::to_value(&project).unwrap();
+
+        // Unknown fields, as left by hand edits, are ignored.
+        let mut noted = good.clone();
+        noted["notes"] = "shot on day 2".into();
+        noted["tracks"][1]["clips"][0]["todo"] = true.into();
+        assert_eq!(parse_project(&noted.to_string()).unwrap().tracks[1].clips.len(), 2);
+
+        let problems = |value: &Value| parse_project(&value.to_string()).unwrap_err().to_string();
+
+        let mut bad = good.clone();
+        bad["tracks"][1]["clips"][1]["duration_s"] = "ten".into();
+        bad["tracks"][0].as_object_mut().unwrap().remove("name");
+        let report = problems(&bad);
+        assert!(report.contains("tracks[1].clips[1].duration_s: invalid type"), "{}", report);
+        assert!(report.contains("tracks[0].name is missing"), "{}", report);
+
+        let mut bad = good.clone();
+        bad["tracks"][1]["clips"][0]["duration_s"] = (-1.0).into();
+        bad["tracks"][1]["clips"][1]["original_channels"] = 0.into();
+        let report = problems(&bad);
+        assert!(report.contains("tracks[1].clips[0].duration_s must be >= 0"), "{}", report);
+        assert!(report.contains("tracks[1].clips[1].original_channels must be > 0"), "{}", report);
+
+        let mut bad = good.clone();
+        bad.as_object_mut().unwrap().remove("version");
+        assert!(problems(&bad).contains("version is missing"));
+        assert!(problems(&Value::Array(Vec::new())).contains("must be a JSON object"));
+        assert!(parse_project("{\"version\": 2,").unwrap_err().to_string().contains("Not valid JSON"));
+    }
 }
@@ -0,0 +1,225 @@
+//! Clip search across a session — by name, device, recording date and
+//! analysis results — so a front end can filter the clip list and act on
+//! every match at once (exclude all low-confidence clips, say).
+
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Clip, Track};
+
+/// Conditions a clip must meet to match. Unset fields match everything;
+/// set ones must all hold.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipQuery {
+    /// Substring of the clip's name, case-insensitive.
+    pub name: Option<String>,
+    /// Substring of the track (device) name or of the camera make/model the
+    /// file reports, case-insensitive.
+    pub device: Option<String>,
+    /// Recorded at or after this time (Unix seconds). Clips without a
+    /// creation time never match a date bound.
+    pub created_after: Option<f64>,
+    /// Recorded before this time (Unix seconds).
+    pub created_before: Option<f64>,
+    /// Analyzed with a match score (0–100) below this.
+    pub confidence_below: Option<f64>,
+    /// Analyzed with clock drift larger than this many ppm either way, on
+    /// any of its drift segments.
+    pub drift_above_ppm: Option<f64>,
+}
+
+/// A clip matching a query: its position in the session and its id (the
+/// source path, as in warnings and `SyncResult::clip_offsets`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipMatch {
+    pub track_index: usize,
+    pub clip_index: usize,
+    pub clip_id: String,
+}
+
+impl ClipQuery {
+    pub fn matches(&self, track: &Track, clip: &Clip) -> bool {
+        let contains =
+            |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+
+        if let Some(name) = &self.name
+            && !contains(&clip.name, name)
+        {
+            return false;
+        }
+        if let Some(device) = &self.device {
+            let meta = &clip.stream_metadata;
+            let reported = [&meta.camera_make, &meta.camera_model];
+            if !contains(&track.name, device)
+                && !reported
+                    .iter()
+                    .any(|r| r.as_deref().is_some_and(|r| contains(r, device)))
+            {
+                return false;
+            }
+        }
+        if self.created_after.is_some() || self.created_before.is_some() {
+            let Some(t) = clip.creation_time else {
+                return false;
+            };
+            if self.created_after.is_some_and(|after| t < after)
+                || self.created_before.is_some_and(|before| t >= before)
+            {
+                return false;
+            }
+        }
+        if let Some(below) = self.confidence_below
+            && !(clip.analyzed && clip.match_score < below)
+        {
+            return false;
+        }
+        if let Some(above) = self.drift_above_ppm {
+            let drift = clip
+                .drift_segments
+                .iter()
+                .map(|s| s.ppm.abs())
+                .fold(clip.drift_ppm.abs(), f64::max);
+            if !(clip.analyzed && drift > above) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Every clip in `tracks` matching `query`, in track and clip order.
+pub fn query_clips(tracks: &[Track], query: &ClipQuery) -> Vec<ClipMatch> {
+    tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(ti, track)| {
+            track
+                .clips
+                .iter()
+                .enumerate()
+                .filter(|(_, clip)| query.matches(track, clip))
+                .map(move |(ci, clip)| ClipMatch {
+                    track_index: ti,
+                    clip_index: ci,
+                    clip_id: clip.file_path.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Parse a date bound: RFC 3339 (`2024-05-01T14:30:00Z`), or a local
+/// `YYYY-MM-DD` (midnight) or `YYYY-MM-DD HH:MM(:SS)`. Unix seconds.
+pub fn parse_date(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp() as f64);
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(s, fmt).ok())
+    .or_else(|| {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+    })?;
+    Some(chrono::Local.from_local_datetime(&naive).earliest()?.timestamp() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DriftSegment;
+
+    #[test]
+    fn test_query_clips() {
+        let mut zoom = Track::new("Zoom H6".into());
+        let mut cam = Track::new("Cam A".into());
+        for (i, name) in ["take1.wav", "take2.wav", "room.wav"].iter().enumerate() {
+            let mut clip = Clip::new(format!("/z/{}", name), name.to_string(), 48000, 2);
+            clip.creation_time = Some(1_000.0 + i as f64 * 100.0);
+            clip.analyzed = true;
+            clip.match_score = [90.0, 30.0, 60.0][i];
+            zoom.clips.push(clip);
+        }
+        zoom.clips[2].drift_segments = vec![
+            DriftSegment {
+                start_s: 0.0,
+                ppm: 0.5,
+            },
+            DriftSegment {
+                start_s: 60.0,
+                ppm: -12.0,
+            },
+        ];
+        let mut mov = Clip::new("/c/C0001.MP4".into(), "C0001.MP4".into(), 48000, 2);
+        mov.stream_metadata.camera_model = Some("ILCE-7SM3".into());
+        cam.clips.push(mov);
+        let tracks = vec![zoom, cam];
+
+        let ids = |q: ClipQuery| {
+            query_clips(&tracks, &q)
+                .into_iter()
+                .map(|m| m.clip_id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(ClipQuery::default()).len(), 4);
+        assert_eq!(
+            ids(ClipQuery {
+                name: Some("TAKE".into()),
+                ..Default::default()
+            }),
+            ["/z/take1.wav", "/z/take2.wav"]
+        );
+        assert_eq!(
+            ids(ClipQuery {
+                device: Some("ilce".into()),
+                ..Default::default()
+            }),
+            ["/c/C0001.MP4"]
+        );
+        assert_eq!(
+            ids(ClipQuery {
+                device: Some("zoom".into()),
+                confidence_below: Some(70.0),
+                ..Default::default()
+            }),
+            ["/z/take2.wav", "/z/room.wav"]
+        );
+        // Untimed clips never fall in a date range.
+        assert_eq!(
+            ids(ClipQuery {
+                created_after: Some(1_050.0),
+                created_before: Some(1_200.0),
+                ..Default::default()
+            }),
+            ["/z/take2.wav"]
+        );
+        assert_eq!(
+            ids(ClipQuery {
+                drift_above_ppm: Some(10.0),
+                ..Default::default()
+            }),
+            ["/z/room.wav"]
+        );
+
+        let m = &query_clips(
+            &tracks,
+            &ClipQuery {
+                name: Some("C0001".into()),
+                ..Default::default()
+            },
+        )[0];
+        assert_eq!((m.track_index, m.clip_index), (1, 0));
+
+        assert_eq!(parse_date("2024-05-01T00:00:00Z"), Some(1_714_521_600.0));
+        assert!(parse_date("2024-05-01").is_some());
+        assert!(parse_date("yesterday").is_none());
+    }
+}
@@ -0,0 +1,384 @@
+//! Remote analysis offload — ship 8 kHz analysis audio to an `audiosync serve`
+//! instance, run `engine::analyze` there, and apply the offsets locally.
+//!
+//! Only the low-res analysis copy travels over the wire (16-bit PCM, base64),
+//! so a one-hour clip costs ~58 MB instead of the original media. Each clip
+//! carries the SHA-256 of its original file; the server echoes those hashes
+//! back in a signed `ResultManifest` that is verified before offsets are applied.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine as _;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::audio_io::hash_file;
+use crate::engine;
+use crate::models::{Clip, SyncConfig, SyncResult, Track, ANALYSIS_SR};
+use crate::project_io::{verify_manifest, ManifestEntry, ResultManifest};
+
+/// Path of the analysis endpoint on a serve-mode instance.
+pub const ANALYZE_PATH: &str = "/analyze";
+
+/// A clip as sent to the server: metadata plus encoded analysis samples.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteClip {
+    pub clip: Clip,
+    /// Little-endian 16-bit PCM at `ANALYSIS_SR`, base64-encoded.
+    pub samples_b64: String,
+    /// SHA-256 of the original media file (hex).
+    pub source_sha256: String,
+}
+
+/// A track as sent to the server: its settings (reference, lock, role, sync
+/// group, band weighting) and its clips.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteTrack {
+    /// The track without its clips, which travel in `clips`.
+    pub track: Track,
+    pub clips: Vec<RemoteClip>,
+}
+
+/// Body of a remote analysis request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisRequest {
+    pub tracks: Vec<RemoteTrack>,
+    pub config: SyncConfig,
+}
+
+/// Body of a remote analysis response — tracks carry placement metadata only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisResponse {
+    pub tracks: Vec<Track>,
+    pub result: SyncResult,
+    pub manifest: ResultManifest,
+}
+
+// ---------------------------------------------------------------------------
+//  Encoding
+// ---------------------------------------------------------------------------
+
+fn encode_samples(samples: &[f32]) -> String {
+    let mut bytes = Vec::with_capacity(samples.len() * 2);
+    for &s in samples {
+        let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn decode_samples(encoded: &str) -> Result<Vec<f32>> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .context("Invalid base64 sample payload")?;
+    let scale = 1.0 / i16::MAX as f32;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 * scale)
+        .collect())
+}
+
+impl AnalysisRequest {
+    /// Build a request from loaded tracks, hashing each original file.
+    pub fn from_tracks(tracks: &[Track], config: &SyncConfig) -> Result<Self> {
+        let mut remote_tracks = Vec::with_capacity(tracks.len());
+        for t in tracks {
+            let mut clips = Vec::with_capacity(t.clips.len());
+            for c in &t.clips {
+                clips.push(RemoteClip {
+                    clip: c.clone(),
+                    samples_b64: encode_samples(&c.samples),
+                    source_sha256: hash_file(&c.file_path)?,
+                });
+            }
+            remote_tracks.push(RemoteTrack {
+                track: t.without_clips(),
+                clips,
+            });
+        }
+        Ok(Self {
+            tracks: remote_tracks,
+            config: config.clone(),
+        })
+    }
+
+    /// Media hashes keyed by file path.
+    pub fn hashes(&self) -> HashMap<String, String> {
+        self.tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .map(|c| (c.clip.file_path.clone(), c.source_sha256.clone()))
+            .collect()
+    }
+
+    /// Decode back into tracks with analysis samples populated.
+    pub fn into_tracks(self) -> Result<(Vec<Track>, SyncConfig)> {
+        let mut tracks = Vec::with_capacity(self.tracks.len());
+        for rt in self.tracks {
+            let mut track = rt.track;
+            track.clips = Vec::with_capacity(rt.clips.len());
+            for rc in rt.clips {
+                let mut clip = rc.clip;
+                clip.samples = decode_samples(&rc.samples_b64)
+                    .with_context(|| format!("Bad samples for '{}'", clip.name))?;
+                clip.sample_rate = ANALYSIS_SR;
+                track.clips.push(clip);
+            }
+            tracks.push(track);
+        }
+        Ok((tracks, self.config))
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Server side
+// ---------------------------------------------------------------------------
+
+/// Run analysis for a decoded request (used by serve mode).
+///
+/// The manifest is signed when the server was started with a shared `key`.
+pub fn handle_analysis_request(
+    request: AnalysisRequest,
+    key: Option<&str>,
+) -> Result<AnalysisResponse> {
+    let hashes = request.hashes();
+    let (mut tracks, config) = request.into_tracks()?;
+    let result = engine::analyze(&mut tracks, &config, &None, &None)?;
+
+    let mut manifest = ResultManifest::new(&tracks, &hashes)?;
+    if let Some(key) = key {
+        manifest.sign(key)?;
+    }
+
+    Ok(AnalysisResponse {
+        tracks,
+        result,
+        manifest,
+    })
+}
+
+// ---------------------------------------------------------------------------
+//  Client side
+// ---------------------------------------------------------------------------
+
+/// Copy remote placements onto local tracks, matching clips by file path.
+///
+/// Placements and drift must agree with the (already verified) manifest,
+/// field for field. Local analysis
+/// samples are kept; clip order follows the server's sort.
+pub fn apply_remote_result(tracks: &mut [Track], response: &AnalysisResponse) -> Result<()> {
+    if tracks.len() != response.tracks.len() {
+        return Err(anyhow!(
+            "Remote result has {} tracks, expected {}",
+            response.tracks.len(),
+            tracks.len()
+        ));
+    }
+
+    for (local, remote) in tracks.iter_mut().zip(response.tracks.iter()) {
+        let placed: HashMap<&str, &Clip> = remote
+            .clips
+            .iter()
+            .map(|c| (c.file_path.as_str(), c))
+            .collect();
+
+        for clip in &mut local.clips {
+            let r = placed.get(clip.file_path.as_str()).ok_or_else(|| {
+                anyhow!("Remote result is missing clip '{}'", clip.name)
+            })?;
+            let entry = response.manifest.entry(&clip.file_path).ok_or_else(|| {
+                anyhow!("Result manifest has no entry for '{}'", clip.name)
+            })?;
+            // Everything applied below must be what the server signed.
+            if ManifestEntry::for_clip(r, entry.sha256.clone()) != *entry {
+                return Err(anyhow!(
+                    "Remote placement for '{}' disagrees with its manifest",
+                    clip.name
+                ));
+            }
+            clip.timeline_offset_samples = r.timeline_offset_samples;
+            clip.timeline_offset_s = r.timeline_offset_s;
+            clip.confidence = r.confidence;
+            clip.match_score = r.match_score;
+            clip.analyzed = r.analyzed;
+            // A clip the server found no place for stays off the timeline.
+            clip.unplaced = r.unplaced;
+            clip.polarity_inverted = r.polarity_inverted;
+            if !clip.drift_override {
+                clip.drift_ppm = r.drift_ppm;
+                clip.drift_confidence = r.drift_confidence;
+                clip.drift_windows = r.drift_windows;
+                clip.drift_inherited = r.drift_inherited;
+                clip.drift_segments = r.drift_segments.clone();
+            }
+            clip.timings.correlation_s = r.timings.correlation_s;
+        }
+
+        let order: HashMap<&str, usize> = remote
+            .clips
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.file_path.as_str(), i))
+            .collect();
+        local
+            .clips
+            .sort_by_key(|c| order.get(c.file_path.as_str()).copied().unwrap_or(usize::MAX));
+        local.is_reference = remote.is_reference;
+        // Automatic sync groups may have moved the track on the server.
+        local.sync_group = remote.sync_group.clone();
+    }
+
+    Ok(())
+}
+
+/// Offload analysis to a serve-mode instance at `endpoint` and apply the result.
+///
+/// With `key` set, the server's manifest must carry a valid signature.
+pub async fn analyze_remote(
+    endpoint: &str,
+    tracks: &mut [Track],
+    config: &SyncConfig,
+    key: Option<&str>,
+) -> Result<SyncResult> {
+    let url = format!("{}{}", endpoint.trim_end_matches('/'), ANALYZE_PATH);
+    // The server can't read our media, so timecode travels with the clips.
+    if let Some(channel) = config.ltc_channel {
+        crate::engine::decode_timecodes(tracks, channel, config.io_parallelism, &None);
+    }
+    let request = AnalysisRequest::from_tracks(tracks, config)?;
+    info!("Submitting {} tracks for remote analysis at {}", tracks.len(), url);
+
+    let resp = reqwest::Client::new()
+        .post(&url)
+        .json(&request)
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach analysis server at {}", endpoint))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(anyhow!("Remote analysis failed ({}): {}", status, body));
+    }
+
+    let response: AnalysisResponse = resp
+        .json()
+        .await
+        .context("Invalid response from analysis server")?;
+    verify_manifest(&response.manifest, tracks, &request.hashes(), key)?;
+    apply_remote_result(tracks, &response)?;
+    Ok(response.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{noise, DriftSegment};
+
+    #[test]
+    fn test_sample_roundtrip() {
+        let samples = vec![0.0f32, 0.5, -0.5, 1.0, -1.0];
+        let decoded = decode_samples(&encode_samples(&samples)).unwrap();
+        assert_eq!(decoded.len(), samples.len());
+        for (a, b) in samples.iter().zip(decoded.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_remote_roundtrip_applies_offsets() {
+        // Deterministic white noise — aperiodic, so the peak is unambiguous.
+        let signal = noise(12345, 16800);
+
+        let dir = std::env::temp_dir();
+        let ref_path = dir.join(format!("audiosync_ref_{}.wav", uuid::Uuid::new_v4()));
+        let tgt_path = dir.join(format!("audiosync_tgt_{}.wav", uuid::Uuid::new_v4()));
+        std::fs::write(&ref_path, b"reference media").unwrap();
+        std::fs::write(&tgt_path, b"target media").unwrap();
+        let ref_path = ref_path.to_string_lossy().to_string();
+        let tgt_path = tgt_path.to_string_lossy().to_string();
+
+        let mut tracks = vec![Track::new("Ref".into()), Track::new("Tgt".into())];
+        let mut r = Clip::new(ref_path.clone(), "ref.wav".into(), 48000, 1);
+        r.samples = signal.clone();
+        r.duration_s = signal.len() as f64 / ANALYSIS_SR as f64;
+        tracks[0].clips.push(r);
+        let mut t = Clip::new(tgt_path.clone(), "tgt.wav".into(), 48000, 1);
+        t.samples = signal[800..].to_vec();
+        t.duration_s = t.samples.len() as f64 / ANALYSIS_SR as f64;
+        tracks[1].clips.push(t);
+
+        // Simulate the wire: serialize, analyze "remotely", apply.
+        let request = AnalysisRequest::from_tracks(&tracks, &SyncConfig::default()).unwrap();
+        let json = serde_json::to_string(&request).unwrap();
+        let response =
+            handle_analysis_request(serde_json::from_str(&json).unwrap(), Some("k")).unwrap();
+        let response: AnalysisResponse =
+            serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+        verify_manifest(&response.manifest, &tracks, &request.hashes(), Some("k")).unwrap();
+        apply_remote_result(&mut tracks, &response).unwrap();
+        let _ = std::fs::remove_file(&ref_path);
+        let _ = std::fs::remove_file(&tgt_path);
+
+        assert!(tracks[0].is_reference);
+        assert!((tracks[1].clips[0].timeline_offset_samples - 800).abs() <= 2);
+        assert!(!tracks[1].clips[0].samples.is_empty());
+
+        // Placement or drift changed after signing is refused.
+        let mut tampered = response.clone();
+        tampered.tracks[1].clips[0].timeline_offset_s += 0.5;
+        assert!(apply_remote_result(&mut tracks, &tampered).is_err());
+        let mut tampered = response.clone();
+        tampered.tracks[1].clips[0].drift_segments.push(DriftSegment { start_s: 1.0, ppm: 40.0 });
+        assert!(apply_remote_result(&mut tracks, &tampered).is_err());
+        let mut tampered = response.clone();
+        tampered.tracks[1].clips[0].unplaced = true;
+        assert!(apply_remote_result(&mut tracks, &tampered).is_err());
+        let mut tampered = response.clone();
+        tampered.tracks[1].clips[0].polarity_inverted = true;
+        assert!(apply_remote_result(&mut tracks, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_remote_analysis_keeps_track_settings() {
+        let (room_a, room_b) = (noise(21, 16800), noise(57, 16800));
+        let mut paths = Vec::new();
+        let mut clip = |name: &str, samples: &[f32]| {
+            let path = std::env::temp_dir().join(format!("audiosync_{}_{}.wav", name, uuid::Uuid::new_v4()));
+            std::fs::write(&path, name.as_bytes()).unwrap();
+            let path = path.to_string_lossy().to_string();
+            paths.push(path.clone());
+            let mut c = Clip::new(path, format!("{}.wav", name), 48000, 1);
+            c.samples = samples.to_vec();
+            c.duration_s = samples.len() as f64 / ANALYSIS_SR as f64;
+            c
+        };
+        let mut tracks: Vec<Track> =
+            ["Room A", "Cam A", "Room B", "Cam B"].map(|n| Track::new(n.into())).into();
+        tracks[0].clips.push(clip("room_a", &room_a));
+        tracks[1].clips.push(clip("cam_a", &room_a[1600..]));
+        tracks[2].clips.push(clip("room_b", &room_b));
+        tracks[3].clips.push(clip("cam_b", &room_b[800..]));
+        // Cam A was placed by hand and locked; Room B is another room.
+        tracks[1].locked = true;
+        tracks[1].clips[0].timeline_offset_samples = 4000;
+        tracks[1].clips[0].timeline_offset_s = 0.5;
+        for t in &mut tracks[2..] {
+            t.sync_group = Some("B".into());
+        }
+
+        let request = AnalysisRequest::from_tracks(&tracks, &SyncConfig::default()).unwrap();
+        let hashes = request.hashes();
+        let request = serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+        let response = handle_analysis_request(request, None).unwrap();
+        verify_manifest(&response.manifest, &tracks, &hashes, None).unwrap();
+        apply_remote_result(&mut tracks, &response).unwrap();
+        paths.iter().for_each(|p| drop(std::fs::remove_file(p)));
+
+        // Each room against its own reference, as a local analysis does.
+        assert_eq!(response.result.groups.len(), 2);
+        assert!((tracks[3].clips[0].timeline_offset_samples - 800).abs() <= 2);
+        assert_eq!(tracks[3].sync_group.as_deref(), Some("B"));
+        assert_eq!(tracks[1].clips[0].timeline_offset_samples, 4000);
+    }
+}
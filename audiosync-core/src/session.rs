@@ -0,0 +1,1287 @@
+//! One project's working state — tracks, settings, last analysis and
+//! comments — and the operations on it, shared by the CLI and the desktop
+//! app. Front ends wrap these calls with their own progress display, events
+//! and threading; the project logic lives here once.
+
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use std::collections::HashSet;
+
+use crate::audio_io::{
+    self, is_supported_file, load_clips, sanitize_path_part, AnalysisCache, ExportLayout, ExportTags,
+    TrackWriter,
+};
+use crate::aaf::{self, AafRegion, AafTrack};
+use crate::engine::{self, with_tracks, Stitched};
+use crate::grouping::group_files_by_device;
+use crate::hooks::{self, ExportReport};
+use crate::models::*;
+use crate::project_io;
+use crate::query::{self, ClipMatch, ClipQuery};
+use crate::stats::{self, ProjectStats};
+use crate::sync_map::{self, SyncMap};
+use crate::timeline_export::{self, FcpxmlFormat};
+
+/// A file that could not be loaded during import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// What to write besides the audio, and where.
+#[derive(Debug, Clone, Default)]
+pub struct ExportRequest {
+    pub output_dir: String,
+    /// Project name for the folder template and embedded tags.
+    pub project_name: String,
+    /// Appended to every file name, e.g. `_preview`.
+    pub suffix: String,
+    /// Embed project/track/offset/version tags in the exported files.
+    pub embed_metadata: bool,
+    pub fcpxml_path: Option<String>,
+    /// FCPXML format; detected from the media when `None`.
+    pub fcpxml_format: Option<FcpxmlFormat>,
+    pub edl_path: Option<String>,
+    /// JSON timeline for web review players.
+    pub timeline_json_path: Option<String>,
+    /// Audio-only AAF with a region per clip (see
+    /// [`SessionController::export_aaf`]).
+    pub aaf_path: Option<String>,
+}
+
+/// Result of [`SessionController::export`].
+#[derive(Debug, Clone, Default)]
+pub struct ExportOutcome {
+    pub files: Vec<String>,
+    /// Clips left silent because their source could not be read.
+    pub skipped: Vec<UnavailableClip>,
+    /// Per-file decode check; empty unless `verify_export` is set.
+    pub verification: Vec<ExportVerification>,
+    /// The post-export command failed. The files themselves were written.
+    pub hook_error: Option<String>,
+}
+
+impl ExportOutcome {
+    pub fn failed_verification(&self) -> usize {
+        self.verification.iter().filter(|v| !v.ok).count()
+    }
+}
+
+/// Working state of one project.
+#[derive(Debug, Clone, Default)]
+pub struct SessionController {
+    pub tracks: Vec<Track>,
+    pub config: SyncConfig,
+    /// Last analysis; cleared whenever the set of clips changes.
+    pub result: Option<SyncResult>,
+    pub comments: Vec<Comment>,
+}
+
+impl SessionController {
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Open a saved project.
+    pub fn open(path: &str) -> Result<Self> {
+        let project = project_io::load_project(path)?;
+        Ok(Self {
+            tracks: project.tracks,
+            config: project.config,
+            result: project.result,
+            comments: project.comments,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        project_io::save_project(path, &self.tracks, &self.config, self.result.as_ref(), &self.comments)
+    }
+
+    /// Write the last analysis as a sync map (see `sync_map`).
+    pub fn save_sync_map(&self, path: &str) -> Result<()> {
+        let result = self
+            .result
+            .as_ref()
+            .ok_or_else(|| anyhow!("No analysis result — run analysis first."))?;
+        sync_map::save_sync_map(path, &SyncMap::new(&self.tracks, result))
+    }
+
+    /// Place the imported clips from a sync map instead of analyzing them.
+    pub fn apply_sync_map(&mut self, path: &str) -> Result<&SyncResult> {
+        let map = sync_map::load_sync_map(path)?;
+        let result = sync_map::apply_sync_map(&mut self.tracks, &map);
+        if result.clip_offsets.is_empty() {
+            bail!("None of these clips are in the sync map '{}'.", path);
+        }
+        Ok(self.result.insert(result))
+    }
+
+    // -- Tracks and clips ---------------------------------------------------
+
+    /// Replace all tracks with `paths`, one track per recording device.
+    pub fn import_files<F, M>(
+        &mut self,
+        paths: &[String],
+        cancel: &Option<CancelToken>,
+        on_start: F,
+        on_meter: M,
+    ) -> Result<Vec<LoadFailure>>
+    where
+        F: Fn(usize, &str) + Sync,
+        M: Fn(&MeterSnapshot) + Sync,
+    {
+        let cache = AnalysisCache::from_config(&self.config);
+        let (tracks, failures) = load_tracks(paths, self.config.io_parallelism, &cache, cancel, on_start, on_meter)?;
+        self.set_tracks(tracks);
+        Ok(failures)
+    }
+
+    /// Replace all tracks (e.g. after loading them off the session's thread).
+    pub fn set_tracks(&mut self, tracks: Vec<Track>) {
+        self.tracks = tracks;
+        self.result = None;
+    }
+
+    /// Append clips to an existing track. A clip's source path is its id, so
+    /// nothing is added when any of them is already in the session or
+    /// appears twice among `clips`.
+    pub fn add_clips(&mut self, track_index: usize, clips: Vec<Clip>) -> Result<()> {
+        self.track_mut(track_index)?;
+        let mut seen: HashSet<&str> =
+            self.tracks.iter().flat_map(|t| &t.clips).map(|c| c.file_path.as_str()).collect();
+        if let Some(clip) = clips.iter().find(|c| !seen.insert(&c.file_path)) {
+            bail!("'{}' is already in the project.", clip.name);
+        }
+        self.tracks[track_index].clips.extend(clips);
+        self.result = None;
+        Ok(())
+    }
+
+    pub fn create_track(&mut self, name: String) {
+        self.tracks.push(Track::new(name));
+    }
+
+    pub fn remove_track(&mut self, index: usize) -> Result<Track> {
+        self.track_mut(index)?;
+        self.result = None;
+        Ok(self.tracks.remove(index))
+    }
+
+    pub fn remove_clip(&mut self, track_index: usize, clip_index: usize) -> Result<Clip> {
+        self.clip_mut(track_index, clip_index)?;
+        self.result = None;
+        Ok(self.tracks[track_index].clips.remove(clip_index))
+    }
+
+    /// Remove the clips with these ids (source paths) in one go. Nothing is
+    /// removed when any id is unknown.
+    pub fn remove_clips(&mut self, clip_ids: &[String]) -> Result<Vec<Clip>> {
+        let mut positions = self.clip_positions(clip_ids)?;
+        // Back to front, so earlier removals don't shift later indices.
+        positions.sort_unstable_by(|a, b| b.cmp(a));
+        self.result = None;
+        Ok(positions
+            .into_iter()
+            .map(|(ti, ci)| self.tracks[ti].clips.remove(ci))
+            .collect())
+    }
+
+    /// Exclude clips from analysis and export, or include them again.
+    pub fn set_clips_excluded(&mut self, clip_ids: &[String], excluded: bool) -> Result<()> {
+        for (ti, ci) in self.clip_positions(clip_ids)? {
+            self.tracks[ti].clips[ci].excluded = excluded;
+        }
+        self.result = None;
+        Ok(())
+    }
+
+    /// Set the stitching gain (dB) of several clips at once.
+    pub fn set_clips_gain(&mut self, clip_ids: &[String], gain_db: f64) -> Result<()> {
+        if !gain_db.is_finite() {
+            bail!("Invalid gain: {} dB", gain_db);
+        }
+        for (ti, ci) in self.clip_positions(clip_ids)? {
+            self.tracks[ti].clips[ci].gain_db = gain_db;
+        }
+        Ok(())
+    }
+
+    /// Lock or unlock a clip so analysis and overlap fixes leave it in place.
+    pub fn set_clip_locked(&mut self, track_index: usize, clip_index: usize, locked: bool) -> Result<()> {
+        self.clip_mut(track_index, clip_index)?.locked = locked;
+        Ok(())
+    }
+
+    /// Place a clip at `offset_s` on the timeline by hand; analysis keeps it
+    /// there and export uses it verbatim. `None` hands it back to analysis.
+    pub fn set_clip_offset(&mut self, track_index: usize, clip_index: usize, offset_s: Option<f64>) -> Result<()> {
+        let clip = self.clip_mut(track_index, clip_index)?;
+        clip.set_offset_override(offset_s);
+        let (path, offset) = (clip.file_path.clone(), clip.timeline_offset_samples);
+        if let Some(result) = self.result.as_mut() {
+            result.clip_offsets.insert(path, offset);
+        }
+        Ok(())
+    }
+
+    /// Pin a clip's drift (see `Clip::set_drift_override`).
+    pub fn set_clip_drift(&mut self, track_index: usize, clip_index: usize, drift_ppm: Option<f64>) -> Result<()> {
+        self.clip_mut(track_index, clip_index)?.set_drift_override(drift_ppm);
+        Ok(())
+    }
+
+    /// Set the gain (dB) a clip is stitched at; 0 leaves its level alone.
+    pub fn set_clip_gain(&mut self, track_index: usize, clip_index: usize, gain_db: f64) -> Result<()> {
+        if !gain_db.is_finite() {
+            bail!("Invalid gain: {} dB", gain_db);
+        }
+        self.clip_mut(track_index, clip_index)?.gain_db = gain_db;
+        Ok(())
+    }
+
+    /// Cut `trim_in_s` from the head and `trim_out_s` from the tail of a clip
+    /// when it is stitched; the rest stays where it is on the timeline.
+    pub fn set_clip_trim(
+        &mut self,
+        track_index: usize,
+        clip_index: usize,
+        trim_in_s: f64,
+        trim_out_s: f64,
+    ) -> Result<()> {
+        let clip = self.clip_mut(track_index, clip_index)?;
+        if !(trim_in_s >= 0.0 && trim_out_s >= 0.0) || trim_in_s + trim_out_s >= clip.duration_s {
+            bail!(
+                "Invalid trim for '{}': {:.3} s in, {:.3} s out of a {:.3} s clip",
+                clip.name,
+                trim_in_s,
+                trim_out_s,
+                clip.duration_s
+            );
+        }
+        clip.trim_in_s = trim_in_s;
+        clip.trim_out_s = trim_out_s;
+        Ok(())
+    }
+
+    pub fn set_track_locked(&mut self, track_index: usize, locked: bool) -> Result<()> {
+        self.track_mut(track_index)?.locked = locked;
+        Ok(())
+    }
+
+    /// Assign a track role; `None` goes back to inferring it from the name.
+    pub fn set_track_role(&mut self, track_index: usize, role: Option<TrackRole>) -> Result<()> {
+        self.track_mut(track_index)?.role = role;
+        Ok(())
+    }
+
+    /// Set a track's own analysis band weighting; `None` goes back to its
+    /// role's profile.
+    pub fn set_track_band_weighting(
+        &mut self,
+        track_index: usize,
+        weighting: Option<BandWeighting>,
+    ) -> Result<()> {
+        self.track_mut(track_index)?.band_weighting = weighting;
+        Ok(())
+    }
+
+    /// Put a track in a sync group; `None` moves it back to the default one.
+    pub fn set_track_sync_group(&mut self, track_index: usize, group: Option<String>) -> Result<()> {
+        let group = group.map(|g| g.trim().to_string()).filter(|g| !g.is_empty());
+        self.track_mut(track_index)?.sync_group = group;
+        Ok(())
+    }
+
+    /// Move the tracks of every sync group the last analysis proposed into
+    /// groups of their own. Returns the new group names; analyze again to
+    /// sync them.
+    pub fn apply_proposed_sync_groups(&mut self) -> Result<Vec<String>> {
+        let result = self
+            .result
+            .take()
+            .ok_or_else(|| anyhow!("No analysis result — run analysis first."))?;
+        Ok(engine::assign_proposed_groups(&mut self.tracks, &result.proposed_groups))
+    }
+
+    pub fn set_track_sync_group_by_name(&mut self, name: &str, group: &str) -> Result<()> {
+        let index = self
+            .tracks
+            .iter()
+            .position(|t| t.name == name)
+            .ok_or_else(|| anyhow!("No track named '{}'", name))?;
+        self.set_track_sync_group(index, Some(group.to_string()))
+    }
+
+    pub fn set_track_role_by_name(&mut self, name: &str, role: TrackRole) -> Result<()> {
+        let track = self
+            .tracks
+            .iter_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| anyhow!("No track named '{}'", name))?;
+        track.role = Some(role);
+        Ok(())
+    }
+
+    /// Keep this clip's placement across re-analysis (see
+    /// `SyncConfig::anchor_clip`); `None` normalizes to zero again.
+    pub fn set_anchor_clip(&mut self, file_path: Option<String>) -> Result<()> {
+        if let Some(ref path) = file_path
+            && !self.tracks.iter().flat_map(|t| &t.clips).any(|c| &c.file_path == path)
+        {
+            bail!("No clip with path '{}'", path);
+        }
+        self.config.anchor_clip = file_path;
+        Ok(())
+    }
+
+    fn track_mut(&mut self, index: usize) -> Result<&mut Track> {
+        self.tracks
+            .get_mut(index)
+            .ok_or_else(|| anyhow!("Track index {} out of range", index))
+    }
+
+    fn clip_mut(&mut self, track_index: usize, clip_index: usize) -> Result<&mut Clip> {
+        self.track_mut(track_index)?
+            .clips
+            .get_mut(clip_index)
+            .ok_or_else(|| anyhow!("Clip index {} out of range", clip_index))
+    }
+
+    /// (track, clip) indices of the clips with these ids (source paths,
+    /// unique within a session — see `add_clips`); an error naming the first id that matches no clip.
+    fn clip_positions(&self, clip_ids: &[String]) -> Result<Vec<(usize, usize)>> {
+        if let Some(id) = clip_ids
+            .iter()
+            .find(|id| !self.tracks.iter().flat_map(|t| &t.clips).any(|c| &c.file_path == *id))
+        {
+            bail!("No clip with id '{}'", id);
+        }
+        let mut positions = Vec::new();
+        for (ti, track) in self.tracks.iter().enumerate() {
+            for (ci, clip) in track.clips.iter().enumerate() {
+                if clip_ids.contains(&clip.file_path) {
+                    positions.push((ti, ci));
+                }
+            }
+        }
+        Ok(positions)
+    }
+
+    // -- Analysis -----------------------------------------------------------
+
+    /// Analyze the project, first splitting it into sessions when
+    /// `session_gap_s` is set (see `engine::split_sessions`).
+    pub fn analyze(&mut self, progress: &Option<ProgressCallback>, cancel: &Option<CancelToken>) -> Result<&SyncResult> {
+        if let Some(gap_s) = self.config.session_gap_s {
+            engine::split_sessions(&mut self.tracks, gap_s, &self.config);
+        }
+        let result = engine::analyze(&mut self.tracks, &self.config, progress, cancel)?;
+        if let Some(cache) = AnalysisCache::from_config(&self.config) {
+            cache.record_analysis(&self.tracks);
+        }
+        Ok(self.result.insert(result))
+    }
+
+    /// Sets of clips here that one earlier analysis, in any project, placed
+    /// together — largest first. Empty when the analysis cache is off.
+    pub fn reusable_analyses(&self) -> Vec<ReusableAnalysis> {
+        let priors = self.look_up_priors();
+        reusable_runs(priors.iter().map(|(ti, ci, prior)| (&self.tracks[*ti].clips[*ci], prior)))
+    }
+
+    /// Attach earlier analyses of the same content to the clips. With
+    /// `PriorReuse::Skip`, the largest set placed together keeps its earlier
+    /// placement and drift and counts as analyzed, so an incremental
+    /// analysis only correlates the other clips. Returns how many clips are
+    /// in reusable sets (`Priors`) or were placed (`Skip`).
+    pub fn reuse_prior_analysis(&mut self, mode: PriorReuse) -> Result<usize> {
+        if AnalysisCache::from_config(&self.config).is_none() {
+            bail!("The analysis cache is turned off, so there are no earlier analyses.");
+        }
+        for (ti, ci, prior) in self.look_up_priors() {
+            self.tracks[ti].clips[ci].prior = Some(prior);
+        }
+        let clips = self.tracks.iter().flat_map(|t| &t.clips);
+        let runs = reusable_runs(clips.filter_map(|c| Some((c, c.prior.as_ref()?))));
+        if mode == PriorReuse::Priors {
+            return Ok(runs.iter().map(|r| r.clip_ids.len()).sum());
+        }
+
+        let best = runs
+            .first()
+            .ok_or_else(|| anyhow!("None of these clips were analyzed together before."))?;
+        for clip in self.tracks.iter_mut().flat_map(|t| &mut t.clips) {
+            let Some(prior) = clip.prior.as_ref().filter(|p| p.run_id == best.run_id) else {
+                continue;
+            };
+            clip.timeline_offset_s = prior.offset_s;
+            clip.timeline_offset_samples = (prior.offset_s * ANALYSIS_SR as f64).round() as i64;
+            clip.confidence = prior.confidence;
+            clip.match_score = prior.match_score;
+            if !clip.drift_override {
+                clip.drift_ppm = prior.drift_ppm;
+                clip.drift_segments = prior.drift_segments.clone();
+            }
+            clip.analyzed = true;
+        }
+        self.result = None;
+        Ok(best.clip_ids.len())
+    }
+
+    /// `(track, clip, prior)` for every clip the analysis cache has a
+    /// record of.
+    fn look_up_priors(&self) -> Vec<(usize, usize, PriorAnalysis)> {
+        let Some(cache) = AnalysisCache::from_config(&self.config) else {
+            return Vec::new();
+        };
+        let mut priors = Vec::new();
+        for (ti, track) in self.tracks.iter().enumerate() {
+            for (ci, clip) in track.clips.iter().enumerate() {
+                if let Some(prior) = clip.content_hash.as_deref().and_then(|h| cache.prior_analysis(h)) {
+                    priors.push((ti, ci, prior));
+                }
+            }
+        }
+        priors
+    }
+
+    /// Re-correlate one clip within `start_s..end_s` of the timeline; other
+    /// clips keep their placement.
+    pub fn reanalyze_clip(&mut self, track_index: usize, clip_index: usize, start_s: f64, end_s: f64) -> Result<()> {
+        let (offset, _) =
+            engine::reanalyze_clip_in_window(&mut self.tracks, track_index, clip_index, start_s, end_s)?;
+        if let Some(result) = self.result.as_mut() {
+            let clip = &self.tracks[track_index].clips[clip_index];
+            result.clip_offsets.insert(clip.file_path.clone(), offset);
+            if clip.end_samples() > result.total_timeline_samples {
+                result.total_timeline_samples = clip.end_samples();
+                result.total_timeline_s = result.total_timeline_samples as f64 / result.sample_rate as f64;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> ProjectStats {
+        stats::project_stats(&self.tracks, self.result.as_ref(), &self.config)
+    }
+
+    /// Clips matching `query`, for filtering and bulk edits.
+    pub fn query_clips(&self, query: &ClipQuery) -> Vec<ClipMatch> {
+        query::query_clips(&self.tracks, query)
+    }
+
+    /// Clips whose source file can no longer be read.
+    pub fn preflight_check(&self) -> Vec<UnavailableClip> {
+        audio_io::preflight_check(&self.tracks)
+    }
+
+    // -- Export -------------------------------------------------------------
+
+    /// Stitch every track and write it, plus any timelines requested, then
+    /// run the post-export command unless a file failed verification.
+    pub fn export(
+        &mut self,
+        request: &ExportRequest,
+        progress: &Option<ProgressCallback>,
+        cancel: &Option<CancelToken>,
+    ) -> Result<ExportOutcome> {
+        let export_sr = engine::resolve_export_sr(&self.tracks, &mut self.config);
+        self.refine_offsets(export_sr, progress, cancel)?;
+        let result = self
+            .result
+            .clone()
+            .ok_or_else(|| anyhow!("No analysis result — run analysis first."))?;
+
+        let config = &self.config;
+        let mut layout = ExportLayout::new(&request.output_dir, config, &request.project_name);
+        layout.grouped = !result.groups.is_empty();
+        let mut targets = Vec::with_capacity(self.tracks.len());
+        for track in &self.tracks {
+            let path = layout.track_path(track, &config.export_format, export_sr, &request.suffix)?;
+            let tags = request
+                .embed_metadata
+                .then(|| ExportTags::for_track(track, &request.project_name));
+            targets.push((path.to_string_lossy().to_string(), tags));
+        }
+        let mut outcome = write_tracks(&mut self.tracks, &result, config, export_sr, &targets, progress, cancel)?;
+
+        self.export_timelines(
+            request.fcpxml_path.as_deref(),
+            request.edl_path.as_deref(),
+            request.timeline_json_path.as_deref(),
+            request.fcpxml_format.as_ref(),
+        )?;
+        if let Some(ref path) = request.aaf_path {
+            let regions = self.write_aaf(path, request, progress, cancel)?;
+            outcome.files.extend(regions.files);
+            outcome.skipped.extend(regions.skipped);
+            outcome.verification.extend(regions.verification);
+        }
+
+        if let Some(ref command) = self.config.post_export_command
+            && outcome.failed_verification() == 0
+        {
+            let report = ExportReport {
+                result: &result,
+                exported_files: &outcome.files,
+                skipped: &outcome.skipped,
+                verification: &outcome.verification,
+                fcpxml: request.fcpxml_path.as_deref(),
+                edl: request.edl_path.as_deref(),
+                timeline_json: request.timeline_json_path.as_deref(),
+                aaf: request.aaf_path.as_deref(),
+            };
+            if let Err(e) = hooks::run_post_export(command, &request.output_dir, &report) {
+                warn!("{}", e);
+                outcome.hook_error = Some(e.to_string());
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Write one clip on its own, lined up with the timeline: silence from
+    /// zero up to where the clip starts, then the clip with its trim, gain
+    /// and drift correction, sample for sample as the full export has it.
+    /// Ready to drop in at the start of a DAW session. The file is named as
+    /// a track called `<track>_<clip>`, in the clip's own channels; no
+    /// timelines are written and no post-export command runs.
+    pub fn export_clip(
+        &mut self,
+        track_index: usize,
+        clip_index: usize,
+        request: &ExportRequest,
+        progress: &Option<ProgressCallback>,
+        cancel: &Option<CancelToken>,
+    ) -> Result<ExportOutcome> {
+        self.clip_mut(track_index, clip_index)?;
+        let export_sr = engine::resolve_export_sr(&self.tracks, &mut self.config);
+        self.refine_offsets(export_sr, progress, cancel)?;
+        let clip = self.tracks[track_index].clips[clip_index].clone();
+        let result = self
+            .result
+            .as_ref()
+            .ok_or_else(|| anyhow!("No analysis result — run analysis first."))?;
+        if clip.unplaced {
+            bail!("'{}' has no place on the timeline — place it by hand first", clip.name);
+        }
+        if clip.excluded {
+            bail!("'{}' is excluded — include it again first", clip.name);
+        }
+        let mut config = self.config.clone();
+        config.export_range_s = None;
+
+        let solo = solo_track(&self.tracks[track_index], clip);
+        let mut layout = ExportLayout::new(&request.output_dir, &config, &request.project_name);
+        layout.grouped = !result.groups.is_empty();
+        write_solo(solo, result, &config, &layout, request, progress, cancel)
+    }
+
+    /// Write an audio-only AAF placing every clip as a region of its own,
+    /// for conforming in a DAW, with one media file per region in a
+    /// `<aaf name>_media` folder beside it. The media is each whole clip
+    /// drift-corrected at the export rate; trimmed-off audio is kept as
+    /// handles. With several sync groups, each gets its own AAF.
+    pub fn export_aaf(
+        &mut self,
+        aaf_path: &str,
+        request: &ExportRequest,
+        progress: &Option<ProgressCallback>,
+        cancel: &Option<CancelToken>,
+    ) -> Result<ExportOutcome> {
+        let export_sr = engine::resolve_export_sr(&self.tracks, &mut self.config);
+        self.refine_offsets(export_sr, progress, cancel)?;
+        self.write_aaf(aaf_path, request, progress, cancel)
+    }
+
+    /// [`Self::export_aaf`] with the placements as they are.
+    fn write_aaf(
+        &mut self,
+        aaf_path: &str,
+        request: &ExportRequest,
+        progress: &Option<ProgressCallback>,
+        cancel: &Option<CancelToken>,
+    ) -> Result<ExportOutcome> {
+        let result = self
+            .result
+            .as_ref()
+            .ok_or_else(|| anyhow!("No analysis result — run analysis first."))?;
+        let export_sr = engine::resolve_export_sr(&self.tracks, &mut self.config);
+        let mut config = self.config.clone();
+        config.export_range_s = None;
+        config.export_format = "wav".to_string();
+        config.export_folder_template = None;
+        config.export_filename_template = None;
+
+        let compositions: Vec<(String, Vec<usize>)> = if result.groups.is_empty() {
+            vec![(aaf_path.to_string(), (0..self.tracks.len()).collect())]
+        } else {
+            result
+                .groups
+                .iter()
+                .map(|g| (group_path(aaf_path, &g.name), g.track_indices.clone()))
+                .collect()
+        };
+        let mut outcome = ExportOutcome::default();
+        for (path, indices) in compositions {
+            let media_dir = format!("{}_media", std::path::Path::new(&path).with_extension("").to_string_lossy());
+            let layout = ExportLayout::new(&media_dir, &config, &request.project_name);
+            let mut tracks = Vec::with_capacity(indices.len());
+            for ti in indices {
+                let track = &self.tracks[ti];
+                let mut regions = Vec::new();
+                for (ci, clip) in track.clips.iter().enumerate() {
+                    let Some((position, end)) = engine::stitched_clip_span(track, ci, &config, export_sr) else {
+                        continue;
+                    };
+                    // Untrimmed, the clip starts this far ahead of the region.
+                    let mut media = clip.clone();
+                    (media.trim_in_s, media.trim_out_s) = (0.0, 0.0);
+                    let (media_start, _) =
+                        engine::stitched_clip_span(&solo_track(track, media.clone()), 0, &config, export_sr)
+                            .unwrap_or((position, end));
+                    media.set_offset_override(Some(0.0));
+                    let solo = solo_track(track, media);
+                    let (_, media_len) = engine::stitched_clip_span(&solo, 0, &config, export_sr).unwrap_or_default();
+                    let channels = engine::output_channels(&solo, &config);
+
+                    let written = write_solo(solo, result, &config, &layout, request, progress, cancel)?;
+                    let (Some(media_path), true) = (written.files.first(), written.skipped.is_empty()) else {
+                        outcome.skipped.extend(written.skipped);
+                        continue;
+                    };
+                    regions.push(AafRegion {
+                        name: clip.name.clone(),
+                        media_path: media_path.clone(),
+                        media_len: media_len as u64,
+                        channels,
+                        bit_depth: config.export_bit_depth,
+                        source_start: position.saturating_sub(media_start) as u64,
+                        position: position as u64,
+                        len: (end - position) as u64,
+                    });
+                    outcome.files.extend(written.files);
+                    outcome.verification.extend(written.verification);
+                }
+                tracks.push(AafTrack {
+                    name: track.name.clone(),
+                    channels: engine::output_channels(track, &config),
+                    regions,
+                });
+            }
+            aaf::export_aaf(&tracks, export_sr, &request.project_name, &path)?;
+        }
+        Ok(outcome)
+    }
+
+    /// Place correlated clips to the export sample before they are written,
+    /// unless `SyncConfig::refine_offsets` is off.
+    fn refine_offsets(
+        &mut self,
+        export_sr: u32,
+        progress: &Option<ProgressCallback>,
+        cancel: &Option<CancelToken>,
+    ) -> Result<()> {
+        let Some(result) = self.result.as_mut().filter(|_| self.config.refine_offsets) else {
+            return Ok(());
+        };
+        engine::refine_offsets(&mut self.tracks, result, &self.config, export_sr, progress, cancel)?;
+        Ok(())
+    }
+
+    /// Write FCPXML, EDL and/or web timeline JSON of the last analysis. With several
+    /// sync groups, each group gets its own file, named `<file>_<group>.<ext>`.
+    pub fn export_timelines(
+        &mut self,
+        fcpxml_path: Option<&str>,
+        edl_path: Option<&str>,
+        timeline_json_path: Option<&str>,
+        fcpxml_format: Option<&FcpxmlFormat>,
+    ) -> Result<()> {
+        let result = self
+            .result
+            .as_ref()
+            .ok_or_else(|| anyhow!("No analysis result — run analysis first."))?;
+        let timelines: Vec<(Option<String>, Vec<usize>, SyncResult)> = if result.groups.is_empty() {
+            vec![(None, (0..self.tracks.len()).collect(), result.clone())]
+        } else {
+            result
+                .groups
+                .iter()
+                .map(|g| (Some(g.name.clone()), g.track_indices.clone(), group_result(result, g)))
+                .collect()
+        };
+        for (group, indices, group_result) in &timelines {
+            let path_for = |path: &str| match group {
+                Some(name) => group_path(path, name),
+                None => path.to_string(),
+            };
+            with_tracks(&mut self.tracks, indices, |tracks| -> Result<()> {
+                if let Some(path) = fcpxml_path {
+                    timeline_export::export_fcpxml(tracks, group_result, &path_for(path), None, fcpxml_format)?;
+                }
+                if let Some(path) = edl_path {
+                    timeline_export::export_edl(tracks, group_result, &path_for(path), None)?;
+                }
+                if let Some(path) = timeline_json_path {
+                    timeline_export::export_timeline_json(tracks, group_result, &path_for(path), None)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    // -- Comments -----------------------------------------------------------
+
+    /// Start a thread at `anchor`, or reply to `parent_id`.
+    pub fn add_comment(
+        &mut self,
+        author: String,
+        body: String,
+        anchor: Option<CommentAnchor>,
+        parent_id: Option<&str>,
+    ) -> Result<&Comment> {
+        let comment = match parent_id {
+            Some(pid) => {
+                let parent = self.comment_mut(pid)?;
+                Comment::reply_to(parent, author, body)
+            }
+            None => {
+                let anchor = anchor.ok_or_else(|| anyhow!("A new comment needs an anchor"))?;
+                Comment::new(author, body, anchor)
+            }
+        };
+        self.comments.push(comment);
+        Ok(self.comments.last().unwrap())
+    }
+
+    pub fn resolve_comment(&mut self, id: &str, resolved: bool) -> Result<()> {
+        self.comment_mut(id)?.set_resolved(resolved);
+        Ok(())
+    }
+
+    /// Delete a comment, leaving a tombstone for comment sync.
+    pub fn delete_comment(&mut self, id: &str) -> Result<()> {
+        self.comment_mut(id)?.delete();
+        Ok(())
+    }
+
+    /// Comments that haven't been deleted, for display.
+    pub fn live_comments(&self) -> Vec<Comment> {
+        self.comments.iter().filter(|c| !c.deleted).cloned().collect()
+    }
+
+    fn comment_mut(&mut self, id: &str) -> Result<&mut Comment> {
+        self.comments
+            .iter_mut()
+            .find(|c| c.id == id && !c.deleted)
+            .ok_or_else(|| anyhow!("Comment {} not found", id))
+    }
+}
+
+/// Load `paths` into one track per recording device. Unsupported files are
+/// ignored; files that fail to load are returned rather than aborting.
+pub fn load_tracks<F, M>(
+    paths: &[String],
+    io_parallelism: usize,
+    cache: &Option<AnalysisCache>,
+    cancel: &Option<CancelToken>,
+    on_start: F,
+    on_meter: M,
+) -> Result<(Vec<Track>, Vec<LoadFailure>)>
+where
+    F: Fn(usize, &str) + Sync,
+    M: Fn(&MeterSnapshot) + Sync,
+{
+    let supported = supported_files(paths)?;
+    let mut tracks = Vec::new();
+    let mut failures = Vec::new();
+    let mut loaded_before = 0;
+    for (device_name, paths) in group_files_by_device(&supported) {
+        let offset = loaded_before;
+        let (clips, failed) = load_clip_files(&paths, io_parallelism, cache, cancel, |i, p| on_start(offset + i, p), &on_meter);
+        loaded_before += paths.len();
+        failures.extend(failed);
+        if !clips.is_empty() {
+            let mut track = Track::new(device_name);
+            track.clips = clips;
+            tracks.push(track);
+        }
+    }
+    Ok((tracks, failures))
+}
+
+/// Load `paths` as clips, for adding to an existing track.
+pub fn load_clips_for_track<F, M>(
+    paths: &[String],
+    io_parallelism: usize,
+    cache: &Option<AnalysisCache>,
+    cancel: &Option<CancelToken>,
+    on_start: F,
+    on_meter: M,
+) -> Result<(Vec<Clip>, Vec<LoadFailure>)>
+where
+    F: Fn(usize, &str) + Sync,
+    M: Fn(&MeterSnapshot) + Sync,
+{
+    let supported = supported_files(paths)?;
+    Ok(load_clip_files(&supported, io_parallelism, cache, cancel, on_start, on_meter))
+}
+
+/// Stitch `tracks` and write each to its `(path, tags)` target, a block at a
+/// time as it is stitched, so no track is ever held whole.
+fn write_tracks(
+    tracks: &mut [Track],
+    result: &SyncResult,
+    config: &SyncConfig,
+    export_sr: u32,
+    targets: &[(String, Option<ExportTags>)],
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+) -> Result<ExportOutcome> {
+    let channels: Vec<(u32, Option<u32>)> = tracks
+        .iter()
+        .map(|t| (engine::output_channels(t, config), engine::output_channel_mask(t, config)))
+        .collect();
+    let mut outcome = ExportOutcome::default();
+    let mut writer: Option<TrackWriter> = None;
+    let skipped = engine::sync_streaming(tracks, result, config, progress, cancel, |ti, audio| {
+        let ((path, tags), (channels, mask)) = (&targets[ti], channels[ti]);
+        let current = match writer.as_mut() {
+            Some(current) => current,
+            None => writer.insert(TrackWriter::create(path, config, export_sr, channels, mask)?),
+        };
+        match audio {
+            Stitched::Block(block) => current.write(block)?,
+            Stitched::End => {
+                let (written, tally) = writer.take().expect("writer open").finish(tags.as_ref())?;
+                if config.verify_export {
+                    outcome.verification.push(audio_io::verify_export(&written, &tally, export_sr));
+                }
+                outcome.files.push(path.clone());
+            }
+        }
+        Ok(())
+    })?;
+    outcome.skipped = skipped;
+    Ok(outcome)
+}
+
+/// `clip` of `track` alone, on a track named `<track>_<clip>`.
+fn solo_track(track: &Track, clip: Clip) -> Track {
+    let stem = std::path::Path::new(&clip.name).file_stem().unwrap_or_default().to_string_lossy();
+    let mut solo = Track::new(format!("{}_{}", track.name, stem));
+    solo.role = track.role;
+    solo.sync_group = track.sync_group.clone();
+    solo.clips.push(clip);
+    solo
+}
+
+/// Write the single clip of `solo` to a file of its own, on a timeline
+/// that ends where the clip does.
+fn write_solo(
+    solo: Track,
+    result: &SyncResult,
+    config: &SyncConfig,
+    layout: &ExportLayout,
+    request: &ExportRequest,
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+) -> Result<ExportOutcome> {
+    let export_sr = config.export_sr.expect("export rate resolved");
+    let Some((_, end)) = engine::stitched_clip_span(&solo, 0, config, export_sr).filter(|&(_, end)| end > 0) else {
+        bail!("'{}' has no audio on the timeline", solo.clips[0].name);
+    };
+    let end_s = end as f64 / export_sr as f64;
+    let result = SyncResult {
+        total_timeline_samples: (end_s * result.sample_rate as f64).ceil() as i64,
+        total_timeline_s: end_s,
+        groups: Vec::new(),
+        ..result.clone()
+    };
+    let path = layout.track_path(&solo, &config.export_format, export_sr, &request.suffix)?;
+    let tags = request
+        .embed_metadata
+        .then(|| ExportTags::for_track(&solo, &request.project_name));
+    let targets = [(path.to_string_lossy().to_string(), tags)];
+    write_tracks(&mut [solo], &result, config, export_sr, &targets, progress, cancel)
+}
+
+/// `result` as seen from one sync group's own tracks, for timeline export.
+fn group_result(result: &SyncResult, group: &SyncGroupResult) -> SyncResult {
+    SyncResult {
+        reference_track_index: group
+            .track_indices
+            .iter()
+            .position(|&i| i == group.reference_track_index)
+            .unwrap_or(0),
+        total_timeline_samples: group.total_timeline_samples,
+        total_timeline_s: group.total_timeline_s,
+        avg_confidence: group.avg_confidence,
+        groups: Vec::new(),
+        ..result.clone()
+    }
+}
+
+/// Group clips by the analysis run their prior comes from, keeping runs that
+/// placed two or more of them (one clip alone has nothing to be relative
+/// to), largest first.
+fn reusable_runs<'a>(clips: impl Iterator<Item = (&'a Clip, &'a PriorAnalysis)>) -> Vec<ReusableAnalysis> {
+    let mut runs: Vec<ReusableAnalysis> = Vec::new();
+    for (clip, prior) in clips {
+        match runs.iter_mut().find(|r| r.run_id == prior.run_id) {
+            Some(run) => run.clip_ids.push(clip.file_path.clone()),
+            None => runs.push(ReusableAnalysis {
+                run_id: prior.run_id.clone(),
+                analyzed_at: prior.analyzed_at.clone(),
+                clip_ids: vec![clip.file_path.clone()],
+            }),
+        }
+    }
+    runs.retain(|r| r.clip_ids.len() >= 2);
+    runs.sort_by_key(|r| std::cmp::Reverse(r.clip_ids.len()));
+    runs
+}
+
+/// `out/timeline.fcpxml` → `out/timeline_<group>.fcpxml`.
+fn group_path(path: &str, group: &str) -> String {
+    let path = std::path::Path::new(path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("timeline");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}_{}.{}", stem, sanitize_path_part(group), ext),
+        None => format!("{}_{}", stem, sanitize_path_part(group)),
+    };
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+fn supported_files(paths: &[String]) -> Result<Vec<String>> {
+    // A path is a clip's id, so each file is loaded once.
+    let mut seen = HashSet::new();
+    let supported: Vec<String> = paths
+        .iter()
+        .filter(|p| is_supported_file(p) && seen.insert(p.as_str()))
+        .cloned()
+        .collect();
+    if supported.is_empty() {
+        bail!(
+            "No supported audio/video files found. \
+             Supported: WAV, AIFF, FLAC, MP3, OGG, OPUS, MP4, MOV, MKV, AVI, etc."
+        );
+    }
+    Ok(supported)
+}
+
+fn load_clip_files<F, M>(
+    paths: &[String],
+    io_parallelism: usize,
+    cache: &Option<AnalysisCache>,
+    cancel: &Option<CancelToken>,
+    on_start: F,
+    on_meter: M,
+) -> (Vec<Clip>, Vec<LoadFailure>)
+where
+    F: Fn(usize, &str) + Sync,
+    M: Fn(&MeterSnapshot) + Sync,
+{
+    let mut clips = Vec::new();
+    let mut failures = Vec::new();
+    for (path, loaded) in paths.iter().zip(load_clips(paths, io_parallelism, cache, cancel, on_start, on_meter)) {
+        match loaded {
+            Ok(clip) => clips.push(clip),
+            Err(e) => {
+                warn!("Failed to load {}: {}", path, e);
+                failures.push(LoadFailure {
+                    path: path.clone(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    (clips, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise_clip(path: &str, samples: Vec<f32>) -> Clip {
+        let mut clip = Clip::new(path.into(), path.into(), 48000, 1);
+        clip.duration_s = samples.len() as f64 / ANALYSIS_SR as f64;
+        clip.samples = samples;
+        clip
+    }
+
+    #[test]
+    fn test_session_edits_and_analysis() {
+        let signal = noise(5, ANALYSIS_SR as usize * 6);
+
+        let mut session = SessionController::default();
+        session.create_track("Ref".into());
+        session.create_track("Cam".into());
+        session.add_clips(0, vec![noise_clip("ref.wav", signal.clone())]).unwrap();
+        session
+            .add_clips(1, vec![noise_clip("cam.wav", signal[8000..32000].to_vec())])
+            .unwrap();
+
+        assert!(session.add_clips(2, Vec::new()).is_err());
+        assert!(session.set_clip_locked(1, 1, true).is_err());
+        assert!(session.set_anchor_clip(Some("nope.wav".into())).is_err());
+        session.set_track_role_by_name("Cam", TrackRole::Camera).unwrap();
+        assert_eq!(session.tracks[1].role, Some(TrackRole::Camera));
+
+        session.analyze(&None, &None).unwrap();
+        assert!((session.tracks[1].clips[0].timeline_offset_samples - 8000).abs() <= 2);
+        assert_eq!(session.result.as_ref().unwrap().clip_offsets["cam.wav"], 8000);
+
+        // Changing the clips invalidates the analysis.
+        session.remove_clip(1, 0).unwrap();
+        session.add_clips(1, vec![noise_clip("cam2.wav", signal[..8000].to_vec())]).unwrap();
+        assert!(session.result.is_none());
+        assert!(session.remove_track(5).is_err());
+        assert_eq!(session.remove_track(1).unwrap().name, "Cam");
+    }
+
+    #[test]
+    fn test_bulk_clip_edits() {
+        let signal = noise(7, ANALYSIS_SR as usize * 6);
+        let mut session = SessionController::default();
+        session.create_track("Ref".into());
+        session.create_track("Cam".into());
+        session.add_clips(0, vec![noise_clip("ref.wav", signal.clone())]).unwrap();
+        session
+            .add_clips(
+                1,
+                vec![
+                    noise_clip("cam1.wav", signal[8000..24000].to_vec()),
+                    noise_clip("cam2.wav", signal[32000..44000].to_vec()),
+                    noise_clip("junk.wav", vec![0.0; 8000]),
+                ],
+            )
+            .unwrap();
+        let ids = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        // An unknown id changes nothing.
+        assert!(session.set_clips_gain(&ids(&["cam1.wav", "nope.wav"]), -6.0).is_err());
+        assert!(session.set_clips_gain(&ids(&["cam1.wav"]), f64::INFINITY).is_err());
+        assert_eq!(session.tracks[1].clips[0].gain_db, 0.0);
+        session.set_clips_gain(&ids(&["cam1.wav", "cam2.wav"]), -6.0).unwrap();
+        assert!(session.tracks[1].clips[..2].iter().all(|c| c.gain_db == -6.0));
+
+        // Excluded clips sit out analysis and stay in the project.
+        session.set_clips_excluded(&ids(&["junk.wav"]), true).unwrap();
+        session.analyze(&None, &None).unwrap();
+        let junk = session.tracks[1].clips.iter().find(|c| c.name == "junk.wav").unwrap();
+        assert!(junk.excluded && !junk.analyzed && !junk.on_timeline());
+        assert!(!session.result.as_ref().unwrap().clip_offsets.contains_key("junk.wav"));
+        assert!((session.tracks[1].clips[1].timeline_offset_samples - 32000).abs() <= 2);
+
+        let removed = session.remove_clips(&ids(&["cam1.wav", "junk.wav"])).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(session.tracks[1].clips.len(), 1);
+        assert!(session.remove_clips(&ids(&["cam1.wav"])).is_err());
+        // The analysis described clips that are gone.
+        assert!(session.result.is_none());
+
+        // A path is a clip's id: the same file can't be added twice.
+        assert!(session.add_clips(0, vec![noise_clip("cam2.wav", vec![0.0; 8000])]).is_err());
+        let twice = vec![noise_clip("new.wav", vec![0.0; 8000]), noise_clip("new.wav", vec![0.0; 8000])];
+        assert!(session.add_clips(0, twice).is_err());
+        assert_eq!(session.tracks[0].clips.len(), 1);
+    }
+
+    #[test]
+    fn test_offset_override_survives_analysis_and_save() {
+        let signal = noise(9, ANALYSIS_SR as usize * 6);
+        let mut session = SessionController::default();
+        session.create_track("Ref".into());
+        session.create_track("Cam".into());
+        session.add_clips(0, vec![noise_clip("ref.wav", signal.clone())]).unwrap();
+        session
+            .add_clips(1, vec![noise_clip("cam.wav", signal[8000..32000].to_vec())])
+            .unwrap();
+
+        // The audio says 1 s; the editor knows better.
+        session.set_clip_offset(1, 0, Some(2.5)).unwrap();
+        session.analyze(&None, &None).unwrap();
+        let clip = &session.tracks[1].clips[0];
+        assert_eq!(clip.timeline_offset_s, 2.5);
+        assert_eq!(clip.timeline_offset_at_sr(48000), 120_000);
+        assert_eq!(session.result.as_ref().unwrap().clip_offsets["cam.wav"], 20_000);
+
+        // Trim and gain are kept with the rest of the clip's edit.
+        assert!(session.set_clip_trim(1, 0, 2.0, 1.0).is_err());
+        assert!(session.set_clip_trim(1, 0, -0.5, 0.0).is_err());
+        assert!(session.set_clip_gain(1, 0, f64::NAN).is_err());
+        session.set_clip_trim(1, 0, 0.5, 0.25).unwrap();
+        session.set_clip_gain(1, 0, -3.0).unwrap();
+
+        let project = crate::project_io::ProjectFile::new(session.tracks.clone(), session.config.clone(), None);
+        let loaded: crate::project_io::ProjectFile =
+            serde_json::from_str(&serde_json::to_string(&project).unwrap()).unwrap();
+        assert_eq!(loaded.tracks[1].clips[0].offset_override_s, Some(2.5));
+        let clip = &loaded.tracks[1].clips[0];
+        assert_eq!((clip.trim_in_s, clip.trim_out_s, clip.gain_db), (0.5, 0.25, -3.0));
+
+        // Released, the clip goes back to where its audio belongs.
+        session.set_clip_offset(1, 0, None).unwrap();
+        session.analyze(&None, &None).unwrap();
+        assert!((session.tracks[1].clips[0].timeline_offset_samples - 8000).abs() <= 2);
+    }
+
+    #[test]
+    fn test_export_streams_what_sync_stitches() {
+        let dir = std::env::temp_dir().join(format!("audiosync_export_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let signal: Vec<f32> = noise(21, ANALYSIS_SR as usize * 10).into_iter().map(|s| 0.5 * s).collect();
+        let wav = |name: &str, samples: &[f32]| {
+            let path = dir.join(name).to_string_lossy().to_string();
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: ANALYSIS_SR,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            samples.iter().for_each(|&s| writer.write_sample(s).unwrap());
+            writer.finalize().unwrap();
+            let mut clip = noise_clip(&path, samples.to_vec());
+            clip.original_sr = ANALYSIS_SR;
+            clip
+        };
+
+        let mut session = SessionController::default();
+        session.create_track("Ref".into());
+        session.create_track("Rec".into());
+        session.add_clips(0, vec![wav("ref.wav", &signal)]).unwrap();
+        // Two takes overlapping across a block join.
+        session
+            .add_clips(1, vec![wav("a.wav", &signal[..70000]), wav("b.wav", &signal[60000..75000])])
+            .unwrap();
+        session.config.export_sr = Some(ANALYSIS_SR);
+        session.config.export_bit_depth = 32;
+        session.config.verify_export = true;
+        session.analyze(&None, &None).unwrap();
+
+        let request = ExportRequest {
+            output_dir: dir.join("out").to_string_lossy().to_string(),
+            project_name: "Streamed".into(),
+            ..Default::default()
+        };
+        let outcome = session.export(&request, &None, &None).unwrap();
+        assert_eq!(outcome.files.len(), 2);
+        assert!(outcome.verification.iter().all(|v| v.ok), "{:?}", outcome.verification);
+        assert!(session.tracks.iter().all(|t| t.synced_audio.is_none()));
+
+        let mut tracks = session.tracks.clone();
+        let mut config = session.config.clone();
+        engine::sync(&mut tracks, session.result.as_ref().unwrap(), &mut config, &None, &None).unwrap();
+        for (track, file) in tracks.iter().zip(&outcome.files) {
+            let written: Vec<f32> =
+                hound::WavReader::open(file).unwrap().samples::<f32>().map(Result::unwrap).collect();
+            let stitched = track.synced_audio.as_ref().unwrap();
+            assert!(stitched.len() > engine::STITCH_BLOCK);
+            assert_eq!(written.len(), stitched.len());
+            assert!(written.iter().zip(stitched).all(|(&w, &s)| w == s as f32), "{}", track.name);
+        }
+
+        // The second take alone: silence up to where it starts, then the take.
+        let punch = session.export_clip(1, 1, &request, &None, &None).unwrap();
+        assert!(punch.files[0].ends_with(&format!("Rec_b_{ANALYSIS_SR}.wav")), "{:?}", punch.files);
+        let written: Vec<f32> =
+            hound::WavReader::open(&punch.files[0]).unwrap().samples::<f32>().map(Result::unwrap).collect();
+        assert_eq!(written.len(), 75000);
+        assert!(written[..60000].iter().all(|&w| w == 0.0));
+        assert_eq!(&written[60000..], &signal[60000..75000]);
+        assert!(session.export_clip(1, 2, &request, &None, &None).is_err());
+
+        // Per-clip regions: one media file per clip, each the whole take.
+        let aaf = dir.join("out").join("regions.aaf");
+        let regions = session.export_aaf(&aaf.to_string_lossy(), &request, &None, &None).unwrap();
+        assert_eq!(regions.files.len(), 3);
+        assert!(regions.files.iter().all(|f| f.contains("regions_media")), "{:?}", regions.files);
+        let media: Vec<f32> =
+            hound::WavReader::open(&regions.files[2]).unwrap().samples::<f32>().map(Result::unwrap).collect();
+        assert_eq!(&media[..], &signal[60000..75000]);
+        assert!(cfb::open(&aaf).unwrap().is_storage("/Header-2"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sync_groups_analyze_separately() {
+        let sr = ANALYSIS_SR as usize;
+        let (room_a, room_b) = (noise(11, sr * 6), noise(23, sr * 4));
+
+        let mut session = SessionController::default();
+        for name in ["Room A", "Cam A", "Room B", "Cam B"] {
+            session.create_track(name.into());
+        }
+        session.add_clips(0, vec![noise_clip("a.wav", room_a.clone())]).unwrap();
+        session.add_clips(1, vec![noise_clip("cam_a.wav", room_a[8000..32000].to_vec())]).unwrap();
+        session.add_clips(2, vec![noise_clip("b.wav", room_b.clone())]).unwrap();
+        session.add_clips(3, vec![noise_clip("cam_b.wav", room_b[4000..20000].to_vec())]).unwrap();
+        session.set_track_sync_group_by_name("Room B", "Room B").unwrap();
+        session.set_track_sync_group(3, Some(" Room B ".into())).unwrap();
+
+        let result = session.analyze(&None, &None).unwrap().clone();
+        let names: Vec<&str> = result.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["default", "Room B"]);
+        assert_eq!(result.groups[1].track_indices, [2, 3]);
+        assert!(result.groups[1].track_indices.contains(&result.groups[1].reference_track_index));
+        assert!((result.clip_offsets["cam_a.wav"] - 8000).abs() <= 2);
+        assert!((result.clip_offsets["cam_b.wav"] - 4000).abs() <= 2);
+        assert!((result.timeline_s_for(1) - 6.0).abs() < 0.01);
+        assert!((result.timeline_s_for(3) - 4.0).abs() < 0.01);
+
+        let project = crate::project_io::ProjectFile::new(session.tracks.clone(), session.config.clone(), None);
+        let loaded: crate::project_io::ProjectFile =
+            serde_json::from_str(&serde_json::to_string(&project).unwrap()).unwrap();
+        assert_eq!(loaded.tracks[3].sync_group.as_deref(), Some("Room B"));
+        assert_eq!(loaded.tracks[0].sync_group_name(), DEFAULT_SYNC_GROUP);
+
+        assert_eq!(group_path("out/edit.fcpxml", "Room B"), "out/edit_Room_B.fcpxml");
+    }
+
+    #[test]
+    fn test_reuse_analysis_from_another_project() {
+        let signal = noise(9, ANALYSIS_SR as usize * 6);
+        let cache_dir = std::env::temp_dir().join(format!("audiosync_reuse_{}", uuid::Uuid::new_v4()));
+        let config = SyncConfig {
+            analysis_cache_dir: Some(cache_dir.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let project = || {
+            let mut session = SessionController::new(config.clone());
+            for (name, samples) in [("ref.wav", signal.clone()), ("cam.wav", signal[8000..32000].to_vec())] {
+                let mut clip = noise_clip(name, samples);
+                clip.content_hash = Some(audio_io::hash_samples(&clip.samples));
+                session.create_track(name.into());
+                let last = session.tracks.len() - 1;
+                session.add_clips(last, vec![clip]).unwrap();
+            }
+            session
+        };
+
+        let mut first = project();
+        assert!(first.reusable_analyses().is_empty());
+        first.analyze(&None, &None).unwrap();
+
+        let mut second = project();
+        let reusable = second.reusable_analyses();
+        assert_eq!(reusable.len(), 1);
+        assert_eq!(reusable[0].clip_ids.len(), 2);
+        assert_eq!(second.reuse_prior_analysis(PriorReuse::Skip).unwrap(), 2);
+        let cam = &second.tracks[1].clips[0];
+        assert!(cam.analyzed);
+        assert!((cam.timeline_offset_samples - second.tracks[0].clips[0].timeline_offset_samples - 8000).abs() <= 2);
+
+        // Nothing left to correlate: incremental analysis keeps the placement.
+        second.config.incremental = true;
+        let result = second.analyze(&None, &None).unwrap();
+        assert!((result.clip_offsets["cam.wav"] - result.clip_offsets["ref.wav"] - 8000).abs() <= 2);
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+}
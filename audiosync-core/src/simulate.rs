@@ -0,0 +1,130 @@
+//! Simulated long operations — progress, cancellation and failure without
+//! real media, for developing front ends and testing their progress, cancel
+//! and queue handling. Front ends expose this in debug builds only.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::models::*;
+
+/// How often a simulated step checks for cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A simulated operation: `steps` items spread evenly over `duration_s`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulatedJob {
+    pub duration_s: f64,
+    pub steps: usize,
+    /// Cancel itself once this many steps are done, as if the user had
+    /// pressed Cancel — a reproducible cancellation for tests.
+    pub cancel_after: Option<usize>,
+    /// Fail with an error once this many steps are done.
+    pub fail_after: Option<usize>,
+}
+
+impl Default for SimulatedJob {
+    fn default() -> Self {
+        Self {
+            duration_s: 10.0,
+            steps: 20,
+            cancel_after: None,
+            fail_after: None,
+        }
+    }
+}
+
+/// Phases a simulated job passes through, an equal share of steps each.
+const PHASES: [ProgressPhase; 4] = [
+    ProgressPhase::Preparing,
+    ProgressPhase::Correlating,
+    ProgressPhase::MeasuringDrift,
+    ProgressPhase::Stitching,
+];
+
+/// Run `job`, reporting progress like a real analysis or export. Returns the
+/// number of steps done; a `CancelledError` when `cancel` is set (or
+/// `cancel_after` is reached), like the real operations.
+pub fn run_simulated_job(
+    job: &SimulatedJob,
+    progress: &Option<ProgressCallback>,
+    cancel: &Option<CancelToken>,
+) -> Result<usize> {
+    let steps = job.steps.max(1);
+    let step_time = Duration::from_secs_f64((job.duration_s / steps as f64).max(0.0));
+    let progress = ProgressReporter::new(progress);
+
+    for step in 1..=steps {
+        let phase = PHASES[(step - 1) * PHASES.len() / steps];
+        progress.report(
+            phase,
+            (step - 1) as f64 / steps as f64,
+            (step, steps),
+            &format!("Simulated step {} of {}...", step, steps),
+        );
+        let started = std::time::Instant::now();
+        while started.elapsed() < step_time {
+            check_cancelled(cancel)?;
+            std::thread::sleep(POLL_INTERVAL.min(step_time.saturating_sub(started.elapsed())));
+        }
+        check_cancelled(cancel)?;
+
+        if job.cancel_after == Some(step) {
+            return Err(CancelledError("Operation cancelled".to_string()).into());
+        }
+        if job.fail_after == Some(step) {
+            bail!("Simulated failure after step {} of {}", step, steps);
+        }
+    }
+    progress.report(ProgressPhase::Done, 1.0, (0, 0), "Simulated job complete.");
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_simulated_job_progress_and_cancel() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let progress: Option<ProgressCallback> =
+            Some(Box::new(move |event: &ProgressEvent| sink.lock().unwrap().push(event.clone())));
+        let job = SimulatedJob {
+            duration_s: 0.04,
+            steps: 8,
+            ..Default::default()
+        };
+        assert_eq!(run_simulated_job(&job, &progress, &None).unwrap(), 8);
+        {
+            let events = events.lock().unwrap();
+            assert_eq!(events.len(), 9);
+            assert_eq!(events[0].phase, ProgressPhase::Preparing);
+            assert_eq!(events[7].phase, ProgressPhase::Stitching);
+            assert_eq!((events[8].phase, events[8].fraction), (ProgressPhase::Done, 1.0));
+        }
+
+        // A scripted cancel stops after its step, like a real one.
+        events.lock().unwrap().clear();
+        let cancelling = SimulatedJob {
+            cancel_after: Some(3),
+            ..job.clone()
+        };
+        let err = run_simulated_job(&cancelling, &progress, &None).unwrap_err();
+        assert!(err.downcast_ref::<CancelledError>().is_some());
+        assert_eq!(events.lock().unwrap().len(), 3);
+
+        let cancel = Some(new_cancel_token());
+        cancel.as_ref().unwrap().store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(run_simulated_job(&job, &None, &cancel).is_err());
+
+        let failing = SimulatedJob {
+            fail_after: Some(1),
+            ..job
+        };
+        let err = run_simulated_job(&failing, &None, &None).unwrap_err();
+        assert!(err.to_string().contains("Simulated failure"));
+    }
+}
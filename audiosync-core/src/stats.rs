@@ -0,0 +1,194 @@
+//! Project statistics for the dashboard — media duration, source sizes,
+//! per-track timeline coverage, analysis status and estimated export size.
+
+use serde::Serialize;
+
+use crate::audio_io::detect_project_sample_rate;
+use crate::models::{SyncConfig, SyncResult, Track};
+
+/// FLAC output as a fraction of the equivalent PCM size (typical for
+/// location recordings; music compresses less, room tone more).
+const FLAC_SIZE_RATIO: f64 = 0.6;
+
+/// Whole-project summary.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProjectStats {
+    pub track_count: usize,
+    pub clip_count: usize,
+    pub video_clip_count: usize,
+    /// Sum of all clip durations.
+    pub total_media_duration_s: f64,
+    /// Size of the source files on disk.
+    pub source_bytes: u64,
+    /// Clips whose source file could not be read.
+    pub missing_sources: usize,
+    pub analyzed_clips: usize,
+    /// Analysis has produced a timeline.
+    pub analyzed: bool,
+    /// Timeline length after analysis; before it, the longest track.
+    pub timeline_duration_s: f64,
+    /// Export sample rate and size with the current settings.
+    pub export_sample_rate: u32,
+    pub estimated_export_bytes: u64,
+    pub tracks: Vec<TrackStats>,
+}
+
+/// Per-track summary.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TrackStats {
+    pub name: String,
+    pub clip_count: usize,
+    pub media_duration_s: f64,
+    pub source_bytes: u64,
+    pub analyzed_clips: usize,
+    /// Timeline seconds covered by at least one clip (overlaps counted once).
+    pub coverage_s: f64,
+    /// `coverage_s` over the timeline length, 0–1.
+    pub coverage: f64,
+}
+
+/// Summarize `tracks`; `result` is the last analysis, if any.
+pub fn project_stats(tracks: &[Track], result: Option<&SyncResult>, config: &SyncConfig) -> ProjectStats {
+    let media_s = |t: &Track| t.clips.iter().map(|c| c.duration_s).sum::<f64>();
+    let timeline_s = match result {
+        Some(r) => r.total_timeline_s,
+        None => tracks.iter().map(media_s).fold(0.0, f64::max),
+    };
+
+    let mut stats = ProjectStats {
+        track_count: tracks.len(),
+        analyzed: result.is_some(),
+        timeline_duration_s: timeline_s,
+        ..Default::default()
+    };
+    for track in tracks {
+        let mut ts = TrackStats {
+            name: track.name.clone(),
+            clip_count: track.clips.len(),
+            media_duration_s: media_s(track),
+            ..Default::default()
+        };
+        for clip in &track.clips {
+            match std::fs::metadata(&clip.file_path) {
+                Ok(meta) => ts.source_bytes += meta.len(),
+                Err(_) => stats.missing_sources += 1,
+            }
+            if clip.analyzed {
+                ts.analyzed_clips += 1;
+            }
+            if clip.is_video {
+                stats.video_clip_count += 1;
+            }
+        }
+        if result.is_some() {
+            ts.coverage_s = covered_s(track);
+            if timeline_s > 0.0 {
+                ts.coverage = (ts.coverage_s / timeline_s).min(1.0);
+            }
+        }
+
+        stats.clip_count += ts.clip_count;
+        stats.total_media_duration_s += ts.media_duration_s;
+        stats.source_bytes += ts.source_bytes;
+        stats.analyzed_clips += ts.analyzed_clips;
+        stats.tracks.push(ts);
+    }
+
+    stats.export_sample_rate = config.export_sr.unwrap_or_else(|| detect_project_sample_rate(tracks));
+    // Every track exports one mono file spanning the whole timeline.
+    let per_track = estimate_export_bytes(timeline_s, stats.export_sample_rate, config);
+    stats.estimated_export_bytes = per_track * tracks.iter().filter(|t| !t.clips.is_empty()).count() as u64;
+    stats
+}
+
+/// Length of the union of a track's clip spans on the timeline.
+fn covered_s(track: &Track) -> f64 {
+    let mut spans: Vec<(f64, f64)> = track
+        .clips
+        .iter()
+        .filter(|c| c.analyzed)
+        .map(|c| (c.timeline_offset_s, c.timeline_offset_s + c.duration_s))
+        .collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut total = 0.0;
+    let mut current: Option<(f64, f64)> = None;
+    for (start, end) in spans {
+        current = match current {
+            Some((s, e)) if start <= e => Some((s, e.max(end))),
+            Some((s, e)) => {
+                total += e - s;
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    total + current.map_or(0.0, |(s, e)| e - s)
+}
+
+/// Size of one exported mono file of `duration_s`.
+fn estimate_export_bytes(duration_s: f64, sr: u32, config: &SyncConfig) -> u64 {
+    let pcm = duration_s * sr as f64 * (config.export_bit_depth as f64 / 8.0);
+    let bytes = match config.export_format.as_str() {
+        "mp3" => duration_s * config.export_bitrate_kbps as f64 * 1000.0 / 8.0,
+        "flac" => pcm * FLAC_SIZE_RATIO,
+        _ => pcm,
+    };
+    bytes.round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Clip;
+
+    #[test]
+    fn test_project_stats() {
+        let source = std::env::temp_dir().join(format!("audiosync_stats_{}.wav", uuid::Uuid::new_v4()));
+        std::fs::write(&source, vec![0u8; 1000]).unwrap();
+
+        let mut track = Track::new("Cam".into());
+        for (offset, duration) in [(0.0, 60.0), (30.0, 60.0), (120.0, 30.0)] {
+            let mut clip = Clip::new(source.to_string_lossy().to_string(), "a.wav".into(), 48000, 2);
+            clip.timeline_offset_s = offset;
+            clip.duration_s = duration;
+            clip.analyzed = true;
+            track.clips.push(clip);
+        }
+        track.clips[2].file_path = "/missing/c.wav".into();
+        let tracks = vec![track];
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 0,
+            total_timeline_s: 200.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+
+        let stats = project_stats(&tracks, Some(&result), &SyncConfig::default());
+        let _ = std::fs::remove_file(&source);
+
+        assert_eq!(stats.clip_count, 3);
+        assert_eq!(stats.total_media_duration_s, 150.0);
+        assert_eq!(stats.source_bytes, 2000);
+        assert_eq!(stats.missing_sources, 1);
+        // 0–90 s and 120–150 s.
+        assert_eq!(stats.tracks[0].coverage_s, 120.0);
+        assert_eq!(stats.tracks[0].coverage, 0.6);
+        // 200 s of 24-bit mono at 48 kHz.
+        assert_eq!(stats.estimated_export_bytes, 200 * 48000 * 3);
+
+        let unanalyzed = project_stats(&tracks, None, &SyncConfig::default());
+        assert!(!unanalyzed.analyzed);
+        assert_eq!(unanalyzed.timeline_duration_s, 150.0);
+        assert_eq!(unanalyzed.tracks[0].coverage_s, 0.0);
+    }
+}
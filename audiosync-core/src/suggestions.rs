@@ -0,0 +1,148 @@
+//! Suggestions for clips that end analysis below the confidence threshold.
+//!
+//! Each rule looks at one diagnostic — signal level, where the correlation
+//! peak landed, how distinct it is, and what the creation time says — and
+//! turns it into a remedy the user can act on.
+
+use crate::engine::peak_diagnostics;
+use crate::models::{Clip, ClipSuggestion, SuggestionKind};
+
+/// RMS below which a clip is treated as silent (about -80 dBFS).
+const SILENCE_RMS: f64 = 1e-4;
+
+/// Runner-up peak ratio above which the match is considered ambiguous.
+pub(crate) const AMBIGUOUS_PEAK_RATIO: f64 = 0.8;
+
+/// Slack (seconds) when checking whether a creation time falls inside the
+/// reference recording.
+const OVERLAP_SLACK_S: f64 = 60.0;
+
+/// Largest UTC offset worth suggesting as a time zone mistake.
+const MAX_TZ_HOURS: f64 = 14.0;
+
+/// Diagnose why `clip` matched `reference` poorly.
+///
+/// `ref_origin` is the reference track's earliest creation time, i.e. the
+/// time at sample 0 of `reference`. Always returns at least one suggestion.
+pub fn suggest_for_clip(
+    clip: &Clip,
+    reference: &[f32],
+    ref_origin: Option<f64>,
+    sr: u32,
+    max_offset_s: Option<f64>,
+) -> Vec<ClipSuggestion> {
+    let make = |kind: SuggestionKind, message: String| ClipSuggestion {
+        clip_id: clip.file_path.clone(),
+        kind,
+        message,
+    };
+
+    if rms(&clip.samples) < SILENCE_RMS {
+        return vec![make(
+            SuggestionKind::SilentClip,
+            "Clip is nearly silent — there is no audio to match; place it by hand".to_string(),
+        )];
+    }
+
+    let mut out = Vec::new();
+    let diag = peak_diagnostics(reference, &clip.samples, sr, max_offset_s);
+
+    if let (Some(outside_s), Some(max_s)) = (diag.stronger_outside_s, max_offset_s) {
+        out.push(make(
+            SuggestionKind::IncreaseMaxOffset,
+            format!(
+                "A much stronger match lies at {:+.1} s, outside the ±{:.0} s search window — increase max_offset",
+                outside_s, max_s
+            ),
+        ));
+    }
+
+    if let (Some(ct), Some(origin)) = (clip.creation_time, ref_origin) {
+        let span_s = reference.len() as f64 / sr as f64;
+        let in_span = |d: f64| d >= -OVERLAP_SLACK_S && d <= span_s + OVERLAP_SLACK_S;
+        let diff = ct - origin;
+        if !in_span(diff) {
+            let hours = (diff / 3600.0).round();
+            if hours != 0.0 && hours.abs() <= MAX_TZ_HOURS && in_span(diff - hours * 3600.0) {
+                out.push(make(
+                    SuggestionKind::CheckClockTimezone,
+                    format!(
+                        "Creation time is {:+.0} h off the reference recording — check the device clock's time zone",
+                        hours
+                    ),
+                ));
+            } else {
+                out.push(make(
+                    SuggestionKind::MayNotOverlap,
+                    "Creation time falls outside the reference recording — the clip may not overlap it"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+
+    if diag.runner_up_ratio >= AMBIGUOUS_PEAK_RATIO {
+        out.push(make(
+            SuggestionKind::RepetitiveContent,
+            "Several near-equal matches (repetitive content such as music) — narrow max_offset or lock a nearby clip"
+                .to_string(),
+        ));
+    }
+
+    if out.is_empty() {
+        out.push(make(
+            SuggestionKind::MayNotOverlap,
+            "No clear match — the clip may not overlap the reference recording".to_string(),
+        ));
+    }
+    out
+}
+
+fn rms(samples: &[f32]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&x| x as f64 * x as f64).sum::<f64>() / samples.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{noise, ANALYSIS_SR};
+
+    fn clip_with(samples: Vec<f32>) -> Clip {
+        let mut clip = Clip::new("/c.wav".into(), "c.wav".into(), 48000, 1);
+        clip.duration_s = samples.len() as f64 / ANALYSIS_SR as f64;
+        clip.samples = samples;
+        clip
+    }
+
+    #[test]
+    fn test_suggestions_for_failed_clips() {
+        let sr = ANALYSIS_SR;
+        let reference = noise(7, sr as usize * 60);
+
+        let silent = clip_with(vec![0.0; sr as usize * 5]);
+        let s = suggest_for_clip(&silent, &reference, None, sr, None);
+        assert_eq!(s[0].kind, SuggestionKind::SilentClip);
+
+        // Creation time three hours late, otherwise inside the reference.
+        let mut shifted = clip_with(noise(99, sr as usize * 5));
+        shifted.creation_time = Some(1_000.0 + 3.0 * 3600.0 + 10.0);
+        let s = suggest_for_clip(&shifted, &reference, Some(1_000.0), sr, None);
+        assert!(s.iter().any(|x| x.kind == SuggestionKind::CheckClockTimezone));
+        assert!(s.iter().all(|x| x.clip_id == "/c.wav"));
+
+        // True offset 40 s but only ±5 s searched.
+        let inside = clip_with(reference[sr as usize * 40..sr as usize * 45].to_vec());
+        let s = suggest_for_clip(&inside, &reference, None, sr, Some(5.0));
+        assert!(s.iter().any(|x| x.kind == SuggestionKind::IncreaseMaxOffset));
+
+        // A repeating loop matches equally well everywhere.
+        let bar = noise(3, sr as usize);
+        let looped: Vec<f32> = bar.iter().cycle().take(sr as usize * 20).copied().collect();
+        let phrase = clip_with(looped[..sr as usize * 4].to_vec());
+        let s = suggest_for_clip(&phrase, &looped, None, sr, None);
+        assert!(s.iter().any(|x| x.kind == SuggestionKind::RepetitiveContent));
+    }
+}
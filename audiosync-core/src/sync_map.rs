@@ -0,0 +1,325 @@
+//! Sync maps — the analysis alone (which media sits where on the timeline),
+//! without config, waveforms or comments.
+//!
+//! A map is meant for interchange with other tools, and for placing a fresh
+//! import of the same media without analyzing it again. Clips are identified
+//! by the SHA-256 of their media file, falling back on file name and
+//! duration when a hash is missing on either side.
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::audio_io::hash_file;
+use crate::models::{
+    DriftSegment, SyncResult, SyncWarning, Track, WarningCode, WarningSeverity, ANALYSIS_SR,
+};
+
+const SYNC_MAP_VERSION: u32 = 1;
+
+/// Durations within this of each other count as the same media when
+/// matching by file name.
+const DURATION_TOLERANCE_S: f64 = 0.5;
+
+/// Placement of every clip on the timeline, keyed by media identity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncMap {
+    pub version: u32,
+    pub app_version: String,
+    /// ISO-8601 timestamp of when the map was written.
+    pub saved_at: String,
+    /// Name of the track the timeline was built around.
+    pub reference_track: Option<String>,
+    pub timeline_s: f64,
+    pub clips: Vec<SyncMapClip>,
+}
+
+/// One placed clip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncMapClip {
+    pub track: String,
+    pub name: String,
+    pub file_path: String,
+    /// SHA-256 of the media file (hex); `None` when it couldn't be read.
+    pub sha256: Option<String>,
+    pub duration_s: f64,
+    /// Start of the clip on the timeline.
+    pub offset_s: f64,
+    #[serde(default)]
+    pub drift_ppm: f64,
+    #[serde(default)]
+    pub drift_segments: Vec<DriftSegment>,
+    #[serde(default)]
+    pub confidence: f64,
+    /// Match score (0–100).
+    #[serde(default)]
+    pub match_score: f64,
+}
+
+impl SyncMap {
+    /// Map of the clips `result` placed, hashing each media file.
+    pub fn new(tracks: &[Track], result: &SyncResult) -> Self {
+        let mut clips = Vec::new();
+        for track in tracks {
+            for clip in track.clips.iter().filter(|c| c.analyzed && c.on_timeline()) {
+                let sha256 = hash_file(&clip.file_path)
+                    .inspect_err(|e| warn!("Sync map: no hash for '{}': {:#}", clip.name, e))
+                    .ok();
+                clips.push(SyncMapClip {
+                    track: track.name.clone(),
+                    name: clip.name.clone(),
+                    file_path: clip.file_path.clone(),
+                    sha256,
+                    duration_s: clip.duration_s,
+                    offset_s: clip.timeline_offset_s,
+                    drift_ppm: clip.drift_ppm,
+                    drift_segments: clip.drift_segments.clone(),
+                    confidence: clip.confidence,
+                    match_score: clip.match_score,
+                });
+            }
+        }
+        Self {
+            version: SYNC_MAP_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            saved_at: chrono::Utc::now().to_rfc3339(),
+            reference_track: tracks.get(result.reference_track_index).map(|t| t.name.clone()),
+            timeline_s: result.total_timeline_s,
+            clips,
+        }
+    }
+}
+
+/// Save a sync map as JSON.
+pub fn save_sync_map(path: &str, map: &SyncMap) -> Result<()> {
+    let json = serde_json::to_string_pretty(map).context("Failed to serialize sync map")?;
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    std::fs::write(path, &json).with_context(|| format!("Failed to write sync map: {}", path))?;
+    info!("Sync map saved: {} ({} clips)", path, map.clips.len());
+    Ok(())
+}
+
+/// Load a sync map from JSON.
+pub fn load_sync_map(path: &str) -> Result<SyncMap> {
+    let json =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot read sync map: {}", path))?;
+    let map: SyncMap =
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse sync map: {}", path))?;
+    if map.version > SYNC_MAP_VERSION {
+        anyhow::bail!(
+            "Sync map version {} is newer than supported version {}. Please update AudioSync Pro.",
+            map.version,
+            SYNC_MAP_VERSION
+        );
+    }
+    Ok(map)
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map_or_else(|| path.to_string(), |n| n.to_string_lossy().to_string())
+        .to_lowercase()
+}
+
+/// Place `tracks` as `map` says, each map entry on at most one clip, and
+/// return the resulting timeline. A clip matches an entry with the same
+/// media hash, or — when either hash is missing — the same file name and
+/// duration. Clips the map doesn't cover are left unplaced, with a
+/// `NotInSession` warning.
+pub fn apply_sync_map(tracks: &mut [Track], map: &SyncMap) -> SyncResult {
+    let sr = ANALYSIS_SR;
+    let hashed = map.clips.iter().any(|e| e.sha256.is_some());
+    let mut by_hash: HashMap<&str, usize> = HashMap::new();
+    for (i, entry) in map.clips.iter().enumerate() {
+        if let Some(hash) = &entry.sha256 {
+            by_hash.entry(hash.as_str()).or_insert(i);
+        }
+    }
+    let mut used = vec![false; map.clips.len()];
+
+    let mut clip_offsets = HashMap::new();
+    let mut confidences = Vec::new();
+    let mut warnings = Vec::new();
+    let mut unplaced_clips = Vec::new();
+    let mut max_end = 0i64;
+    for track in tracks.iter_mut() {
+        for clip in &mut track.clips {
+            let local_hash = if hashed { hash_file(&clip.file_path).ok() } else { None };
+            let by_name = || {
+                let name = file_name(&clip.file_path);
+                map.clips.iter().enumerate().position(|(i, e)| {
+                    !used[i]
+                        && (e.sha256.is_none() || local_hash.is_none())
+                        && file_name(&e.file_path) == name
+                        && (e.duration_s - clip.duration_s).abs() <= DURATION_TOLERANCE_S
+                })
+            };
+            let found = local_hash
+                .as_deref()
+                .and_then(|h| by_hash.get(h).copied())
+                .filter(|&i| !used[i])
+                .or_else(by_name);
+
+            let Some(i) = found else {
+                clip.unplaced = true;
+                clip.analyzed = false;
+                clip.confidence = 0.0;
+                clip.match_score = 0.0;
+                warnings.push(
+                    SyncWarning::new(
+                        WarningCode::NotInSession,
+                        WarningSeverity::Warning,
+                        format!("'{}' is not in the sync map; left unplaced", clip.name),
+                    )
+                    .for_clip(&clip.file_path),
+                );
+                unplaced_clips.push(clip.file_path.clone());
+                continue;
+            };
+            used[i] = true;
+            let entry = &map.clips[i];
+            clip.unplaced = false;
+            clip.analyzed = true;
+            clip.timeline_offset_s = entry.offset_s;
+            clip.timeline_offset_samples = (entry.offset_s * sr as f64).round() as i64;
+            clip.confidence = entry.confidence;
+            clip.match_score = entry.match_score;
+            if !clip.drift_override {
+                clip.drift_ppm = entry.drift_ppm;
+                clip.drift_segments = entry.drift_segments.clone();
+                clip.drift_inherited = false;
+            }
+            clip_offsets.insert(clip.file_path.clone(), clip.timeline_offset_samples);
+            confidences.push(clip.confidence);
+            max_end = max_end.max(clip.end_samples());
+        }
+    }
+
+    let reference_track_index = map
+        .reference_track
+        .as_deref()
+        .and_then(|name| tracks.iter().position(|t| t.name == name))
+        .unwrap_or(0);
+    for (ti, track) in tracks.iter_mut().enumerate() {
+        track.is_reference = ti == reference_track_index;
+    }
+    let drift_detected = tracks
+        .iter()
+        .flat_map(|t| &t.clips)
+        .any(|c| c.on_timeline() && c.drift_ppm != 0.0);
+    info!(
+        "Sync map applied: {} clips placed, {} not in the map",
+        clip_offsets.len(),
+        unplaced_clips.len()
+    );
+
+    SyncResult {
+        reference_track_index,
+        total_timeline_samples: max_end,
+        total_timeline_s: max_end as f64 / sr as f64,
+        sample_rate: sr,
+        clip_offsets,
+        avg_confidence: if confidences.is_empty() {
+            0.0
+        } else {
+            confidences.iter().sum::<f64>() / confidences.len() as f64
+        },
+        drift_detected,
+        warnings,
+        suggestions: Vec::new(),
+        groups: Vec::new(),
+        proposed_groups: Vec::new(),
+        unplaced_clips,
+        diagnostics: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Clip;
+
+    fn temp_media(name: &str, contents: &[u8]) -> String {
+        let dir = std::env::temp_dir().join(format!("audiosync_map_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_sync_map_places_fresh_import() {
+        let zoom = temp_media("ZOOM0001.WAV", b"zoom take");
+        let cam = temp_media("C0001.MP4", b"camera take");
+        let clip = |path: &str, name: &str, offset_s: f64| {
+            let mut clip = Clip::new(path.to_string(), name.into(), 48000, 2);
+            clip.duration_s = 60.0;
+            clip.analyzed = true;
+            clip.match_score = 90.0;
+            clip.timeline_offset_s = offset_s;
+            clip.timeline_offset_samples = (offset_s * ANALYSIS_SR as f64) as i64;
+            clip
+        };
+        let mut tracks = vec![Track::new("Zoom".into()), Track::new("Cam".into())];
+        tracks[0].clips.push(clip(&zoom, "ZOOM0001.WAV", 0.0));
+        tracks[1].clips.push(clip(&cam, "C0001.MP4", 12.5));
+        tracks[1].clips[0].drift_ppm = 20.0;
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 580_000,
+            total_timeline_s: 72.5,
+            sample_rate: ANALYSIS_SR,
+            clip_offsets: HashMap::new(),
+            avg_confidence: 0.0,
+            drift_detected: true,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: HashMap::new(),
+        };
+        let mut map = SyncMap::new(&tracks, &result);
+        assert_eq!(map.clips.len(), 2);
+        assert!(map.clips.iter().all(|c| c.sha256.is_some()));
+
+        // The same media under a new name is found by its hash; a clip the
+        // map doesn't know is left off the timeline.
+        let renamed = temp_media("renamed.mp4", b"camera take");
+        let extra = temp_media("extra.wav", b"something else");
+        let mut fresh = vec![Track::new("Cam".into()), Track::new("Zoom".into())];
+        fresh[0].clips.push(clip(&renamed, "renamed.mp4", 0.0));
+        fresh[1].clips.push(clip(&zoom, "ZOOM0001.WAV", 0.0));
+        fresh[1].clips.push(clip(&extra, "extra.wav", 0.0));
+        let placed = apply_sync_map(&mut fresh, &map);
+        assert_eq!(fresh[0].clips[0].timeline_offset_s, 12.5);
+        assert_eq!(fresh[0].clips[0].drift_ppm, 20.0);
+        assert_eq!(placed.reference_track_index, 1);
+        assert!(fresh[1].is_reference && !fresh[0].is_reference);
+        assert!(fresh[1].clips[1].unplaced);
+        assert_eq!(placed.unplaced_clips, vec![extra.clone()]);
+        assert_eq!(placed.warnings[0].code, WarningCode::NotInSession);
+        assert!(placed.drift_detected);
+        assert_eq!(placed.total_timeline_samples, fresh[0].clips[0].end_samples());
+
+        // Without hashes, the file name and duration identify the clip.
+        for entry in &mut map.clips {
+            entry.sha256 = None;
+        }
+        let mut fresh = vec![Track::new("Cam".into())];
+        fresh[0].clips.push(clip("/elsewhere/c0001.mp4", "c0001.mp4", 0.0));
+        fresh[0].clips.push(clip("/elsewhere/renamed.mp4", "renamed.mp4", 0.0));
+        apply_sync_map(&mut fresh, &map);
+        assert_eq!(fresh[0].clips[0].timeline_offset_s, 12.5);
+        assert!(fresh[0].clips[1].unplaced);
+
+        for path in [&zoom, &cam, &renamed, &extra] {
+            let _ = std::fs::remove_dir_all(Path::new(path).parent().unwrap());
+        }
+    }
+}
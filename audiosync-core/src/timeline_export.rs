@@ -1,29 +1,120 @@
-//! Timeline export — FCPXML and EDL generation.
+//! Timeline export — FCPXML, EDL and web timeline JSON generation.
 //!
 //! Produces industry-standard timeline formats for NLE import
-//! (Final Cut Pro, DaVinci Resolve, Premiere Pro, etc.).
+//! (Final Cut Pro, DaVinci Resolve, Premiere Pro, etc.), plus a JSON layout
+//! for browser-based review players.
 
 use anyhow::Result;
 use log::info;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-use crate::models::{SyncResult, Track};
+use crate::audio_io::detect_project_sample_rate;
+use crate::metadata::probe_video_info;
+use crate::models::{SyncResult, Track, ANALYSIS_SR, CONFORM_WARN_FRAMES};
+
+// ---------------------------------------------------------------------------
+//  Sequence format
+// ---------------------------------------------------------------------------
+
+/// Sequence format written to the FCPXML `<format>` resource.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FcpxmlFormat {
+    /// Frame rate as a rational (e.g. 30000/1001 for 29.97).
+    pub fps_num: u32,
+    pub fps_den: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Sequence audio sample rate in Hz.
+    pub audio_rate: u32,
+}
+
+impl Default for FcpxmlFormat {
+    fn default() -> Self {
+        Self {
+            fps_num: 30000,
+            fps_den: 1001,
+            width: 1920,
+            height: 1080,
+            audio_rate: 48000,
+        }
+    }
+}
+
+impl FcpxmlFormat {
+    /// Defaults from the first video clip (via ffprobe) and the project's
+    /// most common audio rate; 1080p29.97 if there is no probeable video.
+    pub fn detect(tracks: &[Track]) -> Self {
+        let mut format = Self {
+            audio_rate: detect_project_sample_rate(tracks),
+            ..Default::default()
+        };
+        let first_video = tracks
+            .iter()
+            .flat_map(|t| t.clips.iter())
+            .filter(|c| c.is_video)
+            .find_map(|c| probe_video_info(&c.file_path));
+        if let Some((width, height, fps_num, fps_den)) = first_video {
+            format.width = width;
+            format.height = height;
+            format.fps_num = fps_num;
+            format.fps_den = fps_den;
+        }
+        format
+    }
+
+    /// Set the frame rate from a decimal value, mapping NTSC rates
+    /// (23.976, 29.97, 59.94) to their exact x000/1001 rationals.
+    pub fn with_fps(mut self, fps: f64) -> Self {
+        let ntsc = [(23.976, 24000), (29.97, 30000), (47.952, 48000), (59.94, 60000)];
+        if let Some(&(_, num)) = ntsc.iter().find(|(r, _)| (fps - r).abs() < 0.01) {
+            self.fps_num = num;
+            self.fps_den = 1001;
+        } else if (fps - fps.round()).abs() < 1e-6 {
+            self.fps_num = fps.round() as u32;
+            self.fps_den = 1;
+        } else {
+            self.fps_num = (fps * 1000.0).round() as u32;
+            self.fps_den = 1000;
+        }
+        self
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.fps_num as f64 / self.fps_den.max(1) as f64
+    }
+
+    /// FCPXML `audioRate` attribute value (e.g. "48k").
+    fn audio_rate_attr(&self) -> &'static str {
+        match self.audio_rate {
+            32000 => "32k",
+            44100 => "44.1k",
+            88200 => "88.2k",
+            96000 => "96k",
+            176400 => "176.4k",
+            192000 => "192k",
+            _ => "48k",
+        }
+    }
+}
 
 // ---------------------------------------------------------------------------
 //  FCPXML v1.11 (Final Cut Pro / DaVinci Resolve)
 // ---------------------------------------------------------------------------
 
 /// Generate FCPXML v1.11 from analyzed tracks and write to a file.
+///
+/// `format` defaults to `FcpxmlFormat::detect(tracks)`.
 pub fn export_fcpxml(
     tracks: &[Track],
     result: &SyncResult,
     output_path: &str,
     project_name: Option<&str>,
+    format: Option<&FcpxmlFormat>,
 ) -> Result<String> {
     let name = project_name.unwrap_or("AudioSync Pro");
     let timeline_dur = result.total_timeline_s;
-    let fps_num = 30000; // 29.97 NDF
-    let fps_den = 1001;
+    let format = format.cloned().unwrap_or_else(|| FcpxmlFormat::detect(tracks));
 
     let mut xml = String::new();
     xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
@@ -34,8 +125,8 @@ pub fn export_fcpxml(
     // Format resource
     xml.push_str(&format!(
         "    <format id=\"r1\" name=\"FFVideoFormatRateUndefined\" \
-         frameDuration=\"{}/{}s\" width=\"1920\" height=\"1080\"/>\n",
-        fps_den, fps_num
+         frameDuration=\"{}/{}s\" width=\"{}\" height=\"{}\"/>\n",
+        format.fps_den, format.fps_num, format.width, format.height
     ));
 
     // Asset resources for each clip
@@ -43,15 +134,17 @@ pub fn export_fcpxml(
     let mut asset_map: Vec<(usize, usize, usize)> = Vec::new(); // (track_idx, clip_idx, asset_id)
 
     for (ti, track) in tracks.iter().enumerate() {
-        for (ci, clip) in track.clips.iter().enumerate() {
+        for (ci, clip) in track.clips.iter().enumerate().filter(|(_, c)| c.on_timeline()) {
             asset_id += 1;
             xml.push_str(&format!(
                 "    <asset id=\"r{}\" name=\"{}\" src=\"file://{}\" \
-                 start=\"0s\" duration=\"{:.6}s\" hasAudio=\"1\"/>\n",
+                 start=\"0s\" duration=\"{:.6}s\" hasAudio=\"1\" \
+                 audioRate=\"{}\"/>\n",
                 asset_id,
                 escape_xml(&clip.name),
                 escape_xml(&clip.file_path),
                 clip.duration_s,
+                clip.original_sr,
             ));
             asset_map.push((ti, ci, asset_id));
         }
@@ -71,8 +164,9 @@ pub fn export_fcpxml(
     ));
     xml.push_str(&format!(
         "        <sequence format=\"r1\" duration=\"{:.6}s\" tcStart=\"0s\" \
-         tcFormat=\"NDF\">\n",
-        timeline_dur
+         tcFormat=\"NDF\" audioRate=\"{}\">\n",
+        timeline_dur,
+        format.audio_rate_attr()
     ));
     xml.push_str("          <spine>\n");
 
@@ -80,9 +174,13 @@ pub fn export_fcpxml(
     struct PlacedClip {
         lane: i32,
         offset_s: f64,
+        /// Duration on the timeline (shorter/longer than the source when retimed).
         dur_s: f64,
+        /// `(timeline, source)` retime points; empty when not retimed.
+        time_map: Vec<(f64, f64)>,
         aid: usize,
         name: String,
+        role: &'static str,
     }
 
     let mut primary_clips: Vec<PlacedClip> = Vec::new();
@@ -90,18 +188,24 @@ pub fn export_fcpxml(
 
     for (ti, track) in tracks.iter().enumerate() {
         let lane = ti as i32;
-        for (ci, clip) in track.clips.iter().enumerate() {
+        let role = track.role().fcpxml_role();
+        for (ci, clip) in track.clips.iter().enumerate().filter(|(_, c)| c.on_timeline()) {
             let aid = asset_map
                 .iter()
                 .find(|&&(t, c, _)| t == ti && c == ci)
                 .map(|&(_, _, a)| a)
                 .unwrap_or(2);
+            // The NLE still gets the original file, so a drift-corrected
+            // clip is retimed to match the corrected audio.
+            let time_map = clip.drift_time_map();
             let placed = PlacedClip {
                 lane,
                 offset_s: clip.timeline_offset_s,
-                dur_s: clip.duration_s,
+                dur_s: time_map.last().map_or(clip.duration_s, |&(t, _)| t),
+                time_map,
                 aid,
                 name: clip.name.clone(),
+                role,
             };
             if lane == 0 {
                 primary_clips.push(placed);
@@ -132,13 +236,15 @@ pub fn export_fcpxml(
             ));
         }
         xml.push_str(&format!(
-            "            <asset-clip ref=\"r{}\" name=\"{}\" \
-             offset=\"{:.6}s\" duration=\"{:.6}s\" start=\"0s\"/>\n",
+            "            <asset-clip ref=\"r{}\" name=\"{}\" audioRole=\"{}\" \
+             offset=\"{:.6}s\" duration=\"{:.6}s\" start=\"0s\"",
             pc.aid,
             escape_xml(&pc.name),
+            pc.role,
             pc.offset_s,
             pc.dur_s,
         ));
+        push_retime_and_close(&mut xml, &pc.time_map);
         cursor = pc.offset_s + pc.dur_s;
     }
 
@@ -155,15 +261,17 @@ pub fn export_fcpxml(
     // Connected clips (lane > 0) — placed with offset and lane attribute
     for cc in &connected_clips {
         xml.push_str(&format!(
-            "            <asset-clip ref=\"r{}\" name=\"{}\" \
+            "            <asset-clip ref=\"r{}\" name=\"{}\" audioRole=\"{}\" \
              offset=\"{:.6}s\" duration=\"{:.6}s\" start=\"0s\" \
-             lane=\"{}\"/>\n",
+             lane=\"{}\"",
             cc.aid,
             escape_xml(&cc.name),
+            cc.role,
             cc.offset_s,
             cc.dur_s,
             cc.lane,
         ));
+        push_retime_and_close(&mut xml, &cc.time_map);
     }
 
     xml.push_str("          </spine>\n");
@@ -173,6 +281,12 @@ pub fn export_fcpxml(
     xml.push_str("  </library>\n");
     xml.push_str("</fcpxml>\n");
 
+    if is_fcpxml_bundle(output_path) {
+        write_fcpxml_bundle(output_path, &xml)?;
+        info!("FCPXML bundle exported: {}", output_path);
+        return Ok(output_path.to_string());
+    }
+
     if let Some(parent) = Path::new(output_path).parent() {
         std::fs::create_dir_all(parent).ok();
     }
@@ -181,6 +295,45 @@ pub fn export_fcpxml(
     Ok(output_path.to_string())
 }
 
+/// Whether `path` names an `.fcpxmld` bundle (preferred by FCP 10.6+).
+fn is_fcpxml_bundle(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("fcpxmld"))
+}
+
+/// Write the bundle directory: `Info.fcpxml` plus a minimal `Info.plist`.
+fn write_fcpxml_bundle(bundle_path: &str, xml: &str) -> Result<()> {
+    let bundle = Path::new(bundle_path);
+    std::fs::create_dir_all(bundle)?;
+    std::fs::write(bundle.join("Info.fcpxml"), xml)?;
+
+    let plist = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \t<key>CFBundleName</key>\n\
+         \t<string>{}</string>\n\
+         \t<key>CFBundleShortVersionString</key>\n\
+         \t<string>1.11</string>\n\
+         \t<key>CreatedBy</key>\n\
+         \t<string>AudioSync Pro {}</string>\n\
+         </dict>\n\
+         </plist>\n",
+        escape_xml(
+            &bundle
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+        ),
+        env!("CARGO_PKG_VERSION"),
+    );
+    std::fs::write(bundle.join("Info.plist"), plist)?;
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 //  EDL (CMX 3600 format)
 // ---------------------------------------------------------------------------
@@ -203,7 +356,7 @@ pub fn export_edl(
     let mut event_num = 1;
 
     for track in tracks {
-        for clip in &track.clips {
+        for clip in track.clips.iter().filter(|c| c.on_timeline()) {
             let src_in = "00:00:00:00".to_string();
             let src_out = seconds_to_timecode(clip.duration_s, fps);
             let rec_in = seconds_to_timecode(clip.timeline_offset_s, fps);
@@ -239,6 +392,25 @@ pub fn export_edl(
                     clip.drift_ppm, clip.drift_confidence
                 ));
             }
+            if clip.drift_segments.len() > 1 {
+                let segments: Vec<String> = clip
+                    .drift_segments
+                    .iter()
+                    .map(|s| format!("{:.2} ppm from {:.1} s", s.ppm, s.start_s))
+                    .collect();
+                lines.push(format!("* DRIFT SEGMENTS: {}", segments.join(", ")));
+            }
+            // CMX 3600 can't retime, so the picture keeps the camera's clock
+            // while the exported audio is corrected.
+            if clip.is_video && clip.drift_corrected {
+                let frames = clip.conform_error_frames(fps);
+                if frames.abs() >= CONFORM_WARN_FRAMES {
+                    lines.push(format!(
+                        "* WARNING: VIDEO NOT RETIMED, {:.1} FRAMES OFF THE CORRECTED AUDIO AT CLIP END",
+                        frames.abs()
+                    ));
+                }
+            }
 
             lines.push(String::new());
             event_num += 1;
@@ -254,10 +426,172 @@ pub fn export_edl(
     Ok(output_path.to_string())
 }
 
+// ---------------------------------------------------------------------------
+//  Timeline JSON (web review players)
+// ---------------------------------------------------------------------------
+
+/// Bumped when the layout of [`WebTimeline`] changes incompatibly.
+pub const TIMELINE_JSON_VERSION: u32 = 1;
+
+/// Waveform resolution of the exported peak arrays.
+pub const TIMELINE_PEAKS_PER_SECOND: u32 = 50;
+
+/// A synced multicam layout, ready for a web player to draw and play.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebTimeline {
+    pub version: u32,
+    pub title: String,
+    pub duration_s: f64,
+    /// Peaks per second of every clip's `peaks` array.
+    pub peaks_per_second: u32,
+    pub tracks: Vec<WebTimelineTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebTimelineTrack {
+    pub name: String,
+    pub is_reference: bool,
+    pub clips: Vec<WebTimelineClip>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebTimelineClip {
+    pub id: String,
+    pub name: String,
+    /// Relative to the JSON file when the media sits beside or below it,
+    /// otherwise a `file://` URL.
+    pub media_url: String,
+    pub offset_s: f64,
+    pub duration_s: f64,
+    pub confidence: f64,
+    pub drift_ppm: f64,
+    /// Absolute peak per bucket, 0–1, rounded to three decimals.
+    pub peaks: Vec<f32>,
+}
+
+/// Generate a web timeline JSON from analyzed tracks and write to a file.
+pub fn export_timeline_json(
+    tracks: &[Track],
+    result: &SyncResult,
+    output_path: &str,
+    title: Option<&str>,
+) -> Result<String> {
+    let json_dir = match Path::new(output_path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let bucket = (ANALYSIS_SR / TIMELINE_PEAKS_PER_SECOND) as usize;
+    let timeline = WebTimeline {
+        version: TIMELINE_JSON_VERSION,
+        title: title.unwrap_or("AudioSync Pro").to_string(),
+        duration_s: result.total_timeline_s,
+        peaks_per_second: TIMELINE_PEAKS_PER_SECOND,
+        tracks: tracks
+            .iter()
+            .map(|track| WebTimelineTrack {
+                name: track.name.clone(),
+                is_reference: track.is_reference,
+                clips: track
+                    .clips
+                    .iter()
+                    .filter(|clip| clip.on_timeline())
+                    .map(|clip| WebTimelineClip {
+                        id: clip.file_path.clone(),
+                        name: clip.name.clone(),
+                        media_url: media_url(&clip.file_path, json_dir),
+                        offset_s: clip.timeline_offset_s,
+                        duration_s: clip.duration_s,
+                        confidence: clip.confidence,
+                        drift_ppm: clip.drift_ppm,
+                        peaks: peak_envelope(&clip.samples, bucket),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    if let Some(parent) = Path::new(output_path).parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    std::fs::write(output_path, serde_json::to_string(&timeline)?)?;
+    info!("Timeline JSON exported: {}", output_path);
+    Ok(output_path.to_string())
+}
+
+/// Max absolute sample per `bucket` samples.
+fn peak_envelope(samples: &[f32], bucket: usize) -> Vec<f32> {
+    samples
+        .chunks(bucket.max(1))
+        .map(|chunk| {
+            let peak = chunk.iter().fold(0.0f32, |m, s| m.max(s.abs())).min(1.0);
+            (peak * 1000.0).round() / 1000.0
+        })
+        .collect()
+}
+
+/// Where a player finds `file_path`, seen from the directory of the JSON.
+fn media_url(file_path: &str, json_dir: &Path) -> String {
+    let path = Path::new(file_path);
+    let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let base = std::fs::canonicalize(json_dir).unwrap_or_else(|_| json_dir.to_path_buf());
+    if let Ok(relative) = absolute.strip_prefix(&base) {
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| percent_encode(&c.as_os_str().to_string_lossy(), b""))
+            .collect();
+        return parts.join("/");
+    }
+    file_url(&absolute)
+}
+
+/// `file://` URL of an absolute path.
+pub(crate) fn file_url(absolute: &Path) -> String {
+    let mut url = String::from("file://");
+    if !absolute.starts_with("/") {
+        // Windows drive paths: file:///C:/...
+        url.push('/');
+    }
+    url.push_str(&percent_encode(&absolute.to_string_lossy().replace('\\', "/"), b"/:"));
+    url
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters and `keep`.
+fn percent_encode(s: &str, keep: &[u8]) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) || keep.contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
 // ---------------------------------------------------------------------------
 //  Helpers
 // ---------------------------------------------------------------------------
 
+/// Close an open `<asset-clip` tag, adding a linear `timeMap` (one point
+/// per drift segment boundary) when retimed.
+fn push_retime_and_close(xml: &mut String, time_map: &[(f64, f64)]) {
+    if time_map.is_empty() {
+        xml.push_str("/>\n");
+        return;
+    }
+    xml.push_str(">\n");
+    xml.push_str("              <timeMap>\n");
+    xml.push_str("                <timept time=\"0s\" value=\"0s\" interp=\"linear\"/>\n");
+    for &(time_s, src_s) in &time_map[1..] {
+        xml.push_str(&format!(
+            "                <timept time=\"{:.6}s\" value=\"{:.6}s\" interp=\"linear\"/>\n",
+            time_s, src_s
+        ));
+    }
+    xml.push_str("              </timeMap>\n");
+    xml.push_str("            </asset-clip>\n");
+}
+
 fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -293,6 +627,7 @@ fn sanitize_edl_reel(name: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::Clip;
 
     #[test]
     fn test_timecode() {
@@ -305,6 +640,203 @@ mod tests {
         assert_eq!(escape_xml("a<b>c&d"), "a&lt;b&gt;c&amp;d");
     }
 
+    #[test]
+    fn test_fcpxml_retimes_drift_corrected_clips() {
+        let mut tracks = vec![Track::new("Cam".into()), Track::new("Rec".into())];
+        let mut cam = Clip::new("/m/cam.mov".into(), "cam.mov".into(), 48000, 2);
+        cam.duration_s = 100.0;
+        tracks[0].clips.push(cam);
+        let mut rec = Clip::new("/m/rec.wav".into(), "rec.wav".into(), 48000, 2);
+        rec.duration_s = 100.0;
+        rec.drift_ppm = 100.0;
+        rec.drift_corrected = true;
+        tracks[1].clips.push(rec);
+
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 800000,
+            total_timeline_s: 100.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 10.0,
+            drift_detected: true,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+        let path = std::env::temp_dir().join(format!("audiosync_retime_{}.fcpxml", uuid::Uuid::new_v4()));
+        export_fcpxml(&tracks, &result, path.to_str().unwrap(), None, None).unwrap();
+        let xml = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(xml.matches("<timeMap>").count(), 1);
+        assert!(xml.contains("time=\"99.990001s\" value=\"100.000000s\""));
+        assert!(xml.contains("duration=\"99.990001s\" start=\"0s\" lane=\"1\">"));
+        // "Cam" infers CAMERA, "Rec" falls back to dialogue
+        assert!(xml.contains("audioRole=\"Camera\""));
+        assert!(xml.contains("audioRole=\"dialogue\""));
+    }
+
+    #[test]
+    fn test_edl_flags_video_not_retimed() {
+        let mut tracks = vec![Track::new("Cam".into()), Track::new("Rec".into())];
+        for (ti, path, is_video) in [(0, "/m/cam.mov", true), (1, "/m/rec.wav", false)] {
+            let mut clip = Clip::new(path.into(), path[3..].into(), 48000, 2);
+            clip.is_video = is_video;
+            clip.duration_s = 600.0;
+            clip.drift_ppm = -100.0;
+            clip.drift_corrected = true;
+            tracks[ti].clips.push(clip);
+        }
+        let result = SyncResult {
+            reference_track_index: 1,
+            total_timeline_samples: 4_800_000,
+            total_timeline_s: 600.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 10.0,
+            drift_detected: true,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+        let path = std::env::temp_dir().join(format!("audiosync_conform_{}.edl", uuid::Uuid::new_v4()));
+        export_edl(&tracks, &result, path.to_str().unwrap(), None).unwrap();
+        let edl = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // 100 ppm over ten minutes is 60 ms: 1.8 frames at 29.97.
+        assert_eq!(edl.matches("VIDEO NOT RETIMED").count(), 1);
+        assert!(edl.contains("* WARNING: VIDEO NOT RETIMED, 1.8 FRAMES"));
+    }
+
+    #[test]
+    fn test_fcpxml_format_fps_and_output() {
+        let f = FcpxmlFormat::default().with_fps(23.976);
+        assert_eq!((f.fps_num, f.fps_den), (24000, 1001));
+        let f = f.with_fps(25.0);
+        assert_eq!((f.fps_num, f.fps_den), (25, 1));
+        assert!((FcpxmlFormat::default().with_fps(12.5).fps() - 12.5).abs() < 1e-9);
+
+        let format = FcpxmlFormat {
+            width: 3840,
+            height: 2160,
+            audio_rate: 96000,
+            ..FcpxmlFormat::default().with_fps(25.0)
+        };
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 0,
+            total_timeline_s: 0.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+        let path = std::env::temp_dir().join(format!("audiosync_fmt_{}.fcpxml", uuid::Uuid::new_v4()));
+        export_fcpxml(&[], &result, path.to_str().unwrap(), None, Some(&format)).unwrap();
+        let xml = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(xml.contains("frameDuration=\"1/25s\" width=\"3840\" height=\"2160\""));
+        assert!(xml.contains("audioRate=\"96k\""));
+    }
+
+    #[test]
+    fn test_fcpxml_bundle_by_extension() {
+        assert!(is_fcpxml_bundle("/tmp/Project.FCPXMLD"));
+        assert!(!is_fcpxml_bundle("/tmp/Project.fcpxml"));
+
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 0,
+            total_timeline_s: 0.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 0.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+        let dir = std::env::temp_dir().join(format!("audiosync_bundle_{}", uuid::Uuid::new_v4()));
+        let bundle = dir.join("Shoot.fcpxmld");
+        export_fcpxml(&[], &result, bundle.to_str().unwrap(), None, Some(&FcpxmlFormat::default()))
+            .unwrap();
+        let xml = std::fs::read_to_string(bundle.join("Info.fcpxml")).unwrap();
+        let plist = std::fs::read_to_string(bundle.join("Info.plist")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(xml.contains("<fcpxml version=\"1.11\">"));
+        assert!(plist.contains("<string>Shoot</string>"));
+    }
+
+    #[test]
+    fn test_timeline_json_for_web_players() {
+        let dir = std::env::temp_dir().join(format!("audiosync_webtl_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("day 1")).unwrap();
+        let local = dir.join("day 1").join("rec.wav");
+        std::fs::write(&local, b"").unwrap();
+
+        let mut tracks = vec![Track::new("Cam".into()), Track::new("Rec".into())];
+        tracks[0].is_reference = true;
+        let mut cam = Clip::new("/m/cam a.mov".into(), "cam a.mov".into(), 48000, 2);
+        cam.duration_s = 2.0;
+        cam.samples = vec![0.5; 16000];
+        cam.samples[100] = -0.8;
+        tracks[0].clips.push(cam);
+        let mut rec = Clip::new(local.to_string_lossy().to_string(), "rec.wav".into(), 48000, 2);
+        rec.duration_s = 1.0;
+        rec.timeline_offset_s = 0.5;
+        tracks[1].clips.push(rec);
+
+        let result = SyncResult {
+            reference_track_index: 0,
+            total_timeline_samples: 16000,
+            total_timeline_s: 2.0,
+            sample_rate: 8000,
+            clip_offsets: Default::default(),
+            avg_confidence: 10.0,
+            drift_detected: false,
+            warnings: Vec::new(),
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+            proposed_groups: Vec::new(),
+            unplaced_clips: Vec::new(),
+            diagnostics: Default::default(),
+        };
+        let path = dir.join("timeline.json");
+        export_timeline_json(&tracks, &result, path.to_str().unwrap(), Some("Day 1")).unwrap();
+        let timeline: WebTimeline =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(timeline.version, TIMELINE_JSON_VERSION);
+        assert_eq!(timeline.title, "Day 1");
+        assert!(timeline.tracks[0].is_reference);
+        let (cam, rec) = (&timeline.tracks[0].clips[0], &timeline.tracks[1].clips[0]);
+        assert_eq!(cam.media_url, "file:///m/cam%20a.mov");
+        assert_eq!(rec.media_url, "day%201/rec.wav");
+        assert_eq!(rec.offset_s, 0.5);
+        // 2 s at 50 peaks per second; unloaded audio has no peaks.
+        assert_eq!(cam.peaks.len(), 100);
+        assert_eq!((cam.peaks[0], cam.peaks[1]), (0.8, 0.5));
+        assert!(rec.peaks.is_empty());
+    }
+
     #[test]
     fn test_sanitize_reel() {
         assert_eq!(sanitize_edl_reel("CamA_001.mp4"), "CamA_001");
@@ -0,0 +1,189 @@
+//! Preview audio output — cpal device enumeration and the output stream that
+//! pulls frames from the core `Player`.
+//!
+//! cpal streams are not `Send` on every platform, so each stream lives on its
+//! own thread; `OutputHandle` just keeps that thread alive until dropped. The
+//! same thread reports the transport position at ~30 Hz for the UI playhead.
+
+use audiosync_core::playback::{PlaybackStatus, Player};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use std::sync::{mpsc, Arc, Mutex, TryLockError};
+use std::time::Duration;
+
+use crate::commands::LockExt;
+
+/// Interval between position reports (~30 Hz).
+const POSITION_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Sample rates offered to the user when a device supports a range.
+const COMMON_SAMPLE_RATES: &[u32] = &[44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Settings store file and key for the remembered output device.
+pub const SETTINGS_STORE: &str = "settings.json";
+pub const OUTPUT_DEVICE_KEY: &str = "output_device";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub sample_rates: Vec<u32>,
+    pub default_sample_rate: Option<u32>,
+}
+
+/// The user's output choice; `None` fields fall back to the system default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputSelection {
+    pub device: Option<String>,
+    pub sample_rate: Option<u32>,
+}
+
+/// Enumerate output devices on the default host.
+pub fn list_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_name = host.default_output_device().and_then(|d| d.name().ok());
+
+    let mut devices = Vec::new();
+    for device in host.output_devices().map_err(|e| e.to_string())? {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        let mut sample_rates = Vec::new();
+        if let Ok(configs) = device.supported_output_configs() {
+            for range in configs {
+                for &sr in COMMON_SAMPLE_RATES {
+                    if sr >= range.min_sample_rate().0
+                        && sr <= range.max_sample_rate().0
+                        && !sample_rates.contains(&sr)
+                    {
+                        sample_rates.push(sr);
+                    }
+                }
+            }
+        }
+        sample_rates.sort_unstable();
+
+        devices.push(AudioDeviceInfo {
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            default_sample_rate: device.default_output_config().ok().map(|c| c.sample_rate().0),
+            name,
+            sample_rates,
+        });
+    }
+    Ok(devices)
+}
+
+/// A running output stream. Dropping the handle stops the stream.
+pub struct OutputHandle {
+    stop: mpsc::Sender<()>,
+    pub device_name: String,
+    pub sample_rate: u32,
+}
+
+impl OutputHandle {
+    /// Open the selected device and start pulling audio from `player`.
+    ///
+    /// `on_position` is called from the output thread every ~33 ms while
+    /// playing, and once more when playback stops.
+    pub fn start<F>(
+        selection: &OutputSelection,
+        player: Arc<Mutex<Player>>,
+        on_position: F,
+    ) -> Result<Self, String>
+    where
+        F: Fn(PlaybackStatus) + Send + 'static,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(String, u32), String>>();
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let selection = selection.clone();
+
+        std::thread::spawn(move || {
+            let stream = match build_stream(&selection, player.clone()) {
+                Ok((stream, name, sample_rate)) => {
+                    let _ = ready_tx.send(Ok((name, sample_rate)));
+                    stream
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            if let Err(e) = stream.play() {
+                log::warn!("Failed to start output stream: {}", e);
+                return;
+            }
+            // Report position until stopped (or the handle is dropped).
+            let mut was_playing = false;
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(POSITION_INTERVAL) {
+                let status = player.lock_or_recover().status();
+                if status.playing || was_playing {
+                    was_playing = status.playing;
+                    on_position(status);
+                }
+            }
+            drop(stream);
+        });
+
+        let (device_name, sample_rate) = ready_rx
+            .recv()
+            .map_err(|_| "Output thread exited unexpectedly".to_string())??;
+        log::info!("Preview output: '{}' @ {} Hz", device_name, sample_rate);
+
+        Ok(Self {
+            stop: stop_tx,
+            device_name,
+            sample_rate,
+        })
+    }
+}
+
+impl Drop for OutputHandle {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+    }
+}
+
+fn build_stream(
+    selection: &OutputSelection,
+    player: Arc<Mutex<Player>>,
+) -> Result<(cpal::Stream, String, u32), String> {
+    let host = cpal::default_host();
+    let device = match &selection.device {
+        Some(wanted) => host
+            .output_devices()
+            .map_err(|e| e.to_string())?
+            .find(|d| d.name().map(|n| &n == wanted).unwrap_or(false))
+            .ok_or_else(|| format!("Output device '{}' not found", wanted))?,
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "No default output device".to_string())?,
+    };
+
+    let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let default_config = device.default_output_config().map_err(|e| e.to_string())?;
+    let sample_rate = selection
+        .sample_rate
+        .unwrap_or(default_config.sample_rate().0);
+    let config = cpal::StreamConfig {
+        channels: default_config.channels(),
+        sample_rate: cpal::SampleRate(sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+    let channels = config.channels as usize;
+
+    let stream = device
+        .build_output_stream(
+            &config,
+            move |data: &mut [f32], _| match player.try_lock() {
+                Ok(mut p) => p.render(data, channels, sample_rate),
+                Err(TryLockError::Poisoned(e)) => e.into_inner().render(data, channels, sample_rate),
+                // Never block the audio thread; a missed buffer is just silence.
+                Err(TryLockError::WouldBlock) => data.fill(0.0),
+            },
+            |e| log::warn!("Output stream error: {}", e),
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok((stream, name, sample_rate))
+}
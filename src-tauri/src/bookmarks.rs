@@ -0,0 +1,178 @@
+//! Security-scoped bookmarks for the sandboxed macOS build.
+//!
+//! The sandbox only lets the app open files the user picked in a dialog
+//! during the current run, so a project reopened later could not read its
+//! media. Bookmarks made while access is granted (import, save, open) carry
+//! that permission over: they live in the settings store keyed by path, and
+//! opening a project resolves them again without prompting. On other
+//! platforms every call is a no-op.
+
+#[cfg(target_os = "macos")]
+use std::collections::{BTreeMap, HashMap};
+#[cfg(target_os = "macos")]
+use std::sync::Mutex;
+
+use tauri::AppHandle;
+#[cfg(target_os = "macos")]
+use tauri_plugin_store::StoreExt;
+
+#[cfg(target_os = "macos")]
+use crate::audio_output::SETTINGS_STORE;
+#[cfg(target_os = "macos")]
+use crate::commands::LockExt;
+
+/// Settings store key for the bookmark map (path → bookmark bytes).
+#[cfg(target_os = "macos")]
+const BOOKMARKS_KEY: &str = "security_bookmarks";
+
+#[cfg(target_os = "macos")]
+type BookmarkMap = BTreeMap<String, Vec<u8>>;
+
+/// Paths whose security scope this run has entered, left again on reset.
+#[derive(Default)]
+pub struct ScopedAccess {
+    #[cfg(target_os = "macos")]
+    urls: Mutex<HashMap<String, macos::ScopedUrl>>,
+}
+
+/// Bookmark `paths` while this run can access them. A path that can't be
+/// bookmarked keeps any bookmark it already has.
+pub fn remember(app: &AppHandle, paths: &[String]) {
+    #[cfg(target_os = "macos")]
+    {
+        let mut map = load(app);
+        let mut changed = false;
+        for path in paths {
+            match macos::create(path) {
+                Ok(data) => changed |= map.insert(path.clone(), data.clone()) != Some(data),
+                Err(e) => log::debug!("No bookmark for '{}': {}", path, e),
+            }
+        }
+        if changed {
+            save(app, &map);
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, paths);
+}
+
+/// Regain access to bookmarked `paths` for the rest of this run, refreshing
+/// stale bookmarks. Paths without a bookmark are left to the sandbox.
+pub fn restore(app: &AppHandle, access: &ScopedAccess, paths: &[String]) {
+    #[cfg(target_os = "macos")]
+    {
+        let mut map = load(app);
+        let mut changed = false;
+        let mut urls = access.urls.lock_or_recover();
+        for path in paths {
+            if urls.contains_key(path) {
+                continue;
+            }
+            let Some(data) = map.get(path) else {
+                continue;
+            };
+            match macos::resolve(data) {
+                Ok((url, stale)) => {
+                    if stale && let Ok(fresh) = macos::create(path) {
+                        map.insert(path.clone(), fresh);
+                        changed = true;
+                    }
+                    urls.insert(path.clone(), url);
+                }
+                Err(e) => log::warn!("Bookmark for '{}' no longer resolves: {}", path, e),
+            }
+        }
+        if changed {
+            save(app, &map);
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, access, paths);
+}
+
+/// Leave every security scope entered by [`restore`].
+pub fn release_all(access: &ScopedAccess) {
+    #[cfg(target_os = "macos")]
+    access.urls.lock_or_recover().clear();
+    #[cfg(not(target_os = "macos"))]
+    let _ = access;
+}
+
+#[cfg(target_os = "macos")]
+fn load(app: &AppHandle) -> BookmarkMap {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(BOOKMARKS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn save(app: &AppHandle, map: &BookmarkMap) {
+    let saved = app.store(SETTINGS_STORE).map_err(|e| e.to_string()).and_then(|store| {
+        store.set(BOOKMARKS_KEY, serde_json::to_value(map).map_err(|e| e.to_string())?);
+        store.save().map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        log::warn!("Could not save file bookmarks: {}", e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use objc2::rc::Retained;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{
+        NSData, NSString, NSURLBookmarkCreationOptions, NSURLBookmarkResolutionOptions, NSURL,
+    };
+
+    /// A URL whose security scope is entered; dropping it leaves the scope.
+    pub struct ScopedUrl(Retained<NSURL>);
+
+    // SAFETY: NSURL is immutable and documented as safe to use from any thread.
+    unsafe impl Send for ScopedUrl {}
+    unsafe impl Sync for ScopedUrl {}
+
+    impl Drop for ScopedUrl {
+        fn drop(&mut self) {
+            // SAFETY: balances the successful start in `resolve`.
+            unsafe { self.0.stopAccessingSecurityScopedResource() };
+        }
+    }
+
+    pub fn create(path: &str) -> Result<Vec<u8>, String> {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(path));
+        // SAFETY: no resource keys or relative URL are passed.
+        let data = unsafe {
+            url.bookmarkDataWithOptions_includingResourceValuesForKeys_relativeToURL_error(
+                NSURLBookmarkCreationOptions::WithSecurityScope,
+                None,
+                None,
+            )
+        }
+        .map_err(|e| e.localizedDescription().to_string())?;
+        Ok(data.to_vec())
+    }
+
+    /// Resolve bookmark `data` and enter its scope; also whether the
+    /// bookmark is stale and should be made again.
+    pub fn resolve(data: &[u8]) -> Result<(ScopedUrl, bool), String> {
+        let data = NSData::with_bytes(data);
+        let mut stale = Bool::NO;
+        // SAFETY: `stale` outlives the call; no relative URL is passed.
+        let url = unsafe {
+            NSURL::URLByResolvingBookmarkData_options_relativeToURL_bookmarkDataIsStale_error(
+                &data,
+                NSURLBookmarkResolutionOptions::WithSecurityScope,
+                None,
+                &mut stale,
+            )
+        }
+        .map_err(|e| e.localizedDescription().to_string())?;
+        // SAFETY: a resolved security-scoped file URL.
+        if !unsafe { url.startAccessingSecurityScopedResource() } {
+            return Err("access was refused".to_string());
+        }
+        Ok((ScopedUrl(url), stale.as_bool()))
+    }
+}
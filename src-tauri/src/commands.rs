@@ -4,18 +4,29 @@
 //! Long-running operations (analyze, sync) run on a blocking thread and emit
 //! progress events back to the frontend.
 
-use audiosync_core::audio_io::{export_track, is_supported_file, load_clip};
-use audiosync_core::engine;
+use audiosync_core::audio_io::{self, is_supported_file, AnalysisCache};
+use audiosync_core::cloud::{self, CloudConfig};
+use audiosync_core::diagnostics::{self, CapabilityReport};
 use audiosync_core::grouping::group_files_by_device;
 use audiosync_core::models::*;
-use audiosync_core::project_io;
+use audiosync_core::playback::{PlaybackStatus, PlaybackTimeline, Player};
+use audiosync_core::remote;
+use audiosync_core::session::{self, ExportRequest, SessionController};
+use audiosync_core::query::{ClipMatch, ClipQuery};
+use audiosync_core::stats::ProjectStats;
 use audiosync_core::timeline_export;
 
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, MutexGuard};
 use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::audio_output::{
+    self, AudioDeviceInfo, OutputHandle, OutputSelection, OUTPUT_DEVICE_KEY, SETTINGS_STORE,
+};
+use crate::bookmarks::{self, ScopedAccess};
 
 // ---------------------------------------------------------------------------
 //  App state — shared across all commands
@@ -23,10 +34,33 @@ use tauri::{AppHandle, Emitter, State};
 
 #[derive(Default)]
 pub struct AppState {
-    pub tracks: Mutex<Vec<Track>>,
-    pub result: Mutex<Option<SyncResult>>,
-    pub config: Mutex<SyncConfig>,
+    /// Tracks, settings, last analysis and comments.
+    pub session: Mutex<SessionController>,
     pub cancel_token: Mutex<Option<CancelToken>>,
+    /// Preview mixer, shared with the audio output thread.
+    pub player: Arc<Mutex<Player>>,
+    /// Running preview output stream (None when stopped).
+    pub output: Mutex<Option<OutputHandle>>,
+}
+
+/// Locking that survives a panic in another command.
+///
+/// A poisoned std `Mutex` refuses every later lock, which would leave the
+/// whole session unusable until restart. The data may be half-updated, so
+/// recovery is logged; `reset_state` starts over if it turns out bad.
+pub trait LockExt<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|poisoned| {
+            log::warn!("Recovering app state left locked by a panicked operation");
+            let guard = poisoned.into_inner();
+            self.clear_poison();
+            guard
+        })
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -42,13 +76,31 @@ pub struct ClipInfo {
     pub original_channels: u32,
     pub is_video: bool,
     pub creation_time: Option<f64>,
+    pub stream_metadata: StreamMetadata,
     pub timeline_offset_s: f64,
     pub timeline_offset_samples: i64,
     pub confidence: f64,
+    pub match_score: f64,
     pub analyzed: bool,
     pub drift_ppm: f64,
     pub drift_confidence: f64,
+    pub drift_windows: usize,
+    /// Piecewise drift; empty when one rate covers the clip.
+    pub drift_segments: Vec<DriftSegment>,
+    pub drift_override: bool,
+    /// Drift taken from the track's estimate rather than measured here.
+    pub drift_inherited: bool,
     pub drift_corrected: bool,
+    pub locked: bool,
+    /// Timeline position set by hand, if any.
+    pub offset_override_s: Option<f64>,
+    pub gain_db: f64,
+    pub trim_in_s: f64,
+    pub trim_out_s: f64,
+    /// Polarity flipped against the reference; flipped back on export.
+    pub polarity_inverted: bool,
+    /// Left out of analysis and export by the user.
+    pub excluded: bool,
     /// Waveform peaks for Canvas rendering (downsampled).
     pub waveform_peaks: Vec<f32>,
 }
@@ -65,13 +117,26 @@ impl From<&Clip> for ClipInfo {
             original_channels: c.original_channels,
             is_video: c.is_video,
             creation_time: c.creation_time,
+            stream_metadata: c.stream_metadata.clone(),
             timeline_offset_s: c.timeline_offset_s,
             timeline_offset_samples: c.timeline_offset_samples,
             confidence: c.confidence,
+            match_score: c.match_score,
             analyzed: c.analyzed,
             drift_ppm: c.drift_ppm,
             drift_confidence: c.drift_confidence,
+            drift_windows: c.drift_windows,
+            drift_segments: c.drift_segments.clone(),
+            drift_override: c.drift_override,
+            drift_inherited: c.drift_inherited,
             drift_corrected: c.drift_corrected,
+            locked: c.locked,
+            offset_override_s: c.offset_override_s,
+            gain_db: c.gain_db,
+            trim_in_s: c.trim_in_s,
+            trim_out_s: c.trim_out_s,
+            polarity_inverted: c.polarity_inverted,
+            excluded: c.excluded,
             waveform_peaks: peaks,
         }
     }
@@ -81,6 +146,14 @@ impl From<&Clip> for ClipInfo {
 pub struct TrackInfo {
     pub name: String,
     pub is_reference: bool,
+    pub locked: bool,
+    /// Effective role (assigned or inferred from the device name).
+    pub role: TrackRole,
+    pub role_assigned: bool,
+    /// Sync group the track is analyzed and exported in.
+    pub sync_group: String,
+    /// The track's own analysis band weighting, if it overrides its role's.
+    pub band_weighting: Option<BandWeighting>,
     pub clips: Vec<ClipInfo>,
     pub total_duration_s: f64,
 }
@@ -90,6 +163,11 @@ impl From<&Track> for TrackInfo {
         Self {
             name: t.name.clone(),
             is_reference: t.is_reference,
+            locked: t.locked,
+            role: t.role(),
+            role_assigned: t.role.is_some(),
+            sync_group: t.sync_group_name().to_string(),
+            band_weighting: t.band_weighting,
             clips: t.clips.iter().map(ClipInfo::from).collect(),
             total_duration_s: t.total_duration_s(),
         }
@@ -109,24 +187,55 @@ pub struct AnalysisResult {
     pub result: SyncResult,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct DriftResult {
-    pub delay_samples: i64,
-    pub delay_s: f64,
-    pub confidence: f64,
-    pub drift_ppm: f64,
-    pub drift_r_squared: f64,
-    pub drift_significant: bool,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportConfig {
     pub output_dir: String,
     pub format: String,
     pub bit_depth: u32,
     pub drift_correction: bool,
+    /// Drift correction resampler; `None` keeps the project setting, or the
+    /// fast path for previews.
+    #[serde(default)]
+    pub drift_quality: Option<ResampleQuality>,
+    /// How overlapping clips are combined; `None` keeps the project setting.
+    /// A choice made here is kept as the project setting.
+    #[serde(default)]
+    pub overlap_policy: Option<OverlapPolicy>,
     pub fcpxml_path: Option<String>,
     pub edl_path: Option<String>,
+    /// JSON timeline for web review players.
+    #[serde(default)]
+    pub timeline_json_path: Option<String>,
+    /// Audio-only AAF with a region per clip, for DAW conforming.
+    #[serde(default)]
+    pub aaf_path: Option<String>,
+    /// FCPXML sequence overrides; unset values are detected from the first
+    /// video clip (frame rate, resolution) and the project's audio rate.
+    pub fps: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub audio_rate: Option<u32>,
+    /// Preview mode: render only this many seconds, starting at
+    /// `preview_start_s` (default 0).
+    pub preview_duration_s: Option<f64>,
+    pub preview_start_s: Option<f64>,
+    /// Subfolder template, e.g. `{project}/{date}/{format}`; `None` keeps
+    /// the flat layout.
+    pub folder_template: Option<String>,
+    /// File name template, e.g. `{role}_{track}`.
+    pub filename_template: Option<String>,
+    pub project_name: Option<String>,
+    /// Overrides `SyncConfig::post_export_command` for this export.
+    pub post_export_command: Option<String>,
+    /// Embed project/track/offset/version tags in the exported files.
+    #[serde(default)]
+    pub embed_metadata: bool,
+    /// Decode each exported file and compare it with the stitched audio.
+    #[serde(default)]
+    pub verify: bool,
+    /// Mix every track down to mono instead of keeping its channels.
+    #[serde(default)]
+    pub mono: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -145,69 +254,35 @@ pub async fn import_files(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<Vec<TrackInfo>, String> {
-    let supported: Vec<String> = paths
-        .into_iter()
-        .filter(|p| is_supported_file(p))
-        .collect();
-
-    if supported.is_empty() {
-        return Err("No supported audio/video files found.".to_string());
-    }
-
-    let groups = group_files_by_device(&supported);
-    let total_files: usize = groups.values().map(|v| v.len()).sum();
+    let total_files = paths.iter().filter(|p| is_supported_file(p)).count();
+    let (io_parallelism, cache) = {
+        let session = state.session.lock_or_recover();
+        (session.config.io_parallelism, AnalysisCache::from_config(&session.config))
+    };
+    bookmarks::remember(&app, &paths);
     let app_clone = app.clone();
 
-    let result = tokio::task::spawn_blocking(move || {
-        let mut tracks: Vec<Track> = Vec::new();
-        let mut loaded = 0usize;
-
-        for (device_name, paths) in &groups {
-            let mut track = Track::new(device_name.clone());
-            for path in paths {
-                loaded += 1;
-                let fname = Path::new(path)
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-                let _ = app_clone.emit(
-                    "import-progress",
-                    ProgressPayload {
-                        step: loaded,
-                        total: total_files,
-                        message: format!("Loading '{}'...", fname),
-                    },
-                );
-
-                match load_clip(path, &None) {
-                    Ok(clip) => track.clips.push(clip),
-                    Err(e) => {
-                        log::warn!("Failed to load {}: {}", path, e);
-                    }
-                }
-            }
-            if !track.clips.is_empty() {
-                tracks.push(track);
-            }
-        }
-
-        tracks
+    let (tracks, _) = tokio::task::spawn_blocking(move || {
+        session::load_tracks(&paths, io_parallelism, &cache, &None, |i, path| {
+            let _ = app_clone.emit(
+                "import-progress",
+                ProgressPayload {
+                    step: i + 1,
+                    total: total_files,
+                    message: format!("Loading '{}'...", file_name(path)),
+                },
+            );
+        }, |meter| {
+            let _ = app_clone.emit("import-meter", meter);
+        })
     })
     .await
-    .map_err(|e| format!("Import task failed: {}", e))?;
-
-    let track_infos: Vec<TrackInfo> = result.iter().map(TrackInfo::from).collect();
-
-    // Store in app state
-    let mut state_tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    *state_tracks = result;
-
-    // Clear previous results
-    let mut state_result = state.result.lock().map_err(|e| e.to_string())?;
-    *state_result = None;
+    .map_err(|e| format!("Import task failed: {}", e))?
+    .map_err(|e| e.to_string())?;
 
-    Ok(track_infos)
+    let mut session = state.session.lock_or_recover();
+    session.set_tracks(tracks);
+    Ok(track_infos(&session))
 }
 
 /// Add files to an existing track (by index).
@@ -218,69 +293,93 @@ pub async fn add_files_to_track(
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<Vec<TrackInfo>, String> {
-    let supported: Vec<String> = paths
-        .into_iter()
-        .filter(|p| is_supported_file(p))
-        .collect();
-
-    if supported.is_empty() {
-        return Err("No supported files.".to_string());
-    }
-
-    let total = supported.len();
+    let total = paths.iter().filter(|p| is_supported_file(p)).count();
+    let (io_parallelism, cache) = {
+        let session = state.session.lock_or_recover();
+        (session.config.io_parallelism, AnalysisCache::from_config(&session.config))
+    };
+    bookmarks::remember(&app, &paths);
     let app_clone = app.clone();
 
-    let new_clips = tokio::task::spawn_blocking(move || {
-        let mut clips = Vec::new();
-        for (i, path) in supported.iter().enumerate() {
+    let (clips, _) = tokio::task::spawn_blocking(move || {
+        session::load_clips_for_track(&paths, io_parallelism, &cache, &None, |i, path| {
             let _ = app_clone.emit(
                 "import-progress",
                 ProgressPayload {
                     step: i + 1,
                     total,
-                    message: format!("Loading '{}'...", Path::new(path).file_name().unwrap_or_default().to_string_lossy()),
+                    message: format!("Loading '{}'...", file_name(path)),
                 },
             );
-            match load_clip(path, &None) {
-                Ok(clip) => clips.push(clip),
-                Err(e) => log::warn!("Failed to load {}: {}", path, e),
-            }
-        }
-        clips
+        }, |meter| {
+            let _ = app_clone.emit("import-meter", meter);
+        })
     })
     .await
-    .map_err(|e| format!("Load failed: {}", e))?;
+    .map_err(|e| format!("Load failed: {}", e))?
+    .map_err(|e| e.to_string())?;
 
-    let mut state_tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    if track_index >= state_tracks.len() {
-        return Err(format!("Track index {} out of range", track_index));
-    }
-    state_tracks[track_index].clips.extend(new_clips);
+    let mut session = state.session.lock_or_recover();
+    session.add_clips(track_index, clips).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
 
-    // Clear previous analysis
-    let mut state_result = state.result.lock().map_err(|e| e.to_string())?;
-    *state_result = None;
+/// Decode files into the analysis cache in the background so importing them
+/// later is instant — emits "precache-progress" events, returns how many
+/// files were newly cached.
+#[tauri::command]
+pub async fn precache_files(
+    paths: Vec<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let (io_parallelism, cache) = {
+        let session = state.session.lock_or_recover();
+        (session.config.io_parallelism, AnalysisCache::from_config(&session.config))
+    };
+    let cache = cache.ok_or("The analysis cache is turned off.")?;
+    let paths: Vec<String> = paths.into_iter().filter(|p| is_supported_file(p)).collect();
+    let total = paths.len();
+
+    let outcomes = tokio::task::spawn_blocking(move || {
+        audio_io::precache_files(&paths, io_parallelism, &cache, &None, |i, path| {
+            let _ = app.emit(
+                "precache-progress",
+                ProgressPayload {
+                    step: i + 1,
+                    total,
+                    message: format!("Caching '{}'...", file_name(path)),
+                },
+            );
+        })
+    })
+    .await
+    .map_err(|e| format!("Precache task failed: {}", e))?;
 
-    Ok(state_tracks.iter().map(TrackInfo::from).collect())
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter_map(|o| o.as_ref().err().map(|e| e.to_string()))
+        .collect();
+    if !failed.is_empty() {
+        log::warn!("{} file(s) could not be cached: {}", failed.len(), failed.join("; "));
+    }
+    Ok(outcomes.iter().filter(|o| matches!(o, Ok(false))).count())
 }
 
 /// Create a new empty track.
 #[tauri::command]
 pub fn create_track(name: String, state: State<'_, AppState>) -> Result<Vec<TrackInfo>, String> {
-    let mut state_tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    state_tracks.push(Track::new(name));
-    Ok(state_tracks.iter().map(TrackInfo::from).collect())
+    let mut session = state.session.lock_or_recover();
+    session.create_track(name);
+    Ok(track_infos(&session))
 }
 
 /// Remove a track by index.
 #[tauri::command]
 pub fn remove_track(index: usize, state: State<'_, AppState>) -> Result<Vec<TrackInfo>, String> {
-    let mut state_tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    if index >= state_tracks.len() {
-        return Err(format!("Track index {} out of range", index));
-    }
-    state_tracks.remove(index);
-    Ok(state_tracks.iter().map(TrackInfo::from).collect())
+    let mut session = state.session.lock_or_recover();
+    session.remove_track(index).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
 }
 
 /// Remove a clip from a track.
@@ -290,93 +389,319 @@ pub fn remove_clip(
     clip_index: usize,
     state: State<'_, AppState>,
 ) -> Result<Vec<TrackInfo>, String> {
-    let mut state_tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    if track_index >= state_tracks.len() {
-        return Err("Track index out of range".to_string());
-    }
-    if clip_index >= state_tracks[track_index].clips.len() {
-        return Err("Clip index out of range".to_string());
-    }
-    state_tracks[track_index].clips.remove(clip_index);
-    Ok(state_tracks.iter().map(TrackInfo::from).collect())
+    let mut session = state.session.lock_or_recover();
+    session.remove_clip(track_index, clip_index).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Remove several clips, by id (source path), under one lock.
+#[tauri::command]
+pub fn remove_clips_bulk(clip_ids: Vec<String>, state: State<'_, AppState>) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.remove_clips(&clip_ids).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Exclude several clips from analysis and export, or include them again.
+#[tauri::command]
+pub fn set_excluded_bulk(
+    clip_ids: Vec<String>,
+    excluded: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clips_excluded(&clip_ids, excluded)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Set the gain (dB) several clips are stitched at.
+#[tauri::command]
+pub fn set_gain_bulk(
+    clip_ids: Vec<String>,
+    gain_db: f64,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clips_gain(&clip_ids, gain_db)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Lock or unlock a clip so analysis and overlap fixes leave it in place.
+#[tauri::command]
+pub fn set_clip_locked(
+    track_index: usize,
+    clip_index: usize,
+    locked: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clip_locked(track_index, clip_index, locked)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Place a clip at `offset_s` on the timeline by hand; analysis keeps it
+/// there and export uses it verbatim. Null hands it back to analysis.
+#[tauri::command]
+pub fn set_clip_offset(
+    track_index: usize,
+    clip_index: usize,
+    offset_s: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clip_offset(track_index, clip_index, offset_s)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Pin a clip's drift to `drift_ppm` (0 clears it) so analysis neither
+/// measures nor inherits it; null removes the pin.
+#[tauri::command]
+pub fn set_clip_drift(
+    track_index: usize,
+    clip_index: usize,
+    drift_ppm: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clip_drift(track_index, clip_index, drift_ppm)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Set the gain (dB) a clip is stitched at.
+#[tauri::command]
+pub fn set_clip_gain(
+    track_index: usize,
+    clip_index: usize,
+    gain_db: f64,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clip_gain(track_index, clip_index, gain_db)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Cut seconds from a clip's head and tail when it is stitched.
+#[tauri::command]
+pub fn set_clip_trim(
+    track_index: usize,
+    clip_index: usize,
+    trim_in_s: f64,
+    trim_out_s: f64,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_clip_trim(track_index, clip_index, trim_in_s, trim_out_s)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Lock or unlock every clip of a track at once.
+#[tauri::command]
+pub fn set_track_locked(
+    track_index: usize,
+    locked: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.set_track_locked(track_index, locked).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Assign a track role (DIALOG, MUSIC, FX, CAMERA); `None` goes back to
+/// inferring it from the device name.
+#[tauri::command]
+pub fn set_track_role(
+    track_index: usize,
+    role: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let role = role.map(|r| r.parse::<TrackRole>()).transpose()?;
+    let mut session = state.session.lock_or_recover();
+    session.set_track_role(track_index, role).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Weight a track's analysis audio (high/low-pass, speech emphasis) to suit
+/// its microphone; `None` goes back to its role's profile.
+#[tauri::command]
+pub fn set_track_band_weighting(
+    track_index: usize,
+    weighting: Option<BandWeighting>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.set_track_band_weighting(track_index, weighting).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Put a track in a sync group (e.g. one per conference room); `None` or
+/// an empty name moves it back to the default group.
+#[tauri::command]
+pub fn set_track_sync_group(
+    track_index: usize,
+    group: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .set_track_sync_group(track_index, group)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Accept the sync groups the last analysis proposed (tracks that only
+/// match each other); run analysis again afterwards.
+#[tauri::command]
+pub fn apply_proposed_sync_groups(state: State<'_, AppState>) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.apply_proposed_sync_groups().map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Sets of imported clips that an earlier analysis, in any project, placed together.
+#[tauri::command]
+pub fn find_reusable_analyses(state: State<'_, AppState>) -> Result<Vec<ReusableAnalysis>, String> {
+    Ok(state.session.lock_or_recover().reusable_analyses())
+}
+
+/// Reuse earlier analyses of the same media (`"priors"` or `"skip"`).
+#[tauri::command]
+pub fn reuse_prior_analysis(mode: PriorReuse, state: State<'_, AppState>) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.reuse_prior_analysis(mode).map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
 }
 
 /// Get current tracks state.
 #[tauri::command]
 pub fn get_tracks(state: State<'_, AppState>) -> Result<Vec<TrackInfo>, String> {
-    let state_tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    Ok(state_tracks.iter().map(TrackInfo::from).collect())
+    Ok(track_infos(&state.session.lock_or_recover()))
 }
 
 /// Run analysis — emits "analysis-progress" events, returns full result.
 #[tauri::command]
 pub async fn run_analysis(
     max_offset_s: Option<f64>,
+    incremental: Option<bool>,
     app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<AnalysisResult, String> {
-    // Prepare cancel token
     let cancel = new_cancel_token();
-    {
-        let mut ct = state.cancel_token.lock().map_err(|e| e.to_string())?;
-        *ct = Some(cancel.clone());
-    }
+    *state.cancel_token.lock_or_recover() = Some(cancel.clone());
 
-    // Clone tracks out of state for processing
-    let mut tracks = {
-        let st = state.tracks.lock().map_err(|e| e.to_string())?;
-        st.clone()
-    };
-    let config = {
-        let cfg = state.config.lock().map_err(|e| e.to_string())?;
-        let mut c = cfg.clone();
-        c.max_offset_s = max_offset_s;
-        c
-    };
+    // Work on a copy so other commands stay responsive during analysis
+    let mut work = state.session.lock_or_recover().clone();
+    work.config.max_offset_s = max_offset_s;
+    work.config.incremental = incremental.unwrap_or(false);
 
-    let app_clone = app.clone();
-    let cancel_clone = cancel.clone();
-
-    let result = tokio::task::spawn_blocking(move || {
-        let progress: Option<ProgressCallback> =
-            Some(Box::new(move |step, total, msg| {
-                let _ = app_clone.emit(
-                    "analysis-progress",
-                    ProgressPayload {
-                        step,
-                        total,
-                        message: msg.to_string(),
-                    },
-                );
-            }));
-
-        engine::analyze(&mut tracks, &config, &progress, &Some(cancel_clone))
-            .map(|r| (tracks, r))
+    let work = tokio::task::spawn_blocking(move || {
+        let progress: Option<ProgressCallback> = Some(Box::new(move |event: &ProgressEvent| {
+            let _ = app.emit("analysis-progress", event);
+        }));
+
+        work.analyze(&progress, &Some(cancel)).map_err(|e| e.to_string())?;
+        Ok::<_, String>(work)
     })
     .await
-    .map_err(|e| format!("Analysis task failed: {}", e))?
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| format!("Analysis task failed: {}", e))??;
 
-    let (tracks, sync_result) = result;
+    Ok(adopt_analysis(&state, work))
+}
 
-    // Update state
-    let track_infos: Vec<TrackInfo> = tracks.iter().map(TrackInfo::from).collect();
-    {
-        let mut st = state.tracks.lock().map_err(|e| e.to_string())?;
-        *st = tracks;
-    }
-    {
-        let mut sr = state.result.lock().map_err(|e| e.to_string())?;
-        *sr = Some(sync_result.clone());
+/// Run analysis on a remote `audiosync serve` instance and apply the offsets locally.
+#[tauri::command]
+pub async fn run_remote_analysis(
+    endpoint: String,
+    secret: Option<String>,
+    max_offset_s: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<AnalysisResult, String> {
+    let mut work = state.session.lock_or_recover().clone();
+    work.config.max_offset_s = max_offset_s;
+
+    let sync_result = remote::analyze_remote(&endpoint, &mut work.tracks, &work.config, secret.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    work.result = Some(sync_result);
+
+    Ok(adopt_analysis(&state, work))
+}
+
+/// Store the tracks and result of an analysis run on a session copy.
+fn adopt_analysis(state: &AppState, work: SessionController) -> AnalysisResult {
+    let mut session = state.session.lock_or_recover();
+    session.tracks = work.tracks;
+    session.result = work.result;
+    AnalysisResult {
+        tracks: track_infos(&session),
+        result: session.result.clone().unwrap_or_else(empty_result),
     }
+}
 
-    Ok(AnalysisResult {
-        tracks: track_infos,
-        result: sync_result,
-    })
+/// Re-correlate one clip, searching only start positions between `start_s`
+/// and `end_s` on the timeline. Other clips keep their placement.
+#[tauri::command]
+pub fn reanalyze_clip(
+    track_index: usize,
+    clip_index: usize,
+    start_s: f64,
+    end_s: f64,
+    state: State<'_, AppState>,
+) -> Result<Vec<TrackInfo>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .reanalyze_clip(track_index, clip_index, start_s, end_s)
+        .map_err(|e| e.to_string())?;
+    Ok(track_infos(&session))
+}
+
+/// Check that every clip's source file is still readable before exporting.
+#[tauri::command]
+pub fn preflight_check(state: State<'_, AppState>) -> Result<Vec<UnavailableClip>, String> {
+    Ok(state.session.lock_or_recover().preflight_check())
+}
+
+/// Project dashboard numbers: media duration, source sizes, per-track
+/// coverage, analysis status and estimated export size.
+#[tauri::command]
+pub fn get_project_stats(state: State<'_, AppState>) -> Result<ProjectStats, String> {
+    Ok(state.session.lock_or_recover().stats())
+}
+
+/// Clips matching `query` (name, device, date range, confidence, drift),
+/// for the clip list's filter and bulk actions.
+#[tauri::command]
+pub fn query_clips(query: ClipQuery, state: State<'_, AppState>) -> Result<Vec<ClipMatch>, String> {
+    Ok(state.session.lock_or_recover().query_clips(&query))
+}
+
+/// Capability report for the diagnostics screen: ffmpeg/ffprobe, codecs,
+/// writable directories and CPU features. Runs external tools, so it is
+/// async to keep the UI responsive.
+#[tauri::command]
+pub async fn capability_report(output_dir: Option<String>) -> Result<CapabilityReport, String> {
+    tokio::task::spawn_blocking(move || diagnostics::capability_report(output_dir.as_deref()))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Run sync and export — emits "sync-progress" events, returns exported file paths.
+///
+/// Clips whose source disappeared mid-export are skipped and reported via an
+/// "export-skipped" event rather than aborting the other tracks.
 #[tauri::command]
 pub async fn run_sync_and_export(
     export_config: ExportConfig,
@@ -384,192 +709,218 @@ pub async fn run_sync_and_export(
     state: State<'_, AppState>,
 ) -> Result<Vec<String>, String> {
     let cancel = new_cancel_token();
-    {
-        let mut ct = state.cancel_token.lock().map_err(|e| e.to_string())?;
-        *ct = Some(cancel.clone());
-    }
+    *state.cancel_token.lock_or_recover() = Some(cancel.clone());
 
-    let mut tracks = {
-        let st = state.tracks.lock().map_err(|e| e.to_string())?;
-        st.clone()
-    };
-    let sync_result = {
-        let sr = state.result.lock().map_err(|e| e.to_string())?;
-        sr.clone()
-            .ok_or_else(|| "No analysis result — run analysis first.".to_string())?
-    };
+    // Stitched audio stays in the copy; the session only keeps analysis
+    let mut work = state.session.lock_or_recover().clone();
+    if work.result.is_none() {
+        return Err("No analysis result — run analysis first.".to_string());
+    }
 
-    let mut config = {
-        let cfg = state.config.lock().map_err(|e| e.to_string())?;
-        cfg.clone()
-    };
+    let config = &mut work.config;
     config.export_format = export_config.format.clone();
     config.export_bit_depth = export_config.bit_depth;
     config.drift_correction = export_config.drift_correction;
+    config.export_range_s = export_config.preview_duration_s.map(|dur| {
+        let start = export_config.preview_start_s.unwrap_or(0.0);
+        (start, start + dur)
+    });
+    let suffix = if config.export_range_s.is_some() { "_preview" } else { "" };
+    if let Some(quality) = export_config.drift_quality {
+        config.drift_resample_quality = quality;
+    } else if config.export_range_s.is_some() {
+        config.drift_resample_quality = ResampleQuality::Fast;
+    }
+    if let Some(policy) = export_config.overlap_policy {
+        config.overlap_policy = policy;
+        state.session.lock_or_recover().config.overlap_policy = policy;
+    }
+    if export_config.folder_template.is_some() {
+        config.export_folder_template = export_config.folder_template.clone();
+    }
+    if export_config.filename_template.is_some() {
+        config.export_filename_template = export_config.filename_template.clone();
+    }
+    if export_config.post_export_command.is_some() {
+        config.post_export_command = export_config.post_export_command.clone();
+    }
+    config.verify_export |= export_config.verify;
+    config.export_channels = if export_config.mono { ChannelPolicy::Mono } else { ChannelPolicy::Preserve };
 
-    let output_dir = export_config.output_dir.clone();
-    let fcpxml_path = export_config.fcpxml_path.clone();
-    let edl_path = export_config.edl_path.clone();
-    let format = export_config.format.clone();
-
-    let app_clone = app.clone();
-    let cancel_clone = cancel.clone();
-
-    let exported = tokio::task::spawn_blocking(move || -> Result<Vec<String>, String> {
-        let progress: Option<ProgressCallback> =
-            Some(Box::new(move |step, total, msg| {
-                let _ = app_clone.emit(
-                    "sync-progress",
-                    ProgressPayload {
-                        step,
-                        total,
-                        message: msg.to_string(),
-                    },
-                );
-            }));
-
-        // Run sync (stitch)
-        engine::sync(
-            &mut tracks,
-            &sync_result,
-            &mut config,
-            &progress,
-            &Some(cancel_clone),
-        )
-        .map_err(|e| e.to_string())?;
-
-        // Create output directory
-        std::fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
-
-        let export_sr = config.export_sr.unwrap_or(48000);
-        let mut files: Vec<String> = Vec::new();
-
-        for track in &tracks {
-            let filename = format!(
-                "{}_{}.{}",
-                sanitize_filename(&track.name),
-                export_sr,
-                format,
-            );
-            let out_path = Path::new(&output_dir).join(&filename);
-            let out_str = out_path.to_string_lossy().to_string();
-            export_track(track, &out_str, &config).map_err(|e| e.to_string())?;
-            files.push(out_str);
-        }
-
-        // Export FCPXML if requested
-        if let Some(ref path) = fcpxml_path {
-            timeline_export::export_fcpxml(&tracks, &sync_result, path, None)
-                .map_err(|e| e.to_string())?;
-        }
-
-        // Export EDL if requested
-        if let Some(ref path) = edl_path {
-            timeline_export::export_edl(&tracks, &sync_result, path, None)
-                .map_err(|e| e.to_string())?;
+    let fcpxml_format = export_config.fcpxml_path.as_ref().map(|_| {
+        let mut format = timeline_export::FcpxmlFormat::detect(&work.tracks);
+        if let Some(fps) = export_config.fps {
+            format = format.with_fps(fps);
         }
+        format.width = export_config.width.unwrap_or(format.width);
+        format.height = export_config.height.unwrap_or(format.height);
+        format.audio_rate = export_config.audio_rate.unwrap_or(format.audio_rate);
+        format
+    });
+    let request = ExportRequest {
+        output_dir: export_config.output_dir,
+        project_name: export_config
+            .project_name
+            .unwrap_or_else(|| "Untitled".to_string()),
+        suffix: suffix.to_string(),
+        embed_metadata: export_config.embed_metadata,
+        fcpxml_path: export_config.fcpxml_path,
+        fcpxml_format,
+        edl_path: export_config.edl_path,
+        timeline_json_path: export_config.timeline_json_path,
+        aaf_path: export_config.aaf_path,
+    };
 
-        Ok(files)
+    let app_clone = app.clone();
+    let outcome = tokio::task::spawn_blocking(move || {
+        let progress: Option<ProgressCallback> = Some(Box::new(move |event: &ProgressEvent| {
+            let _ = app_clone.emit("sync-progress", event);
+        }));
+        work.export(&request, &progress, &Some(cancel))
     })
     .await
     .map_err(|e| format!("Sync task failed: {}", e))?
     .map_err(|e| e.to_string())?;
 
-    Ok(exported)
+    if !outcome.skipped.is_empty() {
+        let _ = app.emit("export-skipped", &outcome.skipped);
+    }
+    if !outcome.verification.is_empty() {
+        let _ = app.emit("export-verification", &outcome.verification);
+    }
+    // The export itself succeeded, so a failing hook is reported, not fatal
+    if let Some(ref e) = outcome.hook_error {
+        let _ = app.emit("post-export-failed", e);
+    }
+    Ok(outcome.files)
+}
+
+/// Export one clip on its own, padded with silence up to where it starts
+/// on the timeline, in the session's export format.
+#[tauri::command]
+pub async fn export_clip(
+    track_index: usize,
+    clip_index: usize,
+    output_dir: String,
+    project_name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let mut work = state.session.lock_or_recover().clone();
+    let request = ExportRequest {
+        output_dir,
+        project_name: project_name.unwrap_or_else(|| "Untitled".to_string()),
+        ..Default::default()
+    };
+    let outcome = tokio::task::spawn_blocking(move || work.export_clip(track_index, clip_index, &request, &None, &None))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+        .map_err(|e| e.to_string())?;
+    if let Some(s) = outcome.skipped.first() {
+        return Err(format!("{} could not be read: {}", s.clip_name, s.reason));
+    }
+    outcome.files.into_iter().next().ok_or_else(|| "Nothing was exported".to_string())
 }
 
-/// Measure drift between two files.
+/// Run a simulated long job that reports progress like an analysis and
+/// honours `cancel_operation` — for exercising the progress UI without real
+/// media. Debug builds only.
 #[tauri::command]
-pub async fn measure_drift(
-    reference_path: String,
-    target_path: String,
-) -> Result<DriftResult, String> {
+pub async fn simulate_job(
+    job: audiosync_core::simulate::SimulatedJob,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    if !cfg!(debug_assertions) {
+        return Err("Simulated jobs are only available in debug builds".to_string());
+    }
+    let cancel = new_cancel_token();
+    *state.cancel_token.lock_or_recover() = Some(cancel.clone());
+
     tokio::task::spawn_blocking(move || {
-        let ref_clip = load_clip(&reference_path, &None).map_err(|e| e.to_string())?;
-        let mut tgt_clip = load_clip(&target_path, &None).map_err(|e| e.to_string())?;
-
-        let (delay, conf) = engine::compute_delay(
-            &ref_clip.samples,
-            &tgt_clip.samples,
-            ANALYSIS_SR,
-            None,
-        );
-
-        tgt_clip.timeline_offset_samples = delay;
-        tgt_clip.timeline_offset_s = delay as f64 / ANALYSIS_SR as f64;
-        tgt_clip.confidence = conf;
-        tgt_clip.analyzed = true;
-
-        let (drift_ppm, r_sq) =
-            engine::measure_drift(&ref_clip.samples, &tgt_clip, ANALYSIS_SR);
-
-        Ok(DriftResult {
-            delay_samples: delay,
-            delay_s: tgt_clip.timeline_offset_s,
-            confidence: conf,
-            drift_ppm,
-            drift_r_squared: r_sq,
-            drift_significant: drift_ppm.abs() > 0.3 && r_sq > 0.5,
-        })
+        let progress: Option<ProgressCallback> = Some(Box::new(move |event: &ProgressEvent| {
+            let _ = app.emit("analysis-progress", event);
+        }));
+        audiosync_core::simulate::run_simulated_job(&job, &progress, &Some(cancel)).map_err(|e| e.to_string())
     })
     .await
-    .map_err(|e| format!("Drift measurement failed: {}", e))?
+    .map_err(|e| format!("Simulated job failed: {}", e))?
 }
 
 /// Cancel a running operation.
 #[tauri::command]
 pub fn cancel_operation(state: State<'_, AppState>) -> Result<(), String> {
-    let ct = state.cancel_token.lock().map_err(|e| e.to_string())?;
+    let ct = state.cancel_token.lock_or_recover();
     if let Some(ref token) = *ct {
         token.store(true, std::sync::atomic::Ordering::Relaxed);
     }
     Ok(())
 }
 
-/// Save the current project to a file.
+/// Start a fresh session: cancel any running operation, stop the preview and
+/// drop tracks, analysis, comments and settings — recovery after a failed
+/// operation without restarting the app.
 #[tauri::command]
-pub fn save_project(path: String, state: State<'_, AppState>) -> Result<(), String> {
-    let tracks = state.tracks.lock().map_err(|e| e.to_string())?;
-    let config = state.config.lock().map_err(|e| e.to_string())?;
-    let result = state.result.lock().map_err(|e| e.to_string())?;
-
-    project_io::save_project(&path, &tracks, &config, result.as_ref())
-        .map_err(|e| e.to_string())
+pub fn reset_state(state: State<'_, AppState>, access: State<'_, ScopedAccess>) -> Result<(), String> {
+    if let Some(token) = state.cancel_token.lock_or_recover().take() {
+        token.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    *state.output.lock_or_recover() = None;
+    *state.player.lock_or_recover() = Player::default();
+    *state.session.lock_or_recover() = SessionController::default();
+    bookmarks::release_all(&access);
+    log::info!("Session state reset");
+    Ok(())
 }
 
-/// Load a project from a file — replaces current state.
+/// Save the current project to a file, bookmarking it so it can be reopened
+/// in a later run of the sandboxed app.
 #[tauri::command]
-pub fn load_project(path: String, state: State<'_, AppState>) -> Result<AnalysisResult, String> {
-    let project =
-        project_io::load_project(&path).map_err(|e| e.to_string())?;
+pub fn save_project(path: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    state.session.lock_or_recover().save(&path).map_err(|e| e.to_string())?;
+    bookmarks::remember(&app, &[path]);
+    Ok(())
+}
 
-    let track_infos: Vec<TrackInfo> = project.tracks.iter().map(TrackInfo::from).collect();
+/// Save the last analysis as a sync map: clip hashes and offsets only, for
+/// other tools.
+#[tauri::command]
+pub fn save_sync_map(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.session.lock_or_recover().save_sync_map(&path).map_err(|e| e.to_string())
+}
 
-    {
-        let mut st = state.tracks.lock().map_err(|e| e.to_string())?;
-        *st = project.tracks;
-    }
-    {
-        let mut cfg = state.config.lock().map_err(|e| e.to_string())?;
-        *cfg = project.config;
-    }
-    {
-        let mut sr = state.result.lock().map_err(|e| e.to_string())?;
-        *sr = project.result.clone();
-    }
+/// Place the imported clips from a sync map instead of analyzing them.
+#[tauri::command]
+pub fn apply_sync_map(path: String, state: State<'_, AppState>) -> Result<AnalysisResult, String> {
+    let mut session = state.session.lock_or_recover();
+    let result = session.apply_sync_map(&path).map_err(|e| e.to_string())?.clone();
+    Ok(AnalysisResult {
+        tracks: track_infos(&session),
+        result,
+    })
+}
 
+/// Load a project from a file — replaces current state. Bookmarked media
+/// (see [`bookmarks`]) becomes readable again before the clips load.
+#[tauri::command]
+pub fn load_project(
+    path: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    access: State<'_, ScopedAccess>,
+) -> Result<AnalysisResult, String> {
+    let project = std::slice::from_ref(&path);
+    bookmarks::restore(&app, &access, project);
+    let loaded = SessionController::open(&path).map_err(|e| format!("{:#}", e))?;
+    bookmarks::remember(&app, project);
+    let media: Vec<String> =
+        loaded.tracks.iter().flat_map(|t| t.clips.iter().map(|c| c.file_path.clone())).collect();
+    bookmarks::restore(&app, &access, &media);
+
+    let mut session = state.session.lock_or_recover();
+    *session = loaded;
     Ok(AnalysisResult {
-        tracks: track_infos,
-        result: project.result.unwrap_or(SyncResult {
-            reference_track_index: 0,
-            total_timeline_samples: 0,
-            total_timeline_s: 0.0,
-            sample_rate: ANALYSIS_SR,
-            clip_offsets: std::collections::HashMap::new(),
-            avg_confidence: 0.0,
-            drift_detected: false,
-            warnings: Vec::new(),
-        }),
+        tracks: track_infos(&session),
+        result: session.result.clone().unwrap_or_else(empty_result),
     })
 }
 
@@ -579,11 +930,24 @@ pub fn update_config(
     config: SyncConfig,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let mut cfg = state.config.lock().map_err(|e| e.to_string())?;
-    *cfg = config;
+    state.session.lock_or_recover().config = config;
     Ok(())
 }
 
+/// Designate the clip (by file path) whose placement re-analysis must keep;
+/// pass null to go back to normalizing the earliest clip to zero.
+#[tauri::command]
+pub fn set_anchor_clip(
+    file_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .session
+        .lock_or_recover()
+        .set_anchor_clip(file_path)
+        .map_err(|e| e.to_string())
+}
+
 /// Get file grouping info (for preview before full import).
 #[tauri::command]
 pub fn get_file_groups(paths: Vec<String>) -> BTreeMap<String, Vec<String>> {
@@ -594,10 +958,100 @@ pub fn get_file_groups(paths: Vec<String>) -> BTreeMap<String, Vec<String>> {
     group_files_by_device(&supported)
 }
 
+/// Get all project comments.
+#[tauri::command]
+pub fn get_comments(state: State<'_, AppState>) -> Result<Vec<Comment>, String> {
+    Ok(state.session.lock_or_recover().live_comments())
+}
+
+/// Add a comment — a new thread at `anchor`, or a reply when `parent_id` is set.
+#[tauri::command]
+pub fn add_comment(
+    author: String,
+    body: String,
+    anchor: Option<CommentAnchor>,
+    parent_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Comment>, String> {
+    let mut session = state.session.lock_or_recover();
+    session
+        .add_comment(author, body, anchor, parent_id.as_deref())
+        .map_err(|e| e.to_string())?;
+    Ok(session.live_comments())
+}
+
+/// Resolve or reopen a comment.
+#[tauri::command]
+pub fn resolve_comment(
+    id: String,
+    resolved: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<Comment>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.resolve_comment(&id, resolved).map_err(|e| e.to_string())?;
+    Ok(session.live_comments())
+}
+
+/// Delete a comment; a tombstone stays behind so comment sync removes it
+/// elsewhere too.
+#[tauri::command]
+pub fn delete_comment(id: String, state: State<'_, AppState>) -> Result<Vec<Comment>, String> {
+    let mut session = state.session.lock_or_recover();
+    session.delete_comment(&id).map_err(|e| e.to_string())?;
+    Ok(session.live_comments())
+}
+
+/// Two-way sync of comments with a cloud project (pull, merge, push).
+#[tauri::command]
+pub async fn sync_comments(
+    project_id: String,
+    cloud_config: CloudConfig,
+    state: State<'_, AppState>,
+) -> Result<Vec<Comment>, String> {
+    let local = state.session.lock_or_recover().comments.clone();
+
+    let merged = cloud::sync_comments(&cloud_config, &project_id, &local)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Comments added, resolved or deleted during the round trip are merged
+    // in, not overwritten.
+    let mut session = state.session.lock_or_recover();
+    session.comments = cloud::merge_comments(&session.comments, &merged);
+    Ok(session.live_comments())
+}
+
 // ---------------------------------------------------------------------------
 //  Helpers
 // ---------------------------------------------------------------------------
 
+fn track_infos(session: &SessionController) -> Vec<TrackInfo> {
+    session.tracks.iter().map(TrackInfo::from).collect()
+}
+
+/// Placeholder result for a project that has not been analyzed.
+fn empty_result() -> SyncResult {
+    SyncResult {
+        reference_track_index: 0,
+        total_timeline_samples: 0,
+        total_timeline_s: 0.0,
+        sample_rate: ANALYSIS_SR,
+        clip_offsets: std::collections::HashMap::new(),
+        avg_confidence: 0.0,
+        drift_detected: false,
+        warnings: Vec::new(),
+        suggestions: Vec::new(),
+        groups: Vec::new(),
+        proposed_groups: Vec::new(),
+        unplaced_clips: Vec::new(),
+        diagnostics: std::collections::HashMap::new(),
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
 /// Downsample audio samples to N peaks (max absolute value per bucket).
 fn downsample_peaks(samples: &[f32], n: usize) -> Vec<f32> {
     if samples.is_empty() || n == 0 {
@@ -624,14 +1078,164 @@ fn downsample_peaks(samples: &[f32], n: usize) -> Vec<f32> {
     peaks
 }
 
-fn sanitize_filename(name: &str) -> String {
-    name.chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
-                c
-            } else {
-                '_'
-            }
-        })
-        .collect()
+// ---------------------------------------------------------------------------
+//  Preview playback
+// ---------------------------------------------------------------------------
+
+fn load_output_selection(app: &AppHandle) -> OutputSelection {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(OUTPUT_DEVICE_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn start_output(
+    app: &AppHandle,
+    selection: &OutputSelection,
+    state: &AppState,
+) -> Result<OutputHandle, String> {
+    let app = app.clone();
+    OutputHandle::start(selection, state.player.clone(), move |status| {
+        let _ = app.emit("playback-position", status);
+    })
+}
+
+/// Start the output stream on the remembered device if it is not running.
+fn ensure_output(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let mut output = state.output.lock_or_recover();
+    if output.is_none() {
+        let selection = load_output_selection(app);
+        *output = Some(start_output(app, &selection, state)?);
+    }
+    Ok(())
+}
+
+/// List available audio output devices and their supported sample rates.
+#[tauri::command]
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+    audio_output::list_devices()
+}
+
+/// Get the remembered preview output device (None = system default).
+#[tauri::command]
+pub fn get_output_device(app: AppHandle) -> OutputSelection {
+    load_output_selection(&app)
+}
+
+/// Select the preview output device and sample rate, and remember the choice.
+#[tauri::command]
+pub fn set_output_device(
+    device: Option<String>,
+    sample_rate: Option<u32>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<OutputSelection, String> {
+    let selection = OutputSelection {
+        device,
+        sample_rate,
+    };
+
+    // Move a running stream over to the new device.
+    {
+        let mut output = state.output.lock_or_recover();
+        if output.take().is_some() {
+            *output = Some(start_output(&app, &selection, &state)?);
+        }
+    }
+
+    let store = app.store(SETTINGS_STORE).map_err(|e| e.to_string())?;
+    store.set(
+        OUTPUT_DEVICE_KEY,
+        serde_json::to_value(&selection).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(selection)
+}
+
+/// Start (or resume) preview playback of the current tracks.
+#[tauri::command]
+pub fn preview_play(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    {
+        let session = state.session.lock_or_recover();
+        let mut player = state.player.lock_or_recover();
+        player.load(PlaybackTimeline::from_tracks(&session.tracks));
+        player.play();
+    }
+    ensure_output(&app, &state)
+}
+
+/// Pause preview playback, keeping the playhead.
+#[tauri::command]
+pub fn preview_pause(state: State<'_, AppState>) -> Result<(), String> {
+    state.player.lock_or_recover().pause();
+    Ok(())
+}
+
+/// Stop preview playback, rewind, and release the output device.
+#[tauri::command]
+pub fn preview_stop(state: State<'_, AppState>) -> Result<(), String> {
+    {
+        let mut player = state.player.lock_or_recover();
+        player.pause();
+        player.seek(0.0);
+    }
+    *state.output.lock_or_recover() = None;
+    Ok(())
+}
+
+/// Move the playhead (auditioning a short grain while paused).
+#[tauri::command]
+pub fn scrub(position_s: f64, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    {
+        let session = state.session.lock_or_recover();
+        let mut player = state.player.lock_or_recover();
+        if !player.is_loaded() {
+            player.load(PlaybackTimeline::from_tracks(&session.tracks));
+        }
+        player.scrub(position_s);
+    }
+    ensure_output(&app, &state)
+}
+
+/// Loop playback between two timeline positions; pass nulls to clear.
+#[tauri::command]
+pub fn set_loop(
+    start_s: Option<f64>,
+    end_s: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<PlaybackStatus, String> {
+    let mut player = state.player.lock_or_recover();
+    player.set_loop(start_s.zip(end_s));
+    Ok(player.status())
+}
+
+/// Set the preview playback rate (0.25×–4×).
+#[tauri::command]
+pub fn set_playback_rate(rate: f64, state: State<'_, AppState>) -> Result<PlaybackStatus, String> {
+    let mut player = state.player.lock_or_recover();
+    player.set_rate(rate);
+    Ok(player.status())
+}
+
+/// Toggle phase-check mode: solo two tracks with the second polarity-inverted.
+/// Pass nulls to return to the normal mix.
+#[tauri::command]
+pub fn set_phase_check(
+    track_a: Option<usize>,
+    track_b: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<PlaybackStatus, String> {
+    let session = state.session.lock_or_recover();
+    let mut player = state.player.lock_or_recover();
+    if !player.is_loaded() {
+        player.load(PlaybackTimeline::from_tracks(&session.tracks));
+    }
+    let pair = track_a.zip(track_b);
+    player.set_phase_check(pair);
+    if pair.is_some() && player.status().phase_check.is_none() {
+        return Err("Phase check needs two different, existing tracks".to_string());
+    }
+    Ok(player.status())
 }
@@ -0,0 +1,189 @@
+//! Drift measurement tool — a detachable window for comparing two files,
+//! plus the pairwise `compare_clips` command it shares state with.
+//!
+//! The tool never touches the project in `AppState`: it loads its own clips,
+//! keeps its own cancel token in `DriftToolState`, and reports progress only
+//! to the window that asked. A measurement can therefore run while the main
+//! window imports, analyzes or exports, and cancelling one leaves the other
+//! alone.
+
+use audiosync_core::audio_io::load_clip;
+use audiosync_core::engine;
+use audiosync_core::models::*;
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::commands::{LockExt, ProgressPayload};
+
+/// Label of the detached drift window; the frontend routes on `#drift`.
+pub const DRIFT_WINDOW_LABEL: &str = "drift";
+
+/// State owned by the drift tool, separate from the project session.
+#[derive(Default)]
+pub struct DriftToolState {
+    pub cancel_token: Mutex<Option<CancelToken>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftResult {
+    pub delay_samples: i64,
+    pub delay_s: f64,
+    pub confidence: f64,
+    /// Calibrated 0–100 match score.
+    pub match_score: f64,
+    pub drift_ppm: f64,
+    pub drift_r_squared: f64,
+    /// R² scaled for window length; what `drift_significant` is based on.
+    pub drift_confidence: f64,
+    pub drift_windows: usize,
+    /// Windows consistent with the fitted drift line.
+    pub drift_inliers: usize,
+    pub drift_window_s: f64,
+    /// Piecewise drift when the clock rate changed during the take.
+    pub drift_segments: Vec<DriftSegment>,
+    pub drift_significant: bool,
+}
+
+/// Open the drift tool window, or focus it if it is already open.
+#[tauri::command]
+pub fn open_drift_window(app: AppHandle) -> Result<(), String> {
+    open_window(&app).map_err(|e| e.to_string())
+}
+
+pub fn open_window(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(DRIFT_WINDOW_LABEL) {
+        window.unminimize()?;
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(app, DRIFT_WINDOW_LABEL, WebviewUrl::App("index.html#drift".into()))
+        .title("Drift Measurement")
+        .inner_size(520.0, 600.0)
+        .min_inner_size(420.0, 480.0)
+        .resizable(true)
+        .build()?;
+    Ok(())
+}
+
+/// Measure drift between two files.
+///
+/// Progress goes to the calling window as `drift-progress` events.
+#[tauri::command]
+pub async fn measure_drift(
+    reference_path: String,
+    target_path: String,
+    window: WebviewWindow,
+    state: State<'_, DriftToolState>,
+) -> Result<DriftResult, String> {
+    let cancel = new_cancel_token();
+    {
+        let mut ct = state.cancel_token.lock_or_recover();
+        *ct = Some(cancel.clone());
+    }
+    let cancel = Some(cancel);
+    let label = window.label().to_string();
+    let app = window.app_handle().clone();
+    let progress = move |step: usize, message: &str| {
+        let _ = app.emit_to(
+            label.as_str(),
+            "drift-progress",
+            ProgressPayload {
+                step,
+                total: 3,
+                message: message.to_string(),
+            },
+        );
+    };
+
+    tokio::task::spawn_blocking(move || {
+        progress(1, "Loading reference...");
+        let ref_clip = load_clip(&reference_path, &cancel).map_err(|e| e.to_string())?;
+        progress(2, "Loading target...");
+        let mut tgt_clip = load_clip(&target_path, &cancel).map_err(|e| e.to_string())?;
+        check_cancelled(&cancel).map_err(|e| e.to_string())?;
+
+        progress(3, "Measuring drift...");
+        let m = engine::match_delay(&ref_clip.samples, &tgt_clip.samples, ANALYSIS_SR, None);
+        let (delay, conf) = (m.delay_samples, m.confidence);
+
+        tgt_clip.timeline_offset_samples = delay;
+        tgt_clip.timeline_offset_s = m.delay_s;
+        tgt_clip.confidence = conf;
+        tgt_clip.analyzed = true;
+
+        let drift = engine::measure_drift(&ref_clip.samples, &tgt_clip, ANALYSIS_SR, &cancel);
+        check_cancelled(&cancel).map_err(|e| e.to_string())?;
+
+        let peak_ppm = drift
+            .segments
+            .iter()
+            .map(|s| s.ppm.abs())
+            .fold(drift.drift_ppm.abs(), f64::max);
+        Ok(DriftResult {
+            delay_samples: delay,
+            delay_s: tgt_clip.timeline_offset_s,
+            confidence: conf,
+            match_score: m.score,
+            drift_ppm: drift.drift_ppm,
+            drift_r_squared: drift.r_squared,
+            drift_confidence: drift.confidence,
+            drift_windows: drift.windows,
+            drift_inliers: drift.inliers,
+            drift_window_s: drift.window_s,
+            drift_significant: peak_ppm > 0.3 && drift.confidence > 0.5,
+            drift_segments: drift.segments,
+        })
+    })
+    .await
+    .map_err(|e| format!("Drift measurement failed: {}", e))?
+}
+
+/// Points in the correlation curve returned by `compare_clips`.
+const COMPARE_CURVE_POINTS: usize = 1000;
+
+/// Correlate two files and return the delay, confidence, a downsampled
+/// correlation curve and overlap stats, for plotting ambiguous matches.
+///
+/// `max_offset` limits the search to ±seconds around zero delay.
+#[tauri::command]
+pub async fn compare_clips(
+    path_a: String,
+    path_b: String,
+    max_offset: Option<f64>,
+    state: State<'_, DriftToolState>,
+) -> Result<ClipComparison, String> {
+    let cancel = new_cancel_token();
+    {
+        let mut ct = state.cancel_token.lock_or_recover();
+        *ct = Some(cancel.clone());
+    }
+    let cancel = Some(cancel);
+
+    tokio::task::spawn_blocking(move || {
+        let clip_a = load_clip(&path_a, &cancel).map_err(|e| e.to_string())?;
+        let clip_b = load_clip(&path_b, &cancel).map_err(|e| e.to_string())?;
+        check_cancelled(&cancel).map_err(|e| e.to_string())?;
+
+        Ok(engine::compare_clips(
+            &clip_a.samples,
+            &clip_b.samples,
+            ANALYSIS_SR,
+            max_offset,
+            COMPARE_CURVE_POINTS,
+        ))
+    })
+    .await
+    .map_err(|e| format!("Comparison failed: {}", e))?
+}
+
+/// Cancel the running drift measurement or comparison (the project session
+/// is unaffected).
+#[tauri::command]
+pub fn cancel_drift_measurement(state: State<'_, DriftToolState>) -> Result<(), String> {
+    let ct = state.cancel_token.lock_or_recover();
+    if let Some(ref token) = *ct {
+        token.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -1,20 +1,30 @@
 //! AudioSync Pro — Tauri v2 desktop application.
 
+mod audio_output;
+mod bookmarks;
 mod commands;
+mod drift_tool;
+mod logging;
 mod menu;
 
 use commands::AppState;
+use drift_tool::DriftToolState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .manage(AppState::default())
+        .manage(DriftToolState::default())
+        .manage(bookmarks::ScopedAccess::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_clipboard_manager::init())
         .setup(|app| {
             let handle = app.handle().clone();
+            if let Err(e) = logging::init(&handle) {
+                eprintln!("File logging unavailable: {}", e);
+            }
             let m = menu::build_menu(&handle)?;
             app.set_menu(m)?;
             Ok(())
@@ -29,15 +39,63 @@ pub fn run() {
             commands::create_track,
             commands::remove_track,
             commands::remove_clip,
+            commands::remove_clips_bulk,
+            commands::set_excluded_bulk,
+            commands::set_gain_bulk,
+            commands::set_clip_locked,
+            commands::set_clip_drift,
+            commands::set_clip_offset,
+            commands::set_clip_gain,
+            commands::set_clip_trim,
+            commands::set_track_locked,
+            commands::set_track_role,
+            commands::set_track_band_weighting,
+            commands::set_track_sync_group,
+            commands::apply_proposed_sync_groups,
+            commands::find_reusable_analyses,
+            commands::precache_files,
+            commands::reuse_prior_analysis,
             commands::get_tracks,
             commands::run_analysis,
+            commands::run_remote_analysis,
+            commands::reanalyze_clip,
+            commands::preflight_check,
+            commands::get_project_stats,
+            commands::query_clips,
             commands::run_sync_and_export,
-            commands::measure_drift,
+            commands::export_clip,
+            drift_tool::open_drift_window,
+            drift_tool::measure_drift,
+            drift_tool::compare_clips,
+            drift_tool::cancel_drift_measurement,
+            commands::simulate_job,
             commands::cancel_operation,
+            commands::reset_state,
             commands::save_project,
+            commands::save_sync_map,
+            commands::apply_sync_map,
             commands::load_project,
             commands::update_config,
+            commands::set_anchor_clip,
             commands::get_file_groups,
+            commands::get_comments,
+            commands::add_comment,
+            commands::resolve_comment,
+            commands::delete_comment,
+            commands::sync_comments,
+            commands::list_audio_devices,
+            commands::get_output_device,
+            commands::set_output_device,
+            commands::preview_play,
+            commands::preview_pause,
+            commands::preview_stop,
+            commands::scrub,
+            commands::set_loop,
+            commands::set_playback_rate,
+            commands::set_phase_check,
+            commands::capability_report,
+            logging::get_log_path,
+            logging::set_log_level,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,179 @@
+//! File logging for the desktop app.
+//!
+//! Log records go to `<app data>/logs/audiosync.log`, which rotates to
+//! `audiosync.log.1` … `.N` once it grows past `MAX_LOG_BYTES`, so users can
+//! attach logs to bug reports without a terminal. The level for AudioSync's
+//! own crates is a setting (`log_level` in the settings store); other crates
+//! only log warnings and errors.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::audio_output::SETTINGS_STORE;
+
+/// Settings store key for the log level ("error" … "trace").
+pub const LOG_LEVEL_KEY: &str = "log_level";
+
+const LOG_FILENAME: &str = "audiosync.log";
+
+/// Size at which the current log file is rotated.
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept besides the current one.
+const MAX_ROTATED_FILES: usize = 3;
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+struct LogFile {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> Self {
+        let file = OpenOptions::new().create(true).append(true).open(&path).ok();
+        let size = file
+            .as_ref()
+            .and_then(|f| f.metadata().ok())
+            .map_or(0, |m| m.len());
+        Self { path, file, size }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 > MAX_LOG_BYTES {
+            self.rotate();
+        }
+        if let Some(file) = self.file.as_mut()
+            && file.write_all(line.as_bytes()).is_ok()
+        {
+            self.size += line.len() as u64;
+        }
+    }
+
+    /// Shift `.1` → `.2` … dropping the oldest, then start a fresh file.
+    fn rotate(&mut self) {
+        self.file = None;
+        let rotated = |n: usize| rotated_path(&self.path, n);
+        let _ = std::fs::remove_file(rotated(MAX_ROTATED_FILES));
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let _ = std::fs::rename(rotated(n), rotated(n + 1));
+        }
+        let _ = std::fs::rename(&self.path, rotated(1));
+        *self = Self::open(self.path.clone());
+    }
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+struct FileLogger {
+    file: Mutex<LogFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let own = metadata.target().starts_with("audiosync");
+        metadata.level() <= if own { log::max_level() } else { LevelFilter::Warn }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if cfg!(debug_assertions) {
+            eprint!("{}", line);
+        }
+        // A poisoned lock only means another thread panicked mid-write.
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_line(&line);
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock()
+            && let Some(f) = file.file.as_mut()
+        {
+            let _ = f.flush();
+        }
+    }
+}
+
+/// Install the file logger at the level saved in settings. Called once from
+/// app setup.
+pub fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = app.path().app_data_dir()?.join("logs");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(LOG_FILENAME);
+    let _ = LOG_PATH.set(path.clone());
+
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(LogFile::open(path)),
+    }))?;
+    log::set_max_level(saved_level(app));
+    log::info!("AudioSync Pro {} started", env!("CARGO_PKG_VERSION"));
+    Ok(())
+}
+
+fn saved_level(app: &AppHandle) -> LevelFilter {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(LOG_LEVEL_KEY))
+        .and_then(|v| v.as_str().and_then(|s| s.parse().ok()))
+        .unwrap_or(DEFAULT_LEVEL)
+}
+
+/// Current log file, once logging is initialized.
+pub fn log_path() -> Option<&'static Path> {
+    LOG_PATH.get().map(PathBuf::as_path)
+}
+
+/// Path of the current log file.
+#[tauri::command]
+pub fn get_log_path() -> Result<String, String> {
+    log_path()
+        .map(|p| p.to_string_lossy().to_string())
+        .ok_or_else(|| "File logging is not initialized".to_string())
+}
+
+/// Change the log level ("error", "warn", "info", "debug", "trace") and
+/// remember it.
+#[tauri::command]
+pub fn set_log_level(level: String, app: AppHandle) -> Result<String, String> {
+    let filter: LevelFilter = level.parse().map_err(|_| format!("Unknown log level '{}'", level))?;
+    log::set_max_level(filter);
+
+    let store = app.store(SETTINGS_STORE).map_err(|e| e.to_string())?;
+    store.set(LOG_LEVEL_KEY, serde_json::Value::String(filter.to_string().to_lowercase()));
+    store.save().map_err(|e| e.to_string())?;
+    log::info!("Log level set to {}", filter);
+    Ok(filter.to_string().to_lowercase())
+}
+
+/// Open the log folder in the system file manager.
+pub fn reveal_logs() {
+    match log_path().and_then(Path::parent) {
+        Some(dir) => {
+            if let Err(e) = open::that(dir) {
+                log::warn!("Failed to open log folder: {}", e);
+            }
+        }
+        None => log::warn!("File logging is not initialized"),
+    }
+}
@@ -12,6 +12,8 @@ pub fn build_menu(app: &AppHandle) -> Result<Menu<Wry>, tauri::Error> {
     let import = MenuItemBuilder::with_id("import", "Import Files...")
         .accelerator("CmdOrCtrl+O")
         .build(app)?;
+    let precache = MenuItemBuilder::with_id("precache", "Precache Files for Analysis...")
+        .build(app)?;
     let open_project = MenuItemBuilder::with_id("open-project", "Open Project...")
         .accelerator("CmdOrCtrl+Shift+O")
         .build(app)?;
@@ -27,6 +29,7 @@ pub fn build_menu(app: &AppHandle) -> Result<Menu<Wry>, tauri::Error> {
 
     let file_menu = SubmenuBuilder::new(app, "File")
         .item(&import)
+        .item(&precache)
         .item(&open_project)
         .item(&save_project)
         .separator()
@@ -86,12 +89,16 @@ pub fn build_menu(app: &AppHandle) -> Result<Menu<Wry>, tauri::Error> {
         .build(app)?;
     let github = MenuItemBuilder::with_id("github", "GitHub Repository")
         .build(app)?;
+    let reveal_logs = MenuItemBuilder::with_id("reveal-logs", "Reveal Logs")
+        .build(app)?;
 
     let help_menu = SubmenuBuilder::new(app, "Help")
         .item(&about)
         .separator()
         .item(&website)
         .item(&github)
+        .separator()
+        .item(&reveal_logs)
         .build()?;
 
     let menu = MenuBuilder::new(app)
@@ -116,6 +123,12 @@ pub fn handle_menu_event(app: &AppHandle, event_id: &str) {
         "github" => {
             let _ = open::that("https://github.com/KEYHAN-A/audiosync");
         }
+        "reveal-logs" => crate::logging::reveal_logs(),
+        "drift-tool" => {
+            if let Err(e) = crate::drift_tool::open_window(app) {
+                log::warn!("Failed to open drift window: {}", e);
+            }
+        }
         _ => {
             // Forward to frontend
             let _ = app.emit("menu-event", event_id.to_string());
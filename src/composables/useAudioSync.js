@@ -11,7 +11,7 @@
 import { reactive, computed, ref } from "vue";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import { open, save } from "@tauri-apps/plugin-dialog";
+import { ask, open, save } from "@tauri-apps/plugin-dialog";
 
 // ---------------------------------------------------------------------------
 //  Singleton reactive state
@@ -31,6 +31,12 @@ const state = reactive({
   processingStep: 0,
   processingTotal: 0,
   processingMessage: "",
+  // Share done (0–1) and seconds left, from analysis/export progress events;
+  // null while only step/total are known
+  processingFraction: null,
+  processingEta: null,
+  // Live input levels while importing, keyed by file path
+  importMeters: {},
 
   // Status bar
   statusMessage: "Ready",
@@ -38,6 +44,10 @@ const state = reactive({
   // Errors / warnings
   lastError: null,
   warnings: [],
+
+  // Clips keep placements reused from an earlier analysis; the next
+  // analysis only fits in the rest
+  reusedPlacement: false,
 });
 
 // ---------------------------------------------------------------------------
@@ -82,7 +92,7 @@ async function importFiles() {
         {
           name: "Audio & Video",
           extensions: [
-            "wav", "aiff", "aif", "flac", "mp3", "ogg", "opus",
+            "wav", "aiff", "aif", "flac", "mp3", "ogg", "opus", "m4a", "3gp", "amr",
             "mp4", "mov", "mkv", "avi", "webm", "mts", "m4v", "mxf",
           ],
         },
@@ -108,7 +118,11 @@ async function importPaths(paths) {
   state.processingStep = 0;
   state.processingTotal = paths.length;
   state.processingMessage = "Preparing...";
+  state.processingFraction = null;
+  state.processingEta = null;
+  state.importMeters = {};
   state.lastError = null;
+  state.reusedPlacement = false;
 
   try {
     const tracks = await invoke("import_files", { paths });
@@ -121,6 +135,69 @@ async function importPaths(paths) {
   } finally {
     state.processing = false;
   }
+  if (totalClips.value > 0) await offerPriorAnalysis();
+}
+
+/** Decode files into the analysis cache in the background, so importing them later is instant */
+async function precacheFiles() {
+  try {
+    const selected = await open({
+      multiple: true,
+      title: "Precache Files for Analysis",
+      filters: [
+        {
+          name: "Audio & Video",
+          extensions: [
+            "wav", "aiff", "aif", "flac", "mp3", "ogg", "opus", "m4a", "3gp", "amr",
+            "mp4", "mov", "mkv", "avi", "webm", "mts", "m4v", "mxf",
+          ],
+        },
+        { name: "All Files", extensions: ["*"] },
+      ],
+    });
+    if (!selected || selected.length === 0) return;
+
+    const paths = selected.map((f) => (typeof f === "string" ? f : f.path));
+    state.statusMessage = `Caching ${paths.length} file(s) in the background...`;
+    const cached = await invoke("precache_files", { paths });
+    state.statusMessage = `Cached ${cached} file(s) for analysis`;
+  } catch (e) {
+    setError("Precache failed: " + e);
+  }
+}
+
+/** Sets of imported clips an earlier analysis (in any project) placed together */
+async function findReusableAnalyses() {
+  try {
+    return await invoke("find_reusable_analyses");
+  } catch (e) {
+    setError("Looking up earlier analyses failed: " + e);
+    return [];
+  }
+}
+
+/** Reuse earlier analyses of the same media: "priors" (hints) or "skip" (keep placement) */
+async function reusePriorAnalysis(mode) {
+  try {
+    state.tracks = await invoke("reuse_prior_analysis", { mode });
+    state.analysisResult = null;
+    if (mode === "skip") state.reusedPlacement = true;
+  } catch (e) {
+    setError("Reusing earlier analysis failed: " + e);
+  }
+}
+
+/** After an import, offer to keep what an earlier project worked out for the same media */
+async function offerPriorAnalysis() {
+  const reusable = await findReusableAnalyses();
+  if (reusable.length === 0) return;
+  const keep = await ask(
+    `${reusable[0].clip_ids.length} of these clips were synced together before, in this or another project. ` +
+      "Keep that placement and only analyze the other clips?",
+    { title: "Reuse Earlier Analysis", kind: "info", okLabel: "Keep Placement", cancelLabel: "Analyze Again" }
+  );
+  // Analyzing again still lets the earlier placement settle ambiguous matches.
+  await reusePriorAnalysis(keep ? "skip" : "priors");
 }
 
 /** Add files to an existing track */
@@ -133,7 +210,7 @@ async function addFilesToTrack(trackIndex) {
         {
           name: "Audio & Video",
           extensions: [
-            "wav", "aiff", "aif", "flac", "mp3", "ogg", "opus",
+            "wav", "aiff", "aif", "flac", "mp3", "ogg", "opus", "m4a", "3gp", "amr",
             "mp4", "mov", "mkv", "avi", "webm", "mts", "m4v", "mxf",
           ],
         },
@@ -147,6 +224,7 @@ async function addFilesToTrack(trackIndex) {
     state.processing = true;
     state.processingTitle = "Adding Files";
     state.processingMessage = "Loading...";
+    state.importMeters = {};
 
     const tracks = await invoke("add_files_to_track", {
       trackIndex,
@@ -197,8 +275,194 @@ async function removeClip(trackIndex, clipIndex) {
   }
 }
 
-/** Run the analysis engine */
-async function runAnalysis(maxOffsetS = null) {
+/** Remove several clips at once; clipIds are source paths */
+async function removeClipsBulk(clipIds) {
+  try {
+    state.tracks = await invoke("remove_clips_bulk", { clipIds });
+    state.analysisResult = null;
+  } catch (e) {
+    setError("Remove clips failed: " + e);
+  }
+}
+
+/** Leave several clips out of analysis and export, or bring them back */
+async function setExcludedBulk(clipIds, excluded) {
+  try {
+    state.tracks = await invoke("set_excluded_bulk", { clipIds, excluded });
+  } catch (e) {
+    setError("Exclude clips failed: " + e);
+  }
+}
+
+/** Set the export gain (dB) of several clips at once */
+async function setGainBulk(clipIds, gainDb) {
+  try {
+    state.tracks = await invoke("set_gain_bulk", { clipIds, gainDb });
+  } catch (e) {
+    setError("Set clip gain failed: " + e);
+  }
+}
+
+/** Assign a track role (DIALOG, MUSIC, FX, CAMERA); null re-infers it */
+async function setTrackRole(trackIndex, role) {
+  try {
+    state.tracks = await invoke("set_track_role", { trackIndex, role });
+  } catch (e) {
+    setError("Set track role failed: " + e);
+  }
+}
+
+/**
+ * Weight a track's analysis audio to suit its microphone, e.g.
+ * { high_pass_hz: 150, low_pass_hz: null, speech_emphasis_db: 6 };
+ * null goes back to the profile for its role
+ */
+async function setTrackBandWeighting(trackIndex, weighting) {
+  try {
+    state.tracks = await invoke("set_track_band_weighting", { trackIndex, weighting });
+  } catch (e) {
+    setError("Set band weighting failed: " + e);
+  }
+}
+
+/** Lock a clip so re-analysis keeps it where it is */
+async function setClipLocked(trackIndex, clipIndex, locked) {
+  try {
+    state.tracks = await invoke("set_clip_locked", { trackIndex, clipIndex, locked });
+  } catch (e) {
+    setError("Lock clip failed: " + e);
+  }
+}
+
+/** Export one clip padded to start at timeline zero, for a DAW drop-in */
+async function exportClip(trackIndex, clipIndex) {
+  try {
+    const outputDir = await open({ title: "Export Clip To", directory: true });
+    if (!outputDir) return;
+    const file = await invoke("export_clip", { trackIndex, clipIndex, outputDir });
+    state.statusMessage = `Exported ${file}`;
+  } catch (e) {
+    setError("Export clip failed: " + e);
+  }
+}
+
+/** Place a clip at offsetS seconds by hand; null hands it back to analysis */
+async function setClipOffset(trackIndex, clipIndex, offsetS) {
+  try {
+    state.tracks = await invoke("set_clip_offset", { trackIndex, clipIndex, offsetS });
+  } catch (e) {
+    setError("Set clip offset failed: " + e);
+  }
+}
+
+/** Move a track to a sync group (null or "" for the default group) */
+async function setTrackSyncGroup(trackIndex, group) {
+  try {
+    state.tracks = await invoke("set_track_sync_group", { trackIndex, group });
+  } catch (e) {
+    setError("Set sync group failed: " + e);
+  }
+}
+
+/** Accept the sync groups analysis proposed (analysisResult.proposed_groups) */
+async function applyProposedSyncGroups() {
+  try {
+    state.tracks = await invoke("apply_proposed_sync_groups");
+    state.analysisResult = null;
+  } catch (e) {
+    setError("Apply sync groups failed: " + e);
+  }
+}
+
+/** Pin a clip's drift in ppm (0 clears it); null lets analysis measure it again */
+async function setClipDrift(trackIndex, clipIndex, driftPpm) {
+  try {
+    state.tracks = await invoke("set_clip_drift", { trackIndex, clipIndex, driftPpm });
+  } catch (e) {
+    setError("Set clip drift failed: " + e);
+  }
+}
+
+/** Set the gain in dB a clip is stitched at (0 leaves its level alone) */
+async function setClipGain(trackIndex, clipIndex, gainDb) {
+  try {
+    state.tracks = await invoke("set_clip_gain", { trackIndex, clipIndex, gainDb });
+  } catch (e) {
+    setError("Set clip gain failed: " + e);
+  }
+}
+
+/** Cut trimInS seconds from a clip's head and trimOutS from its tail at export */
+async function setClipTrim(trackIndex, clipIndex, trimInS, trimOutS) {
+  try {
+    state.tracks = await invoke("set_clip_trim", { trackIndex, clipIndex, trimInS, trimOutS });
+  } catch (e) {
+    setError("Set clip trim failed: " + e);
+  }
+}
+
+/**
+ * Project dashboard numbers: resolves to { clip_count, total_media_duration_s,
+ * source_bytes, analyzed, estimated_export_bytes, tracks: [{ coverage, ... }] }.
+ */
+async function getProjectStats() {
+  try {
+    return await invoke("get_project_stats");
+  } catch (e) {
+    setError("Project stats failed: " + e);
+    return null;
+  }
+}
+
+/**
+ * Clips matching a query: { name, device, created_after, created_before
+ * (Unix seconds), confidence_below (0–100), drift_above_ppm }, any subset.
+ * Resolves to [{ track_index, clip_index, clip_id }].
+ */
+async function queryClips(query) {
+  try {
+    return await invoke("query_clips", { query });
+  } catch (e) {
+    setError("Clip search failed: " + e);
+    return [];
+  }
+}
+
+/**
+ * Diagnostics: resolves to { version, os, ffmpeg: { found, path, version },
+ * ffprobe, codecs: { export: [{ format, available, via }], ... },
+ * directories: [{ role, path, writable, error }], cpu: { arch, logical_cores,
+ * simd }, problems: [...], ok }. Pass an export directory to check it too.
+ */
+async function getCapabilityReport(outputDir = null) {
+  try {
+    return await invoke("capability_report", { outputDir });
+  } catch (e) {
+    setError("Diagnostics failed: " + e);
+    return null;
+  }
+}
+
+/** Path of the desktop app's log file, for bug reports */
+async function getLogPath() {
+  return invoke("get_log_path");
+}
+
+/** Set and remember the log level: "error", "warn", "info", "debug" or "trace" */
+async function setLogLevel(level) {
+  try {
+    return await invoke("set_log_level", { level });
+  } catch (e) {
+    setError("Set log level failed: " + e);
+    return null;
+  }
+}
+
+/**
+ * Run the analysis engine. With incremental, clips analyzed before keep
+ * their offsets and only new clips are correlated.
+ */
+async function runAnalysis(maxOffsetS = null, incremental = false) {
   if (totalClips.value === 0) {
     setError("No clips to analyze. Import files first.");
     return;
@@ -207,14 +471,18 @@ async function runAnalysis(maxOffsetS = null) {
   state.processing = true;
   state.processingTitle = "Analyzing";
   state.processingStep = 0;
-  state.processingTotal = totalClips.value + 4;
+  state.processingTotal = 0;
   state.processingMessage = "Starting analysis...";
+  state.processingFraction = 0;
+  state.processingEta = null;
   state.lastError = null;
 
   try {
     const result = await invoke("run_analysis", {
       maxOffsetS,
+      incremental: incremental || state.reusedPlacement,
     });
+    state.reusedPlacement = false;
     state.tracks = result.tracks;
     state.analysisResult = result.result;
     state.warnings = result.result.warnings || [];
@@ -243,6 +511,8 @@ async function runSyncAndExport(exportConfig) {
   state.processingStep = 0;
   state.processingTotal = totalClips.value;
   state.processingMessage = "Starting sync...";
+  state.processingFraction = 0;
+  state.processingEta = null;
   state.lastError = null;
 
   try {
@@ -263,6 +533,34 @@ async function runSyncAndExport(exportConfig) {
   }
 }
 
+/**
+ * Run a simulated long job through the progress/cancel plumbing (debug
+ * builds only). job: { duration_s, steps, cancel_after, fail_after }
+ */
+async function simulateJob(job = {}) {
+  state.processing = true;
+  state.processingTitle = "Simulating";
+  state.processingStep = 0;
+  state.processingTotal = 0;
+  state.processingMessage = "Starting simulated job...";
+  state.processingFraction = 0;
+  state.processingEta = null;
+  state.lastError = null;
+
+  try {
+    const steps = await invoke("simulate_job", { job });
+    state.statusMessage = `Simulated job complete — ${steps} steps`;
+  } catch (e) {
+    if (String(e).includes("cancelled")) {
+      state.statusMessage = "Simulated job cancelled";
+    } else {
+      setError("Simulated job failed: " + e);
+    }
+  } finally {
+    state.processing = false;
+  }
+}
+
 /** Cancel the current operation */
 async function cancelOperation() {
   try {
@@ -273,23 +571,57 @@ async function cancelOperation() {
   }
 }
 
-/** Measure drift between two files */
+/** Discard the session on the backend and start over (after a failed operation) */
+async function resetState() {
+  try {
+    await invoke("reset_state");
+    state.tracks = [];
+    state.analysisResult = null;
+    state.warnings = [];
+    state.currentStep = 0;
+    state.processing = false;
+    state.importMeters = {};
+    state.lastError = null;
+    state.reusedPlacement = false;
+    state.statusMessage = "Session reset";
+  } catch (e) {
+    setError("Reset failed: " + e);
+  }
+}
+
+/** Open (or focus) the detached drift measurement window */
+async function openDriftWindow() {
+  try {
+    await invoke("open_drift_window");
+  } catch (e) {
+    setError("Could not open drift tool: " + e);
+  }
+}
+
+/**
+ * Measure drift between two files.
+ *
+ * Runs independently of the project session, so it leaves the shared
+ * processing state alone; callers handle errors and progress themselves.
+ */
 async function measureDrift(referencePath, targetPath) {
-  state.processing = true;
-  state.processingTitle = "Measuring Drift";
-  state.processingMessage = "Loading files...";
+  return invoke("measure_drift", { referencePath, targetPath });
+}
 
+/**
+ * Correlate two files for plotting: resolves to { delay_s, confidence,
+ * runner_up_ratio, curve: [{ lag_s, value }], overlap }.
+ */
+async function compareClips(pathA, pathB, maxOffset = null) {
+  return invoke("compare_clips", { pathA, pathB, maxOffset });
+}
+
+/** Cancel a running drift measurement without touching other operations */
+async function cancelDriftMeasurement() {
   try {
-    const result = await invoke("measure_drift", {
-      referencePath,
-      targetPath,
-    });
-    return result;
+    await invoke("cancel_drift_measurement");
   } catch (e) {
-    setError("Drift measurement failed: " + e);
-    return null;
-  } finally {
-    state.processing = false;
+    console.warn("Cancel failed:", e);
   }
 }
 
@@ -336,13 +668,55 @@ async function loadProject() {
   }
 }
 
+/** Save the analysis as a sync map (clip hashes and offsets) for other tools */
+async function saveSyncMap() {
+  try {
+    const path = await save({
+      title: "Save Sync Map",
+      defaultPath: "project.syncmap.json",
+      filters: [{ name: "Sync Map", extensions: ["syncmap.json", "json"] }],
+    });
+    if (!path) return;
+
+    await invoke("save_sync_map", { path });
+    state.statusMessage = `Sync map saved`;
+  } catch (e) {
+    setError("Save failed: " + e);
+  }
+}
+
+/** Place the imported clips from a sync map instead of analyzing them */
+async function applySyncMap() {
+  try {
+    const selected = await open({
+      title: "Apply Sync Map",
+      filters: [{ name: "Sync Map", extensions: ["syncmap.json", "json"] }],
+    });
+    if (!selected) return;
+
+    const path = typeof selected === "string" ? selected : selected.path;
+
+    const result = await invoke("apply_sync_map", { path });
+    state.tracks = result.tracks;
+    state.analysisResult = result.result;
+    state.currentStep = 2;
+    state.statusMessage = `Sync map applied — ${
+      Object.keys(result.result.clip_offsets).length
+    } clips placed`;
+  } catch (e) {
+    setError("Sync map failed: " + e);
+  }
+}
+
 // ---------------------------------------------------------------------------
 //  Event listeners
 // ---------------------------------------------------------------------------
 
 let unlistenImport = null;
+let unlistenMeter = null;
 let unlistenAnalysis = null;
 let unlistenSync = null;
+let unlistenPrecache = null;
 
 async function setupListeners() {
   unlistenImport = await listen("import-progress", (event) => {
@@ -350,22 +724,38 @@ async function setupListeners() {
     state.processingTotal = event.payload.total;
     state.processingMessage = event.payload.message;
   });
-  unlistenAnalysis = await listen("analysis-progress", (event) => {
-    state.processingStep = event.payload.step;
-    state.processingTotal = event.payload.total;
-    state.processingMessage = event.payload.message;
+  unlistenMeter = await listen("import-meter", (event) => {
+    const m = event.payload;
+    state.importMeters[m.file_path] = {
+      name: m.file_path.split(/[\\/]/).pop(),
+      peak: m.peak,
+      rms: m.rms,
+      done: m.done,
+    };
   });
-  unlistenSync = await listen("sync-progress", (event) => {
-    state.processingStep = event.payload.step;
+  // { phase, fraction, current, total, message, eta_s }
+  const onProgress = (event) => {
+    state.processingStep = event.payload.current;
     state.processingTotal = event.payload.total;
     state.processingMessage = event.payload.message;
+    state.processingFraction = event.payload.fraction;
+    state.processingEta = event.payload.eta_s;
+  };
+  unlistenAnalysis = await listen("analysis-progress", onProgress);
+  unlistenSync = await listen("sync-progress", onProgress);
+  // Precaching runs in the background, so it only reports in the status bar.
+  unlistenPrecache = await listen("precache-progress", (event) => {
+    const { step, total, message } = event.payload;
+    state.statusMessage = `[${step}/${total}] ${message}`;
   });
 }
 
 function teardownListeners() {
   if (unlistenImport) unlistenImport();
+  if (unlistenMeter) unlistenMeter();
   if (unlistenAnalysis) unlistenAnalysis();
   if (unlistenSync) unlistenSync();
+  if (unlistenPrecache) unlistenPrecache();
 }
 
 // ---------------------------------------------------------------------------
@@ -405,12 +795,40 @@ export function useAudioSync() {
     createTrack,
     removeTrack,
     removeClip,
+    setTrackRole,
+    setTrackBandWeighting,
+    setTrackSyncGroup,
+    applyProposedSyncGroups,
+    findReusableAnalyses,
+    precacheFiles,
+    reusePriorAnalysis,
+    setClipLocked,
+    exportClip,
+    setClipDrift,
+    setClipOffset,
+    setClipGain,
+    setGainBulk,
+    setExcludedBulk,
+    removeClipsBulk,
+    setClipTrim,
+    getProjectStats,
+    queryClips,
+    getCapabilityReport,
+    getLogPath,
+    setLogLevel,
     runAnalysis,
     runSyncAndExport,
     cancelOperation,
+    simulateJob,
+    resetState,
+    openDriftWindow,
     measureDrift,
+    compareClips,
+    cancelDriftMeasurement,
     saveProject,
     loadProject,
+    saveSyncMap,
+    applySyncMap,
     clearError,
 
     // Lifecycle
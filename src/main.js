@@ -1,7 +1,11 @@
 import { createApp } from "vue";
 import App from "./App.vue";
+import DriftWindow from "./components/DriftWindow.vue";
 import "./styles/main.css";
 import "./styles/animations.css";
 
-const app = createApp(App);
+// The drift tool runs in its own window, loaded as index.html#drift.
+const root = window.location.hash === "#drift" ? DriftWindow : App;
+
+const app = createApp(root);
 app.mount("#app");